@@ -0,0 +1,154 @@
+//! Timings of each stage of loading and saving a map, on generated maps of a few sizes, through the public API.
+//!
+//! Run with `cargo bench` from this directory, optionally followed by `-- <filter>` to only run the benchmarks whose
+//! names match the filter, e.g. `-- udmf/load`.
+
+use std::{fmt::Write, hint::black_box};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use waddle::{
+    map::{
+        binary::{BinaryFormat, BinaryMapLumps},
+        compat::CompatProfile,
+        Map, RawMap,
+    },
+    String8,
+};
+
+fn load_save(c: &mut Criterion) {
+    // The huge map is about as big as a map can be while still fitting the binary format's 16-bit side indices
+    for (size, cells) in [("small", 4), ("medium", 32), ("huge", 96)] {
+        let name = String8::new_unchecked("MAP01");
+        let textmap = grid_textmap(cells);
+        let map = Map::load_udmf_textmap(name.clone(), &textmap).unwrap();
+        let raw_map = map.unlink().unwrap();
+        let (lumps, _) = map.to_doom_binary(CompatProfile::ZDoom).unwrap();
+
+        c.bench_with_input(
+            BenchmarkId::new("udmf/load", size),
+            &textmap,
+            |b, textmap| {
+                b.iter(|| Map::load_udmf_textmap(name.clone(), black_box(textmap)).unwrap())
+            },
+        );
+        c.bench_with_input(BenchmarkId::new("link", size), &raw_map, |b, raw_map| {
+            b.iter(|| raw_map.link().unwrap())
+        });
+        c.bench_with_input(BenchmarkId::new("unlink", size), &map, |b, map| {
+            b.iter(|| map.unlink().unwrap())
+        });
+        c.bench_with_input(
+            BenchmarkId::new("udmf/write", size),
+            &raw_map,
+            |b, raw_map| b.iter(|| raw_map.to_udmf("zdoom").unwrap()),
+        );
+        c.bench_with_input(BenchmarkId::new("binary/write", size), &map, |b, map| {
+            b.iter(|| map.to_doom_binary(CompatProfile::ZDoom).unwrap())
+        });
+        c.bench_with_input(BenchmarkId::new("binary/load", size), &lumps, |b, lumps| {
+            let lumps = BinaryMapLumps {
+                things: &lumps[0].data,
+                line_defs: &lumps[1].data,
+                side_defs: &lumps[2].data,
+                vertexes: &lumps[3].data,
+                sectors: &lumps[4].data,
+            };
+            b.iter(|| {
+                RawMap::parse_binary(name.clone(), BinaryFormat::Doom, black_box(&lumps)).unwrap()
+            })
+        });
+    }
+}
+
+criterion_group!(benches, load_save);
+criterion_main!(benches);
+
+/// A square of `cells * cells` square sectors, each with a thing in its middle. Lines between two sectors are
+/// two-sided.
+fn grid_textmap(cells: usize) -> String {
+    const SIZE: usize = 64;
+
+    let vertex = |x: usize, y: usize| y * (cells + 1) + x;
+    let sector = |x: usize, y: usize| y * cells + x;
+
+    let mut textmap = String::from("namespace = \"zdoom\";\n");
+
+    for y in 0..=cells {
+        for x in 0..=cells {
+            writeln!(textmap, "vertex {{ x = {}; y = {}; }}", x * SIZE, y * SIZE).unwrap();
+        }
+    }
+
+    for y in 0..cells {
+        for x in 0..cells {
+            writeln!(
+                textmap,
+                "sector {{ heightfloor = {}; heightceiling = 128; texturefloor = \"FLOOR4_8\"; \
+                 textureceiling = \"CEIL3_5\"; lightlevel = 160; }}",
+                (x + y) % 4 * 8
+            )
+            .unwrap();
+            writeln!(
+                textmap,
+                "thing {{ x = {}.0; y = {}.0; angle = 90; type = 2014; skill1 = true; skill2 = true; \
+                 skill3 = true; single = true; }}",
+                x * SIZE + SIZE / 2,
+                y * SIZE + SIZE / 2
+            )
+            .unwrap();
+        }
+    }
+
+    // Each line is listed with the sector on its right (front) side first, and the one on its left, if any
+    let mut lines = Vec::new();
+    for y in 0..=cells {
+        for x in 0..cells {
+            let below = y.checked_sub(1).map(|y| sector(x, y));
+            let above = (y < cells).then(|| sector(x, y));
+            lines.push(match (below, above) {
+                (Some(below), above) => (vertex(x, y), vertex(x + 1, y), below, above),
+                (None, Some(above)) => (vertex(x + 1, y), vertex(x, y), above, None),
+                (None, None) => unreachable!(),
+            });
+        }
+    }
+    for x in 0..=cells {
+        for y in 0..cells {
+            let left = x.checked_sub(1).map(|x| sector(x, y));
+            let right = (x < cells).then(|| sector(x, y));
+            lines.push(match (right, left) {
+                (Some(right), left) => (vertex(x, y), vertex(x, y + 1), right, left),
+                (None, Some(left)) => (vertex(x, y + 1), vertex(x, y), left, None),
+                (None, None) => unreachable!(),
+            });
+        }
+    }
+
+    let mut side_defs = 0;
+    for (v1, v2, front, back) in lines {
+        for sector in [Some(front), back].into_iter().flatten() {
+            writeln!(
+                textmap,
+                "sidedef {{ sector = {sector}; texturemiddle = \"{}\"; }}",
+                if back.is_some() { "-" } else { "STARTAN3" }
+            )
+            .unwrap();
+        }
+
+        write!(
+            textmap,
+            "linedef {{ v1 = {v1}; v2 = {v2}; sidefront = {side_defs}; "
+        )
+        .unwrap();
+        side_defs += 1;
+        if back.is_some() {
+            write!(textmap, "sideback = {side_defs}; twosided = true; ").unwrap();
+            side_defs += 1;
+        } else {
+            write!(textmap, "blocking = true; ").unwrap();
+        }
+        textmap.push_str("}\n");
+    }
+
+    textmap
+}
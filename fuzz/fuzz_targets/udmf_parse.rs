@@ -0,0 +1,11 @@
+//! Load arbitrary TEXTMAPs, which must never panic.
+//!
+//! Run with `cargo +nightly fuzz run udmf_parse` from the repository root.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    waddle::map::udmf::parse::fuzz_translation_unit(data);
+});
@@ -0,0 +1,267 @@
+//! Loading every map in a directory tree of archives, as a shared harness for large-scale analyses and for measuring
+//! the crate's own performance.
+//!
+//! Only WADs can be read for now: PK3s are found and reported as unsupported, so that they show up in the summary
+//! rather than silently disappearing from it.
+
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    map::Map,
+    wad::{MapLoadError, Wad, WadError},
+    String8,
+};
+
+/// The kinds of archive found while walking a corpus
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ArchiveKind {
+    Wad,
+    Pk3,
+}
+
+impl ArchiveKind {
+    /// The kind of archive at `path`, by its extension, or `None` if it isn't an archive
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+
+        match extension.as_str() {
+            "wad" => Some(Self::Wad),
+            "pk3" | "pk7" | "zip" => Some(Self::Pk3),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum CorpusError {
+    #[error("{0:?} archives aren't supported")]
+    UnsupportedArchive(ArchiveKind),
+
+    #[error(transparent)]
+    Wad(#[from] WadError),
+
+    #[error(transparent)]
+    Map(#[from] MapLoadError),
+}
+
+/// A map loaded from an archive in the corpus
+#[derive(Debug)]
+pub struct CorpusMap {
+    pub name: String8,
+    pub load_time: Duration,
+    pub result: Result<Map, CorpusError>,
+}
+
+/// An archive in the corpus, with all of its maps loaded
+#[derive(Debug)]
+pub struct CorpusArchive {
+    pub path: PathBuf,
+    /// The time taken to read the archive, not including loading its maps
+    pub load_time: Duration,
+    pub result: Result<Vec<CorpusMap>, CorpusError>,
+}
+
+/// The archives under a directory, which are loaded one at a time while iterating
+#[derive(Clone, Debug)]
+pub struct Corpus {
+    archives: Vec<PathBuf>,
+    next: usize,
+}
+
+impl Corpus {
+    /// Find every archive under `root`, recursively. Archives are visited in path order, so that runs over the same
+    /// corpus are comparable.
+    pub fn walk(root: impl AsRef<Path>) -> io::Result<Self> {
+        let mut archives = Vec::new();
+        let mut directories = vec![root.as_ref().to_path_buf()];
+
+        while let Some(directory) = directories.pop() {
+            for entry in fs::read_dir(directory)? {
+                let path = entry?.path();
+                let file_type = fs::symlink_metadata(&path)?.file_type();
+
+                // Symlinked directories aren't followed, so a link back up the tree can't loop
+                let is_file = if file_type.is_symlink() {
+                    fs::metadata(&path).is_ok_and(|metadata| metadata.is_file())
+                } else if file_type.is_dir() {
+                    directories.push(path);
+                    continue;
+                } else {
+                    file_type.is_file()
+                };
+
+                if is_file && ArchiveKind::from_path(&path).is_some() {
+                    archives.push(path);
+                }
+            }
+        }
+        archives.sort();
+
+        Ok(Self { archives, next: 0 })
+    }
+
+    /// The paths of every archive in the corpus, including those already visited
+    pub fn archives(&self) -> &[PathBuf] {
+        &self.archives
+    }
+
+    /// Load every remaining archive, summarising the results without keeping the maps
+    pub fn summarize(self) -> CorpusSummary {
+        let mut summary = CorpusSummary::default();
+        for archive in self {
+            summary.add(&archive);
+        }
+        summary
+    }
+}
+
+impl Iterator for Corpus {
+    type Item = CorpusArchive;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let path = self.archives.get(self.next)?.clone();
+        self.next += 1;
+
+        let start = Instant::now();
+        let wad = match ArchiveKind::from_path(&path) {
+            Some(ArchiveKind::Wad) => Wad::open(&path).map_err(CorpusError::from),
+            Some(kind) => Err(CorpusError::UnsupportedArchive(kind)),
+            None => unreachable!("only archives are collected"),
+        };
+        let load_time = start.elapsed();
+
+        let result = wad.map(|wad| {
+            let markers = wad
+                .lumps
+                .windows(2)
+                .enumerate()
+                .filter(|(_, pair)| pair[1].is_named("TEXTMAP") || pair[1].is_named("THINGS"))
+                .map(|(marker, _)| marker);
+
+            markers
+                .map(|marker| {
+                    let start = Instant::now();
+                    let result = wad.load_map(marker).map_err(CorpusError::from);
+
+                    CorpusMap {
+                        name: wad.lumps[marker].name.clone(),
+                        load_time: start.elapsed(),
+                        result,
+                    }
+                })
+                .collect()
+        });
+
+        Some(CorpusArchive {
+            path,
+            load_time,
+            result,
+        })
+    }
+}
+
+/// Counts and timings over the archives of a corpus
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CorpusSummary {
+    pub archives: usize,
+    pub failed_archives: usize,
+    pub maps: usize,
+    pub failed_maps: usize,
+    pub archive_load_time: Duration,
+    pub map_load_time: Duration,
+    /// The slowest map to load, with its archive
+    pub slowest_map: Option<(PathBuf, String8, Duration)>,
+    /// How many archives and maps failed with each error message
+    pub errors: BTreeMap<String, usize>,
+}
+
+impl CorpusSummary {
+    pub fn add(&mut self, archive: &CorpusArchive) {
+        self.archives += 1;
+        self.archive_load_time += archive.load_time;
+
+        let maps = match &archive.result {
+            Ok(maps) => maps,
+            Err(e) => {
+                self.failed_archives += 1;
+                *self.errors.entry(e.to_string()).or_default() += 1;
+                return;
+            }
+        };
+
+        for map in maps {
+            self.maps += 1;
+            self.map_load_time += map.load_time;
+
+            if self
+                .slowest_map
+                .as_ref()
+                .is_none_or(|(_, _, slowest)| map.load_time > *slowest)
+            {
+                self.slowest_map = Some((archive.path.clone(), map.name.clone(), map.load_time));
+            }
+
+            if let Err(e) = &map.result {
+                self.failed_maps += 1;
+                *self.errors.entry(e.to_string()).or_default() += 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::wad::{Lump, WadKind};
+
+    #[test]
+    fn walk_corpus() {
+        let root = std::env::temp_dir().join(format!("waddle-corpus-{}", std::process::id()));
+        fs::create_dir_all(root.join("nested")).unwrap();
+
+        let mut wad = Wad::new(WadKind::Pwad);
+        for (name, data) in [
+            ("MAP01", ""),
+            ("TEXTMAP", "namespace = \"zdoom\"; vertex { x = 0; y = 0; }"),
+            ("ENDMAP", ""),
+            ("MAP02", ""),
+            ("TEXTMAP", "namespace = \"zdoom\"; vertex { x = 0; }"),
+            ("ENDMAP", ""),
+        ] {
+            wad.lumps.push(Lump::new(
+                String8::new_unchecked(name),
+                data.as_bytes().to_vec(),
+            ));
+        }
+        wad.save_atomic(root.join("nested/maps.WAD")).unwrap();
+        fs::write(root.join("mod.pk3"), b"PK").unwrap();
+        fs::write(root.join("readme.txt"), b"").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(&root, root.join("nested/loop")).unwrap();
+
+        let corpus = Corpus::walk(&root).unwrap();
+        assert_eq!(
+            corpus.archives(),
+            &[root.join("mod.pk3"), root.join("nested/maps.WAD")]
+        );
+
+        let summary = corpus.summarize();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(summary.archives, 2);
+        assert_eq!(summary.failed_archives, 1);
+        assert_eq!(summary.maps, 2);
+        assert_eq!(summary.failed_maps, 1);
+        assert_eq!(summary.errors.values().sum::<usize>(), 2);
+        assert_eq!(
+            summary.errors.get("Pk3 archives aren't supported"),
+            Some(&1)
+        );
+    }
+}
@@ -0,0 +1,61 @@
+use miette::Diagnostic;
+
+use crate::source::{LineIndex, LocatedLabel};
+
+/// How serious a `DiagnosticReport` is. Mirrors `miette::Severity`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde_derive::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum ReportSeverity {
+    Advice,
+    Warning,
+    Error,
+}
+
+impl From<miette::Severity> for ReportSeverity {
+    fn from(severity: miette::Severity) -> Self {
+        match severity {
+            miette::Severity::Advice => ReportSeverity::Advice,
+            miette::Severity::Warning => ReportSeverity::Warning,
+            miette::Severity::Error => ReportSeverity::Error,
+        }
+    }
+}
+
+/// A serializable snapshot of a `Diagnostic`, with its labels located in the source by line and column.
+///
+/// This carries everything an editor or language server needs to display the diagnostic, without depending on
+/// miette's reporting.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde_derive::Serialize))]
+pub struct DiagnosticReport {
+    /// A stable identifier for the kind of diagnostic, e.g. `waddle::udmf::out_of_range`
+    pub code: Option<String>,
+    pub severity: ReportSeverity,
+    pub message: String,
+    pub help: Option<String>,
+    pub labels: Vec<LocatedLabel>,
+    /// Diagnostics related to this one, e.g. the causes of a wrapper error
+    pub related: Vec<DiagnosticReport>,
+}
+
+impl DiagnosticReport {
+    /// Build a report for a diagnostic about the source indexed by `line_index`
+    pub fn new(diagnostic: &dyn Diagnostic, line_index: &LineIndex) -> Self {
+        Self {
+            code: diagnostic.code().map(|code| code.to_string()),
+            severity: diagnostic
+                .severity()
+                .map_or(ReportSeverity::Error, ReportSeverity::from),
+            message: diagnostic.to_string(),
+            help: diagnostic.help().map(|help| help.to_string()),
+            labels: line_index.locate(diagnostic),
+            related: diagnostic
+                .related()
+                .into_iter()
+                .flatten()
+                .map(|related| Self::new(related, line_index))
+                .collect(),
+        }
+    }
+}
@@ -1,7 +1,14 @@
+#[cfg(feature = "corpus")]
+pub mod corpus;
+pub mod diagnostic;
+mod fnv;
 pub mod map;
 pub mod number;
 pub mod point;
+pub mod prelude;
+pub mod source;
 pub mod string8;
+pub mod units;
 pub mod wad;
 
 pub use self::{point::*, string8::*};
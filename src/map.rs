@@ -1,34 +1,167 @@
-use std::fmt::{self, Display, Formatter};
+use std::{
+    fmt::{self, Display, Formatter},
+    hash::{Hash, Hasher},
+};
 
-use slotmap::SecondaryMap;
+use slotmap::{SecondaryMap, SlotMap};
 
-use crate::String8;
+use crate::{fnv::Fnv1a, number::Number, String8};
 
+pub mod binary;
+pub mod blockmap;
+pub mod builder;
+pub mod bundle;
+pub mod compat;
+pub mod diff;
+pub mod downconvert;
+pub mod exit;
+pub mod floor_3d;
+pub mod geometry;
+pub mod import;
+pub mod index;
+pub mod integrity;
+#[cfg(feature = "json")]
+mod json;
 pub mod line_def;
+pub mod lint;
+pub mod lock;
+pub mod mechanisms;
+pub mod mesh;
+pub mod nodes;
+pub mod observe;
+pub mod polyobject;
+pub mod preview;
+pub mod procgen;
+pub mod reachability;
 pub mod sector;
+pub mod selection;
+pub mod self_reference;
 pub mod side_def;
+pub mod sight;
+pub mod slope;
+pub mod tag_graph;
 pub mod thing;
+pub mod thing_type;
+pub mod trace;
+pub mod transfer_heights;
+pub mod transform;
 pub mod udmf;
 pub mod vertex;
+pub mod voodoo;
 
 pub use self::{
     line_def::LineDef, sector::Sector, side_def::SideDef, thing::Thing, vertex::Vertex,
 };
 
 use self::{
-    line_def::{LineDefMap, RawLineDef},
-    sector::SectorMap,
-    side_def::{RawSideDef, SideDefMap},
-    thing::ThingMap,
-    vertex::VertexMap,
+    line_def::{LineDefKey, LineDefMap, RawLineDef},
+    lint::EntityRef,
+    sector::{SectorKey, SectorMap},
+    side_def::{RawSideDef, SideDefKey, SideDefMap},
+    thing::{ThingKey, ThingMap},
+    vertex::{VertexKey, VertexMap},
 };
 
+macro_rules! raw_index {
+    ($(#[$meta:meta])* $name:ident, $checked:ident, $field:ident) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        #[cfg_attr(
+            feature = "serde",
+            derive(serde_derive::Serialize, serde_derive::Deserialize),
+            serde(transparent)
+        )]
+        pub struct $name(u16);
+
+        impl $name {
+            pub const fn new(idx: u16) -> Self {
+                Self(idx)
+            }
+
+            pub const fn get(self) -> u16 {
+                self.0
+            }
+
+            pub const fn index(self) -> usize {
+                self.0 as usize
+            }
+        }
+
+        impl From<u16> for $name {
+            fn from(idx: u16) -> Self {
+                Self(idx)
+            }
+        }
+
+        impl From<$name> for u16 {
+            fn from(idx: $name) -> Self {
+                idx.0
+            }
+        }
+
+        impl From<$name> for usize {
+            fn from(idx: $name) -> Self {
+                idx.0.into()
+            }
+        }
+
+        /// Fails if the index doesn't fit in an u16
+        impl TryFrom<usize> for $name {
+            type Error = std::num::TryFromIntError;
+
+            fn try_from(idx: usize) -> Result<Self, Self::Error> {
+                u16::try_from(idx).map(Self)
+            }
+        }
+
+        impl Display for $name {
+            fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+                self.0.fmt(f)
+            }
+        }
+
+        impl RawMap {
+            /// The index, if it refers to an entity in this map
+            pub fn $checked(&self, idx: usize) -> Option<$name> {
+                if idx < self.$field.len() {
+                    $name::try_from(idx).ok()
+                } else {
+                    None
+                }
+            }
+        }
+    };
+}
+
+raw_index!(
+    /// The index of a vertex in a `RawMap`
+    VertexIdx,
+    vertex_idx,
+    vertexes
+);
+raw_index!(
+    /// The index of a side def in a `RawMap`
+    SideDefIdx,
+    side_def_idx,
+    side_defs
+);
+raw_index!(
+    /// The index of a sector in a `RawMap`
+    SectorIdx,
+    sector_idx,
+    sectors
+);
+
 /// A Doom map, with all entities stored as flat `Vec`s and all references to entities stored as indices.
 /// This is very close to the raw representation of a map in a file, but any insertions/deletions require shifting
 /// all subsequent indices (and all references to those indices), so it's generally not very ergonomic to modify.
 ///
 /// You can use [RawMap::link] to validate all indices and convert this to a `Map`, which is easier to work with.
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
 pub struct RawMap {
     pub name: String8,
 
@@ -37,121 +170,247 @@ pub struct RawMap {
     pub sectors: Vec<Sector>,
     pub side_defs: Vec<RawSideDef>,
     pub things: Vec<Thing>,
+
+    /// Top-level UDMF blocks which aren't part of the map, kept by [udmf::UnknownBlocks::Preserve]. They're written
+    /// back by [RawMap::to_udmf], but not kept by `link`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub unknown_blocks: Vec<udmf::UnknownBlock>,
 }
 
 impl RawMap {
     pub fn link(&self) -> Result<Map, LinkError> {
-        let mut vertexes = VertexMap::with_key();
-        let mut line_defs = LineDefMap::with_key();
-        let mut sectors = SectorMap::with_key();
-        let mut side_defs = SideDefMap::with_key();
-        let mut things = ThingMap::with_key();
-
-        let vertex_map: Vec<_> = self
-            .vertexes
-            .iter()
-            .map(|vertex| vertexes.insert(*vertex))
-            .collect();
-
-        let sector_map: Vec<_> = self
-            .sectors
-            .iter()
-            .map(|sector| sectors.insert(sector.clone()))
-            .collect();
-
-        let side_map: Vec<_> = self
-            .side_defs
-            .iter()
-            .enumerate()
-            .map(|(i, side_def)| {
-                Ok(side_defs.insert(SideDef {
-                    sector: *sector_map.get(usize::from(side_def.sector_idx)).ok_or(
-                        LinkError::IndexOutOfRange {
-                            referrer: EntityKind::SideDef,
-                            referrer_index: i,
-                            field: "sector",
-                            referee: EntityKind::Sector,
-                            referee_index: side_def.sector_idx,
-                        },
-                    )?,
-                    offset: side_def.offset,
-                    upper_texture: side_def.upper_texture.clone(),
-                    middle_texture: side_def.middle_texture.clone(),
-                    lower_texture: side_def.lower_texture.clone(),
-                }))
-            })
-            .collect::<Result<_, _>>()?;
+        let mut linker = Linker::new(self.name.clone());
+        linker.link(self)?;
+        Ok(linker.into_map())
+    }
+}
 
-        for (i, line_def) in self.line_defs.iter().enumerate() {
-            line_defs.insert(LineDef {
-                from: *vertex_map.get(usize::from(line_def.from_idx)).ok_or(
-                    LinkError::IndexOutOfRange {
-                        referrer: EntityKind::LineDef,
-                        referrer_index: i,
-                        field: "from",
-                        referee: EntityKind::Vertex,
-                        referee_index: line_def.from_idx,
-                    },
-                )?,
+/// Links a `RawMap` which is only ever appended to, e.g. by a tool streaming map data, into a `Map`.
+///
+/// Each call to [Linker::link] only inserts the entities appended since the previous call, so entities which were
+/// already linked keep their keys. Edits to entities which were already linked aren't picked up.
+#[derive(Clone, Debug)]
+pub struct Linker {
+    map: Map,
 
-                to: *vertex_map.get(usize::from(line_def.to_idx)).ok_or(
-                    LinkError::IndexOutOfRange {
-                        referrer: EntityKind::LineDef,
-                        referrer_index: i,
-                        field: "to",
-                        referee: EntityKind::Vertex,
-                        referee_index: line_def.to_idx,
-                    },
-                )?,
+    vertexes: Vec<VertexKey>,
+    line_defs: Vec<LineDefKey>,
+    sectors: Vec<SectorKey>,
+    side_defs: Vec<SideDefKey>,
+    things: Vec<ThingKey>,
+}
 
-                left_side: *side_map.get(usize::from(line_def.left_side_idx)).ok_or(
-                    LinkError::IndexOutOfRange {
-                        referrer: EntityKind::LineDef,
-                        referrer_index: i,
-                        field: "left_side",
-                        referee: EntityKind::SideDef,
-                        referee_index: line_def.left_side_idx,
-                    },
-                )?,
+impl Linker {
+    pub fn new(name: String8) -> Self {
+        Self {
+            map: Map::new(name),
+            vertexes: Vec::new(),
+            line_defs: Vec::new(),
+            sectors: Vec::new(),
+            side_defs: Vec::new(),
+            things: Vec::new(),
+        }
+    }
 
-                right_side: line_def
-                    .right_side_idx
-                    .map(|right_side_idx| {
-                        side_map
-                            .get(usize::from(right_side_idx))
-                            .ok_or(LinkError::IndexOutOfRange {
-                                referrer: EntityKind::LineDef,
-                                referrer_index: i,
-                                field: "right_side",
-                                referee: EntityKind::SideDef,
-                                referee_index: right_side_idx,
-                            })
-                            .copied()
-                    })
-                    .transpose()?,
-
-                flags: line_def.flags.clone(),
-                special: line_def.special.clone(),
-                trigger_flags: line_def.trigger_flags.clone(),
-            });
+    /// Insert the entities which were appended to `raw_map` since the last call. Nothing is inserted if any of them
+    /// refers to an invalid index.
+    pub fn link(&mut self, raw_map: &RawMap) -> Result<(), LinkError> {
+        for (entity_kind, linked, len) in [
+            (
+                EntityKind::Vertex,
+                self.vertexes.len(),
+                raw_map.vertexes.len(),
+            ),
+            (
+                EntityKind::LineDef,
+                self.line_defs.len(),
+                raw_map.line_defs.len(),
+            ),
+            (
+                EntityKind::Sector,
+                self.sectors.len(),
+                raw_map.sectors.len(),
+            ),
+            (
+                EntityKind::SideDef,
+                self.side_defs.len(),
+                raw_map.side_defs.len(),
+            ),
+            (EntityKind::Thing, self.things.len(), raw_map.things.len()),
+        ] {
+            if len < linked {
+                return Err(LinkError::EntitiesRemoved {
+                    entity_kind,
+                    linked,
+                    len,
+                });
+            }
         }
 
-        for thing in self.things.iter() {
-            things.insert(thing.clone());
+        let new_side_defs = &raw_map.side_defs[self.side_defs.len()..];
+        let new_line_defs = &raw_map.line_defs[self.line_defs.len()..];
+
+        for (i, side_def) in new_side_defs.iter().enumerate() {
+            let referrer_index = self.side_defs.len() + i;
+            let check = |field, referee, referee_index, len| {
+                check_index(
+                    EntityKind::SideDef,
+                    referrer_index,
+                    field,
+                    referee,
+                    referee_index,
+                    len,
+                )
+            };
+
+            check(
+                "sector",
+                EntityKind::Sector,
+                side_def.sector_idx.get(),
+                raw_map.sectors.len(),
+            )?;
         }
 
-        Ok(Map {
-            name: self.name.clone(),
-            vertexes,
-            line_defs,
-            sectors,
-            side_defs,
-            things,
+        for (i, line_def) in new_line_defs.iter().enumerate() {
+            let referrer_index = self.line_defs.len() + i;
+            let check = |field, referee, referee_index, len| {
+                check_index(
+                    EntityKind::LineDef,
+                    referrer_index,
+                    field,
+                    referee,
+                    referee_index,
+                    len,
+                )
+            };
+
+            check(
+                "from",
+                EntityKind::Vertex,
+                line_def.from_idx.get(),
+                raw_map.vertexes.len(),
+            )?;
+            check(
+                "to",
+                EntityKind::Vertex,
+                line_def.to_idx.get(),
+                raw_map.vertexes.len(),
+            )?;
+            check(
+                "left_side",
+                EntityKind::SideDef,
+                line_def.left_side_idx.get(),
+                raw_map.side_defs.len(),
+            )?;
+            if let Some(right_side_idx) = line_def.right_side_idx {
+                check(
+                    "right_side",
+                    EntityKind::SideDef,
+                    right_side_idx.get(),
+                    raw_map.side_defs.len(),
+                )?;
+            }
+        }
+
+        for vertex in &raw_map.vertexes[self.vertexes.len()..] {
+            self.vertexes.push(self.map.vertexes.insert(*vertex));
+        }
+
+        for sector in &raw_map.sectors[self.sectors.len()..] {
+            self.sectors.push(self.map.sectors.insert(sector.clone()));
+        }
+
+        for side_def in new_side_defs {
+            self.side_defs.push(self.map.side_defs.insert(SideDef {
+                sector: self.sectors[usize::from(side_def.sector_idx)],
+                offset: side_def.offset,
+                upper_texture: side_def.upper_texture.clone(),
+                middle_texture: side_def.middle_texture.clone(),
+                lower_texture: side_def.lower_texture.clone(),
+            }));
+        }
+
+        for line_def in new_line_defs {
+            self.line_defs.push(
+                self.map.line_defs.insert(LineDef {
+                    from: self.vertexes[usize::from(line_def.from_idx)],
+                    to: self.vertexes[usize::from(line_def.to_idx)],
+                    left_side: self.side_defs[usize::from(line_def.left_side_idx)],
+                    right_side: line_def
+                        .right_side_idx
+                        .map(|right_side_idx| self.side_defs[usize::from(right_side_idx)]),
+                    id: line_def.id,
+                    flags: line_def.flags.clone(),
+                    special: line_def.special.clone(),
+                    trigger_flags: line_def.trigger_flags.clone(),
+                }),
+            );
+        }
+
+        for thing in &raw_map.things[self.things.len()..] {
+            self.things.push(self.map.things.insert(thing.clone()));
+        }
+
+        Ok(())
+    }
+
+    /// The map linked so far
+    pub fn map(&self) -> &Map {
+        &self.map
+    }
+
+    pub fn into_map(self) -> Map {
+        self.map
+    }
+
+    /// The keys of the linked vertexes, by their index in the `RawMap`
+    pub fn vertex_keys(&self) -> &[VertexKey] {
+        &self.vertexes
+    }
+
+    /// The keys of the linked line defs, by their index in the `RawMap`
+    pub fn line_def_keys(&self) -> &[LineDefKey] {
+        &self.line_defs
+    }
+
+    /// The keys of the linked sectors, by their index in the `RawMap`
+    pub fn sector_keys(&self) -> &[SectorKey] {
+        &self.sectors
+    }
+
+    /// The keys of the linked side defs, by their index in the `RawMap`
+    pub fn side_def_keys(&self) -> &[SideDefKey] {
+        &self.side_defs
+    }
+
+    /// The keys of the linked things, by their index in the `RawMap`
+    pub fn thing_keys(&self) -> &[ThingKey] {
+        &self.things
+    }
+}
+
+fn check_index(
+    referrer: EntityKind,
+    referrer_index: usize,
+    field: &'static str,
+    referee: EntityKind,
+    referee_index: u16,
+    len: usize,
+) -> Result<(), LinkError> {
+    if usize::from(referee_index) < len {
+        Ok(())
+    } else {
+        Err(LinkError::IndexOutOfRange {
+            referrer,
+            referrer_index,
+            field,
+            referee,
+            referee_index,
         })
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum EntityKind {
     Vertex,
     LineDef,
@@ -174,6 +433,57 @@ impl Display for EntityKind {
     }
 }
 
+/// One kind of entity stored in a `Map`, for code which works the same way on every kind
+pub trait MapEntity: Sized {
+    type Key: EntityKey<Entity = Self>;
+
+    fn entities(map: &Map) -> &SlotMap<Self::Key, Self>;
+    fn entities_mut(map: &mut Map) -> &mut SlotMap<Self::Key, Self>;
+
+    /// The entities this entity refers to
+    fn references(&self) -> Vec<EntityRef>;
+}
+
+/// The key of a `MapEntity`
+pub trait EntityKey: slotmap::Key {
+    type Entity: MapEntity<Key = Self>;
+
+    fn entity_ref(self) -> EntityRef;
+}
+
+macro_rules! map_entity {
+    ($entity:ident, $key:ident, $field:ident) => {
+        map_entity!($entity, $key, $field, |_entity| Vec::new());
+    };
+
+    ($entity:ident, $key:ident, $field:ident, |$this:ident| $references:expr) => {
+        impl MapEntity for $entity {
+            type Key = $key;
+
+            fn entities(map: &Map) -> &SlotMap<$key, Self> {
+                &map.$field
+            }
+
+            fn entities_mut(map: &mut Map) -> &mut SlotMap<$key, Self> {
+                &mut map.$field
+            }
+
+            fn references(&self) -> Vec<EntityRef> {
+                let $this = self;
+                $references
+            }
+        }
+
+        impl EntityKey for $key {
+            type Entity = $entity;
+
+            fn entity_ref(self) -> EntityRef {
+                EntityRef::$entity(self)
+            }
+        }
+    };
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum LinkError {
     #[error(
@@ -186,6 +496,13 @@ pub enum LinkError {
         referee: EntityKind,
         referee_index: u16,
     },
+
+    #[error("{len} {entity_kind}s remain, but {linked} were already linked")]
+    EntitiesRemoved {
+        entity_kind: EntityKind,
+        linked: usize,
+        len: usize,
+    },
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -202,7 +519,7 @@ pub enum UnlinkError {
     IndexTooLarge { entity_kind: EntityKind },
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Map {
     pub name: String8,
 
@@ -213,6 +530,41 @@ pub struct Map {
     pub things: ThingMap,
 }
 
+map_entity!(Vertex, VertexKey, vertexes);
+map_entity!(LineDef, LineDefKey, line_defs, |line_def| {
+    let mut references = vec![
+        EntityRef::Vertex(line_def.from),
+        EntityRef::Vertex(line_def.to),
+        EntityRef::SideDef(line_def.left_side),
+    ];
+    references.extend(line_def.right_side.map(EntityRef::SideDef));
+    references
+});
+map_entity!(Sector, SectorKey, sectors);
+map_entity!(SideDef, SideDefKey, side_defs, |side_def| vec![
+    EntityRef::Sector(side_def.sector)
+]);
+map_entity!(Thing, ThingKey, things);
+
+/// Serialized as its `RawMap`, so that references between entities are stable indices rather than keys
+#[cfg(feature = "serde")]
+impl serde::Serialize for Map {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.unlink()
+            .map_err(serde::ser::Error::custom)?
+            .serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Map {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        RawMap::deserialize(deserializer)?
+            .link()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
 impl Map {
     pub fn new(name: String8) -> Self {
         Self {
@@ -260,7 +612,7 @@ impl Map {
         let mut vertexes = Vec::with_capacity(self.vertexes.len());
 
         for (i, (vertex_key, vertex)) in self.vertexes.iter().enumerate() {
-            vertex_idx_map.insert(vertex_key, i as u16);
+            vertex_idx_map.insert(vertex_key, VertexIdx::new(i as u16));
             vertexes.push(*vertex);
         }
 
@@ -268,7 +620,7 @@ impl Map {
         let mut sectors = Vec::with_capacity(self.sectors.len());
 
         for (i, (sector_key, sector)) in self.sectors.iter().enumerate() {
-            sector_idx_map.insert(sector_key, i as u16);
+            sector_idx_map.insert(sector_key, SectorIdx::new(i as u16));
             sectors.push(sector.clone());
         }
 
@@ -276,7 +628,7 @@ impl Map {
         let mut side_defs = Vec::with_capacity(self.side_defs.len());
 
         for (i, (side_def_key, side_def)) in self.side_defs.iter().enumerate() {
-            side_def_idx_map.insert(side_def_key, i as u16);
+            side_def_idx_map.insert(side_def_key, SideDefIdx::new(i as u16));
 
             side_defs.push(RawSideDef {
                 sector_idx: *sector_idx_map.get(side_def.sector).ok_or(
@@ -343,6 +695,7 @@ impl Map {
                         })
                         .transpose()?,
 
+                    id: line_def.id,
                     flags: line_def.flags.clone(),
                     special: line_def.special.clone(),
                     trigger_flags: line_def.trigger_flags.clone(),
@@ -359,27 +712,364 @@ impl Map {
             sectors,
             side_defs,
             things,
+            unknown_blocks: Vec::new(),
+        })
+    }
+
+    /// A hash of the map's contents, ignoring its name, for finding duplicate maps. Maps with the same hash should
+    /// be compared with [Map::same_contents] to rule out collisions.
+    ///
+    /// Consistent with `PartialEq`: the order of the entities and their keys don't matter, and integer and float
+    /// coordinates with the same value hash the same. The hash is stable across runs and platforms.
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = Fnv1a::default();
+        self.contents().hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Whether two maps have the same contents, ignoring their names, as compared by `PartialEq`
+    pub fn same_contents(&self, other: &Map) -> bool {
+        self.contents() == other.contents()
+    }
+
+    /// The map's entities encoded for comparison, ignoring its name. References are replaced by the encoding of the
+    /// entity they refer to, and each kind of entity is sorted, so that neither keys nor order matter.
+    fn contents(&self) -> Contents {
+        fn number(number: Number) -> u64 {
+            float_bits(number.into_float())
+        }
+
+        let vertexes = encode_all(&self.vertexes, |vertex, encoding| {
+            number(vertex.position.x).hash(encoding);
+            number(vertex.position.y).hash(encoding);
+            vertex.floor_z.map(float_bits).hash(encoding);
+            vertex.ceiling_z.map(float_bits).hash(encoding);
+        });
+
+        let sectors = encode_all(&self.sectors, |sector, encoding| {
+            sector.floor_height.hash(encoding);
+            sector.ceiling_height.hash(encoding);
+            sector.floor_flat.hash(encoding);
+            sector.ceiling_flat.hash(encoding);
+            sector.light_level.hash(encoding);
+            i16::from(sector.special).hash(encoding);
+            sector.secret.hash(encoding);
+            sector.tag.hash(encoding);
+            sector.kill_grounded_monsters.hash(encoding);
+        });
+
+        let side_defs = encode_all(&self.side_defs, |side_def, encoding| {
+            sectors.get(side_def.sector).hash(encoding);
+            side_def.offset.hash(encoding);
+            side_def.upper_texture.hash(encoding);
+            side_def.middle_texture.hash(encoding);
+            side_def.lower_texture.hash(encoding);
+        });
+
+        let line_defs = encode_all(&self.line_defs, |line_def, encoding| {
+            vertexes.get(line_def.from).hash(encoding);
+            vertexes.get(line_def.to).hash(encoding);
+            side_defs.get(line_def.left_side).hash(encoding);
+            line_def
+                .right_side
+                .map(|side| side_defs.get(side))
+                .hash(encoding);
+            line_def.id.hash(encoding);
+            line_def.flags.hash(encoding);
+            line_def.special.hash(encoding);
+            line_def.trigger_flags.hash(encoding);
+        });
+
+        let things = encode_all(&self.things, |thing, encoding| {
+            number(thing.position.x).hash(encoding);
+            number(thing.position.y).hash(encoding);
+            thing.height.hash(encoding);
+            thing.angle.hash(encoding);
+            thing.type_.hash(encoding);
+            thing.flags.hash(encoding);
+            thing.special.hash(encoding);
+        });
+
+        Contents {
+            vertexes: sorted(vertexes),
+            sectors: sorted(sectors),
+            side_defs: sorted(side_defs),
+            line_defs: sorted(line_defs),
+            things: sorted(things),
+        }
+    }
+}
+
+/// The bits of a float, with 0.0 and -0.0 the same, and every NaN the same
+fn float_bits(float: f64) -> u64 {
+    if float == 0.0 {
+        0
+    } else if float.is_nan() {
+        f64::NAN.to_bits()
+    } else {
+        float.to_bits()
+    }
+}
+
+/// A `Hasher` which keeps every byte written to it, as an encoding of the hashed value which can be compared exactly
+#[derive(Default)]
+struct Encoding(Vec<u8>);
+
+impl Hasher for Encoding {
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.extend_from_slice(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        0
+    }
+}
+
+fn encode_all<K: slotmap::Key, V>(
+    entities: &SlotMap<K, V>,
+    encode: impl Fn(&V, &mut Encoding),
+) -> SecondaryMap<K, Vec<u8>> {
+    entities
+        .iter()
+        .map(|(key, entity)| {
+            let mut encoding = Encoding::default();
+            encode(entity, &mut encoding);
+            (key, encoding.0)
         })
+        .collect()
+}
+
+fn sorted<K: slotmap::Key>(encodings: SecondaryMap<K, Vec<u8>>) -> Vec<Vec<u8>> {
+    let mut encodings: Vec<_> = encodings
+        .into_iter()
+        .map(|(_, encoding)| encoding)
+        .collect();
+    encodings.sort_unstable();
+    encodings
+}
+
+/// The encoded entities of a map, as built by `Map::contents`
+#[derive(PartialEq, Eq, Hash)]
+struct Contents {
+    vertexes: Vec<Vec<u8>>,
+    sectors: Vec<Vec<u8>>,
+    side_defs: Vec<Vec<u8>>,
+    line_defs: Vec<Vec<u8>>,
+    things: Vec<Vec<u8>>,
+}
+
+/// Maps are equal if their names are equal and they have the same entities, in any order and with any keys.
+/// References are compared by the entities they refer to, e.g. the vertexes of a line by their positions.
+///
+/// Coordinates are compared by value, so an integer equals a float with the same value, -0.0 equals 0.0, and NaN
+/// equals NaN. Identical entities can't be told apart, so a map which leaves one of two identical sectors unused
+/// equals a map which uses both.
+impl PartialEq for Map {
+    fn eq(&self, rhs: &Self) -> bool {
+        self.name == rhs.name && self.same_contents(rhs)
     }
 }
 
-// TODO: Do I need these?
-//impl PartialEq for Map {
-//    fn eq(&self, rhs: &Self) -> bool {
-//        self.name == rhs.name
-//            && itertools::equal(self.linedefs(), rhs.linedefs.iter())
-//            && itertools::equal(self.sectors.iter(), rhs.sectors.iter())
-//            && itertools::equal(self.things.iter(), rhs.things.iter())
-//    }
-//}
-//
-//impl Eq for Map {}
+/// Consistent with `PartialEq`: hashes the name and the same contents as [Map::content_hash]
+impl Hash for Map {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.name.hash(state);
+        self.contents().hash(state);
+    }
+}
 
 #[cfg(test)]
 mod tests {
+    use std::collections::hash_map::DefaultHasher;
+
+    use super::*;
+
+    use crate::Point;
+
+    #[test]
+    fn checked_indices() {
+        let raw_map = RawMap {
+            name: "MAP01".try_into().unwrap(),
+            vertexes: vec![Vertex::default(); 2],
+            line_defs: Vec::new(),
+            sectors: Vec::new(),
+            side_defs: Vec::new(),
+            things: Vec::new(),
+            unknown_blocks: Vec::new(),
+        };
+
+        assert_eq!(raw_map.vertex_idx(1), Some(VertexIdx::new(1)));
+        assert_eq!(raw_map.vertex_idx(2), None);
+        assert_eq!(raw_map.sector_idx(0), None);
+        assert!(SideDefIdx::try_from(70_000_usize).is_err());
+        assert_eq!(usize::from(SectorIdx::new(3)), 3);
+    }
+
+    #[test]
+    fn equality_ignores_keys() {
+        let map = RawMap {
+            name: "MAP01".try_into().unwrap(),
+            vertexes: vec![Vertex::default(); 2],
+            line_defs: vec![RawLineDef {
+                from_idx: VertexIdx::new(0),
+                to_idx: VertexIdx::new(1),
+                left_side_idx: SideDefIdx::new(0),
+                right_side_idx: None,
+                id: line_def::NO_ID,
+                flags: Default::default(),
+                special: Default::default(),
+                trigger_flags: Default::default(),
+            }],
+            sectors: vec![Sector::default()],
+            side_defs: vec![RawSideDef::default()],
+            things: Vec::new(),
+            unknown_blocks: Vec::new(),
+        }
+        .link()
+        .unwrap();
+
+        // Reinserting the vertex gives it a new key in the same slot
+        let mut edited = map.clone();
+        let (line_key, line_def) = edited.line_defs.iter().next().unwrap();
+        let from = line_def.from;
+        let vertex = edited.vertexes.remove(from).unwrap();
+        let new_from = edited.vertexes.insert(vertex);
+        edited.line_defs[line_key].from = new_from;
+
+        assert_ne!(from, new_from);
+        assert_eq!(edited, map);
+        let hash = |map: &Map| {
+            let mut hasher = DefaultHasher::new();
+            map.hash(&mut hasher);
+            hasher.finish()
+        };
+        assert_eq!(hash(&edited), hash(&map));
+
+        edited.vertexes[new_from].position.x = Number::Int(64);
+        assert_ne!(edited, map);
+    }
+
+    #[test]
+    fn equality_ignores_order() {
+        let vertex = |x: i32| Vertex {
+            position: Point::new(Number::Int(x), Number::Int(0)),
+            ..Default::default()
+        };
+        let map = |vertexes: Vec<Vertex>, from: u16, to: u16| {
+            RawMap {
+                name: "MAP01".try_into().unwrap(),
+                vertexes,
+                line_defs: vec![RawLineDef {
+                    from_idx: VertexIdx::new(from),
+                    to_idx: VertexIdx::new(to),
+                    left_side_idx: SideDefIdx::new(0),
+                    right_side_idx: None,
+                    id: line_def::NO_ID,
+                    flags: Default::default(),
+                    special: Default::default(),
+                    trigger_flags: Default::default(),
+                }],
+                sectors: vec![Sector::default()],
+                side_defs: vec![RawSideDef::default()],
+                things: Vec::new(),
+                unknown_blocks: Vec::new(),
+            }
+            .link()
+            .unwrap()
+        };
+
+        let original = map(vec![vertex(0), vertex(64)], 0, 1);
+        let reordered = map(vec![vertex(64), vertex(0)], 1, 0);
+        assert_eq!(reordered, original);
+        assert_eq!(reordered.content_hash(), original.content_hash());
+        assert_ne!(map(vec![vertex(64), vertex(0)], 0, 1), original);
+
+        let mut negative_zero = original.clone();
+        for vertex in negative_zero.vertexes.values_mut() {
+            vertex.position.y = Number::Float(-0.0);
+        }
+        assert_eq!(negative_zero, original);
+        assert_eq!(negative_zero.content_hash(), original.content_hash());
+    }
+
+    #[test]
+    fn content_hash_covers_fields() {
+        let mut map = Map::new("MAP01".try_into().unwrap());
+        let sector = map.sectors.insert(Sector::default());
+        let vertex = map.vertexes.insert(Vertex::default());
+        let hash = map.content_hash();
+
+        let mut changed = map.clone();
+        changed.sectors[sector].secret = true;
+        assert_ne!(changed.content_hash(), hash);
+
+        let mut changed = map.clone();
+        changed.vertexes[vertex].floor_z = Some(16.0);
+        assert_ne!(changed.content_hash(), hash);
+
+        let mut changed = map.clone();
+        changed.sectors[sector].kill_grounded_monsters = true;
+        assert_ne!(changed.content_hash(), hash);
+    }
+
+    #[test]
+    fn incremental_link() {
+        let mut raw_map = RawMap {
+            name: "MAP01".try_into().unwrap(),
+            vertexes: vec![Vertex::default(); 2],
+            line_defs: Vec::new(),
+            sectors: vec![Sector::default()],
+            side_defs: Vec::new(),
+            things: Vec::new(),
+            unknown_blocks: Vec::new(),
+        };
+
+        let mut linker = Linker::new(raw_map.name.clone());
+        linker.link(&raw_map).unwrap();
+        let first_vertex = linker.vertex_keys()[0];
+
+        raw_map.side_defs.push(RawSideDef::default());
+        raw_map.line_defs.push(RawLineDef {
+            from_idx: VertexIdx::new(0),
+            to_idx: VertexIdx::new(2),
+            left_side_idx: SideDefIdx::new(0),
+            right_side_idx: None,
+            id: line_def::NO_ID,
+            flags: Default::default(),
+            special: Default::default(),
+            trigger_flags: Default::default(),
+        });
+        assert!(matches!(
+            linker.link(&raw_map),
+            Err(LinkError::IndexOutOfRange {
+                field: "to",
+                referrer_index: 0,
+                ..
+            })
+        ));
+        assert!(linker.map().side_defs.is_empty());
+
+        raw_map.vertexes.push(Vertex::default());
+        linker.link(&raw_map).unwrap();
+        assert_eq!(linker.vertex_keys()[0], first_vertex);
+        assert_eq!(
+            linker.map().line_defs[linker.line_def_keys()[0]].to,
+            linker.vertex_keys()[2]
+        );
+
+        raw_map.vertexes.pop();
+        assert!(matches!(
+            linker.link(&raw_map),
+            Err(LinkError::EntitiesRemoved {
+                entity_kind: EntityKind::Vertex,
+                ..
+            })
+        ));
+    }
+
     #[test]
     fn test_bitfields() {
-        let range = i16::min_value()..=i16::max_value();
+        let range = i16::MIN..=i16::MAX;
         assert_eq!(range.len(), 2_usize.pow(16));
 
         for n in range {
@@ -393,4 +1083,39 @@ mod tests {
             );
         }
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let map = Map::load_udmf_textmap(
+            "MAP01".try_into().unwrap(),
+            r#"
+            namespace = "zdoom";
+            vertex { x = 0.5; y = 0; }
+            vertex { x = 0; y = 128; }
+            vertex { x = 64; y = 128; }
+            vertex { x = 64; y = 0; }
+            sector { heightfloor = -8; heightceiling = 128; texturefloor = "FLAT1"; textureceiling = "CEIL1"; special = 1024; id = 3; }
+            sector { heightceiling = 96; texturefloor = "FLAT1"; textureceiling = "CEIL1"; }
+            sidedef { sector = 0; texturemiddle = "STARTAN3"; offsetx = 16; }
+            sidedef { sector = 1; }
+            linedef { v1 = 0; v2 = 1; sidefront = 0; blocking = true; }
+            linedef { v1 = 1; v2 = 2; sidefront = 0; }
+            linedef { v1 = 2; v2 = 3; sidefront = 0; sideback = 1; twosided = true; special = 12; arg0 = 3; arg1 = 16; playeruse = true; repeatspecial = true; }
+            linedef { v1 = 3; v2 = 0; sidefront = 0; }
+            thing { x = 32.25; y = 64.0; angle = 90; type = 1; skill1 = true; single = true; }
+            "#,
+        )
+        .unwrap();
+
+        let value = serde_json::to_value(&map).unwrap();
+        let round_tripped: Map = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(round_tripped.unlink().unwrap(), map.unlink().unwrap());
+        assert_eq!(serde_json::to_value(&round_tripped).unwrap(), value);
+
+        let raw_map = map.unlink().unwrap();
+        let raw_round_tripped: RawMap =
+            serde_json::from_value(serde_json::to_value(&raw_map).unwrap()).unwrap();
+        assert_eq!(raw_round_tripped, raw_map);
+    }
 }
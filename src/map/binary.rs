@@ -0,0 +1,748 @@
+//! Reading maps in the binary formats, where each kind of entity is stored in its own lump.
+//!
+//! Doom-format line specials are converted with [DoomConversion], and sector specials are renumbered as in ZDoom, so
+//! a binary map can be converted to UDMF by reading it with [RawMap::parse_binary] and writing it with
+//! [RawMap::to_udmf]. Anything which couldn't be converted is listed in the `ConversionReport`.
+
+use std::fmt::{self, Display, Formatter};
+
+use bitfield::Bit;
+
+use crate::{
+    map::{
+        line_def::{self, DoomConversion, DoomSpecial, RawLineDef, TriggerFlags, UdmfSpecial},
+        sector,
+        side_def::RawSideDef,
+        thing::{self, FlagsEncoding},
+        RawMap, Sector, SideDefIdx, Thing, Vertex, VertexIdx,
+    },
+    number::Number,
+    units::{Angle, Height, TextureOffset},
+    wad::{LumpError, LumpReader},
+    Point, String8,
+};
+
+/// The binary map formats
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BinaryFormat {
+    /// The original format, also used by Boom and MBF
+    Doom,
+    /// Hexen's format, with parameterized line specials and thing ids. It's recognised by its BEHAVIOR lump.
+    Hexen,
+    /// Strife's format, laid out as Doom's but with its own line and thing flags. Strife numbers its line specials as
+    /// Doom does, and adds its own, which aren't converted. It can't be told apart from Doom's by its lumps.
+    Strife,
+}
+
+impl BinaryFormat {
+    /// How the format packs line flags
+    pub fn line_flags_encoding(self) -> line_def::FlagsEncoding {
+        match self {
+            BinaryFormat::Doom => line_def::FlagsEncoding::Doom,
+            BinaryFormat::Hexen => line_def::FlagsEncoding::Hexen,
+            BinaryFormat::Strife => line_def::FlagsEncoding::Strife,
+        }
+    }
+
+    /// How the format packs thing flags
+    pub fn thing_flags_encoding(self) -> FlagsEncoding {
+        match self {
+            BinaryFormat::Doom => FlagsEncoding::Boom,
+            BinaryFormat::Hexen => FlagsEncoding::Hexen,
+            BinaryFormat::Strife => FlagsEncoding::Strife,
+        }
+    }
+}
+
+/// The lumps of a map in a binary format. The node lumps aren't needed, as they're derived from the rest.
+#[derive(Clone, Copy, Debug)]
+pub struct BinaryMapLumps<'a> {
+    pub things: &'a [u8],
+    pub line_defs: &'a [u8],
+    pub side_defs: &'a [u8],
+    pub vertexes: &'a [u8],
+    pub sectors: &'a [u8],
+}
+
+/// Something in a binary map with no exact equivalent in a `RawMap`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConversionIssue {
+    /// A Doom line special with no `Special` equivalent. The line was given `Special::None`.
+    DoomLineSpecial {
+        line_def: usize,
+        special: DoomSpecial,
+    },
+    /// A Hexen line special which isn't known. The line was given `Special::None`.
+    HexenLineSpecial {
+        line_def: usize,
+        special: UdmfSpecial,
+    },
+    /// A sector special with no `sector::Special` equivalent, numbered as in the map. The sector was given
+    /// `sector::Special::None`.
+    SectorSpecial { sector: usize, special: i16 },
+    /// The friction, pusher and sound bits of a Boom generalized sector type, which were dropped
+    SectorFlags { sector: usize, flags: i16 },
+    /// Boom's pass-use line flag (bit 9), which lets a use reach the lines behind, and was dropped
+    PassUse { line_def: usize },
+    /// A Hexen line special arg out of range for its field, e.g. a `bool` arg of 2, which was clamped into range
+    ClampedArg {
+        line_def: usize,
+        arg: usize,
+        value: i16,
+        clamped: i16,
+    },
+    /// A Doom thing with Boom's reserved flag bit (8) set, which marks its deathmatch, co-op and friend bits as junk
+    /// left by an old editor. They were ignored.
+    ReservedThingFlag { thing: usize },
+    /// A Hexen thing's id, special or args, which were dropped
+    ThingFields { thing: usize },
+}
+
+impl Display for ConversionIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ConversionIssue::DoomLineSpecial { line_def, special } => write!(
+                f,
+                "line {line_def} has Doom special {} (tag {}), which has no UDMF equivalent",
+                special.value, special.tag
+            ),
+            ConversionIssue::HexenLineSpecial { line_def, special } => write!(
+                f,
+                "line {line_def} has unknown Hexen special {} with args {:?}",
+                special.value, special.args
+            ),
+            ConversionIssue::SectorSpecial { sector, special } => {
+                write!(f, "sector {sector} has unknown special {special}")
+            }
+            ConversionIssue::SectorFlags { sector, flags } => {
+                write!(
+                    f,
+                    "sector {sector} has generalized flags {flags:#x}, which were dropped"
+                )
+            }
+            ConversionIssue::PassUse { line_def } => {
+                write!(f, "line {line_def} passes uses through, which was dropped")
+            }
+            ConversionIssue::ClampedArg {
+                line_def,
+                arg,
+                value,
+                clamped,
+            } => write!(
+                f,
+                "line {line_def} has arg{arg} {value}, which is out of range and was clamped to {clamped}"
+            ),
+            ConversionIssue::ReservedThingFlag { thing } => write!(
+                f,
+                "thing {thing} has Boom's reserved flag set, so its deathmatch, co-op and friend flags were ignored"
+            ),
+            ConversionIssue::ThingFields { thing } => {
+                write!(f, "thing {thing} has an id or special, which were dropped")
+            }
+        }
+    }
+}
+
+/// What happened to the specials of a binary map as it was read by [RawMap::parse_binary]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ConversionReport {
+    /// The Doom line specials which were converted, with the index of their line
+    pub conversions: Vec<(usize, DoomConversion)>,
+    pub issues: Vec<ConversionIssue>,
+}
+
+impl ConversionReport {
+    /// The indices of the lines whose specials had no equivalent, and were dropped
+    pub fn unconverted_line_defs(&self) -> impl Iterator<Item = usize> + '_ {
+        self.issues.iter().filter_map(|issue| match issue {
+            ConversionIssue::DoomLineSpecial { line_def, .. }
+            | ConversionIssue::HexenLineSpecial { line_def, .. } => Some(*line_def),
+            _ => None,
+        })
+    }
+}
+
+/// The value of a side index meaning there is no side
+const NO_SIDE: u16 = 0xffff;
+
+impl RawMap {
+    /// Read a map in a binary format, converting its specials to the ones used by UDMF
+    pub fn parse_binary(
+        name: String8,
+        format: BinaryFormat,
+        lumps: &BinaryMapLumps,
+    ) -> Result<(Self, ConversionReport), LumpError> {
+        let mut report = ConversionReport::default();
+
+        let vertexes = read_all(
+            LumpReader::new("VERTEXES", lumps.vertexes),
+            4,
+            |reader, _| {
+                Ok(Vertex {
+                    position: Point::new(
+                        Number::Int(reader.i16()?.into()),
+                        Number::Int(reader.i16()?.into()),
+                    ),
+                    ..Default::default()
+                })
+            },
+        )?;
+
+        let side_defs = read_all(
+            LumpReader::new("SIDEDEFS", lumps.side_defs),
+            30,
+            |reader, _| {
+                let offset = Point::new(TextureOffset(reader.i16()?), TextureOffset(reader.i16()?));
+                let upper_texture = reader.string8()?;
+                let lower_texture = reader.string8()?;
+                let middle_texture = reader.string8()?;
+
+                Ok(RawSideDef {
+                    offset,
+                    upper_texture,
+                    middle_texture,
+                    lower_texture,
+                    sector_idx: reader.u16()?.into(),
+                })
+            },
+        )?;
+
+        let sectors = read_all(
+            LumpReader::new("SECTORS", lumps.sectors),
+            26,
+            |reader, i| {
+                let floor_height = Height(reader.i16()?);
+                let ceiling_height = Height(reader.i16()?);
+                let floor_flat = reader.string8()?;
+                let ceiling_flat = reader.string8()?;
+                let light_level = reader.i16()?.clamp(0, 255) as u8;
+                let value = reader.i16()?;
+                let generalized = format == BinaryFormat::Doom && value >= 32;
+
+                // ZDoom numbers the Doom sector types from 65, and marks secrets with a flag rather than a type
+                let (renumbered, secret) = match format {
+                    BinaryFormat::Doom | BinaryFormat::Strife if value == sector::DOOM_SECRET => {
+                        (0, true)
+                    }
+                    BinaryFormat::Doom | BinaryFormat::Strife if (1..=17).contains(&value) => {
+                        (value + 64, false)
+                    }
+                    BinaryFormat::Doom if generalized => (
+                        generalized_sector_special(value),
+                        value & sector::GENERALIZED_SECRET != 0,
+                    ),
+                    BinaryFormat::Doom | BinaryFormat::Strife => (value, false),
+                    BinaryFormat::Hexen => (
+                        value & !sector::SECRET_FLAG,
+                        value & sector::SECRET_FLAG != 0,
+                    ),
+                };
+                let special = sector::Special::try_from(renumbered).unwrap_or_else(|_| {
+                    report.issues.push(ConversionIssue::SectorSpecial {
+                        sector: i,
+                        special: value,
+                    });
+                    sector::Special::None
+                });
+
+                let effects = value & sector::GENERALIZED_EFFECTS;
+                if generalized && effects != 0 {
+                    report.issues.push(ConversionIssue::SectorFlags {
+                        sector: i,
+                        flags: effects,
+                    });
+                }
+
+                Ok(Sector {
+                    floor_height,
+                    ceiling_height,
+                    floor_flat,
+                    ceiling_flat,
+                    light_level,
+                    special,
+                    secret,
+                    tag: reader.i16()?,
+                    kill_grounded_monsters: generalized
+                        && value & sector::MBF21_KILL_GROUNDED_MONSTERS != 0,
+                })
+            },
+        )?;
+
+        let line_def_len = match format {
+            BinaryFormat::Doom | BinaryFormat::Strife => 14,
+            BinaryFormat::Hexen => 16,
+        };
+        let line_defs = read_all(
+            LumpReader::new("LINEDEFS", lumps.line_defs),
+            line_def_len,
+            |reader, i| {
+                let from_idx = VertexIdx::new(reader.u16()?);
+                let to_idx = VertexIdx::new(reader.u16()?);
+                let flags = reader.i16()?;
+                if format == BinaryFormat::Doom && (flags as u16).bit(9) {
+                    report.issues.push(ConversionIssue::PassUse { line_def: i });
+                }
+
+                let (id, special, trigger_flags) = match format {
+                    BinaryFormat::Doom | BinaryFormat::Strife => {
+                        let doom = DoomSpecial::new(reader.i16()?, reader.i16()?);
+                        let id = if doom.tag == 0 {
+                            line_def::NO_ID
+                        } else {
+                            doom.tag
+                        };
+
+                        match DoomConversion::convert(doom) {
+                            Ok(conversion) => {
+                                let converted = (
+                                    id,
+                                    conversion.special.clone(),
+                                    conversion.trigger_flags.clone(),
+                                );
+                                if doom.value != 0 {
+                                    report.conversions.push((i, conversion));
+                                }
+                                converted
+                            }
+                            Err(special) => {
+                                report.issues.push(ConversionIssue::DoomLineSpecial {
+                                    line_def: i,
+                                    special,
+                                });
+                                (id, line_def::Special::None, TriggerFlags::default())
+                            }
+                        }
+                    }
+
+                    BinaryFormat::Hexen => {
+                        let value = reader.u8()?.into();
+                        let args = reader.bytes::<5>()?.map(i16::from);
+                        let special = UdmfSpecial::new(value, args).clamp_args();
+                        for (arg, (value, clamped)) in
+                            args.into_iter().zip(special.args).enumerate()
+                        {
+                            if value != clamped {
+                                report.issues.push(ConversionIssue::ClampedArg {
+                                    line_def: i,
+                                    arg,
+                                    value,
+                                    clamped,
+                                });
+                            }
+                        }
+                        let special =
+                            line_def::Special::try_from(special).unwrap_or_else(|special| {
+                                report.issues.push(ConversionIssue::HexenLineSpecial {
+                                    line_def: i,
+                                    special,
+                                });
+                                line_def::Special::None
+                            });
+                        let id = match special {
+                            line_def::Special::LineSetIdentification { lineid, .. } => lineid,
+                            _ => line_def::NO_ID,
+                        };
+
+                        (id, special, hexen_trigger_flags(flags))
+                    }
+                };
+
+                let left_side_idx = SideDefIdx::new(reader.u16()?);
+                let right_side_idx = Some(reader.u16()?)
+                    .filter(|&side| side != NO_SIDE)
+                    .map(SideDefIdx::new);
+
+                Ok(RawLineDef {
+                    from_idx,
+                    to_idx,
+                    left_side_idx,
+                    right_side_idx,
+                    id,
+                    flags: line_def::Flags::decode(flags as u16, format.line_flags_encoding()),
+                    special,
+                    trigger_flags,
+                })
+            },
+        )?;
+
+        let things = match format {
+            BinaryFormat::Doom | BinaryFormat::Strife => {
+                read_all(LumpReader::new("THINGS", lumps.things), 10, |reader, i| {
+                    let position = Point::new(
+                        Number::Int(reader.i16()?.into()),
+                        Number::Int(reader.i16()?.into()),
+                    );
+                    let angle = Angle(reader.i16()?);
+                    let type_ = reader.i16()?;
+                    let flags = reader.u16()?;
+                    let encoding = format.thing_flags_encoding();
+                    if encoding == FlagsEncoding::Boom && flags.bit(thing::BOOM_RESERVED_BIT) {
+                        report
+                            .issues
+                            .push(ConversionIssue::ReservedThingFlag { thing: i });
+                    }
+
+                    Ok(Thing {
+                        position,
+                        height: Height(0),
+                        angle,
+                        type_,
+                        flags: thing::Flags::decode(flags, encoding),
+                        special: thing::Special::None,
+                    })
+                })?
+            }
+
+            BinaryFormat::Hexen => {
+                read_all(LumpReader::new("THINGS", lumps.things), 20, |reader, i| {
+                    let tid = reader.i16()?;
+                    let position = Point::new(
+                        Number::Int(reader.i16()?.into()),
+                        Number::Int(reader.i16()?.into()),
+                    );
+                    let height = Height(reader.i16()?);
+                    let angle = Angle(reader.i16()?);
+                    let type_ = reader.i16()?;
+                    let flags = thing::Flags::decode(reader.u16()?, format.thing_flags_encoding());
+                    let special = reader.u8()?;
+                    let args = reader.bytes::<5>()?;
+
+                    if tid != 0 || special != 0 || args != [0; 5] {
+                        report
+                            .issues
+                            .push(ConversionIssue::ThingFields { thing: i });
+                    }
+
+                    Ok(Thing {
+                        position,
+                        height,
+                        angle,
+                        type_,
+                        flags,
+                        special: thing::Special::None,
+                    })
+                })?
+            }
+        };
+
+        let raw_map = Self {
+            name,
+            vertexes,
+            line_defs,
+            sectors,
+            side_defs,
+            things,
+            unknown_blocks: Vec::new(),
+        };
+
+        Ok((raw_map, report))
+    }
+}
+
+/// Read every `len`-byte record of a lump. Trailing bytes which don't make up a whole record are ignored, as in the
+/// engine.
+fn read_all<T>(
+    mut reader: LumpReader,
+    len: usize,
+    mut read: impl FnMut(&mut LumpReader, usize) -> Result<T, LumpError>,
+) -> Result<Vec<T>, LumpError> {
+    (0..reader.remaining().len() / len)
+        .map(|i| read(&mut reader, i))
+        .collect()
+}
+
+/// The special of a Boom generalized sector type, numbered as in ZDoom, from its lighting and damage bits, or the
+/// type itself if they have no single equivalent. With MBF21's alternate damage bit, the two lowest damage values
+/// kill the player outright, and the others end the level, which has no equivalent.
+fn generalized_sector_special(value: i16) -> i16 {
+    let lighting = value & 0x1f;
+    let damage = (value & sector::GENERALIZED_DAMAGE) >> 5;
+
+    match (lighting, damage) {
+        (0, 0 | 1) if value & sector::MBF21_ALT_DAMAGE != 0 => 115,
+        _ if value & sector::MBF21_ALT_DAMAGE != 0 => value,
+        (0, damage) => [0, 71, 69, 80][damage as usize],
+        (lighting, 0) => lighting + 64,
+        _ => value,
+    }
+}
+
+/// The activation bits of a Hexen line's flags: a repeat flag, a 3-bit activation type, and ZDoom's flag letting
+/// monsters activate player-triggered lines
+fn hexen_trigger_flags(flags: i16) -> TriggerFlags {
+    let flags_bits = flags as u16;
+    let activation = (flags_bits >> 10) & 0x7;
+
+    TriggerFlags {
+        player_cross: activation == 0,
+        player_use: activation == 1,
+        monster_cross: activation == 2,
+        impact: activation == 3,
+        player_push: activation == 4,
+        missile_cross: activation == 5,
+        repeats: flags_bits.bit(9),
+        monsters_activate: flags_bits.bit(13),
+        ..TriggerFlags::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        map::{compat::CompatProfile, line_def::Special, udmf::WriteError},
+        wad::WriteLe,
+    };
+
+    #[test]
+    fn doom_map_to_udmf() {
+        let mut vertexes = Vec::new();
+        for (x, y) in [(0, 0), (64, 0)] {
+            vertexes.write_i16(x).unwrap();
+            vertexes.write_i16(y).unwrap();
+        }
+
+        let mut side_defs = Vec::new();
+        side_defs.write_i16(0).unwrap();
+        side_defs.write_i16(0).unwrap();
+        for texture in ["-", "-", "STARTAN3"] {
+            side_defs
+                .write_string8(&String8::new_unchecked(texture))
+                .unwrap();
+        }
+        side_defs.write_u16(0).unwrap();
+
+        let mut sectors = Vec::new();
+        sectors.write_i16(0).unwrap();
+        sectors.write_i16(128).unwrap();
+        sectors
+            .write_string8(&String8::new_unchecked("FLOOR4_8"))
+            .unwrap();
+        sectors
+            .write_string8(&String8::new_unchecked("CEIL3_5"))
+            .unwrap();
+        sectors.write_i16(160).unwrap();
+        // Nukage, and a light special which isn't supported yet
+        sectors.write_i16(7).unwrap();
+        sectors.write_i16(0).unwrap();
+        sectors.extend_from_slice(&sectors.clone());
+        sectors[48] = 1;
+
+        // A switched door, and a Boom generalized special
+        let mut line_defs = Vec::new();
+        for (special, tag) in [(1, 0), (0x3c00, 5)] {
+            for value in [0, 1, 0, special, tag, 0, NO_SIDE as i16] {
+                line_defs.write_i16(value).unwrap();
+            }
+        }
+
+        let mut things = Vec::new();
+        for value in [32, 32, 90, 1, 7] {
+            things.write_i16(value).unwrap();
+        }
+
+        let lumps = BinaryMapLumps {
+            things: &things,
+            line_defs: &line_defs,
+            side_defs: &side_defs,
+            vertexes: &vertexes,
+            sectors: &sectors,
+        };
+        let (raw_map, report) =
+            RawMap::parse_binary("MAP01".try_into().unwrap(), BinaryFormat::Doom, &lumps).unwrap();
+
+        assert_eq!(raw_map.sectors[0].special, sector::Special::DamageNukage);
+        assert!(matches!(
+            raw_map.line_defs[0].special,
+            Special::DoorRaise { delay: 150, .. }
+        ));
+        assert_eq!(raw_map.line_defs[1].special, Special::None);
+        assert_eq!(raw_map.line_defs[1].id, 5);
+        assert_eq!(raw_map.things[0].type_, 1);
+
+        assert_eq!(report.conversions.len(), 1);
+        assert_eq!(report.unconverted_line_defs().collect::<Vec<_>>(), vec![1]);
+        assert_eq!(
+            report
+                .issues
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec![
+                "sector 1 has unknown special 1",
+                "line 1 has Doom special 15360 (tag 5), which has no UDMF equivalent",
+            ]
+        );
+
+        assert!(matches!(
+            raw_map.to_udmf("doom"),
+            Err(WriteError::UnsupportedNamespace(_))
+        ));
+        let textmap = raw_map.to_udmf("zdoom").unwrap();
+        assert!(textmap.starts_with("namespace=\"zdoom\";\n"));
+        assert!(textmap.contains("special=12;"));
+        assert!(textmap.contains("id=5;"));
+        assert!(textmap.contains("texturemiddle=\"STARTAN3\";"));
+    }
+
+    #[test]
+    fn mbf21_flags() {
+        let mut vertexes = Vec::new();
+        for (x, y) in [(0, 0), (64, 0)] {
+            vertexes.write_i16(x).unwrap();
+            vertexes.write_i16(y).unwrap();
+        }
+
+        let mut side_defs = Vec::new();
+        side_defs.write_i16(0).unwrap();
+        side_defs.write_i16(0).unwrap();
+        for _ in 0..3 {
+            side_defs
+                .write_string8(&String8::new_unchecked("-"))
+                .unwrap();
+        }
+        side_defs.write_u16(0).unwrap();
+
+        // A secret which kills players outright and grounded monsters, and a nukage sector with friction
+        let mut sectors = Vec::new();
+        for special in [0x3080, 0x0120] {
+            sectors.write_i16(0).unwrap();
+            sectors.write_i16(128).unwrap();
+            for flat in ["FLOOR4_8", "CEIL3_5"] {
+                sectors
+                    .write_string8(&String8::new_unchecked(flat))
+                    .unwrap();
+            }
+            sectors.write_i16(160).unwrap();
+            sectors.write_i16(special).unwrap();
+            sectors.write_i16(0).unwrap();
+        }
+
+        let mut line_defs = Vec::new();
+        for value in [0, 1, 0x3201, 0, 0, 0, NO_SIDE as i16] {
+            line_defs.write_i16(value).unwrap();
+        }
+
+        // A thing whose junk high bits are marked by Boom's reserved bit
+        let mut things = Vec::new();
+        for value in [32, 32, 0, 3001, 0x1e7] {
+            things.write_i16(value).unwrap();
+        }
+
+        let lumps = BinaryMapLumps {
+            things: &things,
+            line_defs: &line_defs,
+            side_defs: &side_defs,
+            vertexes: &vertexes,
+            sectors: &sectors,
+        };
+        let (raw_map, report) =
+            RawMap::parse_binary("MAP01".try_into().unwrap(), BinaryFormat::Doom, &lumps).unwrap();
+
+        let flags = &raw_map.line_defs[0].flags;
+        assert!(flags.impassable && flags.blocks_land_monsters && flags.blocks_players);
+
+        let thing_flags = &raw_map.things[0].flags;
+        assert!(thing_flags.dm && thing_flags.coop && !thing_flags.mbf_friend);
+
+        let sector = &raw_map.sectors[0];
+        assert_eq!(sector.special, sector::Special::DamageInstantDeath);
+        assert!(sector.secret && sector.kill_grounded_monsters);
+        assert_eq!(raw_map.sectors[1].special, sector::Special::DamageNukage);
+        assert_eq!(
+            report.issues,
+            [
+                ConversionIssue::SectorFlags {
+                    sector: 1,
+                    flags: 0x100
+                },
+                ConversionIssue::PassUse { line_def: 0 },
+                ConversionIssue::ReservedThingFlag { thing: 0 },
+            ]
+        );
+
+        let map = raw_map.link().unwrap();
+        assert!(map.validate_for(CompatProfile::Mbf21).is_empty());
+        assert_eq!(
+            map.validate_for(CompatProfile::Boom)
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            [
+                "line has flags the port doesn't have",
+                "sector has DamageInstantDeath, which the port doesn't have",
+                "sector kills grounded monsters, which the port doesn't have",
+            ]
+        );
+    }
+
+    #[test]
+    fn hexen_clamped_args() {
+        let mut vertexes = Vec::new();
+        for (x, y) in [(0, 0), (64, 0)] {
+            vertexes.write_i16(x).unwrap();
+            vertexes.write_i16(y).unwrap();
+        }
+
+        let mut side_defs = Vec::new();
+        side_defs.write_i16(0).unwrap();
+        side_defs.write_i16(0).unwrap();
+        for _ in 0..3 {
+            side_defs
+                .write_string8(&String8::new_unchecked("-"))
+                .unwrap();
+        }
+        side_defs.write_u16(0).unwrap();
+
+        let mut sectors = Vec::new();
+        sectors.write_i16(0).unwrap();
+        sectors.write_i16(128).unwrap();
+        for flat in ["FLOOR4_8", "CEIL3_5"] {
+            sectors
+                .write_string8(&String8::new_unchecked(flat))
+                .unwrap();
+        }
+        for value in [160, 0, 0] {
+            sectors.write_i16(value).unwrap();
+        }
+
+        // Thing_Raise with a nocheck of 2
+        let mut line_defs = Vec::new();
+        for value in [0, 1, 0] {
+            line_defs.write_u16(value).unwrap();
+        }
+        for value in [17, 3, 2, 0, 0, 0] {
+            line_defs.write_u8(value).unwrap();
+        }
+        line_defs.write_u16(0).unwrap();
+        line_defs.write_u16(NO_SIDE).unwrap();
+
+        let lumps = BinaryMapLumps {
+            things: &[],
+            line_defs: &line_defs,
+            side_defs: &side_defs,
+            vertexes: &vertexes,
+            sectors: &sectors,
+        };
+        let (raw_map, report) =
+            RawMap::parse_binary("MAP01".try_into().unwrap(), BinaryFormat::Hexen, &lumps).unwrap();
+
+        assert_eq!(
+            raw_map.line_defs[0].special,
+            Special::ThingRaise {
+                tid: 3,
+                nocheck: true
+            }
+        );
+        assert_eq!(
+            report.issues,
+            [ConversionIssue::ClampedArg {
+                line_def: 0,
+                arg: 1,
+                value: 2,
+                clamped: 1
+            }]
+        );
+    }
+}
@@ -0,0 +1,193 @@
+use crate::{
+    map::{
+        geometry::{check_coordinate, GeometryOverflow},
+        line_def::LineDefKey,
+        lint::EntityRef,
+        Map,
+    },
+    Point,
+};
+
+/// The width and height of a block, in map units
+pub const BLOCK_SIZE: f64 = 128.0;
+
+/// A grid over the map listing the lines which pass through each block, as in the BLOCKMAP lump. The engine uses it
+/// to find the lines near a moving object without testing every line in the map.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Blockmap {
+    /// The bottom left corner of the grid
+    pub origin: Point<i16>,
+    pub columns: u16,
+    pub rows: u16,
+    /// The lines passing through or touching each block, row by row from the bottom, in the map's line order
+    pub blocks: Vec<Vec<LineDefKey>>,
+}
+
+impl Map {
+    /// Build a blockmap covering the map's lines. Lines with invalid vertexes are skipped.
+    ///
+    /// Fails if a vertex is outside `COORDINATE_RANGE`, as for [Map::build_nodes].
+    pub fn build_blockmap(&self) -> Result<Blockmap, GeometryOverflow> {
+        for (key, vertex) in self.vertexes.iter() {
+            check_coordinate(EntityRef::Vertex(key), "x", vertex.position.x.into_float())?;
+            check_coordinate(EntityRef::Vertex(key), "y", vertex.position.y.into_float())?;
+        }
+
+        let lines: Vec<_> = self
+            .line_defs
+            .iter()
+            .filter_map(|(key, line_def)| Some((key, self.line_segment(line_def)?)))
+            .collect();
+
+        let Some((min, max)) = lines
+            .iter()
+            .flat_map(|(_, (from, to))| [*from, *to])
+            .map(|point| (point, point))
+            .reduce(|(min, max), (point, _)| {
+                (
+                    Point::new(min.x.min(point.x), min.y.min(point.y)),
+                    Point::new(max.x.max(point.x), max.y.max(point.y)),
+                )
+            })
+        else {
+            return Ok(Blockmap::default());
+        };
+
+        // Checked coordinates round to at least -32768, so the floor fits
+        let origin = min.map(|value| value.floor() as i16);
+        let columns = block_index(max.x - f64::from(origin.x)) + 1;
+        let rows = block_index(max.y - f64::from(origin.y)) + 1;
+
+        let mut blockmap = Blockmap {
+            origin,
+            columns,
+            rows,
+            blocks: vec![Vec::new(); usize::from(columns) * usize::from(rows)],
+        };
+
+        for (key, (from, to)) in lines {
+            let [from, to] = [from, to].map(|point| {
+                Point::new(point.x - f64::from(origin.x), point.y - f64::from(origin.y))
+            });
+
+            for row in block_index(from.y.min(to.y))..=block_index(from.y.max(to.y)) {
+                for column in block_index(from.x.min(to.x))..=block_index(from.x.max(to.x)) {
+                    if crosses_block(from, to, column, row) {
+                        let index = usize::from(row) * usize::from(columns) + usize::from(column);
+                        blockmap.blocks[index].push(key);
+                    }
+                }
+            }
+        }
+
+        Ok(blockmap)
+    }
+}
+
+impl Blockmap {
+    /// The lines in the block containing `point`. Empty if `point` is outside the grid.
+    pub fn lines_at(&self, point: Point<f64>) -> &[LineDefKey] {
+        let x = point.x - f64::from(self.origin.x);
+        let y = point.y - f64::from(self.origin.y);
+
+        if x < 0.0 || y < 0.0 {
+            return &[];
+        }
+
+        let (column, row) = (block_index(x), block_index(y));
+        if column >= self.columns || row >= self.rows {
+            return &[];
+        }
+
+        &self.blocks[usize::from(row) * usize::from(self.columns) + usize::from(column)]
+    }
+}
+
+/// The index of the block containing an offset from the origin. Coordinates are within `COORDINATE_RANGE`, so there
+/// are at most 512 blocks in either direction.
+fn block_index(offset: f64) -> u16 {
+    (offset / BLOCK_SIZE)
+        .floor()
+        .clamp(0.0, f64::from(u16::MAX)) as u16
+}
+
+/// Whether the segment from `from` to `to` passes through or touches a block, given that their bounding boxes overlap.
+/// It does unless all four corners of the block are strictly on the same side of the line.
+fn crosses_block(from: Point<f64>, to: Point<f64>, column: u16, row: u16) -> bool {
+    let min = Point::new(f64::from(column) * BLOCK_SIZE, f64::from(row) * BLOCK_SIZE);
+    let max = Point::new(min.x + BLOCK_SIZE, min.y + BLOCK_SIZE);
+    let delta = Point::new(to.x - from.x, to.y - from.y);
+
+    let sides = [min, Point::new(min.x, max.y), max, Point::new(max.x, min.y)]
+        .map(|corner| delta.x * (corner.y - from.y) - delta.y * (corner.x - from.x));
+
+    !(sides.iter().all(|side| *side > 0.0) || sides.iter().all(|side| *side < 0.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        map::{line_def, LineDef, Sector, SideDef, Vertex},
+        number::Number,
+    };
+
+    #[test]
+    fn blockmap_lines() {
+        let mut map = Map::new("MAP01".try_into().unwrap());
+        let sector = map.sectors.insert(Sector::default());
+
+        let mut line = |from: (i32, i32), to: (i32, i32)| {
+            let [from, to] = [from, to].map(|(x, y)| {
+                map.vertexes.insert(Vertex {
+                    position: Point::new(Number::Int(x), Number::Int(y)),
+                    ..Default::default()
+                })
+            });
+            let left_side = map.side_defs.insert(SideDef {
+                sector,
+                offset: Point::default(),
+                upper_texture: Default::default(),
+                middle_texture: Default::default(),
+                lower_texture: Default::default(),
+            });
+
+            map.line_defs.insert(LineDef {
+                from,
+                to,
+                left_side,
+                right_side: None,
+                id: line_def::NO_ID,
+                flags: Default::default(),
+                special: Default::default(),
+                trigger_flags: Default::default(),
+            })
+        };
+
+        let bottom = line((-64, -32), (300, -32));
+        let diagonal = line((-64, -32), (300, 200));
+        let short = line((200, 150), (210, 160));
+
+        let blockmap = map.build_blockmap().unwrap();
+        assert_eq!(blockmap.origin, Point::new(-64, -32));
+        assert_eq!((blockmap.columns, blockmap.rows), (3, 2));
+
+        // The diagonal misses the top left and bottom right blocks
+        assert_eq!(blockmap.lines_at(Point::new(0.0, 0.0)), [bottom, diagonal]);
+        assert_eq!(blockmap.lines_at(Point::new(0.0, 150.0)), []);
+        assert_eq!(blockmap.lines_at(Point::new(250.0, 0.0)), [bottom]);
+        assert_eq!(blockmap.lines_at(Point::new(150.0, 150.0)), [diagonal]);
+        assert_eq!(
+            blockmap.lines_at(Point::new(250.0, 150.0)),
+            [diagonal, short]
+        );
+        assert_eq!(blockmap.lines_at(Point::new(-100.0, 0.0)), []);
+        assert_eq!(blockmap.lines_at(Point::new(0.0, 1000.0)), []);
+
+        assert_eq!(
+            Map::new("MAP01".try_into().unwrap()).build_blockmap(),
+            Ok(Blockmap::default())
+        );
+    }
+}
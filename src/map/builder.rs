@@ -0,0 +1,348 @@
+//! Building a `RawMap` from streams of entities, checking their references as they're added rather than in `link`.
+
+use crate::{
+    map::{
+        self, line_def::RawLineDef, side_def::RawSideDef, EntityKind, LinkError, RawMap, Sector,
+        SectorIdx, SideDefIdx, Thing, Vertex, VertexIdx,
+    },
+    source::Position,
+    String8,
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum BuildError {
+    #[error("{source}, but only {len} were added{}", source_position(*position))]
+    IndexOutOfRange {
+        source: LinkError,
+        len: usize,
+        position: Option<Position>,
+    },
+
+    #[error("Too many {entity_kind} to fit in an u16{}", source_position(*position))]
+    IndexTooLarge {
+        entity_kind: EntityKind,
+        position: Option<Position>,
+    },
+}
+
+fn source_position(position: Option<Position>) -> String {
+    position.map_or_else(String::new, |position| {
+        format!(
+            " (line {}, column {})",
+            position.line + 1,
+            position.column + 1
+        )
+    })
+}
+
+/// Where each entity of a built `RawMap` came from in its source, by index, for reporting later errors, e.g. from
+/// [RawMap::link] or lints.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SourcePositions {
+    pub vertexes: Vec<Option<Position>>,
+    pub line_defs: Vec<Option<Position>>,
+    pub sectors: Vec<Option<Position>>,
+    pub side_defs: Vec<Option<Position>>,
+    pub things: Vec<Option<Position>>,
+}
+
+impl SourcePositions {
+    pub fn get(&self, kind: EntityKind, index: usize) -> Option<Position> {
+        let positions = match kind {
+            EntityKind::Vertex => &self.vertexes,
+            EntityKind::LineDef => &self.line_defs,
+            EntityKind::Sector => &self.sectors,
+            EntityKind::SideDef => &self.side_defs,
+            EntityKind::Thing => &self.things,
+        };
+
+        positions.get(index).copied().flatten()
+    }
+}
+
+/// Builds a `RawMap` one entity at a time, e.g. from an importer's iterators.
+///
+/// References are checked as soon as an entity is added, so entities have to be added after the entities they refer
+/// to: side defs after their sectors, and line defs after their vertexes and side defs. A `RawMap` built this way
+/// always links successfully.
+#[derive(Debug)]
+pub struct RawMapBuilder {
+    raw_map: RawMap,
+    positions: SourcePositions,
+}
+
+impl RawMapBuilder {
+    pub fn new(name: String8) -> Self {
+        Self {
+            raw_map: RawMap {
+                name,
+                vertexes: Vec::new(),
+                line_defs: Vec::new(),
+                sectors: Vec::new(),
+                side_defs: Vec::new(),
+                things: Vec::new(),
+                unknown_blocks: Vec::new(),
+            },
+            positions: SourcePositions::default(),
+        }
+    }
+
+    pub fn vertex(
+        &mut self,
+        vertex: Vertex,
+        position: Option<Position>,
+    ) -> Result<VertexIdx, BuildError> {
+        let idx = next_idx(self.raw_map.vertexes.len(), EntityKind::Vertex, position)?;
+        self.raw_map.vertexes.push(vertex);
+        self.positions.vertexes.push(position);
+        Ok(VertexIdx::new(idx))
+    }
+
+    pub fn sector(
+        &mut self,
+        sector: Sector,
+        position: Option<Position>,
+    ) -> Result<SectorIdx, BuildError> {
+        let idx = next_idx(self.raw_map.sectors.len(), EntityKind::Sector, position)?;
+        self.raw_map.sectors.push(sector);
+        self.positions.sectors.push(position);
+        Ok(SectorIdx::new(idx))
+    }
+
+    pub fn side_def(
+        &mut self,
+        side_def: RawSideDef,
+        position: Option<Position>,
+    ) -> Result<SideDefIdx, BuildError> {
+        let idx = next_idx(self.raw_map.side_defs.len(), EntityKind::SideDef, position)?;
+        let check = |field, referee, referee_index: u16, len| {
+            check_index(
+                EntityKind::SideDef,
+                usize::from(idx),
+                field,
+                referee,
+                referee_index,
+                len,
+                position,
+            )
+        };
+
+        check(
+            "sector",
+            EntityKind::Sector,
+            side_def.sector_idx.get(),
+            self.raw_map.sectors.len(),
+        )?;
+
+        self.raw_map.side_defs.push(side_def);
+        self.positions.side_defs.push(position);
+        Ok(SideDefIdx::new(idx))
+    }
+
+    /// Add a line def, returning its index
+    pub fn line_def(
+        &mut self,
+        line_def: RawLineDef,
+        position: Option<Position>,
+    ) -> Result<usize, BuildError> {
+        let idx = next_idx(self.raw_map.line_defs.len(), EntityKind::LineDef, position)?;
+        let vertexes = self.raw_map.vertexes.len();
+        let side_defs = self.raw_map.side_defs.len();
+        let check = |field, referee, referee_index: u16, len| {
+            check_index(
+                EntityKind::LineDef,
+                usize::from(idx),
+                field,
+                referee,
+                referee_index,
+                len,
+                position,
+            )
+        };
+
+        check(
+            "from",
+            EntityKind::Vertex,
+            line_def.from_idx.get(),
+            vertexes,
+        )?;
+        check("to", EntityKind::Vertex, line_def.to_idx.get(), vertexes)?;
+        check(
+            "left_side",
+            EntityKind::SideDef,
+            line_def.left_side_idx.get(),
+            side_defs,
+        )?;
+        if let Some(right_side_idx) = line_def.right_side_idx {
+            check(
+                "right_side",
+                EntityKind::SideDef,
+                right_side_idx.get(),
+                side_defs,
+            )?;
+        }
+
+        self.raw_map.line_defs.push(line_def);
+        self.positions.line_defs.push(position);
+        Ok(usize::from(idx))
+    }
+
+    /// Add a thing, returning its index
+    pub fn thing(&mut self, thing: Thing, position: Option<Position>) -> Result<usize, BuildError> {
+        let idx = next_idx(self.raw_map.things.len(), EntityKind::Thing, position)?;
+        self.raw_map.things.push(thing);
+        self.positions.things.push(position);
+        Ok(usize::from(idx))
+    }
+
+    pub fn vertexes<I>(&mut self, vertexes: I) -> Result<&mut Self, BuildError>
+    where
+        I: IntoIterator<Item = (Vertex, Option<Position>)>,
+    {
+        for (vertex, position) in vertexes {
+            self.vertex(vertex, position)?;
+        }
+        Ok(self)
+    }
+
+    pub fn sectors<I>(&mut self, sectors: I) -> Result<&mut Self, BuildError>
+    where
+        I: IntoIterator<Item = (Sector, Option<Position>)>,
+    {
+        for (sector, position) in sectors {
+            self.sector(sector, position)?;
+        }
+        Ok(self)
+    }
+
+    pub fn side_defs<I>(&mut self, side_defs: I) -> Result<&mut Self, BuildError>
+    where
+        I: IntoIterator<Item = (RawSideDef, Option<Position>)>,
+    {
+        for (side_def, position) in side_defs {
+            self.side_def(side_def, position)?;
+        }
+        Ok(self)
+    }
+
+    pub fn line_defs<I>(&mut self, line_defs: I) -> Result<&mut Self, BuildError>
+    where
+        I: IntoIterator<Item = (RawLineDef, Option<Position>)>,
+    {
+        for (line_def, position) in line_defs {
+            self.line_def(line_def, position)?;
+        }
+        Ok(self)
+    }
+
+    pub fn things<I>(&mut self, things: I) -> Result<&mut Self, BuildError>
+    where
+        I: IntoIterator<Item = (Thing, Option<Position>)>,
+    {
+        for (thing, position) in things {
+            self.thing(thing, position)?;
+        }
+        Ok(self)
+    }
+
+    /// The map built so far
+    pub fn raw_map(&self) -> &RawMap {
+        &self.raw_map
+    }
+
+    pub fn build(self) -> RawMap {
+        self.raw_map
+    }
+
+    pub fn build_with_positions(self) -> (RawMap, SourcePositions) {
+        (self.raw_map, self.positions)
+    }
+}
+
+/// The index of the next entity of a kind, if there's room for it
+fn next_idx(
+    len: usize,
+    entity_kind: EntityKind,
+    position: Option<Position>,
+) -> Result<u16, BuildError> {
+    u16::try_from(len).map_err(|_| BuildError::IndexTooLarge {
+        entity_kind,
+        position,
+    })
+}
+
+fn check_index(
+    referrer: EntityKind,
+    referrer_index: usize,
+    field: &'static str,
+    referee: EntityKind,
+    referee_index: u16,
+    len: usize,
+    position: Option<Position>,
+) -> Result<(), BuildError> {
+    map::check_index(referrer, referrer_index, field, referee, referee_index, len).map_err(
+        |source| BuildError::IndexOutOfRange {
+            source,
+            len,
+            position,
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::line_def;
+
+    #[test]
+    fn validates_eagerly() {
+        let mut builder = RawMapBuilder::new("MAP01".try_into().unwrap());
+        builder
+            .vertexes(vec![(Vertex::default(), None); 2])
+            .unwrap()
+            .sectors([(Sector::default(), Some(Position { line: 2, column: 0 }))])
+            .unwrap();
+
+        let side = builder
+            .side_def(
+                RawSideDef {
+                    sector_idx: SectorIdx::new(0),
+                    ..Default::default()
+                },
+                None,
+            )
+            .unwrap();
+
+        let line_def = |to_idx| RawLineDef {
+            from_idx: VertexIdx::new(0),
+            to_idx,
+            left_side_idx: side,
+            right_side_idx: None,
+            id: line_def::NO_ID,
+            flags: Default::default(),
+            special: Default::default(),
+            trigger_flags: Default::default(),
+        };
+
+        let error = builder
+            .line_def(
+                line_def(VertexIdx::new(2)),
+                Some(Position { line: 4, column: 0 }),
+            )
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line_def[0].to refers to invalid vertex index 2, but only 2 were added (line 5, column 1)"
+        );
+
+        builder.line_def(line_def(VertexIdx::new(1)), None).unwrap();
+        let (raw_map, positions) = builder.build_with_positions();
+        assert_eq!(raw_map.line_defs.len(), 1);
+        assert_eq!(positions.get(EntityKind::LineDef, 0), None);
+        assert_eq!(
+            positions.get(EntityKind::Sector, 0),
+            Some(Position { line: 2, column: 0 })
+        );
+        assert!(raw_map.link().is_ok());
+    }
+}
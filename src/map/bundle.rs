@@ -0,0 +1,94 @@
+//! A map together with the lumps stored alongside it in a WAD which waddle doesn't interpret, such as compiled ACS,
+//! so that loading and resaving a map doesn't lose them.
+
+use crate::{
+    map::{
+        udmf::{PassthroughLumps, WriteError},
+        Map,
+    },
+    wad::{MapLoadError, Wad},
+};
+
+/// A map and its auxiliary lumps
+#[derive(Clone, Debug)]
+pub struct MapBundle {
+    pub map: Map,
+    /// The BEHAVIOR, SCRIPTS and DIALOGUE lumps the map was loaded with. ZNODES aren't kept, since they're out of date
+    /// once the map is edited.
+    pub lumps: PassthroughLumps,
+}
+
+impl MapBundle {
+    /// Append the map to a WAD in UDMF, with its auxiliary lumps, as with [Map::write_udmf_lumps]
+    pub fn write_udmf_lumps(&self, wad: &mut Wad) -> Result<(), WriteError> {
+        self.map.write_udmf_lumps(wad, &self.lumps)
+    }
+}
+
+impl Wad {
+    /// Load the map whose marker is at `marker` as with [Wad::load_map], along with its auxiliary lumps
+    pub fn load_map_bundle(&self, marker: usize) -> Result<MapBundle, MapLoadError> {
+        let map = self.load_map(marker)?;
+        let lumps = self.map_lumps(marker);
+        let find = |name| {
+            lumps
+                .iter()
+                .find(|lump| lump.is_named(name))
+                .map(|lump| lump.data.clone())
+        };
+
+        Ok(MapBundle {
+            map,
+            lumps: PassthroughLumps {
+                znodes: None,
+                dialogue: find("DIALOGUE"),
+                behavior: find("BEHAVIOR"),
+                scripts: find("SCRIPTS"),
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::wad::{Lump, WadKind};
+
+    #[test]
+    fn resave_scripted_map() {
+        let textmap = r#"
+            namespace = "zdoom";
+            vertex { x = 0; y = 0; }
+            vertex { x = 64; y = 0; }
+            sector { texturefloor = "FLOOR4_8"; textureceiling = "CEIL3_5"; }
+            sidedef { sector = 0; }
+            linedef { v1 = 0; v2 = 1; sidefront = 0; }
+            "#;
+        let mut wad = Wad::new(WadKind::Pwad);
+        for (name, data) in [
+            ("MAP01", &b""[..]),
+            ("TEXTMAP", textmap.as_bytes()),
+            ("ZNODES", b"XGLN"),
+            ("BEHAVIOR", b"ACS\0"),
+            ("SCRIPTS", b"script 1 OPEN {}"),
+            ("ENDMAP", b""),
+        ] {
+            wad.lumps
+                .push(Lump::new(name.try_into().unwrap(), data.to_vec()));
+        }
+
+        let bundle = wad.load_map_bundle(0).unwrap();
+        assert_eq!(bundle.lumps.behavior.as_deref(), Some(&b"ACS\0"[..]));
+
+        let mut resaved = Wad::new(WadKind::Pwad);
+        bundle.write_udmf_lumps(&mut resaved).unwrap();
+        let names: Vec<_> = resaved
+            .lumps
+            .iter()
+            .map(|lump| lump.name.to_string())
+            .collect();
+        assert_eq!(names, ["MAP01", "TEXTMAP", "BEHAVIOR", "SCRIPTS", "ENDMAP"]);
+        assert_eq!(resaved.lumps[3].data, b"script 1 OPEN {}");
+    }
+}
@@ -0,0 +1,400 @@
+//! The source ports a map can target, and what each of them can run, so a map can be checked against the port it's
+//! made for with [Map::validate_for], and converted for it with [Map::to_doom_binary].
+
+use std::fmt::{self, Display, Formatter};
+
+use crate::{
+    map::{
+        line_def::{self, LineDefKey, Special, TriggerFlags},
+        lint::EntityRef,
+        sector::{self, SectorKey},
+        thing::{self, FlagsEncoding, ThingKey},
+        vertex::VertexKey,
+        Map,
+    },
+    number::Number,
+};
+
+/// A family of source ports sharing a feature set, from the most to the least restrictive
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum CompatProfile {
+    /// The original engine and ports which stay faithful to it, e.g. Chocolate Doom
+    Vanilla,
+    /// Boom and its descendants, with generalized specials, scrollers, friction and deep water
+    Boom,
+    /// The MBF21 standard, adding MBF's friendly monsters and sky transfers, and instant death sectors, to Boom
+    Mbf21,
+    /// ZDoom and its descendants, e.g. GZDoom, which run everything a `Map` can hold
+    ZDoom,
+    /// The Eternity Engine, with parameterized specials in UDMF but without ZDoom's 3D floors and vertex slopes
+    Eternity,
+}
+
+impl CompatProfile {
+    pub const ALL: [CompatProfile; 5] = [
+        CompatProfile::Vanilla,
+        CompatProfile::Boom,
+        CompatProfile::Mbf21,
+        CompatProfile::ZDoom,
+        CompatProfile::Eternity,
+    ];
+
+    /// Whether maps for the profile are stored in the Doom binary format, rather than UDMF
+    pub fn is_binary(self) -> bool {
+        matches!(
+            self,
+            CompatProfile::Vanilla | CompatProfile::Boom | CompatProfile::Mbf21
+        )
+    }
+
+    /// The encoding of thing flags in the profile's binary format, or `None` for UDMF, which stores them all
+    pub fn thing_flags_encoding(self) -> Option<FlagsEncoding> {
+        match self {
+            CompatProfile::Vanilla => Some(FlagsEncoding::Doom),
+            CompatProfile::Boom | CompatProfile::Mbf21 => Some(FlagsEncoding::Boom),
+            CompatProfile::ZDoom | CompatProfile::Eternity => None,
+        }
+    }
+
+    /// Whether the profile can run a line special with the given trigger flags. Binary profiles need a Doom
+    /// equivalent, or one of the Boom specials their format numbers outside the vanilla range.
+    pub fn allows_special(self, special: &Special, trigger_flags: &TriggerFlags) -> bool {
+        if *special == Special::None {
+            return true;
+        }
+
+        let boom = matches!(
+            special,
+            Special::GenericFloor { .. }
+                | Special::GenericCeiling { .. }
+                | Special::GenericDoor { .. }
+                | Special::GenericLift { .. }
+                | Special::GenericStairs { .. }
+                | Special::GenericCrusher { .. }
+                | Special::TransferHeights { .. }
+                | Special::TransferFloorLight { .. }
+                | Special::TransferCeilingLight { .. }
+                | Special::TransferWallLight { .. }
+                | Special::SectorSetWind { .. }
+                | Special::SectorSetFriction { .. }
+                | Special::SectorSetCurrent { .. }
+                | Special::ScrollTextureLeft { .. }
+                | Special::ScrollTextureRight { .. }
+                | Special::ScrollTextureModel { .. }
+                | Special::ScrollTextureOffsets { .. }
+                | Special::ScrollFloor { .. }
+                | Special::ScrollCeiling { .. }
+                | Special::PointPushSetForce { .. }
+        );
+
+        match self {
+            CompatProfile::Vanilla => special.to_doom(trigger_flags).is_some(),
+            CompatProfile::Boom => boom || special.to_doom(trigger_flags).is_some(),
+            CompatProfile::Mbf21 => {
+                boom || matches!(special, Special::StaticInit { .. })
+                    || special.to_doom(trigger_flags).is_some()
+            }
+            CompatProfile::ZDoom => true,
+            CompatProfile::Eternity => !matches!(special, Special::SectorSet3dFloor { .. }),
+        }
+    }
+
+    /// Whether the profile has all the line flags which are set. MBF21's blocking flags and Strife's flags are also
+    /// in ZDoom's UDMF.
+    pub fn allows_line_flags(self, flags: &line_def::Flags) -> bool {
+        let mbf21 = flags.blocks_land_monsters || flags.blocks_players;
+        let strife = flags.jump_over || flags.blocks_floaters || flags.translucent;
+
+        (!mbf21 || matches!(self, CompatProfile::Mbf21 | CompatProfile::ZDoom))
+            && (!strife || self == CompatProfile::ZDoom)
+    }
+
+    /// Whether things can be friendly monsters, which came with MBF
+    pub fn allows_friends(self) -> bool {
+        !matches!(self, CompatProfile::Vanilla | CompatProfile::Boom)
+    }
+
+    /// Whether sectors can kill the monsters standing in them, which is only in MBF21's generalized sector types
+    pub fn allows_kill_grounded_monsters(self) -> bool {
+        self == CompatProfile::Mbf21
+    }
+
+    /// Whether the profile has the sector special
+    pub fn allows_sector_special(self, special: sector::Special) -> bool {
+        special != sector::Special::DamageInstantDeath
+            || !matches!(self, CompatProfile::Vanilla | CompatProfile::Boom)
+    }
+
+    /// Whether a sector can be both secret and have a special. Boom's generalized sector types have a secret bit,
+    /// where vanilla only has a secret type.
+    pub fn allows_secret_with_special(self) -> bool {
+        self != CompatProfile::Vanilla
+    }
+
+    /// Whether vertexes can have floor and ceiling heights, for slopes
+    pub fn allows_vertex_heights(self) -> bool {
+        self == CompatProfile::ZDoom
+    }
+
+    /// Whether things can have heights above the floor, and vertexes and things fractional coordinates, as in UDMF
+    pub fn allows_udmf_fields(self) -> bool {
+        !self.is_binary()
+    }
+}
+
+impl Display for CompatProfile {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CompatProfile::Vanilla => "vanilla",
+            CompatProfile::Boom => "Boom",
+            CompatProfile::Mbf21 => "MBF21",
+            CompatProfile::ZDoom => "ZDoom",
+            CompatProfile::Eternity => "Eternity",
+        })
+    }
+}
+
+/// Something in a `Map` which a `CompatProfile` can't run, as found by [Map::validate_for]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CompatIssue {
+    /// A line special the profile doesn't have, or can't trigger in the way the line does
+    LineSpecial {
+        line_def: LineDefKey,
+        special: Special,
+        trigger_flags: TriggerFlags,
+    },
+    /// Line flags the profile doesn't have
+    LineFlags { line_def: LineDefKey },
+    /// A line id which differs from the tag of the line's special, which binary formats can't store separately
+    LineId { line_def: LineDefKey, id: i16 },
+    /// A sector special the profile doesn't have
+    SectorSpecial {
+        sector: SectorKey,
+        special: sector::Special,
+    },
+    /// A secret sector with another special
+    SectorSecret { sector: SectorKey },
+    /// A sector which kills grounded monsters
+    SectorKillsMonsters { sector: SectorKey },
+    /// Thing flags the profile can't store, or can only store merged, because its format doesn't tell the skills
+    /// apart
+    ThingFlags { thing: ThingKey },
+    /// A thing's height above the floor
+    ThingHeight { thing: ThingKey },
+    /// A vertex or thing with fractional coordinates
+    Fractional { entity: EntityRef },
+    /// A vertex with a floor or ceiling height for slopes
+    VertexHeight { vertex: VertexKey },
+}
+
+impl CompatIssue {
+    /// The entity the issue is about
+    pub fn entity(&self) -> EntityRef {
+        match *self {
+            CompatIssue::LineSpecial { line_def, .. }
+            | CompatIssue::LineFlags { line_def }
+            | CompatIssue::LineId { line_def, .. } => EntityRef::LineDef(line_def),
+            CompatIssue::SectorSpecial { sector, .. }
+            | CompatIssue::SectorSecret { sector }
+            | CompatIssue::SectorKillsMonsters { sector } => EntityRef::Sector(sector),
+            CompatIssue::ThingFlags { thing } | CompatIssue::ThingHeight { thing } => {
+                EntityRef::Thing(thing)
+            }
+            CompatIssue::Fractional { entity } => entity,
+            CompatIssue::VertexHeight { vertex } => EntityRef::Vertex(vertex),
+        }
+    }
+}
+
+impl Display for CompatIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            CompatIssue::LineSpecial {
+                special,
+                trigger_flags,
+                ..
+            } => write!(
+                f,
+                "line has {special:?} with {}, which the port can't run",
+                trigger_flags.set_names().join("+")
+            ),
+            CompatIssue::LineFlags { .. } => f.write_str("line has flags the port doesn't have"),
+            CompatIssue::LineId { id, .. } => {
+                write!(f, "line has id {id}, which differs from its special's tag")
+            }
+            CompatIssue::SectorSpecial { special, .. } => {
+                write!(f, "sector has {special:?}, which the port doesn't have")
+            }
+            CompatIssue::SectorSecret { .. } => {
+                f.write_str("sector is secret and has a special, which the port can't combine")
+            }
+            CompatIssue::SectorKillsMonsters { .. } => {
+                f.write_str("sector kills grounded monsters, which the port doesn't have")
+            }
+            CompatIssue::ThingFlags { .. } => f.write_str("thing has flags the port can't store"),
+            CompatIssue::ThingHeight { .. } => f.write_str("thing has a height above the floor"),
+            CompatIssue::Fractional { .. } => f.write_str("coordinates aren't whole units"),
+            CompatIssue::VertexHeight { .. } => f.write_str("vertex has a slope height"),
+        }
+    }
+}
+
+impl Map {
+    /// Check whether the map will run on the ports of `profile`, listing everything in it they can't run, in the
+    /// order of vertexes, lines, sectors and things. Only what a `Map` can hold is checked: thing types and textures
+    /// aren't.
+    pub fn validate_for(&self, profile: CompatProfile) -> Vec<CompatIssue> {
+        let mut issues = Vec::new();
+        let fractional = |number: Number| number.into_float().fract() != 0.0;
+
+        for (key, vertex) in self.vertexes.iter() {
+            if !profile.allows_udmf_fields()
+                && (fractional(vertex.position.x) || fractional(vertex.position.y))
+            {
+                issues.push(CompatIssue::Fractional {
+                    entity: EntityRef::Vertex(key),
+                });
+            }
+            if !profile.allows_vertex_heights()
+                && (vertex.floor_z.is_some() || vertex.ceiling_z.is_some())
+            {
+                issues.push(CompatIssue::VertexHeight { vertex: key });
+            }
+        }
+
+        for (key, line_def) in self.line_defs.iter() {
+            if !profile.allows_special(&line_def.special, &line_def.trigger_flags) {
+                issues.push(CompatIssue::LineSpecial {
+                    line_def: key,
+                    special: line_def.special.clone(),
+                    trigger_flags: line_def.trigger_flags.clone(),
+                });
+            }
+            if !profile.allows_line_flags(&line_def.flags) {
+                issues.push(CompatIssue::LineFlags { line_def: key });
+            }
+
+            // Without a special, the tag is free to act as the line's id, as in `Map::to_doom_binary`
+            if profile.is_binary() && line_def.id != line_def::NO_ID {
+                let tag = line_def
+                    .special
+                    .to_doom(&line_def.trigger_flags)
+                    .filter(|doom| doom.value != 0)
+                    .map(|doom| doom.tag);
+                if tag.is_some_and(|tag| tag != line_def.id) {
+                    issues.push(CompatIssue::LineId {
+                        line_def: key,
+                        id: line_def.id,
+                    });
+                }
+            }
+        }
+
+        for (key, sector) in self.sectors.iter() {
+            if !profile.allows_sector_special(sector.special) {
+                issues.push(CompatIssue::SectorSpecial {
+                    sector: key,
+                    special: sector.special,
+                });
+            }
+            if sector.secret
+                && sector.special != sector::Special::None
+                && !profile.allows_secret_with_special()
+            {
+                issues.push(CompatIssue::SectorSecret { sector: key });
+            }
+            if sector.kill_grounded_monsters && !profile.allows_kill_grounded_monsters() {
+                issues.push(CompatIssue::SectorKillsMonsters { sector: key });
+            }
+        }
+
+        for (key, thing) in self.things.iter() {
+            let stored = profile.thing_flags_encoding().is_none_or(|encoding| {
+                thing::Flags::decode(thing.flags.encode(encoding), encoding) == thing.flags
+            });
+            if !stored || (thing.flags.mbf_friend && !profile.allows_friends()) {
+                issues.push(CompatIssue::ThingFlags { thing: key });
+            }
+            if !profile.allows_udmf_fields() {
+                if thing.height.0 != 0 {
+                    issues.push(CompatIssue::ThingHeight { thing: key });
+                }
+                if fractional(thing.position.x) || fractional(thing.position.y) {
+                    issues.push(CompatIssue::Fractional {
+                        entity: EntityRef::Thing(key),
+                    });
+                }
+            }
+        }
+
+        issues
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_for_profiles() {
+        let map = Map::load_udmf_textmap(
+            "MAP01".try_into().unwrap(),
+            r#"
+            namespace = "zdoom";
+            vertex { x = 0; y = 0; }
+            vertex { x = 64; y = 0; zfloor = 16.0; }
+            sector { texturefloor = "FLAT1"; textureceiling = "FLAT1"; heightceiling = 128; special = 1095; }
+            sidedef { sector = 0; }
+            linedef { v1 = 0; v2 = 1; sidefront = 0; special = 12; arg0 = 3; arg1 = 16; arg2 = 150;
+                      playeruse = true; }
+            linedef { v1 = 1; v2 = 0; sidefront = 0; special = 209; arg0 = 3; }
+            thing { x = 32; y = 16; type = 1; }
+            thing { x = 32.5; y = 16; type = 2014; friend = true; }
+            "#,
+        )
+        .unwrap();
+        let line_defs: Vec<_> = map.line_defs.keys().collect();
+        let sector = map.sectors.keys().next().unwrap();
+        let things: Vec<_> = map.things.keys().collect();
+        let vertex = map.vertexes.keys().nth(1).unwrap();
+
+        assert!(map.validate_for(CompatProfile::ZDoom).is_empty());
+        assert_eq!(
+            map.validate_for(CompatProfile::Eternity),
+            [CompatIssue::VertexHeight { vertex }]
+        );
+        assert_eq!(
+            map.validate_for(CompatProfile::Mbf21),
+            [
+                CompatIssue::VertexHeight { vertex },
+                CompatIssue::Fractional {
+                    entity: EntityRef::Thing(things[1])
+                },
+            ]
+        );
+        // Friendly monsters came with MBF, so Boom can't run them even though its format has room for the bit
+        assert_eq!(
+            map.validate_for(CompatProfile::Boom),
+            [
+                CompatIssue::VertexHeight { vertex },
+                CompatIssue::ThingFlags { thing: things[1] },
+                CompatIssue::Fractional {
+                    entity: EntityRef::Thing(things[1])
+                },
+            ]
+        );
+
+        let vanilla = map.validate_for(CompatProfile::Vanilla);
+        assert_eq!(
+            vanilla[1],
+            CompatIssue::LineSpecial {
+                line_def: line_defs[1],
+                special: map.line_defs[line_defs[1]].special.clone(),
+                trigger_flags: TriggerFlags::default(),
+            }
+        );
+        assert!(vanilla.contains(&CompatIssue::SectorSecret { sector }));
+        assert!(vanilla.contains(&CompatIssue::ThingFlags { thing: things[1] }));
+        assert_eq!(vanilla.len(), 5);
+    }
+}
@@ -0,0 +1,544 @@
+//! Differences between two versions of a map, which can be applied as patches or merged, for collaborative editing.
+//!
+//! Entities are matched by key, so diffs are only meaningful between maps which share a history, such as copies of the
+//! same `Map`. Maps linked from the same `RawMap` also share keys.
+
+use std::{collections::HashMap, hash::Hash};
+
+use slotmap::{Key, SecondaryMap, SlotMap};
+
+use crate::map::{
+    line_def::LineDefKey, lint::EntityRef, sector::SectorKey, selection::KeyRemap,
+    side_def::SideDefKey, thing::ThingKey, vertex::VertexKey, LineDef, Map, Sector, SideDef, Thing,
+    Vertex,
+};
+
+/// A change to a single entity
+#[derive(Clone, Debug, PartialEq)]
+pub enum Change<T> {
+    Added(T),
+    Modified { old: T, new: T },
+    Removed(T),
+}
+
+/// The changes which turn one map into another, as produced by [Map::diff]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MapDiff {
+    pub vertexes: Vec<(VertexKey, Change<Vertex>)>,
+    pub line_defs: Vec<(LineDefKey, Change<LineDef>)>,
+    pub sectors: Vec<(SectorKey, Change<Sector>)>,
+    pub side_defs: Vec<(SideDefKey, Change<SideDef>)>,
+    pub things: Vec<(ThingKey, Change<Thing>)>,
+}
+
+impl MapDiff {
+    pub fn is_empty(&self) -> bool {
+        self.vertexes.is_empty()
+            && self.line_defs.is_empty()
+            && self.sectors.is_empty()
+            && self.side_defs.is_empty()
+            && self.things.is_empty()
+    }
+}
+
+/// A change in a `MapDiff` which doesn't fit the map it's applied to. Entities are identified by their keys in the
+/// diff.
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum ApplyError {
+    #[error("{entity:?} was modified or removed, but isn't in the map")]
+    Missing { entity: EntityRef },
+
+    #[error("{entity:?} was modified or removed, but has changed since the diff was made")]
+    Stale { entity: EntityRef },
+
+    #[error("{entity:?} refers to {referee:?}, which isn't in the map")]
+    Dangling {
+        entity: EntityRef,
+        referee: EntityRef,
+    },
+
+    #[error("{entity:?} was removed, but is still referred to")]
+    StillReferenced { entity: EntityRef },
+}
+
+/// A change from the other side of a merge which wasn't applied
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MergeConflict {
+    /// Both sides changed the entity, in different ways. Our version was kept.
+    BothChanged { entity: EntityRef },
+    /// Their change doesn't fit with ours, e.g. because it refers to an entity which we removed
+    Apply(ApplyError),
+}
+
+/// The result of [Map::merge]
+#[derive(Clone, Debug)]
+pub struct Merge {
+    pub map: Map,
+    pub conflicts: Vec<MergeConflict>,
+}
+
+impl Map {
+    /// The changes which turn this map into `other`.
+    ///
+    /// Removals and modifications are listed in the order of this map, followed by additions in the order of
+    /// `other`.
+    pub fn diff(&self, other: &Map) -> MapDiff {
+        MapDiff {
+            vertexes: diff_kind(&self.vertexes, &other.vertexes),
+            line_defs: diff_kind(&self.line_defs, &other.line_defs),
+            sectors: diff_kind(&self.sectors, &other.sectors),
+            side_defs: diff_kind(&self.side_defs, &other.side_defs),
+            things: diff_kind(&self.things, &other.things),
+        }
+    }
+
+    /// Apply the changes in `diff`, returning the keys given to added entities, indexed by their keys in the diff.
+    ///
+    /// Modified and removed entities must be unchanged from the old version in the diff, and removed entities can't
+    /// still be referred to. If any change doesn't fit, the map is left as it was.
+    pub fn apply(&mut self, diff: &MapDiff) -> Result<KeyRemap, ApplyError> {
+        let mut patched = self.clone();
+        let mut errors = Vec::new();
+        let remap = patched.apply_fitting(diff, &mut errors);
+
+        match errors.into_iter().next() {
+            Some(error) => Err(error),
+            None => {
+                *self = patched;
+                Ok(remap)
+            }
+        }
+    }
+
+    /// Merge the changes made in `ours` and `theirs` since `base`.
+    ///
+    /// Their changes are applied on top of ours, except where both sides changed the same entity differently, or
+    /// where their change no longer fits. Those changes are dropped and reported as conflicts.
+    pub fn merge(base: &Map, ours: &Map, theirs: &Map) -> Merge {
+        let our_diff = base.diff(ours);
+        let mut their_diff = base.diff(theirs);
+        let mut conflicts = Vec::new();
+
+        retain_unconflicted(
+            &mut their_diff.vertexes,
+            &our_diff.vertexes,
+            EntityRef::Vertex,
+            &mut conflicts,
+        );
+        retain_unconflicted(
+            &mut their_diff.line_defs,
+            &our_diff.line_defs,
+            EntityRef::LineDef,
+            &mut conflicts,
+        );
+        retain_unconflicted(
+            &mut their_diff.sectors,
+            &our_diff.sectors,
+            EntityRef::Sector,
+            &mut conflicts,
+        );
+        retain_unconflicted(
+            &mut their_diff.side_defs,
+            &our_diff.side_defs,
+            EntityRef::SideDef,
+            &mut conflicts,
+        );
+        retain_unconflicted(
+            &mut their_diff.things,
+            &our_diff.things,
+            EntityRef::Thing,
+            &mut conflicts,
+        );
+
+        let mut map = ours.clone();
+        let mut errors = Vec::new();
+        map.apply_fitting(&their_diff, &mut errors);
+        conflicts.extend(errors.into_iter().map(MergeConflict::Apply));
+
+        Merge { map, conflicts }
+    }
+
+    /// Apply every change in `diff` which fits, collecting errors for the rest.
+    ///
+    /// Additions and modifications are applied first, so that removals can check the references of the patched map.
+    fn apply_fitting(&mut self, diff: &MapDiff, errors: &mut Vec<ApplyError>) -> KeyRemap {
+        let mut remap = KeyRemap::default();
+
+        apply_kind(
+            self,
+            |map| &mut map.vertexes,
+            &diff.vertexes,
+            EntityRef::Vertex,
+            &mut remap.vertexes,
+            |_, vertex| Ok(*vertex),
+            errors,
+        );
+        apply_kind(
+            self,
+            |map| &mut map.sectors,
+            &diff.sectors,
+            EntityRef::Sector,
+            &mut remap.sectors,
+            |_, sector| Ok(sector.clone()),
+            errors,
+        );
+
+        let sectors = remap.sectors.clone();
+        apply_kind(
+            self,
+            |map| &mut map.side_defs,
+            &diff.side_defs,
+            EntityRef::SideDef,
+            &mut remap.side_defs,
+            |map, side_def| {
+                Ok(SideDef {
+                    sector: resolve(map, &sectors, side_def.sector, EntityRef::Sector)?,
+                    ..side_def.clone()
+                })
+            },
+            errors,
+        );
+
+        let (vertexes, side_defs) = (remap.vertexes.clone(), remap.side_defs.clone());
+        apply_kind(
+            self,
+            |map| &mut map.line_defs,
+            &diff.line_defs,
+            EntityRef::LineDef,
+            &mut remap.line_defs,
+            |map, line_def| {
+                Ok(LineDef {
+                    from: resolve(map, &vertexes, line_def.from, EntityRef::Vertex)?,
+                    to: resolve(map, &vertexes, line_def.to, EntityRef::Vertex)?,
+                    left_side: resolve(map, &side_defs, line_def.left_side, EntityRef::SideDef)?,
+                    right_side: line_def
+                        .right_side
+                        .map(|side| resolve(map, &side_defs, side, EntityRef::SideDef))
+                        .transpose()?,
+                    ..line_def.clone()
+                })
+            },
+            errors,
+        );
+        apply_kind(
+            self,
+            |map| &mut map.things,
+            &diff.things,
+            EntityRef::Thing,
+            &mut remap.things,
+            |_, thing| Ok(thing.clone()),
+            errors,
+        );
+
+        remove_kind(
+            &mut self.line_defs,
+            &diff.line_defs,
+            EntityRef::LineDef,
+            |_| false,
+            errors,
+        );
+        remove_kind(
+            &mut self.things,
+            &diff.things,
+            EntityRef::Thing,
+            |_| false,
+            errors,
+        );
+
+        let line_defs = &self.line_defs;
+        remove_kind(
+            &mut self.side_defs,
+            &diff.side_defs,
+            EntityRef::SideDef,
+            |key| {
+                line_defs
+                    .values()
+                    .any(|line_def| line_def.left_side == key || line_def.right_side == Some(key))
+            },
+            errors,
+        );
+
+        let side_defs = &self.side_defs;
+        remove_kind(
+            &mut self.sectors,
+            &diff.sectors,
+            EntityRef::Sector,
+            |key| side_defs.values().any(|side_def| side_def.sector == key),
+            errors,
+        );
+        remove_kind(
+            &mut self.vertexes,
+            &diff.vertexes,
+            EntityRef::Vertex,
+            |key| {
+                line_defs
+                    .values()
+                    .any(|line_def| line_def.from == key || line_def.to == key)
+            },
+            errors,
+        );
+
+        remap
+    }
+}
+
+fn diff_kind<K: Key, V: Clone + PartialEq>(
+    old: &SlotMap<K, V>,
+    new: &SlotMap<K, V>,
+) -> Vec<(K, Change<V>)> {
+    let changed = old.iter().filter_map(|(key, old)| match new.get(key) {
+        None => Some((key, Change::Removed(old.clone()))),
+        Some(new) if new != old => Some((
+            key,
+            Change::Modified {
+                old: old.clone(),
+                new: new.clone(),
+            },
+        )),
+        Some(_) => None,
+    });
+
+    let added = new
+        .iter()
+        .filter(|(key, _)| !old.contains_key(*key))
+        .map(|(key, new)| (key, Change::Added(new.clone())));
+
+    changed.chain(added).collect()
+}
+
+/// Drop their changes to entities which we also changed, reporting those which we changed differently
+fn retain_unconflicted<K: Key + Hash, V: PartialEq>(
+    theirs: &mut Vec<(K, Change<V>)>,
+    ours: &[(K, Change<V>)],
+    entity: fn(K) -> EntityRef,
+    conflicts: &mut Vec<MergeConflict>,
+) {
+    // Additions on both sides can share keys, but are different entities
+    let ours: HashMap<K, &Change<V>> = ours
+        .iter()
+        .filter(|(_, change)| !matches!(change, Change::Added(_)))
+        .map(|(key, change)| (*key, change))
+        .collect();
+
+    theirs.retain(|(key, change)| {
+        if matches!(change, Change::Added(_)) {
+            return true;
+        }
+
+        match ours.get(key) {
+            None => true,
+            Some(&ours) if ours == change => false,
+            Some(_) => {
+                conflicts.push(MergeConflict::BothChanged {
+                    entity: entity(*key),
+                });
+                false
+            }
+        }
+    });
+}
+
+/// Look up the key in `map` of an entity referred to by a `MapDiff`, which is either an entity added by the diff or one
+/// already in the map
+fn resolve<K: Key>(
+    map: &Map,
+    added: &SecondaryMap<K, K>,
+    key: K,
+    entity: fn(K) -> EntityRef,
+) -> Result<K, EntityRef> {
+    let key = added.get(key).copied().unwrap_or(key);
+    let exists = match entity(key) {
+        EntityRef::Vertex(key) => map.vertexes.contains_key(key),
+        EntityRef::LineDef(key) => map.line_defs.contains_key(key),
+        EntityRef::Sector(key) => map.sectors.contains_key(key),
+        EntityRef::SideDef(key) => map.side_defs.contains_key(key),
+        EntityRef::Thing(key) => map.things.contains_key(key),
+    };
+
+    if exists {
+        Ok(key)
+    } else {
+        Err(entity(key))
+    }
+}
+
+/// Apply the additions and modifications of one kind of entity. `resolve_references` replaces the references of an
+/// entity from the diff with keys in the map, or returns the entity which is missing.
+fn apply_kind<K: Key, V: PartialEq>(
+    map: &mut Map,
+    entities: fn(&mut Map) -> &mut SlotMap<K, V>,
+    changes: &[(K, Change<V>)],
+    entity: fn(K) -> EntityRef,
+    added: &mut SecondaryMap<K, K>,
+    resolve_references: impl Fn(&Map, &V) -> Result<V, EntityRef>,
+    errors: &mut Vec<ApplyError>,
+) {
+    for (key, change) in changes {
+        let (old, new) = match change {
+            Change::Added(new) => (None, new),
+            Change::Modified { old, new } => (Some(old), new),
+            Change::Removed(_) => continue,
+        };
+
+        if let Some(old) = old {
+            if let Err(error) = check_current(entities(map), *key, old, entity) {
+                errors.push(error);
+                continue;
+            }
+        }
+
+        let new = match resolve_references(map, new) {
+            Ok(new) => new,
+            Err(referee) => {
+                errors.push(ApplyError::Dangling {
+                    entity: entity(*key),
+                    referee,
+                });
+                continue;
+            }
+        };
+
+        if old.is_some() {
+            entities(map)[*key] = new;
+        } else {
+            added.insert(*key, entities(map).insert(new));
+        }
+    }
+}
+
+/// Apply the removals of one kind of entity, skipping those for which `is_referenced` is true
+fn remove_kind<K: Key, V: PartialEq>(
+    entities: &mut SlotMap<K, V>,
+    changes: &[(K, Change<V>)],
+    entity: fn(K) -> EntityRef,
+    is_referenced: impl Fn(K) -> bool,
+    errors: &mut Vec<ApplyError>,
+) {
+    for (key, change) in changes {
+        let Change::Removed(old) = change else {
+            continue;
+        };
+
+        let result = check_current(entities, *key, old, entity).and_then(|()| {
+            if is_referenced(*key) {
+                Err(ApplyError::StillReferenced {
+                    entity: entity(*key),
+                })
+            } else {
+                Ok(())
+            }
+        });
+
+        match result {
+            Ok(()) => {
+                entities.remove(*key);
+            }
+            Err(error) => errors.push(error),
+        }
+    }
+}
+
+fn check_current<K: Key, V: PartialEq>(
+    entities: &SlotMap<K, V>,
+    key: K,
+    old: &V,
+    entity: fn(K) -> EntityRef,
+) -> Result<(), ApplyError> {
+    match entities.get(key) {
+        None => Err(ApplyError::Missing {
+            entity: entity(key),
+        }),
+        Some(current) if current != old => Err(ApplyError::Stale {
+            entity: entity(key),
+        }),
+        Some(_) => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{number::Number, units::Angle, Point};
+
+    #[test]
+    fn diff_apply_and_merge() {
+        let base = Map::load_udmf_textmap(
+            "MAP01".try_into().unwrap(),
+            r#"
+            namespace = "zdoom";
+            vertex { x = 0; y = 0; }
+            vertex { x = 64; y = 0; }
+            sector { texturefloor = "FLOOR4_8"; textureceiling = "CEIL3_5"; heightceiling = 128; }
+            sidedef { sector = 0; }
+            linedef { v1 = 0; v2 = 1; sidefront = 0; }
+            thing { x = 32; y = 16; type = 1; }
+            thing { x = 32; y = 32; type = 2014; }
+            "#,
+        )
+        .unwrap();
+        let vertex = |map: &Map, i| map.vertexes.keys().nth(i).unwrap();
+        let thing = |map: &Map, i| map.things.keys().nth(i).unwrap();
+
+        // Ours splits the line at a new vertex and moves the player start
+        let mut ours = base.clone();
+        let line_def = ours.line_defs.keys().next().unwrap();
+        let middle = ours.vertexes.insert(Vertex {
+            position: Point::new(Number::Int(32), Number::Int(0)),
+            ..Default::default()
+        });
+        let mut second_half = ours.line_defs[line_def].clone();
+        second_half.from = middle;
+        ours.line_defs[line_def].to = middle;
+        ours.line_defs.insert(second_half);
+        ours.things[thing(&base, 0)].angle = Angle(90);
+
+        // Theirs adds a line from a new vertex, turns the player start the other way and removes the health bonus
+        let mut theirs = base.clone();
+        let corner = theirs.vertexes.insert(Vertex {
+            position: Point::new(Number::Int(0), Number::Int(64)),
+            ..Default::default()
+        });
+        let mut new_line_def = theirs.line_defs[line_def].clone();
+        new_line_def.to = corner;
+        theirs.line_defs.insert(new_line_def);
+        theirs.things[thing(&base, 0)].angle = Angle(270);
+        theirs.things.remove(thing(&base, 1));
+
+        let diff = base.diff(&ours);
+        assert_eq!(diff.vertexes.len(), 1);
+        assert_eq!(diff.line_defs.len(), 2);
+        assert_eq!(diff.things.len(), 1);
+
+        let mut patched = base.clone();
+        patched.apply(&diff).unwrap();
+        assert!(patched.diff(&ours).is_empty());
+        assert_eq!(
+            patched.apply(&diff).unwrap_err(),
+            ApplyError::Stale {
+                entity: EntityRef::LineDef(line_def)
+            }
+        );
+
+        let merge = Map::merge(&base, &ours, &theirs);
+        assert_eq!(
+            merge.conflicts,
+            vec![MergeConflict::BothChanged {
+                entity: EntityRef::Thing(thing(&base, 0))
+            }]
+        );
+        assert_eq!(merge.map.vertexes.len(), 4);
+        assert_eq!(merge.map.line_defs.len(), 3);
+        assert_eq!(merge.map.things.len(), 1);
+        assert_eq!(merge.map.things[thing(&base, 0)].angle, Angle(90));
+
+        // The added vertexes share a key, but are distinct in the merged map
+        assert_eq!(middle, corner);
+        let new_line_def = merge.map.line_defs.values().last().unwrap();
+        assert_eq!(new_line_def.from, vertex(&base, 0));
+        assert_eq!(
+            merge.map.vertexes[new_line_def.to].position,
+            Point::new(Number::Int(0), Number::Int(64))
+        );
+    }
+}
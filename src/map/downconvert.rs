@@ -0,0 +1,662 @@
+//! Converting maps to the Doom and Strife binary formats, the reverse of
+//! [RawMap::parse_binary](crate::map::RawMap::parse_binary).
+//!
+//! The format can only express a subset of what a `Map` can hold, so the conversion is lossy: everything dropped or
+//! approximated is listed in the `DownconversionReport`.
+
+use std::fmt::{self, Display, Formatter};
+
+use crate::{
+    map::{
+        binary::BinaryFormat,
+        compat::CompatProfile,
+        geometry::{checked_i16, GeometryOverflow},
+        line_def::{self, DoomSpecial, Special, TriggerFlags},
+        lint::EntityRef,
+        sector, thing,
+        thing_type::{Game, ThingTypes},
+        transform::{quantized_angle, AngleResolution},
+        Map, SideDefIdx, UnlinkError,
+    },
+    number::Number,
+    units::Angle,
+    wad::{Lump, WriteLe},
+    String8,
+};
+
+/// Something in a `Map` which the Doom binary format can't express exactly
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DownconversionIssue {
+    /// A line special with no Doom equivalent for its args and trigger flags, or which the profile doesn't have. The
+    /// line was written without a special.
+    LineSpecial {
+        line_def: usize,
+        special: Special,
+        trigger_flags: TriggerFlags,
+    },
+    /// Line flags which the format or profile doesn't have, e.g. MBF21's in Strife, which were dropped
+    LineFlags { line_def: usize },
+    /// A line id which differs from the tag of the line's special. Doom lines only have one tag, so the id was dropped.
+    LineId { line_def: usize, id: i16 },
+    /// A vertex with fractional coordinates, which were rounded
+    VertexRounded { vertex: usize },
+    /// A vertex with a floor or ceiling height for slopes, which was dropped
+    VertexHeight { vertex: usize },
+    /// A thing with a fractional position, which was rounded
+    ThingRounded { thing: usize },
+    /// A thing angle which isn't a multiple of 45 degrees, the only facings the engine distinguishes, which was rounded
+    /// to one
+    ThingAngle {
+        thing: usize,
+        angle: Angle,
+        quantized: Angle,
+    },
+    /// A thing's height above the floor, which was dropped
+    ThingHeight { thing: usize },
+    /// Thing flags which were dropped, e.g. MBF's friend bit for Boom, or merged because the format doesn't tell the
+    /// skills apart
+    ThingFlags { thing: usize },
+    /// A secret sector with another special. Doom sectors can only have one type, so the secret was dropped.
+    SectorSecret { sector: usize },
+    /// A sector special the format or profile has no type for, e.g. instant death in Strife, which was dropped
+    SectorSpecial {
+        sector: usize,
+        special: sector::Special,
+    },
+    /// A sector which kills grounded monsters, which only MBF21 can express, so it was dropped
+    SectorKillsMonsters { sector: usize },
+}
+
+impl DownconversionIssue {
+    /// Whether information was dropped outright, rather than approximated
+    pub fn is_dropped(&self) -> bool {
+        !matches!(
+            self,
+            DownconversionIssue::VertexRounded { .. }
+                | DownconversionIssue::ThingRounded { .. }
+                | DownconversionIssue::ThingAngle { .. }
+        )
+    }
+}
+
+impl Display for DownconversionIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            DownconversionIssue::LineSpecial {
+                line_def,
+                special,
+                trigger_flags,
+            } => write!(
+                f,
+                "line {line_def} has {special:?} with {}, which has no Doom equivalent",
+                trigger_flags.set_names().join("+")
+            ),
+            DownconversionIssue::LineFlags { line_def } => {
+                write!(f, "line {line_def} has flags the format can't express")
+            }
+            DownconversionIssue::LineId { line_def, id } => {
+                write!(f, "line {line_def} has id {id}, which was dropped")
+            }
+            DownconversionIssue::VertexRounded { vertex } => {
+                write!(f, "vertex {vertex} was rounded to whole units")
+            }
+            DownconversionIssue::VertexHeight { vertex } => {
+                write!(f, "vertex {vertex} has a slope height, which was dropped")
+            }
+            DownconversionIssue::ThingRounded { thing } => {
+                write!(f, "thing {thing} was rounded to whole units")
+            }
+            DownconversionIssue::ThingAngle {
+                thing,
+                angle,
+                quantized,
+            } => write!(
+                f,
+                "thing {thing} has angle {}, which was rounded to {}",
+                angle.0, quantized.0
+            ),
+            DownconversionIssue::ThingHeight { thing } => {
+                write!(f, "thing {thing} has a height, which was dropped")
+            }
+            DownconversionIssue::ThingFlags { thing } => {
+                write!(f, "thing {thing} has flags which Doom can't express")
+            }
+            DownconversionIssue::SectorSecret { sector } => {
+                write!(
+                    f,
+                    "sector {sector} is secret and has a special, so the secret was dropped"
+                )
+            }
+            DownconversionIssue::SectorSpecial { sector, special } => {
+                write!(
+                    f,
+                    "sector {sector} has {special:?}, which the format can't express"
+                )
+            }
+            DownconversionIssue::SectorKillsMonsters { sector } => {
+                write!(
+                    f,
+                    "sector {sector} kills grounded monsters, which was dropped"
+                )
+            }
+        }
+    }
+}
+
+/// Everything dropped or approximated by [Map::to_doom_binary]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DownconversionReport {
+    pub issues: Vec<DownconversionIssue>,
+}
+
+impl DownconversionReport {
+    /// Whether the binary map is an exact copy of the original
+    pub fn is_lossless(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum DownconversionError {
+    #[error(transparent)]
+    Unlink(#[from] UnlinkError),
+
+    #[error(transparent)]
+    Geometry(#[from] GeometryOverflow),
+}
+
+/// Round a coordinate to the whole units of the binary format, returning whether it had to be rounded
+fn whole_units(
+    number: Number,
+    entity: EntityRef,
+    field: &'static str,
+) -> Result<(i16, bool), GeometryOverflow> {
+    let value = number.into_float();
+    Ok((checked_i16(entity, field, value)?, value.fract() != 0.0))
+}
+
+impl Map {
+    /// Write the map in the Doom binary format for the ports of `profile`, as its THINGS, LINEDEFS, SIDEDEFS, VERTEXES
+    /// and SECTORS lumps.
+    ///
+    /// Line specials are mapped back to Doom specials where one converts to exactly the same special and trigger
+    /// flags. Boom's generalized specials and secret bit, MBF's friends and MBF21's flags and sector bits are only
+    /// written if the profile has them, as in [Map::validate_for]. The UDMF profiles get all of them, as their ports
+    /// also run binary maps made for MBF21. Thing angles are rounded to the multiples of 45 degrees the engine
+    /// distinguishes. The node lumps aren't written: build them with a node builder.
+    pub fn to_doom_binary(
+        &self,
+        profile: CompatProfile,
+    ) -> Result<(Vec<Lump>, DownconversionReport), DownconversionError> {
+        let profile = if profile.is_binary() {
+            profile
+        } else {
+            CompatProfile::Mbf21
+        };
+        self.to_doom_like_binary(BinaryFormat::Doom, profile)
+    }
+
+    /// Write the map in the Strife binary format, as [Map::to_doom_binary] does for Doom. Strife numbers the line
+    /// specials it shares with Doom the same way, and has its own line and thing flags.
+    pub fn to_strife_binary(
+        &self,
+    ) -> Result<(Vec<Lump>, DownconversionReport), DownconversionError> {
+        // Strife has none of the Boom or MBF extensions
+        self.to_doom_like_binary(BinaryFormat::Strife, CompatProfile::Vanilla)
+    }
+
+    /// The Doom and Strife formats only differ in their flags, and what's written in them depends on the profile
+    fn to_doom_like_binary(
+        &self,
+        format: BinaryFormat,
+        profile: CompatProfile,
+    ) -> Result<(Vec<Lump>, DownconversionReport), DownconversionError> {
+        let raw_map = self.unlink()?;
+        let mut report = DownconversionReport::default();
+
+        let mut vertexes = Vec::new();
+        for (i, (key, vertex)) in self.vertexes.iter().enumerate() {
+            let entity = EntityRef::Vertex(key);
+            let (x, rounded_x) = whole_units(vertex.position.x, entity, "x")?;
+            let (y, rounded_y) = whole_units(vertex.position.y, entity, "y")?;
+
+            if rounded_x || rounded_y {
+                report
+                    .issues
+                    .push(DownconversionIssue::VertexRounded { vertex: i });
+            }
+            if vertex.floor_z.is_some() || vertex.ceiling_z.is_some() {
+                report
+                    .issues
+                    .push(DownconversionIssue::VertexHeight { vertex: i });
+            }
+            vertexes.write_i16(x).unwrap();
+            vertexes.write_i16(y).unwrap();
+        }
+
+        let mut line_defs = Vec::new();
+        for (i, line_def) in raw_map.line_defs.iter().enumerate() {
+            let doom = if line_def.special == Special::None {
+                DoomSpecial::new(0, 0)
+            } else {
+                line_def
+                    .special
+                    .to_doom(&line_def.trigger_flags)
+                    .filter(|_| profile.allows_special(&line_def.special, &line_def.trigger_flags))
+                    .unwrap_or_else(|| {
+                        report.issues.push(DownconversionIssue::LineSpecial {
+                            line_def: i,
+                            special: line_def.special.clone(),
+                            trigger_flags: line_def.trigger_flags.clone(),
+                        });
+                        DoomSpecial::new(0, 0)
+                    })
+            };
+
+            // Without a special, the tag is free to act as the line's id
+            let tag = match (doom.value, line_def.id) {
+                (0, line_def::NO_ID) => 0,
+                (0, id) => id,
+                (_, line_def::NO_ID) => doom.tag,
+                (_, id) => {
+                    if id != doom.tag {
+                        report
+                            .issues
+                            .push(DownconversionIssue::LineId { line_def: i, id });
+                    }
+                    doom.tag
+                }
+            };
+
+            let mut flags = line_def.flags.clone();
+            if !profile.allows_line_flags(&flags) {
+                flags.blocks_land_monsters = false;
+                flags.blocks_players = false;
+            }
+            let encoding = format.line_flags_encoding();
+            let flags = flags.encode(encoding);
+            if line_def::Flags::decode(flags, encoding) != line_def.flags {
+                report
+                    .issues
+                    .push(DownconversionIssue::LineFlags { line_def: i });
+            }
+
+            line_defs.write_u16(line_def.from_idx.get()).unwrap();
+            line_defs.write_u16(line_def.to_idx.get()).unwrap();
+            line_defs.write_u16(flags).unwrap();
+            line_defs.write_i16(doom.value).unwrap();
+            line_defs.write_i16(tag).unwrap();
+            line_defs.write_u16(line_def.left_side_idx.get()).unwrap();
+            line_defs
+                .write_u16(line_def.right_side_idx.map_or(0xffff, SideDefIdx::get))
+                .unwrap();
+        }
+
+        let mut side_defs = Vec::new();
+        for side_def in raw_map.side_defs.iter() {
+            side_defs.write_i16(side_def.offset.x.0).unwrap();
+            side_defs.write_i16(side_def.offset.y.0).unwrap();
+            side_defs.write_string8(&side_def.upper_texture).unwrap();
+            side_defs.write_string8(&side_def.lower_texture).unwrap();
+            side_defs.write_string8(&side_def.middle_texture).unwrap();
+            side_defs.write_u16(side_def.sector_idx.get()).unwrap();
+        }
+
+        let mut sectors = Vec::new();
+        for (i, sector) in raw_map.sectors.iter().enumerate() {
+            // Doom numbers its sector types from 1, where ZDoom numbers them from 65
+            let special = match sector.special {
+                sector::Special::None if sector.secret => sector::DOOM_SECRET,
+                sector::Special::None => 0,
+                // Instant death only exists as MBF21's alternate damage, with no damage bits set, in a generalized
+                // type, which can also be secret
+                sector::Special::DamageInstantDeath
+                    if profile.allows_sector_special(sector.special) =>
+                {
+                    let secret = if sector.secret {
+                        sector::GENERALIZED_SECRET
+                    } else {
+                        0
+                    };
+                    sector::MBF21_ALT_DAMAGE | secret
+                }
+                sector::Special::DamageInstantDeath => {
+                    report.issues.push(DownconversionIssue::SectorSpecial {
+                        sector: i,
+                        special: sector.special,
+                    });
+                    if sector.secret {
+                        sector::DOOM_SECRET
+                    } else {
+                        0
+                    }
+                }
+                // Boom's generalized types can be secret as well as have one of Doom's types
+                special => {
+                    let special = i16::from(special) - 64;
+                    if !sector.secret {
+                        special
+                    } else if profile.allows_secret_with_special() && (1..=17).contains(&special) {
+                        special | sector::GENERALIZED_SECRET
+                    } else {
+                        report
+                            .issues
+                            .push(DownconversionIssue::SectorSecret { sector: i });
+                        special
+                    }
+                }
+            };
+            let special = match sector.kill_grounded_monsters {
+                false => special,
+                true if profile.allows_kill_grounded_monsters() => {
+                    special | sector::MBF21_KILL_GROUNDED_MONSTERS
+                }
+                true => {
+                    report
+                        .issues
+                        .push(DownconversionIssue::SectorKillsMonsters { sector: i });
+                    special
+                }
+            };
+
+            sectors.write_i16(sector.floor_height.0).unwrap();
+            sectors.write_i16(sector.ceiling_height.0).unwrap();
+            sectors.write_string8(&sector.floor_flat).unwrap();
+            sectors.write_string8(&sector.ceiling_flat).unwrap();
+            sectors.write_i16(sector.light_level.into()).unwrap();
+            sectors.write_i16(special).unwrap();
+            sectors.write_i16(sector.tag).unwrap();
+        }
+
+        // Polyobject spots keep their angle, which holds their number, in the ports which have them
+        let thing_types = ThingTypes::for_game(match format {
+            BinaryFormat::Strife => Game::Strife,
+            _ => Game::Doom2,
+        });
+        let mut things = Vec::new();
+        for (i, (key, thing)) in self.things.iter().enumerate() {
+            let entity = EntityRef::Thing(key);
+            let (x, rounded_x) = whole_units(thing.position.x, entity, "x")?;
+            let (y, rounded_y) = whole_units(thing.position.y, entity, "y")?;
+            let encoding = match format {
+                BinaryFormat::Doom => profile.thing_flags_encoding().unwrap(),
+                _ => format.thing_flags_encoding(),
+            };
+            let mut flags = thing.flags.clone();
+            flags.mbf_friend &= profile.allows_friends();
+            let flags = flags.encode(encoding);
+
+            if rounded_x || rounded_y {
+                report
+                    .issues
+                    .push(DownconversionIssue::ThingRounded { thing: i });
+            }
+            let angle = quantized_angle(thing, AngleResolution::Octant, &thing_types);
+            if angle.0 != thing.angle.0.rem_euclid(360) {
+                report.issues.push(DownconversionIssue::ThingAngle {
+                    thing: i,
+                    angle: thing.angle,
+                    quantized: angle,
+                });
+            }
+            if thing.height.0 != 0 {
+                report
+                    .issues
+                    .push(DownconversionIssue::ThingHeight { thing: i });
+            }
+            if thing::Flags::decode(flags, encoding) != thing.flags {
+                report
+                    .issues
+                    .push(DownconversionIssue::ThingFlags { thing: i });
+            }
+
+            things.write_i16(x).unwrap();
+            things.write_i16(y).unwrap();
+            things.write_i16(angle.0).unwrap();
+            things.write_i16(thing.type_).unwrap();
+            things.write_u16(flags).unwrap();
+        }
+
+        let lumps = [
+            ("THINGS", things),
+            ("LINEDEFS", line_defs),
+            ("SIDEDEFS", side_defs),
+            ("VERTEXES", vertexes),
+            ("SECTORS", sectors),
+        ]
+        .into_iter()
+        .map(|(name, data)| Lump::new(String8::new_unchecked(name), data))
+        .collect();
+
+        Ok((lumps, report))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        map::{binary::BinaryMapLumps, RawMap},
+        units::Height,
+    };
+
+    #[test]
+    fn downconvert_round_trip() {
+        let mut map = Map::load_udmf_textmap(
+            "MAP01".try_into().unwrap(),
+            r#"
+            namespace = "zdoom";
+            vertex { x = 0; y = 0; }
+            vertex { x = 64.5; y = 0; }
+            sector { texturefloor = "FLOOR4_8"; textureceiling = "CEIL3_5"; heightceiling = 128; special = 71; }
+            sidedef { sector = 0; }
+            linedef { v1 = 0; v2 = 1; sidefront = 0; special = 12; arg0 = 3; arg1 = 16; arg2 = 150;
+                      playeruse = true; }
+            linedef { v1 = 1; v2 = 0; sidefront = 0; id = 7; special = 12; arg0 = 3; arg1 = 16; arg2 = 150;
+                      playercross = true; }
+            thing { x = 32; y = 16; type = 1; angle = 100; }
+            thing { x = 32; y = 16; type = 2014; skill2 = false; }
+            "#,
+        )
+        .unwrap();
+        map.things.values_mut().last().unwrap().height = Height(24);
+
+        let (lumps, report) = map.to_doom_binary(CompatProfile::Mbf21).unwrap();
+        assert_eq!(
+            report.issues,
+            vec![
+                DownconversionIssue::VertexRounded { vertex: 1 },
+                DownconversionIssue::LineSpecial {
+                    line_def: 1,
+                    special: Special::DoorRaise {
+                        tag: 3,
+                        speed: 16,
+                        delay: 150,
+                        light_tag: 0
+                    },
+                    trigger_flags: TriggerFlags {
+                        player_cross: true,
+                        ..Default::default()
+                    },
+                },
+                DownconversionIssue::ThingAngle {
+                    thing: 0,
+                    angle: Angle(100),
+                    quantized: Angle(90)
+                },
+                DownconversionIssue::ThingHeight { thing: 1 },
+                DownconversionIssue::ThingFlags { thing: 1 },
+            ]
+        );
+        assert!(!report.issues[0].is_dropped());
+
+        let lump = |name| &lumps.iter().find(|lump| lump.is_named(name)).unwrap().data;
+        let (raw_map, conversion) = RawMap::parse_binary(
+            "MAP01".try_into().unwrap(),
+            BinaryFormat::Doom,
+            &BinaryMapLumps {
+                things: lump("THINGS"),
+                line_defs: lump("LINEDEFS"),
+                side_defs: lump("SIDEDEFS"),
+                vertexes: lump("VERTEXES"),
+                sectors: lump("SECTORS"),
+            },
+        )
+        .unwrap();
+
+        assert!(conversion.issues.is_empty());
+        assert_eq!(raw_map.sectors[0].special, sector::Special::DamageNukage);
+        assert_eq!(
+            raw_map.line_defs[0].special,
+            map.line_defs.values().next().unwrap().special
+        );
+        assert_eq!(raw_map.line_defs[1].special, Special::None);
+        assert_eq!(raw_map.line_defs[1].id, 7);
+        assert_eq!(raw_map.vertexes[1].position.x, Number::Int(65));
+        assert_eq!(raw_map.things[0].angle, Angle(90));
+    }
+
+    #[test]
+    fn instant_death_sectors() {
+        let textmap = r#"
+            namespace = "zdoom";
+            sector { texturefloor = "FLAT1"; textureceiling = "FLAT1"; special = 115; }
+            sector { texturefloor = "FLAT1"; textureceiling = "FLAT1"; special = 1139; }
+        "#;
+        let map = Map::load_udmf_textmap("MAP01".try_into().unwrap(), textmap).unwrap();
+
+        let (lumps, report) = map.to_doom_binary(CompatProfile::Mbf21).unwrap();
+        assert!(report.is_lossless(), "{:?}", report.issues);
+        let sectors = &lumps.iter().find(|lump| lump.is_named("SECTORS")).unwrap();
+        let (raw_map, conversion) = RawMap::parse_binary(
+            "MAP01".try_into().unwrap(),
+            BinaryFormat::Doom,
+            &BinaryMapLumps {
+                things: &[],
+                line_defs: &[],
+                side_defs: &[],
+                vertexes: &[],
+                sectors: &sectors.data,
+            },
+        )
+        .unwrap();
+        assert!(conversion.issues.is_empty());
+        for (read, written) in raw_map.sectors.iter().zip(map.sectors.values()) {
+            assert_eq!(read.special, sector::Special::DamageInstantDeath);
+            assert_eq!(read.secret, written.secret);
+        }
+
+        let (_, report) = map.to_strife_binary().unwrap();
+        assert_eq!(
+            report.issues,
+            [0, 1].map(|sector| DownconversionIssue::SectorSpecial {
+                sector,
+                special: sector::Special::DamageInstantDeath
+            })
+        );
+    }
+
+    #[test]
+    fn profile_extensions() {
+        let textmap = r#"
+            namespace = "zdoom";
+            vertex { x = 0; y = 0; }
+            vertex { x = 64; y = 0; }
+            sector { texturefloor = "FLAT1"; textureceiling = "FLAT1"; heightceiling = 128; special = 1095; }
+            sidedef { sector = 0; }
+            linedef { v1 = 0; v2 = 1; sidefront = 0; blockplayers = true; }
+            thing { x = 32; y = 16; type = 3001; friend = true; }
+        "#;
+        let map = Map::load_udmf_textmap("MAP01".try_into().unwrap(), textmap).unwrap();
+
+        let (lumps, report) = map.to_doom_binary(CompatProfile::ZDoom).unwrap();
+        assert!(report.is_lossless(), "{:?}", report.issues);
+        let things = &lumps
+            .iter()
+            .find(|lump| lump.is_named("THINGS"))
+            .unwrap()
+            .data;
+        assert_eq!(things[8] & 0x80, 0x80);
+
+        let (_, report) = map.to_doom_binary(CompatProfile::Boom).unwrap();
+        assert_eq!(
+            report.issues,
+            [
+                DownconversionIssue::LineFlags { line_def: 0 },
+                DownconversionIssue::ThingFlags { thing: 0 },
+            ]
+        );
+
+        let (lumps, report) = map.to_doom_binary(CompatProfile::Vanilla).unwrap();
+        assert_eq!(
+            report.issues,
+            [
+                DownconversionIssue::LineFlags { line_def: 0 },
+                DownconversionIssue::SectorSecret { sector: 0 },
+                DownconversionIssue::ThingFlags { thing: 0 },
+            ]
+        );
+        let things = &lumps
+            .iter()
+            .find(|lump| lump.is_named("THINGS"))
+            .unwrap()
+            .data;
+        assert_eq!(things[8] & 0x80, 0);
+    }
+
+    #[test]
+    fn strife_round_trip() {
+        let textmap = r#"
+            namespace = "strife";
+            vertex { x = 0; y = 0; }
+            vertex { x = 64; y = 0; }
+            sector { texturefloor = "F_FLOOR1"; textureceiling = "F_CEIL1"; heightceiling = 128; }
+            sidedef { sector = 0; }
+            linedef { v1 = 0; v2 = 1; sidefront = 0; special = 2; id = 5; jumpover = true; translucent = true; }
+            linedef { v1 = 1; v2 = 0; sidefront = 0; blockplayers = true; }
+            thing { x = 32; y = 16; type = 1; standing = true; strifeally = true; }
+        "#;
+        let map = Map::load_udmf_textmap("MAP01".try_into().unwrap(), textmap).unwrap();
+        let line_defs: Vec<_> = map.line_defs.values().collect();
+        assert!(matches!(
+            line_defs[0].special,
+            Special::DoorOpen { tag: 5, .. }
+        ));
+        assert!(line_defs[0].trigger_flags.player_cross);
+
+        let (lumps, report) = map.to_strife_binary().unwrap();
+        assert_eq!(
+            report.issues,
+            [DownconversionIssue::LineFlags { line_def: 1 }]
+        );
+
+        let lump = |name| &lumps.iter().find(|lump| lump.is_named(name)).unwrap().data;
+        let (raw_map, conversion) = RawMap::parse_binary(
+            "MAP01".try_into().unwrap(),
+            BinaryFormat::Strife,
+            &BinaryMapLumps {
+                things: lump("THINGS"),
+                line_defs: lump("LINEDEFS"),
+                side_defs: lump("SIDEDEFS"),
+                vertexes: lump("VERTEXES"),
+                sectors: lump("SECTORS"),
+            },
+        )
+        .unwrap();
+
+        assert!(conversion.issues.is_empty());
+        assert_eq!(raw_map.line_defs[0].flags, line_defs[0].flags);
+        assert_eq!(raw_map.line_defs[0].special, line_defs[0].special);
+        assert_eq!(
+            raw_map.things[0].flags,
+            map.things.values().next().unwrap().flags
+        );
+
+        let written = raw_map.to_udmf("strife").unwrap();
+        assert!(written.contains("id=5;"));
+        assert!(written.contains("special=2;"));
+        assert!(written.contains("jumpover=true;"));
+        assert!(!written.contains("playercross"));
+    }
+}
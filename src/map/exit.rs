@@ -0,0 +1,243 @@
+use std::collections::BTreeSet;
+
+use crate::{
+    map::{
+        geometry::Side,
+        line_def::Special,
+        lint::EntityRef,
+        sector::{self, SectorKey},
+        thing_type::{ThingKind, ThingTypes},
+        voodoo::{TripEnd, VoodooDoll},
+        Map,
+    },
+    Point,
+};
+
+/// Where an exit sends the player
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ExitKind {
+    /// To the next map
+    Normal,
+    /// To the secret map
+    Secret,
+    /// To the map with the given number, at the given player start
+    NewMap { map: i16, pos: i16 },
+    /// To the end of the game
+    EndGame,
+    /// To the next map, once the player's health drops low enough in a `sector::Special::DamageEnd` sector
+    LowHealth,
+}
+
+/// A way out of a map, as found by [Map::exits]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Exit {
+    pub kind: ExitKind,
+    /// The line or sector which triggers the exit
+    pub source: EntityRef,
+    /// Whether the player is likely to die as they exit, so the next map starts from a pistol start
+    pub death: bool,
+}
+
+impl Exit {
+    /// Whether the player's inventory doesn't carry over into another map through this exit: after a death exit, or
+    /// when the game ends
+    pub fn resets_inventory(&self) -> bool {
+        self.death || self.kind == ExitKind::EndGame
+    }
+}
+
+impl Map {
+    /// Find the exit lines and sectors of the map, and guess which of them are death exits.
+    ///
+    /// An exit is treated as a death exit if a sector on either side of the exit line kills outright, or if the line
+    /// is crossed by a voodoo doll which is also killed: by passing through such a sector, by ending its trip in a
+    /// damaging sector such as nukage or slime, where it stays until the player dies, or by crossing a teleport line
+    /// into a sector with a player start, telefragging the player or another doll. Setups which kill the player with
+    /// crushers or monsters aren't recognised.
+    pub fn exits(&self, thing_types: &ThingTypes) -> Vec<Exit> {
+        let dolls = self.voodoo_dolls(thing_types);
+        let deadly =
+            |sector: SectorKey| self.sectors[sector].special == sector::Special::DamageInstantDeath;
+
+        let mut exits = Vec::new();
+        for (key, line_def) in self.line_defs.iter() {
+            let kind = match line_def.special {
+                Special::ExitNormal { .. } => ExitKind::Normal,
+                Special::ExitSecret { .. } => ExitKind::Secret,
+                Special::TeleportNewMap { map, pos, .. } => ExitKind::NewMap { map, pos },
+                Special::TeleportEndGame => ExitKind::EndGame,
+                _ => continue,
+            };
+
+            let beside_death = [Side::Front, Side::Back]
+                .into_iter()
+                .filter_map(|side| self.sector_on_side(line_def, side))
+                .any(deadly);
+            let killed_doll = dolls.iter().any(|doll| {
+                doll.triggers.contains(&key)
+                    && (doll.conveyors.iter().copied().any(deadly)
+                        || self.rests_in_damage(doll)
+                        || self.telefrags(doll, thing_types))
+            });
+
+            exits.push(Exit {
+                kind,
+                source: EntityRef::LineDef(key),
+                death: beside_death || killed_doll,
+            });
+        }
+
+        for (key, sector) in self.sectors.iter() {
+            if sector.special == sector::Special::DamageEnd {
+                exits.push(Exit {
+                    kind: ExitKind::LowHealth,
+                    source: EntityRef::Sector(key),
+                    death: false,
+                });
+            }
+        }
+
+        exits
+    }
+
+    /// Whether leaving the map resets the player's inventory: at least one exit is a death exit or ends the game
+    pub fn resets_inventory(&self, thing_types: &ThingTypes) -> bool {
+        self.exits(thing_types).iter().any(Exit::resets_inventory)
+    }
+
+    /// Whether a voodoo doll ends up staying in a sector which damages it, so the player keeps taking damage until
+    /// they die
+    fn rests_in_damage(&self, doll: &VoodooDoll) -> bool {
+        let damaging = |&sector: &SectorKey| self.sectors[sector].special.damage().is_some();
+
+        match doll.end {
+            TripEnd::Stopped(sector) => damaging(&sector),
+            TripEnd::Blocked(_) => doll.conveyors.last().is_some_and(damaging),
+            TripEnd::Loop => doll.conveyors.iter().any(damaging),
+            TripEnd::OutsideMap => false,
+        }
+    }
+
+    /// Whether a voodoo doll crosses a teleport line into a sector where a player 1 start is
+    fn telefrags(&self, doll: &VoodooDoll, thing_types: &ThingTypes) -> bool {
+        let occupied: BTreeSet<SectorKey> = self
+            .things
+            .iter()
+            .filter(|&(key, thing)| {
+                key != doll.thing && thing.kind(thing_types) == Some(ThingKind::Player1Start)
+            })
+            .filter_map(|(_, thing)| {
+                let position = thing.position;
+                self.sector_at(Point::new(position.x.into_float(), position.y.into_float()))
+            })
+            .collect();
+
+        doll.triggers.iter().any(|&line_def| {
+            let (Special::Teleport { tag, .. } | Special::TeleportNoFog { tag, .. }) =
+                self.line_defs[line_def].special
+            else {
+                return false;
+            };
+
+            tag != 0
+                && self
+                    .sectors
+                    .iter()
+                    .any(|(key, sector)| sector.tag == tag && occupied.contains(&key))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        map::{thing, thing_type::Game, voodoo::room_and_closet, Thing},
+        number::Number,
+        units::{Angle, Height},
+    };
+
+    #[test]
+    fn death_exits() {
+        let thing_types = ThingTypes::for_game(Game::Doom2);
+        let mut map = Map::new("MAP01".try_into().unwrap());
+
+        // A room and a pit east of it, with an exit line between them
+        let (sectors, lines) = room_and_closet(&mut map, 128);
+        map.things.insert(Thing {
+            position: Point::new(Number::Int(64), Number::Int(64)),
+            height: Height(0),
+            angle: Angle(0),
+            type_: 1,
+            flags: thing::Flags::default(),
+            special: thing::Special::None,
+        });
+
+        let exit_line = lines[3];
+        map.line_defs[exit_line].special = Special::ExitNormal { pos: 0 };
+        map.line_defs[exit_line].trigger_flags.player_cross = true;
+
+        assert_eq!(
+            map.exits(&thing_types),
+            vec![Exit {
+                kind: ExitKind::Normal,
+                source: EntityRef::LineDef(exit_line),
+                death: false,
+            }]
+        );
+        assert!(!map.resets_inventory(&thing_types));
+
+        map.sectors[sectors[1]].special = sector::Special::DamageInstantDeath;
+        assert!(map.exits(&thing_types)[0].death);
+        assert!(map.resets_inventory(&thing_types));
+
+        map.sectors[sectors[1]].special = sector::Special::DamageEnd;
+        map.line_defs[exit_line].special = Special::TeleportEndGame;
+        let exits = map.exits(&thing_types);
+        assert_eq!(exits[0].kind, ExitKind::EndGame);
+        assert_eq!(exits[1].kind, ExitKind::LowHealth);
+        assert!(map.resets_inventory(&thing_types));
+    }
+
+    #[test]
+    fn damage_death_exits() {
+        let thing_types = ThingTypes::for_game(Game::Doom2);
+        let mut map = Map::new("MAP01".try_into().unwrap());
+
+        // A doll carried east by a conveyor across an exit line into a closet, where the player starts
+        let (sectors, lines) = room_and_closet(&mut map, 128);
+        map.sectors[sectors[0]].tag = 1;
+        map.line_defs[lines[1]].special = Special::ScrollFloor {
+            tag: 1,
+            scrollbits: 0,
+            _type: 2,
+            x_move: 0,
+            y_move: 0,
+        };
+        map.line_defs[lines[3]].special = Special::ExitNormal { pos: 0 };
+        map.line_defs[lines[3]].trigger_flags.player_cross = true;
+        for x in [64, 192] {
+            map.things.insert(Thing {
+                position: Point::new(Number::Int(x), Number::Int(64)),
+                height: Height(0),
+                angle: Angle(0),
+                type_: 1,
+                flags: thing::Flags::default(),
+                special: thing::Special::None,
+            });
+        }
+        assert!(!map.exits(&thing_types)[0].death);
+
+        // The doll comes to rest in nukage, which slowly kills the player
+        map.sectors[sectors[1]].special = sector::Special::DamageNukage;
+        assert!(map.exits(&thing_types)[0].death);
+
+        // Carried on against the closet's far wall by a slime conveyor, the doll can't get away from the damage either
+        map.sectors[sectors[1]].special = sector::Special::None;
+        map.sectors[sectors[1]].tag = 1;
+        assert!(!map.exits(&thing_types)[0].death);
+        map.sectors[sectors[1]].special = sector::Special::DamageHellslime;
+        assert!(map.exits(&thing_types)[0].death);
+    }
+}
@@ -0,0 +1,205 @@
+//! 3D floors, as set up by `Special::SectorSet3dFloor`: the floor and ceiling of a control sector, on the front side of
+//! the line with the special, are drawn as a slab inside every sector with the special's tag.
+
+use crate::map::{
+    geometry::Side,
+    line_def::{LineDefKey, Special},
+    sector::SectorKey,
+    Map,
+};
+
+/// How things interact with a 3D floor, from the low bits of its type
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Floor3dKind {
+    /// Drawn upside down, with the control sector's ceiling at the bottom, as in Vavoom
+    Vavoom,
+    Solid,
+    /// Things can swim through it, like water
+    Swimmable,
+    /// Things pass through it
+    NonSolid,
+}
+
+/// The options in the high bits of a 3D floor's type
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Floor3dOptions {
+    /// Draw the inside of the 3D floor as well as its outside
+    pub render_inside: bool,
+    /// The special's last argument is a line id for the control line, rather than the high byte of the tag
+    pub line_id: bool,
+    pub invert_visibility: bool,
+    pub invert_shootability: bool,
+}
+
+/// The flags of a 3D floor
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Floor3dFlags {
+    pub disable_light_effects: bool,
+    pub restrict_light_inside: bool,
+    pub fog: bool,
+    /// Only draw the top of the 3D floor
+    pub ignore_bottom_height: bool,
+    /// Draw the sides with the control line's upper texture rather than its middle texture
+    pub use_upper_texture: bool,
+    /// Draw the sides with the control line's lower texture rather than its middle texture
+    pub use_lower_texture: bool,
+    pub additive: bool,
+}
+
+impl From<i16> for Floor3dFlags {
+    fn from(flags: i16) -> Self {
+        let bit = |i: u32| flags & (1 << i) != 0;
+
+        Self {
+            disable_light_effects: bit(0),
+            restrict_light_inside: bit(1),
+            fog: bit(2),
+            ignore_bottom_height: bit(3),
+            use_upper_texture: bit(4),
+            use_lower_texture: bit(5),
+            additive: bit(6),
+        }
+    }
+}
+
+/// A 3D floor, as found by [Map::floors_3d]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Floor3d {
+    /// The line with the `SectorSet3dFloor` special
+    pub control_line: LineDefKey,
+    /// The sector whose floor and ceiling are the bottom and top of the 3D floor. `None` if the control line has no
+    /// front sector, in which case the 3D floor isn't created.
+    pub control_sector: Option<SectorKey>,
+    /// The sectors the 3D floor is drawn in. Empty if no sector has the tag.
+    pub targets: Vec<SectorKey>,
+    /// The tag of the target sectors, including its high byte if the special gives one
+    pub tag: i16,
+    pub kind: Floor3dKind,
+    pub options: Floor3dOptions,
+    pub flags: Floor3dFlags,
+    pub alpha: u8,
+}
+
+impl Map {
+    /// Every 3D floor in the map, in the order of their control lines
+    pub fn floors_3d(&self) -> Vec<Floor3d> {
+        let graph = self.tag_graph();
+
+        self.line_defs
+            .iter()
+            .filter_map(|(key, line_def)| {
+                let Special::SectorSet3dFloor {
+                    tag,
+                    _type,
+                    flags,
+                    alpha,
+                    hitag_lineid,
+                } = line_def.special
+                else {
+                    return None;
+                };
+
+                let options = Floor3dOptions {
+                    render_inside: _type & 4 != 0,
+                    line_id: _type & 8 != 0,
+                    invert_visibility: _type & 16 != 0,
+                    invert_shootability: _type & 32 != 0,
+                };
+                let tag = if options.line_id {
+                    tag
+                } else {
+                    tag.wrapping_add(hitag_lineid.wrapping_shl(8))
+                };
+
+                Some(Floor3d {
+                    control_line: key,
+                    control_sector: self.sector_on_side(line_def, Side::Front),
+                    targets: graph.sector_tags.get(&tag).cloned().unwrap_or_default(),
+                    tag,
+                    kind: match _type & 3 {
+                        0 => Floor3dKind::Vavoom,
+                        1 => Floor3dKind::Solid,
+                        2 => Floor3dKind::Swimmable,
+                        _ => Floor3dKind::NonSolid,
+                    },
+                    options,
+                    flags: flags.into(),
+                    alpha,
+                })
+            })
+            .collect()
+    }
+
+    /// The 3D floors drawn in a sector, from the bottom up by the height of their control sectors' floors
+    pub fn floors_3d_in(&self, sector: SectorKey) -> Vec<Floor3d> {
+        let mut floors: Vec<_> = self
+            .floors_3d()
+            .into_iter()
+            .filter(|floor| floor.targets.contains(&sector))
+            .collect();
+        floors.sort_by_key(|floor| {
+            floor
+                .control_sector
+                .map(|control| self.sectors[control].floor_height)
+        });
+
+        floors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_3d_floors() {
+        let map = Map::load_udmf_textmap(
+            "MAP01".try_into().unwrap(),
+            r#"
+            namespace = "zdoom";
+            vertex { x = 0; y = 0; }
+            vertex { x = 64; y = 0; }
+            sector { heightfloor = 64; heightceiling = 72; texturefloor = "FLAT1"; textureceiling = "FLAT1"; }
+            sector { heightfloor = 32; heightceiling = 40; texturefloor = "FLAT1"; textureceiling = "FLAT1"; }
+            sector { id = 261; texturefloor = "FLOOR4_8"; textureceiling = "CEIL3_5"; }
+            sidedef { sector = 0; }
+            sidedef { sector = 1; }
+            linedef { v1 = 0; v2 = 1; sidefront = 0; special = 160; arg0 = 5; arg1 = 1; arg2 = 16; arg3 = 255; arg4 = 1; }
+            linedef { v1 = 1; v2 = 0; sidefront = 1; special = 160; arg0 = 261; arg1 = 10; arg3 = 128; arg4 = 7; }
+            linedef { v1 = 1; v2 = 0; sidefront = 1; special = 160; arg0 = 6; arg1 = 3; }
+            "#,
+        )
+        .unwrap();
+        let sectors: Vec<_> = map.sectors.keys().collect();
+        let line_defs: Vec<_> = map.line_defs.keys().collect();
+
+        let floors = map.floors_3d();
+        assert_eq!(
+            floors[0],
+            Floor3d {
+                control_line: line_defs[0],
+                control_sector: Some(sectors[0]),
+                targets: vec![sectors[2]],
+                tag: 261,
+                kind: Floor3dKind::Solid,
+                options: Floor3dOptions::default(),
+                flags: Floor3dFlags {
+                    use_upper_texture: true,
+                    ..Default::default()
+                },
+                alpha: 255,
+            }
+        );
+        assert_eq!(floors[1].kind, Floor3dKind::Swimmable);
+        assert!(floors[1].options.line_id);
+        assert_eq!(floors[1].tag, 261);
+        assert!(floors[2].targets.is_empty());
+
+        let stacked: Vec<_> = map
+            .floors_3d_in(sectors[2])
+            .iter()
+            .map(|floor| floor.control_line)
+            .collect();
+        assert_eq!(stacked, [line_defs[1], line_defs[0]]);
+    }
+}
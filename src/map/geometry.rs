@@ -0,0 +1,260 @@
+use std::ops::Range;
+
+use miette::Diagnostic;
+
+use crate::{
+    map::{
+        line_def::LineDefKey, lint::EntityRef, sector::SectorKey, side_def::SideDefKey, LineDef,
+        Map,
+    },
+    number::Fixed,
+    Point,
+};
+
+/// The range of coordinates representable by the 16.16 fixed point numbers the Doom engine uses for positions
+pub const COORDINATE_RANGE: Range<f64> = -32768.0..32768.0;
+
+/// A coordinate or offset which an operation would move outside the range its format can represent
+#[derive(Clone, Debug, PartialEq, thiserror::Error, Diagnostic)]
+#[error("The {field} of {entity:?} would be {value}, outside the representable range")]
+#[diagnostic(code(waddle::map::geometry_overflow))]
+pub struct GeometryOverflow {
+    pub entity: EntityRef,
+    pub field: &'static str,
+    pub value: f64,
+}
+
+/// Round `value` to the 16.16 fixed point number the engine would store it as, failing if it's outside
+/// `COORDINATE_RANGE`
+pub(crate) fn check_coordinate(
+    entity: EntityRef,
+    field: &'static str,
+    value: f64,
+) -> Result<Fixed, GeometryOverflow> {
+    Fixed::from_float_rounded(value).ok_or(GeometryOverflow {
+        entity,
+        field,
+        value,
+    })
+}
+
+/// Round `value` to an `i16`, failing if it doesn't fit
+pub(crate) fn checked_i16(
+    entity: EntityRef,
+    field: &'static str,
+    value: f64,
+) -> Result<i16, GeometryOverflow> {
+    let rounded = value.round();
+
+    if (f64::from(i16::MIN)..=f64::from(i16::MAX)).contains(&rounded) {
+        Ok(rounded as i16)
+    } else {
+        Err(GeometryOverflow {
+            entity,
+            field,
+            value,
+        })
+    }
+}
+
+/// Which side of a `LineDef` a point is on.
+///
+/// The front side is the one on the right when looking from `from` to `to`, and is where `LineDef::left_side` faces.
+/// Specials which act on one side of a line take it as an arg, as 0 for the front and 1 for the back.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
+pub enum Side {
+    #[default]
+    Front,
+    Back,
+}
+
+impl From<Side> for i16 {
+    fn from(side: Side) -> Self {
+        match side {
+            Side::Front => 0,
+            Side::Back => 1,
+        }
+    }
+}
+
+impl TryFrom<i16> for Side {
+    type Error = i16;
+
+    fn try_from(n: i16) -> Result<Self, Self::Error> {
+        match n {
+            0 => Ok(Side::Front),
+            1 => Ok(Side::Back),
+            _ => Err(n),
+        }
+    }
+}
+
+impl Map {
+    /// The positions of a line's vertexes, or `None` if either key is dangling
+    pub fn line_segment(&self, line_def: &LineDef) -> Option<(Point<f64>, Point<f64>)> {
+        let from = self.vertexes.get(line_def.from)?.position;
+        let to = self.vertexes.get(line_def.to)?.position;
+
+        Some((
+            Point::new(from.x.into_float(), from.y.into_float()),
+            Point::new(to.x.into_float(), to.y.into_float()),
+        ))
+    }
+
+    /// The side def on the given side of a line
+    pub fn side_of(&self, line_def: &LineDef, side: Side) -> Option<SideDefKey> {
+        match side {
+            Side::Front => Some(line_def.left_side),
+            Side::Back => line_def.right_side,
+        }
+    }
+
+    /// Put a side def on the given side of a line
+    pub(crate) fn set_side(&mut self, line: LineDefKey, side: Side, side_def: SideDefKey) {
+        let line_def = &mut self.line_defs[line];
+        match side {
+            Side::Front => line_def.left_side = side_def,
+            Side::Back => line_def.right_side = Some(side_def),
+        }
+    }
+
+    /// The sector on the given side of a line, if there is one and all keys are valid
+    pub fn sector_on_side(&self, line_def: &LineDef, side: Side) -> Option<SectorKey> {
+        let side_def = self.side_of(line_def, side)?;
+        let sector = self.side_defs.get(side_def)?.sector;
+
+        self.sectors.contains_key(sector).then_some(sector)
+    }
+
+    /// Find the sector containing `point`, or `None` if it's outside the map.
+    ///
+    /// This casts a ray from the point and looks at the side of the nearest line it hits, which works for well-formed
+    /// maps but may give odd results with unclosed sectors. Points exactly on a line may be attributed to either side.
+    pub fn sector_at(&self, point: Point<f64>) -> Option<SectorKey> {
+        self.sector_at_ignoring(point, |_| false)
+    }
+
+    /// Find the sector containing `point` as in [Map::sector_at], as if the lines for which `ignored` returns true
+    /// weren't there
+    pub(crate) fn sector_at_ignoring(
+        &self,
+        point: Point<f64>,
+        ignored: impl Fn(LineDefKey) -> bool,
+    ) -> Option<SectorKey> {
+        let (line_def, _) = self.nearest_line_towards_east(point, ignored)?;
+        let line_def = &self.line_defs[line_def];
+        let (from, to) = self.line_segment(line_def)?;
+
+        self.sector_on_side(line_def, side_of_point(from, to, point))
+    }
+
+    /// The nearest line crossed by a ray cast from `point` in the positive x direction, along with its distance
+    fn nearest_line_towards_east(
+        &self,
+        point: Point<f64>,
+        ignored: impl Fn(LineDefKey) -> bool,
+    ) -> Option<(LineDefKey, f64)> {
+        let mut nearest: Option<(LineDefKey, f64)> = None;
+
+        for (key, line_def) in self.line_defs.iter() {
+            if ignored(key) {
+                continue;
+            }
+            let Some((from, to)) = self.line_segment(line_def) else {
+                continue;
+            };
+
+            // Half-open so that a ray through a vertex only counts one of the two lines meeting there
+            if (from.y <= point.y) == (to.y <= point.y) {
+                continue;
+            }
+
+            let x = from.x + (point.y - from.y) * (to.x - from.x) / (to.y - from.y);
+            let distance = x - point.x;
+
+            if distance >= 0.0 && nearest.is_none_or(|(_, nearest)| distance < nearest) {
+                nearest = Some((key, distance));
+            }
+        }
+
+        nearest
+    }
+}
+
+/// Which side of the line from `from` to `to` the point is on. Points on the line count as the front side.
+pub fn side_of_point(from: Point<f64>, to: Point<f64>, point: Point<f64>) -> Side {
+    let cross = (to.x - from.x) * (point.y - from.y) - (to.y - from.y) * (point.x - from.x);
+
+    if cross <= 0.0 {
+        Side::Front
+    } else {
+        Side::Back
+    }
+}
+
+/// Whether the segment from `from` to `to` passes through the axis-aligned box centred on `centre`
+pub fn segment_intersects_box(
+    from: Point<f64>,
+    to: Point<f64>,
+    centre: Point<f64>,
+    half_size: f64,
+) -> bool {
+    let (min_x, max_x) = (centre.x - half_size, centre.x + half_size);
+    let (min_y, max_y) = (centre.y - half_size, centre.y + half_size);
+
+    // Liang-Barsky clipping of the segment against the box
+    let (dx, dy) = (to.x - from.x, to.y - from.y);
+    let mut t0: f64 = 0.0;
+    let mut t1: f64 = 1.0;
+
+    for (p, q) in [
+        (-dx, from.x - min_x),
+        (dx, max_x - from.x),
+        (-dy, from.y - min_y),
+        (dy, max_y - from.y),
+    ] {
+        if p == 0.0 {
+            if q < 0.0 {
+                return false;
+            }
+        } else {
+            let t = q / p;
+
+            if p < 0.0 {
+                t0 = t0.max(t);
+            } else {
+                t1 = t1.min(t);
+            }
+
+            if t0 > t1 {
+                return false;
+            }
+        }
+    }
+
+    true
+}
+
+/// Where the segments from `a0` to `a1` and from `b0` to `b1` cross, as how far along each one it is, from 0 at its
+/// start to 1 at its end. `None` if they don't cross, or are parallel.
+pub fn segment_intersection(
+    a0: Point<f64>,
+    a1: Point<f64>,
+    b0: Point<f64>,
+    b1: Point<f64>,
+) -> Option<(f64, f64)> {
+    let (a, b) = (a1 - a0, b1 - b0);
+    let denominator = a.cross(b);
+    if denominator == 0.0 {
+        return None;
+    }
+
+    let t = (b0 - a0).cross(b) / denominator;
+    let u = (b0 - a0).cross(a) / denominator;
+
+    ((0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&u)).then_some((t, u))
+}
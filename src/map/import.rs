@@ -0,0 +1,254 @@
+use slotmap::{Key, SecondaryMap};
+
+use crate::{
+    map::{
+        line_def::LineDefKey,
+        sector::SectorKey,
+        selection::{KeyRemap, Selection},
+        side_def::SideDefKey,
+        thing::ThingKey,
+        vertex::VertexKey,
+        EntityKind, Map, UnlinkError,
+    },
+    String8,
+};
+
+/// Where an entity originally came from before being imported into a `Map`
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Origin {
+    /// The name of the map the entity was imported from
+    pub map: String8,
+    pub kind: EntityKind,
+    /// The index of the entity in the source map, as it would be in its `RawMap`
+    pub index: usize,
+}
+
+/// A side-table recording the `Origin` of imported entities, indexed by their keys in the destination map.
+///
+/// Entities which were not imported (e.g. created directly in the map) have no entry.
+#[derive(Clone, Debug, Default)]
+pub struct Provenance {
+    pub vertexes: SecondaryMap<VertexKey, Origin>,
+    pub line_defs: SecondaryMap<LineDefKey, Origin>,
+    pub sectors: SecondaryMap<SectorKey, Origin>,
+    pub side_defs: SecondaryMap<SideDefKey, Origin>,
+    pub things: SecondaryMap<ThingKey, Origin>,
+}
+
+impl Provenance {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the origin of every entity in `remap`, which was produced by importing `source`.
+    ///
+    /// Entities which were themselves imported into `source` keep their original origin, if `source_provenance` is
+    /// given.
+    pub fn record(
+        &mut self,
+        source: &Map,
+        source_provenance: Option<&Provenance>,
+        remap: &KeyRemap,
+    ) {
+        record_kind(
+            &mut self.vertexes,
+            &source.name,
+            EntityKind::Vertex,
+            source.vertexes.keys(),
+            &remap.vertexes,
+            source_provenance.map(|p| &p.vertexes),
+        );
+        record_kind(
+            &mut self.line_defs,
+            &source.name,
+            EntityKind::LineDef,
+            source.line_defs.keys(),
+            &remap.line_defs,
+            source_provenance.map(|p| &p.line_defs),
+        );
+        record_kind(
+            &mut self.sectors,
+            &source.name,
+            EntityKind::Sector,
+            source.sectors.keys(),
+            &remap.sectors,
+            source_provenance.map(|p| &p.sectors),
+        );
+        record_kind(
+            &mut self.side_defs,
+            &source.name,
+            EntityKind::SideDef,
+            source.side_defs.keys(),
+            &remap.side_defs,
+            source_provenance.map(|p| &p.side_defs),
+        );
+        record_kind(
+            &mut self.things,
+            &source.name,
+            EntityKind::Thing,
+            source.things.keys(),
+            &remap.things,
+            source_provenance.map(|p| &p.things),
+        );
+    }
+
+    /// Remove the entries for entities no longer present in `map`
+    pub fn retain_existing(&mut self, map: &Map) {
+        self.vertexes
+            .retain(|key, _| map.vertexes.contains_key(key));
+        self.line_defs
+            .retain(|key, _| map.line_defs.contains_key(key));
+        self.sectors.retain(|key, _| map.sectors.contains_key(key));
+        self.side_defs
+            .retain(|key, _| map.side_defs.contains_key(key));
+        self.things.retain(|key, _| map.things.contains_key(key));
+    }
+}
+
+fn record_kind<K: Key>(
+    table: &mut SecondaryMap<K, Origin>,
+    map: &String8,
+    kind: EntityKind,
+    source_keys: impl Iterator<Item = K>,
+    remap: &SecondaryMap<K, K>,
+    source_table: Option<&SecondaryMap<K, Origin>>,
+) {
+    for (index, source_key) in source_keys.enumerate() {
+        let Some(&new_key) = remap.get(source_key) else {
+            continue;
+        };
+
+        let origin = source_table
+            .and_then(|source_table| source_table.get(source_key))
+            .cloned()
+            .unwrap_or_else(|| Origin {
+                map: map.clone(),
+                kind,
+                index,
+            });
+
+        table.insert(new_key, origin);
+    }
+}
+
+impl Map {
+    /// Merge all of the entities in `other` into this map, returning the keys they were given.
+    ///
+    /// No entities are shared between the two maps: vertexes in the same position, for example, are not merged.
+    pub fn import(&mut self, other: &Map) -> Result<KeyRemap, UnlinkError> {
+        let mut selection = Selection::all(other);
+        selection.close_over(other)?;
+
+        Ok(other.copy_selection_into(&selection, self))
+    }
+
+    /// Like [Map::import], but also record where each imported entity came from in `provenance`.
+    ///
+    /// `other_provenance` can be given if `other` was itself built by importing other maps, so that entities are
+    /// traced back to their original map.
+    pub fn import_tracked(
+        &mut self,
+        other: &Map,
+        other_provenance: Option<&Provenance>,
+        provenance: &mut Provenance,
+    ) -> Result<KeyRemap, UnlinkError> {
+        let remap = self.import(other)?;
+        provenance.record(other, other_provenance, &remap);
+
+        Ok(remap)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn room(name: &str, x: i32) -> Map {
+        Map::load_udmf_textmap(
+            name.try_into().unwrap(),
+            &format!(
+                r#"
+                namespace = "zdoom";
+                vertex {{ x = {x}; y = 0; }}
+                vertex {{ x = {x}; y = 64; }}
+                vertex {{ x = {}; y = 0; }}
+                sector {{ heightceiling = 128; texturefloor = "FLAT1"; textureceiling = "FLAT1"; }}
+                sidedef {{ sector = 0; }}
+                linedef {{ v1 = 0; v2 = 1; sidefront = 0; }}
+                linedef {{ v1 = 1; v2 = 2; sidefront = 0; }}
+                linedef {{ v1 = 2; v2 = 0; sidefront = 0; }}
+                thing {{ x = {}.0; y = 16.0; type = 1; }}
+                "#,
+                x + 64,
+                x + 16,
+            ),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn merge_provenance() {
+        let (map01, map02) = (room("MAP01", 0), room("MAP02", 128));
+
+        // Merge both rooms into an intermediate map, then merge that into the final one
+        let mut merged = Map::new("MERGED".try_into().unwrap());
+        let mut merged_provenance = Provenance::new();
+        let remap01 = merged
+            .import_tracked(&map01, None, &mut merged_provenance)
+            .unwrap();
+        let remap02 = merged
+            .import_tracked(&map02, None, &mut merged_provenance)
+            .unwrap();
+        assert_eq!(merged.vertexes.len(), 6);
+        assert_eq!(merged_provenance.line_defs.len(), 6);
+
+        let line = map02.line_defs.keys().nth(1).unwrap();
+        assert_eq!(
+            merged_provenance.line_defs[remap02.line_defs[line]],
+            Origin {
+                map: "MAP02".try_into().unwrap(),
+                kind: EntityKind::LineDef,
+                index: 1,
+            }
+        );
+
+        let mut last = Map::new("LAST".try_into().unwrap());
+        let mut provenance = Provenance::new();
+        let remap = last
+            .import_tracked(&merged, Some(&merged_provenance), &mut provenance)
+            .unwrap();
+
+        // Entities are traced back through the intermediate map to the room they came from
+        let thing = map01.things.keys().next().unwrap();
+        let key = remap.things[remap01.things[thing]];
+        assert_eq!(
+            provenance.things[key],
+            Origin {
+                map: "MAP01".try_into().unwrap(),
+                kind: EntityKind::Thing,
+                index: 0,
+            }
+        );
+        assert_eq!(
+            provenance.line_defs[remap.line_defs[remap02.line_defs[line]]].map,
+            "MAP02"
+        );
+
+        // Without the intermediate provenance, entities are traced to the intermediate map
+        let mut untracked = Provenance::new();
+        let remap = Map::new("LAST".try_into().unwrap())
+            .import_tracked(&merged, None, &mut untracked)
+            .unwrap();
+        assert_eq!(
+            untracked.things[remap.things[remap01.things[thing]]].map,
+            "MERGED"
+        );
+
+        // Entries for removed entities are dropped
+        last.things.remove(key);
+        provenance.retain_existing(&last);
+        assert!(!provenance.things.contains_key(key));
+        assert_eq!(provenance.things.len(), 1);
+        assert_eq!(provenance.vertexes.len(), 6);
+    }
+}
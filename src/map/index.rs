@@ -0,0 +1,253 @@
+//! Looking up entities by key, and the entities which refer to them, without hand-written joins over `Map`'s fields.
+
+use std::{collections::HashMap, ops::Deref};
+
+use crate::map::{
+    line_def::LineDefKey, lint::EntityRef, sector::SectorKey, side_def::SideDefKey,
+    thing::ThingKey, vertex::VertexKey, EntityKey, LineDef, Map, MapEntity, Sector, SideDef, Thing,
+    Vertex,
+};
+
+impl Map {
+    pub fn vertex(&self, key: VertexKey) -> Option<&Vertex> {
+        self.vertexes.get(key)
+    }
+
+    pub fn line_def(&self, key: LineDefKey) -> Option<&LineDef> {
+        self.line_defs.get(key)
+    }
+
+    pub fn sector(&self, key: SectorKey) -> Option<&Sector> {
+        self.sectors.get(key)
+    }
+
+    pub fn side_def(&self, key: SideDefKey) -> Option<&SideDef> {
+        self.side_defs.get(key)
+    }
+
+    pub fn thing(&self, key: ThingKey) -> Option<&Thing> {
+        self.things.get(key)
+    }
+
+    /// The side defs of a line, left side first
+    pub fn side_defs_of_line(&self, key: LineDefKey) -> impl Iterator<Item = SideDefKey> + '_ {
+        self.line_defs
+            .get(key)
+            .into_iter()
+            .flat_map(|line_def| [Some(line_def.left_side), line_def.right_side])
+            .flatten()
+    }
+}
+
+/// A map which keeps an index of the entities referring to each entity, e.g. the side defs facing a sector.
+///
+/// The map can only be changed through the methods of `IndexedMap`, which keep the index up to date. It dereferences
+/// to the `Map` for reading.
+#[derive(Clone, Debug)]
+pub struct IndexedMap {
+    map: Map,
+    referrers: HashMap<EntityRef, Vec<EntityRef>>,
+}
+
+impl IndexedMap {
+    pub fn new(map: Map) -> Self {
+        let mut indexed = Self {
+            map,
+            referrers: HashMap::new(),
+        };
+
+        let line_defs: Vec<_> = indexed.map.line_defs.keys().collect();
+        for key in line_defs {
+            indexed.index::<LineDefKey>(key);
+        }
+        let side_defs: Vec<_> = indexed.map.side_defs.keys().collect();
+        for key in side_defs {
+            indexed.index::<SideDefKey>(key);
+        }
+
+        indexed
+    }
+
+    pub fn into_map(self) -> Map {
+        self.map
+    }
+
+    pub fn insert<E: MapEntity>(&mut self, entity: E) -> E::Key {
+        let key = E::entities_mut(&mut self.map).insert(entity);
+        self.index(key);
+
+        key
+    }
+
+    /// Remove an entity. Entities referring to it are left referring to a missing entity, and are still listed as its
+    /// referrers.
+    pub fn remove<K: EntityKey>(&mut self, key: K) -> Option<K::Entity> {
+        self.unindex(key);
+        K::Entity::entities_mut(&mut self.map).remove(key)
+    }
+
+    /// Change an entity with `f`, returning its result, or `None` if there's no such entity
+    pub fn modify<K: EntityKey, R>(
+        &mut self,
+        key: K,
+        f: impl FnOnce(&mut K::Entity) -> R,
+    ) -> Option<R> {
+        self.unindex(key);
+        let result = K::Entity::entities_mut(&mut self.map).get_mut(key).map(f);
+        self.index(key);
+
+        result
+    }
+
+    /// Every entity referring to `entity`, in no particular order. An entity referring to it twice, such as a line
+    /// with the same side def on both sides, is listed twice.
+    pub fn referrers(&self, entity: EntityRef) -> &[EntityRef] {
+        self.referrers
+            .get(&entity)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// The side defs facing a sector
+    pub fn side_defs_of_sector(&self, key: SectorKey) -> impl Iterator<Item = SideDefKey> + '_ {
+        self.referrers(EntityRef::Sector(key))
+            .iter()
+            .filter_map(|referrer| match referrer {
+                EntityRef::SideDef(side_def) => Some(*side_def),
+                _ => None,
+            })
+    }
+
+    /// The lines starting or ending at a vertex, each listed once
+    pub fn line_defs_of_vertex(&self, key: VertexKey) -> Vec<LineDefKey> {
+        self.line_defs_referring_to([EntityRef::Vertex(key)])
+    }
+
+    /// The lines with a side def on either side, each listed once
+    pub fn line_defs_of_side_def(&self, key: SideDefKey) -> Vec<LineDefKey> {
+        self.line_defs_referring_to([EntityRef::SideDef(key)])
+    }
+
+    /// The lines with a side facing a sector, each listed once
+    pub fn line_defs_of_sector(&self, key: SectorKey) -> Vec<LineDefKey> {
+        self.line_defs_referring_to(self.side_defs_of_sector(key).map(EntityRef::SideDef))
+    }
+
+    fn line_defs_referring_to(
+        &self,
+        entities: impl IntoIterator<Item = EntityRef>,
+    ) -> Vec<LineDefKey> {
+        let mut line_defs: Vec<_> = entities
+            .into_iter()
+            .flat_map(|entity| self.referrers(entity))
+            .filter_map(|referrer| match referrer {
+                EntityRef::LineDef(line_def) => Some(*line_def),
+                _ => None,
+            })
+            .collect();
+        line_defs.sort();
+        line_defs.dedup();
+        line_defs
+    }
+
+    fn index<K: EntityKey>(&mut self, key: K) {
+        let Some(entity) = K::Entity::entities(&self.map).get(key) else {
+            return;
+        };
+
+        for referee in entity.references() {
+            self.referrers
+                .entry(referee)
+                .or_default()
+                .push(key.entity_ref());
+        }
+    }
+
+    fn unindex<K: EntityKey>(&mut self, key: K) {
+        let Some(entity) = K::Entity::entities(&self.map).get(key) else {
+            return;
+        };
+
+        for referee in entity.references() {
+            if let Some(referrers) = self.referrers.get_mut(&referee) {
+                if let Some(i) = referrers
+                    .iter()
+                    .position(|referrer| *referrer == key.entity_ref())
+                {
+                    referrers.swap_remove(i);
+                }
+            }
+        }
+    }
+}
+
+impl Deref for IndexedMap {
+    type Target = Map;
+
+    fn deref(&self) -> &Map {
+        &self.map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reverse_references() {
+        let map = Map::load_udmf_textmap(
+            "MAP01".try_into().unwrap(),
+            r#"
+            namespace = "zdoom";
+            vertex { x = 0; y = 0; }
+            vertex { x = 64; y = 0; }
+            vertex { x = 0; y = 64; }
+            sector { texturefloor = "FLOOR4_8"; textureceiling = "CEIL3_5"; }
+            sector { texturefloor = "FLOOR4_8"; textureceiling = "CEIL3_5"; }
+            sidedef { sector = 0; }
+            sidedef { sector = 1; }
+            linedef { v1 = 0; v2 = 1; sidefront = 0; sideback = 1; }
+            linedef { v1 = 1; v2 = 2; sidefront = 0; }
+            "#,
+        )
+        .unwrap();
+        let vertexes: Vec<_> = map.vertexes.keys().collect();
+        let sectors: Vec<_> = map.sectors.keys().collect();
+        let side_defs: Vec<_> = map.side_defs.keys().collect();
+        let line_defs: Vec<_> = map.line_defs.keys().collect();
+
+        assert_eq!(
+            map.side_defs_of_line(line_defs[0]).collect::<Vec<_>>(),
+            side_defs
+        );
+
+        let mut map = IndexedMap::new(map);
+        assert_eq!(map.line_defs_of_sector(sectors[0]), line_defs);
+        assert_eq!(map.line_defs_of_sector(sectors[1]), vec![line_defs[0]]);
+
+        map.modify(line_defs[1], |line_def| line_def.left_side = side_defs[1]);
+        assert_eq!(map.line_defs_of_sector(sectors[0]), vec![line_defs[0]]);
+        assert_eq!(map.line_defs_of_sector(sectors[1]), line_defs);
+
+        let side_def = map.insert(SideDef {
+            sector: sectors[0],
+            ..Default::default()
+        });
+        let line_def = map.insert(LineDef {
+            right_side: None,
+            left_side: side_def,
+            ..map.line_defs[line_defs[1]].clone()
+        });
+        assert_eq!(
+            map.line_defs_of_vertex(vertexes[2]),
+            vec![line_defs[1], line_def]
+        );
+
+        map.remove(line_defs[1]);
+        assert_eq!(
+            map.line_defs_of_vertex(vertexes[1]),
+            vec![line_defs[0], line_def]
+        );
+        assert_eq!(map.into_map().line_defs.len(), 2);
+    }
+}
@@ -0,0 +1,270 @@
+//! Removing entities without leaving references to them behind, and finding references which were left behind.
+//!
+//! Removing an entity straight from one of `Map`'s `SlotMap`s doesn't touch the entities which refer to it, and the
+//! dangling keys only surface when the map is unlinked.
+
+use crate::map::{
+    lint::EntityRef, sector::SectorKey, side_def::SideDefKey, vertex::VertexKey, EntityKind, Map,
+};
+
+/// What to do with the entities referring to an entity being removed
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RemovalMode {
+    /// Don't remove anything if the entity is referred to
+    #[default]
+    Refuse,
+    /// Remove the referring entities too, and the entities referring to those
+    Cascade,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum RemoveError {
+    #[error("{entity:?} isn't in the map")]
+    Missing { entity: EntityRef },
+
+    #[error("{entity:?} is referred to by {} other entities", referrers.len())]
+    Referenced {
+        entity: EntityRef,
+        referrers: Vec<EntityRef>,
+    },
+}
+
+/// A reference to an entity which isn't in the map, as found by [Map::check_integrity]
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+#[error("{referrer:?}.{field} refers to a {referee} which isn't in the map")]
+pub struct DanglingReference {
+    pub referrer: EntityRef,
+    pub field: &'static str,
+    pub referee: EntityKind,
+}
+
+impl Map {
+    /// Remove a vertex. The lines using it are its referrers, and are removed in `RemovalMode::Cascade`, leaving their
+    /// side defs unused.
+    ///
+    /// Returns every entity removed, with `key` last.
+    pub fn remove_vertex(
+        &mut self,
+        key: VertexKey,
+        mode: RemovalMode,
+    ) -> Result<Vec<EntityRef>, RemoveError> {
+        let entity = EntityRef::Vertex(key);
+        if !self.vertexes.contains_key(key) {
+            return Err(RemoveError::Missing { entity });
+        }
+
+        let referrers: Vec<_> = self
+            .line_defs
+            .iter()
+            .filter(|(_, line_def)| line_def.from == key || line_def.to == key)
+            .map(|(line_def, _)| EntityRef::LineDef(line_def))
+            .collect();
+
+        let mut removed = self.remove_referrers(entity, referrers, mode)?;
+        self.vertexes.remove(key);
+        removed.push(entity);
+
+        Ok(removed)
+    }
+
+    /// Remove a side def. The lines with it on either side are its referrers, and are removed in
+    /// `RemovalMode::Cascade`.
+    ///
+    /// Returns every entity removed, with `key` last.
+    pub fn remove_side_def(
+        &mut self,
+        key: SideDefKey,
+        mode: RemovalMode,
+    ) -> Result<Vec<EntityRef>, RemoveError> {
+        let entity = EntityRef::SideDef(key);
+        if !self.side_defs.contains_key(key) {
+            return Err(RemoveError::Missing { entity });
+        }
+
+        let referrers: Vec<_> = self
+            .line_defs
+            .iter()
+            .filter(|(_, line_def)| line_def.left_side == key || line_def.right_side == Some(key))
+            .map(|(line_def, _)| EntityRef::LineDef(line_def))
+            .collect();
+
+        let mut removed = self.remove_referrers(entity, referrers, mode)?;
+        self.side_defs.remove(key);
+        removed.push(entity);
+
+        Ok(removed)
+    }
+
+    /// Remove a sector. The side defs facing it are its referrers, and are removed along with their lines in
+    /// `RemovalMode::Cascade`.
+    ///
+    /// Returns every entity removed, with `key` last.
+    pub fn remove_sector(
+        &mut self,
+        key: SectorKey,
+        mode: RemovalMode,
+    ) -> Result<Vec<EntityRef>, RemoveError> {
+        let entity = EntityRef::Sector(key);
+        if !self.sectors.contains_key(key) {
+            return Err(RemoveError::Missing { entity });
+        }
+
+        let referrers: Vec<_> = self
+            .side_defs
+            .iter()
+            .filter(|(_, side_def)| side_def.sector == key)
+            .map(|(side_def, _)| EntityRef::SideDef(side_def))
+            .collect();
+
+        let mut removed = self.remove_referrers(entity, referrers, mode)?;
+        self.sectors.remove(key);
+        removed.push(entity);
+
+        Ok(removed)
+    }
+
+    fn remove_referrers(
+        &mut self,
+        entity: EntityRef,
+        referrers: Vec<EntityRef>,
+        mode: RemovalMode,
+    ) -> Result<Vec<EntityRef>, RemoveError> {
+        if referrers.is_empty() {
+            return Ok(Vec::new());
+        }
+        if mode == RemovalMode::Refuse {
+            return Err(RemoveError::Referenced { entity, referrers });
+        }
+
+        let mut removed = Vec::new();
+        for referrer in referrers {
+            match referrer {
+                EntityRef::LineDef(line_def) => {
+                    // A line with the same side def on both sides is only removed once
+                    if self.line_defs.remove(line_def).is_some() {
+                        removed.push(referrer);
+                    }
+                }
+                EntityRef::SideDef(side_def) => {
+                    removed.extend(self.remove_side_def(side_def, mode)?);
+                }
+                _ => unreachable!("only lines and side defs refer to other entities"),
+            }
+        }
+
+        Ok(removed)
+    }
+
+    /// Every reference to an entity which isn't in the map, which would make [Map::unlink] fail
+    pub fn check_integrity(&self) -> Vec<DanglingReference> {
+        let mut dangling = Vec::new();
+
+        for (key, side_def) in &self.side_defs {
+            if !self.sectors.contains_key(side_def.sector) {
+                dangling.push(DanglingReference {
+                    referrer: EntityRef::SideDef(key),
+                    field: "sector",
+                    referee: EntityKind::Sector,
+                });
+            }
+        }
+
+        for (key, line_def) in &self.line_defs {
+            let references = [
+                (
+                    "from",
+                    self.vertexes.contains_key(line_def.from),
+                    EntityKind::Vertex,
+                ),
+                (
+                    "to",
+                    self.vertexes.contains_key(line_def.to),
+                    EntityKind::Vertex,
+                ),
+                (
+                    "left_side",
+                    self.side_defs.contains_key(line_def.left_side),
+                    EntityKind::SideDef,
+                ),
+                (
+                    "right_side",
+                    line_def
+                        .right_side
+                        .is_none_or(|side| self.side_defs.contains_key(side)),
+                    EntityKind::SideDef,
+                ),
+            ];
+
+            for (field, valid, referee) in references {
+                if !valid {
+                    dangling.push(DanglingReference {
+                        referrer: EntityRef::LineDef(key),
+                        field,
+                        referee,
+                    });
+                }
+            }
+        }
+
+        dangling
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn safe_removal() {
+        let mut map = Map::load_udmf_textmap(
+            "MAP01".try_into().unwrap(),
+            r#"
+            namespace = "zdoom";
+            vertex { x = 0; y = 0; }
+            vertex { x = 64; y = 0; }
+            vertex { x = 0; y = 64; }
+            sector { texturefloor = "FLOOR4_8"; textureceiling = "CEIL3_5"; }
+            sector { texturefloor = "FLOOR4_8"; textureceiling = "CEIL3_5"; }
+            sidedef { sector = 0; }
+            sidedef { sector = 1; }
+            linedef { v1 = 0; v2 = 1; sidefront = 0; sideback = 1; }
+            linedef { v1 = 1; v2 = 2; sidefront = 0; }
+            "#,
+        )
+        .unwrap();
+        let vertexes: Vec<_> = map.vertexes.keys().collect();
+        let sectors: Vec<_> = map.sectors.keys().collect();
+        let side_defs: Vec<_> = map.side_defs.keys().collect();
+        let line_defs: Vec<_> = map.line_defs.keys().collect();
+
+        assert_eq!(
+            map.remove_vertex(vertexes[2], RemovalMode::Refuse),
+            Err(RemoveError::Referenced {
+                entity: EntityRef::Vertex(vertexes[2]),
+                referrers: vec![EntityRef::LineDef(line_defs[1])],
+            })
+        );
+        assert_eq!(map.vertexes.len(), 3);
+
+        assert_eq!(
+            map.remove_sector(sectors[1], RemovalMode::Cascade),
+            Ok(vec![
+                EntityRef::LineDef(line_defs[0]),
+                EntityRef::SideDef(side_defs[1]),
+                EntityRef::Sector(sectors[1]),
+            ])
+        );
+        assert_eq!(map.line_defs.len(), 1);
+        assert!(map.check_integrity().is_empty());
+
+        map.vertexes.remove(vertexes[2]);
+        assert_eq!(
+            map.check_integrity(),
+            vec![DanglingReference {
+                referrer: EntityRef::LineDef(line_defs[1]),
+                field: "to",
+                referee: EntityKind::Vertex,
+            }]
+        );
+    }
+}
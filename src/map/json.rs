@@ -0,0 +1,105 @@
+//! Maps as JSON, for tools which can't parse UDMF, such as map viewers written in JavaScript.
+//!
+//! The JSON is the map's serde representation, which is that of its [RawMap](crate::map::RawMap): references between
+//! entities are indices into the entity arrays, and the field names are those of the entity types.
+
+use crate::map::Map;
+
+impl Map {
+    /// Write the map as JSON.
+    ///
+    /// The document is an object with the map's `name`, and an array for each kind of entity: `vertexes`,
+    /// `line_defs`, `sectors`, `side_defs` and `things`. Entities refer to each other by their index in these arrays,
+    /// e.g. a line def's `from_idx` and `to_idx` are indices into `vertexes`:
+    ///
+    /// ```json
+    /// {
+    ///   "name": "MAP01",
+    ///   "vertexes": [
+    ///     {"position": {"x": 0, "y": 0}, "floor_z": null, "ceiling_z": null},
+    ///     {"position": {"x": 64.5, "y": 0}, "floor_z": null, "ceiling_z": null}
+    ///   ],
+    ///   "line_defs": [
+    ///     {"from_idx": 0, "to_idx": 1, "left_side_idx": 0, "right_side_idx": null, ...}
+    ///   ],
+    ///   ...
+    /// }
+    /// ```
+    ///
+    /// Floats are always written with a fraction or an exponent and integers never are, so that numbers keep their
+    /// type through [Map::from_json]. JSON can't express infinities or NaN, which are written as `null` and so can't
+    /// be loaded back.
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// Load a map written by [Map::to_json], or by another tool following the same schema
+    pub fn from_json(contents: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(contents)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::number::Number;
+
+    #[test]
+    fn json_round_trip() {
+        let map = Map::load_udmf_textmap(
+            "MAP01".try_into().unwrap(),
+            r#"
+            namespace = "zdoom";
+            vertex { x = 0; y = 0; }
+            vertex { x = 64.5; y = 0; }
+            vertex { x = 0; y = 64; }
+            sector { texturefloor = "FLOOR4_8"; textureceiling = "CEIL3_5"; heightceiling = 128; }
+            sidedef { sector = 0; texturemiddle = "STARTAN3"; }
+            linedef { v1 = 0; v2 = 1; sidefront = 0; special = 12; arg0 = 3; playeruse = true; }
+            linedef { v1 = 1; v2 = 2; sidefront = 0; }
+            linedef { v1 = 2; v2 = 0; sidefront = 0; }
+            thing { x = 32; y = 16; type = 1; angle = 90; }
+            "#,
+        )
+        .unwrap();
+
+        let json = map.to_json().unwrap();
+        assert!(json.contains(r#""x": 64.5"#), "{json}");
+        assert!(json.contains(r#""middle_texture": "STARTAN3""#), "{json}");
+
+        let loaded = Map::from_json(&json).unwrap();
+        assert_eq!(loaded.to_json().unwrap(), json);
+        assert_eq!(loaded, map);
+
+        let dangling = json.replacen(r#""to_idx": 1"#, r#""to_idx": 7"#, 1);
+        assert!(Map::from_json(&dangling).is_err());
+        assert!(Map::from_json(r#"{"name": "MAP01", "vertexes": [{"x": 0,}]}"#).is_err());
+    }
+
+    #[test]
+    fn json_awkward_values() {
+        let mut map = Map::load_udmf_textmap(
+            "MAP01".try_into().unwrap(),
+            r#"
+            namespace = "zdoom";
+            vertex { x = 0; y = 0; }
+            vertex { x = 64; y = 0; }
+            sector { texturefloor = "A\"B"; textureceiling = "C\\D"; }
+            sidedef { sector = 0; }
+            linedef { v1 = 0; v2 = 1; sidefront = 0; }
+            "#,
+        )
+        .unwrap();
+
+        let json = map.to_json().unwrap();
+        assert!(json.contains(r#""floor_flat": "A\"B""#), "{json}");
+        assert_eq!(Map::from_json(&json).unwrap(), map);
+
+        let vertex = map.vertexes.keys().next().unwrap();
+        map.vertexes[vertex].position.x = Number::Float(f64::NAN);
+        let json = map.to_json().unwrap();
+        assert!(json.contains(r#""x": null"#), "{json}");
+        assert!(Map::from_json(&json).is_err());
+    }
+}
@@ -1,16 +1,32 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
 use bitfield::Bit;
 use slotmap::SlotMap;
 use waddle_derive::LineDefSpecial;
 
-use crate::map::{side_def::SideDefKey, vertex::VertexKey};
+use crate::{
+    map::{
+        geometry::Side, lock::Lock, side_def::SideDefKey, vertex::VertexKey, SideDefIdx, VertexIdx,
+    },
+    units::{Angle, Height},
+};
 
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
 pub struct RawLineDef {
-    pub from_idx: u16,
-    pub to_idx: u16,
-    pub left_side_idx: u16,
-    pub right_side_idx: Option<u16>,
+    pub from_idx: VertexIdx,
+    pub to_idx: VertexIdx,
+    pub left_side_idx: SideDefIdx,
+    pub right_side_idx: Option<SideDefIdx>,
 
+    /// The line id, used by specials to refer to this line. `NO_ID` if unset
+    pub id: i16,
     pub flags: Flags,
     pub special: Special,
     pub trigger_flags: TriggerFlags,
@@ -23,13 +39,22 @@ pub struct LineDef {
     pub left_side: SideDefKey,
     pub right_side: Option<SideDefKey>,
 
+    /// The line id, used by specials to refer to this line. `NO_ID` if unset
+    pub id: i16,
     pub flags: Flags,
     pub special: Special,
     pub trigger_flags: TriggerFlags,
 }
 
+/// The value of `LineDef::id` for lines which can't be referred to by specials
+pub const NO_ID: i16 = -1;
+
 /// Boolean flags associated with a `LineDef`
 #[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
 pub struct Flags {
     pub impassable: bool,
     pub blocks_monsters: bool,
@@ -40,46 +65,115 @@ pub struct Flags {
     pub blocks_sound: bool,
     pub not_on_map: bool,
     pub already_on_map: bool,
+    /// Blocks monsters which can't fly or float, as in MBF21
+    pub blocks_land_monsters: bool,
+    /// Blocks players, as in MBF21
+    pub blocks_players: bool,
+    /// A railing which can be jumped over, as in Strife
+    pub jump_over: bool,
+    /// Blocks floating monsters, as in Strife
+    pub blocks_floaters: bool,
+    /// The middle texture is drawn translucent, as in Strife
+    pub translucent: bool,
+}
+
+/// The ways the binary map formats pack line flags into a word. They agree on the first 9 bits, and differ above
+/// them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FlagsEncoding {
+    /// Doom, with the MBF21 "block land monsters" (12) and "block players" (13) bits
+    Doom,
+    /// Hexen, which uses the bits above 8 for the activation of the line's special
+    Hexen,
+    /// Strife, with "jump over" (9), "block floaters" (10) and "translucent" (11) bits. Its second translucency bit
+    /// (12) isn't kept.
+    Strife,
+}
+
+impl Flags {
+    /// Decode the flags of a line in a binary format
+    pub fn decode(bits: u16, encoding: FlagsEncoding) -> Self {
+        let common = Self {
+            impassable: bits.bit(0),
+            blocks_monsters: bits.bit(1),
+            two_sided: bits.bit(2),
+            upper_unpegged: bits.bit(3),
+            lower_unpegged: bits.bit(4),
+            secret: bits.bit(5),
+            blocks_sound: bits.bit(6),
+            not_on_map: bits.bit(7),
+            already_on_map: bits.bit(8),
+            ..Self::default()
+        };
+
+        match encoding {
+            FlagsEncoding::Doom => Self {
+                blocks_land_monsters: bits.bit(12),
+                blocks_players: bits.bit(13),
+                ..common
+            },
+            FlagsEncoding::Hexen => common,
+            FlagsEncoding::Strife => Self {
+                jump_over: bits.bit(9),
+                blocks_floaters: bits.bit(10),
+                translucent: bits.bit(11),
+                ..common
+            },
+        }
+    }
+
+    /// Encode the flags of a line in a binary format. Flags not representable in the encoding are dropped, and the
+    /// Hexen activation bits are left clear.
+    pub fn encode(&self, encoding: FlagsEncoding) -> u16 {
+        let mut bits: u16 = 0;
+
+        bits.set_bit(0, self.impassable);
+        bits.set_bit(1, self.blocks_monsters);
+        bits.set_bit(2, self.two_sided);
+        bits.set_bit(3, self.upper_unpegged);
+        bits.set_bit(4, self.lower_unpegged);
+        bits.set_bit(5, self.secret);
+        bits.set_bit(6, self.blocks_sound);
+        bits.set_bit(7, self.not_on_map);
+        bits.set_bit(8, self.already_on_map);
+
+        match encoding {
+            FlagsEncoding::Doom => {
+                bits.set_bit(12, self.blocks_land_monsters);
+                bits.set_bit(13, self.blocks_players);
+            }
+            FlagsEncoding::Hexen => {}
+            FlagsEncoding::Strife => {
+                bits.set_bit(9, self.jump_over);
+                bits.set_bit(10, self.blocks_floaters);
+                bits.set_bit(11, self.translucent);
+            }
+        }
+
+        bits
+    }
 }
 
+/// Decode the flags of a line in the Doom binary format, including the MBF21 extensions
 impl From<i16> for Flags {
     fn from(flags: i16) -> Self {
-        let flags_bits: u16 = flags as u16;
-
-        Self {
-            impassable: flags_bits.bit(0),
-            blocks_monsters: flags_bits.bit(1),
-            two_sided: flags_bits.bit(2),
-            upper_unpegged: flags_bits.bit(3),
-            lower_unpegged: flags_bits.bit(4),
-            secret: flags_bits.bit(5),
-            blocks_sound: flags_bits.bit(6),
-            not_on_map: flags_bits.bit(7),
-            already_on_map: flags_bits.bit(8),
-        }
+        Self::decode(flags as u16, FlagsEncoding::Doom)
     }
 }
 
+/// Encode the flags of a line in the Doom binary format, including the MBF21 extensions
 impl From<Flags> for i16 {
     fn from(flags: Flags) -> Self {
-        let mut flags_bits: u16 = 0;
-
-        flags_bits.set_bit(0, flags.impassable);
-        flags_bits.set_bit(1, flags.blocks_monsters);
-        flags_bits.set_bit(2, flags.two_sided);
-        flags_bits.set_bit(3, flags.upper_unpegged);
-        flags_bits.set_bit(4, flags.lower_unpegged);
-        flags_bits.set_bit(5, flags.secret);
-        flags_bits.set_bit(6, flags.blocks_sound);
-        flags_bits.set_bit(7, flags.not_on_map);
-        flags_bits.set_bit(8, flags.already_on_map);
-
-        flags_bits as i16
+        flags.encode(FlagsEncoding::Doom) as i16
     }
 }
 
 /// Flags determining how a `LineDef` `Special` may be triggered
 #[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
 pub struct TriggerFlags {
     pub player_cross: bool,
     pub player_use: bool,
@@ -95,12 +189,202 @@ pub struct TriggerFlags {
     pub monsters_activate: bool,
 }
 
+impl TriggerFlags {
+    /// The names of the flags which are set, as in UDMF but with underscores
+    pub fn set_names(&self) -> Vec<&'static str> {
+        [
+            (self.player_cross, "player_cross"),
+            (self.player_use, "player_use"),
+            (self.monster_cross, "monster_cross"),
+            (self.monster_use, "monster_use"),
+            (self.impact, "impact"),
+            (self.player_push, "player_push"),
+            (self.monster_push, "monster_push"),
+            (self.missile_cross, "missile_cross"),
+            (self.repeats, "repeats"),
+            (self.monsters_activate, "monsters_activate"),
+        ]
+        .into_iter()
+        .filter_map(|(set, name)| set.then_some(name))
+        .collect()
+    }
+}
+
+/// The part of a sector moved by a `Special`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MovingPlane {
+    Floor,
+    Ceiling,
+    /// Both the floor and the ceiling, either together or in opposite directions
+    Both,
+}
+
+/// How a `Special` moves a sector's `MovingPlane` over time
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MotionDirection {
+    Up,
+    Down,
+    /// Up or down, whichever way the target is
+    Toward,
+    /// The floor rises and the ceiling lowers until they meet
+    Together,
+    /// The floor lowers and the ceiling rises
+    Apart,
+    /// Move up to the target, wait, then return. Doors and raising platforms.
+    UpThenDown,
+    /// Move down to the target, wait, then return. Lifts and crushers which stop after one cycle.
+    DownThenUp,
+    /// Cycle back and forth until stopped
+    Perpetual,
+    /// Bob smoothly around the original height
+    Oscillate,
+    /// Move to the target, then back to the original height the next time the special is activated
+    Toggle,
+    /// Stop a movement started by another special
+    Stop,
+    /// Determined by the special's args
+    Variable,
+}
+
+/// The height a `Special` moves a sector's `MovingPlane` towards
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TargetHeight {
+    /// There is no target, e.g. because the special stops movement
+    None,
+    /// A distance relative to the current height, given in the args
+    ByValue,
+    /// An absolute height given in the args
+    ToValue,
+    LowestNeighborFloor,
+    HighestNeighborFloor,
+    /// The nearest neighboring floor in the direction of movement
+    NearestNeighborFloor,
+    LowestNeighborCeiling,
+    HighestNeighborCeiling,
+    /// The nearest neighboring ceiling in the direction of movement
+    NearestNeighborCeiling,
+    /// The sector's own floor
+    Floor,
+    /// The sector's own ceiling
+    Ceiling,
+    /// 4 units below the lowest neighboring ceiling
+    DoorOpen,
+    /// 8 units above the floor, crushing anything in the way
+    Crush,
+    /// Each sector in the staircase moves by the step height in the args
+    StairStep,
+    /// By the height of the shortest lower texture on the sector's lines
+    ShortestLowerTexture,
+    /// By the height of the shortest upper texture on the sector's lines
+    ShortestUpperTexture,
+    /// The floor of the activating line's front sector
+    ActivatingLineFloor,
+    /// Determined by the special's args
+    Variable,
+}
+
+/// A description of how a `Special` moves sectors, as returned by [Special::motion].
+///
+/// This is meant for simple previews, e.g. animating doors and lifts in an editor: it doesn't capture every detail of
+/// the special's behavior.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Motion {
+    pub plane: MovingPlane,
+    pub direction: MotionDirection,
+    pub target: TargetHeight,
+}
+
+/// How a crushing `Special` behaves when it meets an actor, as its `crushmode` arg
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
+pub enum CrushMode {
+    /// Whichever of the others the game uses by default
+    #[default]
+    Default,
+    /// Keeps moving at full speed through actors, as in Doom
+    Doom,
+    /// Stops until the actor is gone, as in Hexen
+    Hexen,
+    /// Keeps moving through actors, but slows down while crushing them
+    DoomSlowdown,
+}
+
+impl From<CrushMode> for i16 {
+    fn from(crush_mode: CrushMode) -> Self {
+        match crush_mode {
+            CrushMode::Default => 0,
+            CrushMode::Doom => 1,
+            CrushMode::Hexen => 2,
+            CrushMode::DoomSlowdown => 3,
+        }
+    }
+}
+
+impl TryFrom<i16> for CrushMode {
+    type Error = i16;
+
+    fn try_from(n: i16) -> Result<Self, Self::Error> {
+        match n {
+            0 => Ok(CrushMode::Default),
+            1 => Ok(CrushMode::Doom),
+            2 => Ok(CrushMode::Hexen),
+            3 => Ok(CrushMode::DoomSlowdown),
+            _ => Err(n),
+        }
+    }
+}
+
+/// The planes of a sector a `Special` applies to. Only portals can apply to both.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
+pub enum SectorPlane {
+    #[default]
+    Floor,
+    Ceiling,
+    Both,
+}
+
+impl From<SectorPlane> for i16 {
+    fn from(plane: SectorPlane) -> Self {
+        match plane {
+            SectorPlane::Floor => 0,
+            SectorPlane::Ceiling => 1,
+            SectorPlane::Both => 2,
+        }
+    }
+}
+
+impl TryFrom<i16> for SectorPlane {
+    type Error = i16;
+
+    fn try_from(n: i16) -> Result<Self, Self::Error> {
+        match n {
+            0 => Ok(SectorPlane::Floor),
+            1 => Ok(SectorPlane::Ceiling),
+            2 => Ok(SectorPlane::Both),
+            _ => Err(n),
+        }
+    }
+}
+
 // TODO: This should preserve unused args
 /// A special action associated with a `LineDef` or a `Thing`. Can also be called as functions in scripts.
 #[derive(Clone, Debug, Default, PartialEq, Eq, Hash, LineDefSpecial)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
 #[doom_special(DoomSpecial)]
 #[udmf_special(UdmfSpecial)]
 #[trigger_flags(TriggerFlags)]
+#[special_meta(SpecialMeta)]
+#[motion(Motion, MovingPlane, MotionDirection, TargetHeight)]
 pub enum Special {
     #[udmf(0)]
     #[doom(id = 0, args = (), triggers = [])]
@@ -110,28 +394,17 @@ pub enum Special {
     #[udmf(1)]
     PolyobjStartLine { po: i16, mirror: i16, sound: i16 },
 
-    #[udmf(2)]
-    PolyobjRotateLeft {
-        po: i16,
-        speed: i16,
-        // TODO Should be u8
-        angle: i16,
-    },
+    #[udmf(2, args(angle: Angle))]
+    PolyobjRotateLeft { po: i16, speed: i16, angle: Angle },
 
-    #[udmf(3)]
-    PolyobjRotateRight {
-        po: i16,
-        speed: i16,
-        // TODO Should be u8
-        angle: i16,
-    },
+    #[udmf(3, args(angle: Angle))]
+    PolyobjRotateRight { po: i16, speed: i16, angle: Angle },
 
-    #[udmf(4)]
+    #[udmf(4, args(angle: Angle))]
     PolyobjMove {
         po: i16,
         speed: i16,
-        // TODO Should be u8
-        angle: i16,
+        angle: Angle,
         dist: i16,
     },
 
@@ -143,30 +416,27 @@ pub enum Special {
         sound: i16,
     },
 
-    #[udmf(6)]
+    #[udmf(6, args(angle: Angle))]
     PolyobjMoveTimes8 {
         po: i16,
         speed: i16,
-        // TODO Should be u8
-        angle: i16,
+        angle: Angle,
         dist: i16,
     },
 
-    #[udmf(7)]
+    #[udmf(7, args(angle: Angle))]
     PolyobjDoorSwing {
         po: i16,
         speed: i16,
-        // TODO Should be u8
-        angle: i16,
+        angle: Angle,
         delay: i16,
     },
 
-    #[udmf(8)]
+    #[udmf(8, args(angle: Angle))]
     PolyobjDoorSlide {
         po: i16,
         speed: i16,
-        // TODO Should be u8
-        angle: i16,
+        angle: Angle,
         dist: i16,
         delay: i16,
     },
@@ -183,6 +453,7 @@ pub enum Special {
     #[doom(id = 110, args = (tag, 64), triggers = [player_cross])]
     #[doom(id = 113, args = (tag, 64), triggers = [player_use])]
     #[doom(id = 116, args = (tag, 64), triggers = [player_use, repeats])]
+    #[moves(Ceiling, Down, Floor)]
     DoorClose {
         tag: i16,
         speed: i16,
@@ -201,6 +472,7 @@ pub enum Special {
     #[doom(id = 112, args = (tag, 64), triggers = [player_use])]
     #[doom(id = 115, args = (tag, 64), triggers = [player_use, repeats])]
     #[doom(id = 118, args = (0, 64, tag), triggers = [player_use])]
+    #[moves(Ceiling, Up, DoorOpen)]
     DoorOpen {
         tag: i16,
         speed: i16,
@@ -218,6 +490,7 @@ pub enum Special {
     #[doom(id = 111, args = (tag, 64, 150), triggers = [player_use])]
     #[doom(id = 114, args = (tag, 64, 150), triggers = [player_use, repeats])]
     #[doom(id = 117, args = (0, 64, 150, tag), triggers = [player_use, repeats])]
+    #[moves(Ceiling, UpThenDown, DoorOpen)]
     DoorRaise {
         tag: i16,
         speed: i16,
@@ -225,7 +498,7 @@ pub enum Special {
         light_tag: i16,
     },
 
-    #[udmf(13)]
+    #[udmf(13, args(lock: Lock))]
     #[doom(id = 26, args = (0, 16, 150, 130, tag), triggers = [player_use, repeats])]
     #[doom(id = 27, args = (0, 16, 150, 131, tag), triggers = [player_use, repeats])]
     #[doom(id = 28, args = (0, 16, 150, 129, tag), triggers = [player_use, repeats])]
@@ -240,21 +513,22 @@ pub enum Special {
     #[doom(id = 135, args = (tag, 64, 0, 129), triggers = [player_use])]
     #[doom(id = 136, args = (tag, 64, 0, 131), triggers = [player_use, repeats])]
     #[doom(id = 137, args = (tag, 64, 0, 131), triggers = [player_use])]
+    #[moves(Ceiling, UpThenDown, DoorOpen)]
     DoorRaiseLocked {
         tag: i16,
         speed: i16,
         delay: i16,
-        // TODO Should be u8
-        lock: i16,
+        lock: Lock,
         lighttag: i16,
     },
 
-    #[udmf(14)]
+    #[udmf(14, args(lock: Lock))]
+    #[moves(Ceiling, UpThenDown, DoorOpen)]
     DoorAnimated {
         tag: i16,
         speed: i16,
         delay: i16,
-        lock: i16,
+        lock: Lock,
     },
 
     #[udmf(15)]
@@ -267,38 +541,47 @@ pub enum Special {
         flags: i16,
     },
 
-    #[udmf(17)]
-    ThingRaise {
-        tid: i16,
-        // TODO Should be bool
-        nocheck: i16,
-    },
+    #[udmf(17, args(nocheck: bool))]
+    ThingRaise { tid: i16, nocheck: bool },
 
-    #[udmf(18)]
+    #[udmf(18, name = "StartConversation")]
     StartConversation { talker_tid: i16, facetalker: i16 },
 
     #[udmf(19)]
     ThingStop { tid: i16 },
 
-    #[udmf(20)]
-    FloorLowerByValue { tag: i16, speed: i16, height: i16 },
+    #[udmf(20, args(height: Height))]
+    #[moves(Floor, Down, ByValue)]
+    FloorLowerByValue {
+        tag: i16,
+        speed: i16,
+        height: Height,
+    },
 
     #[udmf(21)]
     #[doom(id = 23, args = (tag, 8), triggers = [player_use])]
     #[doom(id = 38, args = (tag, 8), triggers = [player_cross])]
     #[doom(id = 60, args = (tag, 8), triggers = [player_use, repeats])]
     #[doom(id = 82, args = (tag, 8), triggers = [player_cross, repeats])]
+    #[moves(Floor, Down, LowestNeighborFloor)]
     FloorLowerToLowest { tag: i16, speed: i16 },
 
     #[udmf(22)]
+    #[moves(Floor, Down, NearestNeighborFloor)]
     FloorLowerToNearest { tag: i16, speed: i16 },
 
-    #[udmf(23)]
+    #[udmf(23, args(height: Height))]
     #[doom(id = 58, args = (tag, 8, 24), triggers = [player_cross])]
     #[doom(id = 92, args = (tag, 8, 24), triggers = [player_cross, repeats])]
-    FloorRaiseByValue { tag: i16, speed: i16, height: i16 },
+    #[moves(Floor, Up, ByValue)]
+    FloorRaiseByValue {
+        tag: i16,
+        speed: i16,
+        height: Height,
+    },
 
     #[udmf(24)]
+    #[moves(Floor, Up, HighestNeighborFloor)]
     FloorRaiseToHighest { tag: i16, speed: i16 },
 
     #[udmf(25)]
@@ -310,66 +593,77 @@ pub enum Special {
     #[doom(id = 130, args = (tag, 32), triggers = [player_cross])]
     #[doom(id = 131, args = (tag, 32), triggers = [player_use])]
     #[doom(id = 132, args = (tag, 32), triggers = [player_use, repeats])]
+    #[moves(Floor, Up, NearestNeighborFloor)]
     FloorRaiseToNearest { tag: i16, speed: i16 },
 
-    #[udmf(26)]
+    #[udmf(26, args(height: Height))]
+    #[moves(Floor, Down, StairStep)]
     StairsBuildDown {
         tag: i16,
         speed: i16,
-        height: i16,
+        height: Height,
         delay: i16,
         reset: i16,
     },
 
-    #[udmf(27)]
+    #[udmf(27, args(height: Height))]
+    #[moves(Floor, Up, StairStep)]
     StairsBuildUp {
         tag: i16,
         speed: i16,
-        height: i16,
+        height: Height,
         delay: i16,
         reset: i16,
     },
 
-    #[udmf(28)]
+    #[udmf(28, args(crushmode: CrushMode))]
+    #[moves(Floor, Up, Crush)]
     FloorRaiseAndCrush {
         tag: i16,
         speed: i16,
         crush: i16,
-        // TODO Should be bitflags
-        crushmode: i16,
+        crushmode: CrushMode,
     },
 
-    #[udmf(29)]
-    PillarBuild { tag: i16, speed: i16, height: i16 },
+    #[udmf(29, args(height: Height))]
+    #[moves(Both, Together, ByValue)]
+    PillarBuild {
+        tag: i16,
+        speed: i16,
+        height: Height,
+    },
 
-    #[udmf(30)]
+    #[udmf(30, args(fdist: Height, cdist: Height))]
+    #[moves(Both, Apart, ToValue)]
     PillarOpen {
         tag: i16,
         speed: i16,
-        fdist: i16,
-        cdist: i16,
+        fdist: Height,
+        cdist: Height,
     },
 
-    #[udmf(31)]
+    #[udmf(31, args(height: Height))]
+    #[moves(Floor, Down, StairStep)]
     StairsBuildDownSync {
         tag: i16,
         speed: i16,
-        height: i16,
+        height: Height,
         reset: i16,
     },
 
-    #[udmf(32)]
+    #[udmf(32, args(height: Height))]
+    #[moves(Floor, Up, StairStep)]
     StairsBuildUpSync {
         tag: i16,
         speed: i16,
-        height: i16,
+        height: Height,
         reset: i16,
     },
 
-    #[udmf(33)]
+    #[udmf(33, name = "ForceField")]
     ForceField,
 
-    #[udmf(34)]
+    #[udmf(34, name = "ClearForceField")]
     ClearForceField { tag: i16 },
 
     #[udmf(35)]
@@ -380,15 +674,17 @@ pub enum Special {
     FloorLowerByValueTimes8 { tag: i16, speed: i16, height: i16 },
 
     // TODO Check how this is actually used in UDMF - why is the neg argument needed if we can use signed parameters?
-    #[udmf(37)]
+    #[udmf(37, args(height: Height))]
+    #[moves(Floor, Toward, ToValue)]
     FloorMoveToValue {
         tag: i16,
         speed: i16,
-        height: i16,
+        height: Height,
         neg: i16,
     },
 
     #[udmf(38)]
+    #[moves(Ceiling, Oscillate, ByValue)]
     CeilingWaggle {
         tag: i16,
         amp: i16,
@@ -400,74 +696,82 @@ pub enum Special {
     #[udmf(39)]
     TeleportZombieChanger { tid: i16, tag: i16 },
 
-    #[udmf(40)]
-    CeilingLowerByValue { tag: i16, speed: i16, height: i16 },
+    #[udmf(40, args(height: Height))]
+    #[moves(Ceiling, Down, ByValue)]
+    CeilingLowerByValue {
+        tag: i16,
+        speed: i16,
+        height: Height,
+    },
 
-    #[udmf(41)]
-    CeilingRaiseByValue { tag: i16, speed: i16, height: i16 },
+    #[udmf(41, args(height: Height))]
+    #[moves(Ceiling, Up, ByValue)]
+    CeilingRaiseByValue {
+        tag: i16,
+        speed: i16,
+        height: Height,
+    },
 
-    #[udmf(42)]
+    #[udmf(42, args(crushmode: CrushMode))]
+    #[moves(Ceiling, Perpetual, Crush)]
     CeilingCrushAndRaise {
         tag: i16,
         speed: i16,
         crush: i16,
-        // TODO Should be bitflags
-        crushmode: i16,
+        crushmode: CrushMode,
     },
 
-    #[udmf(43)]
+    #[udmf(43, args(crushmode: CrushMode))]
     #[doom(id = 44, args = (tag, 8, 0, 2), triggers = [player_cross])]
     #[doom(id = 72, args = (tag, 8, 0, 2), triggers = [player_cross, repeats])]
+    #[moves(Ceiling, Down, Crush)]
     CeilingLowerAndCrush {
         tag: i16,
         speed: i16,
         crush: i16,
-        // TODO Should be bitflags
-        crushmode: i16,
+        crushmode: CrushMode,
     },
 
     #[udmf(44)]
     #[doom(id = 57, args = (tag), triggers = [player_cross])]
     #[doom(id = 74, args = (tag), triggers = [player_cross, repeats])]
+    #[moves(Ceiling, Stop, None)]
     CeilingCrushStop { tag: i16 },
 
-    #[udmf(45)]
+    #[udmf(45, args(crushmode: CrushMode))]
+    #[moves(Ceiling, DownThenUp, Crush)]
     CeilingCrushRaiseAndStay {
         tag: i16,
         speed: i16,
         crush: i16,
-        // TODO Should be bitflags
-        crushmode: i16,
+        crushmode: CrushMode,
     },
 
     #[udmf(46)]
+    #[moves(Floor, Stop, None)]
     FloorCrushStop { tag: i16 },
 
     // TODO Check how this is actually used in UDMF - why is the neg argument needed if we can use signed parameters?
-    #[udmf(47)]
+    #[udmf(47, args(height: Height))]
+    #[moves(Ceiling, Toward, ToValue)]
     CeilingMoveToValue {
         tag: i16,
         speed: i16,
-        height: i16,
+        height: Height,
         neg: i16,
     },
 
-    #[udmf(48)]
+    #[udmf(48, args(floororceiling: SectorPlane))]
     SectorAttach3dMidtex {
         lineid: i16,
         tag: i16,
-        // TODO Should be enum
-        floororceiling: i16,
+        floororceiling: SectorPlane,
     },
 
-    #[udmf(49)]
-    GlassBreak {
-        // TODO Should be bool
-        dontspawnjunk: i16,
-        _type: i16,
-    },
+    #[udmf(49, args(dontspawnjunk: bool), name = "GlassBreak")]
+    GlassBreak { dontspawnjunk: bool, _type: i16 },
 
-    #[udmf(50)]
+    #[udmf(50, name = "ExtraFloor_LightOnly")]
     ExtraFloorLightOnly {
         tag: i16,
         // TODO Should be enum
@@ -484,25 +788,23 @@ pub enum Special {
         _type: i16,
     },
 
-    #[udmf(52)]
+    #[udmf(52, args(side: bool))]
     ScrollWall {
         lineid: i16,
         x: i16,
         y: i16,
-        // TODO Should be bool
-        side: i16,
+        side: bool,
         // TODO Should be bitflags
         flags: i16,
     },
 
     // NOTE: Cannot be used on a linedef
-    #[udmf(53)]
+    #[udmf(53, args(side: Side))]
     LineSetTextureOffset {
         lineid: i16,
         x: i16,
         y: i16,
-        // TODO Should be enum
-        side: i16,
+        side: Side,
         // TODO Should be bitflags
         flags: i16,
     },
@@ -526,24 +828,22 @@ pub enum Special {
     },
 
     // NOTE: Cannot be used on a linedef
-    #[udmf(56)]
+    #[udmf(56, args(side: Side))]
     LineSetTextureScale {
         lineid: i16,
         x: i16,
         y: i16,
-        // TODO Should be enum
-        side: i16,
+        side: Side,
         // TODO Should be bitflags
         flags: i16,
     },
 
-    #[udmf(57)]
+    #[udmf(57, args(plane: SectorPlane))]
     SectorSetPortal {
         tag: i16,
         // TODO should be enum
         _type: i16,
-        // TODO should be enum
-        plane: i16,
+        plane: SectorPlane,
         misc: i16,
         alpha: i16,
     },
@@ -555,21 +855,25 @@ pub enum Special {
         flags: i16,
     },
 
-    #[udmf(59)]
+    #[udmf(59, name = "Polyobj_OR_MoveToSpot")]
     PolyobjOrMoveToSpot { po: i16, speed: i16, target: i16 },
 
     #[udmf(60)]
+    #[moves(Floor, Perpetual, LowestNeighborFloor)]
     PlatPerpetualRaise { tag: i16, speed: i16, delay: i16 },
 
     #[udmf(61)]
     #[doom(id = 54, args = (tag), triggers = [player_cross])]
     #[doom(id = 89, args = (tag), triggers = [player_cross, repeats])]
+    #[moves(Floor, Stop, None)]
     PlatStop { tag: i16 },
 
     #[udmf(62)]
+    #[moves(Floor, DownThenUp, LowestNeighborFloor)]
     PlatDownWaitUpStay { tag: i16, speed: i16, delay: i16 },
 
     #[udmf(63)]
+    #[moves(Floor, DownThenUp, ByValue)]
     PlatDownByValue {
         tag: i16,
         speed: i16,
@@ -578,9 +882,11 @@ pub enum Special {
     },
 
     #[udmf(64)]
+    #[moves(Floor, UpThenDown, HighestNeighborFloor)]
     PlatUpWaitDownStay { tag: i16, speed: i16, delay: i16 },
 
     #[udmf(65)]
+    #[moves(Floor, UpThenDown, ByValue)]
     PlatUpByValue {
         tag: i16,
         speed: i16,
@@ -589,9 +895,11 @@ pub enum Special {
     },
 
     #[udmf(66)]
+    #[moves(Floor, Down, ByValue)]
     FloorLowerInstant { tag: i16, arg1: i16, height: i16 },
 
     #[udmf(67)]
+    #[moves(Floor, Up, ByValue)]
     FloorRaiseInstant { tag: i16, arg1: i16, height: i16 },
 
     #[udmf(68)]
@@ -610,7 +918,7 @@ pub enum Special {
         neg: i16,
     },
 
-    #[udmf(70)]
+    #[udmf(70, args(nosourcefog: bool))]
     #[doom(id = 39, args = (0, tag), triggers = [player_cross, monsters_activate])]
     #[doom(id = 97, args = (0, tag), triggers = [player_cross, repeats, monsters_activate])]
     #[doom(id = 125, args = (0, tag), triggers = [monster_cross])]
@@ -618,77 +926,64 @@ pub enum Special {
     Teleport {
         tid: i16,
         tag: i16,
-        // TODO Should be bool
-        nosourcefog: i16,
+        nosourcefog: bool,
     },
 
-    #[udmf(71)]
+    #[udmf(71, args(keepheight: bool))]
     TeleportNoFog {
         tid: i16,
         // TODO Should be enum
         useangle: i16,
         tag: i16,
-        // TODO Should be bool
-        keepheight: i16,
+        keepheight: bool,
     },
 
-    #[udmf(72)]
+    #[udmf(72, args(angle: Angle, nolimit: bool), name = "ThrustThing")]
     ThrustThing {
-        // TODO Should be u8
-        angle: i16,
+        angle: Angle,
         force: i16,
-        // TODO Should be bool
-        nolimit: i16,
+        nolimit: bool,
         tid: i16,
     },
 
-    #[udmf(73)]
+    #[udmf(73, name = "DamageThing")]
     DamageThing { amount: i16, _mod: i16 },
 
-    #[udmf(74)]
-    TeleportNewMap {
-        map: i16,
-        pos: i16,
-        // TODO Should be bool
-        face: i16,
-    },
+    #[udmf(74, args(face: bool))]
+    TeleportNewMap { map: i16, pos: i16, face: bool },
 
     #[udmf(75)]
     TeleportEndGame,
 
-    #[udmf(76)]
+    #[udmf(76, args(fog: bool), name = "TeleportOther")]
     TeleportOther {
         tid: i16,
         destination: i16,
-        // TODO Should be bool
-        fog: i16,
+        fog: bool,
     },
 
-    #[udmf(77)]
+    #[udmf(77, args(movesource: bool, fog: bool), name = "TeleportGroup")]
     TeleportGroup {
         tid: i16,
         source: i16,
         destination: i16,
-        // TODO Should be bool
-        movesource: i16,
-        // TODO Should be bool
-        fog: i16,
+        movesource: bool,
+        fog: bool,
     },
 
-    #[udmf(78)]
+    #[udmf(78, args(fog: bool), name = "TeleportInSector")]
     TeleportInSector {
         tag: i16,
         source_tid: i16,
         dest_tid: i16,
-        // TODO Should be bool
-        fog: i16,
+        fog: bool,
         group_tid: i16,
     },
 
     #[udmf(79)]
     ThingSetConversation { tid: i16, convid: i16 },
 
-    #[udmf(80)]
+    #[udmf(80, name = "ACS_Execute")]
     AcsExecute {
         script: i16,
         map: i16,
@@ -697,23 +992,22 @@ pub enum Special {
         s_arg3: i16,
     },
 
-    #[udmf(81)]
+    #[udmf(81, name = "ACS_Suspend")]
     AcsSuspend { script: i16, map: i16 },
 
-    #[udmf(82)]
+    #[udmf(82, name = "ACS_Terminate")]
     AcsTerminate { script: i16, map: i16 },
 
-    #[udmf(83)]
+    #[udmf(83, args(lock: Lock), name = "ACS_LockedExecute")]
     AcsLockedExecute {
         script: i16,
         map: i16,
         s_arg1: i16,
         s_arg2: i16,
-        // TODO Should be u8
-        lock: i16,
+        lock: Lock,
     },
 
-    #[udmf(84)]
+    #[udmf(84, name = "ACS_ExecuteWithResult")]
     AcsExecuteWithResult {
         script: i16,
         s_arg1: i16,
@@ -722,14 +1016,13 @@ pub enum Special {
         s_arg4: i16,
     },
 
-    #[udmf(85)]
+    #[udmf(85, args(lock: Lock), name = "ACS_LockedExecuteDoor")]
     AcsLockedExecuteDoor {
         script: i16,
         map: i16,
         s_arg1: i16,
         s_arg2: i16,
-        // TODO Should be u8
-        lock: i16,
+        lock: Lock,
     },
 
     #[udmf(86)]
@@ -746,7 +1039,7 @@ pub enum Special {
         pos_y: i16,
     },
 
-    #[udmf(89)]
+    #[udmf(89, name = "Polyobj_OR_MoveTo")]
     PolyobjOrMoveTo {
         po: i16,
         speed: i16,
@@ -754,90 +1047,79 @@ pub enum Special {
         pos_y: i16,
     },
 
-    #[udmf(90)]
-    PolyobjOrRotateLeft {
-        po: i16,
-        speed: i16,
-        // TODO Should be u8
-        angle: i16,
-    },
+    #[udmf(90, args(angle: Angle), name = "Polyobj_OR_RotateLeft")]
+    PolyobjOrRotateLeft { po: i16, speed: i16, angle: Angle },
 
-    #[udmf(91)]
-    PolyobjOrRotateRight {
-        po: i16,
-        speed: i16,
-        // TODO Should be u8
-        angle: i16,
-    },
+    #[udmf(91, args(angle: Angle), name = "Polyobj_OR_RotateRight")]
+    PolyobjOrRotateRight { po: i16, speed: i16, angle: Angle },
 
-    #[udmf(92)]
+    #[udmf(92, args(angle: Angle), name = "Polyobj_OR_Move")]
     PolyobjOrMove {
         po: i16,
         speed: i16,
-        // TODO Should be u8
-        angle: i16,
+        angle: Angle,
         dist: i16,
     },
 
-    #[udmf(93)]
+    #[udmf(93, args(angle: Angle), name = "Polyobj_OR_MoveTimes8")]
     PolyobjOrMoveTimes8 {
         po: i16,
         speed: i16,
-        // TODO Should be u8
-        angle: i16,
+        angle: Angle,
         dist: i16,
     },
 
-    #[udmf(94)]
+    #[udmf(94, args(crushmode: CrushMode, height: Height))]
+    #[moves(Both, Together, ByValue)]
     PillarBuildAndCrush {
         tag: i16,
         speed: i16,
-        height: i16,
+        height: Height,
         crush: i16,
-        // TODO Should be enum
-        crushmode: i16,
+        crushmode: CrushMode,
     },
 
-    #[udmf(95)]
+    #[udmf(95, name = "FloorAndCeiling_LowerByValue")]
+    #[moves(Both, Down, ByValue)]
     FloorAndCeilingLowerByValue { tag: i16, speed: i16, value: i16 },
 
-    #[udmf(96)]
+    #[udmf(96, name = "FloorAndCeiling_RaiseByValue")]
+    #[moves(Both, Up, ByValue)]
     FloorAndCeilingRaiseByValue { tag: i16, speed: i16, value: i16 },
 
-    #[udmf(97)]
+    #[udmf(97, args(crushmode: CrushMode, dist: Height))]
+    #[moves(Ceiling, Down, Crush)]
     CeilingLowerAndCrushDist {
         tag: i16,
         speed: i16,
         crush: i16,
-        dist: i16,
-        // TODO Should be enum
-        crushmode: i16,
+        dist: Height,
+        crushmode: CrushMode,
     },
 
-    #[udmf(98)]
+    #[udmf(98, args(plane: SectorPlane))]
     SectorSetTranslucent {
         tag: i16,
-        // TODO Should be enum
-        plane: i16,
+        plane: SectorPlane,
         amount: i16,
         // TODO Should be enum
         _type: i16,
     },
 
-    #[udmf(99)]
+    #[udmf(99, args(crushmode: CrushMode))]
     #[doom(id = 55, args = (tag, 8, 10, 2), triggers = [player_use])]
     #[doom(id = 56, args = (tag, 8, 10, 2), triggers = [player_cross])]
     #[doom(id = 65, args = (tag, 8, 10, 2), triggers = [player_use, repeats])]
     #[doom(id = 94, args = (tag, 8, 10, 2), triggers = [player_cross, repeats])]
+    #[moves(Floor, Up, Crush)]
     FloorRaiseAndCrushDoom {
         tag: i16,
         speed: i16,
         crush: i16,
-        // TODO Should be enum
-        crushmode: i16,
+        crushmode: CrushMode,
     },
 
-    #[udmf(100)]
+    #[udmf(100, name = "Scroll_Texture_Left")]
     #[doom(id = 48, args = (64), triggers = [])]
     ScrollTextureLeft {
         speed: i16,
@@ -845,39 +1127,40 @@ pub enum Special {
         flags: i16,
     },
 
-    #[udmf(101)]
+    #[udmf(101, name = "Scroll_Texture_Right")]
     ScrollTextureRight {
         speed: i16,
         // TODO Should be bitflags
         flags: i16,
     },
 
-    #[udmf(102)]
+    #[udmf(102, name = "Scroll_Texture_Up")]
     ScrollTextureUp {
         speed: i16,
         // TODO Should be bitflags
         flags: i16,
     },
 
-    #[udmf(103)]
+    #[udmf(103, name = "Scroll_Texture_Down")]
     ScrollTextureDown {
         speed: i16,
         // TODO Should be bitflags
         flags: i16,
     },
 
-    #[udmf(104)]
+    #[udmf(104, args(crushmode: CrushMode, dist: Height))]
     #[doom(id = 141, args = (tag, 8, 8, 10), triggers = [player_cross])]
+    #[moves(Ceiling, Perpetual, Crush)]
     CeilingCrushAndRaiseSilentDist {
         tag: i16,
-        dist: i16,
+        dist: Height,
         speed: i16,
         crush: i16,
-        // TODO Should be enum
-        crushmode: i16,
+        crushmode: CrushMode,
     },
 
     #[udmf(105)]
+    #[moves(Ceiling, UpThenDown, DoorOpen)]
     DoorWaitRaise {
         tag: i16,
         speed: i16,
@@ -887,6 +1170,7 @@ pub enum Special {
     },
 
     #[udmf(106)]
+    #[moves(Ceiling, Down, Floor)]
     DoorWaitClose {
         tag: i16,
         speed: i16,
@@ -981,13 +1265,8 @@ pub enum Special {
     // UDMF 123 - unused
     // UDMF 124 - unused
     //
-    #[udmf(125)]
-    ThingMove {
-        tid: i16,
-        destid: i16,
-        // TODO Should be bool
-        nofog: i16,
-    },
+    #[udmf(125, args(nofog: bool))]
+    ThingMove { tid: i16, destid: i16, nofog: bool },
     //
     // UDMF 126 - unused
     //
@@ -1000,7 +1279,7 @@ pub enum Special {
         arg2: i16,
     },
 
-    #[udmf(128)]
+    #[udmf(128, name = "ThrustThingZ")]
     ThrustThingZ {
         tid: i16,
         force: i16,
@@ -1010,7 +1289,7 @@ pub enum Special {
         setadd: i16,
     },
 
-    #[udmf(129)]
+    #[udmf(129, name = "UsePuzzleItem")]
     UsePuzzleItem {
         // TODO Should be enum
         item: i16,
@@ -1029,53 +1308,45 @@ pub enum Special {
     #[udmf(132)]
     ThingRemove { tid: i16 },
 
-    #[udmf(133)]
-    ThingDestroy {
-        tid: i16,
-        // TODO Should be bool
-        extreme: i16,
-        tag: i16,
-    },
+    #[udmf(133, args(extreme: bool))]
+    ThingDestroy { tid: i16, extreme: bool, tag: i16 },
 
-    #[udmf(134)]
+    #[udmf(134, args(angle: Angle))]
     ThingProjectile {
         tid: i16,
         _type: i16,
-        // TODO Should be u8
-        angle: i16,
+        angle: Angle,
         speed: i16,
         vspeed: i16,
     },
 
-    #[udmf(135)]
+    #[udmf(135, args(angle: Angle))]
     ThingSpawn {
         tid: i16,
         _type: i16,
-        // TODO Should be u8
-        angle: i16,
+        angle: Angle,
         newtid: i16,
     },
 
-    #[udmf(136)]
+    #[udmf(136, args(angle: Angle))]
     ThingProjectileGravity {
         tid: i16,
         _type: i16,
-        // TODO Should be u8
-        angle: i16,
+        angle: Angle,
         speed: i16,
         vspeed: i16,
     },
 
-    #[udmf(137)]
+    #[udmf(137, args(angle: Angle))]
     ThingSpawnNoFog {
         tid: i16,
         _type: i16,
-        // TODO Should be u8
-        angle: i16,
+        angle: Angle,
         newtid: i16,
     },
 
     #[udmf(138)]
+    #[moves(Floor, Oscillate, ByValue)]
     FloorWaggle {
         tag: i16,
         amp: i16,
@@ -1084,12 +1355,11 @@ pub enum Special {
         time: i16,
     },
 
-    #[udmf(139)]
+    #[udmf(139, args(nofog: bool))]
     ThingSpawnFacing {
         tid: i16,
         _type: i16,
-        // TODO Should be bool
-        nofog: i16,
+        nofog: bool,
         newtid: i16,
     },
 
@@ -1114,21 +1384,19 @@ pub enum Special {
     #[udmf(152)]
     TeamScore { points: i16, nogrin: i16 },
 
-    #[udmf(153)]
+    #[udmf(153, args(announce: bool))]
     TeamGivePoints {
         // TODO Should be enum
         team: i16,
         points: i16,
-        // TODO Should be bool
-        announce: i16,
+        announce: bool,
     },
 
-    #[udmf(154)]
+    #[udmf(154, args(nofog: bool))]
     TeleportNoStop {
         tid: i16,
         sectortag: i16,
-        // TODO Should be bool
-        nofog: i16,
+        nofog: bool,
     },
     //
     // UDMF 155 - unused
@@ -1143,14 +1411,14 @@ pub enum Special {
         planeanchor: i16,
     },
 
-    #[udmf(157)]
+    #[udmf(157, name = "SetGlobalFogParameter")]
     SetGlobalFogParameter {
         // TODO Should be enum
         property: i16,
         value: i16,
     },
 
-    #[udmf(158)]
+    #[udmf(158, name = "FS_Execute")]
     FsExecute {
         scriptnumber: i16,
         // TODO Should be enum
@@ -1160,24 +1428,17 @@ pub enum Special {
         message: i16,
     },
 
-    #[udmf(159)]
-    SectorSetPlaneReflection {
-        tag: i16,
-        // TODO Should be u8
-        floor: i16,
-        // TODO Should be u8
-        ceiling: i16,
-    },
+    #[udmf(159, args(floor: u8, ceiling: u8))]
+    SectorSetPlaneReflection { tag: i16, floor: u8, ceiling: u8 },
 
-    #[udmf(160)]
+    #[udmf(160, args(alpha: u8))]
     SectorSet3dFloor {
         tag: i16,
         // TODO Should be bitflags
         _type: i16,
         // TODO Should be bitflags
         flags: i16,
-        // TODO Should be u8
-        alpha: i16,
+        alpha: u8,
         // TODO Should be something else - perhaps enum?
         hitag_lineid: i16,
     },
@@ -1196,23 +1457,22 @@ pub enum Special {
     // UDMF 166 - unused
     // UDMF 167 - unused
     //
-    #[udmf(168)]
+    #[udmf(168, args(crushmode: CrushMode, dist: Height))]
+    #[moves(Ceiling, Perpetual, Crush)]
     CeilingCrushAndRaiseDist {
         tag: i16,
-        dist: i16,
+        dist: Height,
         speed: i16,
         crush: i16,
-        // TODO Should be enum
-        crushmode: i16,
+        crushmode: CrushMode,
     },
 
-    #[udmf(169)]
+    #[udmf(169, args(silent: bool))]
     GenericCrusher2 {
         tag: i16,
         dspeed: i16,
         uspeed: i16,
-        // TODO Should be bool
-        silent: i16,
+        silent: bool,
         crush: i16,
     },
 
@@ -1237,32 +1497,30 @@ pub enum Special {
     },
 
     #[udmf(172)]
+    #[moves(Floor, UpThenDown, NearestNeighborFloor)]
     PlatUpNearestWaitDownStay { tag: i16, speed: i16, delay: i16 },
 
-    #[udmf(173)]
+    #[udmf(173, name = "NoiseAlert")]
     NoiseAlert { target_tid: i16, emitter_tid: i16 },
 
-    #[udmf(174)]
+    #[udmf(174, args(front_only: bool, nolog: bool), name = "SendToCommunicator")]
     SendToCommunicator {
         voc_id: i16,
-        // TODO Should be bool
-        front_only: i16,
+        front_only: bool,
         identify: i16,
-        // TODO Should be bool
-        nolog: i16,
+        nolog: bool,
     },
 
-    #[udmf(175)]
+    #[udmf(175, args(speed: u8))]
     ThingProjectileIntercept {
         tid: i16,
         _type: i16,
-        // TODO Should be u8
-        speed: i16,
+        speed: u8,
         target: i16,
         newtid: i16,
     },
 
-    #[udmf(176)]
+    #[udmf(176, name = "Thing_ChangeTID")]
     ThingChangeTid { oldtid: i16, newtid: i16 },
 
     #[udmf(177)]
@@ -1282,7 +1540,7 @@ pub enum Special {
         newtid: i16,
     },
 
-    #[udmf(179)]
+    #[udmf(179, name = "ChangeSkill")]
     ChangeSkill {
         // TODO Should be enum
         skill: i16,
@@ -1303,19 +1561,11 @@ pub enum Special {
     #[udmf(182)]
     LineMirror,
 
-    #[udmf(183)]
-    LineAlignCeiling {
-        lineid: i16,
-        // TODO Should be enum
-        side: i16,
-    },
+    #[udmf(183, args(side: Side))]
+    LineAlignCeiling { lineid: i16, side: Side },
 
-    #[udmf(184)]
-    LineAlignFloor {
-        lineid: i16,
-        // TODO Should be enum
-        side: i16,
-    },
+    #[udmf(184, args(side: Side))]
+    LineAlignFloor { lineid: i16, side: Side },
 
     #[udmf(185)]
     SectorSetRotation { tag: i16, floor: i16, ceiling: i16 },
@@ -1365,7 +1615,7 @@ pub enum Special {
         movetype: i16,
     },
 
-    #[udmf(191)]
+    #[udmf(191, name = "SetPlayerProperty")]
     SetPlayerProperty {
         // TODO Should be enum
         who: i16,
@@ -1376,42 +1626,45 @@ pub enum Special {
     },
 
     #[udmf(192)]
+    #[moves(Ceiling, Down, HighestNeighborFloor)]
     CeilingLowerToHighestFloor { tag: i16, speed: i16 },
 
     #[udmf(193)]
+    #[moves(Ceiling, Down, ByValue)]
     CeilingLowerInstant { tag: i16, arg1: i16, height: i16 },
 
     #[udmf(194)]
+    #[moves(Ceiling, Up, ByValue)]
     CeilingRaiseInstant { tag: i16, arg1: i16, height: i16 },
 
-    #[udmf(195)]
+    #[udmf(195, args(crushmode: CrushMode))]
+    #[moves(Ceiling, DownThenUp, Crush)]
     CeilingCrushRaiseAndStayA {
         tag: i16,
         dspeed: i16,
         uspeed: i16,
         crush: i16,
-        // TODO Should be enum
-        crushmode: i16,
+        crushmode: CrushMode,
     },
 
-    #[udmf(196)]
+    #[udmf(196, args(crushmode: CrushMode))]
+    #[moves(Ceiling, Perpetual, Crush)]
     CeilingCrushAndRaiseA {
         tag: i16,
         dspeed: i16,
         uspeed: i16,
         crush: i16,
-        // TODO Should be enum
-        crushmode: i16,
+        crushmode: CrushMode,
     },
 
-    #[udmf(197)]
+    #[udmf(197, args(crushmode: CrushMode))]
+    #[moves(Ceiling, Perpetual, Crush)]
     CeilingCrushAndRaiseSilentA {
         tag: i16,
         dspeed: i16,
         uspeed: i16,
         crush: i16,
-        // TODO Should be enum
-        crushmode: i16,
+        crushmode: CrushMode,
     },
 
     #[udmf(198)]
@@ -1421,6 +1674,7 @@ pub enum Special {
     CeilingLowerByValueTimes8 { tag: i16, speed: i16, height: i16 },
 
     #[udmf(200)]
+    #[moves(Floor, Variable, Variable)]
     GenericFloor {
         tag: i16,
         speed: i16,
@@ -1432,6 +1686,7 @@ pub enum Special {
     },
 
     #[udmf(201)]
+    #[moves(Ceiling, Variable, Variable)]
     GenericCeiling {
         tag: i16,
         speed: i16,
@@ -1442,17 +1697,19 @@ pub enum Special {
         flags: i16,
     },
 
-    #[udmf(202)]
+    #[udmf(202, args(lock: Lock))]
+    #[moves(Ceiling, Variable, DoorOpen)]
     GenericDoor {
         tag: i16,
         speed: i16,
         // TODO Should be enum + bitflags
         kind: i16,
         delay: i16,
-        lock: i16,
+        lock: Lock,
     },
 
     #[udmf(203)]
+    #[moves(Floor, DownThenUp, LowestNeighborFloor)]
     GenericLift {
         tag: i16,
         speed: i16,
@@ -1463,6 +1720,7 @@ pub enum Special {
     },
 
     #[udmf(204)]
+    #[moves(Floor, Variable, StairStep)]
     GenericStairs {
         tag: i16,
         speed: i16,
@@ -1472,13 +1730,13 @@ pub enum Special {
         reset: i16,
     },
 
-    #[udmf(205)]
+    #[udmf(205, args(silent: bool))]
+    #[moves(Ceiling, Perpetual, Crush)]
     GenericCrusher {
         tag: i16,
         dspeed: i16,
         uspeed: i16,
-        // TODO Should be bool
-        silent: i16,
+        silent: bool,
         crush: i16,
     },
 
@@ -1491,6 +1749,7 @@ pub enum Special {
     #[doom(id = 121, args = (tag, 64, 105, 0), triggers = [player_cross])]
     #[doom(id = 122, args = (tag, 64, 105, 0), triggers = [player_use])]
     #[doom(id = 123, args = (tag, 64, 105, 0), triggers = [player_use, repeats])]
+    #[moves(Floor, DownThenUp, LowestNeighborFloor)]
     PlatDownWaitUpStayLip {
         tag: i16,
         speed: i16,
@@ -1503,6 +1762,7 @@ pub enum Special {
     #[udmf(207)]
     #[doom(id = 53, args = (tag, 8, 105, 0), triggers = [player_cross])]
     #[doom(id = 87, args = (tag, 8, 105, 0), triggers = [player_cross, repeats])]
+    #[moves(Floor, Perpetual, LowestNeighborFloor)]
     PlatPerpetualRaiseLip {
         tag: i16,
         speed: i16,
@@ -1510,12 +1770,11 @@ pub enum Special {
         lip: i16,
     },
 
-    #[udmf(208)]
+    #[udmf(208, args(additive: bool), name = "TranslucentLine")]
     TranslucentLine {
         lineid: i16,
         amount: i16,
-        // TODO Should be bool
-        additive: i16,
+        additive: bool,
         // TODO Should be bitflags
         moreflags: i16,
     },
@@ -1533,29 +1792,17 @@ pub enum Special {
     #[udmf(211)]
     TransferCeilingLight { tag: i16 },
 
-    #[udmf(212)]
+    #[udmf(212, args(r: u8, g: u8, b: u8, desat: u8))]
     SectorSetColor {
         tag: i16,
-        // TODO Should be u8
-        r: i16,
-        // TODO Should be u8
-        g: i16,
-        // TODO Should be u8
-        b: i16,
-        // TODO Should be u8
-        desat: i16,
+        r: u8,
+        g: u8,
+        b: u8,
+        desat: u8,
     },
 
-    #[udmf(213)]
-    SectorSetFade {
-        tag: i16,
-        // TODO Should be u8
-        r: i16,
-        // TODO Should be u8
-        g: i16,
-        // TODO Should be u8
-        b: i16,
-    },
+    #[udmf(213, args(r: u8, g: u8, b: u8))]
+    SectorSetFade { tag: i16, r: u8, g: u8, b: u8 },
 
     #[udmf(214)]
     SectorSetDamage {
@@ -1567,12 +1814,11 @@ pub enum Special {
         leaky: i16,
     },
 
-    #[udmf(215)]
+    #[udmf(215, args(flip: bool))]
     TeleportLine {
         thisid: i16,
         destid: i16,
-        // TODO Should be bool
-        flip: i16,
+        flip: bool,
     },
 
     #[udmf(216)]
@@ -1581,6 +1827,7 @@ pub enum Special {
     #[udmf(217)]
     #[doom(id = 7, args = (tag, 2, 8), triggers = [player_use])]
     #[doom(id = 8, args = (tag, 2, 8), triggers = [player_cross])]
+    #[moves(Floor, Up, StairStep)]
     StairsBuildUpDoom {
         tag: i16,
         speed: i16,
@@ -1589,13 +1836,12 @@ pub enum Special {
         reset: i16,
     },
 
-    #[udmf(218)]
+    #[udmf(218, args(useline: bool))]
     SectorSetWind {
         tag: i16,
         amount: i16,
         angle: i16,
-        // TODO Should be bool
-        useline: i16,
+        useline: bool,
     },
 
     #[udmf(219)]
@@ -1604,16 +1850,15 @@ pub enum Special {
         amount: i16,
     },
 
-    #[udmf(220)]
+    #[udmf(220, args(useline: bool))]
     SectorSetCurrent {
         tag: i16,
         amount: i16,
         angle: i16,
-        // TODO Should be bool
-        useline: i16,
+        useline: bool,
     },
 
-    #[udmf(221)]
+    #[udmf(221, name = "Scroll_Texture_Both")]
     ScrollTextureBoth {
         lineid: i16,
         left: i16,
@@ -1622,7 +1867,7 @@ pub enum Special {
         up: i16,
     },
 
-    #[udmf(222)]
+    #[udmf(222, name = "Scroll_Texture_Model")]
     ScrollTextureModel {
         lineid: i16,
         // TODO Should be bitfield
@@ -1652,13 +1897,13 @@ pub enum Special {
         y_move: i16,
     },
 
-    #[udmf(225)]
+    #[udmf(225, name = "Scroll_Texture_Offsets")]
     ScrollTextureOffsets {
         // TODO Should be bitflags
         flags: i16,
     },
 
-    #[udmf(226)]
+    #[udmf(226, name = "ACS_ExecuteAlways")]
     AcsExecuteAlways {
         script: i16,
         map: i16,
@@ -1667,13 +1912,12 @@ pub enum Special {
         s_arg3: i16,
     },
 
-    #[udmf(227)]
+    #[udmf(227, args(useline: bool), name = "PointPush_SetForce")]
     PointPushSetForce {
         tag: i16,
         tid: i16,
         amount: i16,
-        // TODO Should be bool
-        useline: i16,
+        useline: bool,
     },
 
     #[udmf(228)]
@@ -1689,13 +1933,12 @@ pub enum Special {
         lockout: i16,
     },
 
-    #[udmf(229)]
+    #[udmf(229, args(dontchasetarget: bool))]
     ThingSetGoal {
         tid: i16,
         goal: i16,
         delay: i16,
-        // TODO Should be bool
-        dontchasetarget: i16,
+        dontchasetarget: bool,
     },
 
     #[udmf(230)]
@@ -1703,9 +1946,11 @@ pub enum Special {
     #[doom(id = 15, args = (tag, 4, 3), triggers = [player_use])]
     #[doom(id = 66, args = (tag, 4, 3), triggers = [player_use, repeats])]
     #[doom(id = 67, args = (tag, 4, 4), triggers = [player_use, repeats])]
+    #[moves(Floor, Up, ByValue)]
     PlatUpByValueStayTx { tag: i16, speed: i16, height: i16 },
 
     #[udmf(231)]
+    #[moves(Floor, Toggle, Ceiling)]
     PlatToggleCeiling { tag: i16 },
 
     #[udmf(232)]
@@ -1727,13 +1972,12 @@ pub enum Special {
     #[udmf(236)]
     FloorTransferNumeric { tag: i16 },
 
-    #[udmf(237)]
+    #[udmf(237, args(revert: bool), name = "ChangeCamera")]
     ChangeCamera {
         tid: i16,
         // TODO Should be enum
         who: i16,
-        // TODO Should be bool
-        revert: i16,
+        revert: bool,
     },
 
     #[udmf(238)]
@@ -1742,21 +1986,25 @@ pub enum Special {
     #[doom(id = 64, args = (tag, 8), triggers = [player_use, repeats])]
     #[doom(id = 91, args = (tag, 8), triggers = [player_cross, repeats])]
     #[doom(id = 101, args = (tag, 8), triggers = [player_use])]
+    #[moves(Floor, Up, LowestNeighborCeiling)]
     FloorRaiseToLowestCeiling { tag: i16, speed: i16 },
 
     #[udmf(239)]
     #[doom(id = 59, args = (tag, 8, 24), triggers = [player_cross])]
     #[doom(id = 93, args = (tag, 8, 24), triggers = [player_cross, repeats])]
+    #[moves(Floor, Up, ByValue)]
     FloorRaiseByValueTxTy { tag: i16, speed: i16, height: i16 },
 
     #[udmf(240)]
     #[doom(id = 30, args = (tag, 8), triggers = [player_cross])]
     #[doom(id = 96, args = (tag, 8), triggers = [player_cross, repeats])]
+    #[moves(Floor, Up, ShortestLowerTexture)]
     FloorRaiseByTexture { tag: i16, speed: i16 },
 
     #[udmf(241)]
     #[doom(id = 37, args = (tag, 8), triggers = [player_cross])]
     #[doom(id = 84, args = (tag, 8), triggers = [player_cross, repeats])]
+    #[moves(Floor, Down, LowestNeighborFloor)]
     FloorLowerToLowestTxTy { tag: i16, speed: i16 },
 
     #[udmf(242)]
@@ -1768,6 +2016,7 @@ pub enum Special {
     #[doom(id = 83, args = (tag, 8, 128), triggers = [player_cross, repeats])]
     #[doom(id = 98, args = (tag, 32, 136), triggers = [player_cross, repeats])]
     #[doom(id = 102, args = (tag, 8, 128), triggers = [player_use])]
+    #[moves(Floor, Down, HighestNeighborFloor)]
     FloorLowerToHighest {
         tag: i16,
         speed: i16,
@@ -1786,20 +2035,24 @@ pub enum Special {
     ExitSecret { pos: i16 },
 
     #[udmf(245)]
+    #[moves(Both, Up, NearestNeighborFloor)]
     ElevatorRaiseToNearest { tag: i16, speed: i16 },
 
     #[udmf(246)]
+    #[moves(Both, Toward, ActivatingLineFloor)]
     ElevatorMoveToFloor { tag: i16, speed: i16 },
 
     #[udmf(247)]
+    #[moves(Both, Down, NearestNeighborFloor)]
     ElevatorLowerToNearest { tag: i16, speed: i16 },
 
-    #[udmf(248)]
+    #[udmf(248, name = "HealThing")]
     HealThing { amount: i16, max: i16 },
 
     #[udmf(249)]
     #[doom(id = 16, args = (tag, 16, 240), triggers = [player_cross])]
     #[doom(id = 76, args = (tag, 16, 240), triggers = [player_cross, repeats])]
+    #[moves(Ceiling, DownThenUp, Floor)]
     DoorCloseWaitOpen {
         tag: i16,
         speed: i16,
@@ -1811,7 +2064,8 @@ pub enum Special {
     #[doom(id = 9, args = (tag, 4, 4), triggers = [player_use])]
     FloorDonut { ptag: i16, pspeed: i16, sspeed: i16 },
 
-    #[udmf(251)]
+    #[udmf(251, name = "FloorAndCeiling_LowerRaise")]
+    #[moves(Both, Apart, ByValue)]
     FloorAndCeilingLowerRaise {
         tag: i16,
         fspeed: i16,
@@ -1821,36 +2075,43 @@ pub enum Special {
     },
 
     #[udmf(252)]
+    #[moves(Ceiling, Up, NearestNeighborCeiling)]
     CeilingRaiseToNearest { tag: i16, speed: i16 },
 
     #[udmf(253)]
+    #[moves(Ceiling, Down, LowestNeighborCeiling)]
     CeilingLowerToLowest { tag: i16, speed: i16 },
 
     #[udmf(254)]
     #[doom(id = 41, args = (tag, 8), triggers = [player_use])]
     #[doom(id = 43, args = (tag, 8), triggers = [player_use, repeats])]
+    #[moves(Ceiling, Down, Floor)]
     CeilingLowerToFloor { tag: i16, speed: i16 },
 
-    #[udmf(255)]
+    #[udmf(255, args(crushmode: CrushMode))]
+    #[moves(Ceiling, DownThenUp, Crush)]
     CeilingCrushRaiseAndStaySilA {
         tag: i16,
         dspeed: i16,
         uspeed: i16,
         crush: i16,
-        // TODO Should be enum
-        crusmode: i16,
+        crushmode: CrushMode,
     },
 
     #[udmf(256)]
+    #[moves(Floor, Down, HighestNeighborFloor)]
     FloorLowerToHighestEE { tag: i16, speed: i16, change: i16 },
 
     #[udmf(257)]
+    #[moves(Floor, Up, LowestNeighborFloor)]
     FloorRaiseToLowest { tag: i16, change: i16, crush: i16 },
 
     #[udmf(258)]
+    #[moves(Floor, Down, LowestNeighborCeiling)]
     FloorLowerToLowestCeiling { tag: i16, speed: i16, change: i16 },
 
     #[udmf(259)]
+    #[moves(Floor, Up, Ceiling)]
     FloorRaiseToCeiling {
         tag: i16,
         speed: i16,
@@ -1860,6 +2121,7 @@ pub enum Special {
     },
 
     #[udmf(260)]
+    #[moves(Floor, Up, Ceiling)]
     FloorToCeilingInstant {
         tag: i16,
         change: i16,
@@ -1868,6 +2130,7 @@ pub enum Special {
     },
 
     #[udmf(261)]
+    #[moves(Floor, Down, ShortestLowerTexture)]
     FloorLowerByTexture {
         tag: i16,
         speed: i16,
@@ -1876,12 +2139,15 @@ pub enum Special {
     },
 
     #[udmf(262)]
+    #[moves(Ceiling, Up, HighestNeighborCeiling)]
     CeilingRaiseToHighest { tag: i16, speed: i16, change: i16 },
 
     #[udmf(263)]
+    #[moves(Ceiling, Up, HighestNeighborCeiling)]
     CeilingToHighestInstant { tag: i16, change: i16, crush: i16 },
 
     #[udmf(264)]
+    #[moves(Ceiling, Down, NearestNeighborCeiling)]
     CeilingLowerToNearest {
         tag: i16,
         speed: i16,
@@ -1890,12 +2156,15 @@ pub enum Special {
     },
 
     #[udmf(265)]
+    #[moves(Ceiling, Up, LowestNeighborCeiling)]
     CeilingRaiseToLowest { tag: i16, speed: i16, change: i16 },
 
     #[udmf(266)]
+    #[moves(Ceiling, Up, HighestNeighborFloor)]
     CeilingRaiseToHighestFloor { tag: i16, speed: i16, change: i16 },
 
     #[udmf(267)]
+    #[moves(Ceiling, Down, Floor)]
     CeilingToFloorInstant {
         tag: i16,
         change: i16,
@@ -1904,9 +2173,11 @@ pub enum Special {
     },
 
     #[udmf(268)]
+    #[moves(Ceiling, Up, ShortestUpperTexture)]
     CeilingRaiseByTexture { tag: i16, speed: i16, change: i16 },
 
     #[udmf(269)]
+    #[moves(Ceiling, Down, ShortestUpperTexture)]
     CeilingLowerByTexture {
         tag: i16,
         speed: i16,
@@ -1915,6 +2186,7 @@ pub enum Special {
     },
 
     #[udmf(270)]
+    #[moves(Floor, Down, StairStep)]
     StairsBuildDownDoom {
         tag: i16,
         speed: i16,
@@ -1924,6 +2196,7 @@ pub enum Special {
     },
 
     #[udmf(271)]
+    #[moves(Floor, Up, StairStep)]
     StairsBuildUpDoomSync {
         tag: i16,
         speed: i16,
@@ -1932,6 +2205,7 @@ pub enum Special {
     },
 
     #[udmf(272)]
+    #[moves(Floor, Down, StairStep)]
     StairsBuildDownDoomSync {
         tag: i16,
         speed: i16,
@@ -1942,6 +2216,7 @@ pub enum Special {
     #[udmf(273)]
     #[doom(id = 100, args = (tag, 32, 16, 0, 0), triggers = [player_cross])]
     #[doom(id = 127, args = (tag, 32, 16, 0, 0), triggers = [player_use])]
+    #[moves(Floor, Up, StairStep)]
     StairsBuildUpDoomCrush {
         tag: i16,
         speed: i16,
@@ -1950,60 +2225,96 @@ pub enum Special {
         reset: i16,
     },
 
-    #[udmf(274)]
+    #[udmf(274, name = "Door_AnimatedClose")]
+    #[moves(Ceiling, Down, Floor)]
     DoorAnmatedClose { tag: i16, speed: i16 },
 
     #[udmf(275)]
+    #[moves(Floor, Stop, None)]
     FloorStop { tag: i16 },
 
     #[udmf(276)]
+    #[moves(Ceiling, Stop, None)]
     CeilingStop { tag: i16 },
 
-    #[udmf(277)]
+    #[udmf(277, args(r: u8, g: u8, b: u8))]
     SectorSetFloorGlow {
         tag: i16,
         height: i16,
-        // TODO Should be u8
-        r: i16,
-        // TODO Should be u8
-        g: i16,
-        // TODO Should be u8
-        b: i16,
+        r: u8,
+        g: u8,
+        b: u8,
     },
 
-    #[udmf(278)]
+    #[udmf(278, args(r: u8, g: u8, b: u8))]
     SectorSetCeilingGlow {
         tag: i16,
         height: i16,
-        // TODO Should be u8
-        r: i16,
-        // TODO Should be u8
-        g: i16,
-        // TODO Should be u8
-        b: i16,
+        r: u8,
+        g: u8,
+        b: u8,
     },
 
-    #[udmf(279)]
+    #[udmf(279, args(crushmode: CrushMode))]
+    #[moves(Floor, Toward, ToValue)]
     FloorMoveToValueAndCrush {
         tag: i16,
         speed: i16,
         height: i16,
         crush: i16,
-        // TODO Should be enum
-        crushmode: i16,
+        crushmode: CrushMode,
     },
 
-    #[udmf(280)]
+    #[udmf(280, args(crushmode: CrushMode))]
+    #[moves(Ceiling, Toward, ToValue)]
     CeilingMoveToValueAndCrush {
         tag: i16,
         speed: i16,
         height: i16,
         crush: i16,
-        // TODO Should be enum
-        crushmode: i16,
+        crushmode: CrushMode,
     },
 }
 
+/// A description of a `Special`, as listed in `Special::ALL_METADATA`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SpecialMeta {
+    pub udmf_value: i16,
+    /// The name used by ZDoom and ACC, as in `Special::name`
+    pub name: &'static str,
+    /// The names of the args, as in `Special::arg_names`
+    pub arg_names: &'static [&'static str],
+    /// The Doom specials which convert to this special. See `Special::doom_constant_args` for the args they set.
+    pub doom_values: &'static [i16],
+}
+
+impl SpecialMeta {
+    pub fn arg_count(&self) -> usize {
+        self.arg_names.len()
+    }
+
+    /// The metadata of the special with the given UDMF value
+    pub fn find(udmf_value: i16) -> Option<&'static SpecialMeta> {
+        Special::ALL_METADATA
+            .iter()
+            .find(|meta| meta.udmf_value == udmf_value)
+    }
+}
+
+/// A special name which doesn't match any `Special`
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+#[error("{0} is not a recognized linedef special")]
+pub struct UnknownSpecialName(pub String);
+
+impl FromStr for Special {
+    type Err = UnknownSpecialName;
+
+    /// Look up a special by its name, ignoring case, with all of its args set to 0
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Special::from_name(s).ok_or_else(|| UnknownSpecialName(s.to_string()))
+    }
+}
+
 /// A `Special` representation in the UDMF format
 #[derive(PartialEq, Eq, Debug, Clone, Copy)]
 pub struct UdmfSpecial {
@@ -2015,6 +2326,16 @@ impl UdmfSpecial {
     pub fn new(value: i16, args: [i16; 5]) -> Self {
         Self { value, args }
     }
+
+    /// Clamp the args which are out of range for the types of the special's fields, e.g. a `bool` arg of 2 to 1, so
+    /// that a special with this value converts
+    pub fn clamp_args(mut self) -> Self {
+        while let Some((i, range)) = Special::arg_out_of_range(&self) {
+            self.args[i] = self.args[i].clamp(*range.start(), *range.end());
+        }
+
+        self
+    }
 }
 
 /// A `Special` representation in the DOOM format
@@ -2030,6 +2351,307 @@ impl DoomSpecial {
     }
 }
 
+/// The result of converting a `DoomSpecial` to a `Special`, with the details needed to explain the conversion
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DoomConversion {
+    pub doom: DoomSpecial,
+    pub special: Special,
+    pub trigger_flags: TriggerFlags,
+    /// The args set by the Doom special's type rather than its tag, as `(arg name, value)`
+    pub constant_args: &'static [(&'static str, i16)],
+}
+
+impl DoomConversion {
+    /// Convert a `DoomSpecial`, or return it if it has no equivalent `Special`
+    pub fn convert(doom: DoomSpecial) -> Result<Self, DoomSpecial> {
+        let (special, trigger_flags) = doom.try_into()?;
+
+        Ok(Self {
+            doom,
+            special,
+            trigger_flags,
+            constant_args: Special::doom_constant_args(doom.value).unwrap_or_default(),
+        })
+    }
+}
+
+impl Display for DoomConversion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Doom type {} became {:?}", self.doom.value, self.special)?;
+
+        let trigger_names = self.trigger_flags.set_names();
+        if !trigger_names.is_empty() {
+            write!(f, " with {}", trigger_names.join("+"))?;
+        }
+
+        if !self.constant_args.is_empty() {
+            let constants: Vec<String> = self
+                .constant_args
+                .iter()
+                .map(|(name, value)| format!("{name}={value}"))
+                .collect();
+            write!(f, " ({} filled in)", constants.join(", "))?;
+        }
+
+        Ok(())
+    }
+}
+
 slotmap::new_key_type! { pub struct LineDefKey; }
 
 pub type LineDefMap = SlotMap<LineDefKey, LineDef>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn special_motion() {
+        assert_eq!(
+            Special::DoorRaise {
+                tag: 1,
+                speed: 16,
+                delay: 150,
+                light_tag: 0
+            }
+            .motion(),
+            Some(Motion {
+                plane: MovingPlane::Ceiling,
+                direction: MotionDirection::UpThenDown,
+                target: TargetHeight::DoorOpen,
+            })
+        );
+        assert_eq!(Special::None.motion(), None);
+    }
+
+    #[test]
+    fn doom_conversion_report() {
+        let conversion = DoomConversion::convert(DoomSpecial::new(1, 7)).unwrap();
+
+        assert_eq!(
+            conversion.special,
+            Special::DoorRaise {
+                tag: 0,
+                speed: 16,
+                delay: 150,
+                light_tag: 7
+            }
+        );
+        assert_eq!(
+            conversion.constant_args,
+            &[("tag", 0), ("speed", 16), ("delay", 150)]
+        );
+        assert_eq!(
+            conversion.to_string(),
+            "Doom type 1 became DoorRaise { tag: 0, speed: 16, delay: 150, light_tag: 7 } \
+             with player_use+repeats+monsters_activate (tag=0, speed=16, delay=150 filled in)"
+        );
+
+        assert_eq!(
+            DoomConversion::convert(DoomSpecial::new(-1, 0)),
+            Err(DoomSpecial::new(-1, 0))
+        );
+    }
+
+    #[test]
+    fn special_names() {
+        let special = Special::DoorOpen {
+            tag: 1,
+            speed: 16,
+            light_tag: 0,
+        };
+        assert_eq!(special.name(), "Door_Open");
+        assert_eq!(
+            Special::AcsExecute {
+                script: 1,
+                map: 0,
+                s_arg1: 0,
+                s_arg2: 0,
+                s_arg3: 0
+            }
+            .name(),
+            "ACS_Execute"
+        );
+        assert_eq!(
+            Special::ThingChangeTid {
+                oldtid: 1,
+                newtid: 2
+            }
+            .name(),
+            "Thing_ChangeTID"
+        );
+
+        assert_eq!(
+            "door_open".parse(),
+            Ok(Special::DoorOpen {
+                tag: 0,
+                speed: 0,
+                light_tag: 0
+            })
+        );
+        assert_eq!(
+            "Door_Opne".parse::<Special>(),
+            Err(UnknownSpecialName("Door_Opne".to_string()))
+        );
+
+        for udmf_value in 0..=i16::MAX {
+            if let Ok(special) = Special::try_from(UdmfSpecial::new(udmf_value, [0; 5])) {
+                assert_eq!(special.name().parse(), Ok(special));
+            }
+        }
+    }
+
+    #[test]
+    fn special_metadata() {
+        let meta = SpecialMeta::find(12).unwrap();
+
+        assert_eq!(meta.name, "Door_Raise");
+        assert_eq!(meta.arg_names, &["tag", "speed", "delay", "light_tag"]);
+        assert!(meta.doom_values.contains(&1));
+
+        for meta in Special::ALL_METADATA {
+            let special = Special::try_from(UdmfSpecial::new(meta.udmf_value, [0; 5])).unwrap();
+            assert_eq!(special.name(), meta.name);
+            assert_eq!(special.arg_names().len(), meta.arg_count());
+        }
+    }
+
+    #[test]
+    fn typed_args() {
+        let udmf = UdmfSpecial::new(17, [3, 1, 0, 0, 0]);
+        let special = Special::try_from(udmf).unwrap();
+
+        assert_eq!(
+            special,
+            Special::ThingRaise {
+                tid: 3,
+                nocheck: true
+            }
+        );
+        assert_eq!(UdmfSpecial::from(special), udmf);
+
+        let out_of_range = UdmfSpecial::new(2, [1, 8, 256, 0, 0]);
+        assert_eq!(Special::try_from(out_of_range), Err(out_of_range));
+        assert_eq!(Special::arg_out_of_range(&out_of_range), Some((2, 0..=255)));
+        assert_eq!(Special::arg_out_of_range(&udmf), None);
+
+        let clamped = UdmfSpecial::new(17, [3, 2, 0, 0, 0]).clamp_args();
+        assert_eq!(clamped, udmf);
+        assert_eq!(
+            out_of_range.clamp_args(),
+            UdmfSpecial::new(2, [1, 8, 255, 0, 0])
+        );
+    }
+
+    #[test]
+    fn byte_angle_args() {
+        let udmf = UdmfSpecial::new(4, [1, 8, 64, 128, 0]);
+        let special = Special::try_from(udmf).unwrap();
+
+        assert!(matches!(
+            special,
+            Special::PolyobjMove {
+                angle: Angle::NORTH,
+                ..
+            }
+        ));
+        assert_eq!(UdmfSpecial::from(special), udmf);
+
+        // Every byte angle survives the trip through whole degrees
+        for byte_angle in 0..=255 {
+            let udmf = UdmfSpecial::new(72, [byte_angle, 0, 0, 0, 0]);
+            assert_eq!(UdmfSpecial::from(Special::try_from(udmf).unwrap()), udmf);
+        }
+    }
+
+    #[test]
+    fn height_args() {
+        let udmf = UdmfSpecial::new(30, [3, 8, 64, -16, 0]);
+        let special = Special::try_from(udmf).unwrap();
+
+        assert_eq!(
+            special,
+            Special::PillarOpen {
+                tag: 3,
+                speed: 8,
+                fdist: Height(64),
+                cdist: Height(-16)
+            }
+        );
+        assert_eq!(UdmfSpecial::from(special), udmf);
+
+        // Constant heights of Doom specials are heights too
+        let conversion = DoomConversion::convert(DoomSpecial::new(58, 5)).unwrap();
+        assert!(matches!(
+            conversion.special,
+            Special::FloorRaiseByValue {
+                tag: 5,
+                height: Height(24),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn enum_args() {
+        let udmf = UdmfSpecial::new(53, [4, 16, 0, 1, 0]);
+        let special = Special::try_from(udmf).unwrap();
+
+        assert_eq!(
+            special,
+            Special::LineSetTextureOffset {
+                lineid: 4,
+                x: 16,
+                y: 0,
+                side: Side::Back,
+                flags: 0
+            }
+        );
+        assert_eq!(UdmfSpecial::from(special), udmf);
+
+        let conversion = DoomConversion::convert(DoomSpecial::new(44, 2)).unwrap();
+        assert_eq!(
+            conversion.special,
+            Special::CeilingLowerAndCrush {
+                tag: 2,
+                speed: 8,
+                crush: 0,
+                crushmode: CrushMode::Hexen
+            }
+        );
+
+        let out_of_range = UdmfSpecial::new(42, [1, 8, 10, 4, 0]);
+        assert_eq!(Special::try_from(out_of_range), Err(out_of_range));
+        assert_eq!(Special::arg_out_of_range(&out_of_range), Some((3, 0..=3)));
+        assert_eq!(SectorPlane::try_from(3), Err(3));
+    }
+
+    #[test]
+    fn doom_round_trip() {
+        for value in 1..=141 {
+            let Ok(conversion) = DoomConversion::convert(DoomSpecial::new(value, 7)) else {
+                continue;
+            };
+            let doom = conversion
+                .special
+                .to_doom(&conversion.trigger_flags)
+                .unwrap();
+
+            assert_eq!(
+                doom.try_into(),
+                Ok((conversion.special, conversion.trigger_flags))
+            );
+        }
+
+        assert_eq!(
+            Special::DoorRaise {
+                tag: 3,
+                speed: 16,
+                delay: 150,
+                light_tag: 0
+            }
+            .to_doom(&TriggerFlags::default()),
+            None
+        );
+    }
+}
@@ -0,0 +1,1126 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    fmt::{self, Display, Formatter},
+};
+
+use crate::{
+    map::{
+        geometry,
+        line_def::{LineDefKey, Motion, MotionDirection, Special},
+        mechanisms::TeleportDestination,
+        sector::SectorKey,
+        self_reference::SelfReferenceKind,
+        side_def::SideDefKey,
+        tag_graph::TagArg,
+        thing::{GameMode, Skill, ThingKey},
+        thing_type::{ThingCategory, ThingInfo, ThingKind, ThingTypes},
+        vertex::VertexKey,
+        Map, Thing,
+    },
+    Point,
+};
+
+/// How serious a `LintIssue` is
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Severity {
+    /// Probably intentional, but worth knowing about
+    Info,
+    /// Likely to cause problems in some game modes or ports
+    Warning,
+    /// The map will not play correctly, or at all
+    Error,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        })
+    }
+}
+
+/// A reference to any entity in a `Map`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum EntityRef {
+    Vertex(VertexKey),
+    LineDef(LineDefKey),
+    Sector(SectorKey),
+    SideDef(SideDefKey),
+    Thing(ThingKey),
+}
+
+/// The check which produced a `LintIssue`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum LintRule {
+    /// There is no player 1 start, so the map can't be played in single player
+    MissingPlayer1Start,
+    /// A player has more than one start. All but the last spawn voodoo dolls.
+    DuplicatePlayerStart,
+    /// There are fewer than the 4 deathmatch starts vanilla requires to start a deathmatch game
+    MissingDeathmatchStarts,
+    /// A player or deathmatch start is outside the map, overlaps a solid wall or is in a sector too low to fit a
+    /// player
+    StartInSolid,
+    /// A sector is moved in different ways by specials on several lines, which ports resolve differently
+    MotionConflict,
+    /// A teleport line has no destination: no teleport destination thing in the sectors with its tag, or no line with
+    /// its destination line id
+    MissingTeleportDestination,
+    /// A locked line needs keys which the player can't reach on some skills, so it can't be opened on them. Locks not
+    /// known to waddle aren't checked.
+    MissingKey,
+    /// A secret sector has no room for the player to enter it, and isn't a door or lift, so it can't be found
+    ZeroHeightSecret,
+    /// A line which can only be activated once moves a sector temporarily, e.g. a lift which goes back up, with no
+    /// other line to move it again. If the player needs it a second time, the map can't be finished. Only checked
+    /// with `LintOptions::progression`.
+    OneShotTemporaryMotion,
+    /// A secret sector can't be reached from the player 1 start on skill 4, as found by [Map::reachability], so the
+    /// map can't be maxed. Only checked with `LintOptions::progression`.
+    UnreachableSecret,
+    /// A sector is self-referencing, as found by [Map::self_referencing_sectors], which old maps use for deep water and
+    /// invisible bridges. Ports which render them properly need them converted with [Map::convert_self_reference].
+    SelfReferencingSector,
+    /// A monster is stuck in a wall or in a sector too low for it, or two solid things overlap on some skill, game
+    /// mode and class they both appear in, using the radius and height of their thing types
+    ThingClearance,
+}
+
+impl LintRule {
+    pub fn severity(self) -> Severity {
+        match self {
+            LintRule::MissingPlayer1Start => Severity::Error,
+            LintRule::DuplicatePlayerStart => Severity::Warning,
+            LintRule::MissingDeathmatchStarts => Severity::Warning,
+            LintRule::StartInSolid => Severity::Error,
+            LintRule::MotionConflict => Severity::Warning,
+            LintRule::MissingTeleportDestination => Severity::Error,
+            LintRule::MissingKey => Severity::Error,
+            LintRule::ZeroHeightSecret => Severity::Error,
+            LintRule::OneShotTemporaryMotion => Severity::Info,
+            LintRule::UnreachableSecret => Severity::Warning,
+            LintRule::SelfReferencingSector => Severity::Info,
+            LintRule::ThingClearance => Severity::Warning,
+        }
+    }
+}
+
+/// A problem found in a `Map` by [Map::lint]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LintIssue {
+    pub rule: LintRule,
+    pub severity: Severity,
+    pub message: String,
+    /// The entities involved in the issue, most relevant first
+    pub entities: Vec<EntityRef>,
+}
+
+impl LintIssue {
+    pub fn new(rule: LintRule, message: impl Into<String>, entities: Vec<EntityRef>) -> Self {
+        Self {
+            rule,
+            severity: rule.severity(),
+            message: message.into(),
+            entities,
+        }
+    }
+}
+
+impl Display for LintIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.severity, self.message)
+    }
+}
+
+/// Configuration for [Map::lint]
+#[derive(Clone, Debug)]
+pub struct LintOptions<'t> {
+    /// Used to identify things such as player starts
+    pub thing_types: &'t ThingTypes,
+    /// Also run the progression rules, which look for ways the map can become unwinnable. They are heuristic and
+    /// report many intentional setups, so they're off by default.
+    pub progression: bool,
+}
+
+impl<'t> LintOptions<'t> {
+    pub fn new(thing_types: &'t ThingTypes) -> Self {
+        Self {
+            thing_types,
+            progression: false,
+        }
+    }
+}
+
+/// The vanilla engine refuses to start a deathmatch game with fewer deathmatch starts than this
+pub const MIN_DEATHMATCH_STARTS: usize = 4;
+
+/// The radius and height used for player starts whose type doesn't say otherwise
+const PLAYER_RADIUS: u16 = 16;
+pub(crate) const PLAYER_HEIGHT: u16 = 56;
+
+impl Map {
+    /// Check the map for common mistakes, returning every issue found
+    pub fn lint(&self, options: &LintOptions) -> Vec<LintIssue> {
+        let mut issues = Vec::new();
+
+        self.lint_player_starts(options, &mut issues);
+        self.lint_motion_conflicts(&mut issues);
+        self.lint_teleport_destinations(options, &mut issues);
+        self.lint_keys(options, &mut issues);
+        self.lint_zero_height_secrets(options, &mut issues);
+        self.lint_self_referencing_sectors(&mut issues);
+        self.lint_thing_clearance(options, &mut issues);
+
+        if options.progression {
+            self.lint_one_shot_motions(&mut issues);
+            self.lint_unreachable_secrets(options, &mut issues);
+        }
+
+        issues
+    }
+
+    fn lint_player_starts(&self, options: &LintOptions, issues: &mut Vec<LintIssue>) {
+        let mut player_starts: [Vec<ThingKey>; 4] = Default::default();
+        let mut deathmatch_starts = Vec::new();
+
+        for (key, thing) in self.things.iter() {
+            let starts = match thing.kind(options.thing_types) {
+                Some(ThingKind::Player1Start) => &mut player_starts[0],
+                Some(ThingKind::Player2Start) => &mut player_starts[1],
+                Some(ThingKind::Player3Start) => &mut player_starts[2],
+                Some(ThingKind::Player4Start) => &mut player_starts[3],
+                Some(ThingKind::DeathmatchStart) => &mut deathmatch_starts,
+                _ => continue,
+            };
+
+            starts.push(key);
+        }
+
+        if player_starts[0].is_empty() {
+            issues.push(LintIssue::new(
+                LintRule::MissingPlayer1Start,
+                "no player 1 start",
+                Vec::new(),
+            ));
+        }
+
+        for (index, starts) in player_starts.iter().enumerate() {
+            if starts.len() > 1 {
+                issues.push(LintIssue::new(
+                    LintRule::DuplicatePlayerStart,
+                    format!(
+                        "player {} has {} starts; all but the last will spawn voodoo dolls",
+                        index + 1,
+                        starts.len()
+                    ),
+                    starts.iter().copied().map(EntityRef::Thing).collect(),
+                ));
+            }
+        }
+
+        if deathmatch_starts.len() < MIN_DEATHMATCH_STARTS {
+            let message = if deathmatch_starts.is_empty() {
+                "no deathmatch starts".to_owned()
+            } else {
+                format!(
+                    "only {} deathmatch starts, {MIN_DEATHMATCH_STARTS} required",
+                    deathmatch_starts.len()
+                )
+            };
+
+            issues.push(LintIssue::new(
+                LintRule::MissingDeathmatchStarts,
+                message,
+                deathmatch_starts
+                    .iter()
+                    .copied()
+                    .map(EntityRef::Thing)
+                    .collect(),
+            ));
+        }
+
+        for &key in player_starts.iter().flatten().chain(&deathmatch_starts) {
+            if let Some(issue) = self.start_in_solid(key, &self.things[key], options.thing_types) {
+                issues.push(issue);
+            }
+        }
+    }
+
+    /// Find sectors moved by lines with different motions, e.g. a lift and a crusher on the same tag. Vanilla only
+    /// allows one mover per sector, even for the floor and ceiling, while Boom and later ports allow one per plane.
+    /// Identical motions, such as a door opened from either side, and stopping specials are fine.
+    fn lint_motion_conflicts(&self, issues: &mut Vec<LintIssue>) {
+        let graph = self.tag_graph();
+        let mut movers: BTreeMap<SectorKey, Vec<(LineDefKey, Motion)>> = BTreeMap::new();
+
+        for reference in graph
+            .references
+            .iter()
+            .filter(|reference| reference.arg == TagArg::Whole("tag"))
+        {
+            let line_def = &self.line_defs[reference.line_def];
+            let Some(motion) = line_def.special.motion() else {
+                continue;
+            };
+
+            // Lines which can't be activated directly can only conflict through scripts
+            if motion.direction == MotionDirection::Stop
+                || line_def.trigger_flags.set_names().is_empty()
+            {
+                continue;
+            }
+
+            for &sector in graph.sectors_for(reference) {
+                movers
+                    .entry(sector)
+                    .or_default()
+                    .push((reference.line_def, motion));
+            }
+        }
+
+        for (sector, movers) in movers {
+            let motions: HashSet<_> = movers.iter().map(|(_, motion)| motion).collect();
+            if motions.len() < 2 {
+                continue;
+            }
+
+            let lines: BTreeSet<_> = movers.iter().map(|(line_def, _)| *line_def).collect();
+            issues.push(LintIssue::new(
+                LintRule::MotionConflict,
+                format!(
+                    "sector tagged {} is moved in {} different ways by {} lines",
+                    self.sectors[sector].tag,
+                    motions.len(),
+                    lines.len()
+                ),
+                [EntityRef::Sector(sector)]
+                    .into_iter()
+                    .chain(lines.into_iter().map(EntityRef::LineDef))
+                    .collect(),
+            ));
+        }
+    }
+
+    /// Find teleport lines with nowhere to go. Teleports to a thing by tid aren't checked, as things don't have tids.
+    fn lint_teleport_destinations(&self, options: &LintOptions, issues: &mut Vec<LintIssue>) {
+        for teleporter in self.mechanisms(options.thing_types).teleporters {
+            let line_def = &self.line_defs[teleporter.line];
+            let message = match (&teleporter.destination, &line_def.special) {
+                (
+                    TeleportDestination::Things(things),
+                    Special::Teleport { tag, .. } | Special::TeleportNoFog { tag, .. },
+                ) if things.is_empty() => {
+                    format!("teleport line has no destination in sectors tagged {tag}")
+                }
+                (TeleportDestination::Lines(lines), Special::TeleportLine { destid, .. })
+                    if lines.is_empty() =>
+                {
+                    format!("line teleport has no destination line with id {destid}")
+                }
+                _ => continue,
+            };
+
+            issues.push(LintIssue::new(
+                LintRule::MissingTeleportDestination,
+                message,
+                vec![EntityRef::LineDef(teleporter.line)],
+            ));
+        }
+    }
+
+    /// Find locked lines which can't be opened with the keys the player can pick up on each skill, in single
+    /// player. Keys are only counted where the player can reach them, unless there's no player start, as nothing is
+    /// reachable then.
+    fn lint_keys(&self, options: &LintOptions, issues: &mut Vec<LintIssue>) {
+        let keys: Vec<(Skill, Vec<ThingKind>)> = Skill::ALL
+            .into_iter()
+            .map(|skill| {
+                let reachability = self.reachability(options.thing_types, skill);
+                let reachable = |thing: &Thing| {
+                    let position = thing.position.map(|n| n.into_float());
+                    reachability.sectors.is_empty()
+                        || self
+                            .sector_at(position)
+                            .is_some_and(|sector| reachability.sectors.contains(&sector))
+                };
+
+                let keys = self
+                    .things
+                    .values()
+                    .filter(|thing| {
+                        thing.flags.in_skill(skill) && thing.flags.in_mode(GameMode::SinglePlayer)
+                    })
+                    .filter(|thing| reachable(thing))
+                    .filter_map(|thing| thing.info(options.thing_types))
+                    .filter(|info| info.category == ThingCategory::Key)
+                    .map(|info| info.kind)
+                    .collect();
+                (skill, keys)
+            })
+            .collect();
+
+        for (key, line_def) in self.line_defs.iter() {
+            let lock = line_def.special.lock();
+            let missing: Vec<String> = keys
+                .iter()
+                .zip(1..)
+                .filter(|((_, keys), _)| lock.is_opened_by(keys) == Some(false))
+                .map(|(_, number)| number.to_string())
+                .collect();
+
+            if !missing.is_empty() {
+                issues.push(LintIssue::new(
+                    LintRule::MissingKey,
+                    format!(
+                        "line needs {lock}, which can't be picked up on skill {}",
+                        missing.join(", ")
+                    ),
+                    vec![EntityRef::LineDef(key)],
+                ));
+            }
+        }
+    }
+
+    fn lint_zero_height_secrets(&self, options: &LintOptions, issues: &mut Vec<LintIssue>) {
+        let mechanisms = self.mechanisms(options.thing_types);
+        let opened: BTreeSet<SectorKey> = mechanisms
+            .doors
+            .iter()
+            .map(|door| door.sector)
+            .chain(mechanisms.lifts.iter().map(|lift| lift.sector))
+            .collect();
+
+        for key in self.secrets() {
+            let sector = &self.sectors[key];
+            if sector.ceiling_height <= sector.floor_height && !opened.contains(&key) {
+                issues.push(LintIssue::new(
+                    LintRule::ZeroHeightSecret,
+                    "secret sector has no height, and nothing opens it",
+                    vec![EntityRef::Sector(key)],
+                ));
+            }
+        }
+    }
+
+    /// Skill 4 is the one speedrunners max maps on
+    fn lint_unreachable_secrets(&self, options: &LintOptions, issues: &mut Vec<LintIssue>) {
+        let reachability = self.reachability(options.thing_types, Skill::Skill4);
+        // Nothing is reachable without a start, which is reported already
+        if reachability.sectors.is_empty() {
+            return;
+        }
+
+        for sector in reachability.unreachable_secrets {
+            issues.push(LintIssue::new(
+                LintRule::UnreachableSecret,
+                "secret sector can't be reached on skill 4",
+                vec![EntityRef::Sector(sector)],
+            ));
+        }
+    }
+
+    fn lint_self_referencing_sectors(&self, issues: &mut Vec<LintIssue>) {
+        for self_reference in self.self_referencing_sectors() {
+            let trick = match self_reference.kind {
+                SelfReferenceKind::DeepWater => "deep water",
+                SelfReferenceKind::InvisibleBridge => "an invisible bridge",
+                SelfReferenceKind::Other => "drawn as the sector around it",
+            };
+
+            issues.push(LintIssue::new(
+                LintRule::SelfReferencingSector,
+                format!("self-referencing sector is {trick} in vanilla renderers only"),
+                [self_reference.sector]
+                    .into_iter()
+                    .chain(self_reference.surrounding)
+                    .map(EntityRef::Sector)
+                    .collect(),
+            ));
+        }
+    }
+
+    /// Monsters can't move out of a wall or a sector lower than they are, and solid things which overlap get stuck
+    /// in each other. Sectors opened by doors and lifts are fine to be low, as for monster closets. Player and
+    /// deathmatch starts are checked against walls and sectors by `StartInSolid` already.
+    fn lint_thing_clearance(&self, options: &LintOptions, issues: &mut Vec<LintIssue>) {
+        let mechanisms = self.mechanisms(options.thing_types);
+        let opened: BTreeSet<SectorKey> = mechanisms
+            .doors
+            .iter()
+            .map(|door| door.sector)
+            .chain(mechanisms.lifts.iter().map(|lift| lift.sector))
+            .collect();
+
+        let solid: Vec<(ThingKey, &Thing, &ThingInfo)> = self
+            .things
+            .iter()
+            .filter_map(|(key, thing)| Some((key, thing, thing.info(options.thing_types)?)))
+            .filter(|(_, _, info)| {
+                info.radius > 0
+                    && matches!(
+                        info.category,
+                        ThingCategory::Monster
+                            | ThingCategory::Obstacle
+                            | ThingCategory::PlayerStart
+                    )
+            })
+            .collect();
+
+        for &(key, thing, info) in &solid {
+            if info.category != ThingCategory::Monster || Appearances::of(thing, info).is_empty() {
+                continue;
+            }
+            let position = thing.position.map(|n| n.into_float());
+
+            let Some(sector_key) = self.sector_at(position) else {
+                continue;
+            };
+            let sector = &self.sectors[sector_key];
+            let space = sector.ceiling_height - sector.floor_height;
+            if space < i32::from(info.height) && !opened.contains(&sector_key) {
+                issues.push(LintIssue::new(
+                    LintRule::ThingClearance,
+                    format!(
+                        "{} is {} units high, but is in a sector {space} units high",
+                        info.name, info.height
+                    ),
+                    vec![EntityRef::Thing(key), EntityRef::Sector(sector_key)],
+                ));
+            }
+
+            let blocking_line = self.line_defs.iter().find(|(_, line_def)| {
+                (line_def.right_side.is_none() || line_def.flags.impassable)
+                    && self.line_segment(line_def).is_some_and(|(from, to)| {
+                        geometry::segment_intersects_box(from, to, position, f64::from(info.radius))
+                    })
+            });
+            if let Some((line_key, _)) = blocking_line {
+                issues.push(LintIssue::new(
+                    LintRule::ThingClearance,
+                    format!("{} is stuck in a blocking line", info.name),
+                    vec![EntityRef::Thing(key), EntityRef::LineDef(line_key)],
+                ));
+            }
+        }
+
+        for (i, &(a_key, a, a_info)) in solid.iter().enumerate() {
+            for &(b_key, b, b_info) in &solid[i + 1..] {
+                // Obstacles stand still, so they only matter when something has to move out of them, and starts of
+                // the same player are reported by `DuplicatePlayerStart`
+                let movers = [a_info, b_info]
+                    .iter()
+                    .filter(|info| info.category != ThingCategory::Obstacle)
+                    .count();
+                let starts = [a_info, b_info]
+                    .iter()
+                    .filter(|info| info.category == ThingCategory::PlayerStart)
+                    .count();
+                if movers == 0 || starts == 2 {
+                    continue;
+                }
+
+                let reach = f64::from(a_info.radius) + f64::from(b_info.radius);
+                let (a_position, b_position) = (
+                    a.position.map(|n| n.into_float()),
+                    b.position.map(|n| n.into_float()),
+                );
+                let (a_z, b_z) = (i32::from(a.height.0), i32::from(b.height.0));
+                if (a_position.x - b_position.x).abs() >= reach
+                    || (a_position.y - b_position.y).abs() >= reach
+                    || a_z >= b_z + i32::from(b_info.height)
+                    || b_z >= a_z + i32::from(a_info.height)
+                {
+                    continue;
+                }
+
+                let skills: Vec<String> = Appearances::of(a, a_info)
+                    .shared_with(&Appearances::of(b, b_info))
+                    .into_iter()
+                    .map(|skill| (skill as usize + 1).to_string())
+                    .collect();
+                if skills.is_empty() {
+                    continue;
+                }
+
+                issues.push(LintIssue::new(
+                    LintRule::ThingClearance,
+                    format!(
+                        "{} and {} overlap on skill {}",
+                        a_info.name,
+                        b_info.name,
+                        skills.join(", ")
+                    ),
+                    vec![EntityRef::Thing(a_key), EntityRef::Thing(b_key)],
+                ));
+            }
+        }
+    }
+
+    fn lint_one_shot_motions(&self, issues: &mut Vec<LintIssue>) {
+        let graph = self.tag_graph();
+        let tag_references = || {
+            graph
+                .references
+                .iter()
+                .filter(|reference| reference.arg == TagArg::Whole("tag"))
+        };
+
+        for reference in tag_references() {
+            let line_def = &self.line_defs[reference.line_def];
+            let flags = &line_def.trigger_flags;
+            let Some(motion) = line_def.special.motion() else {
+                continue;
+            };
+
+            let temporary = matches!(
+                motion.direction,
+                MotionDirection::UpThenDown | MotionDirection::DownThenUp
+            );
+            if !temporary || flags.repeats || flags.set_names().is_empty() {
+                continue;
+            }
+
+            // Another line which can repeat the same motion on the same tag is a way back
+            let has_backup = tag_references().any(|other| {
+                let other_line = &self.line_defs[other.line_def];
+
+                other.tag == reference.tag
+                    && other_line.trigger_flags.repeats
+                    && other_line.special.motion() == Some(motion)
+            });
+            let sectors = graph.sectors_for(reference);
+            if has_backup || sectors.is_empty() {
+                continue;
+            }
+
+            issues.push(LintIssue::new(
+                LintRule::OneShotTemporaryMotion,
+                format!(
+                    "line can only be activated once, but moves sectors tagged {} back after a delay",
+                    reference.tag
+                ),
+                [EntityRef::LineDef(reference.line_def)]
+                    .into_iter()
+                    .chain(sectors.iter().copied().map(EntityRef::Sector))
+                    .collect(),
+            ));
+        }
+    }
+
+    fn start_in_solid(
+        &self,
+        key: ThingKey,
+        thing: &Thing,
+        thing_types: &ThingTypes,
+    ) -> Option<LintIssue> {
+        let (radius, height) = thing
+            .info(thing_types)
+            .map_or((PLAYER_RADIUS, PLAYER_HEIGHT), |info| {
+                (info.radius, info.height)
+            });
+        let position = Point::new(thing.position.x.into_float(), thing.position.y.into_float());
+        let name = thing
+            .info(thing_types)
+            .map_or("start", |info| info.name.as_ref());
+
+        let Some(sector_key) = self.sector_at(position) else {
+            return Some(LintIssue::new(
+                LintRule::StartInSolid,
+                format!("{name} is outside the map"),
+                vec![EntityRef::Thing(key)],
+            ));
+        };
+
+        let sector = &self.sectors[sector_key];
+        let space = sector.ceiling_height - sector.floor_height;
+
+        if space < i32::from(height) {
+            return Some(LintIssue::new(
+                LintRule::StartInSolid,
+                format!("{name} is in a sector {space} units high, too low for a player"),
+                vec![EntityRef::Thing(key), EntityRef::Sector(sector_key)],
+            ));
+        }
+
+        let blocking_line = self.line_defs.iter().find(|(_, line_def)| {
+            line_def.right_side.is_none()
+                && self.line_segment(line_def).is_some_and(|(from, to)| {
+                    geometry::segment_intersects_box(from, to, position, f64::from(radius))
+                })
+        });
+
+        blocking_line.map(|(line_key, _)| {
+            LintIssue::new(
+                LintRule::StartInSolid,
+                format!("{name} overlaps a one-sided line"),
+                vec![EntityRef::Thing(key), EntityRef::LineDef(line_key)],
+            )
+        })
+    }
+}
+
+/// The skills, game modes and classes a thing appears in, from its flags
+struct Appearances {
+    skills: Vec<Skill>,
+    modes: Vec<GameMode>,
+    /// Things with no class flags, as in every game but Hexen, appear for every class
+    classes: [bool; 3],
+}
+
+impl Appearances {
+    /// Player starts spawn the player whatever their flags say, in single player and cooperative games
+    fn of(thing: &Thing, info: &ThingInfo) -> Self {
+        if info.category == ThingCategory::PlayerStart {
+            return Self {
+                skills: Skill::ALL.to_vec(),
+                modes: vec![GameMode::SinglePlayer, GameMode::Cooperative],
+                classes: [true; 3],
+            };
+        }
+
+        let flags = &thing.flags;
+        let classes = [flags.class1, flags.class2, flags.class3];
+        Self {
+            skills: Skill::ALL
+                .into_iter()
+                .filter(|&skill| flags.in_skill(skill))
+                .collect(),
+            modes: GameMode::ALL
+                .into_iter()
+                .filter(|&mode| flags.in_mode(mode))
+                .collect(),
+            classes: if classes.contains(&true) {
+                classes
+            } else {
+                [true; 3]
+            },
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.skills.is_empty() || self.modes.is_empty()
+    }
+
+    /// The skills on which both appear together in some game mode and for some class
+    fn shared_with(&self, other: &Self) -> Vec<Skill> {
+        let modes = self.modes.iter().any(|mode| other.modes.contains(mode));
+        let classes = (0..3).any(|i| self.classes[i] && other.classes[i]);
+        if !modes || !classes {
+            return Vec::new();
+        }
+
+        self.skills
+            .iter()
+            .copied()
+            .filter(|skill| other.skills.contains(skill))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        map::{
+            line_def::{CrushMode, Special},
+            lock::Lock,
+            thing,
+            thing_type::Game,
+            LineDef, Sector, SideDef, Vertex,
+        },
+        number::Number,
+        units::{Angle, Height},
+    };
+
+    fn square_room(map: &mut Map, size: i32) {
+        let sector = map.sectors.insert(Sector {
+            floor_height: Height(0),
+            ceiling_height: Height(128),
+            ..Default::default()
+        });
+
+        let corners = [(0, 0), (0, size), (size, size), (size, 0)].map(|(x, y)| {
+            map.vertexes.insert(Vertex {
+                position: Point::new(Number::Int(x), Number::Int(y)),
+                ..Default::default()
+            })
+        });
+
+        for i in 0..corners.len() {
+            let side = map.side_defs.insert(SideDef {
+                sector,
+                offset: Point::default(),
+                upper_texture: Default::default(),
+                middle_texture: Default::default(),
+                lower_texture: Default::default(),
+            });
+
+            map.line_defs.insert(LineDef {
+                from: corners[i],
+                to: corners[(i + 1) % corners.len()],
+                left_side: side,
+                right_side: None,
+                id: crate::map::line_def::NO_ID,
+                flags: Default::default(),
+                special: Default::default(),
+                trigger_flags: Default::default(),
+            });
+        }
+    }
+
+    fn add_thing(map: &mut Map, type_: i16, x: i32, y: i32) -> ThingKey {
+        map.things.insert(Thing {
+            position: Point::new(Number::Int(x), Number::Int(y)),
+            height: Height(0),
+            angle: Angle(0),
+            type_,
+            flags: thing::Flags::default(),
+            special: thing::Special::None,
+        })
+    }
+
+    #[test]
+    fn player_start_rules() {
+        let thing_types = ThingTypes::for_game(Game::Doom2);
+        let options = LintOptions::new(&thing_types);
+        let mut map = Map::new("MAP01".try_into().unwrap());
+        square_room(&mut map, 256);
+
+        let rules = |map: &Map| -> Vec<LintRule> {
+            map.lint(&options)
+                .into_iter()
+                .map(|issue| issue.rule)
+                .collect()
+        };
+
+        assert_eq!(
+            rules(&map),
+            vec![
+                LintRule::MissingPlayer1Start,
+                LintRule::MissingDeathmatchStarts
+            ]
+        );
+
+        add_thing(&mut map, 1, 128, 128);
+        for i in 0..4 {
+            add_thing(&mut map, 11, 32 + i * 48, 64);
+        }
+        assert_eq!(rules(&map), vec![]);
+
+        add_thing(&mut map, 1, 64, 192);
+        let stuck = add_thing(&mut map, 2, 8, 128);
+        let outside = add_thing(&mut map, 3, 512, 128);
+
+        let issues = map.lint(&options);
+        assert_eq!(
+            issues.iter().map(|issue| issue.rule).collect::<Vec<_>>(),
+            vec![
+                LintRule::DuplicatePlayerStart,
+                LintRule::StartInSolid,
+                LintRule::StartInSolid
+            ]
+        );
+        assert_eq!(issues[1].entities[0], EntityRef::Thing(stuck));
+        assert_eq!(issues[2].entities, vec![EntityRef::Thing(outside)]);
+    }
+
+    #[test]
+    fn teleport_destinations() {
+        let thing_types = ThingTypes::for_game(Game::Doom2);
+        let options = LintOptions::new(&thing_types);
+        let mut map = Map::new("MAP01".try_into().unwrap());
+        square_room(&mut map, 256);
+        add_thing(&mut map, 1, 128, 128);
+
+        let lines: Vec<_> = map.line_defs.keys().collect();
+        map.line_defs[lines[0]].special = Special::Teleport {
+            tid: 0,
+            tag: 7,
+            nosourcefog: false,
+        };
+        map.line_defs[lines[1]].special = Special::TeleportLine {
+            thisid: 0,
+            destid: 3,
+            flip: false,
+        };
+
+        let missing = |map: &Map| -> Vec<EntityRef> {
+            map.lint(&options)
+                .into_iter()
+                .filter(|issue| issue.rule == LintRule::MissingTeleportDestination)
+                .flat_map(|issue| issue.entities)
+                .collect()
+        };
+        assert_eq!(
+            missing(&map),
+            vec![EntityRef::LineDef(lines[0]), EntityRef::LineDef(lines[1])]
+        );
+
+        let sector = map.sectors.keys().next().unwrap();
+        map.sectors[sector].tag = 7;
+        add_thing(&mut map, 14, 64, 64);
+        map.line_defs[lines[2]].id = 3;
+        assert_eq!(missing(&map), vec![]);
+    }
+
+    #[test]
+    fn missing_keys() {
+        let thing_types = ThingTypes::for_game(Game::Doom2);
+        let options = LintOptions::new(&thing_types);
+        let mut map = Map::new("MAP01".try_into().unwrap());
+        square_room(&mut map, 256);
+        add_thing(&mut map, 1, 128, 128);
+
+        let line = map.line_defs.keys().next().unwrap();
+        map.line_defs[line].special = Special::DoorRaiseLocked {
+            tag: 0,
+            speed: 16,
+            delay: 150,
+            lock: Lock::Red,
+            lighttag: 0,
+        };
+
+        let missing = |map: &Map| -> Vec<String> {
+            map.lint(&options)
+                .into_iter()
+                .filter(|issue| issue.rule == LintRule::MissingKey)
+                .map(|issue| issue.message)
+                .collect()
+        };
+        assert_eq!(
+            missing(&map),
+            ["line needs a red key, which can't be picked up on skill 1, 2, 3, 4, 5"]
+        );
+
+        let skull = add_thing(&mut map, 38, 64, 64);
+        map.things[skull].flags.skill4 = false;
+        map.things[skull].flags.skill5 = false;
+        assert_eq!(
+            missing(&map),
+            ["line needs a red key, which can't be picked up on skill 4, 5"]
+        );
+
+        // Outside of the room, so out of reach
+        let card = add_thing(&mut map, 13, 512, 512);
+        assert_eq!(
+            missing(&map),
+            ["line needs a red key, which can't be picked up on skill 4, 5"]
+        );
+
+        map.things[card].position = Point::new(Number::Int(32), Number::Int(32));
+        assert!(missing(&map).is_empty());
+    }
+
+    #[test]
+    fn thing_clearance() {
+        let thing_types = ThingTypes::for_game(Game::Doom2);
+        let options = LintOptions::new(&thing_types);
+        let mut map = Map::new("MAP01".try_into().unwrap());
+        square_room(&mut map, 256);
+        add_thing(&mut map, 1, 128, 128);
+
+        let clearance = |map: &Map| -> Vec<String> {
+            map.lint(&options)
+                .into_iter()
+                .filter(|issue| issue.rule == LintRule::ThingClearance)
+                .map(|issue| issue.message)
+                .collect()
+        };
+        assert!(clearance(&map).is_empty());
+
+        let imp = add_thing(&mut map, 3001, 10, 64);
+        assert_eq!(clearance(&map), ["Imp is stuck in a blocking line"]);
+        map.things[imp].position.x = Number::Int(32);
+
+        // Monsters placed for different skills don't meet
+        let zombieman = add_thing(&mut map, 3004, 48, 64);
+        map.things[zombieman].flags.skill1 = false;
+        map.things[zombieman].flags.skill2 = false;
+        map.things[zombieman].flags.skill3 = false;
+        assert_eq!(clearance(&map), ["Imp and Zombieman overlap on skill 4, 5"]);
+        map.things[imp].flags.skill4 = false;
+        map.things[imp].flags.skill5 = false;
+        assert!(clearance(&map).is_empty());
+
+        // Obstacles only get in the way of things which move
+        add_thing(&mut map, 2035, 200, 200);
+        add_thing(&mut map, 2035, 210, 200);
+        assert!(clearance(&map).is_empty());
+
+        let room = map.sectors.keys().next().unwrap();
+        map.sectors[room].ceiling_height = Height(48);
+        assert_eq!(
+            clearance(&map),
+            [
+                "Imp is 56 units high, but is in a sector 48 units high",
+                "Zombieman is 56 units high, but is in a sector 48 units high",
+            ]
+        );
+    }
+
+    #[test]
+    fn secret_rules() {
+        let thing_types = ThingTypes::for_game(Game::Doom2);
+        let mut options = LintOptions::new(&thing_types);
+        let mut map = Map::new("MAP01".try_into().unwrap());
+        square_room(&mut map, 256);
+        add_thing(&mut map, 1, 128, 128);
+
+        let room = map.sectors.keys().next().unwrap();
+        map.sectors[room].secret = true;
+        let closet = map.sectors.insert(Sector {
+            secret: true,
+            ..Default::default()
+        });
+
+        let rules = |map: &Map, options: &LintOptions| -> Vec<(LintRule, Vec<EntityRef>)> {
+            map.lint(options)
+                .into_iter()
+                .filter(|issue| {
+                    matches!(
+                        issue.rule,
+                        LintRule::ZeroHeightSecret | LintRule::UnreachableSecret
+                    )
+                })
+                .map(|issue| (issue.rule, issue.entities))
+                .collect()
+        };
+        assert_eq!(
+            rules(&map, &options),
+            [(LintRule::ZeroHeightSecret, vec![EntityRef::Sector(closet)])]
+        );
+
+        options.progression = true;
+        map.sectors[closet].ceiling_height = Height(128);
+        assert_eq!(
+            rules(&map, &options),
+            [(LintRule::UnreachableSecret, vec![EntityRef::Sector(closet)])]
+        );
+
+        // Drawn inside the room as a self-referencing sector, the closet can be walked into
+        let side = map.side_defs.insert(SideDef {
+            sector: closet,
+            offset: Point::default(),
+            upper_texture: Default::default(),
+            middle_texture: Default::default(),
+            lower_texture: Default::default(),
+        });
+        let corners = [(32, 32), (32, 64), (64, 64), (64, 32)].map(|(x, y)| {
+            map.vertexes.insert(Vertex {
+                position: Point::new(Number::Int(x), Number::Int(y)),
+                ..Default::default()
+            })
+        });
+        for i in 0..corners.len() {
+            let mut line_def = LineDef {
+                from: corners[i],
+                to: corners[(i + 1) % corners.len()],
+                left_side: side,
+                right_side: Some(side),
+                id: crate::map::line_def::NO_ID,
+                flags: Default::default(),
+                special: Default::default(),
+                trigger_flags: Default::default(),
+            };
+            line_def.flags.two_sided = true;
+            map.line_defs.insert(line_def);
+        }
+        assert!(rules(&map, &options).is_empty());
+        assert!(map.lint(&options).iter().any(|issue| {
+            issue.rule == LintRule::SelfReferencingSector
+                && issue.entities == [EntityRef::Sector(closet), EntityRef::Sector(room)]
+        }));
+    }
+
+    #[test]
+    fn motion_conflicts() {
+        let thing_types = ThingTypes::for_game(Game::Doom2);
+        let options = LintOptions::new(&thing_types);
+        let mut map = Map::new("MAP01".try_into().unwrap());
+        square_room(&mut map, 256);
+        add_thing(&mut map, 1, 128, 128);
+
+        let sector = map.sectors.keys().next().unwrap();
+        map.sectors[sector].tag = 5;
+
+        let lines: Vec<_> = map.line_defs.keys().collect();
+        let set_special = |map: &mut Map, index: usize, special| {
+            let line_def = &mut map.line_defs[lines[index]];
+            line_def.special = special;
+            line_def.trigger_flags.player_use = true;
+        };
+
+        let crusher = Special::CeilingCrushAndRaise {
+            tag: 5,
+            speed: 8,
+            crush: 10,
+            crushmode: CrushMode::Default,
+        };
+        set_special(&mut map, 0, crusher.clone());
+        set_special(&mut map, 1, crusher);
+        set_special(&mut map, 2, Special::CeilingCrushStop { tag: 5 });
+
+        let conflicts = |map: &Map| -> Vec<LintIssue> {
+            map.lint(&options)
+                .into_iter()
+                .filter(|issue| issue.rule == LintRule::MotionConflict)
+                .collect()
+        };
+        assert_eq!(conflicts(&map), vec![]);
+
+        set_special(
+            &mut map,
+            3,
+            Special::PlatDownWaitUpStay {
+                tag: 5,
+                speed: 32,
+                delay: 105,
+            },
+        );
+        let issues = conflicts(&map);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].entities[0], EntityRef::Sector(sector));
+        assert_eq!(issues[0].entities.len(), 4);
+    }
+
+    #[test]
+    fn one_shot_temporary_motions() {
+        let thing_types = ThingTypes::for_game(Game::Doom2);
+        let options = LintOptions::new(&thing_types);
+        let mut map = Map::new("MAP01".try_into().unwrap());
+        square_room(&mut map, 256);
+        add_thing(&mut map, 1, 128, 128);
+
+        let sector = map.sectors.keys().next().unwrap();
+        map.sectors[sector].tag = 5;
+
+        let line = map.line_defs.keys().next().unwrap();
+        map.line_defs[line].special = Special::PlatDownWaitUpStay {
+            tag: 5,
+            speed: 32,
+            delay: 105,
+        };
+        map.line_defs[line].trigger_flags.player_use = true;
+
+        // The lift is one-shot, which is only reported when asked for
+        let one_shot = |map: &Map, options: &LintOptions| {
+            map.lint(options)
+                .into_iter()
+                .filter(|issue| issue.rule == LintRule::OneShotTemporaryMotion)
+                .count()
+        };
+        assert_eq!(one_shot(&map, &options), 0);
+
+        let progression = LintOptions {
+            progression: true,
+            ..options.clone()
+        };
+        assert_eq!(one_shot(&map, &progression), 1);
+
+        map.line_defs[line].trigger_flags.repeats = true;
+        assert_eq!(one_shot(&map, &progression), 0);
+    }
+}
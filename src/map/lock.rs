@@ -0,0 +1,186 @@
+//! Lock numbers, which locked doors and scripts use to require the player to hold certain keys. Only the locks ZDoom
+//! defines for Doom's keys are known; ports can define others in LOCKDEFS.
+
+use std::fmt::{self, Display, Formatter};
+
+use crate::map::{line_def::Special, thing_type::ThingKind};
+
+/// The lock of a locked special
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
+pub enum Lock {
+    /// No key is needed
+    #[default]
+    None,
+    RedCard,
+    BlueCard,
+    YellowCard,
+    RedSkull,
+    BlueSkull,
+    YellowSkull,
+    /// Any key opens it
+    Any,
+    /// All six keys are needed
+    All,
+    /// The red keycard or the red skull key, as Doom's red doors need
+    Red,
+    /// The blue keycard or the blue skull key, as Doom's blue doors need
+    Blue,
+    /// The yellow keycard or the yellow skull key, as Doom's yellow doors need
+    Yellow,
+    /// A key of each color, keycard or skull key
+    AllColors,
+    /// A lock number not listed above, whose keys are unknown
+    Other(i16),
+}
+
+/// The keys of each color, keycard first
+const KEYS: [[ThingKind; 2]; 3] = [
+    [ThingKind::RedKeycard, ThingKind::RedSkullKey],
+    [ThingKind::BlueKeycard, ThingKind::BlueSkullKey],
+    [ThingKind::YellowKeycard, ThingKind::YellowSkullKey],
+];
+
+impl Lock {
+    /// Whether a player holding `keys` can open the lock, or `None` for an `Other` lock
+    pub fn is_opened_by(self, keys: &[ThingKind]) -> Option<bool> {
+        let has = |kind: &ThingKind| keys.contains(kind);
+        let has_color = |color: &[ThingKind; 2]| color.iter().any(has);
+
+        Some(match self {
+            Lock::None => true,
+            Lock::RedCard => has(&ThingKind::RedKeycard),
+            Lock::BlueCard => has(&ThingKind::BlueKeycard),
+            Lock::YellowCard => has(&ThingKind::YellowKeycard),
+            Lock::RedSkull => has(&ThingKind::RedSkullKey),
+            Lock::BlueSkull => has(&ThingKind::BlueSkullKey),
+            Lock::YellowSkull => has(&ThingKind::YellowSkullKey),
+            Lock::Any => KEYS.iter().flatten().any(has),
+            Lock::All => KEYS.iter().flatten().all(has),
+            Lock::Red => has_color(&KEYS[0]),
+            Lock::Blue => has_color(&KEYS[1]),
+            Lock::Yellow => has_color(&KEYS[2]),
+            Lock::AllColors => KEYS.iter().all(has_color),
+            Lock::Other(_) => return None,
+        })
+    }
+}
+
+impl From<i16> for Lock {
+    fn from(value: i16) -> Self {
+        match value {
+            0 => Lock::None,
+            1 => Lock::RedCard,
+            2 => Lock::BlueCard,
+            3 => Lock::YellowCard,
+            4 => Lock::RedSkull,
+            5 => Lock::BlueSkull,
+            6 => Lock::YellowSkull,
+            100 => Lock::Any,
+            101 => Lock::All,
+            129 => Lock::Red,
+            130 => Lock::Blue,
+            131 => Lock::Yellow,
+            229 => Lock::AllColors,
+            value => Lock::Other(value),
+        }
+    }
+}
+
+impl From<Lock> for i16 {
+    fn from(lock: Lock) -> Self {
+        match lock {
+            Lock::None => 0,
+            Lock::RedCard => 1,
+            Lock::BlueCard => 2,
+            Lock::YellowCard => 3,
+            Lock::RedSkull => 4,
+            Lock::BlueSkull => 5,
+            Lock::YellowSkull => 6,
+            Lock::Any => 100,
+            Lock::All => 101,
+            Lock::Red => 129,
+            Lock::Blue => 130,
+            Lock::Yellow => 131,
+            Lock::AllColors => 229,
+            Lock::Other(value) => value,
+        }
+    }
+}
+
+impl Display for Lock {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Lock::None => f.write_str("no key"),
+            Lock::RedCard => f.write_str("the red keycard"),
+            Lock::BlueCard => f.write_str("the blue keycard"),
+            Lock::YellowCard => f.write_str("the yellow keycard"),
+            Lock::RedSkull => f.write_str("the red skull key"),
+            Lock::BlueSkull => f.write_str("the blue skull key"),
+            Lock::YellowSkull => f.write_str("the yellow skull key"),
+            Lock::Any => f.write_str("any key"),
+            Lock::All => f.write_str("all six keys"),
+            Lock::Red => f.write_str("a red key"),
+            Lock::Blue => f.write_str("a blue key"),
+            Lock::Yellow => f.write_str("a yellow key"),
+            Lock::AllColors => f.write_str("a key of each color"),
+            Lock::Other(value) => write!(f, "lock {value}"),
+        }
+    }
+}
+
+impl Special {
+    /// The lock the special needs opened to activate, or `Lock::None` if it isn't locked
+    pub fn lock(&self) -> Lock {
+        match *self {
+            Special::DoorRaiseLocked { lock, .. }
+            | Special::DoorAnimated { lock, .. }
+            | Special::AcsLockedExecute { lock, .. }
+            | Special::AcsLockedExecuteDoor { lock, .. }
+            | Special::GenericDoor { lock, .. } => lock,
+            _ => Lock::None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::map::line_def::{DoomSpecial, TriggerFlags, UdmfSpecial};
+
+    #[test]
+    fn lock_numbers() {
+        for value in i16::MIN..=i16::MAX {
+            assert_eq!(i16::from(Lock::from(value)), value);
+        }
+        assert_eq!(
+            Special::try_from(UdmfSpecial::new(13, [1, 16, 0, 300, 0]))
+                .unwrap()
+                .lock(),
+            Lock::Other(300)
+        );
+
+        let (special, _) =
+            <(Special, TriggerFlags)>::try_from(DoomSpecial { value: 26, tag: 0 }).unwrap();
+        assert_eq!(special.lock(), Lock::Blue);
+        assert_eq!(UdmfSpecial::from(special).args[3], 130);
+
+        assert_eq!(
+            Lock::AllColors.is_opened_by(&[ThingKind::RedSkullKey, ThingKind::BlueKeycard]),
+            Some(false)
+        );
+        assert_eq!(
+            Lock::AllColors.is_opened_by(&[
+                ThingKind::RedSkullKey,
+                ThingKind::BlueKeycard,
+                ThingKind::YellowKeycard
+            ]),
+            Some(true)
+        );
+        assert_eq!(Lock::Other(7).is_opened_by(&[]), None);
+    }
+}
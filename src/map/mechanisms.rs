@@ -0,0 +1,272 @@
+//! Common constructs built out of specials, such as doors, lifts, staircases and teleporters, recognised from the
+//! lines which activate them and the sectors and things those lines refer to.
+
+use std::collections::BTreeMap;
+
+use crate::{
+    map::{
+        geometry::Side,
+        line_def::{LineDefKey, MotionDirection, MovingPlane, Special, TargetHeight, UdmfSpecial},
+        sector::SectorKey,
+        thing::ThingKey,
+        thing_type::{ThingCategory, ThingTypes},
+        Map,
+    },
+    Point,
+};
+
+/// A sector whose ceiling opens like a door
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Door {
+    pub sector: SectorKey,
+    /// The lines which open or close the door
+    pub triggers: Vec<LineDefKey>,
+}
+
+/// A sector whose floor lowers to let the player ride it up
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Lift {
+    pub sector: SectorKey,
+    /// The lines which lower the lift
+    pub triggers: Vec<LineDefKey>,
+}
+
+/// A staircase, built by a single line from the sectors with its tag
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Staircase {
+    pub trigger: LineDefKey,
+    /// The first step of each staircase built by the line. The other steps are found by the engine as it builds it.
+    pub first_steps: Vec<SectorKey>,
+}
+
+/// Where a teleporter sends things
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TeleportDestination {
+    /// Teleport destination things in the sectors with the teleporter's tag. If there are several, the engine uses
+    /// the first one it finds.
+    Things(Vec<ThingKey>),
+    /// The lines with the teleporter's destination id, for `Special::TeleportLine`
+    Lines(Vec<LineDefKey>),
+    /// The thing with the given tid, in the sectors with the teleporter's tag if it has one. `Thing`s don't have tids
+    /// yet, so the thing can't be found.
+    Tid(i16),
+}
+
+/// A line which teleports things crossing it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Teleporter {
+    pub line: LineDefKey,
+    pub destination: TeleportDestination,
+}
+
+/// The mechanisms of a map, as found by [Map::mechanisms]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Mechanisms {
+    pub doors: Vec<Door>,
+    pub lifts: Vec<Lift>,
+    pub staircases: Vec<Staircase>,
+    pub teleporters: Vec<Teleporter>,
+}
+
+impl Map {
+    /// Recognise the doors, lifts, staircases and teleporters of the map from the specials of its lines.
+    ///
+    /// Doors and lifts are recognised by how their specials move sectors, as in [Special::motion], so generalized and
+    /// Boom specials are included. Sectors moved by a special with no tag are taken to be the back sector of its line,
+    /// as for manual doors.
+    pub fn mechanisms(&self, thing_types: &ThingTypes) -> Mechanisms {
+        let graph = self.tag_graph();
+        let tagged = |tag: i16| -> &[SectorKey] {
+            graph
+                .sector_tags
+                .get(&tag)
+                .map(Vec::as_slice)
+                .unwrap_or_default()
+        };
+
+        let mut doors: BTreeMap<SectorKey, Vec<LineDefKey>> = BTreeMap::new();
+        let mut lifts: BTreeMap<SectorKey, Vec<LineDefKey>> = BTreeMap::new();
+        let mut mechanisms = Mechanisms::default();
+
+        for (key, line_def) in self.line_defs.iter() {
+            let special = &line_def.special;
+
+            match *special {
+                Special::Teleport { tid, tag, .. } | Special::TeleportNoFog { tid, tag, .. } => {
+                    let destination = if tid != 0 {
+                        TeleportDestination::Tid(tid)
+                    } else {
+                        TeleportDestination::Things(
+                            self.teleport_destinations(tagged(tag), thing_types),
+                        )
+                    };
+
+                    mechanisms.teleporters.push(Teleporter {
+                        line: key,
+                        destination,
+                    });
+                    continue;
+                }
+                Special::TeleportLine { destid, .. } => {
+                    mechanisms.teleporters.push(Teleporter {
+                        line: key,
+                        destination: TeleportDestination::Lines(
+                            graph.line_ids.get(&destid).cloned().unwrap_or_default(),
+                        ),
+                    });
+                    continue;
+                }
+                _ => {}
+            }
+
+            let Some(motion) = special.motion() else {
+                continue;
+            };
+            let sectors = match tag_arg(special) {
+                Some(0) | None => self
+                    .sector_on_side(line_def, Side::Back)
+                    .into_iter()
+                    .collect(),
+                Some(tag) => tagged(tag).to_vec(),
+            };
+
+            let opens_door = motion.target == TargetHeight::DoorOpen;
+            let closes_door = motion.plane == MovingPlane::Ceiling
+                && motion.direction == MotionDirection::Down
+                && motion.target == TargetHeight::Floor;
+            let lowers_lift = motion.plane == MovingPlane::Floor
+                && matches!(
+                    motion.direction,
+                    MotionDirection::DownThenUp | MotionDirection::Perpetual
+                );
+
+            if opens_door || closes_door {
+                for sector in sectors {
+                    doors.entry(sector).or_default().push(key);
+                }
+            } else if lowers_lift {
+                for sector in sectors {
+                    lifts.entry(sector).or_default().push(key);
+                }
+            } else if motion.target == TargetHeight::StairStep {
+                mechanisms.staircases.push(Staircase {
+                    trigger: key,
+                    first_steps: sectors,
+                });
+            }
+        }
+
+        mechanisms.doors = doors
+            .into_iter()
+            .map(|(sector, triggers)| Door { sector, triggers })
+            .collect();
+        mechanisms.lifts = lifts
+            .into_iter()
+            .map(|(sector, triggers)| Lift { sector, triggers })
+            .collect();
+
+        mechanisms
+    }
+
+    fn teleport_destinations(
+        &self,
+        sectors: &[SectorKey],
+        thing_types: &ThingTypes,
+    ) -> Vec<ThingKey> {
+        self.things
+            .iter()
+            .filter(|(_, thing)| {
+                thing
+                    .info(thing_types)
+                    .is_some_and(|info| info.category == ThingCategory::Teleport)
+            })
+            .filter(|(_, thing)| {
+                let position =
+                    Point::new(thing.position.x.into_float(), thing.position.y.into_float());
+                self.sector_at(position)
+                    .is_some_and(|sector| sectors.contains(&sector))
+            })
+            .map(|(key, _)| key)
+            .collect()
+    }
+}
+
+/// The sector tag a special moves, if it has a tag argument
+fn tag_arg(special: &Special) -> Option<i16> {
+    let args = UdmfSpecial::from(special.clone()).args;
+
+    special
+        .arg_names()
+        .iter()
+        .zip(args)
+        .find(|(&name, _)| name == "tag")
+        .map(|(_, tag)| tag)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::map::thing_type::Game;
+
+    #[test]
+    fn recognise_mechanisms() {
+        // A room with a manual door on its east wall, a lift to the south and a teleporter into the lift
+        let map = Map::load_udmf_textmap(
+            "MAP01".try_into().unwrap(),
+            r#"
+            namespace = "zdoom";
+            vertex { x = 0; y = 0; }
+            vertex { x = 128; y = 0; }
+            vertex { x = 128; y = 128; }
+            vertex { x = 0; y = 128; }
+            vertex { x = 144; y = 0; }
+            vertex { x = 144; y = 128; }
+            vertex { x = 0; y = -64; }
+            vertex { x = 128; y = -64; }
+            sector { heightceiling = 128; texturefloor = "FLAT1"; textureceiling = "FLAT1"; }
+            sector { heightceiling = 0; texturefloor = "FLAT1"; textureceiling = "FLAT1"; }
+            sector { id = 3; heightfloor = 64; heightceiling = 128; texturefloor = "FLAT1"; textureceiling = "FLAT1"; }
+            sidedef { sector = 0; }
+            sidedef { sector = 1; }
+            sidedef { sector = 2; }
+            linedef { v1 = 2; v2 = 1; sidefront = 0; sideback = 1; special = 12; arg1 = 16; arg2 = 150; playeruse = true; repeatspecial = true; }
+            linedef { v1 = 3; v2 = 2; sidefront = 0; special = 70; arg1 = 3; playercross = true; }
+            linedef { v1 = 0; v2 = 3; sidefront = 0; }
+            linedef { v1 = 1; v2 = 0; sidefront = 0; sideback = 2; special = 62; arg0 = 3; arg1 = 32; arg2 = 105; playeruse = true; }
+            linedef { v1 = 4; v2 = 1; sidefront = 1; }
+            linedef { v1 = 5; v2 = 4; sidefront = 1; }
+            linedef { v1 = 2; v2 = 5; sidefront = 1; }
+            linedef { v1 = 1; v2 = 7; sidefront = 2; }
+            linedef { v1 = 7; v2 = 6; sidefront = 2; }
+            linedef { v1 = 6; v2 = 0; sidefront = 2; }
+            thing { x = 64.0; y = -32.0; type = 14; angle = 90; }
+            thing { x = 64.0; y = 64.0; type = 14; angle = 90; }
+            "#,
+        )
+        .unwrap();
+        let sectors: Vec<_> = map.sectors.keys().collect();
+        let line_defs: Vec<_> = map.line_defs.keys().collect();
+        let things: Vec<_> = map.things.keys().collect();
+
+        let mechanisms = map.mechanisms(&ThingTypes::for_game(Game::Doom2));
+        assert_eq!(
+            mechanisms,
+            Mechanisms {
+                doors: vec![Door {
+                    sector: sectors[1],
+                    triggers: vec![line_defs[0]],
+                }],
+                lifts: vec![Lift {
+                    sector: sectors[2],
+                    triggers: vec![line_defs[3]],
+                }],
+                staircases: Vec::new(),
+                teleporters: vec![Teleporter {
+                    line: line_defs[1],
+                    destination: TeleportDestination::Things(vec![things[0]]),
+                }],
+            }
+        );
+    }
+}
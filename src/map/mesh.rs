@@ -0,0 +1,361 @@
+//! Exporting the floors, ceilings and walls of a map as a triangle mesh, so it can be previewed in 3D viewers. Every
+//! triangle is tagged with the flat or texture it's drawn with, which become material names in the exported file.
+//!
+//! Coordinates are in map units, with y up as in most 3D formats: a map point `(x, y)` at height `z` becomes
+//! `(x, z, -y)`.
+
+use std::{collections::HashMap, fmt::Write};
+
+use slotmap::SecondaryMap;
+
+use crate::{
+    map::{geometry::Side, sector::SectorKey, slope::Plane, LineDef, Map},
+    Point, String8,
+};
+
+/// The part of a map a triangle belongs to
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Surface {
+    Floor,
+    Ceiling,
+    /// A wall between a floor and a ceiling, as drawn with a one-sided line's middle texture
+    MiddleWall,
+    /// The wall under a higher floor on the other side of a two-sided line
+    LowerWall,
+    /// The wall over a lower ceiling on the other side of a two-sided line
+    UpperWall,
+}
+
+/// A triangle of a `Mesh`, facing out of the sector it belongs to
+#[derive(Clone, Debug, PartialEq)]
+pub struct Triangle {
+    /// Indices into `Mesh::vertices`, counterclockwise when seen from the front
+    pub indices: [usize; 3],
+    pub surface: Surface,
+    /// The flat or texture the triangle is drawn with
+    pub material: String8,
+}
+
+/// The geometry of a map as triangles, as built by [Map::mesh]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Mesh {
+    pub vertices: Vec<[f64; 3]>,
+    pub triangles: Vec<Triangle>,
+}
+
+/// The ceiling flat which draws the sky. Upper walls between two sky ceilings aren't drawn.
+const SKY_FLAT: &str = "F_SKY1";
+
+impl Mesh {
+    fn push(&mut self, corners: [[f64; 3]; 3], surface: Surface, material: String8) {
+        let start = self.vertices.len();
+        self.vertices.extend(corners);
+        self.triangles.push(Triangle {
+            indices: [start, start + 1, start + 2],
+            surface,
+            material,
+        });
+    }
+
+    /// Write the mesh as a Wavefront OBJ file, with the triangles grouped by material
+    pub fn to_obj(&self, name: &str) -> String {
+        let mut obj = String::new();
+        writeln!(obj, "o {name}").unwrap();
+        for [x, y, z] in &self.vertices {
+            writeln!(obj, "v {x} {y} {z}").unwrap();
+        }
+
+        let mut materials: Vec<&String8> = Vec::new();
+        let mut by_material: HashMap<&String8, Vec<&Triangle>> = HashMap::new();
+        for triangle in &self.triangles {
+            by_material
+                .entry(&triangle.material)
+                .or_insert_with(|| {
+                    materials.push(&triangle.material);
+                    Vec::new()
+                })
+                .push(triangle);
+        }
+
+        for material in materials {
+            writeln!(obj, "usemtl {}", material.to_string_lossy()).unwrap();
+            for triangle in &by_material[material] {
+                let [a, b, c] = triangle.indices.map(|i| i + 1);
+                writeln!(obj, "f {a} {b} {c}").unwrap();
+            }
+        }
+
+        obj
+    }
+}
+
+impl Map {
+    /// Build a mesh of the map's floors, ceilings and walls, with sectors at their current heights and slopes.
+    ///
+    /// Floors and ceilings are cut into horizontal strips between the heights of their vertexes, so it takes many
+    /// more triangles than needed, but handles sectors with holes and several separate parts. The middle textures of
+    /// two-sided lines aren't included, as their height depends on the texture.
+    pub fn mesh(&self) -> Mesh {
+        let mut mesh = Mesh::default();
+        let planes = self.sector_planes();
+
+        for (key, sector) in self.sectors.iter() {
+            let (floor, ceiling) = planes[key];
+            let at = |point: Point<f64>, plane: &Plane| [point.x, plane.z_at(point), -point.y];
+
+            for [a, b, c] in self.sector_triangles(key) {
+                mesh.push(
+                    [at(a, &floor), at(b, &floor), at(c, &floor)],
+                    Surface::Floor,
+                    sector.floor_flat.clone(),
+                );
+                mesh.push(
+                    [at(a, &ceiling), at(c, &ceiling), at(b, &ceiling)],
+                    Surface::Ceiling,
+                    sector.ceiling_flat.clone(),
+                );
+            }
+        }
+
+        for line_def in self.line_defs.values() {
+            let Some((from, to)) = self.line_segment(line_def) else {
+                continue;
+            };
+
+            for (side, from, to) in [(Side::Front, from, to), (Side::Back, to, from)] {
+                self.push_walls(&mut mesh, &planes, line_def, side, from, to);
+            }
+        }
+
+        mesh
+    }
+
+    /// Add the walls of one side of a line, seen from its sector, to `mesh`
+    fn push_walls(
+        &self,
+        mesh: &mut Mesh,
+        planes: &SecondaryMap<SectorKey, (Plane, Plane)>,
+        line_def: &LineDef,
+        side: Side,
+        from: Point<f64>,
+        to: Point<f64>,
+    ) {
+        let Some(side_def) = self
+            .side_of(line_def, side)
+            .and_then(|key| self.side_defs.get(key))
+        else {
+            return;
+        };
+        let Some(sector) = self.sectors.get(side_def.sector) else {
+            return;
+        };
+        let other_side = match side {
+            Side::Front => Side::Back,
+            Side::Back => Side::Front,
+        };
+        let other = self.sector_on_side(line_def, other_side);
+
+        // Heights at the start and end of the wall
+        let heights = |plane: Plane| [plane.z_at(from), plane.z_at(to)];
+        let Some(&(floor, ceiling)) = planes.get(side_def.sector) else {
+            return;
+        };
+        let (floor, ceiling) = (heights(floor), heights(ceiling));
+
+        let mut wall = |bottom: [f64; 2], top: [f64; 2], surface: Surface, material: &String8| {
+            // Where the top dips below the bottom, the wall is pinched to nothing
+            let top = [top[0].max(bottom[0]), top[1].max(bottom[1])];
+            if top == bottom {
+                return;
+            }
+            let [from_bottom, to_bottom, to_top, from_top] = [
+                [from.x, bottom[0], -from.y],
+                [to.x, bottom[1], -to.y],
+                [to.x, top[1], -to.y],
+                [from.x, top[0], -from.y],
+            ];
+
+            mesh.push([from_bottom, to_bottom, to_top], surface, material.clone());
+            mesh.push([from_bottom, to_top, from_top], surface, material.clone());
+        };
+        let lowest = |a: [f64; 2], b: [f64; 2]| [a[0].min(b[0]), a[1].min(b[1])];
+        let highest = |a: [f64; 2], b: [f64; 2]| [a[0].max(b[0]), a[1].max(b[1])];
+
+        match other {
+            None => wall(
+                floor,
+                ceiling,
+                Surface::MiddleWall,
+                &side_def.middle_texture,
+            ),
+            Some(other_key) => {
+                let other = &self.sectors[other_key];
+                let (other_floor, other_ceiling) = planes[other_key];
+                let (other_floor, other_ceiling) = (heights(other_floor), heights(other_ceiling));
+
+                wall(
+                    floor,
+                    lowest(other_floor, ceiling),
+                    Surface::LowerWall,
+                    &side_def.lower_texture,
+                );
+
+                let sky = sector
+                    .ceiling_flat
+                    .eq_ignore_case(&String8::new_unchecked(SKY_FLAT))
+                    && other
+                        .ceiling_flat
+                        .eq_ignore_case(&String8::new_unchecked(SKY_FLAT));
+                if !sky {
+                    wall(
+                        highest(other_ceiling, floor),
+                        ceiling,
+                        Surface::UpperWall,
+                        &side_def.upper_texture,
+                    );
+                }
+            }
+        }
+    }
+
+    /// Triangles covering a sector, counterclockwise seen from above.
+    ///
+    /// The sector is cut into strips between consecutive heights of its vertexes. In each strip, its edges cross from
+    /// one side to the other, and pairing them up from left to right gives the trapezoids inside the sector, which
+    /// are each cut into two triangles.
+    fn sector_triangles(&self, sector: SectorKey) -> Vec<[Point<f64>; 3]> {
+        let edges: Vec<(Point<f64>, Point<f64>)> = self
+            .line_defs
+            .values()
+            .filter(|line_def| {
+                let front = self.sector_on_side(line_def, Side::Front) == Some(sector);
+                let back = self.sector_on_side(line_def, Side::Back) == Some(sector);
+                // Lines with the sector on both sides aren't on its boundary
+                front != back
+            })
+            .filter_map(|line_def| self.line_segment(line_def))
+            .collect();
+
+        let mut ys: Vec<f64> = edges.iter().flat_map(|(a, b)| [a.y, b.y]).collect();
+        ys.sort_by(f64::total_cmp);
+        ys.dedup();
+
+        let x_at =
+            |(a, b): &(Point<f64>, Point<f64>), y: f64| a.x + (y - a.y) * (b.x - a.x) / (b.y - a.y);
+
+        let mut triangles = Vec::new();
+        for slab in ys.windows(2) {
+            let (bottom, top) = (slab[0], slab[1]);
+            let middle = (bottom + top) / 2.0;
+
+            let mut crossings: Vec<(f64, f64, f64)> = edges
+                .iter()
+                .filter(|(a, b)| a.y.min(b.y) < middle && middle < a.y.max(b.y))
+                .map(|edge| (x_at(edge, middle), x_at(edge, bottom), x_at(edge, top)))
+                .collect();
+            crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+            for pair in crossings.chunks_exact(2) {
+                let (left, right) = (pair[0], pair[1]);
+                let corners = [
+                    Point::new(left.1, bottom),
+                    Point::new(right.1, bottom),
+                    Point::new(right.2, top),
+                    Point::new(left.2, top),
+                ];
+
+                if corners[0] != corners[1] {
+                    triangles.push([corners[0], corners[1], corners[2]]);
+                }
+                if corners[2] != corners[3] {
+                    triangles.push([corners[0], corners[2], corners[3]]);
+                }
+            }
+        }
+
+        triangles
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn room_with_step() {
+        // A room with a raised step in its east half, under a lower ceiling
+        let map = Map::load_udmf_textmap(
+            "MAP01".try_into().unwrap(),
+            r#"
+            namespace = "zdoom";
+            vertex { x = 0; y = 0; }
+            vertex { x = 0; y = 128; }
+            vertex { x = 64; y = 128; }
+            vertex { x = 64; y = 0; }
+            vertex { x = 128; y = 128; }
+            vertex { x = 128; y = 0; }
+            sector { heightceiling = 128; texturefloor = "FLOOR4_8"; textureceiling = "CEIL3_5"; }
+            sector { heightfloor = 16; heightceiling = 96; texturefloor = "STEP1"; textureceiling = "CEIL3_5"; }
+            sidedef { sector = 0; texturemiddle = "STARTAN2"; }
+            sidedef { sector = 1; texturemiddle = "STARTAN2"; }
+            sidedef { sector = 0; texturebottom = "STEP6"; texturetop = "STARTAN3"; }
+            sidedef { sector = 1; }
+            linedef { v1 = 0; v2 = 1; sidefront = 0; }
+            linedef { v1 = 1; v2 = 2; sidefront = 0; }
+            linedef { v1 = 2; v2 = 3; sidefront = 2; sideback = 3; twosided = true; }
+            linedef { v1 = 3; v2 = 0; sidefront = 0; }
+            linedef { v1 = 2; v2 = 4; sidefront = 1; }
+            linedef { v1 = 4; v2 = 5; sidefront = 1; }
+            linedef { v1 = 5; v2 = 3; sidefront = 1; }
+            "#,
+        )
+        .unwrap();
+
+        let mesh = map.mesh();
+        let count = |surface: Surface| {
+            mesh.triangles
+                .iter()
+                .filter(|triangle| triangle.surface == surface)
+                .count()
+        };
+        assert_eq!(count(Surface::Floor), 4);
+        assert_eq!(count(Surface::Ceiling), 4);
+        assert_eq!(count(Surface::MiddleWall), 12);
+        assert_eq!(count(Surface::LowerWall), 2);
+        assert_eq!(count(Surface::UpperWall), 2);
+
+        // Floors face up, and the step's riser faces west, into the lower room
+        let normal = |triangle: &Triangle| {
+            let [a, b, c] = triangle.indices.map(|i| mesh.vertices[i]);
+            let (u, v) = (
+                [b[0] - a[0], b[1] - a[1], b[2] - a[2]],
+                [c[0] - a[0], c[1] - a[1], c[2] - a[2]],
+            );
+            [
+                u[1] * v[2] - u[2] * v[1],
+                u[2] * v[0] - u[0] * v[2],
+                u[0] * v[1] - u[1] * v[0],
+            ]
+        };
+        for triangle in &mesh.triangles {
+            let [x, y, _] = normal(triangle);
+            match triangle.surface {
+                Surface::Floor => assert!(y > 0.0),
+                Surface::Ceiling => assert!(y < 0.0),
+                Surface::LowerWall => {
+                    assert!(x < 0.0);
+                    assert_eq!(triangle.material, "STEP6");
+                }
+                _ => {}
+            }
+        }
+
+        let obj = mesh.to_obj("MAP01");
+        assert!(obj.starts_with("o MAP01\n"));
+        assert_eq!(obj.matches("usemtl ").count(), 6);
+        assert_eq!(
+            obj.lines().filter(|line| line.starts_with("f ")).count(),
+            24
+        );
+    }
+}
@@ -0,0 +1,670 @@
+use std::thread;
+
+use crate::{
+    map::{
+        geometry::{check_coordinate, GeometryOverflow, Side},
+        line_def::LineDefKey,
+        lint::EntityRef,
+        sector::SectorKey,
+        Map,
+    },
+    Point,
+};
+
+pub mod cache;
+
+pub use self::cache::NodeCache;
+
+/// Distances smaller than this are treated as zero when classifying points against partition lines
+const EPSILON: f64 = 1e-6;
+
+/// The cost of splitting a seg relative to an imbalance of one seg between the two sides of a partition
+const SPLIT_COST: usize = 8;
+
+/// At most this many segs are tried as partition lines for each node, evenly sampled from the candidates
+const MAX_PARTITION_CANDIDATES: usize = 64;
+
+/// A piece of one side of a `LineDef`, as stored in a BSP tree
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Seg {
+    pub from: Point<f64>,
+    pub to: Point<f64>,
+    pub line_def: LineDefKey,
+    pub side: Side,
+    /// The distance from the start of the line side to the start of this seg
+    pub offset: f64,
+}
+
+impl Seg {
+    fn delta(&self) -> Point<f64> {
+        Point::new(self.to.x - self.from.x, self.to.y - self.from.y)
+    }
+
+    fn length(&self) -> f64 {
+        let delta = self.delta();
+        delta.x.hypot(delta.y)
+    }
+}
+
+/// A convex region of the map, made of consecutive segs in `Nodes::segs`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SubSector {
+    pub first_seg: usize,
+    pub seg_count: usize,
+}
+
+/// A child of a `Node`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Child {
+    Node(usize),
+    SubSector(usize),
+}
+
+/// An axis-aligned bounding box
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BoundingBox {
+    pub min: Point<f64>,
+    pub max: Point<f64>,
+}
+
+impl BoundingBox {
+    fn of_segs(segs: &[Seg]) -> Self {
+        let mut bbox = BoundingBox {
+            min: Point::new(f64::INFINITY, f64::INFINITY),
+            max: Point::new(f64::NEG_INFINITY, f64::NEG_INFINITY),
+        };
+
+        for point in segs.iter().flat_map(|seg| [seg.from, seg.to]) {
+            bbox.min.x = bbox.min.x.min(point.x);
+            bbox.min.y = bbox.min.y.min(point.y);
+            bbox.max.x = bbox.max.x.max(point.x);
+            bbox.max.y = bbox.max.y.max(point.y);
+        }
+
+        bbox
+    }
+
+    /// Whether the boxes overlap, including touching at an edge
+    pub fn intersects(&self, other: &BoundingBox) -> bool {
+        self.min.x <= other.max.x
+            && other.min.x <= self.max.x
+            && self.min.y <= other.max.y
+            && other.min.y <= self.max.y
+    }
+}
+
+/// A node of a BSP tree, splitting space along a partition line
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Node {
+    pub origin: Point<f64>,
+    pub delta: Point<f64>,
+    /// The bounding boxes of the front (right) and back (left) children
+    pub bboxes: [BoundingBox; 2],
+    /// The front (right) and back (left) children
+    pub children: [Child; 2],
+}
+
+impl Node {
+    fn is_in_front(&self, point: Point<f64>) -> bool {
+        side_value(self.origin, self.delta, point) < EPSILON
+    }
+}
+
+/// A BSP tree built from a `Map` by [Map::build_nodes].
+///
+/// As in the Doom formats, children are stored before their parents, so the root is the last node.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Nodes {
+    pub segs: Vec<Seg>,
+    pub sub_sectors: Vec<SubSector>,
+    pub nodes: Vec<Node>,
+    /// `None` if the map has no lines
+    pub root: Option<Child>,
+}
+
+/// Configuration for [Map::build_nodes]
+#[derive(Clone, Debug)]
+pub struct NodeBuildOptions {
+    /// Subtrees are built on separate threads down to this depth. 0 builds everything on the calling thread.
+    ///
+    /// The output is the same regardless of this setting.
+    pub parallel_depth: usize,
+}
+
+impl Default for NodeBuildOptions {
+    fn default() -> Self {
+        Self { parallel_depth: 3 }
+    }
+}
+
+/// A BSP subtree, before being flattened into `Nodes`
+enum Tree {
+    Leaf(Vec<Seg>),
+    Node {
+        origin: Point<f64>,
+        delta: Point<f64>,
+        front: Box<Tree>,
+        back: Box<Tree>,
+    },
+}
+
+impl Map {
+    /// Build a BSP tree of the map's line sides. Zero-length lines and lines with invalid vertexes are skipped.
+    ///
+    /// Fails if a vertex is outside `COORDINATE_RANGE`, or a line is too long for its partition deltas to fit, as
+    /// the engine's fixed point arithmetic would overflow on either.
+    pub fn build_nodes(&self, options: &NodeBuildOptions) -> Result<Nodes, GeometryOverflow> {
+        for (key, vertex) in self.vertexes.iter() {
+            check_coordinate(EntityRef::Vertex(key), "x", vertex.position.x.into_float())?;
+            check_coordinate(EntityRef::Vertex(key), "y", vertex.position.y.into_float())?;
+        }
+
+        let mut segs = Vec::new();
+
+        for (key, line_def) in self.line_defs.iter() {
+            let Some((from, to)) = self.line_segment(line_def) else {
+                continue;
+            };
+
+            check_coordinate(EntityRef::LineDef(key), "x delta", to.x - from.x)?;
+            check_coordinate(EntityRef::LineDef(key), "y delta", to.y - from.y)?;
+
+            if (to.x - from.x).abs() < EPSILON && (to.y - from.y).abs() < EPSILON {
+                continue;
+            }
+
+            segs.push(Seg {
+                from,
+                to,
+                line_def: key,
+                side: Side::Front,
+                offset: 0.0,
+            });
+
+            if line_def.right_side.is_some() {
+                segs.push(Seg {
+                    from: to,
+                    to: from,
+                    line_def: key,
+                    side: Side::Back,
+                    offset: 0.0,
+                });
+            }
+        }
+
+        let mut nodes = Nodes::default();
+
+        if !segs.is_empty() {
+            let tree = build_tree(segs, options.parallel_depth);
+            nodes.root = Some(nodes.flatten(tree).0);
+        }
+
+        Ok(nodes)
+    }
+
+    /// The sector containing `point`, found through the BSP tree rather than by testing the map's lines. `None` if
+    /// the tree is empty, or the subsector's segs no longer match the map's lines.
+    pub fn subsector_at(&self, nodes: &Nodes, point: Point<f64>) -> Option<(usize, SectorKey)> {
+        let sub_sector = nodes.sub_sector_at(point)?;
+        Some((sub_sector, self.sector_of_sub_sector(nodes, sub_sector)?))
+    }
+
+    /// The sector a subsector is in, from the side of the line its first seg is on. `None` if the subsector or its
+    /// segs aren't in `nodes`.
+    pub fn sector_of_sub_sector(&self, nodes: &Nodes, sub_sector: usize) -> Option<SectorKey> {
+        let seg = nodes.segs_of(sub_sector)?.first()?;
+        self.sector_on_side(self.line_defs.get(seg.line_def)?, seg.side)
+    }
+}
+
+impl Nodes {
+    /// The index of the subsector containing `point`, found by walking down the tree as the engine does. Points on
+    /// a partition line go to its front. The BSP covers the whole plane, so points outside the map are in whichever
+    /// subsector is beyond the nearest walls. `None` if the tree is empty.
+    pub fn sub_sector_at(&self, point: Point<f64>) -> Option<usize> {
+        let mut child = self.root?;
+
+        loop {
+            match child {
+                Child::SubSector(index) => return Some(index),
+                Child::Node(index) => {
+                    let node = &self.nodes[index];
+                    child = node.children[usize::from(!node.is_in_front(point))];
+                }
+            }
+        }
+    }
+
+    /// The segs of a subsector. `None` if there's no such subsector, or its segs run past the end of `segs`.
+    pub fn segs_of(&self, sub_sector: usize) -> Option<&[Seg]> {
+        let sub_sector = self.sub_sectors.get(sub_sector)?;
+        self.segs
+            .get(sub_sector.first_seg..)?
+            .get(..sub_sector.seg_count)
+    }
+
+    /// The subsectors which may overlap `bbox`, skipping the subtrees whose bounding boxes don't, in tree order
+    pub fn sub_sectors_in(&self, bbox: &BoundingBox) -> Vec<usize> {
+        let mut found = Vec::new();
+        let mut stack: Vec<Child> = self.root.into_iter().collect();
+
+        while let Some(child) = stack.pop() {
+            match child {
+                Child::SubSector(index) => found.push(index),
+                Child::Node(index) => {
+                    let node = &self.nodes[index];
+                    for side in [1, 0] {
+                        if node.bboxes[side].intersects(bbox) {
+                            stack.push(node.children[side]);
+                        }
+                    }
+                }
+            }
+        }
+
+        found
+    }
+
+    /// Every subsector, ordered from nearest to furthest from `view` as the renderer visits them: at each node, the
+    /// side `view` is on comes first
+    pub fn front_to_back(&self, view: Point<f64>) -> Vec<usize> {
+        let mut order = Vec::with_capacity(self.sub_sectors.len());
+        let mut stack: Vec<Child> = self.root.into_iter().collect();
+
+        while let Some(child) = stack.pop() {
+            match child {
+                Child::SubSector(index) => order.push(index),
+                Child::Node(index) => {
+                    let node = &self.nodes[index];
+                    let near = usize::from(!node.is_in_front(view));
+                    stack.push(node.children[1 - near]);
+                    stack.push(node.children[near]);
+                }
+            }
+        }
+
+        order
+    }
+
+    /// Append a subtree, returning its root and bounding box
+    fn flatten(&mut self, tree: Tree) -> (Child, BoundingBox) {
+        match tree {
+            Tree::Leaf(segs) => {
+                let bbox = BoundingBox::of_segs(&segs);
+
+                self.sub_sectors.push(SubSector {
+                    first_seg: self.segs.len(),
+                    seg_count: segs.len(),
+                });
+                self.segs.extend(segs);
+
+                (Child::SubSector(self.sub_sectors.len() - 1), bbox)
+            }
+
+            Tree::Node {
+                origin,
+                delta,
+                front,
+                back,
+            } => {
+                let (front, front_bbox) = self.flatten(*front);
+                let (back, back_bbox) = self.flatten(*back);
+
+                self.nodes.push(Node {
+                    origin,
+                    delta,
+                    bboxes: [front_bbox, back_bbox],
+                    children: [front, back],
+                });
+
+                let bbox = BoundingBox {
+                    min: Point::new(
+                        front_bbox.min.x.min(back_bbox.min.x),
+                        front_bbox.min.y.min(back_bbox.min.y),
+                    ),
+                    max: Point::new(
+                        front_bbox.max.x.max(back_bbox.max.x),
+                        front_bbox.max.y.max(back_bbox.max.y),
+                    ),
+                };
+
+                (Child::Node(self.nodes.len() - 1), bbox)
+            }
+        }
+    }
+}
+
+fn build_tree(segs: Vec<Seg>, parallel_depth: usize) -> Tree {
+    let Some(partition) = choose_partition(&segs) else {
+        return Tree::Leaf(segs);
+    };
+
+    let origin = partition.from;
+    let delta = partition.delta();
+    let (front, back) = split_segs(segs, origin, delta);
+
+    let (front, back) = if parallel_depth > 0 {
+        thread::scope(|scope| {
+            let front = scope.spawn(|| build_tree(front, parallel_depth - 1));
+            let back = build_tree(back, parallel_depth - 1);
+
+            (front.join().expect("node builder thread panicked"), back)
+        })
+    } else {
+        (build_tree(front, 0), build_tree(back, 0))
+    };
+
+    Tree::Node {
+        origin,
+        delta,
+        front: Box::new(front),
+        back: Box::new(back),
+    }
+}
+
+/// Which side of a partition a seg is on
+enum Classification {
+    Front,
+    Back,
+    /// The seg crosses the partition, at the given fraction of its length
+    Split(f64),
+}
+
+/// The signed distance-like value of a point relative to a line: negative on the front (right) side
+fn side_value(origin: Point<f64>, delta: Point<f64>, point: Point<f64>) -> f64 {
+    let length = delta.x.hypot(delta.y);
+    (delta.x * (point.y - origin.y) - delta.y * (point.x - origin.x)) / length
+}
+
+fn classify(seg: &Seg, origin: Point<f64>, delta: Point<f64>) -> Classification {
+    let a = side_value(origin, delta, seg.from);
+    let b = side_value(origin, delta, seg.to);
+
+    let a_front = a < EPSILON;
+    let b_front = b < EPSILON;
+    let a_back = a > -EPSILON;
+    let b_back = b > -EPSILON;
+
+    if a.abs() < EPSILON && b.abs() < EPSILON {
+        // Colinear: segs facing the same way as the partition go in front
+        let seg_delta = seg.delta();
+        if seg_delta.x * delta.x + seg_delta.y * delta.y > 0.0 {
+            Classification::Front
+        } else {
+            Classification::Back
+        }
+    } else if a_front && b_front {
+        Classification::Front
+    } else if a_back && b_back {
+        Classification::Back
+    } else {
+        Classification::Split(a / (a - b))
+    }
+}
+
+/// The best seg to partition along, or `None` if the segs already form a convex region
+fn choose_partition(segs: &[Seg]) -> Option<Seg> {
+    let step = segs.len().div_ceil(MAX_PARTITION_CANDIDATES).max(1);
+
+    // The sample can miss the only segs which divide the region, so it's only convex if none of the segs do
+    best_partition(segs, segs.iter().step_by(step)).or_else(|| {
+        if step > 1 {
+            best_partition(segs, segs.iter())
+        } else {
+            None
+        }
+    })
+}
+
+/// The cheapest of `candidates` to partition `segs` along, or `None` if none of them divides the segs
+fn best_partition<'a>(segs: &[Seg], candidates: impl Iterator<Item = &'a Seg>) -> Option<Seg> {
+    let mut best: Option<(usize, Seg)> = None;
+
+    for candidate in candidates {
+        let origin = candidate.from;
+        let delta = candidate.delta();
+
+        let (mut front, mut back, mut splits) = (0usize, 0usize, 0);
+        for seg in segs.iter() {
+            match classify(seg, origin, delta) {
+                Classification::Front => front += 1,
+                Classification::Back => back += 1,
+                Classification::Split(_) => splits += 1,
+            }
+        }
+
+        // A partition with everything on one side doesn't divide the region
+        if splits == 0 && (front == 0 || back == 0) {
+            continue;
+        }
+
+        let cost = splits * SPLIT_COST + front.abs_diff(back);
+        if best.is_none_or(|(best_cost, _)| cost < best_cost) {
+            best = Some((cost, *candidate));
+        }
+    }
+
+    best.map(|(_, seg)| seg)
+}
+
+fn split_segs(segs: Vec<Seg>, origin: Point<f64>, delta: Point<f64>) -> (Vec<Seg>, Vec<Seg>) {
+    let mut front = Vec::new();
+    let mut back = Vec::new();
+
+    for seg in segs {
+        match classify(&seg, origin, delta) {
+            Classification::Front => front.push(seg),
+            Classification::Back => back.push(seg),
+            Classification::Split(t) => {
+                let seg_delta = seg.delta();
+                let split_point =
+                    Point::new(seg.from.x + seg_delta.x * t, seg.from.y + seg_delta.y * t);
+
+                let first = Seg {
+                    to: split_point,
+                    ..seg
+                };
+                let second = Seg {
+                    from: split_point,
+                    offset: seg.offset + first.length(),
+                    ..seg
+                };
+
+                // The start of the seg is on the front side if its side value is negative
+                if side_value(origin, delta, seg.from) < 0.0 {
+                    front.push(first);
+                    back.push(second);
+                } else {
+                    back.push(first);
+                    front.push(second);
+                }
+            }
+        }
+    }
+
+    (front, back)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        map::{line_def, LineDef, Sector, SideDef, Vertex},
+        number::Number,
+    };
+
+    /// Two square rooms side by side, joined by a two-sided line, with a pillar in the first one
+    fn test_map() -> Map {
+        let mut map = Map::new("MAP01".try_into().unwrap());
+        let first = map.sectors.insert(Sector::default());
+        let second = map.sectors.insert(Sector::default());
+
+        let vertex = |map: &mut Map, x: i32, y: i32| {
+            map.vertexes.insert(Vertex {
+                position: Point::new(Number::Int(x), Number::Int(y)),
+                ..Default::default()
+            })
+        };
+        let side = |map: &mut Map, sector: SectorKey| {
+            map.side_defs.insert(SideDef {
+                sector,
+                offset: Point::default(),
+                upper_texture: Default::default(),
+                middle_texture: Default::default(),
+                lower_texture: Default::default(),
+            })
+        };
+        let line = |map: &mut Map, from, to, front: SectorKey, back: Option<SectorKey>| {
+            let left_side = side(map, front);
+            let right_side = back.map(|back| side(map, back));
+            map.line_defs.insert(LineDef {
+                from,
+                to,
+                left_side,
+                right_side,
+                id: line_def::NO_ID,
+                flags: Default::default(),
+                special: Default::default(),
+                trigger_flags: Default::default(),
+            });
+        };
+
+        let corners = [(0, 0), (0, 256), (256, 256), (256, 0), (512, 256), (512, 0)]
+            .map(|(x, y)| vertex(&mut map, x, y));
+        let [a, b, c, d, e, f] = corners;
+
+        line(&mut map, a, b, first, None);
+        line(&mut map, b, c, first, None);
+        line(&mut map, c, d, first, Some(second));
+        line(&mut map, d, a, first, None);
+
+        line(&mut map, c, e, second, None);
+        line(&mut map, e, f, second, None);
+        line(&mut map, f, d, second, None);
+
+        // Anticlockwise, so the pillar's walls face into the first room
+        let pillar =
+            [(96, 96), (160, 96), (160, 160), (96, 160)].map(|(x, y)| vertex(&mut map, x, y));
+        for i in 0..pillar.len() {
+            line(
+                &mut map,
+                pillar[i],
+                pillar[(i + 1) % pillar.len()],
+                first,
+                None,
+            );
+        }
+
+        map
+    }
+
+    #[test]
+    fn parallel_build_matches_serial() {
+        let map = test_map();
+
+        let serial = map
+            .build_nodes(&NodeBuildOptions { parallel_depth: 0 })
+            .unwrap();
+        let parallel = map
+            .build_nodes(&NodeBuildOptions { parallel_depth: 4 })
+            .unwrap();
+
+        assert_eq!(serial, parallel);
+        assert_eq!(serial.root, Some(Child::Node(serial.nodes.len() - 1)));
+
+        // Every subsector must be convex: no seg may be behind another seg of the same subsector
+        for sub_sector in serial.sub_sectors.iter() {
+            let segs = &serial.segs[sub_sector.first_seg..][..sub_sector.seg_count];
+
+            for a in segs {
+                for b in segs {
+                    assert!(side_value(a.from, a.delta(), b.from) < EPSILON);
+                    assert!(side_value(a.from, a.delta(), b.to) < EPSILON);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn queries_through_tree() {
+        let map = test_map();
+        let nodes = map.build_nodes(&NodeBuildOptions::default()).unwrap();
+
+        for point in [Point::new(32.0, 32.0), Point::new(400.0, 200.0)] {
+            let (sub_sector, sector) = map.subsector_at(&nodes, point).unwrap();
+            assert_eq!(Some(sector), map.sector_at(point));
+
+            let order = nodes.front_to_back(point);
+            assert_eq!(order[0], sub_sector);
+            let mut sorted = order.clone();
+            sorted.sort();
+            assert_eq!(sorted, (0..nodes.sub_sectors.len()).collect::<Vec<_>>());
+
+            let around = BoundingBox {
+                min: Point::new(point.x - 1.0, point.y - 1.0),
+                max: Point::new(point.x + 1.0, point.y + 1.0),
+            };
+            let near = nodes.sub_sectors_in(&around);
+            assert!(near.contains(&sub_sector));
+            assert!(near.len() < nodes.sub_sectors.len());
+        }
+
+        let past_end = nodes.sub_sectors.len();
+        assert_eq!(nodes.segs_of(past_end), None);
+        assert_eq!(map.sector_of_sub_sector(&nodes, past_end), None);
+
+        let mut short = nodes.clone();
+        short
+            .segs
+            .truncate(short.sub_sectors[past_end - 1].first_seg);
+        assert_eq!(short.segs_of(past_end - 1), None);
+        assert_eq!(map.sector_of_sub_sector(&short, past_end - 1), None);
+    }
+
+    #[test]
+    fn partition_missed_by_sampling() {
+        let seg = |from: (f64, f64), to: (f64, f64)| Seg {
+            from: Point::new(from.0, from.1),
+            to: Point::new(to.0, to.1),
+            line_def: LineDefKey::default(),
+            side: Side::Front,
+            offset: 0.0,
+        };
+
+        // A clockwise polygon with enough sides to be sampled, and a wall inside it which isn't sampled
+        let corners = MAX_PARTITION_CANDIDATES * 2 + 1;
+        let corner = |i: usize| {
+            let angle = -(i as f64) * std::f64::consts::TAU / corners as f64;
+            (1024.0 * angle.cos(), 1024.0 * angle.sin())
+        };
+        let mut segs: Vec<_> = (0..corners)
+            .map(|i| seg(corner(i), corner(i + 1)))
+            .collect();
+        segs.insert(1, seg((-64.0, 0.0), (64.0, 0.0)));
+
+        assert!(choose_partition(&segs).is_some());
+        assert!(choose_partition(&segs[2..]).is_none());
+    }
+
+    #[test]
+    fn line_too_long_for_segs() {
+        let mut map = test_map();
+        let [from, to] = [-20000, 20000].map(|x| {
+            map.vertexes.insert(Vertex {
+                position: Point::new(Number::Int(x), Number::Int(-64)),
+                ..Default::default()
+            })
+        });
+        let line = map.line_defs.insert(LineDef {
+            from,
+            to,
+            ..map.line_defs.values().next().unwrap().clone()
+        });
+
+        // Both ends are in range, but the seg's x delta of 40000 isn't
+        let overflow = map.build_nodes(&NodeBuildOptions::default()).unwrap_err();
+        assert_eq!(overflow.entity, EntityRef::LineDef(line));
+        assert_eq!((overflow.field, overflow.value), ("x delta", 40000.0));
+    }
+}
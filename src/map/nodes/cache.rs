@@ -0,0 +1,577 @@
+use std::{
+    collections::HashMap,
+    fs,
+    hash::Hasher,
+    io::{self, Write},
+    path::PathBuf,
+};
+
+use slotmap::{Key, KeyData};
+
+use crate::{
+    fnv::Fnv1a,
+    map::{
+        blockmap::Blockmap,
+        geometry::Side,
+        nodes::{BoundingBox, Child, Node, NodeBuildOptions, Nodes, Seg, SubSector},
+        Map,
+    },
+    wad::{LumpError, LumpReader, WriteLe},
+    Point,
+};
+
+/// Identifies node cache files, and must change whenever their layout does
+const MAGIC: &[u8; 8] = b"WDLNODE1";
+
+/// Identifies blockmap cache files, as for `MAGIC`
+const BLOCKMAP_MAGIC: &[u8; 8] = b"WDLBMAP1";
+
+/// The extensions of the files a persistent cache stores nodes and blockmaps in
+const NODES_EXTENSION: &str = "nodes";
+const BLOCKMAP_EXTENSION: &str = "blockmap";
+
+/// A cache of built BSP trees and blockmaps, keyed by [Map::geometry_hash].
+///
+/// Both only depend on the map's lines, so editing things, sectors or textures doesn't require a rebuild.
+/// A persistent cache also stores each tree and blockmap in a directory, so they survive between sessions.
+#[derive(Debug, Default)]
+pub struct NodeCache {
+    directory: Option<PathBuf>,
+    nodes: HashMap<u64, Nodes>,
+    blockmaps: HashMap<u64, Blockmap>,
+}
+
+impl NodeCache {
+    /// A cache that only lives as long as this value
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    /// A cache backed by files in `directory`, which is created if needed
+    pub fn persistent(directory: impl Into<PathBuf>) -> Self {
+        Self {
+            directory: Some(directory.into()),
+            nodes: HashMap::new(),
+            blockmaps: HashMap::new(),
+        }
+    }
+
+    /// The nodes for `map`, only building them if its geometry isn't in the cache.
+    ///
+    /// Unreadable or corrupt cache files are treated as missing, but failing to write one is an error. A
+    /// `GeometryOverflow` from building is returned as an `InvalidData` error.
+    pub fn get_or_build(&mut self, map: &Map, options: &NodeBuildOptions) -> io::Result<&Nodes> {
+        let hash = map.geometry_hash();
+
+        if !self.nodes.contains_key(&hash) {
+            let nodes = match self.load(hash, NODES_EXTENSION, read_nodes) {
+                Some(nodes) => nodes,
+                None => {
+                    let nodes = map
+                        .build_nodes(options)
+                        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+                    self.store(hash, NODES_EXTENSION, |bytes| write_nodes(bytes, &nodes))?;
+                    nodes
+                }
+            };
+
+            self.nodes.insert(hash, nodes);
+        }
+
+        Ok(&self.nodes[&hash])
+    }
+
+    /// The blockmap for `map`, only building it if its geometry isn't in the cache. Errors are as for
+    /// [NodeCache::get_or_build].
+    pub fn get_or_build_blockmap(&mut self, map: &Map) -> io::Result<&Blockmap> {
+        let hash = map.geometry_hash();
+
+        if !self.blockmaps.contains_key(&hash) {
+            let blockmap = match self.load(hash, BLOCKMAP_EXTENSION, read_blockmap) {
+                Some(blockmap) => blockmap,
+                None => {
+                    let blockmap = map
+                        .build_blockmap()
+                        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+                    self.store(hash, BLOCKMAP_EXTENSION, |bytes| {
+                        write_blockmap(bytes, &blockmap)
+                    })?;
+                    blockmap
+                }
+            };
+
+            self.blockmaps.insert(hash, blockmap);
+        }
+
+        Ok(&self.blockmaps[&hash])
+    }
+
+    /// Forget every cached tree and blockmap, including the ones stored on disk. Only the cache's own files are removed, the
+    /// directory and anything else in it are left alone.
+    pub fn clear(&mut self) -> io::Result<()> {
+        self.nodes.clear();
+        self.blockmaps.clear();
+
+        let Some(directory) = &self.directory else {
+            return Ok(());
+        };
+
+        let entries = match fs::read_dir(directory) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+            entries => entries?,
+        };
+
+        for entry in entries {
+            let entry = entry?;
+            if is_cache_file_name(&entry.file_name().to_string_lossy())
+                && entry.file_type()?.is_file()
+            {
+                fs::remove_file(entry.path())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn path(&self, hash: u64, extension: &str) -> Option<PathBuf> {
+        self.directory
+            .as_ref()
+            .map(|directory| directory.join(format!("{hash:016x}.{extension}")))
+    }
+
+    fn load<T>(
+        &self,
+        hash: u64,
+        extension: &str,
+        read: fn(&[u8]) -> Result<T, LumpError>,
+    ) -> Option<T> {
+        let bytes = fs::read(self.path(hash, extension)?).ok()?;
+        read(&bytes).ok()
+    }
+
+    fn store(
+        &self,
+        hash: u64,
+        extension: &str,
+        write: impl FnOnce(&mut Vec<u8>) -> io::Result<()>,
+    ) -> io::Result<()> {
+        let Some(path) = self.path(hash, extension) else {
+            return Ok(());
+        };
+
+        let mut bytes = Vec::new();
+        write(&mut bytes)?;
+
+        fs::create_dir_all(path.parent().unwrap())?;
+        fs::write(path, bytes)
+    }
+}
+
+/// Whether a file name is one `NodeCache::path` generates
+fn is_cache_file_name(name: &str) -> bool {
+    name.split_once('.').is_some_and(|(hash, extension)| {
+        [NODES_EXTENSION, BLOCKMAP_EXTENSION].contains(&extension)
+            && hash.len() == 16
+            && hash.bytes().all(|b| b.is_ascii_hexdigit())
+    })
+}
+
+impl Map {
+    /// A hash of everything the BSP tree and blockmap depend on: the keys, positions and sidedness of every line.
+    ///
+    /// The hash is stable across runs and platforms, so it can be used to key persistent caches.
+    pub fn geometry_hash(&self) -> u64 {
+        let mut hasher = Fnv1a::default();
+
+        for (key, line_def) in self.line_defs.iter() {
+            hasher.write_u64(key.data().as_ffi());
+
+            match self.line_segment(line_def) {
+                Some((from, to)) => {
+                    for value in [from.x, from.y, to.x, to.y] {
+                        hasher.write_u64(value.to_bits());
+                    }
+                }
+                None => hasher.write_u8(0xff),
+            }
+
+            hasher.write_u8(line_def.right_side.is_some().into());
+        }
+
+        hasher.finish()
+    }
+}
+
+fn write_point<W: Write>(writer: &mut W, point: Point<f64>) -> io::Result<()> {
+    writer.write_f64(point.x)?;
+    writer.write_f64(point.y)
+}
+
+fn write_index<W: Write>(writer: &mut W, index: usize) -> io::Result<()> {
+    let index = u32::try_from(index).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("index {index} doesn't fit in a node cache file"),
+        )
+    })?;
+
+    writer.write_u32(index)
+}
+
+fn write_child<W: Write>(writer: &mut W, child: Option<Child>) -> io::Result<()> {
+    let (tag, index) = match child {
+        None => (0u8, 0),
+        Some(Child::Node(index)) => (1, index),
+        Some(Child::SubSector(index)) => (2, index),
+    };
+
+    writer.write_u8(tag)?;
+    write_index(writer, index)
+}
+
+fn write_nodes<W: Write>(writer: &mut W, nodes: &Nodes) -> io::Result<()> {
+    writer.write_all(MAGIC)?;
+    for len in [nodes.segs.len(), nodes.sub_sectors.len(), nodes.nodes.len()] {
+        write_index(writer, len)?;
+    }
+
+    for seg in nodes.segs.iter() {
+        write_point(writer, seg.from)?;
+        write_point(writer, seg.to)?;
+        writer.write_u64(seg.line_def.data().as_ffi())?;
+        writer.write_u8((seg.side == Side::Back).into())?;
+        writer.write_f64(seg.offset)?;
+    }
+
+    for sub_sector in nodes.sub_sectors.iter() {
+        write_index(writer, sub_sector.first_seg)?;
+        write_index(writer, sub_sector.seg_count)?;
+    }
+
+    for node in nodes.nodes.iter() {
+        write_point(writer, node.origin)?;
+        write_point(writer, node.delta)?;
+        for bbox in node.bboxes {
+            write_point(writer, bbox.min)?;
+            write_point(writer, bbox.max)?;
+        }
+        for child in node.children {
+            write_child(writer, Some(child))?;
+        }
+    }
+
+    write_child(writer, nodes.root)
+}
+
+fn write_blockmap<W: Write>(writer: &mut W, blockmap: &Blockmap) -> io::Result<()> {
+    writer.write_all(BLOCKMAP_MAGIC)?;
+    writer.write_i16(blockmap.origin.x)?;
+    writer.write_i16(blockmap.origin.y)?;
+    writer.write_u16(blockmap.columns)?;
+    writer.write_u16(blockmap.rows)?;
+
+    for block in blockmap.blocks.iter() {
+        write_index(writer, block.len())?;
+        for line_def in block {
+            writer.write_u64(line_def.data().as_ffi())?;
+        }
+    }
+
+    Ok(())
+}
+
+fn read_index(reader: &mut LumpReader) -> Result<usize, LumpError> {
+    let index = reader.u32()?;
+    usize::try_from(index).map_err(|_| reader.invalid("index", index.into()))
+}
+
+fn read_point(reader: &mut LumpReader) -> Result<Point<f64>, LumpError> {
+    Ok(Point::new(reader.f64()?, reader.f64()?))
+}
+
+/// Read a child, checking that it refers to one of the first `node_count` nodes or one of the `sub_sector_count`
+/// subsectors. Limiting node children to the nodes stored before their parent keeps the tree acyclic.
+fn read_child(
+    reader: &mut LumpReader,
+    node_count: usize,
+    sub_sector_count: usize,
+) -> Result<Option<Child>, LumpError> {
+    let tag = reader.u8()?;
+    let index = read_index(reader)?;
+
+    let (child, count) = match tag {
+        0 => return Ok(None),
+        1 => (Child::Node(index), node_count),
+        2 => (Child::SubSector(index), sub_sector_count),
+        _ => return Err(reader.invalid("child tag", tag.into())),
+    };
+
+    if index < count {
+        Ok(Some(child))
+    } else {
+        Err(reader.invalid("child index", i64::try_from(index).unwrap_or(i64::MAX)))
+    }
+}
+
+fn read_nodes(bytes: &[u8]) -> Result<Nodes, LumpError> {
+    let mut reader = LumpReader::new("node cache", bytes);
+
+    if &reader.bytes::<8>()? != MAGIC {
+        return Err(reader.invalid("magic", 0));
+    }
+
+    let seg_count = read_index(&mut reader)?;
+    let sub_sector_count = read_index(&mut reader)?;
+    let node_count = read_index(&mut reader)?;
+
+    let segs = (0..seg_count)
+        .map(|_| {
+            Ok(Seg {
+                from: read_point(&mut reader)?,
+                to: read_point(&mut reader)?,
+                line_def: KeyData::from_ffi(reader.u64()?).into(),
+                side: match reader.u8()? {
+                    0 => Side::Front,
+                    _ => Side::Back,
+                },
+                offset: reader.f64()?,
+            })
+        })
+        .collect::<Result<_, LumpError>>()?;
+
+    let sub_sectors = (0..sub_sector_count)
+        .map(|_| {
+            let first_seg = read_index(&mut reader)?;
+            let count = read_index(&mut reader)?;
+
+            if first_seg
+                .checked_add(count)
+                .is_none_or(|end| end > seg_count)
+            {
+                return Err(reader.invalid(
+                    "sub sector seg count",
+                    i64::try_from(count).unwrap_or(i64::MAX),
+                ));
+            }
+
+            Ok(SubSector {
+                first_seg,
+                seg_count: count,
+            })
+        })
+        .collect::<Result<_, LumpError>>()?;
+
+    let nodes = (0..node_count)
+        .map(|index| {
+            let origin = read_point(&mut reader)?;
+            let delta = read_point(&mut reader)?;
+
+            let mut bboxes = [BoundingBox {
+                min: Point::default(),
+                max: Point::default(),
+            }; 2];
+            for bbox in bboxes.iter_mut() {
+                bbox.min = read_point(&mut reader)?;
+                bbox.max = read_point(&mut reader)?;
+            }
+
+            let mut children = [Child::SubSector(0); 2];
+            for child in children.iter_mut() {
+                *child = read_child(&mut reader, index, sub_sector_count)?
+                    .ok_or_else(|| reader.invalid("child tag", 0))?;
+            }
+
+            Ok(Node {
+                origin,
+                delta,
+                bboxes,
+                children,
+            })
+        })
+        .collect::<Result<_, LumpError>>()?;
+
+    let root = read_child(&mut reader, node_count, sub_sector_count)?;
+
+    Ok(Nodes {
+        segs,
+        sub_sectors,
+        nodes,
+        root,
+    })
+}
+
+fn read_blockmap(bytes: &[u8]) -> Result<Blockmap, LumpError> {
+    let mut reader = LumpReader::new("blockmap cache", bytes);
+
+    if &reader.bytes::<8>()? != BLOCKMAP_MAGIC {
+        return Err(reader.invalid("magic", 0));
+    }
+
+    let origin = Point::new(reader.i16()?, reader.i16()?);
+    let columns = reader.u16()?;
+    let rows = reader.u16()?;
+
+    let blocks = (0..usize::from(columns) * usize::from(rows))
+        .map(|_| {
+            let count = read_index(&mut reader)?;
+            (0..count)
+                .map(|_| Ok(KeyData::from_ffi(reader.u64()?).into()))
+                .collect()
+        })
+        .collect::<Result<_, LumpError>>()?;
+
+    Ok(Blockmap {
+        origin,
+        columns,
+        rows,
+        blocks,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        map::{line_def, LineDef, Sector, SideDef, Vertex},
+        number::Number,
+        units::Height,
+    };
+
+    #[test]
+    fn cache_round_trip() {
+        let mut map = Map::new("MAP01".try_into().unwrap());
+        let sector = map.sectors.insert(Sector::default());
+        let corners = [(0, 0), (0, 128), (64, 192), (128, 128), (128, 0)].map(|(x, y)| {
+            map.vertexes.insert(Vertex {
+                position: Point::new(Number::Int(x), Number::Int(y)),
+                ..Default::default()
+            })
+        });
+
+        for i in 0..corners.len() {
+            let side = map.side_defs.insert(SideDef {
+                sector,
+                offset: Point::default(),
+                upper_texture: Default::default(),
+                middle_texture: Default::default(),
+                lower_texture: Default::default(),
+            });
+
+            map.line_defs.insert(LineDef {
+                from: corners[i],
+                to: corners[(i + 2) % corners.len()],
+                left_side: side,
+                right_side: None,
+                id: line_def::NO_ID,
+                flags: Default::default(),
+                special: Default::default(),
+                trigger_flags: Default::default(),
+            });
+        }
+
+        let directory =
+            std::env::temp_dir().join(format!("waddle-node-cache-{}", std::process::id()));
+        let options = NodeBuildOptions::default();
+
+        let built = map.build_nodes(&options).unwrap();
+        let built_blockmap = map.build_blockmap().unwrap();
+        let mut cache = NodeCache::persistent(&directory);
+        assert_eq!(cache.get_or_build(&map, &options).unwrap(), &built);
+        assert_eq!(cache.get_or_build_blockmap(&map).unwrap(), &built_blockmap);
+
+        // A fresh cache reads the tree and blockmap back from disk
+        let mut cache = NodeCache::persistent(&directory);
+        let hash = map.geometry_hash();
+        assert_eq!(cache.load(hash, NODES_EXTENSION, read_nodes), Some(built));
+        assert_eq!(
+            cache.load(hash, BLOCKMAP_EXTENSION, read_blockmap),
+            Some(built_blockmap)
+        );
+
+        // Non-geometric edits keep the hash, moving a vertex changes it
+        map.sectors[sector].ceiling_height = Height(256);
+        assert_eq!(map.geometry_hash(), hash);
+        map.vertexes[corners[2]].position.y = Number::Int(256);
+        assert_ne!(map.geometry_hash(), hash);
+
+        // Clearing only removes the cache's own files
+        let other = directory.join("notes.txt");
+        fs::write(&other, "keep me").unwrap();
+        cache.clear().unwrap();
+        assert_eq!(cache.load(hash, NODES_EXTENSION, read_nodes), None);
+        assert_eq!(cache.load(hash, BLOCKMAP_EXTENSION, read_blockmap), None);
+        assert!(other.exists());
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn corrupt_cache_files() {
+        let leaf = |first_seg, seg_count| SubSector {
+            first_seg,
+            seg_count,
+        };
+        let node = |children| Node {
+            origin: Point::default(),
+            delta: Point::new(1.0, 0.0),
+            bboxes: [BoundingBox {
+                min: Point::default(),
+                max: Point::default(),
+            }; 2],
+            children,
+        };
+        let seg = Seg {
+            from: Point::default(),
+            to: Point::new(1.0, 0.0),
+            line_def: Default::default(),
+            side: Side::Front,
+            offset: 0.0,
+        };
+        let read_back = |nodes: &Nodes| {
+            let mut bytes = Vec::new();
+            write_nodes(&mut bytes, nodes).unwrap();
+            read_nodes(&bytes)
+        };
+
+        let valid = Nodes {
+            segs: vec![seg; 2],
+            sub_sectors: vec![leaf(0, 1), leaf(1, 1)],
+            nodes: vec![node([Child::SubSector(0), Child::SubSector(1)])],
+            root: Some(Child::Node(0)),
+        };
+        assert_eq!(read_back(&valid).unwrap(), valid);
+
+        // Segs past the end
+        let mut nodes = valid.clone();
+        nodes.sub_sectors[1] = leaf(1, usize::try_from(u32::MAX).unwrap());
+        assert!(read_back(&nodes).is_err());
+
+        // Dangling children
+        let mut nodes = valid.clone();
+        nodes.nodes[0].children[1] = Child::SubSector(2);
+        assert!(read_back(&nodes).is_err());
+
+        // A node which is its own child
+        let mut nodes = valid.clone();
+        nodes.nodes[0].children[0] = Child::Node(0);
+        assert!(read_back(&nodes).is_err());
+
+        // A dangling root
+        let mut nodes = valid;
+        nodes.root = Some(Child::Node(1));
+        assert!(read_back(&nodes).is_err());
+
+        // A blockmap with fewer blocks than its size needs
+        let blockmap = Blockmap {
+            origin: Point::new(-64, 0),
+            columns: 2,
+            rows: 1,
+            blocks: vec![vec![Default::default()], vec![]],
+        };
+        let mut bytes = Vec::new();
+        write_blockmap(&mut bytes, &blockmap).unwrap();
+        assert_eq!(read_blockmap(&bytes).unwrap(), blockmap);
+        bytes.truncate(bytes.len() - 4);
+        assert!(read_blockmap(&bytes).is_err());
+    }
+}
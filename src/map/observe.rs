@@ -0,0 +1,185 @@
+//! Notifications of changes to a map, so that views of it (an editor's display, lint results, ...) can be updated
+//! incrementally instead of rescanning the whole map.
+//!
+//! Changes made directly through `Map`'s fields aren't seen: edit through an [ObservedMap] to be notified.
+
+use std::{ops::Deref, sync::mpsc::Sender};
+
+use slotmap::SecondaryMap;
+
+use crate::map::{
+    diff::{ApplyError, Change, MapDiff},
+    lint::EntityRef,
+    selection::KeyRemap,
+    EntityKey, Map, MapEntity,
+};
+
+/// A change to a single entity
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MapEvent {
+    Added(EntityRef),
+    Modified(EntityRef),
+    Removed(EntityRef),
+}
+
+/// Something notified of every change made through an `ObservedMap`
+pub trait MapObserver {
+    fn notify(&mut self, event: MapEvent);
+}
+
+impl<F: FnMut(MapEvent)> MapObserver for F {
+    fn notify(&mut self, event: MapEvent) {
+        self(event)
+    }
+}
+
+/// Events are sent to the receiving end of a channel, e.g. on a UI thread. Events are dropped once the receiver is.
+impl MapObserver for Sender<MapEvent> {
+    fn notify(&mut self, event: MapEvent) {
+        let _ = self.send(event);
+    }
+}
+
+/// A map being edited, which notifies an observer of every change. It dereferences to the `Map` for reading.
+#[derive(Debug)]
+pub struct ObservedMap<'m, O> {
+    map: &'m mut Map,
+    observer: O,
+}
+
+impl Map {
+    /// Edit this map while notifying `observer` of every change
+    pub fn observed<O: MapObserver>(&mut self, observer: O) -> ObservedMap<'_, O> {
+        ObservedMap {
+            map: self,
+            observer,
+        }
+    }
+}
+
+impl<O: MapObserver> ObservedMap<'_, O> {
+    pub fn insert<E: MapEntity>(&mut self, entity: E) -> E::Key {
+        let key = E::entities_mut(self.map).insert(entity);
+        self.observer.notify(MapEvent::Added(key.entity_ref()));
+
+        key
+    }
+
+    /// Remove an entity, without removing the entities which refer to it
+    pub fn remove<K: EntityKey>(&mut self, key: K) -> Option<K::Entity> {
+        let entity = K::Entity::entities_mut(self.map).remove(key)?;
+        self.observer.notify(MapEvent::Removed(key.entity_ref()));
+
+        Some(entity)
+    }
+
+    /// Change an entity with `f`, returning its result, or `None` if there's no such entity. The entity is reported as
+    /// modified even if `f` leaves it as it was.
+    pub fn modify<K: EntityKey, R>(
+        &mut self,
+        key: K,
+        f: impl FnOnce(&mut K::Entity) -> R,
+    ) -> Option<R> {
+        let result = f(K::Entity::entities_mut(self.map).get_mut(key)?);
+        self.observer.notify(MapEvent::Modified(key.entity_ref()));
+
+        Some(result)
+    }
+
+    /// Apply a diff as with [Map::apply], notifying the observer of each change once the whole diff has been applied
+    pub fn apply(&mut self, diff: &MapDiff) -> Result<KeyRemap, ApplyError> {
+        let remap = self.map.apply(diff)?;
+
+        notify_changes(&mut self.observer, &diff.vertexes, &remap.vertexes);
+        notify_changes(&mut self.observer, &diff.line_defs, &remap.line_defs);
+        notify_changes(&mut self.observer, &diff.sectors, &remap.sectors);
+        notify_changes(&mut self.observer, &diff.side_defs, &remap.side_defs);
+        notify_changes(&mut self.observer, &diff.things, &remap.things);
+
+        Ok(remap)
+    }
+
+    pub fn into_observer(self) -> O {
+        self.observer
+    }
+}
+
+impl<O> Deref for ObservedMap<'_, O> {
+    type Target = Map;
+
+    fn deref(&self) -> &Map {
+        self.map
+    }
+}
+
+fn notify_changes<K: EntityKey, V>(
+    observer: &mut impl MapObserver,
+    changes: &[(K, Change<V>)],
+    added: &SecondaryMap<K, K>,
+) {
+    for (key, change) in changes {
+        let event = match change {
+            Change::Added(_) => MapEvent::Added(added[*key].entity_ref()),
+            Change::Modified { .. } => MapEvent::Modified(key.entity_ref()),
+            Change::Removed(_) => MapEvent::Removed(key.entity_ref()),
+        };
+        observer.notify(event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::mpsc;
+
+    use crate::{
+        map::{thing, Thing, Vertex},
+        units::{Angle, Height},
+    };
+
+    #[test]
+    fn observe_changes() {
+        let mut map = Map::new("MAP01".try_into().unwrap());
+        let (sender, receiver) = mpsc::channel();
+        let mut observed = map.observed(sender);
+
+        let vertex = observed.insert(Vertex::default());
+        let thing = observed.insert(Thing {
+            position: Default::default(),
+            height: Height(0),
+            angle: Angle(0),
+            type_: 1,
+            flags: Default::default(),
+            special: thing::Special::None,
+        });
+        assert_eq!(
+            observed.modify(thing, |thing| thing.angle = Angle(90)),
+            Some(())
+        );
+        assert!(observed.remove(vertex).is_some());
+        assert!(observed.remove(vertex).is_none());
+        drop(observed);
+
+        assert_eq!(
+            receiver.try_iter().collect::<Vec<_>>(),
+            vec![
+                MapEvent::Added(EntityRef::Vertex(vertex)),
+                MapEvent::Added(EntityRef::Thing(thing)),
+                MapEvent::Modified(EntityRef::Thing(thing)),
+                MapEvent::Removed(EntityRef::Vertex(vertex)),
+            ]
+        );
+
+        let base = map.clone();
+        map.things[thing].angle = Angle(180);
+        let diff = base.diff(&map);
+        let mut map = base;
+
+        let mut events = Vec::new();
+        map.observed(|event| events.push(event))
+            .apply(&diff)
+            .unwrap();
+        assert_eq!(events, vec![MapEvent::Modified(EntityRef::Thing(thing))]);
+    }
+}
@@ -0,0 +1,296 @@
+//! Polyobjects are groups of lines which move as one, set up by specials and things spread over the map: the lines
+//! are marked with `Special::PolyobjStartLine` or `Special::PolyobjExplicitLine`, an anchor thing marks the point
+//! they're placed relative to, and a spawn spot thing marks where the engine moves them at the start of the map. The
+//! things are numbered with the polyobject number in their angle.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::map::{
+    line_def::{LineDefKey, Special},
+    thing::ThingKey,
+    thing_type::{ThingKind, ThingTypes},
+    vertex::VertexKey,
+    Map,
+};
+
+/// How a polyobject affects things it runs into, as chosen by its spawn spot
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SpawnKind {
+    /// It stops
+    Normal,
+    /// It keeps moving, crushing the thing
+    Crushing,
+    /// It stops, and damages the thing
+    Harmful,
+}
+
+/// A polyobject, as found by [Map::polyobjects]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Polyobject {
+    pub anchor: Option<ThingKey>,
+    pub spawn_spot: Option<(ThingKey, SpawnKind)>,
+    /// The lines of the polyobject: the loop of lines from its start line, or its explicit lines by their order
+    pub lines: Vec<LineDefKey>,
+    /// The number of the polyobject which mirrors this one's movement, or 0 if there's none
+    pub mirror: i16,
+}
+
+/// A mistake in the setup of a polyobject, which stops it from spawning or moving as intended
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum PolyobjectIssue {
+    #[error("polyobject {po} has no anchor")]
+    MissingAnchor { po: i16 },
+
+    #[error("polyobject {po} has no spawn spot")]
+    MissingSpawnSpot { po: i16 },
+
+    #[error("polyobject {po} has no lines")]
+    MissingLines { po: i16 },
+
+    #[error("polyobject {po} has {} anchors or spawn spots", things.len())]
+    DuplicateThings { po: i16, things: Vec<ThingKey> },
+
+    #[error("polyobject {po} has both a start line and explicit lines")]
+    MixedLines { po: i16 },
+
+    #[error("the lines from the start line of polyobject {po} don't form a closed loop")]
+    OpenLoop { po: i16, start_line: LineDefKey },
+
+    #[error("the lines of polyobject {po} give different mirror polyobjects: {mirrors:?}")]
+    MirrorMismatch { po: i16, mirrors: Vec<i16> },
+
+    #[error("polyobject {po} is mirrored by polyobject {mirror}, which doesn't exist")]
+    MissingMirror { po: i16, mirror: i16 },
+}
+
+/// The polyobjects of a map and the problems with their setup
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Polyobjects {
+    /// Every polyobject with an anchor, spawn spot or line, by number
+    pub polyobjects: BTreeMap<i16, Polyobject>,
+    pub issues: Vec<PolyobjectIssue>,
+}
+
+impl Map {
+    /// Group the anchors, spawn spots and lines of each polyobject by its number, and check that they fit together
+    pub fn polyobjects(&self, thing_types: &ThingTypes) -> Polyobjects {
+        let mut polyobjects: BTreeMap<i16, Polyobject> = BTreeMap::new();
+        let mut issues = Vec::new();
+
+        let mut anchors: BTreeMap<i16, Vec<ThingKey>> = BTreeMap::new();
+        let mut spawn_spots: BTreeMap<i16, Vec<(ThingKey, SpawnKind)>> = BTreeMap::new();
+        for (key, thing) in self.things.iter() {
+            let Some(info) = thing_types.get(thing.type_) else {
+                continue;
+            };
+            let po = thing.angle.0;
+
+            match info.kind {
+                ThingKind::PolyobjectAnchor | ThingKind::HexenPolyobjectAnchor => {
+                    anchors.entry(po).or_default().push(key)
+                }
+                ThingKind::PolyobjectStartSpot | ThingKind::HexenPolyobjectStartSpot => spawn_spots
+                    .entry(po)
+                    .or_default()
+                    .push((key, SpawnKind::Normal)),
+                ThingKind::PolyobjectStartSpotCrush | ThingKind::HexenPolyobjectStartSpotCrush => {
+                    spawn_spots
+                        .entry(po)
+                        .or_default()
+                        .push((key, SpawnKind::Crushing))
+                }
+                ThingKind::PolyobjectStartSpotHurt => spawn_spots
+                    .entry(po)
+                    .or_default()
+                    .push((key, SpawnKind::Harmful)),
+                _ => {}
+            }
+        }
+
+        let mut start_lines: BTreeMap<i16, Vec<(LineDefKey, i16)>> = BTreeMap::new();
+        let mut explicit_lines: BTreeMap<i16, Vec<(i16, LineDefKey, i16)>> = BTreeMap::new();
+        for (key, line_def) in self.line_defs.iter() {
+            match line_def.special {
+                Special::PolyobjStartLine { po, mirror, .. } => {
+                    start_lines.entry(po).or_default().push((key, mirror))
+                }
+                Special::PolyobjExplicitLine {
+                    po, order, mirror, ..
+                } => explicit_lines
+                    .entry(po)
+                    .or_default()
+                    .push((order, key, mirror)),
+                _ => {}
+            }
+        }
+
+        let numbers: BTreeSet<i16> = anchors
+            .keys()
+            .chain(spawn_spots.keys())
+            .chain(start_lines.keys())
+            .chain(explicit_lines.keys())
+            .copied()
+            .collect();
+
+        for po in numbers {
+            let polyobject = polyobjects.entry(po).or_default();
+            let anchors = anchors.remove(&po).unwrap_or_default();
+            let spawn_spots = spawn_spots.remove(&po).unwrap_or_default();
+            let start_lines = start_lines.remove(&po).unwrap_or_default();
+            let mut explicit_lines = explicit_lines.remove(&po).unwrap_or_default();
+
+            polyobject.anchor = anchors.first().copied();
+            polyobject.spawn_spot = spawn_spots.first().copied();
+            if anchors.len() > 1 || spawn_spots.len() > 1 {
+                issues.push(PolyobjectIssue::DuplicateThings {
+                    po,
+                    things: anchors
+                        .iter()
+                        .copied()
+                        .chain(spawn_spots.iter().map(|&(thing, _)| thing))
+                        .collect(),
+                });
+            }
+            if polyobject.anchor.is_none() {
+                issues.push(PolyobjectIssue::MissingAnchor { po });
+            }
+            if polyobject.spawn_spot.is_none() {
+                issues.push(PolyobjectIssue::MissingSpawnSpot { po });
+            }
+
+            if !start_lines.is_empty() && !explicit_lines.is_empty() {
+                issues.push(PolyobjectIssue::MixedLines { po });
+            }
+
+            let mut mirrors: Vec<i16> = start_lines
+                .iter()
+                .map(|&(_, mirror)| mirror)
+                .chain(explicit_lines.iter().map(|&(.., mirror)| mirror))
+                .filter(|&mirror| mirror != 0)
+                .collect();
+            mirrors.sort();
+            mirrors.dedup();
+            polyobject.mirror = mirrors.first().copied().unwrap_or(0);
+            if mirrors.len() > 1 {
+                issues.push(PolyobjectIssue::MirrorMismatch { po, mirrors });
+            }
+
+            if let Some(&(start_line, _)) = start_lines.first() {
+                match self.line_loop(start_line) {
+                    Some(lines) => polyobject.lines = lines,
+                    None => {
+                        polyobject.lines = vec![start_line];
+                        issues.push(PolyobjectIssue::OpenLoop { po, start_line });
+                    }
+                }
+            } else {
+                explicit_lines.sort();
+                polyobject.lines = explicit_lines.iter().map(|&(_, key, _)| key).collect();
+            }
+
+            if polyobject.lines.is_empty() {
+                issues.push(PolyobjectIssue::MissingLines { po });
+            }
+        }
+
+        for (&po, polyobject) in &polyobjects {
+            if polyobject.mirror != 0 && !polyobjects.contains_key(&polyobject.mirror) {
+                issues.push(PolyobjectIssue::MissingMirror {
+                    po,
+                    mirror: polyobject.mirror,
+                });
+            }
+        }
+
+        Polyobjects {
+            polyobjects,
+            issues,
+        }
+    }
+
+    /// The lines joined end to start from `start` back to itself, as the engine follows them from a polyobject's start
+    /// line, or `None` if they don't lead back to it
+    fn line_loop(&self, start: LineDefKey) -> Option<Vec<LineDefKey>> {
+        let mut by_start: HashMap<VertexKey, LineDefKey> = HashMap::new();
+        for (key, line_def) in self.line_defs.iter() {
+            by_start.entry(line_def.from).or_insert(key);
+        }
+
+        let mut lines = vec![start];
+        let mut end = self.line_defs[start].to;
+        while end != self.line_defs[start].from {
+            let next = *by_start.get(&end)?;
+            if lines.len() > self.line_defs.len() || lines.contains(&next) {
+                return None;
+            }
+
+            lines.push(next);
+            end = self.line_defs[next].to;
+        }
+
+        Some(lines)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::map::thing_type::Game;
+
+    #[test]
+    fn find_polyobjects() {
+        let map = Map::load_udmf_textmap(
+            "MAP01".try_into().unwrap(),
+            r#"
+            namespace = "zdoom";
+            vertex { x = 0; y = 0; }
+            vertex { x = 16; y = 0; }
+            vertex { x = 16; y = 16; }
+            vertex { x = 128; y = 0; }
+            vertex { x = 144; y = 0; }
+            sector { texturefloor = "FLOOR4_8"; textureceiling = "CEIL3_5"; }
+            sidedef { sector = 0; }
+            linedef { v1 = 0; v2 = 1; sidefront = 0; special = 1; arg0 = 1; arg1 = 2; }
+            linedef { v1 = 1; v2 = 2; sidefront = 0; }
+            linedef { v1 = 2; v2 = 0; sidefront = 0; }
+            linedef { v1 = 4; v2 = 3; sidefront = 0; special = 5; arg0 = 3; arg1 = 2; arg2 = 2; }
+            linedef { v1 = 3; v2 = 4; sidefront = 0; special = 5; arg0 = 3; arg1 = 1; arg2 = 4; }
+            thing { x = 8.0; y = 4.0; type = 9300; angle = 1; }
+            thing { x = 64.0; y = 64.0; type = 9302; angle = 1; }
+            thing { x = 136.0; y = 0.0; type = 9300; angle = 3; }
+            "#,
+        )
+        .unwrap();
+        let line_defs: Vec<_> = map.line_defs.keys().collect();
+        let things: Vec<_> = map.things.keys().collect();
+
+        let Polyobjects {
+            polyobjects,
+            issues,
+        } = map.polyobjects(&ThingTypes::for_game(Game::Doom2));
+
+        assert_eq!(
+            polyobjects[&1],
+            Polyobject {
+                anchor: Some(things[0]),
+                spawn_spot: Some((things[1], SpawnKind::Crushing)),
+                lines: line_defs[..3].to_vec(),
+                mirror: 2,
+            }
+        );
+        assert_eq!(polyobjects[&3].lines, [line_defs[4], line_defs[3]]);
+        assert_eq!(
+            issues,
+            [
+                PolyobjectIssue::MissingSpawnSpot { po: 3 },
+                PolyobjectIssue::MirrorMismatch {
+                    po: 3,
+                    mirrors: vec![2, 4],
+                },
+                PolyobjectIssue::MissingMirror { po: 1, mirror: 2 },
+                PolyobjectIssue::MissingMirror { po: 3, mirror: 2 },
+            ]
+        );
+    }
+}
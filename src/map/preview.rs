@@ -0,0 +1,596 @@
+use std::fmt::Write;
+
+use crate::{
+    map::{
+        line_def::{LineDefKey, Special},
+        side_def::SideDefKey,
+        LineDef, Map, Sector,
+    },
+    wad::image::IndexedImage,
+    Point,
+};
+
+/// Which of the in-game automap modes to emulate when coloring a map preview.
+///
+/// These correspond to what the player would see in vanilla Doom's automap, with the lines that have not been seen
+/// yet treated as if they had.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum AutomapMode {
+    /// The regular automap, as seen by a player who has walked past every line
+    #[default]
+    Normal,
+
+    /// The automap as shown at the start of the level with the computer area map powerup: lines which are not
+    /// `already_on_map` are drawn grey, as they have not been seen yet
+    AllMap,
+
+    /// The automap with the `IDDT` cheat active, which reveals secrets and lines with no height change
+    Cheat,
+}
+
+/// The color class the automap assigns to a `LineDef`, following the vanilla Doom rules.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AutomapColor {
+    /// One-sided walls, and secret lines when not cheating
+    Wall,
+    /// Lines separating sectors with different floor heights
+    FloorChange,
+    /// Lines separating sectors with the same floor height but different ceiling heights
+    CeilingChange,
+    /// Lines with a teleport special
+    Teleporter,
+    /// Lines flagged as `secret`, only distinguishable in `AutomapMode::Cheat`
+    Secret,
+    /// Two-sided lines with no height change, only drawn in `AutomapMode::Cheat`
+    NoChange,
+    /// Lines revealed by the computer area map but not yet seen
+    Unseen,
+}
+
+impl AutomapColor {
+    /// The index in the Doom PLAYPAL of the base color used for this class
+    pub fn palette_index(self) -> u8 {
+        match self {
+            AutomapColor::Wall | AutomapColor::Secret => 176,
+            AutomapColor::FloorChange => 64,
+            AutomapColor::CeilingChange => 231,
+            AutomapColor::Teleporter => 184,
+            AutomapColor::NoChange => 96,
+            AutomapColor::Unseen => 99,
+        }
+    }
+
+    /// An approximation of the RGB color of `palette_index` in the Doom PLAYPAL
+    pub fn rgb(self) -> [u8; 3] {
+        match self {
+            AutomapColor::Wall => [255, 0, 0],
+            AutomapColor::Secret => [255, 0, 255],
+            AutomapColor::FloorChange => [191, 123, 75],
+            AutomapColor::CeilingChange => [255, 255, 0],
+            AutomapColor::Teleporter => [187, 0, 0],
+            AutomapColor::NoChange => [131, 131, 131],
+            AutomapColor::Unseen => [107, 107, 107],
+        }
+    }
+}
+
+impl Map {
+    /// The automap color of a single `LineDef`, or `None` if the automap would not draw it at all in the given mode.
+    ///
+    /// Lines with dangling keys are treated as one-sided.
+    pub fn automap_color(&self, line_def: &LineDef, mode: AutomapMode) -> Option<AutomapColor> {
+        if line_def.flags.not_on_map && mode != AutomapMode::Cheat {
+            return None;
+        }
+
+        if mode == AutomapMode::AllMap && !line_def.flags.already_on_map {
+            return Some(AutomapColor::Unseen);
+        }
+
+        let front = self.line_sector(line_def.left_side);
+        let back = line_def
+            .right_side
+            .and_then(|right_side| self.line_sector(right_side));
+
+        let (Some(front), Some(back)) = (front, back) else {
+            return Some(AutomapColor::Wall);
+        };
+
+        if matches!(
+            line_def.special,
+            Special::Teleport { .. } | Special::TeleportNoFog { .. } | Special::TeleportLine { .. }
+        ) {
+            Some(AutomapColor::Teleporter)
+        } else if line_def.flags.secret {
+            if mode == AutomapMode::Cheat {
+                Some(AutomapColor::Secret)
+            } else {
+                Some(AutomapColor::Wall)
+            }
+        } else if front.floor_height != back.floor_height {
+            Some(AutomapColor::FloorChange)
+        } else if front.ceiling_height != back.ceiling_height {
+            Some(AutomapColor::CeilingChange)
+        } else if mode == AutomapMode::Cheat {
+            Some(AutomapColor::NoChange)
+        } else {
+            None
+        }
+    }
+
+    /// All the `LineDef`s the automap would draw in the given mode, along with their color.
+    pub fn automap_lines(
+        &self,
+        mode: AutomapMode,
+    ) -> impl Iterator<Item = (LineDefKey, AutomapColor)> + '_ {
+        self.line_defs.iter().filter_map(move |(key, line_def)| {
+            self.automap_color(line_def, mode).map(|color| (key, color))
+        })
+    }
+
+    fn line_sector(&self, side_def: SideDefKey) -> Option<&Sector> {
+        self.side_defs
+            .get(side_def)
+            .and_then(|side_def| self.sectors.get(side_def.sector))
+    }
+}
+
+/// The palette index and color things are drawn with, the green of the automap's things with the `IDDT` cheat
+const THING_PALETTE_INDEX: u8 = 112;
+const THING_RGB: [u8; 3] = [119, 255, 111];
+
+/// The size of the markers things are drawn as, in map units: the radius of a player
+const THING_MARKER_RADIUS: f64 = 16.0;
+
+/// Where a rendered preview places the map: `scale` pixels per map unit, with the map point `origin` at the top left
+/// corner of the image. The y axis is flipped, so north is up as on the automap.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PreviewTransform {
+    pub origin: Point<f64>,
+    pub scale: f64,
+}
+
+impl Default for PreviewTransform {
+    fn default() -> Self {
+        Self {
+            origin: Point::new(0.0, 0.0),
+            scale: 1.0,
+        }
+    }
+}
+
+impl PreviewTransform {
+    /// The transform which centers the map's vertexes in a `width` by `height` image, as large as fits with
+    /// `margin` pixels left around it
+    pub fn fit(map: &Map, width: u16, height: u16, margin: u16) -> Self {
+        let mut points = map
+            .vertexes
+            .values()
+            .map(|vertex| vertex.position.map(|n| n.into_float()));
+        let Some(first) = points.next() else {
+            return Self::default();
+        };
+        let (min, max) = points.fold((first, first), |(min, max), point| {
+            (
+                Point::new(min.x.min(point.x), min.y.min(point.y)),
+                Point::new(max.x.max(point.x), max.y.max(point.y)),
+            )
+        });
+
+        let room = |size: u16| f64::from(size.saturating_sub(margin.saturating_mul(2)).max(1));
+        let (map_width, map_height) = ((max.x - min.x).max(1.0), (max.y - min.y).max(1.0));
+        let scale = (room(width) / map_width).min(room(height) / map_height);
+
+        // Split the space left over on the longer axis evenly on both sides
+        let center = Point::new((min.x + max.x) / 2.0, (min.y + max.y) / 2.0);
+        Self {
+            origin: Point::new(
+                center.x - f64::from(width) / scale / 2.0,
+                center.y + f64::from(height) / scale / 2.0,
+            ),
+            scale,
+        }
+    }
+
+    /// The position in the image of a point on the map
+    pub fn apply(&self, point: Point<f64>) -> Point<f64> {
+        Point::new(
+            (point.x - self.origin.x) * self.scale,
+            (self.origin.y - point.y) * self.scale,
+        )
+    }
+}
+
+/// Configuration for [Map::render_automap] and [Map::render_automap_svg]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PreviewOptions {
+    pub width: u16,
+    pub height: u16,
+    pub mode: AutomapMode,
+    pub transform: PreviewTransform,
+    /// Draw things as markers, as the automap does with the `IDDT` cheat
+    pub things: bool,
+}
+
+impl PreviewOptions {
+    /// A `width` by `height` preview of the whole map, with its things
+    pub fn fit(map: &Map, width: u16, height: u16) -> Self {
+        Self {
+            width,
+            height,
+            mode: AutomapMode::default(),
+            transform: PreviewTransform::fit(map, width, height, 8),
+            things: true,
+        }
+    }
+}
+
+impl Map {
+    /// Draw the map as the automap would, with the palette indices of [AutomapColor::palette_index] on a transparent
+    /// background. Two-sided lines are dimmed by drawing every other pixel of them, and things are drawn as crosses.
+    pub fn render_automap(&self, options: &PreviewOptions) -> IndexedImage {
+        let mut image = IndexedImage::new(options.width, options.height);
+        let transform = &options.transform;
+
+        for (line_def, color, two_sided) in self.preview_lines(options.mode) {
+            let Some((from, to)) = self.line_segment(line_def) else {
+                continue;
+            };
+            let segment = (transform.apply(from), transform.apply(to));
+            let Some((from, to)) = clip_segment(segment, options.width, options.height) else {
+                continue;
+            };
+            let pixels = line_pixels(from, to);
+            let step = if two_sided { 2 } else { 1 };
+            for (x, y) in pixels.step_by(step) {
+                plot(&mut image, x, y, color.palette_index());
+            }
+        }
+
+        if options.things {
+            let arm = (THING_MARKER_RADIUS * transform.scale).round().max(1.0) as i64;
+            for thing in self.things.values() {
+                let center = transform.apply(thing.position.map(|n| n.into_float()));
+                let reach = arm as f64;
+                if !(-reach..f64::from(options.width) + reach).contains(&center.x)
+                    || !(-reach..f64::from(options.height) + reach).contains(&center.y)
+                {
+                    continue;
+                }
+                let (x, y) = (center.x.round() as i64, center.y.round() as i64);
+                for i in -arm..=arm {
+                    plot(&mut image, x + i, y, THING_PALETTE_INDEX);
+                    plot(&mut image, x, y + i, THING_PALETTE_INDEX);
+                }
+            }
+        }
+
+        image
+    }
+
+    /// Draw the map as the automap would, as an SVG document with the colors of [AutomapColor::rgb] on black.
+    /// Two-sided lines are drawn at half opacity, and things are drawn as circles.
+    pub fn render_automap_svg(&self, options: &PreviewOptions) -> String {
+        let transform = &options.transform;
+        let (width, height) = (options.width, options.height);
+
+        let mut svg = String::new();
+        writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#
+        )
+        .unwrap();
+        writeln!(svg, r#"<rect width="100%" height="100%" fill="black"/>"#).unwrap();
+
+        for (line_def, color, two_sided) in self.preview_lines(options.mode) {
+            let Some((from, to)) = self.line_segment(line_def) else {
+                continue;
+            };
+            let (from, to) = (transform.apply(from), transform.apply(to));
+            let opacity = if two_sided {
+                r#" stroke-opacity="0.5""#
+            } else {
+                ""
+            };
+            writeln!(
+                svg,
+                r#"<line x1="{:.2}" y1="{:.2}" x2="{:.2}" y2="{:.2}" stroke="{}"{opacity}/>"#,
+                from.x,
+                from.y,
+                to.x,
+                to.y,
+                hex(color.rgb()),
+            )
+            .unwrap();
+        }
+
+        if options.things {
+            let radius = (THING_MARKER_RADIUS * transform.scale).max(1.0);
+            for thing in self.things.values() {
+                let center = transform.apply(thing.position.map(|n| n.into_float()));
+                writeln!(
+                    svg,
+                    r#"<circle cx="{:.2}" cy="{:.2}" r="{radius:.2}" fill="none" stroke="{}"/>"#,
+                    center.x,
+                    center.y,
+                    hex(THING_RGB),
+                )
+                .unwrap();
+            }
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// The lines the automap draws with their color and whether they're two-sided, two-sided first so one-sided
+    /// lines are drawn over them
+    fn preview_lines(&self, mode: AutomapMode) -> Vec<(&LineDef, AutomapColor, bool)> {
+        let mut lines: Vec<_> = self
+            .automap_lines(mode)
+            .map(|(key, color)| {
+                let line_def = &self.line_defs[key];
+                (line_def, color, line_def.right_side.is_some())
+            })
+            .collect();
+        lines.sort_by_key(|&(_, _, two_sided)| !two_sided);
+
+        lines
+    }
+}
+
+/// Clip a segment to the pixels of a `width` by `height` image, from the Liang-Barsky algorithm, or `None` if none of
+/// it is in the image. The ends are within the image once rounded.
+fn clip_segment(
+    (from, to): (Point<f64>, Point<f64>),
+    width: u16,
+    height: u16,
+) -> Option<(Point<f64>, Point<f64>)> {
+    if width == 0 || height == 0 || ![from.x, from.y, to.x, to.y].iter().all(|c| c.is_finite()) {
+        return None;
+    }
+
+    // Pixel centers are at whole coordinates, so the image spans half a pixel past them. Where a boundary cuts the
+    // segment, the clipped end is put exactly on it, as interpolating far off points loses precision.
+    let max = Point::new(f64::from(width), f64::from(height)) - Point::new(0.5, 0.5);
+    let delta = to - from;
+    let (mut enter, mut exit) = ((0.0_f64, None), (1.0_f64, None));
+    for (p, q, boundary) in [
+        (-delta.x, from.x + 0.5, Point::new(Some(-0.5), None)),
+        (delta.x, max.x - from.x, Point::new(Some(max.x), None)),
+        (-delta.y, from.y + 0.5, Point::new(None, Some(-0.5))),
+        (delta.y, max.y - from.y, Point::new(None, Some(max.y))),
+    ] {
+        if p == 0.0 {
+            if q < 0.0 {
+                return None;
+            }
+        } else if p < 0.0 && q / p > enter.0 {
+            enter = (q / p, Some(boundary));
+        } else if p > 0.0 && q / p < exit.0 {
+            exit = (q / p, Some(boundary));
+        }
+    }
+
+    let end = |(t, boundary): (f64, Option<Point<Option<f64>>>)| {
+        let point = from + delta * t;
+        let boundary = boundary.unwrap_or(Point::new(None, None));
+        Point::new(
+            boundary
+                .x
+                .unwrap_or(point.x)
+                .clamp(0.0, f64::from(width - 1)),
+            boundary
+                .y
+                .unwrap_or(point.y)
+                .clamp(0.0, f64::from(height - 1)),
+        )
+    };
+    (enter.0 <= exit.0).then(|| (end(enter), end(exit)))
+}
+
+/// The pixels of the line between two points in an image, from Bresenham's algorithm. The points must be clipped to
+/// the image with `clip_segment`, so the arithmetic can't overflow.
+fn line_pixels(from: Point<f64>, to: Point<f64>) -> impl Iterator<Item = (i64, i64)> {
+    let (mut x, mut y) = (from.x.round() as i64, from.y.round() as i64);
+    let (to_x, to_y) = (to.x.round() as i64, to.y.round() as i64);
+    let (dx, dy) = ((to_x - x).abs(), -(to_y - y).abs());
+    let (step_x, step_y) = ((to_x - x).signum(), (to_y - y).signum());
+    let mut error = dx + dy;
+    let mut done = false;
+
+    std::iter::from_fn(move || {
+        if done {
+            return None;
+        }
+        let pixel = (x, y);
+        if (x, y) == (to_x, to_y) {
+            done = true;
+        }
+
+        let doubled = 2 * error;
+        if doubled >= dy {
+            error += dy;
+            x += step_x;
+        }
+        if doubled <= dx {
+            error += dx;
+            y += step_y;
+        }
+
+        Some(pixel)
+    })
+}
+
+/// Set a pixel, ignoring pixels outside the image
+fn plot(image: &mut IndexedImage, x: i64, y: i64, index: u8) {
+    if let (Ok(x), Ok(y)) = (u16::try_from(x), u16::try_from(y)) {
+        image.set(x, y, Some(index)).ok();
+    }
+}
+
+fn hex([r, g, b]: [u8; 3]) -> String {
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::units::Height;
+
+    #[test]
+    fn automap_color() {
+        // Two rooms side by side, with a step between them
+        let mut map = Map::load_udmf_textmap(
+            "MAP01".try_into().unwrap(),
+            r#"
+            namespace = "zdoom";
+            vertex { x = 0; y = 0; }
+            vertex { x = 0; y = 128; }
+            vertex { x = 64; y = 128; }
+            vertex { x = 64; y = 0; }
+            vertex { x = 128; y = 128; }
+            vertex { x = 128; y = 0; }
+            sector { heightceiling = 128; texturefloor = "FLAT1"; textureceiling = "FLAT1"; }
+            sector { heightfloor = 16; heightceiling = 128; texturefloor = "FLAT1"; textureceiling = "FLAT1"; }
+            sidedef { sector = 0; }
+            sidedef { sector = 1; }
+            linedef { v1 = 0; v2 = 1; sidefront = 0; }
+            linedef { v1 = 2; v2 = 3; sidefront = 0; sideback = 1; twosided = true; }
+            "#,
+        )
+        .unwrap();
+        let keys: Vec<_> = map.line_defs.keys().collect();
+        let sectors: Vec<_> = map.sectors.keys().collect();
+        let color = |map: &Map, mode| map.automap_color(&map.line_defs[keys[1]], mode);
+
+        assert_eq!(
+            map.automap_color(&map.line_defs[keys[0]], AutomapMode::Normal),
+            Some(AutomapColor::Wall)
+        );
+        assert_eq!(
+            color(&map, AutomapMode::Normal),
+            Some(AutomapColor::FloorChange)
+        );
+
+        map.sectors[sectors[1]].floor_height = Height(0);
+        assert_eq!(color(&map, AutomapMode::Normal), None);
+        assert_eq!(
+            color(&map, AutomapMode::Cheat),
+            Some(AutomapColor::NoChange)
+        );
+
+        map.sectors[sectors[1]].ceiling_height = Height(96);
+        assert_eq!(
+            color(&map, AutomapMode::Normal),
+            Some(AutomapColor::CeilingChange)
+        );
+        assert_eq!(color(&map, AutomapMode::AllMap), Some(AutomapColor::Unseen));
+        map.line_defs[keys[1]].flags.already_on_map = true;
+        assert_eq!(
+            color(&map, AutomapMode::AllMap),
+            Some(AutomapColor::CeilingChange)
+        );
+
+        // Secret lines look like walls unless cheating, which also shows lines hidden from the map
+        map.line_defs[keys[1]].flags.secret = true;
+        map.line_defs[keys[1]].flags.not_on_map = true;
+        assert_eq!(color(&map, AutomapMode::Normal), None);
+        map.line_defs[keys[1]].flags.not_on_map = false;
+        assert_eq!(color(&map, AutomapMode::Normal), Some(AutomapColor::Wall));
+        assert_eq!(color(&map, AutomapMode::Cheat), Some(AutomapColor::Secret));
+
+        map.line_defs[keys[1]].special = Special::Teleport {
+            tid: 0,
+            tag: 1,
+            nosourcefog: false,
+        };
+        assert_eq!(
+            color(&map, AutomapMode::Normal),
+            Some(AutomapColor::Teleporter)
+        );
+    }
+
+    #[test]
+    fn render_previews() {
+        // Two rooms side by side, with a step between them
+        let map = Map::load_udmf_textmap(
+            "MAP01".try_into().unwrap(),
+            r#"
+            namespace = "zdoom";
+            vertex { x = 0; y = 0; }
+            vertex { x = 0; y = 128; }
+            vertex { x = 64; y = 128; }
+            vertex { x = 64; y = 0; }
+            vertex { x = 128; y = 128; }
+            vertex { x = 128; y = 0; }
+            sector { heightceiling = 128; texturefloor = "FLAT1"; textureceiling = "FLAT1"; }
+            sector { heightfloor = 16; heightceiling = 128; texturefloor = "FLAT1"; textureceiling = "FLAT1"; }
+            sidedef { sector = 0; }
+            sidedef { sector = 1; }
+            linedef { v1 = 0; v2 = 1; sidefront = 0; }
+            linedef { v1 = 1; v2 = 2; sidefront = 0; }
+            linedef { v1 = 2; v2 = 3; sidefront = 0; sideback = 1; twosided = true; }
+            linedef { v1 = 3; v2 = 0; sidefront = 0; }
+            linedef { v1 = 2; v2 = 4; sidefront = 1; }
+            linedef { v1 = 4; v2 = 5; sidefront = 1; }
+            linedef { v1 = 5; v2 = 3; sidefront = 1; }
+            thing { x = 32.0; y = 64.0; type = 1; }
+            "#,
+        )
+        .unwrap();
+
+        let options = PreviewOptions {
+            transform: PreviewTransform::fit(&map, 66, 66, 1),
+            ..PreviewOptions::fit(&map, 66, 66)
+        };
+        assert_eq!(
+            options.transform,
+            PreviewTransform {
+                origin: Point::new(-2.0, 130.0),
+                scale: 0.5,
+            }
+        );
+
+        let wall = AutomapColor::Wall.palette_index();
+        let step = AutomapColor::FloorChange.palette_index();
+        let image = map.render_automap(&options);
+        assert_eq!(image.get(1, 65), Ok(Some(wall)));
+        assert_eq!(image.get(65, 1), Ok(Some(wall)));
+        assert_eq!(image.get(33, 3), Ok(Some(step)));
+        assert_eq!(image.get(33, 4), Ok(None));
+        assert_eq!(image.get(17, 33), Ok(Some(THING_PALETTE_INDEX)));
+        assert_eq!(image.get(30, 30), Ok(None));
+
+        let svg = map.render_automap_svg(&options);
+        assert!(svg.starts_with("<svg "));
+        assert_eq!(svg.matches("<line ").count(), 7);
+        assert_eq!(svg.matches(r#"stroke-opacity="0.5""#).count(), 1);
+        assert_eq!(svg.matches("<circle ").count(), 1);
+    }
+
+    #[test]
+    fn clip_segments() {
+        let clip = |from: (f64, f64), to: (f64, f64)| {
+            clip_segment((Point::new(from.0, from.1), Point::new(to.0, to.1)), 10, 10)
+                .map(|(from, to)| ((from.x, from.y), (to.x, to.y)))
+        };
+
+        assert_eq!(clip((1.0, 1.0), (8.0, 2.0)), Some(((1.0, 1.0), (8.0, 2.0))));
+        assert_eq!(
+            clip((-10.0, 5.0), (20.0, 5.0)),
+            Some(((0.0, 5.0), (9.0, 5.0)))
+        );
+        assert_eq!(clip((-10.0, -1.0), (20.0, -1.0)), None);
+        assert_eq!(clip((20.0, 20.0), (30.0, 0.0)), None);
+        assert_eq!(clip((f64::NAN, 0.0), (5.0, 5.0)), None);
+
+        // Far off coordinates would overflow Bresenham's algorithm unclipped
+        let (from, to) = clip((-1e300, 4.0), (1e300, 4.0)).unwrap();
+        assert_eq!(
+            line_pixels(Point::new(from.0, from.1), Point::new(to.0, to.1)).count(),
+            10
+        );
+    }
+}
@@ -0,0 +1,522 @@
+//! Building blocks for generating maps: rooms, corridors and doors added straight to a `Map`, taking care of the
+//! vertexes, lines and side defs each one needs, and ways to pick entities by index, e.g. at random.
+
+use crate::{
+    map::{
+        geometry::Side,
+        line_def::{DoomSpecial, LineDefKey, Special, TriggerFlags, NO_ID},
+        mechanisms::Door,
+        sector::SectorKey,
+        vertex::VertexKey,
+        LineDef, Map, MapEntity, Sector, SideDef, Vertex,
+    },
+    number::Number,
+    Point, String8,
+};
+
+/// The texture name for no texture
+const NO_TEXTURE: &str = "-";
+
+/// Distances below this are taken to be zero when matching up walls
+const EPSILON: f64 = 1e-6;
+
+/// A room added by [Map::add_rect_room]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Room {
+    pub sector: SectorKey,
+    /// The west, north, east and south walls
+    pub walls: [LineDefKey; 4],
+}
+
+/// A corridor added by [Map::add_corridor_between]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Corridor {
+    pub sector: SectorKey,
+    /// The two-sided lines into the first and second sector
+    pub ends: [LineDefKey; 2],
+    /// The one-sided walls along the corridor
+    pub walls: [LineDefKey; 2],
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum ProcgenError {
+    #[error("{a:?} and {b:?} have no parallel walls facing each other with room for a corridor {width} wide")]
+    NoFacingWalls {
+        a: SectorKey,
+        b: SectorKey,
+        width: i32,
+    },
+
+    #[error("{line:?} isn't a two-sided line")]
+    NotTwoSided { line: LineDefKey },
+
+    #[error("the sector behind {line:?} has no walls running straight back {depth} units from both its ends")]
+    DoorDoesNotFit { line: LineDefKey, depth: i32 },
+}
+
+impl Map {
+    /// Add a rectangular room spanning from `min` to `max`, with the properties of `sector` and walls drawn with
+    /// `wall_texture`. It isn't connected to anything else in the map.
+    pub fn add_rect_room(
+        &mut self,
+        min: Point<i32>,
+        max: Point<i32>,
+        sector: Sector,
+        wall_texture: String8,
+    ) -> Room {
+        let sector = self.sectors.insert(sector);
+        let corners = [
+            (min.x, min.y),
+            (min.x, max.y),
+            (max.x, max.y),
+            (max.x, min.y),
+        ]
+        .map(|(x, y)| {
+            self.vertexes.insert(Vertex {
+                position: Point::new(Number::Int(x), Number::Int(y)),
+                ..Default::default()
+            })
+        });
+
+        // Clockwise, so the room is on the right of every wall
+        let walls = [0, 1, 2, 3].map(|i| {
+            self.add_wall(
+                corners[i],
+                corners[(i + 1) % corners.len()],
+                sector,
+                wall_texture.clone(),
+            )
+        });
+
+        Room { sector, walls }
+    }
+
+    /// Join two sectors with a corridor `width` wide, between the closest pair of their one-sided walls which are
+    /// parallel, face each other and overlap by at least `width`. The corridor runs from the middle of the overlap,
+    /// with the properties of sector `a` and the walls of the wall it leaves `a` through.
+    ///
+    /// The walls are split where the corridor meets them. Nothing checks that the corridor's path is clear.
+    pub fn add_corridor_between(
+        &mut self,
+        a: SectorKey,
+        b: SectorKey,
+        width: i32,
+    ) -> Result<Corridor, ProcgenError> {
+        let (wall_a, wall_b, near, far) = self
+            .facing_walls(a, b, f64::from(width))
+            .ok_or(ProcgenError::NoFacingWalls { a, b, width })?;
+
+        let (from, to) = self.line_segment(&self.line_defs[wall_a]).unwrap();
+        let direction = (to - from) * (1.0 / (to - from).length());
+        let at = |t: f64| from + direction * t;
+        let gap = direction_left(direction) * self.distance_between(wall_a, wall_b);
+
+        let end_a = self.split_out(wall_a, at(near), at(far));
+        let end_b = self.split_out(wall_b, at(near) + gap, at(far) + gap);
+
+        let sector = self.sectors.insert(Sector {
+            special: Default::default(),
+            secret: false,
+            tag: 0,
+            ..self.sectors[a].clone()
+        });
+        let wall_texture = self.side_defs[self.line_defs[end_a].left_side]
+            .middle_texture
+            .clone();
+        self.open_to(end_a, sector);
+        self.open_to(end_b, sector);
+
+        // The ends run in opposite directions, with the corridor on their left
+        let (p, q) = (self.line_defs[end_a].from, self.line_defs[end_a].to);
+        let (r, s) = (self.line_defs[end_b].from, self.line_defs[end_b].to);
+        let walls = [
+            self.add_wall(p, s, sector, wall_texture.clone()),
+            self.add_wall(r, q, sector, wall_texture),
+        ];
+
+        Ok(Corridor {
+            sector,
+            ends: [end_a, end_b],
+            walls,
+        })
+    }
+
+    /// Turn a two-sided line into a door, by moving its back `depth` units into its back sector and making the space
+    /// between them a door sector, opened from either side like Doom's manual doors. Both faces of the door are drawn
+    /// with `door_texture`.
+    ///
+    /// The back sector needs a wall running straight back from each end of the line for at least `depth`, which become
+    /// the door's tracks, as at the end of a corridor from [Map::add_corridor_between].
+    pub fn insert_door(
+        &mut self,
+        line: LineDefKey,
+        depth: i32,
+        door_texture: String8,
+    ) -> Result<Door, ProcgenError> {
+        let line_def = &self.line_defs[line];
+        let Some(back) = self.sector_on_side(line_def, Side::Back) else {
+            return Err(ProcgenError::NotTwoSided { line });
+        };
+        let back_side = line_def.right_side.unwrap();
+        let (from, to) = self.line_segment(line_def).unwrap();
+        let into_back = direction_left((to - from) * (1.0 / (to - from).length()));
+        let offset = into_back * f64::from(depth);
+
+        let (Some(from_track), Some(to_track)) = (
+            self.track(line_def.from, back, into_back, f64::from(depth)),
+            self.track(line_def.to, back, into_back, f64::from(depth)),
+        ) else {
+            return Err(ProcgenError::DoorDoesNotFit { line, depth });
+        };
+
+        let door = self.sectors.insert(Sector {
+            ceiling_height: self.sectors[back].floor_height,
+            special: Default::default(),
+            secret: false,
+            tag: 0,
+            ..self.sectors[back].clone()
+        });
+
+        // Split the tracks where the door ends, and put the pieces next to the line in the door sector
+        let mut far_vertexes = Vec::new();
+        for (vertex, track, end) in [
+            (self.line_defs[line].from, from_track, from),
+            (self.line_defs[line].to, to_track, to),
+        ] {
+            let rest = self.split_line_def(track, point(end + offset));
+            let piece = if self.line_defs[track].from == vertex {
+                track
+            } else {
+                rest
+            };
+            far_vertexes.push(self.line_defs[rest].from);
+
+            let piece = &mut self.line_defs[piece];
+            piece.flags.lower_unpegged = true;
+            self.side_defs[piece.left_side].sector = door;
+        }
+
+        let (special, trigger_flags) =
+            <(Special, TriggerFlags)>::try_from(DoomSpecial { value: 1, tag: 0 }).unwrap();
+        let no_texture = String8::new_unchecked(NO_TEXTURE);
+
+        self.side_defs[back_side] = SideDef {
+            sector: door,
+            offset: Point::default(),
+            upper_texture: no_texture.clone(),
+            middle_texture: no_texture.clone(),
+            lower_texture: no_texture.clone(),
+        };
+        let front_side = self.line_defs[line].left_side;
+        self.side_defs[front_side].upper_texture = door_texture.clone();
+        let line_def = &mut self.line_defs[line];
+        line_def.special = special.clone();
+        line_def.trigger_flags = trigger_flags.clone();
+
+        // The far face runs the other way, so it faces into the back sector
+        let face = self.add_wall(far_vertexes[1], far_vertexes[0], back, no_texture.clone());
+        self.side_defs[self.line_defs[face].left_side].upper_texture = door_texture;
+        self.open_to(face, door);
+        let face_def = &mut self.line_defs[face];
+        face_def.special = special;
+        face_def.trigger_flags = trigger_flags;
+
+        Ok(Door {
+            sector: door,
+            triggers: vec![line, face],
+        })
+    }
+
+    /// Split a line at `at`, which should be on it. The line is shortened to end at `at`, and the rest becomes a new
+    /// line with the same properties and copies of the side defs, which is returned.
+    pub fn split_line_def(&mut self, line: LineDefKey, at: Point) -> LineDefKey {
+        let vertex = self.vertexes.insert(Vertex {
+            position: at,
+            ..Default::default()
+        });
+        let mut rest = self.line_defs[line].clone();
+        rest.from = vertex;
+        rest.left_side = self
+            .side_defs
+            .insert(self.side_defs[rest.left_side].clone());
+        rest.right_side = rest
+            .right_side
+            .map(|side| self.side_defs.insert(self.side_defs[side].clone()));
+
+        self.line_defs[line].to = vertex;
+        self.line_defs.insert(rest)
+    }
+
+    /// The keys of every entity of type `E`, in iteration order, for picking entities by index
+    pub fn keys<E: MapEntity>(&self) -> Vec<E::Key> {
+        E::entities(self).keys().collect()
+    }
+
+    /// The `n`th entity of type `E` in iteration order, e.g. to pick one at random with `n` below
+    /// `E::entities(map).len()`
+    pub fn nth<E: MapEntity>(&self, n: usize) -> Option<(E::Key, &E)> {
+        E::entities(self).iter().nth(n)
+    }
+
+    /// Add a one-sided wall with `sector` on its front
+    fn add_wall(
+        &mut self,
+        from: VertexKey,
+        to: VertexKey,
+        sector: SectorKey,
+        texture: String8,
+    ) -> LineDefKey {
+        let no_texture = String8::new_unchecked(NO_TEXTURE);
+        let left_side = self.side_defs.insert(SideDef {
+            sector,
+            offset: Point::default(),
+            upper_texture: no_texture.clone(),
+            middle_texture: texture,
+            lower_texture: no_texture,
+        });
+
+        let mut line_def = LineDef {
+            from,
+            to,
+            left_side,
+            right_side: None,
+            id: NO_ID,
+            flags: Default::default(),
+            special: Default::default(),
+            trigger_flags: Default::default(),
+        };
+        line_def.flags.impassable = true;
+
+        self.line_defs.insert(line_def)
+    }
+
+    /// Give a one-sided wall a back side in `sector`, moving its texture to where the floors and ceilings differ
+    fn open_to(&mut self, line: LineDefKey, sector: SectorKey) {
+        let no_texture = String8::new_unchecked(NO_TEXTURE);
+        let front = &mut self.side_defs[self.line_defs[line].left_side];
+        let texture = std::mem::replace(&mut front.middle_texture, no_texture.clone());
+        front.upper_texture = texture.clone();
+        front.lower_texture = texture.clone();
+
+        let right_side = self.side_defs.insert(SideDef {
+            sector,
+            offset: Point::default(),
+            upper_texture: texture.clone(),
+            middle_texture: no_texture,
+            lower_texture: texture,
+        });
+
+        let line_def = &mut self.line_defs[line];
+        line_def.right_side = Some(right_side);
+        line_def.flags.impassable = false;
+        line_def.flags.two_sided = true;
+    }
+
+    /// Split a line so the part between `p` and `q`, both on it, is its own line, and return that line
+    fn split_out(&mut self, line: LineDefKey, p: Point<f64>, q: Point<f64>) -> LineDefKey {
+        let (from, to) = self.line_segment(&self.line_defs[line]).unwrap();
+        let length = (to - from).length();
+        let along = |point: Point<f64>| (point - from).dot(to - from) / length;
+        let (near, far) = if along(p) <= along(q) { (p, q) } else { (q, p) };
+
+        let mut middle = line;
+        if along(near) > EPSILON {
+            middle = self.split_line_def(line, point(near));
+        }
+        if along(far) < length - EPSILON {
+            self.split_line_def(middle, point(far));
+        }
+
+        middle
+    }
+
+    /// The closest pair of one-sided walls of `a` and `b` which face each other and overlap by at least `width`,
+    /// with where along the wall of `a` the corridor starts and ends
+    fn facing_walls(
+        &self,
+        a: SectorKey,
+        b: SectorKey,
+        width: f64,
+    ) -> Option<(LineDefKey, LineDefKey, f64, f64)> {
+        let walls = |sector: SectorKey| {
+            self.line_defs.iter().filter(move |(_, line_def)| {
+                line_def.right_side.is_none()
+                    && self.sector_on_side(line_def, Side::Front) == Some(sector)
+            })
+        };
+
+        let mut best: Option<(f64, (LineDefKey, LineDefKey, f64, f64))> = None;
+        for (key_a, wall_a) in walls(a) {
+            let Some((from_a, to_a)) = self.line_segment(wall_a) else {
+                continue;
+            };
+            let length = (to_a - from_a).length();
+            let direction = (to_a - from_a) * (1.0 / length);
+
+            for (key_b, wall_b) in walls(b) {
+                let Some((from_b, to_b)) = self.line_segment(wall_b) else {
+                    continue;
+                };
+                let direction_b = (to_b - from_b) * (1.0 / (to_b - from_b).length());
+                let gap = self.distance_between(key_a, key_b);
+                if direction.dot(direction_b) > EPSILON - 1.0 || gap <= EPSILON {
+                    continue;
+                }
+
+                let along = |point: Point<f64>| (point - from_a).dot(direction);
+                let low = along(to_b).max(0.0);
+                let high = along(from_b).min(length);
+                if high - low < width - EPSILON
+                    || best.as_ref().is_some_and(|(best_gap, _)| *best_gap <= gap)
+                {
+                    continue;
+                }
+
+                let middle = ((low + high) / 2.0).round();
+                let near = (middle - width / 2.0).max(low);
+                best = Some((gap, (key_a, key_b, near, near + width)));
+            }
+        }
+
+        best.map(|(_, walls)| walls)
+    }
+
+    /// How far the line of `b` is from the line of `a`, to the left of `a`
+    fn distance_between(&self, a: LineDefKey, b: LineDefKey) -> f64 {
+        let (from_a, to_a) = self.line_segment(&self.line_defs[a]).unwrap();
+        let (from_b, _) = self.line_segment(&self.line_defs[b]).unwrap();
+        let direction = (to_a - from_a) * (1.0 / (to_a - from_a).length());
+
+        (from_b - from_a).dot(direction_left(direction))
+    }
+
+    /// A one-sided wall of `sector` from `vertex`, running at least `depth` in `direction`
+    fn track(
+        &self,
+        vertex: VertexKey,
+        sector: SectorKey,
+        direction: Point<f64>,
+        depth: f64,
+    ) -> Option<LineDefKey> {
+        let start = self.vertexes[vertex].position.map(|n| n.into_float());
+
+        self.line_defs
+            .iter()
+            .filter(|(_, line_def)| {
+                line_def.right_side.is_none()
+                    && self.sector_on_side(line_def, Side::Front) == Some(sector)
+            })
+            .find(|(_, line_def)| {
+                let other = if line_def.from == vertex {
+                    line_def.to
+                } else if line_def.to == vertex {
+                    line_def.from
+                } else {
+                    return false;
+                };
+                let run = self.vertexes[other].position.map(|n| n.into_float()) - start;
+
+                direction.cross(run).abs() < EPSILON && direction.dot(run) >= depth - EPSILON
+            })
+            .map(|(key, _)| key)
+    }
+}
+
+/// The unit vector a quarter turn counterclockwise from `direction`, which points to the back of a line
+fn direction_left(direction: Point<f64>) -> Point<f64> {
+    Point::new(-direction.y, direction.x)
+}
+
+/// A map position, as an integer where possible
+fn point(point: Point<f64>) -> Point {
+    point.map(|value| {
+        if (value - value.round()).abs() < EPSILON {
+            Number::Int(value.round() as i32)
+        } else {
+            Number::Float(value)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        map::{
+            thing::{self, Skill, Thing},
+            thing_type::{Game, ThingTypes},
+        },
+        units::{Angle, Height},
+    };
+
+    #[test]
+    fn rooms_corridor_and_door() {
+        let mut map = Map::new("MAP01".try_into().unwrap());
+        let room = |floor: i16| Sector {
+            floor_height: Height(floor),
+            ceiling_height: Height(128),
+            floor_flat: String8::new_unchecked("FLOOR4_8"),
+            ceiling_flat: String8::new_unchecked("CEIL3_5"),
+            light_level: 160,
+            ..Default::default()
+        };
+        let texture = String8::new_unchecked("STARTAN2");
+
+        let start = map.add_rect_room(
+            Point::new(0, 0),
+            Point::new(256, 256),
+            room(0),
+            texture.clone(),
+        );
+        let end = map.add_rect_room(
+            Point::new(64, 384),
+            Point::new(512, 640),
+            room(16),
+            texture.clone(),
+        );
+        map.things.insert(Thing {
+            position: Point::new(Number::Int(128), Number::Int(128)),
+            height: Height(0),
+            angle: Angle(90),
+            type_: 1,
+            flags: thing::Flags::default(),
+            special: thing::Special::None,
+        });
+
+        let corridor = map
+            .add_corridor_between(start.sector, end.sector, 64)
+            .unwrap();
+        let end_a = &map.line_defs[corridor.ends[0]];
+        assert_eq!(
+            map.line_segment(end_a).unwrap(),
+            (Point::new(128.0, 256.0), Point::new(192.0, 256.0))
+        );
+        assert_eq!(map.sector_on_side(end_a, Side::Back), Some(corridor.sector));
+        assert_eq!(
+            map.sector_at(Point::new(160.0, 320.0)),
+            Some(corridor.sector)
+        );
+
+        let door = map
+            .insert_door(corridor.ends[1], 16, String8::new_unchecked("DOOR3"))
+            .unwrap();
+        assert_eq!(map.sector_at(Point::new(160.0, 376.0)), Some(door.sector));
+        assert_eq!(
+            map.sector_at(Point::new(160.0, 360.0)),
+            Some(corridor.sector)
+        );
+
+        let thing_types = ThingTypes::for_game(Game::Doom2);
+        let reachability = map.reachability(&thing_types, Skill::Skill4);
+        assert!(reachability.unreachable_sectors.is_empty());
+
+        assert!(matches!(
+            map.add_corridor_between(start.sector, end.sector, 512),
+            Err(ProcgenError::NoFacingWalls { .. })
+        ));
+        assert_eq!(map.keys::<Sector>().len(), 4);
+        assert_eq!(map.nth::<Sector>(3).unwrap().0, door.sector);
+        assert_eq!(map.mechanisms(&thing_types).doors, [door]);
+    }
+}
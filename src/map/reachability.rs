@@ -0,0 +1,438 @@
+//! A coarse analysis of where the player can get to in a map, walking from the player starts through the sectors
+//! the player can step between, and opening doors, lowering lifts and taking teleporters along the way once their
+//! lines can be reached and the keys for them have been picked up.
+//!
+//! Sectors are treated as a whole: the player is taken to reach all of a sector once they reach any of it, and a
+//! sector moved by a door or lift is passable from every side once it's been triggered. Line specials other than
+//! doors, lifts and teleporters, monsters and damage aren't taken into account.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{
+    map::{
+        geometry::Side,
+        line_def::LineDefKey,
+        lint::{EntityRef, PLAYER_HEIGHT},
+        mechanisms::{Mechanisms, TeleportDestination},
+        sector::SectorKey,
+        self_reference::SelfReferencingSector,
+        thing::{GameMode, Skill, ThingKey},
+        thing_type::{ThingCategory, ThingKind, ThingTypes},
+        LineDef, Map,
+    },
+    Point,
+};
+
+/// The highest step the player can climb without jumping
+pub const MAX_STEP_HEIGHT: i32 = 24;
+
+/// The parts of a map the player can get to on some skill, as found by [Map::reachability]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Reachability {
+    /// The sectors the player can get to
+    pub sectors: BTreeSet<SectorKey>,
+    /// The sectors the player can't get to
+    pub unreachable_sectors: Vec<SectorKey>,
+    /// The weapons, ammo, health, armor, powerups and keys on the skill which the player can't get to
+    pub unreachable_items: Vec<ThingKey>,
+    /// The secret sectors the player can't get to, so can't find
+    pub unreachable_secrets: Vec<SectorKey>,
+    /// Whether an exit can be reached
+    pub exit_reachable: bool,
+    /// Sectors the player can get to but can't get from to an exit, such as pits with no way out. Empty if no exit
+    /// can be reached at all.
+    pub softlocks: Vec<SectorKey>,
+}
+
+impl Map {
+    /// Find which sectors and items the players can get to from any of the player starts on `skill`, and where they
+    /// can get stuck
+    pub fn reachability(&self, thing_types: &ThingTypes, skill: Skill) -> Reachability {
+        let things: Vec<(ThingKey, SectorKey, ThingCategory, ThingKind)> = self
+            .things
+            .iter()
+            .filter(|(_, thing)| {
+                thing.flags.in_skill(skill) && thing.flags.in_mode(GameMode::SinglePlayer)
+            })
+            .filter_map(|(key, thing)| {
+                let info = thing.info(thing_types)?;
+                let position =
+                    Point::new(thing.position.x.into_float(), thing.position.y.into_float());
+                Some((key, self.sector_at(position)?, info.category, info.kind))
+            })
+            .collect();
+
+        let mut reachable: BTreeSet<SectorKey> = things
+            .iter()
+            .filter(|&&(_, _, category, _)| category == ThingCategory::PlayerStart)
+            .map(|&(_, sector, ..)| sector)
+            .collect();
+        let mechanisms = self.mechanisms(thing_types);
+        let self_references = self.self_referencing_sectors();
+        let mut moving = BTreeSet::new();
+
+        // Flood out from what's been reached, then trigger whatever that makes possible, until nothing changes
+        loop {
+            self.flood(&mut reachable, &moving, &self_references);
+
+            let keys: Vec<ThingKind> = things
+                .iter()
+                .filter(|&&(_, sector, category, _)| {
+                    category == ThingCategory::Key && reachable.contains(&sector)
+                })
+                .map(|&(.., kind)| kind)
+                .collect();
+            let usable = |line_def: &LineDef| {
+                let flags = &line_def.trigger_flags;
+                let by_player =
+                    flags.player_cross || flags.player_use || flags.player_push || flags.impact;
+
+                by_player
+                    && line_def.special.lock().is_opened_by(&keys) != Some(false)
+                    && self
+                        .line_sectors(line_def)
+                        .any(|sector| reachable.contains(&sector))
+            };
+
+            let mut changed = false;
+            let moved = mechanisms
+                .doors
+                .iter()
+                .map(|door| (door.sector, &door.triggers))
+                .chain(
+                    mechanisms
+                        .lifts
+                        .iter()
+                        .map(|lift| (lift.sector, &lift.triggers)),
+                );
+            for (sector, triggers) in moved {
+                if !moving.contains(&sector)
+                    && triggers.iter().any(|&line| usable(&self.line_defs[line]))
+                {
+                    moving.insert(sector);
+                    changed = true;
+                }
+            }
+
+            let arrivals: Vec<SectorKey> = self
+                .teleports(&mechanisms)
+                .into_iter()
+                .filter(|&(line, _)| usable(&self.line_defs[line]))
+                .flat_map(|(_, destinations)| destinations)
+                .collect();
+            for sector in arrivals {
+                changed |= reachable.insert(sector);
+            }
+
+            if !changed {
+                break;
+            }
+        }
+
+        let unreachable_sectors = self
+            .sectors
+            .keys()
+            .filter(|sector| !reachable.contains(sector))
+            .collect();
+        let unreachable_secrets = self
+            .secrets()
+            .into_iter()
+            .filter(|sector| !reachable.contains(sector))
+            .collect();
+        let unreachable_items = things
+            .iter()
+            .filter(|&&(_, sector, category, _)| {
+                matches!(
+                    category,
+                    ThingCategory::Weapon
+                        | ThingCategory::Ammo
+                        | ThingCategory::Health
+                        | ThingCategory::Armor
+                        | ThingCategory::Powerup
+                        | ThingCategory::Key
+                ) && !reachable.contains(&sector)
+            })
+            .map(|&(key, ..)| key)
+            .collect();
+
+        // Walk back from the exits to find the sectors which lead to one
+        let mut exit_sectors = BTreeSet::new();
+        for exit in self.exits(thing_types) {
+            match exit.source {
+                EntityRef::LineDef(line) => {
+                    exit_sectors.extend(self.line_sectors(&self.line_defs[line]))
+                }
+                EntityRef::Sector(sector) => {
+                    exit_sectors.insert(sector);
+                }
+                _ => {}
+            }
+        }
+        let leads_to_exit = self.leads_to(exit_sectors, &moving, &self_references, &mechanisms);
+        let exit_reachable = reachable
+            .iter()
+            .any(|sector| leads_to_exit.contains(sector));
+        let softlocks = if exit_reachable {
+            reachable
+                .iter()
+                .filter(|sector| !leads_to_exit.contains(sector))
+                .copied()
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        Reachability {
+            sectors: reachable,
+            unreachable_sectors,
+            unreachable_items,
+            unreachable_secrets,
+            exit_reachable,
+            softlocks,
+        }
+    }
+
+    /// Add every sector the player can walk to from `reachable` to it
+    fn flood(
+        &self,
+        reachable: &mut BTreeSet<SectorKey>,
+        moving: &BTreeSet<SectorKey>,
+        self_references: &[SelfReferencingSector],
+    ) {
+        let mut queue: Vec<SectorKey> = reachable.iter().copied().collect();
+        let steps = self.steps(moving, self_references);
+
+        while let Some(sector) = queue.pop() {
+            for &to in steps.get(&sector).into_iter().flatten() {
+                if reachable.insert(to) {
+                    queue.push(to);
+                }
+            }
+        }
+    }
+
+    /// The sectors `goals` and every sector the player can get from to one of them
+    fn leads_to(
+        &self,
+        goals: BTreeSet<SectorKey>,
+        moving: &BTreeSet<SectorKey>,
+        self_references: &[SelfReferencingSector],
+        mechanisms: &Mechanisms,
+    ) -> BTreeSet<SectorKey> {
+        let mut back_steps: BTreeMap<SectorKey, Vec<SectorKey>> = BTreeMap::new();
+        for (from, tos) in self.steps(moving, self_references) {
+            for to in tos {
+                back_steps.entry(to).or_default().push(from);
+            }
+        }
+        for (line, destinations) in self.teleports(mechanisms) {
+            for from in self.line_sectors(&self.line_defs[line]) {
+                for &to in &destinations {
+                    back_steps.entry(to).or_default().push(from);
+                }
+            }
+        }
+
+        let mut found = goals;
+        let mut queue: Vec<SectorKey> = found.iter().copied().collect();
+        while let Some(sector) = queue.pop() {
+            for &from in back_steps.get(&sector).into_iter().flatten() {
+                if found.insert(from) {
+                    queue.push(from);
+                }
+            }
+        }
+
+        found
+    }
+
+    /// The sectors the player can walk to from each sector across two-sided lines or into the `self_references`, with
+    /// the sectors in `moving` open
+    fn steps(
+        &self,
+        moving: &BTreeSet<SectorKey>,
+        self_references: &[SelfReferencingSector],
+    ) -> BTreeMap<SectorKey, Vec<SectorKey>> {
+        let can_step = |from: SectorKey, to: SectorKey| {
+            if moving.contains(&from) || moving.contains(&to) {
+                return true;
+            }
+
+            let (from, to) = (&self.sectors[from], &self.sectors[to]);
+            let floor = i32::from(from.floor_height.max(to.floor_height));
+            let ceiling = i32::from(from.ceiling_height.min(to.ceiling_height));
+            let room = i32::from(to.ceiling_height) - i32::from(to.floor_height);
+
+            i32::from(to.floor_height) - i32::from(from.floor_height) <= MAX_STEP_HEIGHT
+                && ceiling - floor >= i32::from(PLAYER_HEIGHT)
+                && room >= i32::from(PLAYER_HEIGHT)
+        };
+
+        let mut steps: BTreeMap<SectorKey, Vec<SectorKey>> = BTreeMap::new();
+        for line_def in self.line_defs.values() {
+            if line_def.flags.impassable {
+                continue;
+            }
+            let (Some(front), Some(back)) = (
+                self.sector_on_side(line_def, Side::Front),
+                self.sector_on_side(line_def, Side::Back),
+            ) else {
+                continue;
+            };
+
+            for (from, to) in [(front, back), (back, front)] {
+                if from != to && can_step(from, to) {
+                    steps.entry(from).or_default().push(to);
+                }
+            }
+        }
+
+        // Self-referencing sectors have no lines into the sector around them, but are walked into all the same
+        for self_reference in self_references {
+            let Some(surrounding) = self_reference.surrounding else {
+                continue;
+            };
+
+            for (from, to) in [
+                (surrounding, self_reference.sector),
+                (self_reference.sector, surrounding),
+            ] {
+                if can_step(from, to) {
+                    steps.entry(from).or_default().push(to);
+                }
+            }
+        }
+
+        steps
+    }
+
+    /// Each teleport line with the sectors it can send the player to. Teleports to a tid are left out, as things
+    /// don't have tids.
+    fn teleports(&self, mechanisms: &Mechanisms) -> Vec<(LineDefKey, Vec<SectorKey>)> {
+        mechanisms
+            .teleporters
+            .iter()
+            .map(|teleporter| {
+                let sectors = match &teleporter.destination {
+                    TeleportDestination::Things(things) => things
+                        .first()
+                        .and_then(|&thing| {
+                            let position = self.things[thing].position;
+                            self.sector_at(Point::new(
+                                position.x.into_float(),
+                                position.y.into_float(),
+                            ))
+                        })
+                        .into_iter()
+                        .collect(),
+                    TeleportDestination::Lines(lines) => lines
+                        .iter()
+                        .flat_map(|&line| self.line_sectors(&self.line_defs[line]))
+                        .collect(),
+                    TeleportDestination::Tid(_) => Vec::new(),
+                };
+
+                (teleporter.line, sectors)
+            })
+            .collect()
+    }
+
+    /// The sectors on either side of a line
+    fn line_sectors<'m>(&'m self, line_def: &'m LineDef) -> impl Iterator<Item = SectorKey> + 'm {
+        [Side::Front, Side::Back]
+            .into_iter()
+            .filter_map(|side| self.sector_on_side(line_def, side))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        map::{thing::Thing, thing_type::Game},
+        number::Number,
+    };
+
+    #[test]
+    fn reach_through_locked_door() {
+        // A start room with a pit to the south, and a red door to the east into the exit room
+        let mut map = Map::load_udmf_textmap(
+            "MAP01".try_into().unwrap(),
+            r#"
+            namespace = "zdoom";
+            vertex { x = 0; y = 0; }
+            vertex { x = 128; y = 0; }
+            vertex { x = 128; y = 128; }
+            vertex { x = 0; y = 128; }
+            vertex { x = 144; y = 0; }
+            vertex { x = 144; y = 128; }
+            vertex { x = 272; y = 0; }
+            vertex { x = 272; y = 128; }
+            vertex { x = 0; y = -64; }
+            vertex { x = 128; y = -64; }
+            sector { heightceiling = 128; texturefloor = "FLAT1"; textureceiling = "FLAT1"; }
+            sector { heightceiling = 0; texturefloor = "FLAT1"; textureceiling = "FLAT1"; }
+            sector { heightceiling = 128; texturefloor = "FLAT1"; textureceiling = "FLAT1"; }
+            sector { heightfloor = -64; heightceiling = 128; texturefloor = "FLAT1"; textureceiling = "FLAT1"; }
+            sidedef { sector = 0; }
+            sidedef { sector = 1; }
+            sidedef { sector = 2; }
+            sidedef { sector = 3; }
+            linedef { v1 = 0; v2 = 3; sidefront = 0; }
+            linedef { v1 = 3; v2 = 2; sidefront = 0; }
+            linedef { v1 = 2; v2 = 1; sidefront = 0; sideback = 1; special = 13; arg1 = 16; arg2 = 150; arg3 = 129; playeruse = true; repeatspecial = true; }
+            linedef { v1 = 1; v2 = 0; sidefront = 0; sideback = 3; }
+            linedef { v1 = 2; v2 = 5; sidefront = 1; }
+            linedef { v1 = 5; v2 = 4; sidefront = 1; sideback = 2; }
+            linedef { v1 = 4; v2 = 1; sidefront = 1; }
+            linedef { v1 = 5; v2 = 7; sidefront = 2; }
+            linedef { v1 = 7; v2 = 6; sidefront = 2; special = 243; playeruse = true; }
+            linedef { v1 = 6; v2 = 4; sidefront = 2; }
+            linedef { v1 = 8; v2 = 0; sidefront = 3; }
+            linedef { v1 = 1; v2 = 9; sidefront = 3; }
+            linedef { v1 = 9; v2 = 8; sidefront = 3; }
+            thing { x = 64.0; y = 64.0; type = 1; }
+            thing { x = 200.0; y = 64.0; type = 13; }
+            "#,
+        )
+        .unwrap();
+        let thing_types = ThingTypes::for_game(Game::Doom2);
+        let sectors: Vec<_> = map.sectors.keys().collect();
+        let key = map.things.keys().nth(1).unwrap();
+
+        let reachability = map.reachability(&thing_types, Skill::Skill3);
+        assert_eq!(
+            reachability,
+            Reachability {
+                sectors: [sectors[0], sectors[3]].into(),
+                unreachable_sectors: vec![sectors[1], sectors[2]],
+                unreachable_items: vec![key],
+                unreachable_secrets: Vec::new(),
+                exit_reachable: false,
+                softlocks: Vec::new(),
+            }
+        );
+
+        // With the key in the start room, the door opens, but there's no way back up out of the pit
+        map.things[key].position = Point::new(Number::Int(64), Number::Int(32));
+        let reachability = map.reachability(&thing_types, Skill::Skill3);
+        assert_eq!(reachability.sectors.len(), 4);
+        assert!(reachability.unreachable_items.is_empty());
+        assert!(reachability.exit_reachable);
+        assert_eq!(reachability.softlocks, [sectors[3]]);
+
+        // With the key back in the exit room, a second player starting there picks it up and opens the door
+        map.things[key].position = Point::new(Number::Int(200), Number::Int(64));
+        let start = map.things.values().next().unwrap().clone();
+        map.things.insert(Thing {
+            type_: 2,
+            position: Point::new(Number::Int(200), Number::Int(32)),
+            ..start
+        });
+        let reachability = map.reachability(&thing_types, Skill::Skill3);
+        assert_eq!(reachability.sectors.len(), 4);
+        assert!(reachability.unreachable_items.is_empty());
+        assert!(reachability.exit_reachable);
+    }
+}
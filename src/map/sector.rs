@@ -2,29 +2,99 @@ use std::convert::TryFrom;
 
 use slotmap::SlotMap;
 
-use crate::String8;
+use crate::{map::Map, units::Height, String8};
 
 #[derive(Clone, Default, PartialEq, Eq, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
 pub struct Sector {
-    pub floor_height: i16,
-    pub ceiling_height: i16,
+    pub floor_height: Height,
+    pub ceiling_height: Height,
     pub floor_flat: String8,
     pub ceiling_flat: String8,
     pub light_level: u8,
     pub special: Special,
+    /// Entering the sector counts as finding a secret. This is independent of `special`, as in ZDoom, though Doom
+    /// can't have both.
+    pub secret: bool,
     pub tag: i16,
+    /// Monsters standing on the floor die instantly, as with MBF21's generalized sector flag. UDMF has no field for
+    /// it, so it's only kept by binary maps.
+    pub kill_grounded_monsters: bool,
 }
 
-#[derive(Clone, Copy,Debug, Default, PartialEq, Eq)]
+/// The bits of a Boom generalized sector type giving the damage, as 0 to 3 for none, 5, 10 or 20 damage
+pub const GENERALIZED_DAMAGE: i16 = 0x60;
+
+/// The bit of a Boom generalized sector type marking the sector as secret
+pub const GENERALIZED_SECRET: i16 = 0x80;
+
+/// The bits of a Boom generalized sector type for friction, pushers and sounds, which aren't supported
+pub const GENERALIZED_EFFECTS: i16 = 0xf00;
+
+/// The MBF21 bit of a generalized sector type switching its damage bits to instant death, or instant exits
+pub const MBF21_ALT_DAMAGE: i16 = 0x1000;
+
+/// The MBF21 bit of a generalized sector type killing grounded monsters
+pub const MBF21_KILL_GROUNDED_MONSTERS: i16 = 0x2000;
+
+/// The bit ZDoom sets in a sector special to mark the sector as secret, in Hexen format and UDMF maps
+pub const SECRET_FLAG: i16 = 1024;
+
+/// Doom's secret sector type, which has no other effect
+pub const DOOM_SECRET: i16 = 9;
+
+/// A sector special, numbered as in ZDoom. The Doom types are translated by adding 64, e.g. Doom's nukage (7) is
+/// `DamageNukage` (71).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
 pub enum Special {
     #[default]
     None,
+    /// 20 damage every 32 tics, with a strobing light
+    LightStrobeHurt,
+    /// 10 damage every 32 tics
+    DamageHellslime,
+    /// 5 damage every 32 tics
+    DamageNukage,
+    /// 20 damage every 32 tics, and the level ends once the player's health drops below 11. God mode stops working.
+    DamageEnd,
+    /// 20 damage every 32 tics
+    DamageSuperHellslime,
+    /// Kills the player outright
+    DamageInstantDeath,
+}
+
+impl Special {
+    /// The damage the special deals to players standing in the sector every 32 tics, if any
+    pub fn damage(self) -> Option<u16> {
+        match self {
+            Special::None => None,
+            Special::DamageNukage => Some(5),
+            Special::DamageHellslime => Some(10),
+            Special::LightStrobeHurt | Special::DamageEnd | Special::DamageSuperHellslime => {
+                Some(20)
+            }
+            Special::DamageInstantDeath => Some(10000),
+        }
+    }
 }
 
 impl From<Special> for i16 {
     fn from(special: Special) -> Self {
         match special {
             Special::None => 0,
+            Special::LightStrobeHurt => 68,
+            Special::DamageHellslime => 69,
+            Special::DamageNukage => 71,
+            Special::DamageEnd => 75,
+            Special::DamageSuperHellslime => 80,
+            Special::DamageInstantDeath => 115,
         }
     }
 }
@@ -35,6 +105,12 @@ impl TryFrom<i16> for Special {
     fn try_from(n: i16) -> Result<Self, Self::Error> {
         match n {
             0 => Ok(Special::None),
+            68 => Ok(Special::LightStrobeHurt),
+            69 => Ok(Special::DamageHellslime),
+            71 => Ok(Special::DamageNukage),
+            75 => Ok(Special::DamageEnd),
+            80 => Ok(Special::DamageSuperHellslime),
+            115 => Ok(Special::DamageInstantDeath),
             _ => Err(n),
         }
     }
@@ -43,3 +119,54 @@ impl TryFrom<i16> for Special {
 slotmap::new_key_type! { pub struct SectorKey; }
 
 pub type SectorMap = SlotMap<SectorKey, Sector>;
+
+impl Map {
+    /// The secret sectors of the map, which count towards the secrets found at the end of it
+    pub fn secrets(&self) -> Vec<SectorKey> {
+        self.sectors
+            .iter()
+            .filter(|(_, sector)| sector.secret)
+            .map(|(key, _)| key)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::map::{compat::CompatProfile, downconvert::DownconversionIssue};
+
+    #[test]
+    fn secret_sectors() {
+        let map = Map::load_udmf_textmap(
+            "MAP01".try_into().unwrap(),
+            r#"
+            namespace = "zdoom";
+            sector { texturefloor = "FLAT1"; textureceiling = "FLAT1"; special = 1095; }
+            sector { texturefloor = "FLAT1"; textureceiling = "FLAT1"; secret = true; }
+            sector { texturefloor = "FLAT1"; textureceiling = "FLAT1"; special = 71; }
+            "#,
+        )
+        .unwrap();
+        let sectors: Vec<_> = map.sectors.keys().collect();
+
+        assert_eq!(map.secrets(), [sectors[0], sectors[1]]);
+        assert_eq!(map.sectors[sectors[0]].special, Special::DamageNukage);
+
+        let mut textmap = Vec::new();
+        map.write_udmf_textmap(&mut textmap).unwrap();
+        let textmap = String::from_utf8(textmap).unwrap();
+        assert!(textmap.contains("special=1095;"));
+        assert!(textmap.contains("special=1024;"));
+
+        let (_, report) = map.to_doom_binary(CompatProfile::Vanilla).unwrap();
+        assert_eq!(
+            report.issues,
+            [DownconversionIssue::SectorSecret { sector: 0 }]
+        );
+        // Boom's generalized types have a secret bit
+        let (_, report) = map.to_doom_binary(CompatProfile::Boom).unwrap();
+        assert!(report.is_lossless(), "{:?}", report.issues);
+    }
+}
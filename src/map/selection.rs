@@ -0,0 +1,204 @@
+use std::collections::HashSet;
+
+use slotmap::SecondaryMap;
+
+use crate::map::{
+    line_def::LineDefKey, sector::SectorKey, side_def::SideDefKey, thing::ThingKey,
+    vertex::VertexKey, EntityKind, LineDef, Map, SideDef, UnlinkError,
+};
+
+/// A set of entities within a `Map`, identified by their keys.
+///
+/// A selection doesn't need to be closed over references: [Map::extract] will pull in any entities the selected ones
+/// depend on.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Selection {
+    pub vertexes: HashSet<VertexKey>,
+    pub line_defs: HashSet<LineDefKey>,
+    pub sectors: HashSet<SectorKey>,
+    pub side_defs: HashSet<SideDefKey>,
+    pub things: HashSet<ThingKey>,
+}
+
+impl Selection {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vertexes.is_empty()
+            && self.line_defs.is_empty()
+            && self.sectors.is_empty()
+            && self.side_defs.is_empty()
+            && self.things.is_empty()
+    }
+}
+
+impl Selection {
+    /// A selection containing every entity in the map
+    pub fn all(map: &Map) -> Self {
+        Self {
+            vertexes: map.vertexes.keys().collect(),
+            line_defs: map.line_defs.keys().collect(),
+            sectors: map.sectors.keys().collect(),
+            side_defs: map.side_defs.keys().collect(),
+            things: map.things.keys().collect(),
+        }
+    }
+
+    /// Extend this selection with every entity the selected entities refer to, checking that all references are
+    /// valid along the way.
+    pub fn close_over(&mut self, map: &Map) -> Result<(), UnlinkError> {
+        for (i, key) in map.line_defs.keys().enumerate() {
+            if !self.line_defs.contains(&key) {
+                continue;
+            }
+
+            let line_def = &map.line_defs[key];
+
+            for (field, referee, valid) in [
+                (
+                    "from",
+                    EntityKind::Vertex,
+                    map.vertexes.contains_key(line_def.from),
+                ),
+                (
+                    "to",
+                    EntityKind::Vertex,
+                    map.vertexes.contains_key(line_def.to),
+                ),
+                (
+                    "left_side",
+                    EntityKind::SideDef,
+                    map.side_defs.contains_key(line_def.left_side),
+                ),
+                (
+                    "right_side",
+                    EntityKind::SideDef,
+                    line_def
+                        .right_side
+                        .is_none_or(|right_side| map.side_defs.contains_key(right_side)),
+                ),
+            ] {
+                if !valid {
+                    return Err(UnlinkError::InvalidKey {
+                        referrer: EntityKind::LineDef,
+                        referrer_index: i,
+                        field,
+                        referee,
+                    });
+                }
+            }
+
+            self.vertexes.insert(line_def.from);
+            self.vertexes.insert(line_def.to);
+            self.side_defs.insert(line_def.left_side);
+            self.side_defs.extend(line_def.right_side);
+        }
+
+        for (i, key) in map.side_defs.keys().enumerate() {
+            if !self.side_defs.contains(&key) {
+                continue;
+            }
+
+            let sector = map.side_defs[key].sector;
+
+            if !map.sectors.contains_key(sector) {
+                return Err(UnlinkError::InvalidKey {
+                    referrer: EntityKind::SideDef,
+                    referrer_index: i,
+                    field: "sector",
+                    referee: EntityKind::Sector,
+                });
+            }
+
+            self.sectors.insert(sector);
+        }
+
+        Ok(())
+    }
+}
+
+/// The keys given to entities copied from one `Map` to another, indexed by their keys in the source map
+#[derive(Clone, Debug, Default)]
+pub struct KeyRemap {
+    pub vertexes: SecondaryMap<VertexKey, VertexKey>,
+    pub line_defs: SecondaryMap<LineDefKey, LineDefKey>,
+    pub sectors: SecondaryMap<SectorKey, SectorKey>,
+    pub side_defs: SecondaryMap<SideDefKey, SideDefKey>,
+    pub things: SecondaryMap<ThingKey, ThingKey>,
+}
+
+impl Map {
+    /// Copy the selected entities, plus every entity they refer to, into a new `Map` with the same name.
+    ///
+    /// Entities are copied in the same relative order as they appear in `self`, so unlinking the result gives a stable
+    /// ordering.
+    pub fn extract(&self, selection: &Selection) -> Result<Map, UnlinkError> {
+        let mut selection = selection.clone();
+        selection.close_over(self)?;
+
+        let mut map = Map::new(self.name.clone());
+        self.copy_selection_into(&selection, &mut map);
+
+        Ok(map)
+    }
+
+    /// Copy the selected entities into `target`, in the same relative order as they appear in `self`.
+    ///
+    /// The selection must be closed over references (see [Selection::close_over]).
+    pub(crate) fn copy_selection_into(&self, selection: &Selection, target: &mut Map) -> KeyRemap {
+        let mut remap = KeyRemap::default();
+
+        for (key, vertex) in self.vertexes.iter() {
+            if selection.vertexes.contains(&key) {
+                remap.vertexes.insert(key, target.vertexes.insert(*vertex));
+            }
+        }
+
+        for (key, sector) in self.sectors.iter() {
+            if selection.sectors.contains(&key) {
+                remap
+                    .sectors
+                    .insert(key, target.sectors.insert(sector.clone()));
+            }
+        }
+
+        for (key, side_def) in self.side_defs.iter() {
+            if selection.side_defs.contains(&key) {
+                let new_key = target.side_defs.insert(SideDef {
+                    sector: remap.sectors[side_def.sector],
+                    ..side_def.clone()
+                });
+
+                remap.side_defs.insert(key, new_key);
+            }
+        }
+
+        for (key, line_def) in self.line_defs.iter() {
+            if selection.line_defs.contains(&key) {
+                let new_key = target.line_defs.insert(LineDef {
+                    from: remap.vertexes[line_def.from],
+                    to: remap.vertexes[line_def.to],
+                    left_side: remap.side_defs[line_def.left_side],
+                    right_side: line_def
+                        .right_side
+                        .map(|right_side| remap.side_defs[right_side]),
+                    ..line_def.clone()
+                });
+
+                remap.line_defs.insert(key, new_key);
+            }
+        }
+
+        for (key, thing) in self.things.iter() {
+            if selection.things.contains(&key) {
+                remap
+                    .things
+                    .insert(key, target.things.insert(thing.clone()));
+            }
+        }
+
+        remap
+    }
+}
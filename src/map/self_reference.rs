@@ -0,0 +1,370 @@
+//! Self-referencing sectors: sectors all of whose lines have the sector on both sides. The vanilla renderer doesn't
+//! draw their floors and ceilings, and shows the surrounding sector's instead, which old maps use to fake deep water
+//! and invisible bridges. Ports which render them properly need them converted to `TransferHeights` or 3D floors.
+
+use std::collections::BTreeSet;
+
+use crate::{
+    map::{
+        geometry::{Side, COORDINATE_RANGE},
+        line_def::{LineDefKey, Special},
+        sector::SectorKey,
+        Map, Sector, SideDef,
+    },
+    Point, String8,
+};
+
+/// The trick a self-referencing sector is used for, from how its floor compares to the surrounding sector's
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SelfReferenceKind {
+    /// The floor is below the surrounding floor, so things sink into what looks like the surrounding floor
+    DeepWater,
+    /// The floor is above the surrounding floor, so things walk over what looks like a drop
+    InvisibleBridge,
+    /// The floor is level with the surrounding floor, or there's no surrounding sector
+    Other,
+}
+
+/// A self-referencing sector, as found by [Map::self_referencing_sectors]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SelfReferencingSector {
+    pub sector: SectorKey,
+    /// The sector whose floor and ceiling are drawn in its place, or `None` if it's outside every other sector
+    pub surrounding: Option<SectorKey>,
+    pub kind: SelfReferenceKind,
+    /// Its lines, which all have it on both sides
+    pub lines: Vec<LineDefKey>,
+}
+
+/// The control sector added by [Map::convert_self_reference]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SelfReferenceConversion {
+    pub control_sector: SectorKey,
+    /// The line with the `TransferHeights` or `SectorSet3dFloor` special
+    pub control_line: LineDefKey,
+    /// The tag of the converted sector
+    pub tag: i16,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum ConvertSelfReferenceError {
+    #[error("{sector:?} is level with the sector around it, or has none, so there's nothing to convert it to")]
+    NotConvertible { sector: SectorKey },
+
+    #[error("every sector tag is in use, so {sector:?} can't be tagged")]
+    NoFreeTag { sector: SectorKey },
+
+    #[error("there's no room past the east edge of the map for the control sector of {sector:?}")]
+    NoRoom { sector: SectorKey },
+}
+
+impl Map {
+    /// Every self-referencing sector: sectors with at least one line, all of them two-sided with the sector on both
+    /// sides
+    pub fn self_referencing_sectors(&self) -> Vec<SelfReferencingSector> {
+        self.sectors
+            .iter()
+            .filter_map(|(key, sector)| {
+                let lines: Vec<LineDefKey> = self
+                    .line_defs
+                    .iter()
+                    .filter(|(_, line_def)| {
+                        [Side::Front, Side::Back]
+                            .into_iter()
+                            .any(|side| self.sector_on_side(line_def, side) == Some(key))
+                    })
+                    .map(|(line, _)| line)
+                    .collect();
+                let self_referencing = !lines.is_empty()
+                    && lines.iter().all(|&line| {
+                        let line_def = &self.line_defs[line];
+                        self.sector_on_side(line_def, Side::Front) == Some(key)
+                            && self.sector_on_side(line_def, Side::Back) == Some(key)
+                    });
+                if !self_referencing {
+                    return None;
+                }
+
+                let line_set: BTreeSet<LineDefKey> = lines.iter().copied().collect();
+                let (from, _) = self.line_segment(&self.line_defs[lines[0]])?;
+                let surrounding = self.sector_at_ignoring(from, |line| line_set.contains(&line));
+                let kind = match surrounding.map(|surrounding| &self.sectors[surrounding]) {
+                    Some(surrounding) if sector.floor_height < surrounding.floor_height => {
+                        SelfReferenceKind::DeepWater
+                    }
+                    Some(surrounding) if sector.floor_height > surrounding.floor_height => {
+                        SelfReferenceKind::InvisibleBridge
+                    }
+                    _ => SelfReferenceKind::Other,
+                };
+
+                Some(SelfReferencingSector {
+                    sector: key,
+                    surrounding,
+                    kind,
+                    lines,
+                })
+            })
+            .collect()
+    }
+
+    /// Convert a self-referencing sector to what ZDoom-based ports use for the same trick, so it keeps looking and
+    /// playing the same when rendered properly.
+    ///
+    /// Its lines get the surrounding sector on their outer sides. Deep water becomes a `TransferHeights` whose control
+    /// sector has the surrounding heights, so they're drawn as the water's surface. An invisible bridge is lowered to
+    /// the surrounding floor, with an invisible solid 3D floor up to its old height. The control sector is added past
+    /// the east edge of the map, walled with a texture the surrounding sector uses, and the sector is given a new tag if
+    /// it has none.
+    pub fn convert_self_reference(
+        &mut self,
+        self_reference: &SelfReferencingSector,
+    ) -> Result<SelfReferenceConversion, ConvertSelfReferenceError> {
+        let sector = self_reference.sector;
+        let (Some(surrounding), SelfReferenceKind::DeepWater | SelfReferenceKind::InvisibleBridge) =
+            (self_reference.surrounding, self_reference.kind)
+        else {
+            return Err(ConvertSelfReferenceError::NotConvertible { sector });
+        };
+
+        let tag = match self.sectors[sector].tag {
+            0 => self
+                .next_free_sector_tag()
+                .ok_or(ConvertSelfReferenceError::NoFreeTag { sector })?,
+            tag => tag,
+        };
+
+        let east = self
+            .vertexes
+            .values()
+            .map(|vertex| vertex.position.x.into_float().ceil() as i32)
+            .max()
+            .unwrap_or_default()
+            + 64;
+        if !COORDINATE_RANGE.contains(&f64::from(east + 16)) {
+            return Err(ConvertSelfReferenceError::NoRoom { sector });
+        }
+
+        for &line in &self_reference.lines {
+            let outer = self.outer_side(line, &self_reference.lines);
+            let line_def = &self.line_defs[line];
+            let Some(side) = self.side_of(line_def, outer) else {
+                continue;
+            };
+
+            // Side defs may be shared with other lines, so the outer side gets its own
+            let side_def = self.side_defs.insert(SideDef {
+                sector: surrounding,
+                ..self.side_defs[side].clone()
+            });
+            self.set_side(line, outer, side_def);
+        }
+
+        let outside = self.sectors[surrounding].clone();
+        let (control, special) = match self_reference.kind {
+            SelfReferenceKind::DeepWater => (outside, Special::TransferHeights { tag, flags: 0 }),
+            _ => {
+                let inside = &mut self.sectors[sector];
+                let control = Sector {
+                    floor_height: outside.floor_height,
+                    ceiling_height: inside.floor_height,
+                    ..inside.clone()
+                };
+                inside.floor_height = outside.floor_height;
+                inside.floor_flat = outside.floor_flat;
+
+                (
+                    control,
+                    Special::SectorSet3dFloor {
+                        tag: tag & 0xff,
+                        _type: 1,
+                        flags: 0,
+                        alpha: 0,
+                        hitag_lineid: tag >> 8,
+                    },
+                )
+            }
+        };
+        self.sectors[sector].tag = tag;
+
+        let wall_texture = self.wall_texture_near(surrounding);
+        let room = self.add_rect_room(
+            Point::new(east, 0),
+            Point::new(east + 16, 16),
+            Sector {
+                tag: 0,
+                special: Default::default(),
+                secret: false,
+                ..control
+            },
+            wall_texture,
+        );
+        let control_line = room.walls[0];
+        self.line_defs[control_line].special = special;
+
+        Ok(SelfReferenceConversion {
+            control_sector: room.sector,
+            control_line,
+            tag,
+        })
+    }
+
+    /// A middle texture to wall a new room with, preferring one on a side facing `sector`
+    fn wall_texture_near(&self, sector: SectorKey) -> String8 {
+        let no_texture = String8::new_unchecked("-");
+        let textured = || {
+            self.side_defs
+                .values()
+                .filter(|side_def| side_def.middle_texture != no_texture)
+        };
+
+        textured()
+            .find(|side_def| side_def.sector == sector)
+            .or_else(|| textured().next())
+            .map_or(no_texture.clone(), |side_def| {
+                side_def.middle_texture.clone()
+            })
+    }
+
+    /// Which side of one of the lines of a self-referencing sector faces away from the sector, by whether a point just
+    /// in front of it is inside an odd number of the sector's loops of lines
+    fn outer_side(&self, line: LineDefKey, lines: &[LineDefKey]) -> Side {
+        let Some((from, to)) = self.line_segment(&self.line_defs[line]) else {
+            return Side::Front;
+        };
+        let length = (to - from).length();
+        let front = (from + to) * 0.5 + Point::new(to.y - from.y, from.x - to.x) * (0.5 / length);
+
+        let crossings = lines
+            .iter()
+            .filter_map(|&line| self.line_segment(&self.line_defs[line]))
+            .filter(|(a, b)| {
+                (a.y <= front.y) != (b.y <= front.y)
+                    && a.x + (front.y - a.y) * (b.x - a.x) / (b.y - a.y) >= front.x
+            })
+            .count();
+
+        if crossings % 2 == 1 {
+            Side::Back
+        } else {
+            Side::Front
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::number::Number;
+
+    #[test]
+    fn convert_deep_water_and_bridge() {
+        // A room with a self-referencing pool in its west half, and a self-referencing bridge in a pit to the east
+        let mut map = Map::load_udmf_textmap(
+            "MAP01".try_into().unwrap(),
+            r#"
+            namespace = "zdoom";
+            vertex { x = 0; y = 0; }
+            vertex { x = 0; y = 256; }
+            vertex { x = 256; y = 256; }
+            vertex { x = 256; y = 0; }
+            vertex { x = 32; y = 32; }
+            vertex { x = 32; y = 96; }
+            vertex { x = 96; y = 96; }
+            vertex { x = 96; y = 32; }
+            vertex { x = 160; y = 32; }
+            vertex { x = 160; y = 224; }
+            vertex { x = 224; y = 224; }
+            vertex { x = 224; y = 32; }
+            vertex { x = 176; y = 32; }
+            vertex { x = 176; y = 224; }
+            vertex { x = 208; y = 224; }
+            vertex { x = 208; y = 32; }
+            sector { heightceiling = 128; texturefloor = "FLOOR4_8"; textureceiling = "CEIL3_5"; }
+            sector { heightfloor = -64; heightceiling = 128; texturefloor = "FWATER1"; textureceiling = "CEIL3_5"; }
+            sector { heightfloor = -128; heightceiling = 128; texturefloor = "LAVA1"; textureceiling = "CEIL3_5"; }
+            sector { heightceiling = 128; texturefloor = "FLOOR4_8"; textureceiling = "CEIL3_5"; }
+            sidedef { sector = 0; texturemiddle = "STARTAN2"; }
+            sidedef { sector = 1; }
+            sidedef { sector = 2; texturebottom = "STARTAN2"; }
+            sidedef { sector = 0; }
+            sidedef { sector = 3; }
+            linedef { v1 = 0; v2 = 1; sidefront = 0; }
+            linedef { v1 = 1; v2 = 2; sidefront = 0; }
+            linedef { v1 = 2; v2 = 3; sidefront = 0; }
+            linedef { v1 = 3; v2 = 0; sidefront = 0; }
+            linedef { v1 = 4; v2 = 5; sidefront = 1; sideback = 1; twosided = true; }
+            linedef { v1 = 5; v2 = 6; sidefront = 1; sideback = 1; twosided = true; }
+            linedef { v1 = 6; v2 = 7; sidefront = 1; sideback = 1; twosided = true; }
+            linedef { v1 = 7; v2 = 4; sidefront = 1; sideback = 1; twosided = true; }
+            linedef { v1 = 8; v2 = 9; sidefront = 2; sideback = 3; twosided = true; }
+            linedef { v1 = 9; v2 = 10; sidefront = 2; sideback = 3; twosided = true; }
+            linedef { v1 = 10; v2 = 11; sidefront = 2; sideback = 3; twosided = true; }
+            linedef { v1 = 11; v2 = 8; sidefront = 2; sideback = 3; twosided = true; }
+            linedef { v1 = 12; v2 = 13; sidefront = 4; sideback = 4; twosided = true; }
+            linedef { v1 = 13; v2 = 14; sidefront = 4; sideback = 4; twosided = true; }
+            linedef { v1 = 14; v2 = 15; sidefront = 4; sideback = 4; twosided = true; }
+            linedef { v1 = 15; v2 = 12; sidefront = 4; sideback = 4; twosided = true; }
+            "#,
+        )
+        .unwrap();
+        let sectors: Vec<_> = map.sectors.keys().collect();
+
+        let found = map.self_referencing_sectors();
+        assert_eq!(
+            found
+                .iter()
+                .map(|found| (found.sector, found.surrounding, found.kind))
+                .collect::<Vec<_>>(),
+            [
+                (sectors[1], Some(sectors[0]), SelfReferenceKind::DeepWater),
+                (
+                    sectors[3],
+                    Some(sectors[2]),
+                    SelfReferenceKind::InvisibleBridge
+                ),
+            ]
+        );
+
+        // Nothing changes if the control sector wouldn't fit past the east edge
+        let mut crowded = map.clone();
+        crowded.vertexes.values_mut().next().unwrap().position.x = Number::Int(32720);
+        assert_eq!(
+            crowded.convert_self_reference(&found[0]),
+            Err(ConvertSelfReferenceError::NoRoom { sector: sectors[1] })
+        );
+        assert_eq!(crowded.sectors.len(), map.sectors.len());
+
+        let water = map.convert_self_reference(&found[0]).unwrap();
+        assert_eq!(water.tag, 1);
+        assert_eq!(
+            map.line_defs[water.control_line].special,
+            Special::TransferHeights { tag: 1, flags: 0 }
+        );
+        assert_eq!(map.sectors[water.control_sector].floor_height.0, 0);
+        // The control sector is walled with the room's texture
+        let control_line = &map.line_defs[water.control_line];
+        let control_side = map.side_of(control_line, Side::Front).unwrap();
+        assert_eq!(map.side_defs[control_side].middle_texture, "STARTAN2");
+        // The pool's lines now face out into the room
+        for &line in &found[0].lines {
+            let line_def = &map.line_defs[line];
+            assert_eq!(map.sector_on_side(line_def, Side::Back), Some(sectors[0]));
+            assert_eq!(map.sector_on_side(line_def, Side::Front), Some(sectors[1]));
+        }
+
+        let bridge = map.convert_self_reference(&found[1]).unwrap();
+        assert_eq!(bridge.tag, 2);
+        assert_eq!(map.sectors[sectors[3]].floor_height.0, -128);
+        assert_eq!(
+            map.floors_3d_in(sectors[3])[0].control_line,
+            bridge.control_line
+        );
+        let control = &map.sectors[bridge.control_sector];
+        assert_eq!(
+            (control.floor_height.0, control.ceiling_height.0),
+            (-128, 0)
+        );
+        assert!(map.self_referencing_sectors().is_empty());
+    }
+}
@@ -1,12 +1,20 @@
 use slotmap::SlotMap;
 
-use crate::{map::sector::SectorKey, Point, String8};
+use crate::{
+    map::{sector::SectorKey, SectorIdx},
+    units::TextureOffset,
+    Point, String8,
+};
 
 #[derive(Clone, Default, PartialEq, Eq, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
 pub struct RawSideDef {
-    pub sector_idx: u16,
+    pub sector_idx: SectorIdx,
 
-    pub offset: Point<i16>,
+    pub offset: Point<TextureOffset>,
     pub upper_texture: String8,
     pub middle_texture: String8,
     pub lower_texture: String8,
@@ -16,7 +24,7 @@ pub struct RawSideDef {
 pub struct SideDef {
     pub sector: SectorKey,
 
-    pub offset: Point<i16>,
+    pub offset: Point<TextureOffset>,
     pub upper_texture: String8,
     pub middle_texture: String8,
     pub lower_texture: String8,
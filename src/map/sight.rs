@@ -0,0 +1,100 @@
+//! Line of sight between points, as the engine checks it for monsters: sight is blocked by one-sided lines and by
+//! two-sided lines with no gap between the floors and ceilings on either side, such as closed doors.
+
+use crate::{
+    map::{geometry::Side, LineDef, Map},
+    Point,
+};
+
+impl Map {
+    /// Whether there's a clear line of sight from `from` to `to`.
+    ///
+    /// This looks at the map from above, ignoring how high the points are, so sight is only blocked where a line is
+    /// closed at every height. Lines the points are on don't block sight.
+    pub fn line_of_sight(&self, from: Point<f64>, to: Point<f64>) -> bool {
+        let distance = (to - from).length();
+
+        !self
+            .trace(from, to - from)
+            .into_iter()
+            .take_while(|intersection| intersection.distance < distance)
+            .any(|intersection| {
+                intersection.distance > 0.0
+                    && self.blocks_sight(&self.line_defs[intersection.line_def])
+            })
+    }
+
+    /// Whether a line blocks sight at every height
+    fn blocks_sight(&self, line_def: &LineDef) -> bool {
+        let (Some(front), Some(back)) = (
+            self.sector_on_side(line_def, Side::Front),
+            self.sector_on_side(line_def, Side::Back),
+        ) else {
+            return true;
+        };
+        let (front, back) = (&self.sectors[front], &self.sectors[back]);
+
+        front.floor_height.max(back.floor_height) >= front.ceiling_height.min(back.ceiling_height)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::units::Height;
+
+    #[test]
+    fn sight_through_doors() {
+        // Two rooms joined by a door, with a pillar in the west room
+        let mut map = Map::load_udmf_textmap(
+            "MAP01".try_into().unwrap(),
+            r#"
+            namespace = "zdoom";
+            vertex { x = 0; y = 0; }
+            vertex { x = 0; y = 128; }
+            vertex { x = 128; y = 128; }
+            vertex { x = 128; y = 0; }
+            vertex { x = 144; y = 128; }
+            vertex { x = 144; y = 0; }
+            vertex { x = 272; y = 128; }
+            vertex { x = 272; y = 0; }
+            vertex { x = 32; y = 96; }
+            vertex { x = 64; y = 96; }
+            vertex { x = 64; y = 64; }
+            vertex { x = 32; y = 64; }
+            sector { heightceiling = 128; texturefloor = "FLAT1"; textureceiling = "FLAT1"; }
+            sector { heightceiling = 128; texturefloor = "FLAT1"; textureceiling = "FLAT1"; }
+            sector { heightceiling = 128; texturefloor = "FLAT1"; textureceiling = "FLAT1"; }
+            sidedef { sector = 0; }
+            sidedef { sector = 1; }
+            sidedef { sector = 2; }
+            linedef { v1 = 0; v2 = 1; sidefront = 0; }
+            linedef { v1 = 1; v2 = 2; sidefront = 0; }
+            linedef { v1 = 2; v2 = 3; sidefront = 0; sideback = 1; twosided = true; }
+            linedef { v1 = 3; v2 = 0; sidefront = 0; }
+            linedef { v1 = 2; v2 = 4; sidefront = 1; }
+            linedef { v1 = 4; v2 = 5; sidefront = 1; sideback = 2; twosided = true; }
+            linedef { v1 = 5; v2 = 3; sidefront = 1; }
+            linedef { v1 = 4; v2 = 6; sidefront = 2; }
+            linedef { v1 = 6; v2 = 7; sidefront = 2; }
+            linedef { v1 = 7; v2 = 5; sidefront = 2; }
+            linedef { v1 = 8; v2 = 11; sidefront = 0; }
+            linedef { v1 = 11; v2 = 10; sidefront = 0; }
+            linedef { v1 = 10; v2 = 9; sidefront = 0; }
+            linedef { v1 = 9; v2 = 8; sidefront = 0; }
+            "#,
+        )
+        .unwrap();
+        let door = map.sectors.keys().nth(1).unwrap();
+
+        let (west, east) = (Point::new(16.0, 16.0), Point::new(200.0, 64.0));
+        assert!(map.line_of_sight(west, east));
+        assert!(!map.line_of_sight(Point::new(16.0, 80.0), east));
+        assert!(!map.line_of_sight(west, Point::new(300.0, 64.0)));
+
+        map.sectors[door].ceiling_height = Height(0);
+        assert!(!map.line_of_sight(west, east));
+        assert!(map.line_of_sight(west, Point::new(100.0, 16.0)));
+    }
+}
@@ -0,0 +1,294 @@
+//! Sloped floors and ceilings, as set up by `Special::PlaneAlign` and by the floor and ceiling heights of the vertexes
+//! of triangular sectors, resolved to the plane each floor and ceiling lies in.
+
+use std::collections::BTreeSet;
+
+use slotmap::SecondaryMap;
+
+use crate::{
+    map::{
+        geometry::Side,
+        line_def::{LineDefKey, Special},
+        sector::SectorKey,
+        vertex::{Vertex, VertexKey},
+        Map,
+    },
+    Point,
+};
+
+/// A plane, as the equation `a * x + b * y + c * z + d = 0` ZDoom uses for floors and ceilings. `c` is never 0, so
+/// there's a height for every point.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Plane {
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+    pub d: f64,
+}
+
+impl Plane {
+    /// A level plane at `height`
+    pub fn flat(height: f64) -> Self {
+        Self {
+            a: 0.0,
+            b: 0.0,
+            c: 1.0,
+            d: -height,
+        }
+    }
+
+    /// The plane through three points given as `[x, y, z]`, or `None` if they're in a line seen from above
+    pub fn through(points: [[f64; 3]; 3]) -> Option<Self> {
+        let [p, q, r] = points;
+        let u = [q[0] - p[0], q[1] - p[1], q[2] - p[2]];
+        let v = [r[0] - p[0], r[1] - p[1], r[2] - p[2]];
+        let [a, b, c] = [
+            u[1] * v[2] - u[2] * v[1],
+            u[2] * v[0] - u[0] * v[2],
+            u[0] * v[1] - u[1] * v[0],
+        ];
+
+        (c.abs() > 1e-9).then(|| Self {
+            a,
+            b,
+            c,
+            d: -(a * p[0] + b * p[1] + c * p[2]),
+        })
+    }
+
+    /// The height of the plane at `point`
+    pub fn z_at(&self, point: Point<f64>) -> f64 {
+        -(self.a * point.x + self.b * point.y + self.d) / self.c
+    }
+
+    pub fn is_flat(&self) -> bool {
+        self.a == 0.0 && self.b == 0.0
+    }
+}
+
+/// Which of a sector's planes to look at
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Surface {
+    Floor,
+    Ceiling,
+}
+
+impl Map {
+    /// The plane of a sector's floor, taking slopes into account
+    pub fn floor_plane(&self, sector: SectorKey) -> Plane {
+        self.plane(sector, Surface::Floor)
+    }
+
+    /// The plane of a sector's ceiling, taking slopes into account
+    pub fn ceiling_plane(&self, sector: SectorKey) -> Plane {
+        self.plane(sector, Surface::Ceiling)
+    }
+
+    /// The height of the floor at `point`, or `None` if it's outside the map
+    pub fn floor_height_at(&self, point: Point<f64>) -> Option<f64> {
+        Some(self.floor_plane(self.sector_at(point)?).z_at(point))
+    }
+
+    /// The height of the ceiling at `point`, or `None` if it's outside the map
+    pub fn ceiling_height_at(&self, point: Point<f64>) -> Option<f64> {
+        Some(self.ceiling_plane(self.sector_at(point)?).z_at(point))
+    }
+
+    /// The floor and ceiling planes of every sector, which is quicker than asking for each in turn
+    pub(crate) fn sector_planes(&self) -> SecondaryMap<SectorKey, (Plane, Plane)> {
+        let lines = self.lines_by_sector();
+
+        self.sectors
+            .keys()
+            .map(|sector| {
+                let lines = lines.get(sector).map_or(&[][..], Vec::as_slice);
+                let floor = self.plane_with_lines(sector, Surface::Floor, lines);
+                let ceiling = self.plane_with_lines(sector, Surface::Ceiling, lines);
+
+                (sector, (floor, ceiling))
+            })
+            .collect()
+    }
+
+    /// The lines with each sector on at least one side
+    fn lines_by_sector(&self) -> SecondaryMap<SectorKey, Vec<LineDefKey>> {
+        let mut lines: SecondaryMap<SectorKey, Vec<LineDefKey>> = SecondaryMap::new();
+        for (key, line_def) in self.line_defs.iter() {
+            let front = self.sector_on_side(line_def, Side::Front);
+            let back = self
+                .sector_on_side(line_def, Side::Back)
+                .filter(|&back| Some(back) != front);
+            for sector in front.into_iter().chain(back) {
+                if let Some(entry) = lines.entry(sector) {
+                    entry.or_default().push(key);
+                }
+            }
+        }
+
+        lines
+    }
+
+    fn plane(&self, sector: SectorKey, surface: Surface) -> Plane {
+        let lines: Vec<LineDefKey> = self
+            .line_defs
+            .iter()
+            .filter(|(_, line_def)| {
+                self.sector_on_side(line_def, Side::Front) == Some(sector)
+                    || self.sector_on_side(line_def, Side::Back) == Some(sector)
+            })
+            .map(|(key, _)| key)
+            .collect();
+
+        self.plane_with_lines(sector, surface, &lines)
+    }
+
+    /// The plane of a sector, given its `lines` as found by `lines_by_sector`. Planes are resolved as in ZDoom: vertex
+    /// heights apply first, and are overridden by `Special::PlaneAlign`.
+    fn plane_with_lines(&self, sector: SectorKey, surface: Surface, lines: &[LineDefKey]) -> Plane {
+        let lines = || lines.iter().map(|&key| &self.line_defs[key]);
+        let height = |sector: SectorKey| {
+            let sector = &self.sectors[sector];
+            f64::from(match surface {
+                Surface::Floor => sector.floor_height.0,
+                Surface::Ceiling => sector.ceiling_height.0,
+            })
+        };
+        let position = |vertex: &Vertex| vertex.position.map(|n| n.into_float());
+
+        let vertexes: BTreeSet<VertexKey> = lines()
+            .flat_map(|line_def| [line_def.from, line_def.to])
+            .filter(|&vertex| self.vertexes.contains_key(vertex))
+            .collect();
+
+        let mut plane = Plane::flat(height(sector));
+
+        let vertex_z = |vertex: &Vertex| match surface {
+            Surface::Floor => vertex.floor_z,
+            Surface::Ceiling => vertex.ceiling_z,
+        };
+        if let [a, b, c] = vertexes
+            .iter()
+            .map(|&key| &self.vertexes[key])
+            .collect::<Vec<_>>()[..]
+        {
+            if [a, b, c]
+                .into_iter()
+                .any(|vertex| vertex_z(vertex).is_some())
+            {
+                let point = |vertex: &Vertex| {
+                    let Point { x, y } = position(vertex);
+                    [x, y, vertex_z(vertex).unwrap_or(height(sector))]
+                };
+                plane = Plane::through([point(a), point(b), point(c)]).unwrap_or(plane);
+            }
+        }
+
+        for line_def in lines() {
+            let Special::PlaneAlign { floor, ceiling, .. } = line_def.special else {
+                continue;
+            };
+            let (sloped, other) = match match surface {
+                Surface::Floor => floor,
+                Surface::Ceiling => ceiling,
+            } {
+                1 => (Side::Front, Side::Back),
+                2 => (Side::Back, Side::Front),
+                _ => continue,
+            };
+            let (Some(sloped), Some(other), Some((from, to))) = (
+                self.sector_on_side(line_def, sloped),
+                self.sector_on_side(line_def, other),
+                self.line_segment(line_def),
+            ) else {
+                continue;
+            };
+            if sloped != sector {
+                continue;
+            }
+
+            // The slope runs from the line, at the other sector's height, to the vertex furthest from it
+            let length = (to - from).length();
+            let distance = |point: Point<f64>| ((to - from).cross(point - from) / length).abs();
+            let Some(furthest) = vertexes
+                .iter()
+                .map(|&key| position(&self.vertexes[key]))
+                .max_by(|p, q| distance(*p).total_cmp(&distance(*q)))
+            else {
+                continue;
+            };
+
+            let z = height(other);
+            plane = Plane::through([
+                [from.x, from.y, z],
+                [to.x, to.y, z],
+                [furthest.x, furthest.y, height(sector)],
+            ])
+            .unwrap_or(plane);
+        }
+
+        plane
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn align_and_vertex_slopes() {
+        // A ramp sloping up to the east from a room, and a triangle with a raised corner
+        let map = Map::load_udmf_textmap(
+            "MAP01".try_into().unwrap(),
+            r#"
+            namespace = "zdoom";
+            vertex { x = 0; y = 0; }
+            vertex { x = 0; y = 64; }
+            vertex { x = 64; y = 64; }
+            vertex { x = 64; y = 0; }
+            vertex { x = 128; y = 64; }
+            vertex { x = 128; y = 0; }
+            vertex { x = 0; y = -64; zfloor = 32.0; }
+            sector { heightceiling = 128; texturefloor = "FLAT1"; textureceiling = "FLAT1"; }
+            sector { heightfloor = 32; heightceiling = 128; texturefloor = "FLAT1"; textureceiling = "FLAT1"; }
+            sector { heightceiling = 128; texturefloor = "FLAT1"; textureceiling = "FLAT1"; }
+            sidedef { sector = 0; }
+            sidedef { sector = 1; }
+            sidedef { sector = 2; }
+            linedef { v1 = 0; v2 = 1; sidefront = 0; }
+            linedef { v1 = 1; v2 = 2; sidefront = 0; }
+            linedef { v1 = 2; v2 = 3; sidefront = 0; sideback = 1; twosided = true; special = 181; arg0 = 2; }
+            linedef { v1 = 2; v2 = 4; sidefront = 1; }
+            linedef { v1 = 4; v2 = 5; sidefront = 1; }
+            linedef { v1 = 5; v2 = 3; sidefront = 1; }
+            linedef { v1 = 3; v2 = 0; sidefront = 0; sideback = 2; twosided = true; }
+            linedef { v1 = 6; v2 = 0; sidefront = 2; }
+            linedef { v1 = 3; v2 = 6; sidefront = 2; }
+            "#,
+        )
+        .unwrap();
+        let sectors: Vec<_> = map.sectors.keys().collect();
+
+        assert!(map.floor_plane(sectors[0]).is_flat());
+        assert!(map.ceiling_plane(sectors[1]).is_flat());
+        assert_eq!(map.floor_height_at(Point::new(96.0, 32.0)), Some(16.0));
+        assert_eq!(map.floor_height_at(Point::new(32.0, 32.0)), Some(0.0));
+
+        let triangle = map.floor_plane(sectors[2]);
+        assert_eq!(triangle.z_at(Point::new(0.0, -64.0)), 32.0);
+        assert_eq!(triangle.z_at(Point::new(64.0, 0.0)), 0.0);
+        assert_eq!(map.floor_height_at(Point::new(0.0, -32.0)), Some(16.0));
+
+        // Resolving every plane at once gives the same planes
+        let planes = map.sector_planes();
+        for sector in sectors {
+            assert_eq!(
+                planes[sector],
+                (map.floor_plane(sector), map.ceiling_plane(sector))
+            );
+        }
+
+        // The mesh follows the ramp up to its east edge
+        let mesh = map.mesh();
+        assert!(mesh.vertices.contains(&[128.0, 32.0, -64.0]));
+    }
+}
@@ -0,0 +1,497 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::map::{
+    line_def::{self, LineDefKey, Special, UdmfSpecial},
+    sector::SectorKey,
+    Map,
+};
+
+/// What kind of entity a `Special` argument refers to
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum TagKind {
+    /// A `Sector::tag`
+    Sector,
+    /// A `LineDef::id`
+    Line,
+}
+
+/// Where a `Special` keeps a tag or line id, by argument name as in `Special::arg_names`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TagArg {
+    /// The tag is held in one argument
+    Whole(&'static str),
+    /// The tag is `low + 256 * high`, as in `SectorSet3dFloor`'s hi-tag mode
+    Split {
+        low: &'static str,
+        high: &'static str,
+    },
+}
+
+/// A reference from a `LineDef`'s special to a sector tag or line id
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TagReference {
+    pub line_def: LineDefKey,
+    pub kind: TagKind,
+    /// The special arguments holding the tag
+    pub arg: TagArg,
+    pub tag: i16,
+}
+
+/// A cross-reference index between the specials in a `Map` and the sector tags and line ids they target.
+///
+/// Built with [Map::tag_graph]. The graph is a snapshot: it's not updated when the map is modified.
+#[derive(Clone, Debug, Default)]
+pub struct TagGraph {
+    /// Every tagged sector, by tag
+    pub sector_tags: BTreeMap<i16, Vec<SectorKey>>,
+    /// Every line with an id, by id
+    pub line_ids: BTreeMap<i16, Vec<LineDefKey>>,
+    /// Every reference to a sector tag or line id made by a special
+    pub references: Vec<TagReference>,
+}
+
+/// A special which can't hold the tags given to it by [Map::renumber_tags]
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+#[error("the special of {line_def:?} can't hold its renumbered tags: {special:?}")]
+pub struct RenumberTagsError {
+    pub line_def: LineDefKey,
+    /// The special with its tags renumbered
+    pub special: UdmfSpecial,
+}
+
+impl TagGraph {
+    /// The references targeting the given tag or line id
+    pub fn references_to(&self, kind: TagKind, tag: i16) -> impl Iterator<Item = &TagReference> {
+        self.references
+            .iter()
+            .filter(move |reference| reference.kind == kind && reference.tag == tag)
+    }
+
+    /// The sectors targeted by a reference. Empty if the reference is dangling or targets lines.
+    pub fn sectors_for(&self, reference: &TagReference) -> &[SectorKey] {
+        match reference.kind {
+            TagKind::Sector => self
+                .sector_tags
+                .get(&reference.tag)
+                .map(Vec::as_slice)
+                .unwrap_or_default(),
+            TagKind::Line => &[],
+        }
+    }
+
+    /// The lines targeted by a reference. Empty if the reference is dangling or targets sectors.
+    pub fn lines_for(&self, reference: &TagReference) -> &[LineDefKey] {
+        match reference.kind {
+            TagKind::Line => self
+                .line_ids
+                .get(&reference.tag)
+                .map(Vec::as_slice)
+                .unwrap_or_default(),
+            TagKind::Sector => &[],
+        }
+    }
+
+    /// References to a sector tag or line id which no entity in the map has
+    pub fn dangling(&self) -> impl Iterator<Item = &TagReference> {
+        self.references
+            .iter()
+            .filter(|reference| match reference.kind {
+                TagKind::Sector => !self.sector_tags.contains_key(&reference.tag),
+                TagKind::Line => !self.line_ids.contains_key(&reference.tag),
+            })
+    }
+
+    /// Sector tags which are assigned to at least one sector, but never referred to by a special
+    pub fn unused_sector_tags(&self) -> impl Iterator<Item = i16> + '_ {
+        self.sector_tags
+            .keys()
+            .copied()
+            .filter(|tag| self.references_to(TagKind::Sector, *tag).next().is_none())
+    }
+
+    /// Line ids which are assigned to at least one line, but never referred to by a special
+    pub fn unused_line_ids(&self) -> impl Iterator<Item = i16> + '_ {
+        self.line_ids
+            .keys()
+            .copied()
+            .filter(|id| self.references_to(TagKind::Line, *id).next().is_none())
+    }
+
+    /// Every sector tag or line id which is assigned to an entity or referred to by a special, in order
+    pub fn used(&self, kind: TagKind) -> BTreeSet<i16> {
+        let mut used: BTreeSet<i16> = match kind {
+            TagKind::Sector => self.sector_tags.keys().copied().collect(),
+            TagKind::Line => self.line_ids.keys().copied().collect(),
+        };
+
+        used.extend(
+            self.references
+                .iter()
+                .filter(|reference| reference.kind == kind)
+                .map(|reference| reference.tag),
+        );
+
+        used
+    }
+
+    /// The lowest positive sector tag or line id which no entity has and no special refers to, or `None` if they're
+    /// all taken
+    pub fn next_free(&self, kind: TagKind) -> Option<i16> {
+        let used = self.used(kind);
+
+        (1..=i16::MAX).find(|tag| !used.contains(tag))
+    }
+}
+
+/// The tag or line id references made by a special, as `(arg, kind, tag)`.
+///
+/// A tag of 0 is not a reference: depending on the special it means "no tag", the activating line's back sector, or
+/// the activating line itself.
+pub fn special_tag_references(special: &Special) -> Vec<(TagArg, TagKind, i16)> {
+    // In hi-tag mode, `SectorSet3dFloor` targets `tag + 256 * hitag_lineid`
+    if let Special::SectorSet3dFloor {
+        tag,
+        _type,
+        hitag_lineid,
+        ..
+    } = *special
+    {
+        let (arg, tag) = if _type & 8 != 0 {
+            (TagArg::Whole("tag"), tag)
+        } else {
+            (
+                TagArg::Split {
+                    low: "tag",
+                    high: "hitag_lineid",
+                },
+                tag.wrapping_add(hitag_lineid.wrapping_shl(8)),
+            )
+        };
+
+        return match tag {
+            0 => Vec::new(),
+            _ => vec![(arg, TagKind::Sector, tag)],
+        };
+    }
+
+    let args = UdmfSpecial::from(special.clone()).args;
+
+    special
+        .arg_names()
+        .iter()
+        .zip(args)
+        .filter(|(_, tag)| *tag != 0)
+        .filter_map(|(&name, tag)| {
+            let kind = match (special, name) {
+                (_, "tag" | "ptag" | "light_tag" | "lighttag" | "sectortag" | "controltag") => {
+                    TagKind::Sector
+                }
+
+                // These assign an id to the activating line rather than referring to another line
+                (Special::LineSetIdentification { .. }, _) => return None,
+                (_, "lineid" | "sourceline" | "targetline") => TagKind::Line,
+                (Special::TeleportLine { .. }, "destid") => TagKind::Line,
+
+                _ => return None,
+            };
+
+            Some((TagArg::Whole(name), kind, tag))
+        })
+        .collect()
+}
+
+/// The line id a special assigns to its own line, as `(arg, id)`, as in the Hexen format where lines have no id field
+pub fn special_line_id(special: &Special) -> Option<(TagArg, i16)> {
+    match *special {
+        Special::LineSetIdentification {
+            lineid, lineid_hi, ..
+        } => Some((
+            TagArg::Split {
+                low: "lineid",
+                high: "lineid_hi",
+            },
+            lineid.wrapping_add(lineid_hi.wrapping_shl(8)),
+        )),
+        // In line id mode, the hi-tag argument is the control line's id instead
+        Special::SectorSet3dFloor {
+            _type,
+            hitag_lineid,
+            ..
+        } if _type & 8 != 0 => Some((TagArg::Whole("hitag_lineid"), hitag_lineid)),
+        _ => None,
+    }
+    .filter(|&(_, id)| id != 0)
+}
+
+impl Map {
+    /// Build a cross-reference index of the sector tags and line ids used in this map
+    pub fn tag_graph(&self) -> TagGraph {
+        let mut graph = TagGraph::default();
+
+        for (key, sector) in self.sectors.iter() {
+            if sector.tag != 0 {
+                graph.sector_tags.entry(sector.tag).or_default().push(key);
+            }
+        }
+
+        for (key, line_def) in self.line_defs.iter() {
+            if line_def.id != line_def::NO_ID {
+                graph.line_ids.entry(line_def.id).or_default().push(key);
+            }
+            if let Some((_, id)) = special_line_id(&line_def.special) {
+                if id != line_def.id {
+                    graph.line_ids.entry(id).or_default().push(key);
+                }
+            }
+
+            for (arg, kind, tag) in special_tag_references(&line_def.special) {
+                graph.references.push(TagReference {
+                    line_def: key,
+                    kind,
+                    arg,
+                    tag,
+                });
+            }
+        }
+
+        graph
+    }
+
+    /// The lowest positive sector tag which no sector has and no special refers to, or `None` if they're all taken
+    pub fn next_free_sector_tag(&self) -> Option<i16> {
+        self.tag_graph().next_free(TagKind::Sector)
+    }
+
+    /// The lowest positive line id which no line has and no special refers to, or `None` if they're all taken
+    pub fn next_free_line_id(&self) -> Option<i16> {
+        self.tag_graph().next_free(TagKind::Line)
+    }
+
+    /// Change sector tags or line ids as given by `renumbering`, from old to new, both on the entities which have
+    /// them and in the specials which refer to or assign them. Tags not in `renumbering` are left alone, so tags can
+    /// be swapped but renumbering one onto another tag still in use merges them.
+    ///
+    /// Fails without changing the map if a special can't hold its renumbered tags.
+    pub fn renumber_tags(
+        &mut self,
+        kind: TagKind,
+        renumbering: &BTreeMap<i16, i16>,
+    ) -> Result<(), RenumberTagsError> {
+        let renumbered = |tag: i16| renumbering.get(&tag).copied().unwrap_or(tag);
+
+        let mut specials = Vec::new();
+        for (key, line_def) in self.line_defs.iter() {
+            let mut references = special_tag_references(&line_def.special);
+            if kind == TagKind::Line {
+                references.extend(
+                    special_line_id(&line_def.special).map(|(arg, id)| (arg, TagKind::Line, id)),
+                );
+            }
+            references.retain(|&(_, reference_kind, tag)| {
+                reference_kind == kind && renumbering.contains_key(&tag)
+            });
+            if references.is_empty() {
+                continue;
+            }
+
+            let arg_names = line_def.special.arg_names();
+            let index = |arg: &str| arg_names.iter().position(|&name| name == arg).unwrap();
+            let mut udmf = UdmfSpecial::from(line_def.special.clone());
+            for (arg, _, tag) in references {
+                let tag = renumbered(tag);
+                match arg {
+                    TagArg::Whole(arg) => udmf.args[index(arg)] = tag,
+                    TagArg::Split { low, high } => {
+                        udmf.args[index(low)] = tag & 0xFF;
+                        udmf.args[index(high)] = tag >> 8;
+                    }
+                }
+            }
+
+            let special = Special::try_from(udmf).map_err(|special| RenumberTagsError {
+                line_def: key,
+                special,
+            })?;
+            specials.push((key, special));
+        }
+
+        match kind {
+            TagKind::Sector => {
+                for sector in self.sectors.values_mut() {
+                    if sector.tag != 0 {
+                        sector.tag = renumbered(sector.tag);
+                    }
+                }
+            }
+            TagKind::Line => {
+                for line_def in self.line_defs.values_mut() {
+                    if line_def.id != line_def::NO_ID {
+                        line_def.id = renumbered(line_def.id);
+                    }
+                }
+            }
+        }
+
+        for (key, special) in specials {
+            self.line_defs[key].special = special;
+        }
+
+        Ok(())
+    }
+
+    /// Renumber the sector tags or line ids in use to count up from 1 without gaps, keeping their order. Returns the
+    /// renumbering, from old to new, leaving out the tags which kept their number.
+    pub fn compact_tags(&mut self, kind: TagKind) -> Result<BTreeMap<i16, i16>, RenumberTagsError> {
+        let renumbering: BTreeMap<i16, i16> = self
+            .tag_graph()
+            .used(kind)
+            .into_iter()
+            .filter(|&tag| tag > 0)
+            .zip(1..)
+            .filter(|(old, new)| old != new)
+            .collect();
+
+        self.renumber_tags(kind, &renumbering)?;
+
+        Ok(renumbering)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allocate_and_compact_tags() {
+        let mut map = Map::load_udmf_textmap(
+            "MAP01".try_into().unwrap(),
+            r#"
+            namespace = "zdoom";
+            vertex { x = 0; y = 0; }
+            vertex { x = 0; y = 64; }
+            sector { id = 3; texturefloor = "FLAT1"; textureceiling = "FLAT1"; }
+            sector { id = 7; texturefloor = "FLAT1"; textureceiling = "FLAT1"; }
+            sidedef { sector = 0; }
+            linedef { v1 = 0; v2 = 1; sidefront = 0; id = 2; special = 12; arg0 = 3; arg1 = 16; playeruse = true; }
+            linedef { v1 = 1; v2 = 0; sidefront = 0; special = 12; arg0 = 5; arg1 = 16; playeruse = true; }
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(map.next_free_sector_tag(), Some(1));
+        assert_eq!(map.next_free_line_id(), Some(1));
+
+        let renumbering = map.compact_tags(TagKind::Sector).unwrap();
+        assert_eq!(renumbering, BTreeMap::from([(3, 1), (5, 2), (7, 3)]));
+
+        let graph = map.tag_graph();
+        assert_eq!(
+            graph.sector_tags.keys().copied().collect::<Vec<_>>(),
+            [1, 3]
+        );
+        assert_eq!(graph.used(TagKind::Sector), BTreeSet::from([1, 2, 3]));
+        assert_eq!(
+            graph
+                .dangling()
+                .map(|reference| reference.tag)
+                .collect::<Vec<_>>(),
+            [2]
+        );
+        assert_eq!(map.next_free_sector_tag(), Some(4));
+
+        map.compact_tags(TagKind::Line).unwrap();
+        assert_eq!(
+            map.tag_graph().line_ids.keys().copied().collect::<Vec<_>>(),
+            [1]
+        );
+    }
+
+    #[test]
+    fn special_references() {
+        let map = Map::load_udmf_textmap(
+            "MAP01".try_into().unwrap(),
+            r#"
+            namespace = "zdoom";
+            vertex { x = 0; y = 0; }
+            vertex { x = 0; y = 64; }
+            sector { id = 5; texturefloor = "FLAT1"; textureceiling = "FLAT1"; }
+            sector { id = 6; texturefloor = "FLAT1"; textureceiling = "FLAT1"; }
+            sector { id = 300; texturefloor = "FLAT1"; textureceiling = "FLAT1"; }
+            sidedef { sector = 0; }
+            linedef { v1 = 0; v2 = 1; sidefront = 0; special = 154; arg1 = 5; playercross = true; }
+            linedef { v1 = 0; v2 = 1; sidefront = 0; special = 51; arg0 = 6; arg1 = 5; }
+            linedef { v1 = 0; v2 = 1; sidefront = 0; special = 160; arg0 = 44; arg1 = 1; arg4 = 1; }
+            linedef { v1 = 0; v2 = 1; sidefront = 0; special = 160; arg0 = 5; arg1 = 9; arg4 = 12; }
+            linedef { v1 = 0; v2 = 1; sidefront = 0; special = 121; arg0 = 4; arg2 = 1; }
+            "#,
+        )
+        .unwrap();
+
+        let graph = map.tag_graph();
+        let references: Vec<_> = graph
+            .references
+            .iter()
+            .map(|reference| (reference.arg, reference.kind, reference.tag))
+            .collect();
+        assert_eq!(
+            references,
+            [
+                (TagArg::Whole("sectortag"), TagKind::Sector, 5),
+                (TagArg::Whole("controltag"), TagKind::Sector, 6),
+                (TagArg::Whole("tag"), TagKind::Sector, 5),
+                (
+                    TagArg::Split {
+                        low: "tag",
+                        high: "hitag_lineid"
+                    },
+                    TagKind::Sector,
+                    300
+                ),
+                (TagArg::Whole("tag"), TagKind::Sector, 5),
+            ]
+        );
+        assert_eq!(graph.dangling().count(), 0);
+        assert_eq!(
+            graph.line_ids.keys().copied().collect::<Vec<_>>(),
+            [12, 260]
+        );
+    }
+
+    #[test]
+    fn renumber_split_tags() {
+        let mut map = Map::load_udmf_textmap(
+            "MAP01".try_into().unwrap(),
+            r#"
+            namespace = "zdoom";
+            vertex { x = 0; y = 0; }
+            vertex { x = 0; y = 64; }
+            sector { id = 5; texturefloor = "FLAT1"; textureceiling = "FLAT1"; }
+            sector { id = 300; texturefloor = "FLAT1"; textureceiling = "FLAT1"; }
+            sidedef { sector = 0; }
+            linedef { v1 = 0; v2 = 1; sidefront = 0; special = 160; arg0 = 44; arg1 = 1; arg4 = 1; }
+            linedef { v1 = 0; v2 = 1; sidefront = 0; special = 160; arg0 = 5; arg1 = 9; arg4 = 12; }
+            linedef { v1 = 0; v2 = 1; sidefront = 0; special = 121; arg0 = 4; arg2 = 1; }
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            map.compact_tags(TagKind::Sector).unwrap(),
+            BTreeMap::from([(5, 1), (300, 2)])
+        );
+        assert_eq!(
+            map.compact_tags(TagKind::Line).unwrap(),
+            BTreeMap::from([(12, 1), (260, 2)])
+        );
+
+        let specials: Vec<_> = map
+            .line_defs
+            .values()
+            .map(|line_def| UdmfSpecial::from(line_def.special.clone()).args)
+            .collect();
+        assert_eq!(
+            specials,
+            [[2, 1, 0, 0, 0], [1, 9, 0, 0, 1], [2, 0, 0, 0, 0]]
+        );
+    }
+}
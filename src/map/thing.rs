@@ -1,8 +1,17 @@
+use bitfield::Bit;
 use slotmap::SlotMap;
 
-use crate::Point;
+use crate::{
+    map::Map,
+    units::{Angle, Height},
+    Point,
+};
 
-#[derive(PartialEq, Eq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
 pub struct Flags {
     pub skill1: bool,
     pub skill2: bool,
@@ -55,9 +64,238 @@ impl Flags {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// All flags cleared: the thing doesn't appear on any skill or in any game mode
+    pub fn none() -> Self {
+        Self {
+            skill1: false,
+            skill2: false,
+            skill3: false,
+            skill4: false,
+            skill5: false,
+            ambush: false,
+            single: false,
+            dm: false,
+            coop: false,
+            mbf_friend: false,
+            dormant: false,
+            class1: false,
+            class2: false,
+            class3: false,
+            npc: false,
+            strife_ally: false,
+            translucent: false,
+            invisible: false,
+        }
+    }
+
+    /// Whether a thing with these flags appears on the given skill level
+    pub fn in_skill(&self, skill: Skill) -> bool {
+        match skill {
+            Skill::Skill1 => self.skill1,
+            Skill::Skill2 => self.skill2,
+            Skill::Skill3 => self.skill3,
+            Skill::Skill4 => self.skill4,
+            Skill::Skill5 => self.skill5,
+        }
+    }
+
+    /// Whether a thing with these flags appears in the given game mode
+    pub fn in_mode(&self, mode: GameMode) -> bool {
+        match mode {
+            GameMode::SinglePlayer => self.single,
+            GameMode::Cooperative => self.coop,
+            GameMode::Deathmatch => self.dm,
+        }
+    }
+}
+
+/// The bit Boom reserves in thing flags, which marks the bits above the vanilla ones as junk when set
+pub const BOOM_RESERVED_BIT: usize = 8;
+
+/// The ways the binary map formats pack thing flags into a word. They agree on the skill and ambush bits, except
+/// Strife which moves ambush, and differ above them.
+///
+/// None of them can distinguish skills 1 and 2, or 4 and 5: a thing is written as present in both if it's present in
+/// either. Flags not representable in an encoding are dropped when encoding.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FlagsEncoding {
+    /// Vanilla Doom, which only has the "multiplayer only" bit (4) above ambush
+    Doom,
+    /// Doom with the Boom "not in deathmatch" (5) and "not in co-op" (6) bits and the MBF friend bit (7). Bit 8 is
+    /// reserved: editors for vanilla Doom wrote junk above bit 4, so Boom ignores bits 5 to 7 when it's set.
+    Boom,
+    /// Hexen, with dormant and class bits, positive game mode bits, and ZDoom's translucency and friend bits
+    Hexen,
+    /// Strife, with standing, ally and translucency bits, and ambush moved to bit 5
+    Strife,
+}
+
+impl Flags {
+    /// Decode the flags of a thing in a binary format.
+    ///
+    /// The Doom "multiplayer only" and Boom "not in deathmatch" and "not in co-op" bits are negative, so they're
+    /// inverted into `single`, `dm` and `coop` rather than copied.
+    pub fn decode(bits: u16, encoding: FlagsEncoding) -> Self {
+        let bits = if encoding == FlagsEncoding::Boom && bits.bit(BOOM_RESERVED_BIT) {
+            bits & !0b1110_0000
+        } else {
+            bits
+        };
+
+        let skills = Self {
+            skill1: bits.bit(0),
+            skill2: bits.bit(0),
+            skill3: bits.bit(1),
+            skill4: bits.bit(2),
+            skill5: bits.bit(2),
+            ..Self::none()
+        };
+
+        match encoding {
+            FlagsEncoding::Doom => Self {
+                ambush: bits.bit(3),
+                single: !bits.bit(4),
+                dm: true,
+                coop: true,
+                ..skills
+            },
+            FlagsEncoding::Boom => Self {
+                ambush: bits.bit(3),
+                single: !bits.bit(4),
+                dm: !bits.bit(5),
+                coop: !bits.bit(6),
+                mbf_friend: bits.bit(7),
+                ..skills
+            },
+            FlagsEncoding::Hexen => Self {
+                ambush: bits.bit(3),
+                dormant: bits.bit(4),
+                class1: bits.bit(5),
+                class2: bits.bit(6),
+                class3: bits.bit(7),
+                single: bits.bit(8),
+                coop: bits.bit(9),
+                dm: bits.bit(10),
+                translucent: bits.bit(11),
+                invisible: bits.bit(12),
+                mbf_friend: bits.bit(13),
+                ..skills
+            },
+            FlagsEncoding::Strife => Self {
+                npc: bits.bit(3),
+                single: !bits.bit(4),
+                dm: true,
+                coop: true,
+                ambush: bits.bit(5),
+                strife_ally: bits.bit(6),
+                translucent: bits.bit(8),
+                invisible: bits.bit(9),
+                ..skills
+            },
+        }
+    }
+
+    /// Encode the flags of a thing in a binary format
+    pub fn encode(&self, encoding: FlagsEncoding) -> u16 {
+        let mut bits: u16 = 0;
+
+        bits.set_bit(0, self.skill1 || self.skill2);
+        bits.set_bit(1, self.skill3);
+        bits.set_bit(2, self.skill4 || self.skill5);
+
+        match encoding {
+            FlagsEncoding::Doom | FlagsEncoding::Boom => {
+                bits.set_bit(3, self.ambush);
+                bits.set_bit(4, !self.single);
+
+                if encoding == FlagsEncoding::Boom {
+                    bits.set_bit(5, !self.dm);
+                    bits.set_bit(6, !self.coop);
+                    bits.set_bit(7, self.mbf_friend);
+                }
+            }
+            FlagsEncoding::Hexen => {
+                bits.set_bit(3, self.ambush);
+                bits.set_bit(4, self.dormant);
+                bits.set_bit(5, self.class1);
+                bits.set_bit(6, self.class2);
+                bits.set_bit(7, self.class3);
+                bits.set_bit(8, self.single);
+                bits.set_bit(9, self.coop);
+                bits.set_bit(10, self.dm);
+                bits.set_bit(11, self.translucent);
+                bits.set_bit(12, self.invisible);
+                bits.set_bit(13, self.mbf_friend);
+            }
+            FlagsEncoding::Strife => {
+                bits.set_bit(3, self.npc);
+                bits.set_bit(4, !self.single);
+                bits.set_bit(5, self.ambush);
+                bits.set_bit(6, self.strife_ally);
+                bits.set_bit(8, self.translucent);
+                bits.set_bit(9, self.invisible);
+            }
+        }
+
+        bits
+    }
+}
+
+/// Decode the flags of a thing in the Doom binary format, including the Boom and MBF extensions
+impl From<i16> for Flags {
+    fn from(flags: i16) -> Self {
+        Self::decode(flags as u16, FlagsEncoding::Boom)
+    }
+}
+
+/// Encode the flags of a thing in the Doom binary format, including the Boom and MBF extensions
+impl From<Flags> for i16 {
+    fn from(flags: Flags) -> Self {
+        flags.encode(FlagsEncoding::Boom) as i16
+    }
+}
+
+/// A difficulty level, numbered as in UDMF. In Doom, these are "I'm too young to die" to "Nightmare!".
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Skill {
+    Skill1,
+    Skill2,
+    Skill3,
+    Skill4,
+    Skill5,
+}
+
+impl Skill {
+    pub const ALL: [Skill; 5] = [
+        Skill::Skill1,
+        Skill::Skill2,
+        Skill::Skill3,
+        Skill::Skill4,
+        Skill::Skill5,
+    ];
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GameMode {
+    SinglePlayer,
+    Cooperative,
+    Deathmatch,
+}
+
+impl GameMode {
+    pub const ALL: [GameMode; 3] = [
+        GameMode::SinglePlayer,
+        GameMode::Cooperative,
+        GameMode::Deathmatch,
+    ];
 }
 
 #[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
 pub enum Special {
     #[default]
     None,
@@ -65,10 +303,14 @@ pub enum Special {
 }
 
 #[derive(PartialEq, Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
 pub struct Thing {
     pub position: Point,
-    pub height: i16,
-    pub angle: i16,
+    pub height: Height,
+    pub angle: Angle,
     pub type_: i16,
     pub flags: Flags,
     pub special: Special,
@@ -77,3 +319,127 @@ pub struct Thing {
 slotmap::new_key_type! { pub struct ThingKey; }
 
 pub type ThingMap = SlotMap<ThingKey, Thing>;
+
+impl Map {
+    /// The things which appear on the given skill level, in any game mode
+    pub fn things_for_skill(&self, skill: Skill) -> impl Iterator<Item = (ThingKey, &Thing)> {
+        self.things
+            .iter()
+            .filter(move |(_, thing)| thing.flags.in_skill(skill))
+    }
+
+    /// The things which appear in the given game mode, on any skill level
+    pub fn things_for_mode(&self, mode: GameMode) -> impl Iterator<Item = (ThingKey, &Thing)> {
+        self.things
+            .iter()
+            .filter(move |(_, thing)| thing.flags.in_mode(mode))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::number::Number;
+
+    #[test]
+    fn doom_flags_multiplayer_round_trip() {
+        // Easy, medium, hard, multiplayer only
+        let flags = Flags::from(0b1_0111);
+
+        assert!(!flags.single);
+        assert!(flags.coop);
+        assert!(flags.dm);
+        assert_eq!(i16::from(flags), 0b1_0111);
+
+        // Boom: not in deathmatch
+        let flags = Flags::from(0b10_0111);
+
+        assert!(flags.single);
+        assert!(flags.coop);
+        assert!(!flags.dm);
+        assert_eq!(i16::from(flags), 0b10_0111);
+    }
+
+    #[test]
+    fn flag_encodings() {
+        // Easy, hard, ambush, class 2, single player and deathmatch
+        let hexen = Flags::decode(0b101_0100_1101, FlagsEncoding::Hexen);
+
+        assert!(hexen.ambush && hexen.class2 && hexen.single && hexen.dm);
+        assert!(!hexen.skill3 && !hexen.class1 && !hexen.coop);
+        assert_eq!(hexen.encode(FlagsEncoding::Hexen), 0b101_0100_1101);
+
+        // Standing, ambush, ally and translucent
+        let strife = Flags::decode(0b1_0110_1111, FlagsEncoding::Strife);
+
+        assert!(strife.npc && strife.ambush && strife.strife_ally && strife.translucent);
+        assert!(strife.single && strife.dm && strife.coop && !strife.invisible);
+        assert_eq!(strife.encode(FlagsEncoding::Strife), 0b1_0110_1111);
+
+        // Vanilla Doom has no friend bit, and ignores the Boom bits
+        let friend = Flags {
+            mbf_friend: true,
+            ..Flags::default()
+        };
+        assert_eq!(friend.encode(FlagsEncoding::Boom), 0b1000_1111);
+        assert_eq!(friend.encode(FlagsEncoding::Doom), 0b1111);
+        assert_eq!(
+            Flags::decode(0b110_0111, FlagsEncoding::Doom),
+            Flags {
+                ambush: false,
+                ..Flags::default()
+            }
+        );
+
+        // Boom's reserved bit marks the deathmatch, co-op and friend bits as junk
+        let junk = Flags::decode(0b1_1110_0111, FlagsEncoding::Boom);
+        assert!(junk.dm && junk.coop && !junk.mbf_friend);
+        assert_eq!(junk, Flags::decode(0b111, FlagsEncoding::Boom));
+        assert!(!Flags::decode(0b1110_0111, FlagsEncoding::Boom).dm);
+    }
+
+    #[test]
+    fn filtered_things() {
+        let mut map = Map::new("MAP01".try_into().unwrap());
+        let mut add = |flags: Flags| {
+            map.things.insert(Thing {
+                position: Point::new(Number::Int(0), Number::Int(0)),
+                height: Height(0),
+                angle: Angle(0),
+                type_: 3004,
+                flags,
+                special: Special::None,
+            })
+        };
+
+        let everywhere = add(Flags::default());
+        let hard_only = add(Flags {
+            skill1: false,
+            skill2: false,
+            skill3: false,
+            ..Flags::default()
+        });
+        let multiplayer_only = add(Flags {
+            single: false,
+            ..Flags::default()
+        });
+
+        let keys = |things: &mut dyn Iterator<Item = (ThingKey, &Thing)>| -> Vec<ThingKey> {
+            things.map(|(key, _)| key).collect()
+        };
+
+        assert_eq!(
+            keys(&mut map.things_for_skill(Skill::Skill2)),
+            vec![everywhere, multiplayer_only]
+        );
+        assert_eq!(
+            keys(&mut map.things_for_skill(Skill::Skill4)),
+            vec![everywhere, hard_only, multiplayer_only]
+        );
+        assert_eq!(
+            keys(&mut map.things_for_mode(GameMode::SinglePlayer)),
+            vec![everywhere, hard_only]
+        );
+    }
+}
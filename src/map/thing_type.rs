@@ -0,0 +1,877 @@
+use std::{borrow::Cow, collections::BTreeMap};
+
+use crate::map::{Map, Thing};
+
+/// The games a thing type is available in
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Games {
+    pub doom: bool,
+    pub doom2: bool,
+    pub heretic: bool,
+    pub hexen: bool,
+    pub strife: bool,
+}
+
+impl Games {
+    pub const NONE: Self = Self {
+        doom: false,
+        doom2: false,
+        heretic: false,
+        hexen: false,
+        strife: false,
+    };
+
+    pub const ALL: Self = Self {
+        doom: true,
+        doom2: true,
+        heretic: true,
+        hexen: true,
+        strife: true,
+    };
+
+    /// Both Doom and Doom 2
+    pub const DOOM: Self = Self {
+        doom: true,
+        doom2: true,
+        ..Self::NONE
+    };
+
+    /// Doom 2 only
+    pub const DOOM2: Self = Self {
+        doom2: true,
+        ..Self::NONE
+    };
+
+    pub const HERETIC: Self = Self {
+        heretic: true,
+        ..Self::NONE
+    };
+
+    pub const HEXEN: Self = Self {
+        hexen: true,
+        ..Self::NONE
+    };
+
+    /// Both of Raven's games, Heretic and Hexen, which share some items
+    pub const RAVEN: Self = Self {
+        heretic: true,
+        hexen: true,
+        ..Self::NONE
+    };
+
+    pub const STRIFE: Self = Self {
+        strife: true,
+        ..Self::NONE
+    };
+
+    /// Every game except Hexen, where the editor numbers are taken by other things
+    pub const NOT_HEXEN: Self = Self {
+        hexen: false,
+        ..Self::ALL
+    };
+
+    /// The games in either `self` or `other`
+    pub fn union(self, other: Self) -> Self {
+        Self {
+            doom: self.doom || other.doom,
+            doom2: self.doom2 || other.doom2,
+            heretic: self.heretic || other.heretic,
+            hexen: self.hexen || other.hexen,
+            strife: self.strife || other.strife,
+        }
+    }
+
+    pub fn contains(self, game: Game) -> bool {
+        match game {
+            Game::Doom => self.doom,
+            Game::Doom2 => self.doom2,
+            Game::Heretic => self.heretic,
+            Game::Hexen => self.hexen,
+            Game::Strife => self.strife,
+        }
+    }
+}
+
+/// An IWAD game, which determines which things editor numbers refer to
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Game {
+    Doom,
+    Doom2,
+    Heretic,
+    Hexen,
+    Strife,
+}
+
+/// A broad classification of thing types
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ThingCategory {
+    PlayerStart,
+    DeathmatchStart,
+    Teleport,
+    Monster,
+    Weapon,
+    Ammo,
+    Health,
+    Armor,
+    Powerup,
+    Key,
+    Obstacle,
+    Light,
+    Decoration,
+    /// Things which only exist to mark positions for specials or the engine, like map spots and polyobject anchors
+    EditorHelper,
+    /// Things registered by the user without a category
+    Other,
+}
+
+impl ThingCategory {
+    pub const ALL: [ThingCategory; 15] = [
+        ThingCategory::PlayerStart,
+        ThingCategory::DeathmatchStart,
+        ThingCategory::Teleport,
+        ThingCategory::Monster,
+        ThingCategory::Weapon,
+        ThingCategory::Ammo,
+        ThingCategory::Health,
+        ThingCategory::Armor,
+        ThingCategory::Powerup,
+        ThingCategory::Key,
+        ThingCategory::Obstacle,
+        ThingCategory::Light,
+        ThingCategory::Decoration,
+        ThingCategory::EditorHelper,
+        ThingCategory::Other,
+    ];
+
+    /// A human-readable name, e.g. for editor palettes
+    pub fn name(self) -> &'static str {
+        match self {
+            ThingCategory::PlayerStart => "Player starts",
+            ThingCategory::DeathmatchStart => "Deathmatch starts",
+            ThingCategory::Teleport => "Teleports",
+            ThingCategory::Monster => "Monsters",
+            ThingCategory::Weapon => "Weapons",
+            ThingCategory::Ammo => "Ammunition",
+            ThingCategory::Health => "Health",
+            ThingCategory::Armor => "Armor",
+            ThingCategory::Powerup => "Powerups",
+            ThingCategory::Key => "Keys",
+            ThingCategory::Obstacle => "Obstacles",
+            ThingCategory::Light => "Light sources",
+            ThingCategory::Decoration => "Decorations",
+            ThingCategory::EditorHelper => "Editor helpers",
+            ThingCategory::Other => "Other",
+        }
+    }
+
+    /// The top-level group this category belongs to
+    pub fn group(self) -> ThingGroup {
+        match self {
+            ThingCategory::PlayerStart | ThingCategory::DeathmatchStart => ThingGroup::Starts,
+            ThingCategory::Monster => ThingGroup::Monsters,
+            ThingCategory::Weapon => ThingGroup::Weapons,
+            ThingCategory::Ammo => ThingGroup::Ammo,
+            ThingCategory::Health
+            | ThingCategory::Armor
+            | ThingCategory::Powerup
+            | ThingCategory::Key => ThingGroup::Items,
+            ThingCategory::Obstacle | ThingCategory::Light | ThingCategory::Decoration => {
+                ThingGroup::Decorations
+            }
+            ThingCategory::Teleport | ThingCategory::EditorHelper => ThingGroup::EditorHelpers,
+            ThingCategory::Other => ThingGroup::Other,
+        }
+    }
+}
+
+/// The top level of the thing taxonomy, grouping related `ThingCategory`s
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ThingGroup {
+    Starts,
+    Monsters,
+    Weapons,
+    Ammo,
+    /// Health, armor, powerups and keys
+    Items,
+    /// Obstacles, light sources and purely decorative things
+    Decorations,
+    EditorHelpers,
+    Other,
+}
+
+impl ThingGroup {
+    pub const ALL: [ThingGroup; 8] = [
+        ThingGroup::Starts,
+        ThingGroup::Monsters,
+        ThingGroup::Weapons,
+        ThingGroup::Ammo,
+        ThingGroup::Items,
+        ThingGroup::Decorations,
+        ThingGroup::EditorHelpers,
+        ThingGroup::Other,
+    ];
+
+    /// A human-readable name, e.g. for editor palettes
+    pub fn name(self) -> &'static str {
+        match self {
+            ThingGroup::Starts => "Starts",
+            ThingGroup::Monsters => "Monsters",
+            ThingGroup::Weapons => "Weapons",
+            ThingGroup::Ammo => "Ammunition",
+            ThingGroup::Items => "Items",
+            ThingGroup::Decorations => "Decorations",
+            ThingGroup::EditorHelpers => "Editor helpers",
+            ThingGroup::Other => "Other",
+        }
+    }
+
+    /// The categories in this group, in order
+    pub fn categories(self) -> impl Iterator<Item = ThingCategory> {
+        ThingCategory::ALL
+            .into_iter()
+            .filter(move |category| category.group() == self)
+    }
+}
+
+/// A tree of thing types for an editor palette: groups, then categories, then types sorted by name.
+/// Empty groups and categories are omitted.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ThingTree {
+    pub groups: Vec<GroupNode>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GroupNode {
+    pub group: ThingGroup,
+    /// The games any type in this group is available in
+    pub games: Games,
+    pub categories: Vec<CategoryNode>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CategoryNode {
+    pub category: ThingCategory,
+    /// The games any type in this category is available in
+    pub games: Games,
+    pub types: Vec<ThingInfo>,
+}
+
+macro_rules! thing_types {
+    ($($kind:ident = $ed_num:literal, $class:literal, $name:literal, $category:ident, $radius:literal, $height:literal, $games:ident;)*) => {
+        /// The thing types known to waddle. Types registered at runtime resolve to `ThingKind::Custom`.
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+        pub enum ThingKind {
+            $($kind,)*
+            Custom,
+        }
+
+        const BUILTIN: &[(ThingKind, i16, &str, &str, ThingCategory, u16, u16, Games)] = &[
+            $((ThingKind::$kind, $ed_num, $class, $name, ThingCategory::$category, $radius, $height, Games::$games),)*
+        ];
+    };
+}
+
+thing_types! {
+    Player1Start = 1, "", "Player 1 start", PlayerStart, 16, 56, ALL;
+    Player2Start = 2, "", "Player 2 start", PlayerStart, 16, 56, ALL;
+    Player3Start = 3, "", "Player 3 start", PlayerStart, 16, 56, ALL;
+    Player4Start = 4, "", "Player 4 start", PlayerStart, 16, 56, ALL;
+    DeathmatchStart = 11, "", "Deathmatch start", DeathmatchStart, 16, 56, ALL;
+    TeleportDestination = 14, "TeleportDest", "Teleport destination", Teleport, 20, 16, ALL;
+
+    Zombieman = 3004, "ZombieMan", "Zombieman", Monster, 20, 56, DOOM;
+    ShotgunGuy = 9, "ShotgunGuy", "Shotgun guy", Monster, 20, 56, DOOM;
+    HeavyWeaponDude = 65, "ChaingunGuy", "Heavy weapon dude", Monster, 20, 56, DOOM2;
+    Imp = 3001, "DoomImp", "Imp", Monster, 20, 56, DOOM;
+    Demon = 3002, "Demon", "Demon", Monster, 30, 56, DOOM;
+    Spectre = 58, "Spectre", "Spectre", Monster, 30, 56, DOOM;
+    LostSoul = 3006, "LostSoul", "Lost soul", Monster, 16, 56, DOOM;
+    Cacodemon = 3005, "Cacodemon", "Cacodemon", Monster, 31, 56, DOOM;
+    HellKnight = 69, "HellKnight", "Hell knight", Monster, 24, 64, DOOM2;
+    BaronOfHell = 3003, "BaronOfHell", "Baron of Hell", Monster, 24, 64, DOOM;
+    Arachnotron = 68, "Arachnotron", "Arachnotron", Monster, 64, 64, DOOM2;
+    PainElemental = 71, "PainElemental", "Pain elemental", Monster, 31, 56, DOOM2;
+    Revenant = 66, "Revenant", "Revenant", Monster, 20, 56, DOOM2;
+    Mancubus = 67, "Fatso", "Mancubus", Monster, 48, 64, DOOM2;
+    ArchVile = 64, "Archvile", "Arch-vile", Monster, 20, 56, DOOM2;
+    SpiderMastermind = 7, "SpiderMastermind", "Spider mastermind", Monster, 128, 100, DOOM;
+    Cyberdemon = 16, "Cyberdemon", "Cyberdemon", Monster, 40, 110, DOOM;
+    WolfensteinSs = 84, "WolfensteinSS", "Wolfenstein SS", Monster, 20, 56, DOOM2;
+    CommanderKeen = 72, "CommanderKeen", "Commander Keen", Monster, 16, 72, DOOM2;
+    BossBrain = 88, "BossBrain", "Boss brain", Monster, 16, 16, DOOM2;
+    BossShooter = 89, "BossEye", "Monster spawner", EditorHelper, 20, 32, DOOM2;
+    BossTarget = 87, "BossTarget", "Spawn spot", EditorHelper, 20, 32, DOOM2;
+
+    Chainsaw = 2005, "Chainsaw", "Chainsaw", Weapon, 20, 16, DOOM;
+    Shotgun = 2001, "Shotgun", "Shotgun", Weapon, 20, 16, DOOM;
+    SuperShotgun = 82, "SuperShotgun", "Super shotgun", Weapon, 20, 16, DOOM2;
+    Chaingun = 2002, "Chaingun", "Chaingun", Weapon, 20, 16, DOOM;
+    RocketLauncher = 2003, "RocketLauncher", "Rocket launcher", Weapon, 20, 16, DOOM;
+    PlasmaGun = 2004, "PlasmaRifle", "Plasma gun", Weapon, 20, 16, DOOM;
+    Bfg9000 = 2006, "BFG9000", "BFG9000", Weapon, 20, 16, DOOM;
+
+    Clip = 2007, "Clip", "Clip", Ammo, 20, 16, DOOM;
+    BoxOfBullets = 2048, "ClipBox", "Box of bullets", Ammo, 20, 16, DOOM;
+    Shells = 2008, "Shell", "Shotgun shells", Ammo, 20, 16, DOOM;
+    BoxOfShells = 2049, "ShellBox", "Box of shotgun shells", Ammo, 20, 16, DOOM;
+    Rocket = 2010, "RocketAmmo", "Rocket", Ammo, 20, 16, DOOM;
+    BoxOfRockets = 2046, "RocketBox", "Box of rockets", Ammo, 20, 16, DOOM;
+    EnergyCell = 2047, "Cell", "Energy cell", Ammo, 20, 16, DOOM;
+    EnergyCellPack = 17, "CellPack", "Energy cell pack", Ammo, 20, 16, DOOM;
+    Backpack = 8, "Backpack", "Backpack", Ammo, 20, 16, DOOM;
+
+    Stimpack = 2011, "Stimpack", "Stimpack", Health, 20, 16, DOOM;
+    Medikit = 2012, "Medikit", "Medikit", Health, 20, 16, DOOM;
+    HealthBonus = 2014, "HealthBonus", "Health bonus", Health, 20, 16, DOOM;
+    ArmorBonus = 2015, "ArmorBonus", "Armor bonus", Armor, 20, 16, DOOM;
+    GreenArmor = 2018, "GreenArmor", "Green armor", Armor, 20, 16, DOOM;
+    BlueArmor = 2019, "BlueArmor", "Blue armor", Armor, 20, 16, DOOM;
+
+    Soulsphere = 2013, "Soulsphere", "Soulsphere", Powerup, 20, 16, DOOM;
+    Megasphere = 83, "Megasphere", "Megasphere", Powerup, 20, 16, DOOM2;
+    Invulnerability = 2022, "InvulnerabilitySphere", "Invulnerability", Powerup, 20, 16, DOOM;
+    Berserk = 2023, "Berserk", "Berserk", Powerup, 20, 16, DOOM;
+    PartialInvisibility = 2024, "BlurSphere", "Partial invisibility", Powerup, 20, 16, DOOM;
+    RadiationSuit = 2025, "RadSuit", "Radiation shielding suit", Powerup, 20, 16, DOOM;
+    ComputerAreaMap = 2026, "Allmap", "Computer area map", Powerup, 20, 16, DOOM;
+    LightAmplificationVisor = 2045, "Infrared", "Light amplification visor", Powerup, 20, 16, DOOM;
+
+    BlueKeycard = 5, "BlueCard", "Blue keycard", Key, 20, 16, DOOM;
+    RedKeycard = 13, "RedCard", "Red keycard", Key, 20, 16, DOOM;
+    YellowKeycard = 6, "YellowCard", "Yellow keycard", Key, 20, 16, DOOM;
+    BlueSkullKey = 40, "BlueSkull", "Blue skull key", Key, 20, 16, DOOM;
+    RedSkullKey = 38, "RedSkull", "Red skull key", Key, 20, 16, DOOM;
+    YellowSkullKey = 39, "YellowSkull", "Yellow skull key", Key, 20, 16, DOOM;
+
+    ExplodingBarrel = 2035, "ExplosiveBarrel", "Exploding barrel", Obstacle, 10, 42, DOOM;
+    BurningBarrel = 70, "BurningBarrel", "Burning barrel", Obstacle, 16, 16, DOOM2;
+    TallTechnoColumn = 48, "TechPillar", "Tall techno column", Obstacle, 16, 16, DOOM;
+    TallGreenPillar = 30, "TallGreenColumn", "Tall green pillar", Obstacle, 16, 16, DOOM;
+    TallRedPillar = 32, "TallRedColumn", "Tall red pillar", Obstacle, 16, 16, DOOM;
+    ShortGreenPillar = 31, "ShortGreenColumn", "Short green pillar", Obstacle, 16, 16, DOOM;
+    ShortGreenPillarWithHeart = 36, "HeartColumn", "Short green pillar with beating heart", Obstacle, 16, 16, DOOM;
+    ShortRedPillar = 33, "ShortRedColumn", "Short red pillar", Obstacle, 16, 16, DOOM;
+    ShortRedPillarWithSkull = 37, "SkullColumn", "Short red pillar with skull", Obstacle, 16, 16, DOOM;
+    Stalagmite = 47, "Stalagtite", "Stalagmite", Obstacle, 16, 16, DOOM;
+    BurntTree = 43, "TorchTree", "Burnt tree", Obstacle, 16, 16, DOOM;
+    LargeBrownTree = 54, "BigTree", "Large brown tree", Obstacle, 32, 16, DOOM;
+    EvilEye = 41, "EvilEye", "Evil eye", Obstacle, 16, 16, DOOM;
+    FloatingSkull = 42, "FloatingSkull", "Floating skull", Obstacle, 16, 16, DOOM;
+    ImpaledHuman = 25, "DeadStick", "Impaled human", Obstacle, 16, 16, DOOM;
+    TwitchingImpaledHuman = 26, "LiveStick", "Twitching impaled human", Obstacle, 16, 16, DOOM;
+    SkullOnAPole = 27, "HeadOnAStick", "Skull on a pole", Obstacle, 16, 16, DOOM;
+    FiveSkullsShishKebab = 28, "HeadsOnAStick", "Five skulls shish kebab", Obstacle, 16, 16, DOOM;
+    PileOfSkullsAndCandles = 29, "HeadCandles", "Pile of skulls and candles", Obstacle, 16, 16, DOOM;
+    HangingVictimTwitching = 49, "BloodyTwitch", "Hanging victim, twitching", Obstacle, 16, 68, DOOM;
+    HangingVictimArmsOut = 50, "Meat2", "Hanging victim, arms out", Obstacle, 16, 84, DOOM;
+    HangingVictimOneLegged = 51, "Meat3", "Hanging victim, one-legged", Obstacle, 16, 84, DOOM;
+    HangingPairOfLegs = 52, "Meat4", "Hanging pair of legs", Obstacle, 16, 68, DOOM;
+    HangingLeg = 53, "Meat5", "Hanging leg", Obstacle, 16, 52, DOOM;
+    HangingVictimGutsRemoved = 73, "HangNoGuts", "Hanging victim, guts removed", Obstacle, 16, 88, DOOM2;
+    HangingVictimGutsAndBrainRemoved = 74, "HangBNoBrain", "Hanging victim, guts and brain removed", Obstacle, 16, 88, DOOM2;
+    HangingTorsoLookingDown = 75, "HangTLookingDown", "Hanging torso, looking down", Obstacle, 16, 64, DOOM2;
+    HangingTorsoOpenSkull = 76, "HangTSkull", "Hanging torso, open skull", Obstacle, 16, 64, DOOM2;
+    HangingTorsoLookingUp = 77, "HangTLookingUp", "Hanging torso, looking up", Obstacle, 16, 64, DOOM2;
+    HangingTorsoBrainRemoved = 78, "HangTNoBrain", "Hanging torso, brain removed", Obstacle, 16, 64, DOOM2;
+
+    FloorLamp = 2028, "Column", "Floor lamp", Light, 16, 16, DOOM;
+    TallTechnoFloorLamp = 85, "TechLamp", "Tall techno floor lamp", Light, 16, 16, DOOM2;
+    ShortTechnoFloorLamp = 86, "TechLamp2", "Short techno floor lamp", Light, 16, 16, DOOM2;
+    Candle = 34, "Candlestick", "Candle", Light, 20, 16, DOOM;
+    Candelabra = 35, "Candelabra", "Candelabra", Light, 16, 16, DOOM;
+    TallBlueFirestick = 44, "BlueTorch", "Tall blue firestick", Light, 16, 16, DOOM;
+    TallGreenFirestick = 45, "GreenTorch", "Tall green firestick", Light, 16, 16, DOOM;
+    TallRedFirestick = 46, "RedTorch", "Tall red firestick", Light, 16, 16, DOOM;
+    ShortBlueFirestick = 55, "ShortBlueTorch", "Short blue firestick", Light, 16, 16, DOOM;
+    ShortGreenFirestick = 56, "ShortGreenTorch", "Short green firestick", Light, 16, 16, DOOM;
+    ShortRedFirestick = 57, "ShortRedTorch", "Short red firestick", Light, 16, 16, DOOM;
+
+    HangingVictimTwitchingNonSolid = 63, "NonsolidTwitch", "Hanging victim, twitching (non-solid)", Decoration, 20, 68, DOOM;
+    HangingVictimArmsOutNonSolid = 59, "NonsolidMeat2", "Hanging victim, arms out (non-solid)", Decoration, 20, 84, DOOM;
+    HangingVictimOneLeggedNonSolid = 61, "NonsolidMeat3", "Hanging victim, one-legged (non-solid)", Decoration, 20, 52, DOOM;
+    HangingPairOfLegsNonSolid = 60, "NonsolidMeat4", "Hanging pair of legs (non-solid)", Decoration, 20, 68, DOOM;
+    HangingLegNonSolid = 62, "NonsolidMeat5", "Hanging leg (non-solid)", Decoration, 20, 52, DOOM;
+    BloodyMess = 10, "GibbedMarine", "Bloody mess", Decoration, 20, 16, DOOM;
+    BloodyMess2 = 12, "GibbedMarineExtra", "Bloody mess 2", Decoration, 20, 16, DOOM;
+    DeadPlayer = 15, "DeadMarine", "Dead player", Decoration, 20, 16, DOOM;
+    DeadZombieman = 18, "DeadZombieMan", "Dead zombieman", Decoration, 20, 16, DOOM;
+    DeadShotgunGuy = 19, "DeadShotgunGuy", "Dead shotgun guy", Decoration, 20, 16, DOOM;
+    DeadImp = 20, "DeadDoomImp", "Dead imp", Decoration, 20, 16, DOOM;
+    DeadDemon = 21, "DeadDemon", "Dead demon", Decoration, 20, 16, DOOM;
+    DeadCacodemon = 22, "DeadCacodemon", "Dead cacodemon", Decoration, 20, 16, DOOM;
+    DeadLostSoul = 23, "DeadLostSoul", "Dead lost soul", Decoration, 20, 16, DOOM;
+    PoolOfBloodAndBones = 24, "Gibs", "Pool of blood and bones", Decoration, 20, 16, DOOM;
+    PoolOfBlood = 79, "ColonGibs", "Pool of blood", Decoration, 20, 16, DOOM2;
+    PoolOfBlood2 = 80, "SmallBloodPool", "Pool of blood 2", Decoration, 20, 16, DOOM2;
+    PoolOfBrains = 81, "BrainStem", "Pool of brains", Decoration, 20, 16, DOOM2;
+
+    Gargoyle = 66, "HereticImp", "Gargoyle", Monster, 16, 36, HERETIC;
+    FireGargoyle = 5, "HereticImpLeader", "Fire gargoyle", Monster, 16, 36, HERETIC;
+    Golem = 68, "Mummy", "Golem", Monster, 22, 62, HERETIC;
+    GolemLeader = 45, "MummyLeader", "Nitrogolem", Monster, 22, 62, HERETIC;
+    GolemGhost = 69, "MummyGhost", "Golem ghost", Monster, 22, 62, HERETIC;
+    GolemLeaderGhost = 46, "MummyLeaderGhost", "Nitrogolem ghost", Monster, 22, 62, HERETIC;
+    UndeadWarrior = 64, "Knight", "Undead warrior", Monster, 24, 78, HERETIC;
+    UndeadWarriorGhost = 65, "KnightGhost", "Undead warrior ghost", Monster, 24, 78, HERETIC;
+    Sabreclaw = 90, "Clink", "Sabreclaw", Monster, 20, 64, HERETIC;
+    Weredragon = 70, "Beast", "Weredragon", Monster, 32, 74, HERETIC;
+    Ophidian = 92, "Snake", "Ophidian", Monster, 22, 70, HERETIC;
+    DiscipleOfDsparil = 15, "Wizard", "Disciple of D'Sparil", Monster, 16, 68, HERETIC;
+    IronLich = 6, "Ironlich", "Iron lich", Monster, 40, 72, HERETIC;
+    Maulotaur = 9, "Minotaur", "Maulotaur", Monster, 28, 100, HERETIC;
+    Dsparil = 7, "Sorcerer1", "D'Sparil", Monster, 28, 100, HERETIC;
+    DsparilTeleportSpot = 56, "BossSpot", "D'Sparil teleport spot", EditorHelper, 8, 16, HERETIC;
+
+    Gauntlets = 2005, "Gauntlets", "Gauntlets of the Necromancer", Weapon, 20, 16, HERETIC;
+    EtherealCrossbow = 2001, "Crossbow", "Ethereal crossbow", Weapon, 20, 16, HERETIC;
+    DragonClaw = 53, "Blaster", "Dragon claw", Weapon, 20, 16, HERETIC;
+    Hellstaff = 2004, "SkullRod", "Hellstaff", Weapon, 20, 16, HERETIC;
+    PhoenixRod = 2003, "PhoenixRod", "Phoenix rod", Weapon, 20, 16, HERETIC;
+    Firemace = 2002, "Mace", "Firemace", Weapon, 20, 16, HERETIC;
+
+    WandCrystal = 10, "GoldWandAmmo", "Wand crystal", Ammo, 20, 16, HERETIC;
+    CrystalGeode = 12, "GoldWandHefty", "Crystal geode", Ammo, 20, 16, HERETIC;
+    EtherealArrows = 18, "CrossbowAmmo", "Ethereal arrows", Ammo, 20, 16, HERETIC;
+    QuiverOfEtherealArrows = 19, "CrossbowHefty", "Quiver of ethereal arrows", Ammo, 20, 16, HERETIC;
+    ClawOrb = 54, "BlasterAmmo", "Claw orb", Ammo, 20, 16, HERETIC;
+    EnergyOrb = 55, "BlasterHefty", "Energy orb", Ammo, 20, 16, HERETIC;
+    LesserRunes = 20, "SkullRodAmmo", "Lesser runes", Ammo, 20, 16, HERETIC;
+    GreaterRunes = 21, "SkullRodHefty", "Greater runes", Ammo, 20, 16, HERETIC;
+    FlameOrb = 22, "PhoenixRodAmmo", "Flame orb", Ammo, 20, 16, HERETIC;
+    InfernoOrb = 23, "PhoenixRodHefty", "Inferno orb", Ammo, 20, 16, HERETIC;
+    MaceSpheres = 13, "MaceAmmo", "Mace spheres", Ammo, 20, 16, HERETIC;
+    PileOfMaceSpheres = 16, "MaceHefty", "Pile of mace spheres", Ammo, 20, 16, HERETIC;
+    BagOfHolding = 8, "BagOfHolding", "Bag of holding", Ammo, 20, 16, HERETIC;
+
+    CrystalVial = 81, "CrystalVial", "Crystal vial", Health, 20, 16, RAVEN;
+    QuartzFlask = 82, "ArtiHealth", "Quartz flask", Health, 20, 16, RAVEN;
+    MysticUrn = 32, "ArtiSuperHealth", "Mystic urn", Health, 20, 16, RAVEN;
+    SilverShield = 85, "SilverShield", "Silver shield", Armor, 20, 16, HERETIC;
+    EnchantedShield = 31, "EnchantedShield", "Enchanted shield", Armor, 20, 16, HERETIC;
+
+    RingOfInvincibility = 84, "ArtiInvulnerability", "Ring of invincibility", Powerup, 20, 16, HERETIC;
+    Shadowsphere = 75, "ArtiInvisibility", "Shadowsphere", Powerup, 20, 16, HERETIC;
+    Torch = 33, "ArtiTorch", "Torch", Powerup, 20, 16, RAVEN;
+    TimeBomb = 34, "ArtiTimeBomb", "Time bomb of the ancients", Powerup, 20, 16, HERETIC;
+    MapScroll = 35, "SuperMap", "Map scroll", Powerup, 20, 16, HERETIC;
+    ChaosDevice = 36, "ArtiTeleport", "Chaos device", Powerup, 20, 16, RAVEN;
+    MorphOvum = 30, "ArtiEgg", "Morph ovum", Powerup, 20, 16, HERETIC;
+    WingsOfWrath = 83, "ArtiFly", "Wings of wrath", Powerup, 20, 16, RAVEN;
+    TomeOfPower = 86, "ArtiTomeOfPower", "Tome of power", Powerup, 20, 16, HERETIC;
+
+    GreenKey = 73, "KeyGreen", "Green key", Key, 20, 16, HERETIC;
+    BlueKey = 79, "KeyBlue", "Blue key", Key, 20, 16, HERETIC;
+    YellowKey = 80, "KeyYellow", "Yellow key", Key, 20, 16, HERETIC;
+
+    Pod = 2035, "Pod", "Pod", Obstacle, 16, 54, HERETIC;
+    HereticBarrel = 44, "Barrel", "Barrel", Obstacle, 12, 32, HERETIC;
+    BrownPillar = 47, "BrownPillar", "Brown pillar", Obstacle, 14, 128, HERETIC;
+    SmallPillar = 29, "SmallPillar", "Small pillar", Obstacle, 16, 34, HERETIC;
+    Volcano = 87, "Volcano", "Volcano", Obstacle, 12, 20, HERETIC;
+    HangingCorpse = 51, "HangingCorpse", "Hanging corpse", Obstacle, 8, 104, HERETIC;
+    SmallStalagmite = 37, "StalagmiteSmall", "Small stalagmite", Obstacle, 8, 32, HERETIC;
+    LargeStalagmite = 38, "StalagmiteLarge", "Large stalagmite", Obstacle, 12, 64, HERETIC;
+    SmallStalactite = 39, "StalactiteSmall", "Small stalactite", Obstacle, 8, 36, HERETIC;
+    LargeStalactite = 40, "StalactiteLarge", "Large stalactite", Obstacle, 12, 68, HERETIC;
+    BlueKeyStatue = 94, "KeyGizmoBlue", "Blue key statue", Obstacle, 16, 50, HERETIC;
+    GreenKeyStatue = 95, "KeyGizmoGreen", "Green key statue", Obstacle, 16, 50, HERETIC;
+    YellowKeyStatue = 96, "KeyGizmoYellow", "Yellow key statue", Obstacle, 16, 50, HERETIC;
+
+    WallTorch = 50, "WallTorch", "Wall torch", Light, 6, 16, HERETIC;
+    FireBrazier = 76, "FireBrazier", "Fire brazier", Light, 16, 44, HERETIC;
+    SerpentTorch = 27, "SerpentTorch", "Serpent torch", Light, 12, 54, HERETIC;
+    HereticChandelier = 28, "Chandelier", "Chandelier", Light, 20, 60, HERETIC;
+
+    HangingSkull = 17, "SkullHang70", "Hanging skull", Decoration, 20, 70, HERETIC;
+    Moss = 48, "Moss1", "Moss", Decoration, 20, 23, HERETIC;
+    Moss2 = 49, "Moss2", "Moss 2", Decoration, 20, 27, HERETIC;
+    TeleportGlitter = 74, "TeleGlitterGenerator1", "Teleport glitter", Decoration, 20, 16, HERETIC;
+    ExitGlitter = 52, "TeleGlitterGenerator2", "Exit glitter", Decoration, 20, 16, HERETIC;
+    PodGenerator = 43, "PodGenerator", "Pod generator", EditorHelper, 20, 16, HERETIC;
+
+    Centaur = 107, "Centaur", "Centaur", Monster, 20, 64, HEXEN;
+    Slaughtaur = 115, "CentaurLeader", "Slaughtaur", Monster, 20, 64, HEXEN;
+    GreenChaosSerpent = 31, "Demon1", "Green chaos serpent", Monster, 32, 64, HEXEN;
+    BrownChaosSerpent = 8080, "Demon2", "Brown chaos serpent", Monster, 32, 64, HEXEN;
+    Reiver = 34, "Wraith", "Reiver", Monster, 20, 55, HEXEN;
+    BuriedReiver = 10011, "WraithBuried", "Buried reiver", Monster, 20, 68, HEXEN;
+    Ettin = 10030, "Ettin", "Ettin", Monster, 25, 68, HEXEN;
+    Afrit = 10060, "FireDemon", "Afrit", Monster, 20, 68, HEXEN;
+    DarkBishop = 114, "Bishop", "Dark bishop", Monster, 22, 65, HEXEN;
+    Stalker = 121, "Serpent", "Stalker", Monster, 32, 70, HEXEN;
+    StalkerLeader = 120, "SerpentLeader", "Stalker leader", Monster, 32, 70, HEXEN;
+    Wendigo = 8020, "IceGuy", "Wendigo", Monster, 22, 75, HEXEN;
+    DeathWyvern = 254, "Dragon", "Death wyvern", Monster, 20, 65, HEXEN;
+    Heresiarch = 10080, "Heresiarch", "Heresiarch", Monster, 40, 120, HEXEN;
+    Zedek = 10100, "FighterBoss", "Zedek", Monster, 16, 64, HEXEN;
+    Traductus = 10101, "ClericBoss", "Traductus", Monster, 16, 64, HEXEN;
+    Menelkir = 10102, "MageBoss", "Menelkir", Monster, 16, 64, HEXEN;
+    Korax = 9, "Korax", "Korax", Monster, 65, 115, HEXEN;
+
+    SerpentStaff = 10, "CWeapStaff", "Serpent staff", Weapon, 20, 16, HEXEN;
+    TimonsAxe = 8010, "FWeapAxe", "Timon's axe", Weapon, 20, 16, HEXEN;
+    FrostShards = 53, "MWeapFrost", "Frost shards", Weapon, 20, 16, HEXEN;
+    HammerOfRetribution = 123, "FWeapHammer", "Hammer of retribution", Weapon, 20, 16, HEXEN;
+    Firestorm = 8009, "CWeapFlame", "Firestorm", Weapon, 20, 16, HEXEN;
+    ArcOfDeath = 8040, "MWeapLightning", "Arc of death", Weapon, 20, 16, HEXEN;
+    QuietusHilt = 12, "FWeaponPiece1", "Quietus hilt", Weapon, 20, 16, HEXEN;
+    QuietusCrosspiece = 13, "FWeaponPiece2", "Quietus crosspiece", Weapon, 20, 16, HEXEN;
+    QuietusBlade = 16, "FWeaponPiece3", "Quietus blade", Weapon, 20, 16, HEXEN;
+    WraithvergeShaft = 18, "CWeaponPiece1", "Wraithverge shaft", Weapon, 20, 16, HEXEN;
+    WraithvergeCross = 19, "CWeaponPiece2", "Wraithverge cross", Weapon, 20, 16, HEXEN;
+    WraithvergeHead = 20, "CWeaponPiece3", "Wraithverge head", Weapon, 20, 16, HEXEN;
+    BloodscourgeHead = 21, "MWeaponPiece1", "Bloodscourge head", Weapon, 20, 16, HEXEN;
+    BloodscourgeCentre = 22, "MWeaponPiece2", "Bloodscourge centre", Weapon, 20, 16, HEXEN;
+    BloodscourgeHandle = 23, "MWeaponPiece3", "Bloodscourge handle", Weapon, 20, 16, HEXEN;
+
+    BlueMana = 122, "Mana1", "Blue mana", Ammo, 20, 16, HEXEN;
+    GreenMana = 124, "Mana2", "Green mana", Ammo, 20, 16, HEXEN;
+    CombinedMana = 8004, "Mana3", "Combined mana", Ammo, 20, 16, HEXEN;
+
+    MeshArmor = 8005, "MeshArmor", "Mesh armor", Armor, 20, 16, HEXEN;
+    FalconShield = 8006, "FalconShield", "Falcon shield", Armor, 20, 16, HEXEN;
+    PlatinumHelmet = 8007, "PlatinumHelm", "Platinum helmet", Armor, 20, 16, HEXEN;
+    AmuletOfWarding = 8008, "AmuletOfWarding", "Amulet of warding", Armor, 20, 16, HEXEN;
+
+    IconOfTheDefender = 84, "ArtiInvulnerability2", "Icon of the defender", Powerup, 20, 16, HEXEN;
+    Porkalator = 30, "ArtiPork", "Porkalator", Powerup, 20, 16, HEXEN;
+    DarkServant = 86, "ArtiSummon", "Dark servant", Powerup, 20, 16, HEXEN;
+    BanishmentDevice = 10040, "ArtiTeleportOther", "Banishment device", Powerup, 20, 16, HEXEN;
+    BootsOfSpeed = 8002, "ArtiSpeedBoots", "Boots of speed", Powerup, 20, 16, HEXEN;
+    KraterOfMight = 8003, "ArtiBoostMana", "Krater of might", Powerup, 20, 16, HEXEN;
+    DragonskinBracers = 8041, "ArtiBoostArmor", "Dragonskin bracers", Powerup, 20, 16, HEXEN;
+    DiscOfRepulsion = 10110, "ArtiBlastRadius", "Disc of repulsion", Powerup, 20, 16, HEXEN;
+    Flechette = 8000, "ArtiPoisonBag", "Flechette", Powerup, 20, 16, HEXEN;
+    MysticAmbitIncant = 10120, "ArtiHealingRadius", "Mystic ambit incant", Powerup, 20, 16, HEXEN;
+
+    SteelKey = 8030, "KeySteel", "Steel key", Key, 20, 16, HEXEN;
+    CaveKey = 8031, "KeyCave", "Cave key", Key, 20, 16, HEXEN;
+    AxeKey = 8032, "KeyAxe", "Axe key", Key, 20, 16, HEXEN;
+    FireKey = 8033, "KeyFire", "Fire key", Key, 20, 16, HEXEN;
+    EmeraldKey = 8034, "KeyEmerald", "Emerald key", Key, 20, 16, HEXEN;
+    DungeonKey = 8035, "KeyDungeon", "Dungeon key", Key, 20, 16, HEXEN;
+    SilverKey = 8036, "KeySilver", "Silver key", Key, 20, 16, HEXEN;
+    RustedKey = 8037, "KeyRusted", "Rusted key", Key, 20, 16, HEXEN;
+    HornKey = 8038, "KeyHorn", "Horn key", Key, 20, 16, HEXEN;
+    SwampKey = 8039, "KeySwamp", "Swamp key", Key, 20, 16, HEXEN;
+    CastleKey = 8200, "KeyCastle", "Castle key", Key, 20, 16, HEXEN;
+
+    WingedStatue = 5, "ZWingedStatue", "Winged statue", Obstacle, 10, 62, HEXEN;
+    PoisonShroom = 8104, "ZPoisonShroom", "Poison mushroom", Obstacle, 6, 20, HEXEN;
+    Pottery = 104, "ZPottery1", "Pottery", Obstacle, 10, 32, HEXEN;
+    Pottery2 = 105, "ZPottery2", "Pottery 2", Obstacle, 10, 25, HEXEN;
+    Pottery3 = 106, "ZPottery3", "Pottery 3", Obstacle, 15, 25, HEXEN;
+
+    HexenWallTorch = 54, "ZWallTorch", "Wall torch", Light, 6, 16, HEXEN;
+    HexenWallTorchUnlit = 55, "ZWallTorchUnlit", "Wall torch (unlit)", Light, 6, 16, HEXEN;
+    TwinedTorch = 116, "ZTwinedTorch", "Twined torch", Light, 10, 64, HEXEN;
+    TwinedTorchUnlit = 117, "ZTwinedTorchUnlit", "Twined torch (unlit)", Light, 10, 64, HEXEN;
+    FireBull = 8042, "ZFireBull", "Minotaur statue", Light, 20, 80, HEXEN;
+    FireBullUnlit = 8043, "ZFireBullUnlit", "Minotaur statue (unlit)", Light, 20, 80, HEXEN;
+    HexenChandelier = 8063, "ZChandelier", "Chandelier", Light, 20, 60, HEXEN;
+    HexenChandelierUnlit = 8064, "ZChandelierUnlit", "Chandelier (unlit)", Light, 20, 60, HEXEN;
+
+    TeleportSmoke = 140, "TeleportSmoke", "Teleport smoke", Decoration, 20, 16, HEXEN;
+
+    Acolyte = 3002, "AcolyteTan", "Acolyte", Monster, 24, 64, STRIFE;
+    Reaver = 3001, "Reaver", "Reaver", Monster, 20, 60, STRIFE;
+    Templar = 3003, "Templar", "Templar", Monster, 20, 60, STRIFE;
+    Crusader = 3005, "Crusader", "Crusader", Monster, 40, 56, STRIFE;
+    Sentinel = 3006, "Sentinel", "Sentinel", Monster, 23, 53, STRIFE;
+    StrifeStalker = 186, "Stalker", "Stalker", Monster, 31, 25, STRIFE;
+    Inquisitor = 16, "Inquisitor", "Inquisitor", Monster, 40, 110, STRIFE;
+    Programmer = 71, "Programmer", "Programmer", Monster, 45, 60, STRIFE;
+    Loremaster = 12, "Loremaster", "Loremaster", Monster, 15, 56, STRIFE;
+    Entity = 128, "EntityBoss", "Entity", Monster, 130, 200, STRIFE;
+    Peasant = 3004, "Peasant1", "Peasant", Monster, 20, 56, STRIFE;
+    Rebel = 9, "Rebel1", "Rebel", Monster, 15, 56, STRIFE;
+    Oracle = 199, "Oracle", "Oracle", Monster, 15, 56, STRIFE;
+
+    StrifeCrossbow = 2001, "StrifeCrossbow", "Crossbow", Weapon, 20, 16, STRIFE;
+    AssaultGun = 2002, "AssaultGun", "Assault gun", Weapon, 20, 16, STRIFE;
+    MiniMissileLauncher = 2003, "MiniMissileLauncher", "Mini-missile launcher", Weapon, 20, 16, STRIFE;
+    Mauler = 2004, "Mauler", "Mauler", Weapon, 20, 16, STRIFE;
+    Flamethrower = 2005, "FlameThrower", "Flamethrower", Weapon, 20, 16, STRIFE;
+    GrenadeLauncher = 154, "StrifeGrenadeLauncher", "Grenade launcher", Weapon, 20, 16, STRIFE;
+
+    ClipOfBullets = 2007, "ClipOfBullets", "Clip of bullets", Ammo, 20, 16, STRIFE;
+    StrifeBoxOfBullets = 2048, "BoxOfBullets", "Ammo", Ammo, 20, 16, STRIFE;
+    ElectricBolts = 114, "ElectricBolts", "Electric bolts", Ammo, 20, 16, STRIFE;
+    PoisonBolts = 115, "PoisonBolts", "Poison bolts", Ammo, 20, 16, STRIFE;
+    MiniMissiles = 2010, "MiniMissiles", "Mini missiles", Ammo, 20, 16, STRIFE;
+    CrateOfMissiles = 2046, "CrateOfMissiles", "Crate of missiles", Ammo, 20, 16, STRIFE;
+    EnergyPod = 2047, "EnergyPod", "Energy pod", Ammo, 20, 16, STRIFE;
+    EnergyPack = 17, "EnergyPack", "Energy pack", Ammo, 20, 16, STRIFE;
+    HeGrenadeRounds = 152, "HEGrenadeRounds", "HE-grenade rounds", Ammo, 20, 16, STRIFE;
+    PhosphorusGrenadeRounds = 153, "PhosphorusGrenadeRounds", "Phosphorus-grenade rounds", Ammo, 20, 16, STRIFE;
+    AmmoSatchel = 183, "AmmoSatchel", "Ammo satchel", Ammo, 20, 16, STRIFE;
+
+    MedPatch = 2011, "MedPatch", "Med patch", Health, 20, 16, STRIFE;
+    MedicalKit = 2012, "MedicalKit", "Medical kit", Health, 20, 16, STRIFE;
+    SurgeryKit = 83, "SurgeryKit", "Surgery kit", Health, 20, 16, STRIFE;
+    LeatherArmor = 2018, "LeatherArmor", "Leather armor", Armor, 20, 16, STRIFE;
+    MetalArmor = 2019, "MetalArmor", "Metal armor", Armor, 20, 16, STRIFE;
+
+    ShadowArmor = 2024, "ShadowArmor", "Shadow armor", Powerup, 20, 16, STRIFE;
+    EnvironmentalSuit = 2025, "EnvironmentalSuit", "Environmental suit", Powerup, 20, 16, STRIFE;
+    StrifeMap = 2026, "StrifeMap", "Map", Powerup, 20, 16, STRIFE;
+    Targeter = 207, "Targeter", "Targeter", Powerup, 20, 16, STRIFE;
+    TeleporterBeacon = 10, "TeleporterBeacon", "Teleporter beacon", Powerup, 20, 16, STRIFE;
+
+    BaseKey = 230, "BaseKey", "Base key", Key, 20, 16, STRIFE;
+    GovernorsKey = 233, "GovsKey", "Governor's key", Key, 20, 16, STRIFE;
+    Passcard = 234, "Passcard", "Passcard", Key, 20, 16, STRIFE;
+
+    StrifeExplodingBarrel = 94, "ExplosiveBarrel2", "Exploding barrel", Obstacle, 10, 32, STRIFE;
+
+    MapSpot = 9001, "MapSpot", "Map spot", EditorHelper, 8, 16, ALL;
+    MapSpotGravity = 9013, "MapSpotGravity", "Map spot (gravity)", EditorHelper, 8, 16, ALL;
+    PolyobjectAnchor = 9300, "", "Polyobject anchor", EditorHelper, 8, 16, NOT_HEXEN;
+    PolyobjectStartSpot = 9301, "", "Polyobject start spot", EditorHelper, 8, 16, NOT_HEXEN;
+    PolyobjectStartSpotCrush = 9302, "", "Polyobject start spot (crushing)", EditorHelper, 8, 16, NOT_HEXEN;
+    PolyobjectStartSpotHurt = 9303, "", "Polyobject start spot (harmful)", EditorHelper, 8, 16, NOT_HEXEN;
+    HexenPolyobjectAnchor = 3000, "", "Polyobject anchor", EditorHelper, 8, 16, HEXEN;
+    HexenPolyobjectStartSpot = 3001, "", "Polyobject start spot", EditorHelper, 8, 16, HEXEN;
+    HexenPolyobjectStartSpotCrush = 3002, "", "Polyobject start spot (crushing)", EditorHelper, 8, 16, HEXEN;
+}
+
+/// A description of a thing type, as identified by its editor number
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ThingInfo {
+    pub ed_num: i16,
+    pub kind: ThingKind,
+    /// The actor class, as named in DECORATE and ZScript, e.g. `DoomImp`. Empty for things which aren't actors, such
+    /// as player starts.
+    pub class: Cow<'static, str>,
+    /// A human-readable name, e.g. `Imp`
+    pub name: Cow<'static, str>,
+    pub category: ThingCategory,
+    pub radius: u16,
+    pub height: u16,
+    pub games: Games,
+}
+
+/// A registry mapping editor numbers to `ThingInfo`s for a given game. Types are kept in order of their editor
+/// numbers, so lookups by anything else find the type with the lowest editor number.
+///
+/// Starts with the built-in types for the game, and can be extended with custom types (e.g. from DECORATE), which
+/// replace any built-in type with the same editor number.
+#[derive(Clone, Debug)]
+pub struct ThingTypes {
+    game: Game,
+    by_ed_num: BTreeMap<i16, ThingInfo>,
+}
+
+impl ThingTypes {
+    /// The built-in thing types for `game`
+    pub fn for_game(game: Game) -> Self {
+        let by_ed_num = BUILTIN
+            .iter()
+            .filter(|(.., games)| games.contains(game))
+            .map(
+                |&(kind, ed_num, class, name, category, radius, height, games)| {
+                    (
+                        ed_num,
+                        ThingInfo {
+                            ed_num,
+                            kind,
+                            class: Cow::Borrowed(class),
+                            name: Cow::Borrowed(name),
+                            category,
+                            radius,
+                            height,
+                            games,
+                        },
+                    )
+                },
+            )
+            .collect();
+
+        Self { game, by_ed_num }
+    }
+
+    pub fn game(&self) -> Game {
+        self.game
+    }
+
+    /// Add a thing type, returning the type it replaced if any
+    pub fn register(&mut self, info: ThingInfo) -> Option<ThingInfo> {
+        self.by_ed_num.insert(info.ed_num, info)
+    }
+
+    pub fn get(&self, ed_num: i16) -> Option<&ThingInfo> {
+        self.by_ed_num.get(&ed_num)
+    }
+
+    /// Find a type by its name, ignoring case
+    pub fn by_name(&self, name: &str) -> Option<&ThingInfo> {
+        self.by_ed_num
+            .values()
+            .find(|info| info.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Find a type by its actor class, ignoring case
+    pub fn by_class(&self, class: &str) -> Option<&ThingInfo> {
+        self.by_ed_num
+            .values()
+            .find(|info| !info.class.is_empty() && info.class.eq_ignore_ascii_case(class))
+    }
+
+    /// The editor number of a built-in kind in this game
+    pub fn ed_num_of(&self, kind: ThingKind) -> Option<i16> {
+        self.by_ed_num
+            .values()
+            .find(|info| info.kind == kind)
+            .map(|info| info.ed_num)
+    }
+
+    /// The types in order of their editor numbers
+    pub fn iter(&self) -> impl Iterator<Item = &ThingInfo> {
+        self.by_ed_num.values()
+    }
+
+    /// Organize the types into a `ThingTree`
+    pub fn tree(&self) -> ThingTree {
+        let groups = ThingGroup::ALL
+            .into_iter()
+            .filter_map(|group| {
+                let categories: Vec<_> = group
+                    .categories()
+                    .filter_map(|category| {
+                        let mut types: Vec<_> = self
+                            .iter()
+                            .filter(|info| info.category == category)
+                            .cloned()
+                            .collect();
+                        types.sort_by(|a, b| a.name.cmp(&b.name).then(a.ed_num.cmp(&b.ed_num)));
+
+                        let games = types
+                            .iter()
+                            .fold(Games::NONE, |games, info| games.union(info.games));
+
+                        (!types.is_empty()).then_some(CategoryNode {
+                            category,
+                            games,
+                            types,
+                        })
+                    })
+                    .collect();
+
+                let games = categories
+                    .iter()
+                    .fold(Games::NONE, |games, node| games.union(node.games));
+
+                (!categories.is_empty()).then_some(GroupNode {
+                    group,
+                    games,
+                    categories,
+                })
+            })
+            .collect();
+
+        ThingTree { groups }
+    }
+}
+
+impl Map {
+    /// The number of things in each group. Things of unknown types are counted as `ThingGroup::Other`.
+    pub fn thing_counts_by_group(&self, types: &ThingTypes) -> BTreeMap<ThingGroup, usize> {
+        let mut counts = BTreeMap::new();
+
+        for thing in self.things.values() {
+            let group = thing
+                .info(types)
+                .map_or(ThingGroup::Other, |info| info.category.group());
+            *counts.entry(group).or_default() += 1;
+        }
+
+        counts
+    }
+}
+
+impl Thing {
+    /// Look up this thing's type in `types`
+    pub fn info<'t>(&self, types: &'t ThingTypes) -> Option<&'t ThingInfo> {
+        types.get(self.type_)
+    }
+
+    /// Resolve this thing's type to a `ThingKind`, if it's known to `types`
+    pub fn kind(&self, types: &ThingTypes) -> Option<ThingKind> {
+        self.info(types).map(|info| info.kind)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_ed_nums_unique_per_game() {
+        for game in [
+            Game::Doom,
+            Game::Doom2,
+            Game::Heretic,
+            Game::Hexen,
+            Game::Strife,
+        ] {
+            let builtin = BUILTIN
+                .iter()
+                .filter(|(.., games)| games.contains(game))
+                .count();
+
+            assert_eq!(ThingTypes::for_game(game).iter().count(), builtin);
+        }
+    }
+
+    #[test]
+    fn resolve_kind() {
+        let doom = ThingTypes::for_game(Game::Doom2);
+        let hexen = ThingTypes::for_game(Game::Hexen);
+
+        assert_eq!(doom.get(16).unwrap().kind, ThingKind::Cyberdemon);
+        assert_eq!(doom.get(3001).unwrap().kind, ThingKind::Imp);
+        assert_eq!(
+            hexen.get(3001).unwrap().kind,
+            ThingKind::HexenPolyobjectStartSpot
+        );
+        assert_eq!(doom.by_name("arch-vile").unwrap().ed_num, 64);
+        assert_eq!(doom.by_class("archvile").unwrap().ed_num, 64);
+        assert!(doom.by_class("").is_none());
+
+        let heretic = ThingTypes::for_game(Game::Heretic);
+        let strife = ThingTypes::for_game(Game::Strife);
+        assert_eq!(heretic.get(66).unwrap().kind, ThingKind::Gargoyle);
+        assert_eq!(heretic.by_class("mummy").unwrap().ed_num, 68);
+        assert_eq!(hexen.get(10030).unwrap().kind, ThingKind::Ettin);
+        assert_eq!(hexen.ed_num_of(ThingKind::CrystalVial), Some(81));
+        assert_eq!(strife.get(3001).unwrap().kind, ThingKind::Reaver);
+        assert_eq!(strife.get(3004).unwrap().class, "Peasant1");
+        assert!(strife.iter().map(|info| info.ed_num).is_sorted());
+    }
+
+    #[test]
+    fn taxonomy_tree() {
+        let tree = ThingTypes::for_game(Game::Doom2).tree();
+        let groups: Vec<_> = tree.groups.iter().map(|node| node.group).collect();
+        assert_eq!(
+            groups,
+            [
+                ThingGroup::Starts,
+                ThingGroup::Monsters,
+                ThingGroup::Weapons,
+                ThingGroup::Ammo,
+                ThingGroup::Items,
+                ThingGroup::Decorations,
+                ThingGroup::EditorHelpers,
+            ]
+        );
+
+        let items = &tree.groups[4];
+        assert_eq!(items.categories.len(), 4);
+        assert_eq!(items.categories[0].types[0].name, "Health bonus");
+
+        let monsters = &tree.groups[1];
+        assert_eq!(monsters.games, Games::DOOM);
+        assert_eq!(monsters.categories[0].games, Games::DOOM);
+    }
+}
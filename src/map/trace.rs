@@ -0,0 +1,144 @@
+//! Casting rays through a map, finding every line they cross in order, e.g. to simulate hitscan attacks or pick the
+//! line under the cursor in an editor.
+
+use crate::{
+    map::{
+        geometry::{side_of_point, Side},
+        line_def::LineDefKey,
+        sector::SectorKey,
+        Map,
+    },
+    Point,
+};
+
+/// A line crossed by a ray, as found by [Map::trace]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Intersection {
+    pub line_def: LineDefKey,
+    /// How far along the ray the line is crossed
+    pub distance: f64,
+    /// Where the line is crossed
+    pub point: Point<f64>,
+    /// The side of the line the ray comes from
+    pub side: Side,
+    /// The sector on the side the ray comes from
+    pub from_sector: Option<SectorKey>,
+    /// The sector on the side the ray goes into, `None` for a one-sided line
+    pub to_sector: Option<SectorKey>,
+}
+
+impl Map {
+    /// Every line crossed by a ray cast from `origin` in `direction`, nearest first. `direction` doesn't need to be
+    /// normalized, but distances are in map units either way.
+    ///
+    /// Lines the ray runs along, rather than across, aren't included. A ray through a vertex crosses every line at
+    /// the vertex.
+    pub fn trace(&self, origin: Point<f64>, direction: Point<f64>) -> Vec<Intersection> {
+        let length = direction.length();
+        if length == 0.0 {
+            return Vec::new();
+        }
+        let direction = direction * (1.0 / length);
+
+        let mut intersections: Vec<Intersection> = self
+            .line_defs
+            .iter()
+            .filter_map(|(key, line_def)| {
+                let (from, to) = self.line_segment(line_def)?;
+                let along = to - from;
+                let denominator = direction.cross(along);
+                if denominator == 0.0 {
+                    return None;
+                }
+
+                let distance = (from - origin).cross(along) / denominator;
+                let u = (from - origin).cross(direction) / denominator;
+                if distance < 0.0 || !(0.0..=1.0).contains(&u) {
+                    return None;
+                }
+
+                let side = side_of_point(from, to, origin);
+                let other = match side {
+                    Side::Front => Side::Back,
+                    Side::Back => Side::Front,
+                };
+
+                Some(Intersection {
+                    line_def: key,
+                    distance,
+                    point: origin + direction * distance,
+                    side,
+                    from_sector: self.sector_on_side(line_def, side),
+                    to_sector: self.sector_on_side(line_def, other),
+                })
+            })
+            .collect();
+        intersections.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+
+        intersections
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trace_through_rooms() {
+        // Two rooms side by side, joined by a two-sided line
+        let map = Map::load_udmf_textmap(
+            "MAP01".try_into().unwrap(),
+            r#"
+            namespace = "zdoom";
+            vertex { x = 0; y = 0; }
+            vertex { x = 0; y = 128; }
+            vertex { x = 128; y = 128; }
+            vertex { x = 128; y = 0; }
+            vertex { x = 256; y = 128; }
+            vertex { x = 256; y = 0; }
+            sector { heightceiling = 128; texturefloor = "FLAT1"; textureceiling = "FLAT1"; }
+            sector { heightceiling = 128; texturefloor = "FLAT1"; textureceiling = "FLAT1"; }
+            sidedef { sector = 0; }
+            sidedef { sector = 1; }
+            linedef { v1 = 0; v2 = 1; sidefront = 0; }
+            linedef { v1 = 1; v2 = 2; sidefront = 0; }
+            linedef { v1 = 2; v2 = 3; sidefront = 0; sideback = 1; twosided = true; }
+            linedef { v1 = 3; v2 = 0; sidefront = 0; }
+            linedef { v1 = 2; v2 = 4; sidefront = 1; }
+            linedef { v1 = 4; v2 = 5; sidefront = 1; }
+            linedef { v1 = 5; v2 = 3; sidefront = 1; }
+            "#,
+        )
+        .unwrap();
+        let sectors: Vec<_> = map.sectors.keys().collect();
+        let line_defs: Vec<_> = map.line_defs.keys().collect();
+
+        let intersections = map.trace(Point::new(64.0, 64.0), Point::new(2.0, 0.0));
+        assert_eq!(
+            intersections,
+            [
+                Intersection {
+                    line_def: line_defs[2],
+                    distance: 64.0,
+                    point: Point::new(128.0, 64.0),
+                    side: Side::Front,
+                    from_sector: Some(sectors[0]),
+                    to_sector: Some(sectors[1]),
+                },
+                Intersection {
+                    line_def: line_defs[5],
+                    distance: 192.0,
+                    point: Point::new(256.0, 64.0),
+                    side: Side::Front,
+                    from_sector: Some(sectors[1]),
+                    to_sector: None,
+                },
+            ]
+        );
+
+        let back = map.trace(Point::new(192.0, 64.0), Point::new(-1.0, 0.0));
+        assert_eq!(back[0].side, Side::Back);
+        assert_eq!(back[0].from_sector, Some(sectors[1]));
+        assert_eq!(back[1].line_def, line_defs[0]);
+    }
+}
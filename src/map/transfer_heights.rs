@@ -0,0 +1,198 @@
+//! Fake floors and ceilings, as set up by `Special::TransferHeights`: the floor and ceiling of a control sector, on the
+//! front side of the line with the special, are drawn in every sector with the special's tag in place of its own, as
+//! used for Boom's deep water. The real floor and ceiling still stop things.
+
+use crate::{
+    map::{
+        geometry::Side,
+        line_def::{LineDefKey, Special},
+        sector::SectorKey,
+        Map,
+    },
+    units::Height,
+};
+
+/// The flags of a `TransferHeights`, as in ZDoom
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct TransferHeightsFlags {
+    /// Only the fake floor is drawn, and the real ceiling is left alone
+    pub fake_floor_only: bool,
+    /// The fake planes clip things drawn through them, like real floors and ceilings
+    pub clip_fake_planes: bool,
+    /// The space below the fake floor is underwater, with the control sector's colormap
+    pub underwater: bool,
+    /// The fake planes aren't drawn at all, and only the control sector's lighting is used
+    pub ignore_heights: bool,
+    /// The control sector's light levels aren't used
+    pub no_fake_light: bool,
+}
+
+impl From<i16> for TransferHeightsFlags {
+    fn from(flags: i16) -> Self {
+        let bit = |i: u32| flags & (1 << i) != 0;
+
+        Self {
+            fake_floor_only: bit(0),
+            clip_fake_planes: bit(1),
+            underwater: bit(2),
+            ignore_heights: bit(3),
+            no_fake_light: bit(4),
+        }
+    }
+}
+
+/// A `TransferHeights`, as found by [Map::transfer_heights]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TransferHeights {
+    /// The line with the `TransferHeights` special
+    pub control_line: LineDefKey,
+    /// The sector whose floor and ceiling are drawn as the fake planes. `None` if the control line has no front
+    /// sector, in which case nothing is transferred.
+    pub control_sector: Option<SectorKey>,
+    /// The sectors the fake planes are drawn in. Empty if no sector has the tag.
+    pub targets: Vec<SectorKey>,
+    pub tag: i16,
+    pub flags: TransferHeightsFlags,
+}
+
+/// The heights a sector is drawn at, from its own floor and ceiling and any `TransferHeights` targeting it, as found
+/// by [Map::effective_heights]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EffectiveHeights {
+    /// The real floor, which things stand on
+    pub floor: Height,
+    /// The real ceiling, which stops things
+    pub ceiling: Height,
+    /// The control sector's floor, drawn in place of the floor when viewed from above it
+    pub fake_floor: Option<Height>,
+    /// The control sector's ceiling, drawn in place of the ceiling when viewed from below it
+    pub fake_ceiling: Option<Height>,
+    /// The control sector of the `TransferHeights` applied, if any
+    pub control_sector: Option<SectorKey>,
+}
+
+impl EffectiveHeights {
+    /// The floor and ceiling heights drawn for a viewer at height `view_z`.
+    ///
+    /// A viewer between the fake planes sees them in place of the real floor and ceiling. Below the fake floor, it's
+    /// drawn as the ceiling over the real floor, and above the fake ceiling, it's drawn as the floor under the real
+    /// ceiling.
+    pub fn drawn(&self, view_z: i32) -> (Height, Height) {
+        let below = |plane: Option<Height>| plane.is_some_and(|plane| view_z < i32::from(plane));
+        let above = |plane: Option<Height>| plane.is_some_and(|plane| view_z > i32::from(plane));
+
+        if below(self.fake_floor) {
+            (self.floor, self.fake_floor.unwrap())
+        } else if above(self.fake_ceiling) {
+            (self.fake_ceiling.unwrap(), self.ceiling)
+        } else {
+            (
+                self.fake_floor.unwrap_or(self.floor),
+                self.fake_ceiling.unwrap_or(self.ceiling),
+            )
+        }
+    }
+}
+
+impl Map {
+    /// Every `TransferHeights` in the map, in the order of their control lines
+    pub fn transfer_heights(&self) -> Vec<TransferHeights> {
+        let graph = self.tag_graph();
+
+        self.line_defs
+            .iter()
+            .filter_map(|(key, line_def)| {
+                let Special::TransferHeights { tag, flags } = line_def.special else {
+                    return None;
+                };
+
+                Some(TransferHeights {
+                    control_line: key,
+                    control_sector: self.sector_on_side(line_def, Side::Front),
+                    targets: graph.sector_tags.get(&tag).cloned().unwrap_or_default(),
+                    tag,
+                    flags: flags.into(),
+                })
+            })
+            .collect()
+    }
+
+    /// The heights a sector is drawn at. If several `TransferHeights` target it, the last one applies, as in Boom.
+    pub fn effective_heights(&self, sector: SectorKey) -> EffectiveHeights {
+        let own = &self.sectors[sector];
+        let mut heights = EffectiveHeights {
+            floor: own.floor_height,
+            ceiling: own.ceiling_height,
+            fake_floor: None,
+            fake_ceiling: None,
+            control_sector: None,
+        };
+
+        let transfer = self.transfer_heights().into_iter().rev().find(|transfer| {
+            transfer.control_sector.is_some() && transfer.targets.contains(&sector)
+        });
+        if let Some(transfer) = transfer.filter(|transfer| !transfer.flags.ignore_heights) {
+            let control = &self.sectors[transfer.control_sector.unwrap()];
+
+            heights.fake_floor = Some(control.floor_height);
+            if !transfer.flags.fake_floor_only {
+                heights.fake_ceiling = Some(control.ceiling_height);
+            }
+            heights.control_sector = transfer.control_sector;
+        }
+
+        heights
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deep_water_heights() {
+        // A pool 64 deep with its surface at 0, and a control sector for it off to the side
+        let map = Map::load_udmf_textmap(
+            "MAP01".try_into().unwrap(),
+            r#"
+            namespace = "zdoom";
+            vertex { x = 0; y = 0; }
+            vertex { x = 64; y = 0; }
+            sector { id = 4; heightfloor = -64; heightceiling = 128; texturefloor = "FLAT1"; textureceiling = "FLAT1"; }
+            sector { heightceiling = 96; texturefloor = "FWATER1"; textureceiling = "FLAT1"; }
+            sidedef { sector = 0; }
+            sidedef { sector = 1; }
+            linedef { v1 = 0; v2 = 1; sidefront = 0; }
+            linedef { v1 = 1; v2 = 0; sidefront = 1; special = 209; arg0 = 4; arg1 = 4; }
+            "#,
+        )
+        .unwrap();
+        let sectors: Vec<_> = map.sectors.keys().collect();
+        let line_defs: Vec<_> = map.line_defs.keys().collect();
+
+        assert_eq!(
+            map.transfer_heights(),
+            [TransferHeights {
+                control_line: line_defs[1],
+                control_sector: Some(sectors[1]),
+                targets: vec![sectors[0]],
+                tag: 4,
+                flags: TransferHeightsFlags {
+                    underwater: true,
+                    ..Default::default()
+                },
+            }]
+        );
+
+        let heights = map.effective_heights(sectors[0]);
+        assert_eq!(heights.fake_floor, Some(Height(0)));
+        assert_eq!(heights.fake_ceiling, Some(Height(96)));
+        assert_eq!(heights.drawn(41), (Height(0), Height(96)));
+        assert_eq!(heights.drawn(-20), (Height(-64), Height(0)));
+        assert_eq!(heights.drawn(100), (Height(96), Height(128)));
+
+        let control = map.effective_heights(sectors[1]);
+        assert_eq!(control.control_sector, None);
+        assert_eq!(control.drawn(41), (Height(0), Height(96)));
+    }
+}
@@ -0,0 +1,875 @@
+use std::{collections::HashMap, num::NonZeroU16};
+
+use crate::{
+    map::{
+        geometry::{check_coordinate, checked_i16, GeometryOverflow, Side},
+        line_def::{LineDefKey, Special},
+        lint::EntityRef,
+        side_def::SideDefKey,
+        thing::ThingKey,
+        thing_type::{ThingKind, ThingTypes},
+        Map, Thing,
+    },
+    number::Number,
+    units::{Angle, TextureOffset},
+    Point, String8,
+};
+
+/// The granularity with which a map format can store thing facing angles
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AngleResolution {
+    /// Whole degrees, as in UDMF and the Hexen format
+    Degree,
+    /// Multiples of 45 degrees, the only facings the vanilla Doom engine distinguishes
+    Octant,
+}
+
+impl AngleResolution {
+    fn step(self) -> i32 {
+        match self {
+            AngleResolution::Degree => 1,
+            AngleResolution::Octant => 45,
+        }
+    }
+
+    /// Round an angle to the nearest angle representable at this resolution, in the range `0..360`
+    pub fn quantize(self, angle: Angle) -> Angle {
+        let step = self.step();
+        let rounded = ((f64::from(angle) / step as f64).round() as i32) * step;
+
+        Angle(rounded.rem_euclid(360) as i16)
+    }
+}
+
+/// The line a map is mirrored across by [Map::mirror]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MirrorAxis {
+    /// A vertical line, swapping east and west
+    Vertical,
+    /// A horizontal line, swapping north and south
+    Horizontal,
+}
+
+/// A thing angle which was changed when quantizing to an `AngleResolution`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AngleQuantization {
+    pub thing: ThingKey,
+    pub original: Angle,
+    pub quantized: Angle,
+}
+
+/// Configuration for [Map::scale]
+#[derive(Clone, Debug, Default)]
+pub struct ScaleOptions {
+    /// Scale side def x offsets along with the map, so textures stay aligned relative to the start of each wall
+    pub adjust_texture_offsets: bool,
+    /// The widths of the textures used in the map, used to detect new seams. Textures not listed are not checked.
+    pub texture_widths: HashMap<String8, u16>,
+}
+
+/// A wall whose length used to be a multiple of its texture's width, but no longer is after scaling, so the texture
+/// will visibly repeat partway through the next wall
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextureSeam {
+    pub side_def: SideDefKey,
+    pub texture: String8,
+    pub width: u16,
+    /// The length of the wall after scaling
+    pub length: f64,
+}
+
+impl Map {
+    /// Move the whole map by `offset`.
+    ///
+    /// This moves vertexes, things and polyobject movement destinations. Integer coordinates stay integers when
+    /// moved by whole units. Texture offsets are relative to the walls, so they don't need adjusting.
+    ///
+    /// Fails without changing the map if any coordinate would leave `COORDINATE_RANGE`.
+    pub fn translate(&mut self, offset: Point<f64>) -> Result<(), GeometryOverflow> {
+        self.transform_positions(|entity, point| {
+            Ok(Point::new(
+                transformed_number(entity, "x", point.x, point.x.into_float() + offset.x)?,
+                transformed_number(entity, "y", point.y, point.y.into_float() + offset.y)?,
+            ))
+        })
+    }
+
+    /// Rotate the whole map counter-clockwise by `degrees` around `origin`.
+    ///
+    /// This moves vertexes and things, turns thing facing angles, and adjusts the direction and destination arguments
+    /// of polyobject movement specials. Polyobject anchors and start spots keep their angle, since it holds the
+    /// polyobject number rather than a direction.
+    ///
+    /// Integer coordinates stay integers when rotated by multiples of 90 degrees; other rotations produce floats,
+    /// rounded to the 16.16 fixed point numbers ports store coordinates as.
+    /// Angles are rounded to the nearest degree. [Map::to_doom_binary] rounds them further to multiples of 45 degrees,
+    /// and [Map::quantize_thing_angles] does so in place.
+    ///
+    /// Fails without changing the map if any coordinate would leave `COORDINATE_RANGE`.
+    pub fn rotate(
+        &mut self,
+        origin: Point<f64>,
+        degrees: f64,
+        thing_types: &ThingTypes,
+    ) -> Result<(), GeometryOverflow> {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+
+        self.transform_positions(|entity, point| {
+            let x = point.x.into_float() - origin.x;
+            let y = point.y.into_float() - origin.y;
+
+            Ok(Point::new(
+                transformed_number(entity, "x", point.x, origin.x + x * cos - y * sin)?,
+                transformed_number(entity, "y", point.y, origin.y + x * sin + y * cos)?,
+            ))
+        })?;
+
+        for thing in self.things.values_mut() {
+            if !is_polyobject_spot(thing.type_, thing_types) {
+                thing.angle = rotate_degrees(thing.angle, degrees);
+            }
+        }
+
+        for line_def in self.line_defs.values_mut() {
+            if let Special::PolyobjMove { angle, .. }
+            | Special::PolyobjMoveTimes8 { angle, .. }
+            | Special::PolyobjDoorSlide { angle, .. }
+            | Special::PolyobjOrMove { angle, .. }
+            | Special::PolyobjOrMoveTimes8 { angle, .. } = &mut line_def.special
+            {
+                *angle = rotate_degrees(*angle, degrees);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Scale the whole map by `factor` around `origin`.
+    ///
+    /// This moves vertexes, things and polyobject movement destinations. Integer coordinates stay integers when the
+    /// scaled value is integral. Thing heights and sector floor/ceiling heights are not changed.
+    ///
+    /// Returns the walls which gain a texture seam, for every side def using a texture in
+    /// `ScaleOptions::texture_widths`. Fails without changing the map if any coordinate would leave
+    /// `COORDINATE_RANGE`, or a texture offset would no longer fit.
+    pub fn scale(
+        &mut self,
+        origin: Point<f64>,
+        factor: f64,
+        options: &ScaleOptions,
+    ) -> Result<Vec<TextureSeam>, GeometryOverflow> {
+        let old_lengths = self.wall_lengths();
+
+        let offsets = if options.adjust_texture_offsets {
+            self.side_defs
+                .iter()
+                .map(|(key, side_def)| {
+                    let offset = f64::from(side_def.offset.x) * factor;
+                    Ok((key, checked_i16(EntityRef::SideDef(key), "offset", offset)?))
+                })
+                .collect::<Result<Vec<_>, _>>()?
+        } else {
+            Vec::new()
+        };
+
+        self.transform_positions(|entity, point| {
+            Ok(Point::new(
+                transformed_number(
+                    entity,
+                    "x",
+                    point.x,
+                    origin.x + (point.x.into_float() - origin.x) * factor,
+                )?,
+                transformed_number(
+                    entity,
+                    "y",
+                    point.y,
+                    origin.y + (point.y.into_float() - origin.y) * factor,
+                )?,
+            ))
+        })?;
+
+        for (key, offset) in offsets {
+            self.side_defs[key].offset.x = TextureOffset(offset);
+        }
+
+        let new_lengths = self.wall_lengths();
+        let mut seams = Vec::new();
+
+        for (side_def_key, side_def) in self.side_defs.iter() {
+            let (Some(&old_length), Some(&new_length)) = (
+                old_lengths.get(&side_def_key),
+                new_lengths.get(&side_def_key),
+            ) else {
+                continue;
+            };
+
+            let textures = [
+                &side_def.upper_texture,
+                &side_def.middle_texture,
+                &side_def.lower_texture,
+            ];
+
+            for texture in textures {
+                let Some(&width) = options.texture_widths.get(texture) else {
+                    continue;
+                };
+
+                if divides(width, old_length) && !divides(width, new_length) {
+                    seams.push(TextureSeam {
+                        side_def: side_def_key,
+                        texture: texture.clone(),
+                        width,
+                        length: new_length,
+                    });
+                }
+            }
+        }
+
+        Ok(seams)
+    }
+
+    /// Mirror the whole map across the line through `origin` along `axis`.
+    ///
+    /// Besides moving vertexes and things, this reverses every line so its front side stays on its right, reflects
+    /// thing facing angles and polyobject movement, and swaps polyobject rotation specials between left and right.
+    /// Side def x offsets are changed so textures tile from the same points along each wall as before, although they
+    /// are still drawn the right way round.
+    ///
+    /// Fails without changing the map if any coordinate would leave `COORDINATE_RANGE`, or a texture offset would no
+    /// longer fit.
+    pub fn mirror(
+        &mut self,
+        origin: Point<f64>,
+        axis: MirrorAxis,
+        thing_types: &ThingTypes,
+    ) -> Result<(), GeometryOverflow> {
+        let lengths = self.wall_lengths();
+        let offsets = self
+            .side_defs
+            .iter()
+            .filter_map(|(key, side_def)| Some((key, side_def, lengths.get(&key)?)))
+            .map(|(key, side_def, length)| {
+                let offset = -(f64::from(side_def.offset.x) + length);
+                Ok((key, checked_i16(EntityRef::SideDef(key), "offset", offset)?))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        self.transform_positions(|entity, point| {
+            let (x, y) = (point.x.into_float(), point.y.into_float());
+
+            Ok(match axis {
+                MirrorAxis::Vertical => Point::new(
+                    transformed_number(entity, "x", point.x, 2.0 * origin.x - x)?,
+                    point.y,
+                ),
+                MirrorAxis::Horizontal => Point::new(
+                    point.x,
+                    transformed_number(entity, "y", point.y, 2.0 * origin.y - y)?,
+                ),
+            })
+        })?;
+
+        for (key, offset) in offsets {
+            self.side_defs[key].offset.x = TextureOffset(offset);
+        }
+
+        for thing in self.things.values_mut() {
+            if !is_polyobject_spot(thing.type_, thing_types) {
+                thing.angle = mirror_degrees(thing.angle, axis);
+            }
+        }
+
+        for line_def in self.line_defs.values_mut() {
+            (line_def.from, line_def.to) = (line_def.to, line_def.from);
+
+            line_def.special = match line_def.special.clone() {
+                Special::PolyobjRotateLeft { po, speed, angle } => {
+                    Special::PolyobjRotateRight { po, speed, angle }
+                }
+                Special::PolyobjRotateRight { po, speed, angle } => {
+                    Special::PolyobjRotateLeft { po, speed, angle }
+                }
+                Special::PolyobjOrRotateLeft { po, speed, angle } => {
+                    Special::PolyobjOrRotateRight { po, speed, angle }
+                }
+                Special::PolyobjOrRotateRight { po, speed, angle } => {
+                    Special::PolyobjOrRotateLeft { po, speed, angle }
+                }
+                mut special => {
+                    if let Special::PolyobjMove { angle, .. }
+                    | Special::PolyobjMoveTimes8 { angle, .. }
+                    | Special::PolyobjDoorSlide { angle, .. }
+                    | Special::PolyobjOrMove { angle, .. }
+                    | Special::PolyobjOrMoveTimes8 { angle, .. } = &mut special
+                    {
+                        *angle = mirror_degrees(*angle, axis);
+                    }
+                    special
+                }
+            };
+        }
+
+        Ok(())
+    }
+
+    /// Round every vertex, thing and polyobject destination to the nearest multiple of `grid`.
+    ///
+    /// Side def x offsets are adjusted for how far the start of each wall moved along it, so textures stay where they
+    /// were on the wall. A side def shared by walls which moved by different amounts is copied for each of them but
+    /// the first. Returns the lines whose ends were snapped to the same point, which are left in the map.
+    ///
+    /// Fails without changing the map if any coordinate would leave `COORDINATE_RANGE`, or a texture offset would no
+    /// longer fit.
+    pub fn snap_to_grid(&mut self, grid: NonZeroU16) -> Result<Vec<LineDefKey>, GeometryOverflow> {
+        let grid = f64::from(grid.get());
+        let snap = |point: Point| point.map(|value| (value.into_float() / grid).round() * grid);
+
+        let mut offsets = Vec::new();
+        for (line, line_def) in self.line_defs.iter() {
+            let (Some(from), Some(to)) = (
+                self.vertexes.get(line_def.from),
+                self.vertexes.get(line_def.to),
+            ) else {
+                continue;
+            };
+            let (old_from, old_to) = (
+                from.position.map(Number::into_float),
+                to.position.map(Number::into_float),
+            );
+            let length = (old_to - old_from).length();
+            if length == 0.0 {
+                continue;
+            }
+            let direction = (old_to - old_from) * (1.0 / length);
+
+            let sides = [
+                (
+                    Side::Front,
+                    Some(line_def.left_side),
+                    old_from,
+                    snap(from.position),
+                    direction,
+                ),
+                (
+                    Side::Back,
+                    line_def.right_side,
+                    old_to,
+                    snap(to.position),
+                    direction * -1.0,
+                ),
+            ];
+            for (side, side_def, old_start, new_start, direction) in sides {
+                let Some((key, side_def)) =
+                    side_def.and_then(|key| Some((key, self.side_defs.get(key)?)))
+                else {
+                    continue;
+                };
+                let offset = f64::from(side_def.offset.x) + (new_start - old_start).dot(direction);
+                let offset = checked_i16(EntityRef::SideDef(key), "offset", offset)?;
+                offsets.push((line, side, key, offset));
+            }
+        }
+
+        self.transform_positions(|entity, point| {
+            let snapped = snap(point);
+
+            Ok(Point::new(
+                transformed_number(entity, "x", Number::Int(0), snapped.x)?,
+                transformed_number(entity, "y", Number::Int(0), snapped.y)?,
+            ))
+        })?;
+
+        let mut assigned = HashMap::new();
+        for (line, side, key, offset) in offsets {
+            match assigned.get(&key) {
+                None => {
+                    assigned.insert(key, offset);
+                    self.side_defs[key].offset.x = TextureOffset(offset);
+                }
+                Some(&assigned) if assigned == offset => {}
+                Some(_) => {
+                    let mut side_def = self.side_defs[key].clone();
+                    side_def.offset.x = TextureOffset(offset);
+                    let copy = self.side_defs.insert(side_def);
+                    self.set_side(line, side, copy);
+                }
+            }
+        }
+
+        Ok(self
+            .line_defs
+            .iter()
+            .filter(|(_, line_def)| {
+                let position = |key| self.vertexes.get(key).map(|vertex| vertex.position);
+                position(line_def.from) == position(line_def.to)
+            })
+            .map(|(key, _)| key)
+            .collect())
+    }
+
+    /// Move vertexes, things and polyobject destinations with `transform`. Every position is computed before any is
+    /// changed, so the map is left untouched on error.
+    fn transform_positions(
+        &mut self,
+        transform: impl Fn(EntityRef, Point) -> Result<Point, GeometryOverflow>,
+    ) -> Result<(), GeometryOverflow> {
+        let vertexes = self
+            .vertexes
+            .iter()
+            .map(|(key, vertex)| Ok((key, transform(EntityRef::Vertex(key), vertex.position)?)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let things = self
+            .things
+            .iter()
+            .map(|(key, thing)| Ok((key, transform(EntityRef::Thing(key), thing.position)?)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let destinations = self
+            .line_defs
+            .iter()
+            .filter_map(|(key, line_def)| match line_def.special {
+                Special::PolyobjMoveTo { pos_x, pos_y, .. }
+                | Special::PolyobjOrMoveTo { pos_x, pos_y, .. } => Some((key, pos_x, pos_y)),
+                _ => None,
+            })
+            .map(|(key, pos_x, pos_y)| {
+                let entity = EntityRef::LineDef(key);
+                let position = transform(
+                    entity,
+                    Point::new(Number::Int(pos_x.into()), Number::Int(pos_y.into())),
+                )?;
+
+                Ok((
+                    key,
+                    checked_i16(entity, "pos_x", position.x.into_float())?,
+                    checked_i16(entity, "pos_y", position.y.into_float())?,
+                ))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for (key, position) in vertexes {
+            self.vertexes[key].position = position;
+        }
+
+        for (key, position) in things {
+            self.things[key].position = position;
+        }
+
+        for (key, x, y) in destinations {
+            if let Special::PolyobjMoveTo { pos_x, pos_y, .. }
+            | Special::PolyobjOrMoveTo { pos_x, pos_y, .. } = &mut self.line_defs[key].special
+            {
+                (*pos_x, *pos_y) = (x, y);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The length of the line each side def is on
+    fn wall_lengths(&self) -> HashMap<SideDefKey, f64> {
+        let mut lengths = HashMap::new();
+
+        for line_def in self.line_defs.values() {
+            let Some((from, to)) = self.line_segment(line_def) else {
+                continue;
+            };
+            let length = (to.x - from.x).hypot(to.y - from.y);
+
+            lengths.insert(line_def.left_side, length);
+
+            if let Some(right_side) = line_def.right_side {
+                lengths.insert(right_side, length);
+            }
+        }
+
+        lengths
+    }
+
+    /// Round every thing's facing angle to the given resolution, returning the things whose angle changed.
+    ///
+    /// Polyobject anchors and start spots are left alone, since their angle holds the polyobject number.
+    pub fn quantize_thing_angles(
+        &mut self,
+        resolution: AngleResolution,
+        thing_types: &ThingTypes,
+    ) -> Vec<AngleQuantization> {
+        let mut quantizations = Vec::new();
+
+        for (key, thing) in self.things.iter_mut() {
+            let quantized = quantized_angle(thing, resolution, thing_types);
+
+            if quantized != thing.angle {
+                quantizations.push(AngleQuantization {
+                    thing: key,
+                    original: thing.angle,
+                    quantized,
+                });
+
+                thing.angle = quantized;
+            }
+        }
+
+        quantizations
+    }
+}
+
+/// A thing's angle rounded to `resolution`, or left alone for polyobject spots
+pub(crate) fn quantized_angle(
+    thing: &Thing,
+    resolution: AngleResolution,
+    thing_types: &ThingTypes,
+) -> Angle {
+    if is_polyobject_spot(thing.type_, thing_types) {
+        thing.angle
+    } else {
+        resolution.quantize(thing.angle)
+    }
+}
+
+/// Polyobject anchors and start spots use their angle to store the polyobject number
+pub(crate) fn is_polyobject_spot(type_: i16, thing_types: &ThingTypes) -> bool {
+    use ThingKind as K;
+
+    matches!(
+        thing_types.get(type_).map(|info| info.kind),
+        Some(
+            K::PolyobjectAnchor
+                | K::PolyobjectStartSpot
+                | K::PolyobjectStartSpotCrush
+                | K::PolyobjectStartSpotHurt
+                | K::HexenPolyobjectAnchor
+                | K::HexenPolyobjectStartSpot
+                | K::HexenPolyobjectStartSpotCrush
+        )
+    )
+}
+
+/// Round a transformed coordinate to 16.16 fixed point, as ports store it, so the map holds what they will load.
+/// Integer coordinates stay integers if the rounded value is integral.
+fn transformed_number(
+    entity: EntityRef,
+    field: &'static str,
+    original: Number,
+    value: f64,
+) -> Result<Number, GeometryOverflow> {
+    let value = check_coordinate(entity, field, value)?;
+
+    Ok(match original {
+        Number::Int(_) => Number::from(value),
+        Number::Float(_) => Number::Float(value.into_float()),
+    })
+}
+
+/// Whether `length` is a whole number of `width`s, allowing for rounding error in either direction
+fn divides(width: u16, length: f64) -> bool {
+    let quotient = length / f64::from(width);
+    width != 0 && (quotient - quotient.round()).abs() < 1e-6
+}
+
+fn rotate_degrees(angle: Angle, degrees: f64) -> Angle {
+    Angle::from_degrees(f64::from(angle) + degrees)
+}
+
+fn mirror_degrees(angle: Angle, axis: MirrorAxis) -> Angle {
+    match axis {
+        MirrorAxis::Vertical => Angle::WEST - angle,
+        MirrorAxis::Horizontal => -angle,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        map::{line_def, thing, thing_type::Game, LineDef, SideDef, Thing, Vertex},
+        number::Fixed,
+        units::Height,
+    };
+
+    #[test]
+    fn rotate_quarter_turn() {
+        let thing_types = ThingTypes::for_game(Game::Doom2);
+        let mut map = Map::new("MAP01".try_into().unwrap());
+
+        let vertex = map.vertexes.insert(Vertex {
+            position: Point::new(Number::Int(64), Number::Int(0)),
+            ..Default::default()
+        });
+        let thing = map.things.insert(Thing {
+            position: Point::new(Number::Int(0), Number::Int(32)),
+            height: Height(0),
+            angle: Angle(300),
+            type_: 3001,
+            flags: thing::Flags::default(),
+            special: thing::Special::None,
+        });
+
+        map.rotate(Point::new(0.0, 0.0), 90.0, &thing_types)
+            .unwrap();
+
+        assert_eq!(
+            map.vertexes[vertex].position,
+            Point::new(Number::Int(0), Number::Int(64))
+        );
+        assert_eq!(
+            map.things[thing].position,
+            Point::new(Number::Int(-32), Number::Int(0))
+        );
+        assert_eq!(map.things[thing].angle, Angle(30));
+
+        let quantizations = map.quantize_thing_angles(AngleResolution::Octant, &thing_types);
+
+        assert_eq!(
+            quantizations,
+            vec![AngleQuantization {
+                thing,
+                original: Angle(30),
+                quantized: Angle(45)
+            }]
+        );
+
+        // Coordinates are rounded to the 16.16 fixed point numbers ports store them as
+        map.rotate(Point::new(0.0, 0.0), 30.0, &thing_types)
+            .unwrap();
+        let Point { x, y } = map.vertexes[vertex].position;
+        assert_eq!(x, Number::Int(-32));
+        assert_eq!(Fixed::try_from(y), Ok(Fixed(3632374)));
+    }
+
+    #[test]
+    fn scale_texture_seams() {
+        let mut map = Map::new("MAP01".try_into().unwrap());
+
+        let sector = map.sectors.insert(Default::default());
+        let side_def = map.side_defs.insert(SideDef {
+            sector,
+            offset: Point::new(TextureOffset(16), TextureOffset(0)),
+            upper_texture: String8::new_unchecked("-"),
+            middle_texture: String8::new_unchecked("STARTAN3"),
+            lower_texture: String8::new_unchecked("-"),
+        });
+        let [from, to] = [0, 128].map(|x| {
+            map.vertexes.insert(Vertex {
+                position: Point::new(Number::Int(x), Number::Int(0)),
+                ..Default::default()
+            })
+        });
+        map.line_defs.insert(LineDef {
+            from,
+            to,
+            left_side: side_def,
+            right_side: None,
+            id: line_def::NO_ID,
+            flags: Default::default(),
+            special: Default::default(),
+            trigger_flags: Default::default(),
+        });
+
+        let options = ScaleOptions {
+            adjust_texture_offsets: true,
+            texture_widths: HashMap::from([(String8::new_unchecked("STARTAN3"), 128)]),
+        };
+        let seams = map.scale(Point::new(0.0, 0.0), 1.5, &options).unwrap();
+
+        assert_eq!(
+            map.vertexes[to].position,
+            Point::new(Number::Int(192), Number::Int(0))
+        );
+        assert_eq!(
+            map.side_defs[side_def].offset,
+            Point::new(TextureOffset(24), TextureOffset(0))
+        );
+        assert_eq!(
+            seams,
+            vec![TextureSeam {
+                side_def,
+                texture: String8::new_unchecked("STARTAN3"),
+                width: 128,
+                length: 192.0,
+            }]
+        );
+
+        let overflow = map
+            .scale(Point::new(0.0, 0.0), 256.0, &ScaleOptions::default())
+            .unwrap_err();
+        assert_eq!(overflow.entity, EntityRef::Vertex(to));
+        assert_eq!(overflow.value, 192.0 * 256.0);
+        assert_eq!(
+            map.vertexes[to].position,
+            Point::new(Number::Int(192), Number::Int(0))
+        );
+    }
+
+    #[test]
+    fn mirror_and_snap() {
+        let thing_types = ThingTypes::for_game(Game::Doom2);
+        let mut map = Map::new("MAP01".try_into().unwrap());
+
+        let sector = map.sectors.insert(Default::default());
+        let side_def = map.side_defs.insert(SideDef {
+            sector,
+            offset: Point::new(TextureOffset(16), TextureOffset(0)),
+            upper_texture: String8::new_unchecked("-"),
+            middle_texture: String8::new_unchecked("STARTAN3"),
+            lower_texture: String8::new_unchecked("-"),
+        });
+        let [from, to] = [3, 101].map(|x| {
+            map.vertexes.insert(Vertex {
+                position: Point::new(Number::Int(x), Number::Int(0)),
+                ..Default::default()
+            })
+        });
+        let line_def = map.line_defs.insert(LineDef {
+            from,
+            to,
+            left_side: side_def,
+            right_side: None,
+            id: line_def::NO_ID,
+            flags: Default::default(),
+            special: Default::default(),
+            trigger_flags: Default::default(),
+        });
+        let thing = map.things.insert(Thing {
+            position: Point::new(Number::Int(40), Number::Int(20)),
+            height: Height(0),
+            angle: Angle(45),
+            type_: 3001,
+            flags: thing::Flags::default(),
+            special: thing::Special::None,
+        });
+
+        map.mirror(Point::new(0.0, 0.0), MirrorAxis::Vertical, &thing_types)
+            .unwrap();
+        assert_eq!(
+            (map.line_defs[line_def].from, map.line_defs[line_def].to),
+            (to, from)
+        );
+        assert_eq!(
+            map.vertexes[to].position,
+            Point::new(Number::Int(-101), Number::Int(0))
+        );
+        assert_eq!(map.side_defs[side_def].offset.x, TextureOffset(-114));
+        assert_eq!(map.things[thing].angle, Angle(135));
+
+        map.translate(Point::new(100.0, 0.0)).unwrap();
+        assert_eq!(
+            map.things[thing].position,
+            Point::new(Number::Int(60), Number::Int(20))
+        );
+
+        // The line now runs east from x = -1 to 97, and its start snaps 1 unit along it
+        let collapsed = map.snap_to_grid(NonZeroU16::new(64).unwrap()).unwrap();
+        assert!(collapsed.is_empty());
+        assert_eq!(
+            map.vertexes[from].position,
+            Point::new(Number::Int(128), Number::Int(0))
+        );
+        assert_eq!(map.side_defs[side_def].offset.x, TextureOffset(-113));
+        assert_eq!(
+            map.things[thing].position,
+            Point::new(Number::Int(64), Number::Int(0))
+        );
+
+        assert_eq!(
+            map.snap_to_grid(NonZeroU16::new(512).unwrap()).unwrap(),
+            vec![line_def]
+        );
+    }
+
+    #[test]
+    fn snap_shared_side_defs() {
+        // Two walls sharing a side def, where only the first one's start moves along it
+        let mut map = Map::load_udmf_textmap(
+            "MAP01".try_into().unwrap(),
+            r#"
+            namespace = "zdoom";
+            vertex { x = 1; y = 0; }
+            vertex { x = 64; y = 0; }
+            vertex { x = 0; y = 64; }
+            vertex { x = 64; y = 64; }
+            sector { texturefloor = "FLAT1"; textureceiling = "FLAT1"; heightceiling = 128; }
+            sidedef { sector = 0; offsetx = 8; }
+            linedef { v1 = 0; v2 = 1; sidefront = 0; }
+            linedef { v1 = 2; v2 = 3; sidefront = 0; }
+            "#,
+        )
+        .unwrap();
+        let line_defs: Vec<_> = map.line_defs.keys().collect();
+
+        map.snap_to_grid(NonZeroU16::new(64).unwrap()).unwrap();
+        let offset = |line: LineDefKey| map.side_defs[map.line_defs[line].left_side].offset.x;
+        assert_eq!(map.side_defs.len(), 2);
+        assert_eq!(offset(line_defs[0]), TextureOffset(7));
+        assert_eq!(offset(line_defs[1]), TextureOffset(8));
+    }
+
+    #[test]
+    fn divides_lengths() {
+        assert!(divides(64, 128.0));
+        assert!(divides(64, 128.00001));
+        assert!(divides(64, 127.99999));
+        assert!(divides(64, -64.0));
+        assert!(!divides(64, 96.0));
+        assert!(!divides(0, 0.0));
+    }
+
+    #[test]
+    fn overflows_leave_map_unchanged() {
+        let thing_types = ThingTypes::for_game(Game::Doom2);
+        let mut map = Map::new("MAP01".try_into().unwrap());
+
+        let sector = map.sectors.insert(Default::default());
+        let side_def = map.side_defs.insert(SideDef {
+            sector,
+            offset: Point::default(),
+            upper_texture: String8::new_unchecked("-"),
+            middle_texture: String8::new_unchecked("STARTAN3"),
+            lower_texture: String8::new_unchecked("-"),
+        });
+        let [from, to] = [0, 32700].map(|x| {
+            map.vertexes.insert(Vertex {
+                position: Point::new(Number::Int(x), Number::Int(0)),
+                ..Default::default()
+            })
+        });
+        map.line_defs.insert(LineDef {
+            from,
+            to,
+            left_side: side_def,
+            right_side: None,
+            id: line_def::NO_ID,
+            flags: Default::default(),
+            special: Default::default(),
+            trigger_flags: Default::default(),
+        });
+        let original = map.clone();
+
+        // Half a turn around a point west of the map takes the far vertex past the west edge
+        let overflow = map
+            .rotate(Point::new(-100.0, 0.0), 180.0, &thing_types)
+            .unwrap_err();
+        assert_eq!(overflow.entity, EntityRef::Vertex(to));
+        assert_eq!(overflow.field, "x");
+        assert!(overflow.value < -32768.0);
+        assert_eq!(map, original);
+
+        let overflow = map
+            .scale(Point::new(0.0, 0.0), 2.0, &ScaleOptions::default())
+            .unwrap_err();
+        assert_eq!(overflow.entity, EntityRef::Vertex(to));
+        assert_eq!((overflow.field, overflow.value), ("x", 65400.0));
+        assert_eq!(map, original);
+
+        // 32700 rounds up to 32 * 1024, one past the last representable coordinate
+        let overflow = map
+            .snap_to_grid(NonZeroU16::new(1024).unwrap())
+            .unwrap_err();
+        assert_eq!(overflow.entity, EntityRef::Vertex(to));
+        assert_eq!((overflow.field, overflow.value), ("x", 32768.0));
+        assert_eq!(map, original);
+    }
+}
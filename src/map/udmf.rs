@@ -1,39 +1,127 @@
 use std::{
+    borrow::Cow,
     convert::TryInto,
-    fmt::{self, Display, Formatter},
-    io::{self, Read, Write},
+    fmt::{self, Display, Formatter, Write as _},
+    hash::{Hash, Hasher},
+    io::{self, Write},
     ops::{Range, RangeInclusive},
 };
 
 use miette::Diagnostic;
-use winnow::Located;
 
+// The syntax tree and assignment names are internals, only exposed for tooling which accepts that they may change
+#[cfg(feature = "unstable")]
+#[doc(hidden)]
 pub mod ast;
+#[cfg(not(feature = "unstable"))]
+mod ast;
+#[cfg(feature = "unstable")]
+#[doc(hidden)]
+pub mod consts;
+#[cfg(not(feature = "unstable"))]
 mod consts;
+#[cfg(feature = "unstable")]
+#[doc(hidden)]
+pub mod parse;
+#[cfg(not(feature = "unstable"))]
 mod parse;
+mod symbol;
 
 use crate::{
-    map::{line_def::RawLineDef, side_def::RawSideDef, *},
-    number::Number,
+    map::{line_def::RawLineDef, selection::Selection, side_def::RawSideDef, *},
+    number::{Fixed, Number},
     point::Point,
+    source::{LineIndex, LocatedLabel},
     string8::{IntoString8Error, String8},
+    units::{Angle, Height, TextureOffset},
+    wad::{Lump, Wad},
 };
 
+pub use self::symbol::Symbol;
+
 use self::ast::GlobalExpr;
 
+/// An identifier as spelled in a TEXTMAP.
+///
+/// Identifiers are case-insensitive, so identifiers spelled with different cases are equal and have the same
+/// symbol, which is the symbol of the lowercase spelling. The original spelling is kept for diagnostics, and is only
+/// allocated if it isn't a known identifier in lowercase.
 #[derive(Clone, Debug)]
-pub struct Identifier(String);
+pub struct Identifier {
+    symbol: Symbol,
+    spelling: Cow<'static, str>,
+}
+
+impl Identifier {
+    pub fn new(name: &str) -> Self {
+        let symbol = Symbol::lookup_ignore_case(name);
+
+        let spelling = match symbol.as_str() {
+            Some(known) if known == name => Cow::Borrowed(known),
+            _ => Cow::Owned(name.to_owned()),
+        };
+
+        Self { symbol, spelling }
+    }
+
+    /// The symbol of the lowercase spelling, which is `Symbol::OTHER` for identifiers the compiler doesn't know
+    pub fn symbol(&self) -> Symbol {
+        self.symbol
+    }
+
+    /// The identifier as it was spelled
+    pub fn as_str(&self) -> &str {
+        &self.spelling
+    }
+}
+
+impl PartialEq for Identifier {
+    fn eq(&self, other: &Self) -> bool {
+        self.symbol == other.symbol
+            && (self.symbol != Symbol::OTHER || self.spelling.eq_ignore_ascii_case(&other.spelling))
+    }
+}
+
+impl Eq for Identifier {}
+
+impl Hash for Identifier {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.symbol.hash(state);
+        if self.symbol == Symbol::OTHER {
+            for b in self.spelling.bytes() {
+                state.write_u8(b.to_ascii_lowercase());
+            }
+        }
+    }
+}
 
 impl Display for Identifier {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", &self.0)
+        f.write_str(self.as_str())
     }
 }
 
 #[derive(Debug, thiserror::Error, Diagnostic)]
 pub enum LoadError {
-    #[error("Parse error: {0}")]
-    Parse(winnow::error::ContextError),
+    #[error("Parse error: {error}")]
+    #[diagnostic(code(waddle::udmf::parse))]
+    Parse {
+        error: winnow::error::ContextError,
+        #[label("Parsing failed here")]
+        offset: usize,
+    },
+
+    #[error("{text} is not a valid number")]
+    #[diagnostic(code(waddle::udmf::malformed_number))]
+    MalformedNumber {
+        text: String,
+        #[label("This number is malformed")]
+        span: Range<usize>,
+        #[help]
+        help: String,
+        /// The number the text was probably meant to be, if it can be guessed
+        suggestion: Option<String>,
+    },
 
     #[error(transparent)]
     #[diagnostic(transparent)]
@@ -43,9 +131,79 @@ pub enum LoadError {
     Link(#[from] LinkError),
 }
 
+impl LoadError {
+    fn from_parse_error(error: winnow::error::ContextError, contents: &str, offset: usize) -> Self {
+        if !error
+            .context()
+            .any(|context| *context == parse::MALFORMED_NUMBER)
+        {
+            return LoadError::Parse { error, offset };
+        }
+
+        let text: String = contents[offset..]
+            .chars()
+            .take_while(|&c| parse::is_numeric_token_char(c))
+            .collect();
+        let span = offset..offset + text.len();
+
+        // Some exporters write floats with the decimal separator of their locale
+        let is_decimal_comma = text.split_once(',').is_some_and(|(whole, fraction)| {
+            let is_digits = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+            is_digits(whole.trim_start_matches(['+', '-'])) && is_digits(fraction)
+        });
+
+        let (help, suggestion) = if is_decimal_comma {
+            let suggestion = text.replace(',', ".");
+            (
+                format!("UDMF uses '.' as the decimal separator: try {suggestion}"),
+                Some(suggestion),
+            )
+        } else {
+            (
+                "Numbers must be integers like 12, -3 or 0x1F, or floats like 1.5 or 2e3"
+                    .to_owned(),
+                None,
+            )
+        };
+
+        LoadError::MalformedNumber {
+            text,
+            span,
+            help,
+            suggestion,
+        }
+    }
+
+    /// The labels of this error with line and column positions, for consumers which don't use miette's reporting.
+    ///
+    /// When locating many errors in the same source, build a `LineIndex` once and use [LineIndex::locate] instead.
+    pub fn locate(&self, contents: &str) -> Vec<LocatedLabel> {
+        LineIndex::new(contents).locate(self)
+    }
+
+    /// Suggested edits to the TEXTMAP source which would fix this error, most likely first
+    pub fn fix_its(&self) -> Vec<FixIt> {
+        match self {
+            LoadError::MalformedNumber {
+                text,
+                span,
+                suggestion: Some(suggestion),
+                ..
+            } => vec![FixIt::new(
+                format!("Replace {text} with {suggestion}"),
+                span.clone(),
+                suggestion.clone(),
+            )],
+            LoadError::Compile(error) => error.fix_its(),
+            _ => Vec::new(),
+        }
+    }
+}
+
 #[derive(Debug, thiserror::Error, Diagnostic)]
 pub enum CompileError {
     #[error("Invalid string8: {error}")]
+    #[diagnostic(code(waddle::udmf::invalid_string8))]
     String8 {
         #[source]
         error: IntoString8Error,
@@ -54,6 +212,7 @@ pub enum CompileError {
     },
 
     #[error("{identifier} was assigned to multiple times")]
+    #[diagnostic(code(waddle::udmf::multiple_assignment))]
     MultipleAssignment {
         identifier: Identifier,
         #[label("{identifier} was previously assigned here...")]
@@ -63,6 +222,7 @@ pub enum CompileError {
     },
 
     #[error("{identifier} was assigned a value of the wrong type")]
+    #[diagnostic(code(waddle::udmf::invalid_assignment_type))]
     InvalidAssignmentType {
         identifier: Identifier,
         value: Value,
@@ -74,14 +234,17 @@ pub enum CompileError {
     },
 
     #[error("{identifier} must be in the range {range:?}")]
+    #[diagnostic(code(waddle::udmf::out_of_range))]
     OutOfRange {
         identifier: Identifier,
+        value: i32,
         range: RangeInclusive<i32>,
         #[label("This value is out of range")]
         span: Range<usize>,
     },
 
     #[error("{identifier} is not a valid assignment here")]
+    #[diagnostic(code(waddle::udmf::invalid_assignment))]
     InvalidAssignment {
         identifier: Identifier,
         valid: ValidIdentifiers,
@@ -90,6 +253,7 @@ pub enum CompileError {
     },
 
     #[error("{identifier} is not a valid block here")]
+    #[diagnostic(code(waddle::udmf::invalid_block))]
     InvalidBlock {
         identifier: Identifier,
         valid: ValidIdentifiers,
@@ -98,6 +262,7 @@ pub enum CompileError {
     },
 
     #[error("Some required assignments were missing")]
+    #[diagnostic(code(waddle::udmf::missing_assignments))]
     MissingAssignments {
         missing: MissingAssignments,
         #[label("{missing}")]
@@ -106,6 +271,7 @@ pub enum CompileError {
 
     /// The args must be tuples since Range does not impl Copy
     #[error("{value} is not a recognized linedef/thing special")]
+    #[diagnostic(code(waddle::udmf::unknown_special))]
     LineDefSpecial {
         value: i16,
         #[label("This linedef special")]
@@ -124,6 +290,7 @@ pub enum CompileError {
     },
 
     #[error("{value} is not a recognized sector special")]
+    #[diagnostic(code(waddle::udmf::unknown_sector_special))]
     SectorSpecial {
         value: i16,
         #[label("This sector special is invalid")]
@@ -131,6 +298,102 @@ pub enum CompileError {
     },
 }
 
+impl CompileError {
+    /// Suggested edits to the TEXTMAP source which would fix this error, most likely first.
+    ///
+    /// Errors with no obvious fix, e.g. unknown blocks, have no suggestions.
+    pub fn fix_its(&self) -> Vec<FixIt> {
+        match self {
+            CompileError::MultipleAssignment {
+                identifier,
+                previous_span,
+                span,
+            } => vec![
+                FixIt::new(
+                    format!("Remove the later assignment to {identifier}"),
+                    span.clone(),
+                    "",
+                ),
+                FixIt::new(
+                    format!("Remove the earlier assignment to {identifier}"),
+                    previous_span.clone(),
+                    "",
+                ),
+            ],
+
+            CompileError::InvalidAssignmentType {
+                value,
+                expected,
+                value_span,
+                ..
+            } => expected
+                .0
+                .iter()
+                .filter_map(|&value_type| value.coerce(value_type))
+                .map(|coerced| {
+                    let replacement = match coerced {
+                        // Make sure integral floats aren't parsed back as integers
+                        Value::Float(f) if f.fract() == 0.0 => format!("{f:.1}"),
+                        _ => coerced.to_string(),
+                    };
+
+                    FixIt::new(
+                        format!("Replace {value} with {replacement}"),
+                        value_span.clone(),
+                        replacement,
+                    )
+                })
+                .collect(),
+
+            CompileError::OutOfRange {
+                value, range, span, ..
+            } => {
+                let clamped = (*value).clamp(*range.start(), *range.end());
+
+                vec![FixIt::new(
+                    format!("Clamp {value} to {clamped}"),
+                    span.clone(),
+                    clamped.to_string(),
+                )]
+            }
+
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// A machine-readable suggestion for fixing a `CompileError`, replacing a span of the source with new text
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FixIt {
+    /// A human-readable summary of the fix
+    pub description: String,
+    /// The byte range of the source to replace
+    pub span: Range<usize>,
+    /// The text to replace the span with. Empty to delete it.
+    pub replacement: String,
+}
+
+impl FixIt {
+    pub fn new(
+        description: impl Into<String>,
+        span: Range<usize>,
+        replacement: impl Into<String>,
+    ) -> Self {
+        Self {
+            description: description.into(),
+            span,
+            replacement: replacement.into(),
+        }
+    }
+
+    /// Apply the fix to the source it was generated from
+    pub fn apply(&self, source: &str) -> String {
+        let mut fixed = source.to_owned();
+        fixed.replace_range(self.span.clone(), &self.replacement);
+        fixed
+    }
+}
+
 #[derive(Debug)]
 pub struct ValidIdentifiers(&'static [&'static str]);
 
@@ -206,22 +469,239 @@ pub enum WriteError {
 
     #[error("IO error")]
     Io(#[from] io::Error),
+
+    #[error("Writing the {0} namespace isn't supported, as it numbers specials differently")]
+    UnsupportedNamespace(String),
+
+    #[error("{0:?} has no Doom equivalent, so it can't be written in a namespace which numbers specials as Doom")]
+    NoDoomSpecial(line_def::Special),
+
+    #[error("{key} is {value}, but UDMF can only express finite numbers")]
+    NonFiniteFloat { key: String, value: f64 },
+}
+
+/// How texture and flat names are compiled
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum String8Case {
+    /// Keep names as they're spelled in the TEXTMAP
+    #[default]
+    Preserve,
+    /// Uppercase names, as they're stored in WADs, so that e.g. `stone2` and `STONE2` compare equal
+    Uppercase,
+}
+
+/// Which values are accepted for an assignment of a different type
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Coercion {
+    /// Only accept values of the types in the spec, except integers for floats, as the spec allows
+    #[default]
+    Strict,
+    /// Also accept floats with no fractional part for integers, and 0 and 1 for booleans, as written by some editors
+    Lenient,
+}
+
+/// What to do when the namespace is assigned more than once, to different values. Assigning the same namespace again
+/// is always accepted, as it is by TEXTMAP fragments which were concatenated together.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DuplicateNamespace {
+    /// Fail to compile, as the spec requires
+    #[default]
+    Error,
+    /// Use the first namespace
+    FirstWins,
+    /// Use the last namespace
+    LastWins,
+}
+
+/// What to do with top-level blocks which aren't vertexes, line defs, side defs, sectors or things, e.g. metadata
+/// written by editors
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UnknownBlocks {
+    /// Fail to compile
+    #[default]
+    Error,
+    /// Leave them out of the map
+    Skip,
+    /// Keep them in [RawMap::unknown_blocks], so that they're written back by [RawMap::to_udmf]
+    Preserve,
+}
+
+/// A top-level block which isn't part of the map, kept as it was written
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
+pub struct UnknownBlock {
+    pub identifier: String,
+    pub assignments: Vec<(String, Value)>,
+}
+
+/// Settings for compiling a TEXTMAP into a map
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CompileOptions {
+    pub string8_case: String8Case,
+    pub coercion: Coercion,
+    pub duplicate_namespace: DuplicateNamespace,
+    pub unknown_blocks: UnknownBlocks,
+    pub special_args: SpecialArgs,
+}
+
+/// What to do with line special args which are out of range for the type of the special's field, e.g. a `bool` arg
+/// of 2
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SpecialArgs {
+    /// Clamp them into range, with [line_def::UdmfSpecial::clamp_args]. Nothing records the args which changed.
+    Clamp,
+    /// Fail to compile
+    #[default]
+    Strict,
+}
+
+/// How floats are written
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FloatFormat {
+    /// The shortest representation which reads back as the same float. Floats left over from arithmetic may be
+    /// written as e.g. `0.30000000000000004`.
+    #[default]
+    Shortest,
+    /// Round to at most this many decimal places, dropping trailing zeros
+    Decimals(u8),
+}
+
+/// Settings for writing a map as a TEXTMAP
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WriteOptions {
+    pub float_format: FloatFormat,
+    /// Write floats with no fractional part, after rounding, as integers, e.g. `64` rather than `64.0`. They're read
+    /// back as integers.
+    pub integral_floats_as_ints: bool,
+    /// Keep lines to at most this many bytes, for ports which choke on long lines. Comments are wrapped onto several
+    /// lines, and values which don't fit are moved to a line of their own. Strings can't be broken, so a line with a
+    /// long string value may still exceed the limit.
+    pub max_line_length: Option<usize>,
+}
+
+impl WriteOptions {
+    const DEFAULT: Self = Self {
+        float_format: FloatFormat::Shortest,
+        integral_floats_as_ints: true,
+        max_line_length: None,
+    };
+
+    /// Format the float assigned to `key`, which fails if it's infinite or NaN
+    fn format_float(&self, key: &str, f: f64) -> Result<String, WriteError> {
+        if !f.is_finite() {
+            return Err(WriteError::NonFiniteFloat {
+                key: key.to_owned(),
+                value: f,
+            });
+        }
+
+        let f = match self.float_format {
+            FloatFormat::Shortest => f,
+            FloatFormat::Decimals(decimals) => {
+                let rounded: f64 = format!("{f:.0$}", usize::from(decimals))
+                    .parse()
+                    .unwrap_or(f);
+                // Don't write e.g. -0.001 as -0
+                if rounded == 0.0 {
+                    0.0
+                } else {
+                    rounded
+                }
+            }
+        };
+
+        Ok(if f.fract() == 0.0 && !self.integral_floats_as_ints {
+            format!("{f:.1}")
+        } else {
+            f.to_string()
+        })
+    }
+}
+
+impl Default for WriteOptions {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
 }
 
+/// Lumps built by other tools which are written along with a map's TEXTMAP by [Map::write_udmf_lumps]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PassthroughLumps {
+    /// Nodes built by a node builder. They're out of date as soon as the map's geometry changes.
+    pub znodes: Option<Vec<u8>>,
+    /// Strife conversations
+    pub dialogue: Option<Vec<u8>>,
+    /// Compiled ACS scripts
+    pub behavior: Option<Vec<u8>>,
+    /// The ACS source of the BEHAVIOR lump
+    pub scripts: Option<Vec<u8>>,
+}
+
+/// The namespaces whose specials are numbered as in `UdmfSpecial`
+const NAMESPACES: &[&str] = &["zdoom", "hexen"];
+
+/// The namespaces whose specials are numbered as in `DoomSpecial`, with the line's id as their tag
+const DOOM_NUMBERED_NAMESPACES: &[&str] = &["strife"];
+
 /// A map entity which is expressed as a block in UDMF
 pub trait UdmfBlock: Sized {
-    fn compile(block: &ast::Block) -> Result<Self, Box<CompileError>>;
+    fn compile(block: ast::BlockRef, options: &CompileOptions) -> Result<Self, Box<CompileError>>;
     fn write<W: UdmfWriter>(&self, writer: &mut W) -> Result<(), WriteError>;
 }
 
+/// How a namespace numbers line specials
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SpecialNumbering {
+    /// As in `UdmfSpecial`, with args and trigger flags
+    Udmf,
+    /// As in `DoomSpecial`, where the special's type sets the trigger flags and its tag is the line's id
+    Doom,
+}
+
+impl SpecialNumbering {
+    fn of(namespace: &str) -> Option<Self> {
+        if NAMESPACES
+            .iter()
+            .any(|ns| ns.eq_ignore_ascii_case(namespace))
+        {
+            Some(SpecialNumbering::Udmf)
+        } else if DOOM_NUMBERED_NAMESPACES
+            .iter()
+            .any(|ns| ns.eq_ignore_ascii_case(namespace))
+        {
+            Some(SpecialNumbering::Doom)
+        } else {
+            None
+        }
+    }
+}
+
 impl UdmfBlock for RawLineDef {
-    fn compile(block: &ast::Block) -> Result<Self, Box<CompileError>> {
-        use consts::line_def::assignments as a;
+    fn compile(block: ast::BlockRef, options: &CompileOptions) -> Result<Self, Box<CompileError>> {
+        Self::compile_numbered(block, options, SpecialNumbering::Udmf)
+    }
+
+    fn write<W: UdmfWriter>(&self, writer: &mut W) -> Result<(), WriteError> {
+        self.write_numbered(writer, SpecialNumbering::Udmf)
+    }
+}
+
+impl RawLineDef {
+    fn compile_numbered(
+        block: ast::BlockRef,
+        options: &CompileOptions,
+        numbering: SpecialNumbering,
+    ) -> Result<Self, Box<CompileError>> {
+        use consts::line_def::assignments::{self as a, symbols as s};
 
         let mut from_idx = None;
         let mut to_idx = None;
         let mut left_side_idx = None;
         let mut right_side_idx = None;
+        let mut id = None;
 
         let mut impassable = None;
         let mut blocks_monsters = None;
@@ -232,6 +712,11 @@ impl UdmfBlock for RawLineDef {
         let mut blocks_sound = None;
         let mut not_on_map = None;
         let mut already_on_map = None;
+        let mut blocks_land_monsters = None;
+        let mut blocks_players = None;
+        let mut jump_over = None;
+        let mut blocks_floaters = None;
+        let mut translucent = None;
 
         let mut special = None;
         let mut arg0 = None;
@@ -254,49 +739,95 @@ impl UdmfBlock for RawLineDef {
         let default_flags = line_def::Flags::default();
         let default_trigger_flags = line_def::TriggerFlags::default();
 
-        for assignment in &block.assignments {
-            match assignment.item.identifier.item.0.as_str() {
-                a::FROM_IDX => assign_once(&mut from_idx, expect_u16_value, assignment)?,
-                a::TO_IDX => assign_once(&mut to_idx, expect_u16_value, assignment)?,
-                a::LEFT_SIDE_IDX => assign_once(&mut left_side_idx, expect_u16_value, assignment)?,
-                a::RIGHT_SIDE_IDX => {
-                    assign_once(&mut right_side_idx, expect_u16_value, assignment)?
+        for assignment in block.assignments {
+            match assignment.item.identifier.item.symbol() {
+                s::FROM_IDX => assign_once(&mut from_idx, expect_u16_value, assignment, options)?,
+                s::TO_IDX => assign_once(&mut to_idx, expect_u16_value, assignment, options)?,
+                s::LEFT_SIDE_IDX => {
+                    assign_once(&mut left_side_idx, expect_u16_value, assignment, options)?
+                }
+                s::RIGHT_SIDE_IDX => {
+                    assign_once(&mut right_side_idx, expect_u16_value, assignment, options)?
+                }
+                s::ID => assign_once(&mut id, expect_i16_value, assignment, options)?,
+                s::IMPASSABLE => {
+                    assign_once(&mut impassable, expect_bool_value, assignment, options)?
+                }
+                s::BLOCKS_MONSTERS => {
+                    assign_once(&mut blocks_monsters, expect_bool_value, assignment, options)?
+                }
+                s::TWO_SIDED => {
+                    assign_once(&mut two_sided, expect_bool_value, assignment, options)?
+                }
+                s::UPPER_UNPEGGED => {
+                    assign_once(&mut upper_unpegged, expect_bool_value, assignment, options)?
+                }
+                s::LOWER_UNPEGGED => {
+                    assign_once(&mut lower_unpegged, expect_bool_value, assignment, options)?
+                }
+                s::SECRET => assign_once(&mut secret, expect_bool_value, assignment, options)?,
+                s::BLOCKS_SOUND => {
+                    assign_once(&mut blocks_sound, expect_bool_value, assignment, options)?
+                }
+                s::NOT_ON_MAP => {
+                    assign_once(&mut not_on_map, expect_bool_value, assignment, options)?
+                }
+                s::ALREADY_ON_MAP => {
+                    assign_once(&mut already_on_map, expect_bool_value, assignment, options)?
+                }
+                s::BLOCKS_LAND_MONSTERS => assign_once(
+                    &mut blocks_land_monsters,
+                    expect_bool_value,
+                    assignment,
+                    options,
+                )?,
+                s::BLOCKS_PLAYERS => {
+                    assign_once(&mut blocks_players, expect_bool_value, assignment, options)?
+                }
+                s::JUMP_OVER => {
+                    assign_once(&mut jump_over, expect_bool_value, assignment, options)?
+                }
+                s::BLOCKS_FLOATERS => {
+                    assign_once(&mut blocks_floaters, expect_bool_value, assignment, options)?
+                }
+                s::TRANSLUCENT => {
+                    assign_once(&mut translucent, expect_bool_value, assignment, options)?
                 }
-                a::IMPASSABLE => assign_once(&mut impassable, expect_bool_value, assignment)?,
-                a::BLOCKS_MONSTERS => {
-                    assign_once(&mut blocks_monsters, expect_bool_value, assignment)?
+                s::SPECIAL => assign_once(&mut special, expect_i16_value, assignment, options)?,
+                s::ARG0 => assign_once(&mut arg0, expect_i16_value, assignment, options)?,
+                s::ARG1 => assign_once(&mut arg1, expect_i16_value, assignment, options)?,
+                s::ARG2 => assign_once(&mut arg2, expect_i16_value, assignment, options)?,
+                s::ARG3 => assign_once(&mut arg3, expect_i16_value, assignment, options)?,
+                s::ARG4 => assign_once(&mut arg4, expect_i16_value, assignment, options)?,
+                s::PLAYER_CROSS => {
+                    assign_once(&mut player_cross, expect_bool_value, assignment, options)?
                 }
-                a::TWO_SIDED => assign_once(&mut two_sided, expect_bool_value, assignment)?,
-                a::UPPER_UNPEGGED => {
-                    assign_once(&mut upper_unpegged, expect_bool_value, assignment)?
+                s::PLAYER_USE => {
+                    assign_once(&mut player_use, expect_bool_value, assignment, options)?
                 }
-                a::LOWER_UNPEGGED => {
-                    assign_once(&mut lower_unpegged, expect_bool_value, assignment)?
+                s::MONSTER_CROSS => {
+                    assign_once(&mut monster_cross, expect_bool_value, assignment, options)?
                 }
-                a::SECRET => assign_once(&mut secret, expect_bool_value, assignment)?,
-                a::BLOCKS_SOUND => assign_once(&mut blocks_sound, expect_bool_value, assignment)?,
-                a::NOT_ON_MAP => assign_once(&mut not_on_map, expect_bool_value, assignment)?,
-                a::ALREADY_ON_MAP => {
-                    assign_once(&mut already_on_map, expect_bool_value, assignment)?
+                s::MONSTER_USE => {
+                    assign_once(&mut monster_use, expect_bool_value, assignment, options)?
                 }
-                a::SPECIAL => assign_once(&mut special, expect_i16_value, assignment)?,
-                a::ARG0 => assign_once(&mut arg0, expect_i16_value, assignment)?,
-                a::ARG1 => assign_once(&mut arg1, expect_i16_value, assignment)?,
-                a::ARG2 => assign_once(&mut arg2, expect_i16_value, assignment)?,
-                a::ARG3 => assign_once(&mut arg3, expect_i16_value, assignment)?,
-                a::ARG4 => assign_once(&mut arg4, expect_i16_value, assignment)?,
-                a::PLAYER_CROSS => assign_once(&mut player_cross, expect_bool_value, assignment)?,
-                a::PLAYER_USE => assign_once(&mut player_use, expect_bool_value, assignment)?,
-                a::MONSTER_CROSS => assign_once(&mut monster_cross, expect_bool_value, assignment)?,
-                a::MONSTER_USE => assign_once(&mut monster_use, expect_bool_value, assignment)?,
-                a::IMPACT => assign_once(&mut impact, expect_bool_value, assignment)?,
-                a::PLAYER_PUSH => assign_once(&mut player_push, expect_bool_value, assignment)?,
-                a::MONSTER_PUSH => assign_once(&mut monster_push, expect_bool_value, assignment)?,
-                a::MISSILE_CROSS => assign_once(&mut missile_cross, expect_bool_value, assignment)?,
-                a::REPEATS => assign_once(&mut repeats, expect_bool_value, assignment)?,
-                a::MONSTER_ACTIVATE => {
-                    assign_once(&mut monster_activate, expect_bool_value, assignment)?
+                s::IMPACT => assign_once(&mut impact, expect_bool_value, assignment, options)?,
+                s::PLAYER_PUSH => {
+                    assign_once(&mut player_push, expect_bool_value, assignment, options)?
                 }
+                s::MONSTER_PUSH => {
+                    assign_once(&mut monster_push, expect_bool_value, assignment, options)?
+                }
+                s::MISSILE_CROSS => {
+                    assign_once(&mut missile_cross, expect_bool_value, assignment, options)?
+                }
+                s::REPEATS => assign_once(&mut repeats, expect_bool_value, assignment, options)?,
+                s::MONSTER_ACTIVATE => assign_once(
+                    &mut monster_activate,
+                    expect_bool_value,
+                    assignment,
+                    options,
+                )?,
 
                 _ => {
                     return Err(Box::new(CompileError::InvalidAssignment {
@@ -329,6 +860,7 @@ impl UdmfBlock for RawLineDef {
             }));
         }
 
+        let mut doom_trigger_flags = None;
         let special = if let Some((value, span)) = special {
             let (arg0, arg0_span) = arg0.unzip();
             let (arg1, arg1_span) = arg1.unzip();
@@ -336,38 +868,76 @@ impl UdmfBlock for RawLineDef {
             let (arg3, arg3_span) = arg3.unzip();
             let (arg4, arg4_span) = arg4.unzip();
 
-            let udmf_special = line_def::UdmfSpecial {
-                value,
-                args: [
-                    arg0.unwrap_or(0),
-                    arg1.unwrap_or(0),
-                    arg2.unwrap_or(0),
-                    arg3.unwrap_or(0),
-                    arg4.unwrap_or(0),
-                ],
-            };
-
-            line_def::Special::try_from(udmf_special).map_err(|_| {
+            let unknown_special = || {
                 Box::new(CompileError::LineDefSpecial {
                     value,
-                    special_span: span,
-                    arg0_span: arg0_span.map(|r| (r.start, r.end)),
-                    arg1_span: arg1_span.map(|r| (r.start, r.end)),
-                    arg2_span: arg2_span.map(|r| (r.start, r.end)),
-                    arg3_span: arg3_span.map(|r| (r.start, r.end)),
-                    arg4_span: arg4_span.map(|r| (r.start, r.end)),
+                    special_span: span.clone(),
+                    arg0_span: arg0_span.clone().map(|r| (r.start, r.end)),
+                    arg1_span: arg1_span.clone().map(|r| (r.start, r.end)),
+                    arg2_span: arg2_span.clone().map(|r| (r.start, r.end)),
+                    arg3_span: arg3_span.clone().map(|r| (r.start, r.end)),
+                    arg4_span: arg4_span.clone().map(|r| (r.start, r.end)),
                 })
-            })?
+            };
+
+            match numbering {
+                SpecialNumbering::Doom => {
+                    let tag = id.as_ref().map_or(0, |v| v.0);
+                    let conversion =
+                        line_def::DoomConversion::convert(line_def::DoomSpecial::new(value, tag))
+                            .map_err(|_| unknown_special())?;
+
+                    doom_trigger_flags = Some(conversion.trigger_flags);
+                    conversion.special
+                }
+
+                SpecialNumbering::Udmf => {
+                    let udmf_special = line_def::UdmfSpecial {
+                        value,
+                        args: [
+                            arg0.unwrap_or(0),
+                            arg1.unwrap_or(0),
+                            arg2.unwrap_or(0),
+                            arg3.unwrap_or(0),
+                            arg4.unwrap_or(0),
+                        ],
+                    };
+
+                    let arg_spans = [&arg0_span, &arg1_span, &arg2_span, &arg3_span, &arg4_span];
+                    let out_of_range = match options.special_args {
+                        SpecialArgs::Clamp => None,
+                        SpecialArgs::Strict => line_def::Special::arg_out_of_range(&udmf_special),
+                    };
+                    if let Some((i, range)) = out_of_range {
+                        let arg = [a::ARG0, a::ARG1, a::ARG2, a::ARG3, a::ARG4][i];
+
+                        return Err(Box::new(CompileError::OutOfRange {
+                            identifier: Identifier::new(arg),
+                            value: i32::from(udmf_special.args[i]),
+                            range: i32::from(*range.start())..=i32::from(*range.end()),
+                            span: arg_spans[i].clone().unwrap_or_else(|| span.clone()),
+                        }));
+                    }
+
+                    line_def::Special::try_from(udmf_special.clamp_args())
+                        .map_err(|_| unknown_special())?
+                }
+            }
         } else {
             line_def::Special::None
         };
 
         Ok(Self {
-            from_idx: from_idx.unwrap().0,
-            to_idx: to_idx.unwrap().0,
-            left_side_idx: left_side_idx.unwrap().0,
-            right_side_idx: right_side_idx.map(|v| v.0),
+            from_idx: from_idx.unwrap().0.into(),
+            to_idx: to_idx.unwrap().0.into(),
+            left_side_idx: left_side_idx.unwrap().0.into(),
+            right_side_idx: right_side_idx.map(|v| v.0.into()),
 
+            // A Doom numbered line without a tag can't be referred to, as in binary maps
+            id: id
+                .map(|v| v.0)
+                .filter(|&id| numbering == SpecialNumbering::Udmf || id != 0)
+                .unwrap_or(line_def::NO_ID),
             flags: line_def::Flags {
                 impassable: impassable.map(|v| v.0).unwrap_or(default_flags.impassable),
                 blocks_monsters: blocks_monsters
@@ -388,11 +958,24 @@ impl UdmfBlock for RawLineDef {
                 already_on_map: already_on_map
                     .map(|v| v.0)
                     .unwrap_or(default_flags.already_on_map),
+                blocks_land_monsters: blocks_land_monsters
+                    .map(|v| v.0)
+                    .unwrap_or(default_flags.blocks_land_monsters),
+                blocks_players: blocks_players
+                    .map(|v| v.0)
+                    .unwrap_or(default_flags.blocks_players),
+                jump_over: jump_over.map(|v| v.0).unwrap_or(default_flags.jump_over),
+                blocks_floaters: blocks_floaters
+                    .map(|v| v.0)
+                    .unwrap_or(default_flags.blocks_floaters),
+                translucent: translucent
+                    .map(|v| v.0)
+                    .unwrap_or(default_flags.translucent),
             },
 
             special,
 
-            trigger_flags: line_def::TriggerFlags {
+            trigger_flags: doom_trigger_flags.unwrap_or(line_def::TriggerFlags {
                 player_cross: player_cross
                     .map(|v| v.0)
                     .unwrap_or(default_trigger_flags.player_cross),
@@ -421,22 +1004,112 @@ impl UdmfBlock for RawLineDef {
                 monsters_activate: monster_activate
                     .map(|v| v.0)
                     .unwrap_or(default_trigger_flags.monsters_activate),
-            },
+            }),
         })
     }
 
-    fn write<W: UdmfWriter>(&self, writer: &mut W) -> Result<(), WriteError> {
+    fn write_numbered<W: UdmfWriter>(
+        &self,
+        writer: &mut W,
+        numbering: SpecialNumbering,
+    ) -> Result<(), WriteError> {
+        use consts::line_def::assignments as a;
+
+        // Doom numbered specials take their tag from the line's id, as in `Map::to_doom_binary`
+        let (udmf_special, id) = match numbering {
+            SpecialNumbering::Udmf => (line_def::UdmfSpecial::from(self.special.clone()), self.id),
+            SpecialNumbering::Doom => {
+                let doom = self
+                    .special
+                    .to_doom(&self.trigger_flags)
+                    .ok_or_else(|| WriteError::NoDoomSpecial(self.special.clone()))?;
+                let id = match (doom.value, doom.tag) {
+                    (0, _) => self.id,
+                    (_, 0) => line_def::NO_ID,
+                    (_, tag) => tag,
+                };
+
+                (line_def::UdmfSpecial::new(doom.value, [0; 5]), id)
+            }
+        };
+
         writer.write_block(consts::line_def::BLOCK, |block| {
+            block.write_assignment(a::FROM_IDX, &Value::Int(i32::from(self.from_idx.get())))?;
+            block.write_assignment(a::TO_IDX, &Value::Int(i32::from(self.to_idx.get())))?;
             block.write_assignment(
-                consts::line_def::assignments::FROM_IDX,
-                &Value::Int(i32::from(self.from_idx)),
-            )?;
-            block.write_assignment(
-                consts::line_def::assignments::TO_IDX,
-                &Value::Int(i32::from(self.to_idx)),
+                a::LEFT_SIDE_IDX,
+                &Value::Int(i32::from(self.left_side_idx.get())),
             )?;
 
-            // TODO: The rest of the owl
+            if let Some(right_side_idx) = self.right_side_idx {
+                block.write_assignment(
+                    a::RIGHT_SIDE_IDX,
+                    &Value::Int(i32::from(right_side_idx.get())),
+                )?;
+            }
+
+            if id != line_def::NO_ID {
+                block.write_assignment(a::ID, &Value::Int(i32::from(id)))?;
+            }
+
+            let flags = [
+                (a::IMPASSABLE, self.flags.impassable),
+                (a::BLOCKS_MONSTERS, self.flags.blocks_monsters),
+                (a::TWO_SIDED, self.flags.two_sided),
+                (a::UPPER_UNPEGGED, self.flags.upper_unpegged),
+                (a::LOWER_UNPEGGED, self.flags.lower_unpegged),
+                (a::SECRET, self.flags.secret),
+                (a::BLOCKS_SOUND, self.flags.blocks_sound),
+                (a::NOT_ON_MAP, self.flags.not_on_map),
+                (a::ALREADY_ON_MAP, self.flags.already_on_map),
+                (a::BLOCKS_LAND_MONSTERS, self.flags.blocks_land_monsters),
+                (a::BLOCKS_PLAYERS, self.flags.blocks_players),
+                (a::JUMP_OVER, self.flags.jump_over),
+                (a::BLOCKS_FLOATERS, self.flags.blocks_floaters),
+                (a::TRANSLUCENT, self.flags.translucent),
+            ];
+
+            for (key, value) in flags {
+                if value {
+                    block.write_assignment(key, &Value::Bool(true))?;
+                }
+            }
+
+            if udmf_special.value != 0 {
+                block.write_assignment(a::SPECIAL, &Value::Int(i32::from(udmf_special.value)))?;
+            }
+
+            for (key, arg) in [a::ARG0, a::ARG1, a::ARG2, a::ARG3, a::ARG4]
+                .into_iter()
+                .zip(udmf_special.args)
+            {
+                if arg != 0 {
+                    block.write_assignment(key, &Value::Int(i32::from(arg)))?;
+                }
+            }
+
+            if numbering == SpecialNumbering::Doom {
+                return Ok(());
+            }
+
+            let trigger_flags = [
+                (a::PLAYER_CROSS, self.trigger_flags.player_cross),
+                (a::PLAYER_USE, self.trigger_flags.player_use),
+                (a::MONSTER_CROSS, self.trigger_flags.monster_cross),
+                (a::MONSTER_USE, self.trigger_flags.monster_use),
+                (a::IMPACT, self.trigger_flags.impact),
+                (a::PLAYER_PUSH, self.trigger_flags.player_push),
+                (a::MONSTER_PUSH, self.trigger_flags.monster_push),
+                (a::MISSILE_CROSS, self.trigger_flags.missile_cross),
+                (a::REPEATS, self.trigger_flags.repeats),
+                (a::MONSTER_ACTIVATE, self.trigger_flags.monsters_activate),
+            ];
+
+            for (key, value) in trigger_flags {
+                if value {
+                    block.write_assignment(key, &Value::Bool(true))?;
+                }
+            }
 
             Ok(())
         })
@@ -444,8 +1117,8 @@ impl UdmfBlock for RawLineDef {
 }
 
 impl UdmfBlock for RawSideDef {
-    fn compile(block: &ast::Block) -> Result<Self, Box<CompileError>> {
-        use consts::side_def::assignments as a;
+    fn compile(block: ast::BlockRef, options: &CompileOptions) -> Result<Self, Box<CompileError>> {
+        use consts::side_def::assignments::{self as a, symbols as s};
 
         let mut offset_x = None;
         let mut offset_y = None;
@@ -454,16 +1127,22 @@ impl UdmfBlock for RawSideDef {
         let mut middle_texture = None;
         let mut lower_texture = None;
 
-        for assignment in &block.assignments {
-            match assignment.item.identifier.item.0.as_str() {
-                a::OFFSET_X => assign_once(&mut offset_x, expect_i16_value, assignment)?,
-                a::OFFSET_Y => assign_once(&mut offset_y, expect_i16_value, assignment)?,
-                a::SECTOR_IDX => assign_once(&mut sector_idx, expect_u16_value, assignment)?,
-                a::UPPER_TEXTURE => assign_once(&mut upper_texture, expect_str8_value, assignment)?,
-                a::MIDDLE_TEXTURE => {
-                    assign_once(&mut middle_texture, expect_str8_value, assignment)?
+        for assignment in block.assignments {
+            match assignment.item.identifier.item.symbol() {
+                s::OFFSET_X => assign_once(&mut offset_x, expect_i16_value, assignment, options)?,
+                s::OFFSET_Y => assign_once(&mut offset_y, expect_i16_value, assignment, options)?,
+                s::SECTOR_IDX => {
+                    assign_once(&mut sector_idx, expect_u16_value, assignment, options)?
+                }
+                s::UPPER_TEXTURE => {
+                    assign_once(&mut upper_texture, expect_str8_value, assignment, options)?
+                }
+                s::MIDDLE_TEXTURE => {
+                    assign_once(&mut middle_texture, expect_str8_value, assignment, options)?
+                }
+                s::LOWER_TEXTURE => {
+                    assign_once(&mut lower_texture, expect_str8_value, assignment, options)?
                 }
-                a::LOWER_TEXTURE => assign_once(&mut lower_texture, expect_str8_value, assignment)?,
 
                 _ => {
                     return Err(Box::new(CompileError::InvalidAssignment {
@@ -490,10 +1169,10 @@ impl UdmfBlock for RawSideDef {
 
         Ok(Self {
             offset: Point::new(
-                offset_x.map(|v| v.0).unwrap_or(0),
-                offset_y.map(|v| v.0).unwrap_or(0),
+                TextureOffset(offset_x.map(|v| v.0).unwrap_or(0)),
+                TextureOffset(offset_y.map(|v| v.0).unwrap_or(0)),
             ),
-            sector_idx: sector_idx.unwrap().0,
+            sector_idx: sector_idx.unwrap().0.into(),
 
             upper_texture: upper_texture
                 .map(|v| v.0)
@@ -511,11 +1190,13 @@ impl UdmfBlock for RawSideDef {
         use consts::side_def::assignments as a;
 
         writer.write_block(consts::side_def::BLOCK, |block| {
-            if self.offset.x != 0 {
+            block.write_assignment(a::SECTOR_IDX, &Value::Int(i32::from(self.sector_idx.get())))?;
+
+            if self.offset.x != TextureOffset(0) {
                 block.write_assignment(a::OFFSET_X, &Value::Int(i32::from(self.offset.x)))?;
             }
 
-            if self.offset.y != 0 {
+            if self.offset.y != TextureOffset(0) {
                 block.write_assignment(a::OFFSET_Y, &Value::Int(i32::from(self.offset.y)))?;
             }
 
@@ -550,8 +1231,8 @@ impl UdmfBlock for RawSideDef {
 }
 
 impl UdmfBlock for Sector {
-    fn compile(block: &ast::Block) -> Result<Self, Box<CompileError>> {
-        use consts::sector::assignments as a;
+    fn compile(block: ast::BlockRef, options: &CompileOptions) -> Result<Self, Box<CompileError>> {
+        use consts::sector::assignments::{self as a, symbols as s};
 
         let mut floor_height = None;
         let mut ceiling_height = None;
@@ -559,19 +1240,29 @@ impl UdmfBlock for Sector {
         let mut ceiling_flat = None;
         let mut light_level = None;
         let mut special = None;
+        let mut secret = None;
         let mut tag = None;
 
-        for assignment in &block.assignments {
-            match assignment.item.identifier.item.0.as_str() {
-                a::FLOOR_HEIGHT => assign_once(&mut floor_height, expect_i16_value, assignment)?,
-                a::CEILING_HEIGHT => {
-                    assign_once(&mut ceiling_height, expect_i16_value, assignment)?
+        for assignment in block.assignments {
+            match assignment.item.identifier.item.symbol() {
+                s::FLOOR_HEIGHT => {
+                    assign_once(&mut floor_height, expect_i16_value, assignment, options)?
+                }
+                s::CEILING_HEIGHT => {
+                    assign_once(&mut ceiling_height, expect_i16_value, assignment, options)?
+                }
+                s::FLOOR_FLAT => {
+                    assign_once(&mut floor_flat, expect_str8_value, assignment, options)?
                 }
-                a::FLOOR_FLAT => assign_once(&mut floor_flat, expect_str8_value, assignment)?,
-                a::CEILING_FLAT => assign_once(&mut ceiling_flat, expect_str8_value, assignment)?,
-                a::LIGHT_LEVEL => assign_once(&mut light_level, expect_u8_value, assignment)?,
-                a::SPECIAL => assign_once(&mut special, expect_i16_value, assignment)?,
-                a::TAG => assign_once(&mut tag, expect_i16_value, assignment)?,
+                s::CEILING_FLAT => {
+                    assign_once(&mut ceiling_flat, expect_str8_value, assignment, options)?
+                }
+                s::LIGHT_LEVEL => {
+                    assign_once(&mut light_level, expect_u8_value, assignment, options)?
+                }
+                s::SPECIAL => assign_once(&mut special, expect_i16_value, assignment, options)?,
+                s::SECRET => assign_once(&mut secret, expect_bool_value, assignment, options)?,
+                s::TAG => assign_once(&mut tag, expect_i16_value, assignment, options)?,
 
                 _ => {
                     return Err(Box::new(CompileError::InvalidAssignment {
@@ -600,8 +1291,11 @@ impl UdmfBlock for Sector {
             }));
         }
 
+        // The secret can be given by its own field or by a flag in the special
+        let mut secret = secret.map(|v| v.0).unwrap_or(false);
         let special = if let Some((value, span)) = special {
-            value
+            secret |= value & sector::SECRET_FLAG != 0;
+            (value & !sector::SECRET_FLAG)
                 .try_into()
                 .map_err(|_| Box::new(CompileError::SectorSpecial { value, span }))?
         } else {
@@ -609,8 +1303,8 @@ impl UdmfBlock for Sector {
         };
 
         Ok(Self {
-            floor_height: floor_height.map(|v| v.0).unwrap_or(0),
-            ceiling_height: ceiling_height.map(|v| v.0).unwrap_or(0),
+            floor_height: Height(floor_height.map(|v| v.0).unwrap_or(0)),
+            ceiling_height: Height(ceiling_height.map(|v| v.0).unwrap_or(0)),
 
             floor_flat: floor_flat.unwrap().0,
             ceiling_flat: ceiling_flat.unwrap().0,
@@ -619,7 +1313,9 @@ impl UdmfBlock for Sector {
                 .map(|v| v.0)
                 .unwrap_or(consts::sector::DEFAULT_LIGHT_LEVEL),
             special,
+            secret,
             tag: tag.map(|v| v.0).unwrap_or(0),
+            kill_grounded_monsters: false,
         })
     }
 
@@ -627,11 +1323,11 @@ impl UdmfBlock for Sector {
         use consts::sector::assignments as a;
 
         writer.write_block(consts::sector::BLOCK, |block| {
-            if self.floor_height != 0 {
+            if self.floor_height != Height(0) {
                 block
                     .write_assignment(a::FLOOR_HEIGHT, &Value::Int(i32::from(self.floor_height)))?;
             }
-            if self.ceiling_height != 0 {
+            if self.ceiling_height != Height(0) {
                 block.write_assignment(
                     a::CEILING_HEIGHT,
                     &Value::Int(i32::from(self.ceiling_height)),
@@ -660,7 +1356,10 @@ impl UdmfBlock for Sector {
             if self.light_level != consts::sector::DEFAULT_LIGHT_LEVEL {
                 block.write_assignment(a::LIGHT_LEVEL, &Value::Int(i32::from(self.light_level)))?;
             }
-            let special: i16 = self.special.into();
+            let mut special: i16 = self.special.into();
+            if self.secret {
+                special |= sector::SECRET_FLAG;
+            }
             if special != 0 {
                 block.write_assignment(a::SPECIAL, &Value::Int(i32::from(special)))?;
             }
@@ -675,16 +1374,22 @@ impl UdmfBlock for Sector {
 }
 
 impl UdmfBlock for Vertex {
-    fn compile(block: &ast::Block) -> Result<Self, Box<CompileError>> {
-        use consts::vertex::assignments as a;
+    fn compile(block: ast::BlockRef, options: &CompileOptions) -> Result<Self, Box<CompileError>> {
+        use consts::vertex::assignments::{self as a, symbols as s};
 
         let mut x = None;
         let mut y = None;
-
-        for assignment in &block.assignments {
-            match assignment.item.identifier.item.0.as_str() {
-                a::X => assign_once(&mut x, expect_number_value, assignment)?,
-                a::Y => assign_once(&mut y, expect_number_value, assignment)?,
+        let mut floor_z = None;
+        let mut ceiling_z = None;
+
+        for assignment in block.assignments {
+            match assignment.item.identifier.item.symbol() {
+                s::X => assign_once(&mut x, expect_number_value, assignment, options)?,
+                s::Y => assign_once(&mut y, expect_number_value, assignment, options)?,
+                s::FLOOR_Z => assign_once(&mut floor_z, expect_number_value, assignment, options)?,
+                s::CEILING_Z => {
+                    assign_once(&mut ceiling_z, expect_number_value, assignment, options)?
+                }
 
                 _ => {
                     return Err(Box::new(CompileError::InvalidAssignment {
@@ -718,6 +1423,8 @@ impl UdmfBlock for Vertex {
                 x: x.unwrap().0,
                 y: y.unwrap().0,
             },
+            floor_z: floor_z.map(|(z, _): (Number, _)| z.into_float()),
+            ceiling_z: ceiling_z.map(|(z, _): (Number, _)| z.into_float()),
         })
     }
 
@@ -727,6 +1434,12 @@ impl UdmfBlock for Vertex {
         writer.write_block(consts::vertex::BLOCK, |block| {
             block.write_assignment(a::X, &self.position.x.into())?;
             block.write_assignment(a::Y, &self.position.y.into())?;
+            if let Some(z) = self.floor_z {
+                block.write_assignment(a::FLOOR_Z, &Value::Float(z))?;
+            }
+            if let Some(z) = self.ceiling_z {
+                block.write_assignment(a::CEILING_Z, &Value::Float(z))?;
+            }
 
             Ok(())
         })
@@ -734,8 +1447,8 @@ impl UdmfBlock for Vertex {
 }
 
 impl UdmfBlock for Thing {
-    fn compile(block: &ast::Block) -> Result<Self, Box<CompileError>> {
-        use consts::thing::assignments as a;
+    fn compile(block: ast::BlockRef, options: &CompileOptions) -> Result<Self, Box<CompileError>> {
+        use consts::thing::assignments::{self as a, symbols as s};
 
         let mut x = None;
         let mut y = None;
@@ -767,35 +1480,43 @@ impl UdmfBlock for Thing {
 
         let default_flags = thing::Flags::default();
 
-        for assignment in &block.assignments {
-            match assignment.item.identifier.item.0.as_str() {
-                a::X => assign_once(&mut x, expect_number_value, assignment)?,
-                a::Y => assign_once(&mut y, expect_number_value, assignment)?,
-                a::ANGLE => assign_once(&mut angle, expect_i16_value, assignment)?,
-                a::HEIGHT => assign_once(&mut height, expect_i16_value, assignment)?,
-                a::TYPE => assign_once(&mut type_, expect_i16_value, assignment)?,
-
-                a::SKILL1 => assign_once(&mut skill1, expect_bool_value, assignment)?,
-                a::SKILL2 => assign_once(&mut skill2, expect_bool_value, assignment)?,
-                a::SKILL3 => assign_once(&mut skill3, expect_bool_value, assignment)?,
-                a::SKILL4 => assign_once(&mut skill4, expect_bool_value, assignment)?,
-                a::SKILL5 => assign_once(&mut skill5, expect_bool_value, assignment)?,
-
-                a::AMBUSH => assign_once(&mut ambush, expect_bool_value, assignment)?,
-
-                a::CLASS1 => assign_once(&mut class1, expect_bool_value, assignment)?,
-                a::CLASS2 => assign_once(&mut class2, expect_bool_value, assignment)?,
-                a::CLASS3 => assign_once(&mut class3, expect_bool_value, assignment)?,
-
-                a::MBF_FRIEND => assign_once(&mut mbf_friend, expect_bool_value, assignment)?,
-                a::DORMANT => assign_once(&mut dormant, expect_bool_value, assignment)?,
-                a::COOP => assign_once(&mut coop, expect_bool_value, assignment)?,
-                a::DM => assign_once(&mut dm, expect_bool_value, assignment)?,
-                a::INVISIBLE => assign_once(&mut invisible, expect_bool_value, assignment)?,
-                a::NPC => assign_once(&mut npc, expect_bool_value, assignment)?,
-                a::SINGLE => assign_once(&mut single, expect_bool_value, assignment)?,
-                a::STRIFE_ALLY => assign_once(&mut strife_ally, expect_bool_value, assignment)?,
-                a::TRANSLUCENT => assign_once(&mut translucent, expect_bool_value, assignment)?,
+        for assignment in block.assignments {
+            match assignment.item.identifier.item.symbol() {
+                s::X => assign_once(&mut x, expect_number_value, assignment, options)?,
+                s::Y => assign_once(&mut y, expect_number_value, assignment, options)?,
+                s::ANGLE => assign_once(&mut angle, expect_i16_value, assignment, options)?,
+                s::HEIGHT => assign_once(&mut height, expect_i16_value, assignment, options)?,
+                s::TYPE => assign_once(&mut type_, expect_i16_value, assignment, options)?,
+
+                s::SKILL1 => assign_once(&mut skill1, expect_bool_value, assignment, options)?,
+                s::SKILL2 => assign_once(&mut skill2, expect_bool_value, assignment, options)?,
+                s::SKILL3 => assign_once(&mut skill3, expect_bool_value, assignment, options)?,
+                s::SKILL4 => assign_once(&mut skill4, expect_bool_value, assignment, options)?,
+                s::SKILL5 => assign_once(&mut skill5, expect_bool_value, assignment, options)?,
+
+                s::AMBUSH => assign_once(&mut ambush, expect_bool_value, assignment, options)?,
+
+                s::CLASS1 => assign_once(&mut class1, expect_bool_value, assignment, options)?,
+                s::CLASS2 => assign_once(&mut class2, expect_bool_value, assignment, options)?,
+                s::CLASS3 => assign_once(&mut class3, expect_bool_value, assignment, options)?,
+
+                s::MBF_FRIEND => {
+                    assign_once(&mut mbf_friend, expect_bool_value, assignment, options)?
+                }
+                s::DORMANT => assign_once(&mut dormant, expect_bool_value, assignment, options)?,
+                s::COOP => assign_once(&mut coop, expect_bool_value, assignment, options)?,
+                s::DM => assign_once(&mut dm, expect_bool_value, assignment, options)?,
+                s::INVISIBLE => {
+                    assign_once(&mut invisible, expect_bool_value, assignment, options)?
+                }
+                s::NPC => assign_once(&mut npc, expect_bool_value, assignment, options)?,
+                s::SINGLE => assign_once(&mut single, expect_bool_value, assignment, options)?,
+                s::STRIFE_ALLY => {
+                    assign_once(&mut strife_ally, expect_bool_value, assignment, options)?
+                }
+                s::TRANSLUCENT => {
+                    assign_once(&mut translucent, expect_bool_value, assignment, options)?
+                }
 
                 _ => {
                     return Err(Box::new(CompileError::InvalidAssignment {
@@ -834,8 +1555,8 @@ impl UdmfBlock for Thing {
                 y: y.unwrap().0,
             },
 
-            angle: angle.map(|v| v.0).unwrap_or(0),
-            height: height.map(|v| v.0).unwrap_or(0),
+            angle: Angle(angle.map(|v| v.0).unwrap_or(0)),
+            height: Height(height.map(|v| v.0).unwrap_or(0)),
 
             type_: type_.unwrap().0,
 
@@ -876,10 +1597,13 @@ impl UdmfBlock for Thing {
         use consts::thing::assignments as a;
 
         writer.write_block(consts::thing::BLOCK, |block| {
-            if self.height != 0 {
+            block.write_assignment(a::X, &self.position.x.into())?;
+            block.write_assignment(a::Y, &self.position.y.into())?;
+
+            if self.height != Height(0) {
                 block.write_assignment(a::HEIGHT, &Value::Int(i32::from(self.height)))?;
             }
-            if self.angle != 0 {
+            if self.angle != Angle::EAST {
                 block.write_assignment(a::ANGLE, &Value::Int(i32::from(self.angle)))?;
             }
 
@@ -969,7 +1693,11 @@ impl Display for ValueType {
 }
 
 // TODO: Move to AST?
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
 pub enum Value {
     Int(i32),
     Float(f64),
@@ -977,6 +1705,44 @@ pub enum Value {
     Bool(bool),
 }
 
+impl Value {
+    /// Convert this value to another type, if it can be done without losing information
+    pub fn coerce(&self, value_type: ValueType) -> Option<Value> {
+        match (self, value_type) {
+            (Value::Int(_), ValueType::Int)
+            | (Value::Float(_), ValueType::Float)
+            | (Value::Str(_), ValueType::Str)
+            | (Value::Bool(_), ValueType::Bool) => Some(self.clone()),
+
+            (Value::Int(i), ValueType::Float) => Some(Value::Float(f64::from(*i))),
+            (Value::Float(f), ValueType::Int) if f.fract() == 0.0 => {
+                Some(Value::Int(*f as i32)).filter(|_| f.abs() <= f64::from(i32::MAX))
+            }
+            (Value::Int(0), ValueType::Bool) => Some(Value::Bool(false)),
+            (Value::Int(1), ValueType::Bool) => Some(Value::Bool(true)),
+
+            (Value::Str(s), ValueType::Int) => s.trim().parse().ok().map(Value::Int),
+            (Value::Str(s), ValueType::Float) => s.trim().parse().ok().map(Value::Float),
+            (Value::Str(s), ValueType::Bool) => s.trim().parse().ok().map(Value::Bool),
+
+            (Value::Int(_) | Value::Float(_) | Value::Bool(_), ValueType::Str) => {
+                Some(Value::Str(self.to_string()))
+            }
+
+            _ => None,
+        }
+    }
+
+    /// Convert a value as with [Coercion::Lenient], i.e. between numbers and from 0 and 1 to booleans
+    fn coerce_leniently(&self, value_type: ValueType) -> Option<Value> {
+        match (self, value_type) {
+            (Value::Int(_) | Value::Float(_), ValueType::Int | ValueType::Float)
+            | (Value::Int(0 | 1), ValueType::Bool) => self.coerce(value_type),
+            _ => None,
+        }
+    }
+}
+
 impl From<Number> for Value {
     fn from(n: Number) -> Self {
         match n {
@@ -986,12 +1752,32 @@ impl From<Number> for Value {
     }
 }
 
+/// Fixed point numbers are always written as floats, which represent them exactly
+impl From<Fixed> for Value {
+    fn from(fixed: Fixed) -> Self {
+        Self::Float(fixed.into_float())
+    }
+}
+
+/// Displays the value as it would appear in a TEXTMAP, for diagnostics. TEXTMAPs are written through
+/// `UdmfWriter::write_assignment` instead, which formats floats with the `WriteOptions` and rejects infinities and NaN.
 impl Display for Value {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Value::Int(v) => write!(f, "{}", v),
             Value::Float(v) => write!(f, "{}", v),
-            Value::Str(v) => write!(f, "\"{}\"", v),
+            Value::Str(v) => {
+                f.write_char('"')?;
+                for c in v.chars() {
+                    match c {
+                        '"' => f.write_str("\\\"")?,
+                        '\\' => f.write_str("\\\\")?,
+                        '\n' => f.write_str("\\n")?,
+                        c => f.write_char(c)?,
+                    }
+                }
+                f.write_char('"')
+            }
             Value::Bool(v) => write!(f, "{}", v),
         }
     }
@@ -1005,6 +1791,7 @@ fn expect_u16_value(
     u16::try_from(n).map_err(|_| {
         Box::new(CompileError::OutOfRange {
             identifier: assignment.item.identifier.item.clone(),
+            value: n,
             range: i32::from(u16::MIN)..=i32::from(u16::MAX),
             span: assignment.item.value.span.clone(),
         })
@@ -1019,6 +1806,7 @@ fn expect_i16_value(
     i16::try_from(n).map_err(|_| {
         Box::new(CompileError::OutOfRange {
             identifier: assignment.item.identifier.item.clone(),
+            value: n,
             range: i32::from(i16::MIN)..=i32::from(i16::MAX),
             span: assignment.item.value.span.clone(),
         })
@@ -1033,6 +1821,7 @@ fn expect_u8_value(
     u8::try_from(n).map_err(|_| {
         Box::new(CompileError::OutOfRange {
             identifier: assignment.item.identifier.item.clone(),
+            value: n,
             range: i32::from(u8::MIN)..=i32::from(u8::MAX),
             span: assignment.item.value.span.clone(),
         })
@@ -1128,6 +1917,7 @@ fn assign_once<T, F>(
     opt: &mut Option<(T, Range<usize>)>,
     expect: F,
     assignment: &ast::Spanned<ast::AssignmentExpr>,
+    options: &CompileOptions,
 ) -> Result<(), Box<CompileError>>
 where
     F: Fn(&ast::Spanned<ast::AssignmentExpr>) -> Result<T, Box<CompileError>>,
@@ -1136,15 +1926,40 @@ where
         Err(Box::new(CompileError::MultipleAssignment {
             identifier: assignment.item.identifier.item.clone(),
             previous_span: previous_span.clone(),
-            span: assignment.span.clone(),
+            span: statement_span(assignment),
         }))
     } else {
-        let value = expect(assignment)?;
-        *opt = Some((value, assignment.span.clone()));
+        let value = match expect(assignment) {
+            Err(error) if options.coercion == Coercion::Lenient => {
+                let coerced = match &*error {
+                    CompileError::InvalidAssignmentType {
+                        value, expected, ..
+                    } => expected
+                        .0
+                        .iter()
+                        .find_map(|&value_type| value.coerce_leniently(value_type)),
+                    _ => None,
+                };
+                let Some(coerced) = coerced else {
+                    return Err(error);
+                };
+
+                let mut assignment = assignment.clone();
+                assignment.item.value.item = coerced;
+                expect(&assignment)?
+            }
+            result => result?,
+        };
+        *opt = Some((value, statement_span(assignment)));
         Ok(())
     }
 }
 
+/// The span of an assignment from its identifier to its semicolon, excluding any leading whitespace and comments
+fn statement_span(assignment: &ast::Spanned<ast::AssignmentExpr>) -> Range<usize> {
+    assignment.item.identifier.span.start..assignment.span.end
+}
+
 // TODO: Rewrite this to take ast types
 pub trait UdmfWriter: Sized {
     type Writer: Write;
@@ -1152,9 +1967,20 @@ pub trait UdmfWriter: Sized {
 
     fn indent(&self) -> usize;
 
+    fn options(&self) -> &WriteOptions {
+        &WriteOptions::DEFAULT
+    }
+
     fn write_comment(&mut self, text: &str) -> Result<(), WriteError> {
         let indent = self.indent();
-        writeln!(self.writer(), "{:2$}//{}", "", text, indent)?;
+        let width = self
+            .options()
+            .max_line_length
+            .map_or(usize::MAX, |max| max.saturating_sub(indent + 2).max(1));
+
+        for line in wrap(text, width) {
+            writeln!(self.writer(), "{:2$}//{}", "", line, indent)?;
+        }
         Ok(())
     }
 
@@ -1165,7 +1991,22 @@ pub trait UdmfWriter: Sized {
 
     fn write_assignment(&mut self, key: &str, value: &Value) -> Result<(), WriteError> {
         let indent = self.indent();
-        writeln!(self.writer(), "{:3$}{}={};", "", key, value, indent)?;
+        let value = match value {
+            Value::Float(f) => self.options().format_float(key, *f)?,
+            _ => value.to_string(),
+        };
+
+        let length = indent + key.len() + value.len() + 2;
+        if self
+            .options()
+            .max_line_length
+            .is_some_and(|max| length > max)
+        {
+            writeln!(self.writer(), "{:1$}{key}=", "", indent)?;
+            writeln!(self.writer(), "{:1$}{value};", "", indent + 2)?;
+        } else {
+            writeln!(self.writer(), "{:1$}{key}={value};", "", indent)?;
+        }
         Ok(())
     }
 
@@ -1183,6 +2024,32 @@ pub trait UdmfWriter: Sized {
     }
 }
 
+/// Split text into lines of at most `width` bytes, breaking at spaces where possible
+fn wrap(text: &str, width: usize) -> Vec<&str> {
+    let mut lines = Vec::new();
+    let mut rest = text;
+
+    while rest.len() > width {
+        let mut end = width;
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        if end == 0 {
+            end = rest.chars().next().map_or(rest.len(), char::len_utf8);
+        }
+
+        let (line, next) = match rest[..end].rfind(' ') {
+            Some(space) if space > 0 => (&rest[..space], &rest[space + 1..]),
+            _ => rest.split_at(end),
+        };
+        lines.push(line);
+        rest = next;
+    }
+
+    lines.push(rest);
+    lines
+}
+
 pub struct UdmfBlockWriter<'w, W>(&'w mut W);
 
 impl<'w, W: UdmfWriter> UdmfBlockWriter<'w, W> {
@@ -1209,6 +2076,61 @@ impl<'w, W: UdmfWriter> UdmfWriter for UdmfBlockWriter<'w, W> {
     fn indent(&self) -> usize {
         self.0.indent() + 2
     }
+
+    fn options(&self) -> &WriteOptions {
+        self.0.options()
+    }
+}
+
+/// A writer using options other than the defaults
+struct OptionsWriter<'o, W> {
+    writer: W,
+    options: &'o WriteOptions,
+}
+
+impl<W: Write> UdmfWriter for OptionsWriter<'_, W> {
+    type Writer = W;
+
+    fn writer(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    fn indent(&self) -> usize {
+        0
+    }
+
+    fn options(&self) -> &WriteOptions {
+        self.options
+    }
+}
+
+/// A writer which records where top-level blocks end, so that the output can be split between them
+struct FragmentWriter<'o> {
+    textmap: Vec<u8>,
+    options: &'o WriteOptions,
+    boundaries: Vec<usize>,
+}
+
+impl UdmfWriter for FragmentWriter<'_> {
+    type Writer = Vec<u8>;
+
+    fn writer(&mut self) -> &mut Vec<u8> {
+        &mut self.textmap
+    }
+
+    fn indent(&self) -> usize {
+        0
+    }
+
+    fn options(&self) -> &WriteOptions {
+        self.options
+    }
+
+    fn write_blank_line(&mut self) -> Result<(), WriteError> {
+        writeln!(self.textmap)?;
+        self.boundaries.push(self.textmap.len());
+        Ok(())
+    }
 }
 
 impl<W: Write> UdmfWriter for W {
@@ -1225,7 +2147,16 @@ impl<W: Write> UdmfWriter for W {
 
 impl Map {
     pub fn write_udmf_textmap<W: Write>(&self, writer: &mut W) -> Result<(), WriteError> {
+        self.write_udmf_textmap_with_options(writer, &WriteOptions::default())
+    }
+
+    pub fn write_udmf_textmap_with_options<W: Write>(
+        &self,
+        writer: &mut W,
+        options: &WriteOptions,
+    ) -> Result<(), WriteError> {
         let raw_map = self.unlink()?;
+        let writer = &mut OptionsWriter { writer, options };
 
         writer.write_comment(&format!(
             "Written by {} v{}",
@@ -1233,103 +2164,356 @@ impl Map {
             env!("CARGO_PKG_VERSION")
         ))?;
 
-        writer.write_assignment("namespace", &Value::Str("zdoom".to_string()))?;
+        write_udmf_raw_map(&raw_map, "zdoom", writer)
+    }
 
-        writer.write_comment("Vertexes")?;
-        for (i, vertex) in raw_map.vertexes.iter().enumerate() {
-            writer.write_comment(&format!("#{}", i))?;
-            vertex.write(writer)?;
-            writer.write_blank_line()?;
-        }
+    /// Write only the selected entities and the entities they depend on as a self-contained TEXTMAP.
+    ///
+    /// Indices in the output are relative to the selection, not to the whole map, so the result can be loaded on its
+    /// own (e.g. as a prefab, or attached to a bug report).
+    pub fn write_udmf_selection<W: Write>(
+        &self,
+        selection: &Selection,
+        writer: &mut W,
+    ) -> Result<(), WriteError> {
+        let raw_map = self.extract(selection)?.unlink()?;
 
-        writer.write_comment("Line Defs")?;
-        for (i, line_def) in raw_map.line_defs.iter().enumerate() {
-            writer.write_comment(&format!("#{}", i))?;
-            line_def.write(writer)?;
-            writer.write_blank_line()?;
-        }
+        writer.write_comment(&format!(
+            "Selection written by {} v{}",
+            env!("CARGO_PKG_NAME"),
+            env!("CARGO_PKG_VERSION")
+        ))?;
 
-        writer.write_comment("Sectors")?;
-        for (i, sector) in raw_map.sectors.iter().enumerate() {
-            writer.write_comment(&format!("#{}", i))?;
-            sector.write(writer)?;
-            writer.write_blank_line()?;
-        }
+        write_udmf_raw_map(&raw_map, "zdoom", writer)
+    }
 
-        writer.write_comment("Side Defs")?;
-        for (i, side_def) in raw_map.side_defs.iter().enumerate() {
-            writer.write_comment(&format!("#{}", i))?;
-            side_def.write(writer)?;
-            writer.write_blank_line()?;
-        }
+    /// Append the map to a WAD: a marker lump with the map's name, TEXTMAP, the passthrough lumps which are present
+    /// and ENDMAP, in the order engines expect.
+    pub fn write_udmf_lumps(
+        &self,
+        wad: &mut Wad,
+        passthrough: &PassthroughLumps,
+    ) -> Result<(), WriteError> {
+        let mut textmap = Vec::new();
+        self.write_udmf_textmap(&mut textmap)?;
+
+        let lumps = [
+            ("TEXTMAP", Some(textmap)),
+            ("ZNODES", passthrough.znodes.clone()),
+            ("DIALOGUE", passthrough.dialogue.clone()),
+            ("BEHAVIOR", passthrough.behavior.clone()),
+            ("SCRIPTS", passthrough.scripts.clone()),
+            ("ENDMAP", Some(Vec::new())),
+        ];
 
-        writer.write_comment("Things")?;
-        for (i, thing) in raw_map.things.iter().enumerate() {
-            writer.write_comment(&format!("#{}", i))?;
-            thing.write(writer)?;
-            writer.write_blank_line()?;
-        }
+        wad.lumps.push(Lump::new(self.name.clone(), Vec::new()));
+        wad.lumps.extend(
+            lumps
+                .into_iter()
+                .filter_map(|(name, data)| Some(Lump::new(String8::new_unchecked(name), data?))),
+        );
 
         Ok(())
     }
 
-    pub fn load_udmf_textmap<R: Read>(name: String8, contents: &str) -> Result<Self, LoadError> {
-        let translation_unit =
-            parse::parse_translation_unit(&mut Located::new(contents)).map_err(|e| {
-                LoadError::Parse(e.into_inner().expect("Incomplete parse error not expected"))
-            })?;
-        let raw_map = compile_udmf_translation_unit(&translation_unit, name)?;
+    pub fn load_udmf_textmap(name: String8, contents: &str) -> Result<Self, LoadError> {
+        Self::load_udmf_textmap_with_options(name, contents, &CompileOptions::default())
+    }
+
+    pub fn load_udmf_textmap_with_options(
+        name: String8,
+        contents: &str,
+        options: &CompileOptions,
+    ) -> Result<Self, LoadError> {
+        let translation_unit = ast::TranslationUnit::parse(contents)?;
+        let raw_map = compile_udmf_translation_unit(&translation_unit, name, options)?;
         let map = raw_map.link()?;
 
         Ok(map)
     }
+
+    /// Load a TEXTMAP which was split into several fragments, e.g. by [RawMap::to_udmf_fragments] or by hand.
+    ///
+    /// The fragments are concatenated as they are, so they may be split anywhere. Each fragment may repeat the
+    /// `namespace` assignment, as long as it's to the same namespace. Spans in errors are relative to the
+    /// concatenated TEXTMAP.
+    pub fn load_udmf_textmap_fragments<S: AsRef<str>>(
+        name: String8,
+        fragments: &[S],
+    ) -> Result<Self, LoadError> {
+        Self::load_udmf_textmap_fragments_with_options(name, fragments, &CompileOptions::default())
+    }
+
+    pub fn load_udmf_textmap_fragments_with_options<S: AsRef<str>>(
+        name: String8,
+        fragments: &[S],
+        options: &CompileOptions,
+    ) -> Result<Self, LoadError> {
+        let contents: String = fragments.iter().map(AsRef::as_ref).collect();
+        Self::load_udmf_textmap_with_options(name, &contents, options)
+    }
+}
+
+impl RawMap {
+    /// Write the map as a TEXTMAP in the given namespace. Specials are written as in ZDoom in the `zdoom` and `hexen`
+    /// namespaces, and as in Doom in the `strife` namespace, which fails on specials with no Doom equivalent. Other
+    /// namespaces aren't supported.
+    ///
+    /// Together with [RawMap::parse_binary], this converts binary maps to UDMF.
+    pub fn to_udmf(&self, namespace: &str) -> Result<String, WriteError> {
+        self.to_udmf_with_options(namespace, &WriteOptions::default())
+    }
+
+    pub fn to_udmf_with_options(
+        &self,
+        namespace: &str,
+        options: &WriteOptions,
+    ) -> Result<String, WriteError> {
+        let mut textmap = Vec::new();
+        write_udmf_raw_map(
+            self,
+            namespace,
+            &mut OptionsWriter {
+                writer: &mut textmap,
+                options,
+            },
+        )?;
+
+        Ok(String::from_utf8(textmap).expect("UDMF is written as UTF-8"))
+    }
+
+    /// Write the map as a TEXTMAP split into fragments of at most `max_fragment_length` bytes, for ports and tools
+    /// which can't handle a single large lump. Fragments are only split between top-level blocks, so a block which is
+    /// longer than the limit on its own gets a fragment to itself.
+    ///
+    /// Concatenating the fragments gives the same TEXTMAP as [RawMap::to_udmf_with_options], and
+    /// [Map::load_udmf_textmap_fragments] loads them back.
+    pub fn to_udmf_fragments(
+        &self,
+        namespace: &str,
+        options: &WriteOptions,
+        max_fragment_length: usize,
+    ) -> Result<Vec<String>, WriteError> {
+        let mut writer = FragmentWriter {
+            textmap: Vec::new(),
+            options,
+            boundaries: Vec::new(),
+        };
+        write_udmf_raw_map(self, namespace, &mut writer)?;
+
+        let textmap = String::from_utf8(writer.textmap).expect("UDMF is written as UTF-8");
+        let mut fragments = Vec::new();
+        let mut start = 0;
+        let mut end = 0;
+
+        for boundary in writer.boundaries.into_iter().chain([textmap.len()]) {
+            if boundary - start > max_fragment_length && end > start {
+                fragments.push(textmap[start..end].to_owned());
+                start = end;
+            }
+            end = boundary;
+        }
+        if end > start {
+            fragments.push(textmap[start..end].to_owned());
+        }
+
+        Ok(fragments)
+    }
+}
+
+fn write_udmf_raw_map<W: UdmfWriter>(
+    raw_map: &RawMap,
+    namespace: &str,
+    writer: &mut W,
+) -> Result<(), WriteError> {
+    let Some(numbering) = SpecialNumbering::of(namespace) else {
+        return Err(WriteError::UnsupportedNamespace(namespace.to_owned()));
+    };
+
+    writer.write_assignment("namespace", &Value::Str(namespace.to_owned()))?;
+
+    writer.write_comment("Vertexes")?;
+    for (i, vertex) in raw_map.vertexes.iter().enumerate() {
+        writer.write_comment(&format!("#{}", i))?;
+        vertex.write(writer)?;
+        writer.write_blank_line()?;
+    }
+
+    writer.write_comment("Line Defs")?;
+    for (i, line_def) in raw_map.line_defs.iter().enumerate() {
+        writer.write_comment(&format!("#{}", i))?;
+        line_def.write_numbered(writer, numbering)?;
+        writer.write_blank_line()?;
+    }
+
+    writer.write_comment("Sectors")?;
+    for (i, sector) in raw_map.sectors.iter().enumerate() {
+        writer.write_comment(&format!("#{}", i))?;
+        sector.write(writer)?;
+        writer.write_blank_line()?;
+    }
+
+    writer.write_comment("Side Defs")?;
+    for (i, side_def) in raw_map.side_defs.iter().enumerate() {
+        writer.write_comment(&format!("#{}", i))?;
+        side_def.write(writer)?;
+        writer.write_blank_line()?;
+    }
+
+    writer.write_comment("Things")?;
+    for (i, thing) in raw_map.things.iter().enumerate() {
+        writer.write_comment(&format!("#{}", i))?;
+        thing.write(writer)?;
+        writer.write_blank_line()?;
+    }
+
+    if !raw_map.unknown_blocks.is_empty() {
+        writer.write_comment("Unknown Blocks")?;
+    }
+    for block in &raw_map.unknown_blocks {
+        writer.write_block(&block.identifier, |block_writer| {
+            for (key, value) in &block.assignments {
+                block_writer.write_assignment(key, value)?;
+            }
+            Ok::<_, WriteError>(())
+        })?;
+        writer.write_blank_line()?;
+    }
+
+    Ok(())
 }
 
 fn compile_udmf_translation_unit(
     translation_unit: &ast::TranslationUnit,
     name: String8,
+    options: &CompileOptions,
 ) -> Result<RawMap, Box<CompileError>> {
-    use consts::global::assignments as a;
+    use consts::global::assignments::{self as a, symbols as s};
 
+    // The namespace decides how the specials of every line def are numbered, wherever it's assigned
     let mut namespace = None;
 
+    for assignment in
+        translation_unit
+            .expressions
+            .iter()
+            .filter_map(|expression| match expression {
+                GlobalExpr::AssignmentExpr(assignment) => Some(assignment),
+                GlobalExpr::Block(_) => None,
+            })
+    {
+        match assignment.item.identifier.item.symbol() {
+            // Fragments which were concatenated into one TEXTMAP may each repeat the namespace
+            s::NAMESPACE
+                if namespace.as_ref().is_some_and(|(previous, _)| {
+                    expect_str_value(assignment).is_ok_and(|value| value == *previous)
+                }) => {}
+            s::NAMESPACE if namespace.is_some() => match options.duplicate_namespace {
+                DuplicateNamespace::Error => {
+                    assign_once(&mut namespace, expect_str_value, assignment, options)?
+                }
+                DuplicateNamespace::FirstWins => {
+                    expect_str_value(assignment)?;
+                }
+                DuplicateNamespace::LastWins => {
+                    namespace = Some((expect_str_value(assignment)?, statement_span(assignment)));
+                }
+            },
+            s::NAMESPACE => assign_once(&mut namespace, expect_str_value, assignment, options)?,
+
+            _ => {
+                return Err(Box::new(CompileError::InvalidAssignment {
+                    identifier: assignment.item.identifier.item.clone(),
+                    valid: ValidIdentifiers(a::ALL),
+                    span: assignment.span.clone(),
+                }))
+            }
+        }
+    }
+
     let mut vertexes: Vec<Vertex> = Vec::new();
     let mut line_defs: Vec<RawLineDef> = Vec::new();
     let mut side_defs: Vec<RawSideDef> = Vec::new();
     let mut sectors: Vec<Sector> = Vec::new();
     let mut things: Vec<Thing> = Vec::new();
+    let mut unknown_blocks: Vec<UnknownBlock> = Vec::new();
 
     for global_expression in &translation_unit.expressions {
         match global_expression {
-            GlobalExpr::AssignmentExpr(assignment) => {
-                match assignment.item.identifier.item.0.as_str() {
-                    a::NAMESPACE => assign_once(&mut namespace, expect_str_value, assignment)?,
-
-                    _ => {
-                        return Err(Box::new(CompileError::InvalidAssignment {
-                            identifier: assignment.item.identifier.item.clone(),
-                            valid: ValidIdentifiers(a::ALL),
-                            span: assignment.span.clone(),
+            GlobalExpr::AssignmentExpr(_) => {}
+
+            GlobalExpr::Block(block) => match block.item.identifier.item.symbol() {
+                consts::vertex::BLOCK_SYMBOL => vertexes.push(Vertex::compile(
+                    translation_unit.block(&block.item),
+                    options,
+                )?),
+                consts::line_def::BLOCK_SYMBOL => {
+                    let numbering = namespace
+                        .as_ref()
+                        .and_then(|(namespace, _)| SpecialNumbering::of(namespace))
+                        .unwrap_or(SpecialNumbering::Udmf);
+
+                    line_defs.push(RawLineDef::compile_numbered(
+                        translation_unit.block(&block.item),
+                        options,
+                        numbering,
+                    )?)
+                }
+                consts::sector::BLOCK_SYMBOL => sectors.push(Sector::compile(
+                    translation_unit.block(&block.item),
+                    options,
+                )?),
+                consts::side_def::BLOCK_SYMBOL => side_defs.push(RawSideDef::compile(
+                    translation_unit.block(&block.item),
+                    options,
+                )?),
+                consts::thing::BLOCK_SYMBOL => things.push(Thing::compile(
+                    translation_unit.block(&block.item),
+                    options,
+                )?),
+
+                _ => match options.unknown_blocks {
+                    UnknownBlocks::Error => {
+                        return Err(Box::new(CompileError::InvalidBlock {
+                            identifier: block.item.identifier.item.clone(),
+                            valid: ValidIdentifiers(consts::global::BLOCKS),
+                            span: block.item.identifier.span.clone(),
                         }))
                     }
-                }
-            }
+                    UnknownBlocks::Skip => {}
+                    UnknownBlocks::Preserve => unknown_blocks.push(UnknownBlock {
+                        identifier: block.item.identifier.item.as_str().to_owned(),
+                        assignments: translation_unit
+                            .block(&block.item)
+                            .assignments
+                            .iter()
+                            .map(|assignment| {
+                                (
+                                    assignment.item.identifier.item.as_str().to_owned(),
+                                    assignment.item.value.item.clone(),
+                                )
+                            })
+                            .collect(),
+                    }),
+                },
+            },
+        }
+    }
 
-            GlobalExpr::Block(block) => match block.item.identifier.item.0.as_str() {
-                consts::vertex::BLOCK => vertexes.push(Vertex::compile(&block.item)?),
-                consts::line_def::BLOCK => line_defs.push(RawLineDef::compile(&block.item)?),
-                consts::sector::BLOCK => sectors.push(Sector::compile(&block.item)?),
-                consts::side_def::BLOCK => side_defs.push(RawSideDef::compile(&block.item)?),
-                consts::thing::BLOCK => things.push(Thing::compile(&block.item)?),
+    if options.string8_case == String8Case::Uppercase {
+        for side_def in side_defs.iter_mut() {
+            for texture in [
+                &mut side_def.upper_texture,
+                &mut side_def.middle_texture,
+                &mut side_def.lower_texture,
+            ] {
+                *texture = texture.to_uppercase();
+            }
+        }
 
-                _ => {
-                    return Err(Box::new(CompileError::InvalidBlock {
-                        identifier: block.item.identifier.item.clone(),
-                        valid: ValidIdentifiers(consts::global::BLOCKS),
-                        span: block.item.identifier.span.clone(),
-                    }))
-                }
-            },
+        for sector in sectors.iter_mut() {
+            sector.floor_flat = sector.floor_flat.to_uppercase();
+            sector.ceiling_flat = sector.ceiling_flat.to_uppercase();
         }
     }
 
@@ -1340,6 +2524,7 @@ fn compile_udmf_translation_unit(
         side_defs,
         sectors,
         things,
+        unknown_blocks,
     })
 }
 
@@ -1347,110 +2532,540 @@ fn compile_udmf_translation_unit(
 mod tests {
     use super::*;
 
-    use std::{convert::TryInto, io::Cursor};
+    use std::convert::TryInto;
 
     use pretty_assertions::assert_eq;
 
+    use crate::{
+        diagnostic::{DiagnosticReport, ReportSeverity},
+        source::Position,
+    };
+
+    #[test]
+    fn compile_error_fix_its() {
+        let fixed_by = |source: &str| -> Vec<String> {
+            let error = match Map::load_udmf_textmap("MAP01".try_into().unwrap(), source) {
+                Err(LoadError::Compile(error)) => error,
+                other => panic!("Expected a compile error, got {other:?}"),
+            };
+
+            error
+                .fix_its()
+                .iter()
+                .map(|fix_it| fix_it.apply(source))
+                .collect()
+        };
+
+        assert_eq!(
+            fixed_by("vertex { x = \"16\"; y = 0; }"),
+            vec!["vertex { x = 16; y = 0; }", "vertex { x = 16.0; y = 0; }"]
+        );
+        assert_eq!(
+            fixed_by("sector { heightfloor = 40000; }"),
+            vec!["sector { heightfloor = 32767; }"]
+        );
+        assert_eq!(
+            fixed_by("vertex { x = 0; /* x */ x = 1; y = 0; }"),
+            vec![
+                "vertex { x = 0; /* x */  y = 0; }",
+                "vertex {  /* x */ x = 1; y = 0; }"
+            ]
+        );
+    }
+
+    #[test]
+    fn malformed_numbers() {
+        let source = "vertex { x = 1,5; y = 0; }";
+        let error = Map::load_udmf_textmap("MAP01".try_into().unwrap(), source).unwrap_err();
+
+        assert!(matches!(
+            &error,
+            LoadError::MalformedNumber { text, span, .. } if text == "1,5" && *span == (13..16)
+        ));
+        assert_eq!(
+            error.fix_its()[0].apply(source),
+            "vertex { x = 1.5; y = 0; }"
+        );
+
+        let report = DiagnosticReport::new(&error, &LineIndex::new(source));
+        assert_eq!(
+            report.code.as_deref(),
+            Some("waddle::udmf::malformed_number")
+        );
+        assert_eq!(report.severity, ReportSeverity::Error);
+        assert_eq!(report.message, "1,5 is not a valid number");
+
+        let located = error.locate(source);
+        assert_eq!(located.len(), 1);
+        assert_eq!(
+            located[0].positions,
+            Position {
+                line: 0,
+                column: 13
+            }..Position {
+                line: 0,
+                column: 16
+            }
+        );
+
+        let error = Map::load_udmf_textmap("MAP01".try_into().unwrap(), "thing { x = 1.2.3; }")
+            .unwrap_err();
+
+        assert!(matches!(
+            &error,
+            LoadError::MalformedNumber { text, suggestion: None, .. } if text == "1.2.3"
+        ));
+    }
+
+    #[test]
+    fn malformed_input_doesnt_panic() {
+        for input in [
+            &b"\xff\xfe"[..],
+            b"namespace = \"zdoom",
+            b"vertex { x = 1e999999; y = -0x; }",
+            b"linedef { v1 = 99999999999; } /* unterminated",
+            b"thing { x = 0; y = 0; type = 1; angle = 1,5e; } \"\\",
+            "sector { texturefloor = \"\u{e9}\u{e9}\u{e9}\u{e9}\u{e9}\u{e9}\u{e9}\u{e9}\u{e9}\"; }"
+                .as_bytes(),
+        ] {
+            parse::fuzz_translation_unit(input);
+        }
+    }
+
+    #[test]
+    fn duplicate_namespaces() {
+        let textmap = r#"
+            namespace = "zdoom";
+            vertex { x = 0; y = 0; }
+            vertex { x = 64; y = 0; }
+            sector { texturefloor = "F"; textureceiling = "C"; }
+            sidedef { sector = 0; }
+            linedef { v1 = 0; v2 = 1; sidefront = 0; special = 2; id = 5; }
+            namespace = "strife";
+        "#;
+        let special = |duplicate_namespace| {
+            let options = CompileOptions {
+                duplicate_namespace,
+                ..Default::default()
+            };
+            Map::load_udmf_textmap_with_options("MAP01".try_into().unwrap(), textmap, &options)
+                .map(|map| map.line_defs.values().next().unwrap().special.clone())
+        };
+
+        assert!(matches!(
+            special(DuplicateNamespace::Error),
+            Err(LoadError::Compile(error)) if matches!(*error, CompileError::MultipleAssignment { .. })
+        ));
+        // Special 2 is Polyobj_RotateLeft in ZDoom, and a door which opens and stays open in Doom
+        assert!(matches!(
+            special(DuplicateNamespace::FirstWins).unwrap(),
+            line_def::Special::PolyobjRotateLeft { .. }
+        ));
+        assert!(matches!(
+            special(DuplicateNamespace::LastWins).unwrap(),
+            line_def::Special::DoorOpen { tag: 5, .. }
+        ));
+    }
+
+    #[test]
+    fn unknown_blocks() {
+        let textmap = r#"
+            namespace = "zdoom";
+            vertex { x = 0; y = 0; }
+            EditorView { zoom = 1.5; author = "me"; }
+        "#;
+        let compile = |unknown_blocks| {
+            let options = CompileOptions {
+                unknown_blocks,
+                ..Default::default()
+            };
+            let translation_unit = ast::TranslationUnit::parse(textmap).unwrap();
+            compile_udmf_translation_unit(&translation_unit, "MAP01".try_into().unwrap(), &options)
+        };
+
+        assert!(matches!(
+            compile(UnknownBlocks::Error).map_err(|error| *error),
+            Err(CompileError::InvalidBlock { .. })
+        ));
+        assert!(compile(UnknownBlocks::Skip)
+            .unwrap()
+            .unknown_blocks
+            .is_empty());
+
+        let raw_map = compile(UnknownBlocks::Preserve).unwrap();
+        assert_eq!(
+            raw_map.unknown_blocks,
+            [UnknownBlock {
+                identifier: "EditorView".to_owned(),
+                assignments: vec![
+                    ("zoom".to_owned(), Value::Float(1.5)),
+                    ("author".to_owned(), Value::Str("me".to_owned())),
+                ],
+            }]
+        );
+
+        let written = raw_map.to_udmf("zdoom").unwrap();
+        assert!(written.contains("EditorView {"), "{written}");
+        let translation_unit = ast::TranslationUnit::parse(&written).unwrap();
+        let options = CompileOptions {
+            unknown_blocks: UnknownBlocks::Preserve,
+            ..Default::default()
+        };
+        assert_eq!(
+            compile_udmf_translation_unit(&translation_unit, raw_map.name.clone(), &options)
+                .unwrap(),
+            raw_map
+        );
+    }
+
+    #[test]
+    fn mixed_case_identifiers() {
+        let source = r#"
+            Namespace = "zdoom";
+            VERTEX { X = 0; y = 0; }
+            Vertex { x = 64; Y = 0; }
+            sector { HeightCeiling = 128; TextureFloor = "FLAT1"; textureceiling = "F_SKY1"; }
+            sidedef { Sector = 0; }
+            linedef { V1 = 0; v2 = 1; SideFront = 0; BLOCKING = true; }
+        "#;
+        let map = Map::load_udmf_textmap("MAP01".try_into().unwrap(), source).unwrap();
+        assert!(map.line_defs.values().next().unwrap().flags.impassable);
+
+        let error = match Map::load_udmf_textmap(
+            "MAP01".try_into().unwrap(),
+            "vertex { x = 0; X = 1; y = 0; }",
+        ) {
+            Err(LoadError::Compile(error)) => error,
+            result => panic!("Expected a compile error, got {result:?}"),
+        };
+        assert_eq!(error.to_string(), "X was assigned to multiple times");
+    }
+
+    #[test]
+    fn lenient_coercion() {
+        let source = r#"
+            namespace = "zdoom";
+            vertex { x = 0; y = 0; }
+            vertex { x = 64; y = 0; }
+            sector { heightfloor = 8.0; heightceiling = 128; texturefloor = "FLAT1"; textureceiling = "F_SKY1"; }
+            sidedef { sector = 0; }
+            linedef { v1 = 0; v2 = 1.0; sidefront = 0; blocking = 1; twosided = 0; }
+        "#;
+        let lenient = CompileOptions {
+            coercion: Coercion::Lenient,
+            ..Default::default()
+        };
+
+        let error = match Map::load_udmf_textmap("MAP01".try_into().unwrap(), source) {
+            Err(LoadError::Compile(error)) => error,
+            result => panic!("Expected a compile error, got {result:?}"),
+        };
+        assert!(matches!(*error, CompileError::InvalidAssignmentType { .. }));
+
+        let map =
+            Map::load_udmf_textmap_with_options("MAP01".try_into().unwrap(), source, &lenient)
+                .unwrap();
+        let line_def = map.line_defs.values().next().unwrap();
+        assert!(line_def.flags.impassable);
+        assert!(!line_def.flags.two_sided);
+        assert_eq!(map.sectors.values().next().unwrap().floor_height, Height(8));
+
+        let error = Map::load_udmf_textmap_with_options(
+            "MAP01".try_into().unwrap(),
+            &source.replace("blocking = 1", "blocking = 2"),
+            &lenient,
+        );
+        assert!(matches!(error, Err(LoadError::Compile(_))));
+    }
+
+    #[test]
+    fn special_arg_ranges() {
+        let source = r#"
+            namespace = "zdoom";
+            vertex { x = 0; y = 0; }
+            vertex { x = 64; y = 0; }
+            sector { texturefloor = "FLAT1"; textureceiling = "F_SKY1"; }
+            sidedef { sector = 0; }
+            linedef { v1 = 0; v2 = 1; sidefront = 0; special = 17; arg0 = 3; arg1 = 2; }
+        "#;
+
+        let error = match Map::load_udmf_textmap("MAP01".try_into().unwrap(), source) {
+            Err(LoadError::Compile(error)) => error,
+            result => panic!("Expected a compile error, got {result:?}"),
+        };
+        assert!(matches!(*error, CompileError::OutOfRange { value: 2, .. }));
+
+        let clamp = CompileOptions {
+            special_args: SpecialArgs::Clamp,
+            ..Default::default()
+        };
+        let map = Map::load_udmf_textmap_with_options("MAP01".try_into().unwrap(), source, &clamp)
+            .unwrap();
+        assert_eq!(
+            map.line_defs.values().next().unwrap().special,
+            line_def::Special::ThingRaise {
+                tid: 3,
+                nocheck: true
+            }
+        );
+    }
+
+    #[test]
+    fn uppercase_names() {
+        let source = r#"
+            sector { heightfloor = 0; heightceiling = 128; texturefloor = "flat1"; textureceiling = "F_SKY1"; }
+            sidedef { sector = 0; texturemiddle = "stone2"; }
+        "#;
+        let options = CompileOptions {
+            string8_case: String8Case::Uppercase,
+            ..Default::default()
+        };
+        let map =
+            Map::load_udmf_textmap_with_options("MAP01".try_into().unwrap(), source, &options)
+                .unwrap();
+
+        let side_def = map.side_defs.values().next().unwrap();
+        assert_eq!(side_def.middle_texture, String8::new_unchecked("STONE2"));
+        assert_eq!(side_def.upper_texture, String8::new_unchecked("-"));
+
+        let sector = map.sectors.values().next().unwrap();
+        assert_eq!(sector.floor_flat, String8::new_unchecked("FLAT1"));
+
+        let preserved = Map::load_udmf_textmap("MAP01".try_into().unwrap(), source).unwrap();
+        let texture = &preserved.side_defs.values().next().unwrap().middle_texture;
+        assert_eq!(texture, &String8::new_unchecked("stone2"));
+        assert!(texture.eq_ignore_case(&side_def.middle_texture));
+    }
+
+    #[test]
+    fn float_formats() {
+        let raw_map = RawMap {
+            name: "MAP01".try_into().unwrap(),
+            vertexes: vec![
+                Vertex {
+                    position: Point::new(Number::Float(0.1 + 0.2), Number::Float(64.0)),
+                    ..Default::default()
+                },
+                Vertex {
+                    position: Point::new(Number::Float(-0.0001), Number::Float(1.0 / 3.0)),
+                    ..Default::default()
+                },
+            ],
+            line_defs: Vec::new(),
+            sectors: Vec::new(),
+            side_defs: Vec::new(),
+            things: Vec::new(),
+            unknown_blocks: Vec::new(),
+        };
+
+        let coordinates = |options: &WriteOptions| -> Vec<String> {
+            raw_map
+                .to_udmf_with_options("zdoom", options)
+                .unwrap()
+                .lines()
+                .filter_map(|line| line.trim().strip_prefix(['x', 'y']))
+                .map(|value| value.trim_matches(['=', ';']).to_owned())
+                .collect()
+        };
+
+        assert_eq!(
+            coordinates(&WriteOptions::default()),
+            ["0.30000000000000004", "64", "-0.0001", "0.3333333333333333"]
+        );
+        assert_eq!(
+            coordinates(&WriteOptions {
+                float_format: FloatFormat::Decimals(3),
+                integral_floats_as_ints: false,
+                max_line_length: None,
+            }),
+            ["0.3", "64.0", "0.0", "0.333"]
+        );
+
+        let mut raw_map = raw_map;
+        raw_map.vertexes[1].position.y = Number::Float(f64::NAN);
+        assert!(matches!(
+            raw_map.to_udmf("zdoom"),
+            Err(WriteError::NonFiniteFloat { key, .. }) if key == "y"
+        ));
+
+        // Floats keep every bit of the 16.16 fixed point numbers ports store coordinates and scales in
+        let finest = -32767.0 - 65535.0 / 65536.0;
+        raw_map.vertexes[1].position.y = Number::Float(finest);
+        let textmap = raw_map.to_udmf("zdoom").unwrap();
+        let written = textmap
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix('y'))
+            .nth(1)
+            .unwrap();
+        assert_eq!(written.trim_matches(['=', ';']).parse(), Ok(finest));
+    }
+
+    #[test]
+    fn split_textmap() {
+        let textmap = r#"
+            namespace = "zdoom";
+            vertex { x = 0; y = 0; }
+            vertex { x = 64; y = 0; }
+            vertex { x = 64; y = 64; }
+            sector { texturefloor = "F_FLOOR1"; textureceiling = "F_CEIL1"; heightceiling = 128; }
+            sidedef { sector = 0; texturemiddle = "STARTAN2"; }
+            linedef { v1 = 0; v2 = 1; sidefront = 0; }
+            linedef { v1 = 1; v2 = 2; sidefront = 0; }
+            linedef { v1 = 2; v2 = 0; sidefront = 0; }
+        "#;
+        let map = Map::load_udmf_textmap("MAP01".try_into().unwrap(), textmap).unwrap();
+        let raw_map = map.unlink().unwrap();
+
+        let options = WriteOptions {
+            max_line_length: Some(20),
+            ..Default::default()
+        };
+        let fragments = raw_map.to_udmf_fragments("zdoom", &options, 160).unwrap();
+        assert!(fragments.len() > 1);
+        assert_eq!(
+            fragments.concat(),
+            raw_map.to_udmf_with_options("zdoom", &options).unwrap()
+        );
+        for fragment in &fragments {
+            assert!(fragment.len() <= 160);
+            assert!(fragment.lines().all(|line| line.len() <= 20), "{fragment}");
+        }
+
+        let mut fragments: Vec<_> = fragments
+            .iter()
+            .map(|fragment| format!("namespace=\"zdoom\";\n{fragment}"))
+            .collect();
+        let loaded =
+            Map::load_udmf_textmap_fragments("MAP01".try_into().unwrap(), &fragments).unwrap();
+        assert_eq!(loaded, map);
+
+        fragments.push("namespace=\"hexen\";".to_owned());
+        assert!(Map::load_udmf_textmap_fragments("MAP01".try_into().unwrap(), &fragments).is_err());
+    }
+
+    #[test]
+    fn write_lumps() {
+        let map =
+            Map::load_udmf_textmap("MAP07".try_into().unwrap(), include_str!("udmf_test.txt"))
+                .unwrap();
+        let mut wad = Wad::new(crate::wad::WadKind::Pwad);
+        map.write_udmf_lumps(
+            &mut wad,
+            &PassthroughLumps {
+                behavior: Some(b"ACS\0".to_vec()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let names: Vec<_> = wad.lumps.iter().map(|lump| lump.name.to_string()).collect();
+        assert_eq!(names, ["MAP07", "TEXTMAP", "BEHAVIOR", "ENDMAP"]);
+        assert_eq!(wad.maps().next(), Some(&wad.lumps[0]));
+
+        let textmap = std::str::from_utf8(&wad.lumps[1].data).unwrap();
+        let loaded = Map::load_udmf_textmap(map.name.clone(), textmap).unwrap();
+        assert_eq!(loaded.content_hash(), map.content_hash());
+    }
+
     #[test]
     fn udmf_parsing() {
         let s = include_str!("udmf_test.txt");
 
-        let result =
-            Map::load_udmf_textmap("foo".try_into().unwrap(), &mut Cursor::new(s)).unwrap();
-
-        let mut expected = Map::new("foo".try_into().unwrap());
-
-        let vertices: Vec<_> = [
-            Point::new(-96, 32),
-            Point::new(64, -64),
-            Point::new(128, 64),
-            Point::new(-64, 96),
-        ]
-        .iter()
-        .map(|&position| Rc::new(RefCell::new(Vertex { position })))
-        .collect();
-
-        let sidedefs = vec![
-            Rc::new(RefCell::new(SideDef {
-                upper_texture: String8::new_unchecked("-"),
-                middle_texture: String8::new_unchecked("STONE2"),
-                lower_texture: String8::new_unchecked("-"),
-                offset: Point::new(0, 0),
-            }));
-            4
-        ];
+        let result = Map::load_udmf_textmap("foo".try_into().unwrap(), s)
+            .unwrap()
+            .unlink()
+            .unwrap();
+
+        let side_def = RawSideDef {
+            sector_idx: SectorIdx::new(0),
+            upper_texture: String8::new_unchecked("-"),
+            middle_texture: String8::new_unchecked("STONE2"),
+            lower_texture: String8::new_unchecked("-"),
+            offset: Point::default(),
+        };
 
-        expected.linedefs.insert(LineDef {
-            from: vertices[1].clone(),
-            to: vertices[0].clone(),
-            left_side: sidedefs[0].clone(),
-            right_side: None,
+        let line_def = |from_idx, to_idx, left_side_idx| RawLineDef {
+            from_idx: VertexIdx::new(from_idx),
+            to_idx: VertexIdx::new(to_idx),
+            left_side_idx: SideDefIdx::new(left_side_idx),
+            right_side_idx: None,
+            id: line_def::NO_ID,
             special: line_def::Special::default(),
             flags: line_def::Flags {
                 impassable: true,
                 ..line_def::Flags::default()
             },
             trigger_flags: line_def::TriggerFlags::default(),
-        });
-        expected.linedefs.insert(LineDef {
-            from: vertices[2].clone(),
-            to: vertices[1].clone(),
-            left_side: sidedefs[3].clone(),
-            right_side: None,
-            special: line_def::Special::default(),
-            flags: line_def::Flags {
-                impassable: true,
-                ..line_def::Flags::default()
-            },
-            trigger_flags: line_def::TriggerFlags::default(),
-        });
-        expected.linedefs.insert(LineDef {
-            from: vertices[3].clone(),
-            to: vertices[2].clone(),
-            left_side: sidedefs[2].clone(),
-            right_side: None,
-            special: line_def::Special::default(),
-            flags: line_def::Flags {
-                impassable: true,
-                ..line_def::Flags::default()
-            },
-            trigger_flags: line_def::TriggerFlags::default(),
-        });
-        expected.linedefs.insert(LineDef {
-            from: vertices[0].clone(),
-            to: vertices[3].clone(),
-            left_side: sidedefs[1].clone(),
-            right_side: None,
-            special: line_def::Special::default(),
-            flags: line_def::Flags {
-                impassable: true,
-                ..line_def::Flags::default()
-            },
-            trigger_flags: line_def::TriggerFlags::default(),
-        });
+        };
 
-        expected.sectors.insert(Sector {
-            sides: sidedefs[0..4].iter().cloned().collect(),
-            floor_flat: String8::from_str_unchecked("MFLR8_1"),
-            ceiling_flat: String8::from_str_unchecked("MFLR8_1"),
-            ceiling_height: 128,
-            floor_height: 0,
-            light_level: 160,
-            special: sector::Special::default(),
-            tag: 0,
-        });
+        let expected = RawMap {
+            name: "foo".try_into().unwrap(),
+            vertexes: [(-96.0, 32.0), (64.0, -64.0), (128.0, 64.0), (-64.0, 96.0)]
+                .iter()
+                .map(|&(x, y)| Vertex {
+                    position: Point::new(Number::Float(x), Number::Float(y)),
+                    ..Default::default()
+                })
+                .collect(),
+            line_defs: vec![
+                line_def(1, 0, 0),
+                line_def(2, 1, 3),
+                line_def(3, 2, 2),
+                line_def(0, 3, 1),
+            ],
+            sectors: vec![Sector {
+                floor_flat: String8::new_unchecked("MFLR8_1"),
+                ceiling_flat: String8::new_unchecked("MFLR8_1"),
+                ceiling_height: Height(128),
+                floor_height: Height(0),
+                light_level: 160,
+                special: sector::Special::default(),
+                secret: false,
+                tag: 0,
+                kill_grounded_monsters: false,
+            }],
+            side_defs: vec![side_def; 4],
+            things: vec![],
+            unknown_blocks: Vec::new(),
+        };
 
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn udmf_selection_round_trip() {
+        let s = include_str!("udmf_test.txt");
+        let map = Map::load_udmf_textmap("foo".try_into().unwrap(), s).unwrap();
+
+        let mut selection = Selection::new();
+        selection
+            .line_defs
+            .insert(map.line_defs.keys().next().unwrap());
+
+        let mut out = Vec::new();
+        map.write_udmf_selection(&selection, &mut out).unwrap();
+
+        let fragment = Map::load_udmf_textmap(
+            "foo".try_into().unwrap(),
+            std::str::from_utf8(&out).unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(fragment.line_defs.len(), 1);
+        assert_eq!(fragment.vertexes.len(), 2);
+        assert_eq!(fragment.side_defs.len(), 1);
+        assert_eq!(fragment.sectors.len(), 1);
+        assert_eq!(fragment.things.len(), 0);
+    }
+
     #[test]
     fn udmf_linedef_specials() {
-        for value in i16::min_value()..=i16::max_value() {
+        for value in i16::MIN..=i16::MAX {
             for args_len in 0..5 {
                 let mut args = [0; 5];
 
-                for i in 0..args_len {
-                    args[i] = 1;
+                for arg in args.iter_mut().take(args_len) {
+                    *arg = 1;
                 }
 
                 let udmf_special = line_def::UdmfSpecial::new(value, args);
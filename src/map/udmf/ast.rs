@@ -1,8 +1,10 @@
 use std::ops::Range;
 
+use winnow::{stream::Location, Located};
+
 use crate::{
     map::{
-        udmf::{self, CompileError, Identifier, Value},
+        udmf::{self, parse, CompileError, Identifier, LoadError, Value},
         RawMap,
     },
     String8,
@@ -29,17 +31,46 @@ pub struct AssignmentExpr {
 #[derive(Clone, Debug)]
 pub struct Block {
     pub identifier: Spanned<Identifier>,
-    pub assignments: Vec<Spanned<AssignmentExpr>>,
+    /// The block's assignments, as a range of `TranslationUnit::assignments`
+    pub assignments: Range<usize>,
 }
 
-#[derive(Clone, Debug)]
+/// A block with its assignments, borrowed from a `TranslationUnit`
+#[derive(Clone, Copy, Debug)]
+pub struct BlockRef<'t> {
+    pub identifier: &'t Spanned<Identifier>,
+    pub assignments: &'t [Spanned<AssignmentExpr>],
+}
+
+#[derive(Clone, Debug, Default)]
 pub struct TranslationUnit {
     pub expressions: Vec<GlobalExpr>,
+    /// The assignments of every block, stored together so that parsing doesn't allocate once per block
+    pub assignments: Vec<Spanned<AssignmentExpr>>,
 }
 
 impl TranslationUnit {
+    pub fn block<'t>(&'t self, block: &'t Block) -> BlockRef<'t> {
+        BlockRef {
+            identifier: &block.identifier,
+            assignments: &self.assignments[block.assignments.clone()],
+        }
+    }
+
+    pub fn parse(contents: &str) -> Result<Self, LoadError> {
+        let mut input = Located::new(contents);
+        parse::parse_translation_unit(&mut input).map_err(|e| {
+            LoadError::from_parse_error(
+                e.into_inner().expect("Incomplete parse error not expected"),
+                contents,
+                input.location(),
+            )
+        })
+    }
+
+    #[cfg_attr(not(feature = "unstable"), allow(dead_code))]
     pub fn compile(&self, name: String8) -> Result<RawMap, Box<CompileError>> {
-        udmf::compile_udmf_translation_unit(self, name)
+        udmf::compile_udmf_translation_unit(self, name, &udmf::CompileOptions::default())
     }
 }
 
@@ -1,3 +1,5 @@
+use crate::map::udmf::Symbol;
+
 macro_rules! assignments {
     ($($name:ident => $key:literal),* $(,)?) => {
         pub mod assignments {
@@ -6,6 +8,14 @@ macro_rules! assignments {
             pub const ALL: &[&str] = &[
                 $($name,)*
             ];
+
+            /// The interned assignment names, for matching identifiers
+            #[allow(dead_code)] // Not every assignment is compiled yet
+            pub mod symbols {
+                use crate::map::udmf::Symbol;
+
+                $(pub const $name: Symbol = Symbol::known(super::$name);)*
+            }
         }
     };
 }
@@ -25,22 +35,31 @@ pub mod global {
 }
 
 pub mod vertex {
+    use super::Symbol;
+
     pub const BLOCK: &str = "vertex";
+    pub const BLOCK_SYMBOL: Symbol = Symbol::known(BLOCK);
 
     assignments! {
         X => "x",
         Y => "y",
+        FLOOR_Z => "zfloor",
+        CEILING_Z => "zceiling",
     }
 }
 
 pub mod line_def {
+    use super::Symbol;
+
     pub const BLOCK: &str = "linedef";
+    pub const BLOCK_SYMBOL: Symbol = Symbol::known(BLOCK);
 
     assignments! {
         FROM_IDX => "v1",
         TO_IDX => "v2",
         LEFT_SIDE_IDX => "sidefront",
         RIGHT_SIDE_IDX => "sideback",
+        ID => "id",
         IMPASSABLE => "blocking",
         BLOCKS_MONSTERS => "blockmonsters",
         TWO_SIDED => "twosided",
@@ -50,6 +69,11 @@ pub mod line_def {
         BLOCKS_SOUND => "blocksound",
         NOT_ON_MAP => "dontdraw",
         ALREADY_ON_MAP => "mapped",
+        BLOCKS_LAND_MONSTERS => "blocklandmonsters",
+        BLOCKS_PLAYERS => "blockplayers",
+        JUMP_OVER => "jumpover",
+        BLOCKS_FLOATERS => "blockfloaters",
+        TRANSLUCENT => "translucent",
         SPECIAL => "special",
         ARG0 => "arg0",
         ARG1 => "arg1",
@@ -70,7 +94,10 @@ pub mod line_def {
 }
 
 pub mod side_def {
+    use super::Symbol;
+
     pub const BLOCK: &str = "sidedef";
+    pub const BLOCK_SYMBOL: Symbol = Symbol::known(BLOCK);
 
     assignments! {
         OFFSET_X => "offsetx",
@@ -85,7 +112,10 @@ pub mod side_def {
 }
 
 pub mod sector {
+    use super::Symbol;
+
     pub const BLOCK: &str = "sector";
+    pub const BLOCK_SYMBOL: Symbol = Symbol::known(BLOCK);
 
     assignments! {
         FLOOR_HEIGHT => "heightfloor",
@@ -95,13 +125,17 @@ pub mod sector {
         LIGHT_LEVEL => "lightlevel",
         TAG => "id",
         SPECIAL => "special", // TODO: Double-check
+        SECRET => "secret",
     }
 
     pub const DEFAULT_LIGHT_LEVEL: u8 = 160;
 }
 
 pub mod thing {
+    use super::Symbol;
+
     pub const BLOCK: &str = "thing";
+    pub const BLOCK_SYMBOL: Symbol = Symbol::known(BLOCK);
 
     assignments! {
         X => "x",
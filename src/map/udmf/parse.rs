@@ -1,16 +1,47 @@
 use winnow::{
     ascii::{dec_int, dec_uint, escaped_transform, float, hex_uint, Caseless},
     combinator::{alt, cut_err, delimited, eof, preceded, repeat, repeat_till0, rest, terminated},
+    error::{AddContext, ContextError, ErrMode, StrContext},
+    stream::Stream,
     token::{one_of, take_till, take_while},
     Located, PResult, Parser,
 };
 
-use crate::map::udmf::{ast, Identifier, Value};
+use crate::{
+    map::{
+        udmf::{ast, Identifier, Value},
+        Map,
+    },
+    String8,
+};
+
+/// Load arbitrary bytes as a TEXTMAP and report the outcome the way a tool accepting uploads would, for fuzzing.
+///
+/// Errors are expected, but nothing here should panic, whatever the input.
+#[cfg_attr(not(feature = "unstable"), allow(dead_code))]
+pub fn fuzz_translation_unit(data: &[u8]) {
+    let Ok(contents) = std::str::from_utf8(data) else {
+        return;
+    };
+
+    match Map::load_udmf_textmap(String8::new_unchecked("FUZZ"), contents) {
+        Ok(map) => {
+            let _ = map.write_udmf_textmap(&mut Vec::new());
+        }
+        Err(error) => {
+            let _ = error.to_string();
+            let _ = error.locate(contents);
+            let _ = error.fix_its();
+        }
+    }
+}
 
 pub fn parse_translation_unit(input: &mut Located<&str>) -> PResult<ast::TranslationUnit> {
+    let mut assignments = Vec::new();
+
     let (expressions, _) = repeat_till0(
         alt((
-            parse_block
+            (|input: &mut Located<&str>| parse_block(input, &mut assignments))
                 .with_span()
                 .map(ast::Spanned::wrap)
                 .map(ast::GlobalExpr::Block),
@@ -23,10 +54,17 @@ pub fn parse_translation_unit(input: &mut Located<&str>) -> PResult<ast::Transla
     )
     .parse_next(input)?;
 
-    Ok(ast::TranslationUnit { expressions })
+    Ok(ast::TranslationUnit {
+        expressions,
+        assignments,
+    })
 }
 
-fn parse_block(input: &mut Located<&str>) -> PResult<ast::Block> {
+/// Parse a block, pushing its assignments onto the translation unit's `assignments`
+fn parse_block(
+    input: &mut Located<&str>,
+    assignments: &mut Vec<ast::Spanned<ast::AssignmentExpr>>,
+) -> PResult<ast::Block> {
     let _wc = parse_whitespace_and_comments.parse_next(input)?;
     let identifier = parse_identifier
         .with_span()
@@ -36,31 +74,74 @@ fn parse_block(input: &mut Located<&str>) -> PResult<ast::Block> {
     let _wc = parse_whitespace_and_comments.parse_next(input)?;
     let _brace = '{'.parse_next(input)?;
 
-    let assignments = repeat(
-        0..,
-        parse_assignment_expr.with_span().map(ast::Spanned::wrap),
-    )
-    .parse_next(input)?;
-
-    let _wc = parse_whitespace_and_comments.parse_next(input)?;
-    let _brace = '}'.parse_next(input)?;
+    let start = assignments.len();
+    let result = (|| {
+        loop {
+            let checkpoint = input.checkpoint();
+            match parse_assignment_expr.with_span().parse_next(input) {
+                Ok(assignment) => assignments.push(ast::Spanned::wrap(assignment)),
+                Err(ErrMode::Backtrack(_)) => {
+                    input.reset(checkpoint);
+                    break;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        let _wc = parse_whitespace_and_comments.parse_next(input)?;
+        '}'.parse_next(input)
+    })();
+
+    // Don't leave the assignments of a block which failed to parse behind
+    if let Err(e) = result {
+        assignments.truncate(start);
+        return Err(e);
+    }
 
     Ok(ast::Block {
         identifier,
-        assignments,
+        assignments: start..assignments.len(),
     })
 }
 
+/// The context attached to the error for a token which looks like a number but isn't a valid one
+pub const MALFORMED_NUMBER: StrContext = StrContext::Label("malformed number");
+
+/// Characters which can make up a numeric token, including ones which are only valid in some positions, so that
+/// malformed numbers like `1,5` are recognized as a single token instead of a number followed by garbage
+pub fn is_numeric_token_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '.' | ',' | '_' | '+' | '-')
+}
+
 fn parse_value(input: &mut Located<&str>) -> PResult<Value> {
     alt((
-        parse_integer.map(Value::Int),
-        parse_float.map(Value::Float),
+        parse_number,
         parse_quoted_string.map(Value::Str),
         parse_bool.map(Value::Bool),
     ))
     .parse_next(input)
 }
 
+/// Parse an integer or float, failing without backtracking if the whole token isn't a valid number.
+///
+/// On failure, the input is left at the start of the malformed token.
+fn parse_number(input: &mut Located<&str>) -> PResult<Value> {
+    let start = input.checkpoint();
+    let token = take_while(1.., is_numeric_token_char)
+        .verify(|token: &str| token.starts_with(|c: char| c.is_ascii_digit() || "+-.".contains(c)))
+        .parse_next(input)?;
+
+    alt((
+        terminated(parse_integer, eof).map(Value::Int),
+        terminated(parse_float, eof).map(Value::Float),
+    ))
+    .parse_next(&mut Located::new(token))
+    .map_err(|_| {
+        input.reset(start);
+        ErrMode::Cut(ContextError::new().add_context(input, MALFORMED_NUMBER))
+    })
+}
+
 fn parse_assignment_expr(input: &mut Located<&str>) -> PResult<ast::AssignmentExpr> {
     let _wc = parse_whitespace_and_comments.parse_next(input)?;
     let identifier = parse_identifier
@@ -85,9 +166,9 @@ fn parse_assignment_expr(input: &mut Located<&str>) -> PResult<ast::AssignmentEx
 
 fn parse_integer(input: &mut Located<&str>) -> PResult<i32> {
     alt((
+        preceded("0x", hex_uint.try_map(|n: u32| i32::try_from(n))),
         dec_int,
         dec_uint.try_map(|n: u32| i32::try_from(n)),
-        preceded("0x", hex_uint.try_map(|n: u32| i32::try_from(n))),
     ))
     .parse_next(input)
 }
@@ -101,7 +182,7 @@ fn parse_quoted_string(input: &mut Located<&str>) -> PResult<String> {
         '"',
         cut_err(terminated(
             escaped_transform(
-                take_till(0.., &['"', '\\']),
+                take_till(1.., &['"', '\\']),
                 '\\',
                 alt(("\\".value("\\"), "\"".value("\""), "n".value("\n"))),
             ),
@@ -121,7 +202,7 @@ fn parse_identifier(input: &mut Located<&str>) -> PResult<Identifier> {
         take_while(0.., ('a'..='z', 'A'..='Z', '0'..='9', '_')),
     )
         .recognize()
-        .map(|s| Identifier(String::from(s)))
+        .map(Identifier::new)
         .parse_next(input)
 }
 
@@ -131,7 +212,7 @@ fn parse_whitespace_and_comments<'s>(input: &mut Located<&'s str>) -> PResult<&'
         alt((
             parse_line_comment,
             parse_block_comment,
-            take_till(1.., |c: char| c.is_whitespace()),
+            take_while(1.., |c: char| c.is_whitespace()),
         )),
     )
     .recognize()
@@ -0,0 +1,186 @@
+//! Interned UDMF identifiers. A TEXTMAP repeats a few dozen distinct keys millions of times, so they're stored as
+//! small copyable symbols and compared as integers.
+//!
+//! Only the identifiers the compiler knows about have symbols of their own. Interning anything else would let a
+//! TEXTMAP grow a global table without bound, so every other identifier shares `Symbol::OTHER`, and is kept as a
+//! string by whatever holds it.
+
+use std::{collections::HashMap, sync::LazyLock};
+
+/// Every identifier the compiler knows about, which are interned ahead of time so that their symbols are constants
+const KNOWN: &[&str] = &[
+    "namespace",
+    "vertex",
+    "x",
+    "y",
+    "zfloor",
+    "zceiling",
+    "linedef",
+    "v1",
+    "v2",
+    "sidefront",
+    "sideback",
+    "id",
+    "blocking",
+    "blockmonsters",
+    "twosided",
+    "dontpegtop",
+    "dontpegbottom",
+    "secret",
+    "blocksound",
+    "dontdraw",
+    "mapped",
+    "blocklandmonsters",
+    "blockplayers",
+    "jumpover",
+    "blockfloaters",
+    "special",
+    "arg0",
+    "arg1",
+    "arg2",
+    "arg3",
+    "arg4",
+    "playercross",
+    "playeruse",
+    "monstercross",
+    "monsteruse",
+    "impact",
+    "playerpush",
+    "monsterpush",
+    "missilecross",
+    "repeatspecial",
+    "monsteractivate",
+    "sidedef",
+    "offsetx",
+    "offsety",
+    "sector",
+    "texturetop",
+    "texturemiddle",
+    "texturebottom",
+    "heightfloor",
+    "heightceiling",
+    "texturefloor",
+    "textureceiling",
+    "lightlevel",
+    "thing",
+    "height",
+    "angle",
+    "type",
+    "skill1",
+    "skill2",
+    "skill3",
+    "skill4",
+    "skill5",
+    "ambush",
+    "single",
+    "dm",
+    "coop",
+    "friend",
+    "class1",
+    "class2",
+    "class3",
+    "dormant",
+    "invisible",
+    "standing",
+    "translucent",
+    "strifeally",
+];
+
+/// At least as long as the longest known identifier
+const MAX_KNOWN_LEN: usize = 32;
+
+static KNOWN_SYMBOLS: LazyLock<HashMap<&'static str, Symbol>> = LazyLock::new(|| {
+    KNOWN
+        .iter()
+        .enumerate()
+        .map(|(i, name)| (*name, Symbol(i as u32)))
+        .collect()
+});
+
+/// An interned identifier
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Symbol(u32);
+
+impl Symbol {
+    /// The symbol shared by every identifier the compiler doesn't know about
+    pub const OTHER: Symbol = Symbol(u32::MAX);
+
+    /// The symbol of an identifier the compiler knows about, failing to compile if it isn't one
+    pub const fn known(name: &str) -> Self {
+        let mut i = 0;
+        while i < KNOWN.len() {
+            if const_str_eq(KNOWN[i], name) {
+                return Self(i as u32);
+            }
+            i += 1;
+        }
+
+        panic!("not a known UDMF identifier")
+    }
+
+    /// The symbol of any identifier, which is `OTHER` if the compiler doesn't know about it
+    pub fn lookup(name: &str) -> Self {
+        KNOWN_SYMBOLS.get(name).copied().unwrap_or(Symbol::OTHER)
+    }
+
+    /// The symbol of any identifier, ignoring its case, without allocating a lowercase copy of it
+    pub fn lookup_ignore_case(name: &str) -> Self {
+        if !name.bytes().any(|b| b.is_ascii_uppercase()) {
+            return Self::lookup(name);
+        }
+
+        // Names longer than every known identifier can't be one
+        let mut buffer = [0; MAX_KNOWN_LEN];
+        let Some(lowercase) = buffer.get_mut(..name.len()) else {
+            return Symbol::OTHER;
+        };
+        lowercase.copy_from_slice(name.as_bytes());
+        lowercase.make_ascii_lowercase();
+
+        // Lowercasing ASCII letters keeps the bytes valid UTF-8
+        std::str::from_utf8(lowercase).map_or(Symbol::OTHER, Self::lookup)
+    }
+
+    /// The identifier of a known symbol, or `None` for `OTHER`
+    pub fn as_str(self) -> Option<&'static str> {
+        KNOWN.get(self.0 as usize).copied()
+    }
+}
+
+const fn const_str_eq(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interning() {
+        assert_eq!(Symbol::lookup("linedef"), Symbol::known("linedef"));
+        assert_eq!(Symbol::known("sidefront").as_str(), Some("sidefront"));
+
+        assert_eq!(Symbol::lookup("user_custom_key"), Symbol::OTHER);
+        assert_eq!(Symbol::OTHER.as_str(), None);
+
+        assert!(KNOWN.iter().all(|name| name.len() <= MAX_KNOWN_LEN));
+        assert_eq!(
+            Symbol::lookup_ignore_case("SideFront"),
+            Symbol::known("sidefront")
+        );
+        assert_eq!(Symbol::lookup_ignore_case("User_Custom_Key"), Symbol::OTHER);
+    }
+}
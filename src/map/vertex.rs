@@ -3,8 +3,17 @@ use slotmap::SlotMap;
 use crate::Point;
 
 #[derive(Default, PartialEq, Debug, PartialOrd, Clone, Copy)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
 pub struct Vertex {
     pub position: Point,
+    /// The height of the floor at this vertex, from UDMF's `zfloor`. Only used in sectors with exactly three
+    /// vertexes, whose floor is sloped to pass through them.
+    pub floor_z: Option<f64>,
+    /// The height of the ceiling at this vertex, from UDMF's `zceiling`, as for `floor_z`
+    pub ceiling_z: Option<f64>,
 }
 
 slotmap::new_key_type! { pub struct VertexKey; }
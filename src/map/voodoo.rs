@@ -0,0 +1,389 @@
+//! Voodoo dolls are the extra copies of a player spawned by duplicate player starts. Anything that happens to a doll
+//! happens to the player, and dolls trigger walk-over lines like the player does, so Boom-era maps put them on
+//! conveyors which carry them over a sequence of lines: a script, with the conveyor speed as its clock.
+
+use std::collections::{BTreeMap, HashSet};
+
+use crate::{
+    map::{
+        geometry::{side_of_point, Side},
+        line_def::{LineDefKey, Special},
+        sector::SectorKey,
+        thing::ThingKey,
+        thing_type::{ThingKind, ThingTypes},
+        Map,
+    },
+    Point,
+};
+
+/// The highest step a doll can be carried up, as for the player
+const MAX_STEP: i32 = 24;
+
+/// How a voodoo doll's trip along its conveyors ends
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TripEnd {
+    /// The doll reaches a sector which isn't a conveyor and stops there
+    Stopped(SectorKey),
+    /// The doll is stopped by a wall, an impassable line or a step too high to climb
+    Blocked(LineDefKey),
+    /// The doll is carried around the same conveyors forever
+    Loop,
+    /// The doll starts outside the map, or is carried out of it through a gap in its lines
+    OutsideMap,
+}
+
+/// A voodoo doll and the path its conveyors carry it along, as found by [Map::voodoo_dolls]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VoodooDoll {
+    pub thing: ThingKey,
+    /// The conveyor sectors the doll passes through, in order
+    pub conveyors: Vec<SectorKey>,
+    /// The lines with walk-over specials the doll crosses, in order
+    pub triggers: Vec<LineDefKey>,
+    pub end: TripEnd,
+}
+
+impl VoodooDoll {
+    /// A one-line description of the sequence the doll runs, e.g. "carried through 2 conveyors, crossing
+    /// Door_Open then Teleport, until it stops"
+    pub fn summary(&self, map: &Map) -> String {
+        let mut summary = format!("carried through {} conveyors", self.conveyors.len());
+
+        if !self.triggers.is_empty() {
+            let specials: Vec<&str> = self
+                .triggers
+                .iter()
+                .map(|&line_def| map.line_defs[line_def].special.name())
+                .collect();
+            summary += &format!(", crossing {}", specials.join(" then "));
+        }
+
+        summary += match self.end {
+            TripEnd::Stopped(_) => ", until it stops",
+            TripEnd::Blocked(_) => ", until it's blocked",
+            TripEnd::Loop => ", in a loop",
+            TripEnd::OutsideMap => ", until it leaves the map",
+        };
+
+        summary
+    }
+}
+
+impl Map {
+    /// The sectors whose floors carry things, with the direction and speed they carry them at in map units per tic.
+    ///
+    /// Only `Special::ScrollFloor` lines which carry things are considered. When their speed args are both 0, as for
+    /// Boom conveyors converted from Doom format, things are carried along the control line, at 1/32 of its length.
+    pub fn conveyors(&self) -> BTreeMap<SectorKey, Point<f64>> {
+        let graph = self.tag_graph();
+        let mut conveyors = BTreeMap::new();
+
+        for reference in graph.references.iter() {
+            let line_def = &self.line_defs[reference.line_def];
+            let Special::ScrollFloor {
+                _type,
+                x_move,
+                y_move,
+                ..
+            } = line_def.special
+            else {
+                continue;
+            };
+
+            // Type 0 only scrolls the flat
+            if _type == 0 {
+                continue;
+            }
+
+            let velocity = if x_move == 0 && y_move == 0 {
+                let Some((from, to)) = self.line_segment(line_def) else {
+                    continue;
+                };
+                Point::new((to.x - from.x) / 32.0, (to.y - from.y) / 32.0)
+            } else {
+                Point::new(f64::from(x_move), f64::from(y_move))
+            };
+
+            for &sector in graph.sectors_for(reference) {
+                conveyors.insert(sector, velocity);
+            }
+        }
+
+        conveyors
+    }
+
+    /// Find the voodoo dolls, i.e. every player 1 start but the last, and trace where conveyors carry them.
+    ///
+    /// The trace is a straight-line approximation: dolls travel in the direction of each conveyor they're on, and
+    /// momentum, friction and the doll's radius are ignored.
+    pub fn voodoo_dolls(&self, thing_types: &ThingTypes) -> Vec<VoodooDoll> {
+        let mut starts: Vec<ThingKey> = self
+            .things
+            .iter()
+            .filter(|(_, thing)| thing.kind(thing_types) == Some(ThingKind::Player1Start))
+            .map(|(key, _)| key)
+            .collect();
+        starts.pop();
+
+        let conveyors = self.conveyors();
+        starts
+            .into_iter()
+            .map(|thing| self.trace_doll(thing, &conveyors))
+            .collect()
+    }
+
+    fn trace_doll(
+        &self,
+        thing: ThingKey,
+        conveyors: &BTreeMap<SectorKey, Point<f64>>,
+    ) -> VoodooDoll {
+        let position = self.things[thing].position;
+        let mut position = Point::new(position.x.into_float(), position.y.into_float());
+        let mut doll = VoodooDoll {
+            thing,
+            conveyors: Vec::new(),
+            triggers: Vec::new(),
+            end: TripEnd::OutsideMap,
+        };
+
+        let Some(mut sector) = self.sector_at(position) else {
+            return doll;
+        };
+        let mut last_line = None;
+        let mut crossed = HashSet::new();
+
+        loop {
+            let Some(&velocity) = conveyors.get(&sector) else {
+                doll.end = TripEnd::Stopped(sector);
+                break;
+            };
+            // Trigger lines are often inside a conveyor, with the same sector on both sides
+            if doll.conveyors.last() != Some(&sector) {
+                doll.conveyors.push(sector);
+            }
+
+            let Some((key, hit)) = self.nearest_line_along(position, velocity, last_line) else {
+                doll.end = TripEnd::OutsideMap;
+                break;
+            };
+
+            // Crossing the same line into the same conveyor twice means the path repeats from here
+            if !crossed.insert((key, sector)) {
+                doll.end = TripEnd::Loop;
+                break;
+            }
+
+            let line_def = &self.line_defs[key];
+            let (from, to) = self.line_segment(line_def).unwrap();
+            let next = match side_of_point(from, to, position) {
+                Side::Front => self.sector_on_side(line_def, Side::Back),
+                Side::Back => self.sector_on_side(line_def, Side::Front),
+            };
+            let blocked = next.is_none_or(|next| {
+                let step = self.sectors[next].floor_height - self.sectors[sector].floor_height;
+                line_def.flags.impassable || step > MAX_STEP
+            });
+            if blocked {
+                doll.end = TripEnd::Blocked(key);
+                break;
+            }
+
+            if line_def.trigger_flags.player_cross && line_def.special != Special::None {
+                doll.triggers.push(key);
+            }
+
+            position = hit;
+            last_line = Some(key);
+            sector = next.unwrap();
+        }
+
+        doll
+    }
+
+    /// The nearest line hit by a ray from `origin` in `direction`, other than `skip`, and where it's hit
+    fn nearest_line_along(
+        &self,
+        origin: Point<f64>,
+        direction: Point<f64>,
+        skip: Option<LineDefKey>,
+    ) -> Option<(LineDefKey, Point<f64>)> {
+        let cross = |a: Point<f64>, b: Point<f64>| a.x * b.y - a.y * b.x;
+        let mut nearest: Option<(LineDefKey, f64)> = None;
+
+        for (key, line_def) in self.line_defs.iter() {
+            let Some((from, to)) = self.line_segment(line_def).filter(|_| Some(key) != skip) else {
+                continue;
+            };
+
+            let edge = Point::new(to.x - from.x, to.y - from.y);
+            let offset = Point::new(from.x - origin.x, from.y - origin.y);
+            let denominator = cross(direction, edge);
+            if denominator == 0.0 {
+                continue;
+            }
+
+            let distance = cross(offset, edge) / denominator;
+            let along_edge = cross(offset, direction) / denominator;
+            if distance > 1e-9
+                && (0.0..=1.0).contains(&along_edge)
+                && nearest.is_none_or(|(_, nearest)| distance < nearest)
+            {
+                nearest = Some((key, distance));
+            }
+        }
+
+        nearest.map(|(key, distance)| {
+            let hit = Point::new(
+                origin.x + direction.x * distance,
+                origin.y + direction.y * distance,
+            );
+            (key, hit)
+        })
+    }
+}
+
+/// A room `width` wide with a closet as wide east of it, both 128 high, for tests. Returns the room and closet
+/// sectors, and the lines: the room's west, north and south walls, the two-sided line between the room and the
+/// closet, then the closet's north, east and south walls.
+#[cfg(test)]
+pub(crate) fn room_and_closet(map: &mut Map, width: i32) -> ([SectorKey; 2], Vec<LineDefKey>) {
+    use crate::{
+        map::{line_def::NO_ID, LineDef, Sector, SideDef, Vertex},
+        number::Number,
+        units::Height,
+    };
+
+    let sectors = [(); 2].map(|_| {
+        map.sectors.insert(Sector {
+            ceiling_height: Height(128),
+            ..Default::default()
+        })
+    });
+    let vertexes = [
+        (0, 0),
+        (0, 128),
+        (width, 128),
+        (width, 0),
+        (2 * width, 128),
+        (2 * width, 0),
+    ]
+    .map(|(x, y)| {
+        map.vertexes.insert(Vertex {
+            position: Point::new(Number::Int(x), Number::Int(y)),
+            ..Default::default()
+        })
+    });
+
+    let lines = [
+        (0, 1, 0, None),
+        (1, 2, 0, None),
+        (3, 0, 0, None),
+        (2, 3, 0, Some(1)),
+        (2, 4, 1, None),
+        (4, 5, 1, None),
+        (5, 3, 1, None),
+    ]
+    .into_iter()
+    .map(
+        |(from, to, front, back): (usize, usize, usize, Option<usize>)| {
+            let mut side = |sector: usize| {
+                map.side_defs.insert(SideDef {
+                    sector: sectors[sector],
+                    offset: Point::default(),
+                    upper_texture: Default::default(),
+                    middle_texture: Default::default(),
+                    lower_texture: Default::default(),
+                })
+            };
+            let left_side = side(front);
+            let right_side = back.map(side);
+
+            map.line_defs.insert(LineDef {
+                from: vertexes[from],
+                to: vertexes[to],
+                left_side,
+                right_side,
+                id: NO_ID,
+                flags: Default::default(),
+                special: Default::default(),
+                trigger_flags: Default::default(),
+            })
+        },
+    )
+    .collect();
+
+    (sectors, lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        map::{thing, thing_type::Game, Thing},
+        number::Number,
+        units::{Angle, Height},
+    };
+
+    #[test]
+    fn trace_conveyor() {
+        let mut map = Map::new("MAP01".try_into().unwrap());
+
+        // A conveyor room and a closet east of it, separated by a walk-over line
+        let (sectors, lines) = room_and_closet(&mut map, 256);
+        map.sectors[sectors[0]].tag = 1;
+        let (control, trigger) = (lines[1], lines[3]);
+
+        map.line_defs[control].special = Special::ScrollFloor {
+            tag: 1,
+            scrollbits: 0,
+            _type: 2,
+            x_move: 0,
+            y_move: 0,
+        };
+        map.line_defs[trigger].special = Special::DoorOpen {
+            tag: 2,
+            speed: 16,
+            light_tag: 0,
+        };
+        map.line_defs[trigger].trigger_flags.player_cross = true;
+
+        let thing_types = ThingTypes::for_game(Game::Doom2);
+        let mut add_start = |x| {
+            map.things.insert(Thing {
+                position: Point::new(Number::Int(x), Number::Int(64)),
+                height: Height(0),
+                angle: Angle(0),
+                type_: 1,
+                flags: thing::Flags::default(),
+                special: thing::Special::None,
+            })
+        };
+        let doll = add_start(64);
+        add_start(448);
+
+        assert_eq!(map.conveyors()[&sectors[0]], Point::new(8.0, 0.0));
+
+        let dolls = map.voodoo_dolls(&thing_types);
+        assert_eq!(
+            dolls,
+            vec![VoodooDoll {
+                thing: doll,
+                conveyors: vec![sectors[0]],
+                triggers: vec![trigger],
+                end: TripEnd::Stopped(sectors[1]),
+            }]
+        );
+        assert_eq!(
+            dolls[0].summary(&map),
+            "carried through 1 conveyors, crossing Door_Open, until it stops"
+        );
+
+        // Raising the closet floor out of reach blocks the doll at the trigger line
+        map.sectors[sectors[1]].floor_height = Height(32);
+        assert_eq!(
+            map.voodoo_dolls(&thing_types)[0].end,
+            TripEnd::Blocked(trigger)
+        );
+    }
+}
@@ -1,4 +1,7 @@
-use std::fmt::{self, Display, Formatter};
+use std::{
+    fmt::{self, Display, Formatter},
+    ops::{Add, Mul, Neg, Sub},
+};
 
 /// The various Doom specifications are sometimes inconsistent about the representations of numbers.
 /// For example, VERTEXES in the original WAD format are 2-byte integers, but in UDMF they're floats (although in practice integers work too).
@@ -38,6 +41,17 @@ impl Number {
         }
     }
 
+    /// The number as an integer, if it's a whole number in range
+    pub fn to_exact_int(self) -> Option<i32> {
+        match self {
+            Self::Int(i) => Some(i),
+            Self::Float(f) => {
+                let i = f as i32;
+                (f64::from(i) == f).then_some(i)
+            }
+        }
+    }
+
     pub fn is_zero(self) -> bool {
         match self {
             Number::Int(i) => i == 0,
@@ -64,6 +78,89 @@ impl From<f64> for Number {
     }
 }
 
+impl From<Number> for f64 {
+    fn from(n: Number) -> Self {
+        n.into_float()
+    }
+}
+
+/// Serialized as a plain integer or float
+#[cfg(feature = "serde")]
+impl serde::Serialize for Number {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Number::Int(i) => serializer.serialize_i32(*i),
+            Number::Float(f) => serializer.serialize_f64(*f),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Number {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct NumberVisitor;
+
+        impl serde::de::Visitor<'_> for NumberVisitor {
+            type Value = Number;
+
+            fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+                formatter.write_str("a number")
+            }
+
+            fn visit_i64<E: serde::de::Error>(self, v: i64) -> Result<Number, E> {
+                Ok(i32::try_from(v).map_or(Number::Float(v as f64), Number::Int))
+            }
+
+            fn visit_u64<E: serde::de::Error>(self, v: u64) -> Result<Number, E> {
+                Ok(i32::try_from(v).map_or(Number::Float(v as f64), Number::Int))
+            }
+
+            fn visit_f64<E: serde::de::Error>(self, v: f64) -> Result<Number, E> {
+                Ok(Number::Float(v))
+            }
+        }
+
+        deserializer.deserialize_any(NumberVisitor)
+    }
+}
+
+/// Arithmetic on two integers stays integral unless it overflows, otherwise the result is a float
+macro_rules! number_op {
+    ($trait:ident, $method:ident, $checked:ident) => {
+        impl $trait for Number {
+            type Output = Number;
+
+            fn $method(self, other: Number) -> Number {
+                match (self, other) {
+                    (Number::Int(a), Number::Int(b)) => a
+                        .$checked(b)
+                        .map(Number::Int)
+                        .unwrap_or_else(|| Number::Float(f64::from(a).$method(f64::from(b)))),
+                    _ => Number::Float(self.into_float().$method(other.into_float())),
+                }
+            }
+        }
+    };
+}
+
+number_op!(Add, add, checked_add);
+number_op!(Sub, sub, checked_sub);
+number_op!(Mul, mul, checked_mul);
+
+impl Neg for Number {
+    type Output = Number;
+
+    fn neg(self) -> Number {
+        match self {
+            Number::Int(i) => i
+                .checked_neg()
+                .map(Number::Int)
+                .unwrap_or(Number::Float(-f64::from(i))),
+            Number::Float(f) => Number::Float(-f),
+        }
+    }
+}
+
 impl Display for Number {
     fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
         match self {
@@ -72,3 +169,116 @@ impl Display for Number {
         }
     }
 }
+
+/// A 16.16 fixed point number, as used by the Doom engine for positions and by ZDoom for scaling and panning.
+///
+/// Every fixed point number is exactly representable as an `f64`, so converting to a `Number` is lossless.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
+pub struct Fixed(pub i32);
+
+impl Fixed {
+    pub const FRAC_BITS: u32 = 16;
+    pub const ONE: Fixed = Fixed(1 << Self::FRAC_BITS);
+
+    pub fn from_int(i: i16) -> Self {
+        Self(i32::from(i) << Self::FRAC_BITS)
+    }
+
+    /// The fixed point number equal to `f`, or `None` if `f` is out of range or has more fractional precision
+    pub fn from_float_exact(f: f64) -> Option<Self> {
+        let fixed = Self::from_float_rounded(f)?;
+        (fixed.into_float() == f).then_some(fixed)
+    }
+
+    /// The fixed point number nearest to `f`, or `None` if `f` is out of range
+    pub fn from_float_rounded(f: f64) -> Option<Self> {
+        let bits = (f * f64::from(Self::ONE.0)).round();
+        (f64::from(i32::MIN)..=f64::from(i32::MAX))
+            .contains(&bits)
+            .then_some(Self(bits as i32))
+    }
+
+    /// The integer part, rounded towards negative infinity as in the engine
+    pub fn floor(self) -> i16 {
+        (self.0 >> Self::FRAC_BITS) as i16
+    }
+
+    pub fn fract_bits(self) -> u16 {
+        self.0 as u16
+    }
+
+    pub fn into_float(self) -> f64 {
+        f64::from(self.0) / f64::from(Self::ONE.0)
+    }
+}
+
+impl From<Fixed> for Number {
+    fn from(fixed: Fixed) -> Self {
+        if fixed.fract_bits() == 0 {
+            Number::Int(i32::from(fixed.floor()))
+        } else {
+            Number::Float(fixed.into_float())
+        }
+    }
+}
+
+/// Converts exactly, returning the number if it isn't representable
+impl TryFrom<Number> for Fixed {
+    type Error = Number;
+
+    fn try_from(number: Number) -> Result<Self, Self::Error> {
+        match number {
+            Number::Int(i) => i16::try_from(i).map(Fixed::from_int).map_err(|_| number),
+            Number::Float(f) => Fixed::from_float_exact(f).ok_or(number),
+        }
+    }
+}
+
+/// Displays the exact decimal value, which always has at least one fractional digit, e.g. `1.0` or `-0.5`
+impl Display for Fixed {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
+        let magnitude = i64::from(self.0).abs();
+        let sign = if self.0 < 0 { "-" } else { "" };
+
+        // A 16 bit binary fraction is a whole number of 10^-16ths, since 2^-16 = 5^16 * 10^-16
+        let fraction = (magnitude & 0xffff) * 5i64.pow(Self::FRAC_BITS);
+        let digits = format!("{fraction:016}");
+        let digits = digits.trim_end_matches('0');
+
+        write!(
+            formatter,
+            "{sign}{}.{}",
+            magnitude >> Self::FRAC_BITS,
+            if digits.is_empty() { "0" } else { digits }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_conversions() {
+        let half = Fixed(0x8000);
+
+        assert_eq!(half.into_float(), 0.5);
+        assert_eq!(Number::from(half), Number::Float(0.5));
+        assert_eq!(Number::from(Fixed::from_int(-3)), Number::Int(-3));
+        assert_eq!(Fixed::try_from(Number::Float(-2.5)), Ok(Fixed(-0x28000)));
+        assert_eq!(Fixed::try_from(Number::Int(40000)), Err(Number::Int(40000)));
+        assert_eq!(Fixed::from_float_exact(0.1), None);
+        assert_eq!(Fixed::from_float_rounded(0.1), Some(Fixed(6554)));
+
+        let smallest = Fixed(1);
+        assert_eq!(smallest.to_string(), "0.0000152587890625");
+        assert_eq!(Fixed::try_from(Number::from(smallest)), Ok(smallest));
+        assert_eq!(Fixed(-0x28000).to_string(), "-2.5");
+        assert_eq!(Fixed::from_int(7).to_string(), "7.0");
+        assert_eq!(Fixed(-0x8000).floor(), -1);
+    }
+}
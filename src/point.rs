@@ -1,6 +1,12 @@
+use std::ops::{Add, Mul, Neg, Sub};
+
 use crate::number::Number;
 
 #[derive(Default, PartialEq, Eq, Hash, PartialOrd, Ord, Debug, Clone, Copy)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
 pub struct Point<T = Number> {
     pub x: T,
     pub y: T,
@@ -10,4 +16,141 @@ impl<T> Point<T> {
     pub fn new(x: T, y: T) -> Self {
         Self { x, y }
     }
+
+    /// Apply `f` to both coordinates
+    pub fn map<U>(self, mut f: impl FnMut(T) -> U) -> Point<U> {
+        Point::new(f(self.x), f(self.y))
+    }
+}
+
+impl<T: Copy + Add<Output = T> + Sub<Output = T> + Mul<Output = T>> Point<T> {
+    pub fn dot(self, other: Self) -> T {
+        self.x * other.x + self.y * other.y
+    }
+
+    /// The z component of the 3D cross product, which is positive if `other` is counterclockwise from `self`
+    pub fn cross(self, other: Self) -> T {
+        self.x * other.y - self.y * other.x
+    }
+}
+
+impl<T: Into<f64>> Point<T> {
+    pub fn into_f64(self) -> Point<f64> {
+        self.map(Into::into)
+    }
+
+    pub fn length(self) -> f64 {
+        let point = self.into_f64();
+        point.x.hypot(point.y)
+    }
+
+    pub fn distance(self, other: Self) -> f64 {
+        (other.into_f64() - self.into_f64()).length()
+    }
+}
+
+impl<T: Add<Output = T>> Add for Point<T> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl<T: Sub<Output = T>> Sub for Point<T> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl<T: Neg<Output = T>> Neg for Point<T> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::new(-self.x, -self.y)
+    }
+}
+
+/// Scaling by a scalar
+impl<T: Copy + Mul<Output = T>> Mul<T> for Point<T> {
+    type Output = Self;
+
+    fn mul(self, scalar: T) -> Self {
+        Self::new(self.x * scalar, self.y * scalar)
+    }
+}
+
+impl From<Point<i16>> for Point<i32> {
+    fn from(point: Point<i16>) -> Self {
+        point.map(i32::from)
+    }
+}
+
+impl From<Point<i16>> for Point<Number> {
+    fn from(point: Point<i16>) -> Self {
+        point.map(|c| Number::Int(i32::from(c)))
+    }
+}
+
+impl From<Point<i32>> for Point<Number> {
+    fn from(point: Point<i32>) -> Self {
+        point.map(Number::Int)
+    }
+}
+
+/// Converts if both coordinates fit, returning the point otherwise
+impl TryFrom<Point<i32>> for Point<i16> {
+    type Error = Point<i32>;
+
+    fn try_from(point: Point<i32>) -> Result<Self, Self::Error> {
+        match (i16::try_from(point.x), i16::try_from(point.y)) {
+            (Ok(x), Ok(y)) => Ok(Point::new(x, y)),
+            _ => Err(point),
+        }
+    }
+}
+
+/// Converts if both coordinates are whole numbers in range, returning the point otherwise
+impl TryFrom<Point<Number>> for Point<i32> {
+    type Error = Point<Number>;
+
+    fn try_from(point: Point<Number>) -> Result<Self, Self::Error> {
+        match (point.x.to_exact_int(), point.y.to_exact_int()) {
+            (Some(x), Some(y)) => Ok(Point::new(x, y)),
+            _ => Err(point),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn point_operations() {
+        let a = Point::new(3, 4);
+        let b = Point::new(-1, 2);
+
+        assert_eq!(a + b, Point::new(2, 6));
+        assert_eq!(a - b, Point::new(4, 2));
+        assert_eq!(-a * 2, Point::new(-6, -8));
+        assert_eq!(a.dot(b), 5);
+        assert_eq!(a.cross(b), 10);
+        assert_eq!(a.length(), 5.0);
+        assert_eq!(Point::new(1, 1).distance(Point::new(4, 5)), 5.0);
+
+        let number = Point::<Number>::from(Point::<i16>::new(3, -4));
+        assert_eq!(number + number, Point::new(Number::Int(6), Number::Int(-8)));
+        assert_eq!(number.length(), 5.0);
+        assert_eq!(Point::<i32>::try_from(number), Ok(Point::new(3, -4)));
+
+        let fractional = Point::new(Number::Float(0.5), Number::Int(1));
+        assert_eq!(Point::<i32>::try_from(fractional), Err(fractional));
+        assert_eq!(
+            Point::<i16>::try_from(Point::new(40000, 0)),
+            Err(Point::new(40000, 0))
+        );
+    }
 }
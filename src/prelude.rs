@@ -0,0 +1,38 @@
+//! The types most users of the crate need, for glob importing:
+//!
+//! ```
+//! use waddle::prelude::*;
+//!
+//! let raw_map = RawMap {
+//!     name: String8::new_unchecked("MAP01"),
+//!     vertexes: vec![Vertex::default()],
+//!     line_defs: Vec::new(),
+//!     sectors: Vec::new(),
+//!     side_defs: Vec::new(),
+//!     things: Vec::new(),
+//!     unknown_blocks: Vec::new(),
+//! };
+//! let map: Map = raw_map.link().unwrap();
+//! let key: VertexKey = map.vertexes.keys().next().unwrap();
+//! ```
+//!
+//! Everything here is part of the crate's stable API. Errors from the UDMF module are renamed, as their names are
+//! too general on their own.
+
+pub use crate::{
+    map::{
+        builder::{BuildError, RawMapBuilder},
+        line_def::{LineDefKey, RawLineDef, Special},
+        sector::SectorKey,
+        side_def::{RawSideDef, SideDefKey},
+        thing::ThingKey,
+        udmf::{LoadError as UdmfLoadError, WriteError as UdmfWriteError},
+        vertex::VertexKey,
+        LineDef, LinkError, Linker, Map, RawMap, Sector, SectorIdx, SideDef, SideDefIdx, Thing,
+        UnlinkError, Vertex, VertexIdx,
+    },
+    number::Number,
+    point::Point,
+    string8::{IntoString8Error, String8},
+    wad::{LumpError, Wad, WadError},
+};
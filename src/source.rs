@@ -0,0 +1,95 @@
+use std::ops::Range;
+
+use miette::Diagnostic;
+
+/// A position in a source text, as a zero-based line and column. Columns count characters, not bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde_derive::Serialize))]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// An index of the line starts in a source text, for converting byte offsets to `Position`s.
+///
+/// Build it once per source and reuse it for every span, rather than rescanning the source each time.
+#[derive(Clone, Debug)]
+pub struct LineIndex<'s> {
+    source: &'s str,
+    line_starts: Vec<usize>,
+}
+
+impl<'s> LineIndex<'s> {
+    pub fn new(source: &'s str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+
+        Self {
+            source,
+            line_starts,
+        }
+    }
+
+    /// The position of a byte offset. Offsets past the end of the source are clamped to it.
+    pub fn position(&self, offset: usize) -> Position {
+        let mut offset = offset.min(self.source.len());
+        while !self.source.is_char_boundary(offset) {
+            offset -= 1;
+        }
+
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let column = self.source[self.line_starts[line]..offset].chars().count();
+
+        Position { line, column }
+    }
+
+    pub fn span(&self, span: Range<usize>) -> Range<Position> {
+        self.position(span.start)..self.position(span.end)
+    }
+
+    /// The labels of a diagnostic, with their spans converted to positions
+    pub fn locate(&self, diagnostic: &dyn Diagnostic) -> Vec<LocatedLabel> {
+        diagnostic
+            .labels()
+            .into_iter()
+            .flatten()
+            .map(|label| {
+                let span = label.offset()..label.offset() + label.len();
+
+                LocatedLabel {
+                    label: label.label().map(str::to_owned),
+                    positions: self.span(span.clone()),
+                    span,
+                }
+            })
+            .collect()
+    }
+}
+
+/// A diagnostic label with both its byte span and its line/column positions
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde_derive::Serialize))]
+pub struct LocatedLabel {
+    pub label: Option<String>,
+    pub span: Range<usize>,
+    pub positions: Range<Position>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn positions() {
+        let index = LineIndex::new("ab\nçd\n\nx");
+
+        assert_eq!(index.position(0), Position { line: 0, column: 0 });
+        assert_eq!(index.position(2), Position { line: 0, column: 2 });
+        assert_eq!(index.position(3), Position { line: 1, column: 0 });
+        assert_eq!(index.position(5), Position { line: 1, column: 1 });
+        assert_eq!(index.position(7), Position { line: 2, column: 0 });
+        assert_eq!(index.position(8), Position { line: 3, column: 0 });
+        assert_eq!(index.position(100), Position { line: 3, column: 1 });
+    }
+}
@@ -1,5 +1,7 @@
 use std::{
+    borrow::Cow,
     convert::TryFrom,
+    fmt::{self, Display, Formatter},
     str::{self, Utf8Error},
 };
 
@@ -42,12 +44,45 @@ impl String8 {
         Self(arr)
     }
 
+    /// The raw bytes of the string, padded with nul bytes
+    pub fn as_bytes(&self) -> &[u8; 8] {
+        &self.0
+    }
+
+    /// The string with ASCII letters uppercased, as names are stored in the original WADs
+    pub fn to_uppercase(&self) -> Self {
+        Self(self.0.map(|byte| byte.to_ascii_uppercase()))
+    }
+
+    /// Whether the strings are equal ignoring ASCII case, as the engine compares lump, texture and flat names
+    pub fn eq_ignore_case(&self, other: &Self) -> bool {
+        self.0.eq_ignore_ascii_case(&other.0)
+    }
+
+    /// The bytes of the string up to the first nul byte
+    pub fn as_bytes_trimmed(&self) -> &[u8] {
+        trim_nul(&self.0)
+    }
+
     pub fn try_as_str(&self) -> Result<&str, Utf8Error> {
-        let p = self.0.iter().position(|&byte| byte != 0).unwrap_or(8);
-        str::from_utf8(&self.0[..p])
+        str::from_utf8(self.as_bytes_trimmed())
+    }
+
+    /// The string, with any invalid UTF-8 replaced by `U+FFFD`
+    pub fn to_string_lossy(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(self.as_bytes_trimmed())
     }
 }
 
+/// The bytes up to the first nul byte, as C strings are read
+pub(crate) fn trim_nul(bytes: &[u8]) -> &[u8] {
+    let len = bytes
+        .iter()
+        .position(|&byte| byte == 0)
+        .unwrap_or(bytes.len());
+    &bytes[..len]
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum IntoString8Error {
     #[error("Inner null byte at position {position}")]
@@ -56,6 +91,48 @@ pub enum IntoString8Error {
     Len { length: usize },
 }
 
+/// Displays the string lossily, as in `to_string_lossy`
+impl Display for String8 {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_string_lossy())
+    }
+}
+
+impl From<String8> for String {
+    fn from(s: String8) -> Self {
+        s.to_string_lossy().into_owned()
+    }
+}
+
+impl PartialEq<str> for String8 {
+    fn eq(&self, other: &str) -> bool {
+        self.as_bytes_trimmed() == other.as_bytes()
+    }
+}
+
+impl PartialEq<&str> for String8 {
+    fn eq(&self, other: &&str) -> bool {
+        *self == **other
+    }
+}
+
+/// Serialized as a string, which must be valid UTF-8
+#[cfg(feature = "serde")]
+impl serde::Serialize for String8 {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let s = self.try_as_str().map_err(serde::ser::Error::custom)?;
+        serializer.serialize_str(s)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for String8 {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = <std::borrow::Cow<str>>::deserialize(deserializer)?;
+        Self::new(&s).map_err(serde::de::Error::custom)
+    }
+}
+
 impl TryFrom<&str> for String8 {
     type Error = IntoString8Error;
 
@@ -79,3 +156,24 @@ impl TryFrom<&[u8]> for String8 {
         Self::from_bytes(s)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn string_conversions() {
+        let s = String8::new("STONE2").unwrap();
+
+        assert_eq!(s.as_bytes_trimmed(), b"STONE2");
+        assert_eq!(s.try_as_str().unwrap(), "STONE2");
+        assert_eq!(s, "STONE2");
+        assert_ne!(s, "STONE");
+        assert_eq!(s.to_string(), "STONE2");
+        assert_eq!(String::from(s), "STONE2");
+
+        let invalid = String8::from_raw_parts([b'A', 0xff, 0, 0, 0, 0, 0, 0]);
+        assert!(invalid.try_as_str().is_err());
+        assert_eq!(invalid.to_string(), "A\u{fffd}");
+    }
+}
@@ -0,0 +1,189 @@
+//! Map units, the distance unit of the Doom engine, measure three different things which the map formats all store
+//! as plain integers. They get distinct types here so they can't be mixed up:
+//!
+//! - World coordinates, on the horizontal plane, are `Point<Number>`, or `Point<f64>` in geometric computations
+//! - Heights, along the vertical axis, are `Height`, including the distances specials move floors and ceilings by
+//! - Texture offsets, in texels along a wall's texture, are `TextureOffset`
+//!
+//! Thing facings, stored as whole degrees, are `Angle`.
+//!
+//! None of them convert implicitly from a plain `i16`: wrapping a raw value, e.g. `Height(value)`, is the explicit
+//! point where it's given a unit.
+
+use std::{
+    fmt::{self, Display, Formatter},
+    ops::{Add, Neg, Sub},
+};
+
+/// A vertical position or distance: a sector's floor or ceiling height, or a thing's height above the floor
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
+pub struct Height(pub i16);
+
+/// How far a texture is shifted on a wall, in texels
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
+pub struct TextureOffset(pub i16);
+
+/// A thing's facing, in whole degrees counterclockwise from east, as every map format stores it.
+///
+/// Values outside `0..360` are kept as they are, since polyobject spots store their polyobject number in their angle.
+/// Arithmetic wraps around into `0..360`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
+pub struct Angle(pub i16);
+
+macro_rules! impl_unit {
+    ($unit:ident) => {
+        impl From<$unit> for i32 {
+            fn from(value: $unit) -> Self {
+                value.0.into()
+            }
+        }
+
+        impl From<$unit> for f64 {
+            fn from(value: $unit) -> Self {
+                value.0.into()
+            }
+        }
+
+        impl Display for $unit {
+            fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                self.0.fmt(f)
+            }
+        }
+    };
+}
+
+impl_unit!(Height);
+impl_unit!(TextureOffset);
+impl_unit!(Angle);
+
+/// A full turn in binary angle measurement
+const BAM_TURN: u64 = 1 << 32;
+
+impl Angle {
+    pub const EAST: Self = Self(0);
+    pub const NORTH: Self = Self(90);
+    pub const WEST: Self = Self(180);
+    pub const SOUTH: Self = Self(270);
+
+    /// The same direction in the range `0..360`
+    pub fn normalized(self) -> Self {
+        Self(self.0.rem_euclid(360))
+    }
+
+    /// The nearest whole degree to `degrees`, in the range `0..360`
+    pub fn from_degrees(degrees: f64) -> Self {
+        Self(degrees.round().rem_euclid(360.0) as i16)
+    }
+
+    pub fn from_radians(radians: f64) -> Self {
+        Self::from_degrees(radians.to_degrees())
+    }
+
+    pub fn to_radians(self) -> f64 {
+        f64::from(self.0).to_radians()
+    }
+
+    /// The nearest whole degree to a binary angle measurement (BAM), where a full turn is 2^32, as the engine
+    /// stores angles at runtime
+    pub fn from_bam(bam: u32) -> Self {
+        let degrees = (u64::from(bam) * 360 + BAM_TURN / 2) / BAM_TURN;
+        Self((degrees % 360) as i16)
+    }
+
+    pub fn to_bam(self) -> u32 {
+        (u64::from(self.normalized().0.unsigned_abs()) * BAM_TURN / 360) as u32
+    }
+
+    /// The nearest whole degree to a byte angle, the top 8 bits of a BAM, where a full turn is 256. Specials which
+    /// take a direction, e.g. to move polyobjects or thrust things, take byte angles.
+    pub fn from_byte_angle(byte_angle: u8) -> Self {
+        Self::from_bam(u32::from(byte_angle) << 24)
+    }
+
+    /// The nearest byte angle to this angle
+    pub fn to_byte_angle(self) -> u8 {
+        (self.to_bam().wrapping_add(1 << 23) >> 24) as u8
+    }
+}
+
+impl Add for Angle {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self((i32::from(self.0) + i32::from(other.0)).rem_euclid(360) as i16)
+    }
+}
+
+impl Sub for Angle {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self((i32::from(self.0) - i32::from(other.0)).rem_euclid(360) as i16)
+    }
+}
+
+impl Neg for Angle {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self((-i32::from(self.0)).rem_euclid(360) as i16)
+    }
+}
+
+/// The distance between two heights, which may not fit in a `Height`
+impl Sub for Height {
+    type Output = i32;
+
+    fn sub(self, other: Self) -> i32 {
+        i32::from(self.0) - i32::from(other.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn angle_conversions() {
+        assert_eq!(Angle(45).to_bam(), 0x2000_0000);
+        assert_eq!(Angle(-90).to_bam(), 0xc000_0000);
+        assert_eq!(Angle::from_bam(0xffff_ffff), Angle::EAST);
+        assert_eq!(Angle::from_bam(0x4000_0000), Angle::NORTH);
+
+        assert_eq!(Angle(90).to_byte_angle(), 64);
+        assert_eq!(Angle(359).to_byte_angle(), 255);
+        assert_eq!(Angle::from_byte_angle(192), Angle::SOUTH);
+
+        assert_eq!(Angle::from_radians(std::f64::consts::PI), Angle::WEST);
+        assert_eq!(Angle::from_degrees(-0.4), Angle::EAST);
+        assert_eq!(Angle(720).normalized(), Angle::EAST);
+
+        assert_eq!(Angle(350) + Angle(20), Angle(10));
+        assert_eq!(Angle(10) - Angle(20), Angle(350));
+        assert_eq!(-Angle::NORTH, Angle::SOUTH);
+    }
+
+    #[test]
+    fn height_differences() {
+        assert_eq!(Height(i16::MAX) - Height(i16::MIN), 65535);
+        assert_eq!(Height(-8) - Height(16), -24);
+        assert!(Height(-8) < Height(0));
+
+        assert_eq!(i32::from(Height(-8)), -8);
+        assert_eq!(f64::from(TextureOffset(12)), 12.0);
+        assert_eq!(Height(-8).to_string(), "-8");
+        assert_eq!(TextureOffset(3).to_string(), "3");
+    }
+}
@@ -1,17 +1,526 @@
-pub struct ACSLibrary;
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    ops::Range,
+    path::{Path, PathBuf},
+    process,
+};
+
+use miette::Diagnostic;
+
+use crate::{
+    map::{
+        binary::{BinaryFormat, BinaryMapLumps},
+        udmf::LoadError,
+        LinkError, Map, RawMap,
+    },
+    String8,
+};
+
+pub mod acs;
+pub mod animation;
+mod binary;
+pub mod browser;
+pub mod decorate;
+pub mod demo;
+pub mod duplicates;
+pub mod flat;
+pub mod image;
+pub mod mapinfo;
+pub mod namespace;
+pub mod report;
+pub mod resource_set;
+mod script;
+pub mod session;
+pub mod texture;
+
+pub(crate) use self::binary::{LumpReader, WriteLe};
+pub use self::{
+    acs::ACSLibrary,
+    browser::TextureBrowser,
+    demo::Demo,
+    flat::Flat,
+    image::IndexedImage,
+    namespace::Namespace,
+    report::WadReport,
+    resource_set::{ImageNamespace, ResourceSet},
+    session::{DirectoryEntry, WadSession},
+    texture::{Patch, PatchNames, Texture, Textures},
+};
+
 pub struct ColorMap;
 pub struct Filter;
-pub struct Flat;
 pub struct Graphic;
 pub struct HiRes;
 pub struct Music;
-pub struct Patch;
 pub struct Sound;
 pub struct Sprite;
-pub struct Texture;
 pub struct Voice;
 pub struct Voxel;
 
-pub struct Wad {}
+/// Whether a WAD is a complete game (IWAD) or a patch to load on top of one (PWAD)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum WadKind {
+    Iwad,
+    Pwad,
+}
+
+impl WadKind {
+    fn magic(self) -> &'static [u8; 4] {
+        match self {
+            WadKind::Iwad => b"IWAD",
+            WadKind::Pwad => b"PWAD",
+        }
+    }
+}
+
+/// A named chunk of data in a WAD
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Lump {
+    pub name: String8,
+    pub data: Vec<u8>,
+}
+
+impl Lump {
+    pub fn new(name: String8, data: Vec<u8>) -> Self {
+        Self { name, data }
+    }
+
+    /// Whether this lump's name is `name`, ignoring case
+    pub fn is_named(&self, name: &str) -> bool {
+        name_matches(&self.name, name)
+    }
+}
+
+/// Whether a lump name is `name`, ignoring case
+fn name_matches(lump_name: &String8, name: &str) -> bool {
+    lump_name
+        .as_bytes_trimmed()
+        .eq_ignore_ascii_case(name.as_bytes())
+}
+
+/// The lumps which can follow the marker of a map in a binary format
+const BINARY_MAP_LUMPS: &[&str] = &[
+    "THINGS", "LINEDEFS", "SIDEDEFS", "VERTEXES", "SEGS", "SSECTORS", "NODES", "SECTORS", "REJECT",
+    "BLOCKMAP", "BEHAVIOR", "SCRIPTS",
+];
+
+/// A WAD archive: an ordered list of lumps
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Wad {
+    pub kind: WadKind,
+    pub lumps: Vec<Lump>,
+}
+
+impl Wad {
+    pub fn new(kind: WadKind) -> Self {
+        Self {
+            kind,
+            lumps: Vec::new(),
+        }
+    }
+
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, WadError> {
+        Ok(Self::parse(&fs::read(path)?)?)
+    }
+
+    pub fn parse(bytes: &[u8]) -> Result<Self, LumpError> {
+        let header = WadHeader::parse(bytes, bytes.len() as u64)?;
+
+        let mut reader = LumpReader::new("WAD directory", bytes).with_start(0);
+        reader.seek(header.directory_offset)?;
+
+        let lumps = (0..header.lump_count)
+            .map(|_| {
+                let entry = DirectoryEntry::read(&mut reader)?;
+                let (offset, size) = (entry.offset as usize, entry.size as usize);
+
+                let data = bytes
+                    .get(offset..offset + size)
+                    .ok_or_else(|| reader.invalid("lump extent", (offset + size) as i64))?;
+
+                Ok(Lump::new(entry.name, data.to_vec()))
+            })
+            .collect::<Result<_, LumpError>>()?;
+
+        Ok(Self {
+            kind: header.kind,
+            lumps,
+        })
+    }
+
+    /// Write the WAD, with the lump data in order followed by the directory
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let data_len: usize = self.lumps.iter().map(|lump| lump.data.len()).sum();
+
+        WadHeader {
+            kind: self.kind,
+            lump_count: self.lumps.len(),
+            directory_offset: WadHeader::LEN + data_len,
+        }
+        .write(writer)?;
+
+        for lump in self.lumps.iter() {
+            writer.write_all(&lump.data)?;
+        }
+
+        let mut offset = WadHeader::LEN;
+        for lump in self.lumps.iter() {
+            DirectoryEntry {
+                name: lump.name.clone(),
+                offset: offset as u64,
+                size: lump.data.len() as u64,
+            }
+            .write(writer)?;
+            offset += lump.data.len();
+        }
+
+        Ok(())
+    }
+
+    /// Write the WAD to `path` through a temporary file in the same directory, which is then renamed over `path`.
+    /// If writing fails, the previous file is left untouched.
+    pub fn save_atomic(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.save_atomic_with_backups(path, 0)
+    }
+
+    /// Like `save_atomic`, but first keeps up to `backups` previous versions of the file, as `<path>.1` (the most
+    /// recent) to `<path>.<backups>`
+    pub fn save_atomic_with_backups(
+        &self,
+        path: impl AsRef<Path>,
+        backups: usize,
+    ) -> io::Result<()> {
+        let path = path.as_ref();
+        let backup_path = |number: usize| {
+            let mut backup = path.as_os_str().to_owned();
+            backup.push(format!(".{number}"));
+            PathBuf::from(backup)
+        };
+
+        let mut temp_path = path.as_os_str().to_owned();
+        temp_path.push(format!(".{}.tmp", process::id()));
+        let temp_path = PathBuf::from(temp_path);
+
+        let result = (|| {
+            let mut temp = BufWriter::new(File::create(&temp_path)?);
+            self.write(&mut temp)?;
+            temp.into_inner()?.sync_all()
+        })();
+        if let Err(e) = result {
+            let _ = fs::remove_file(&temp_path);
+            return Err(e);
+        }
+
+        if backups > 0 && path.exists() {
+            for number in (1..backups).rev() {
+                match fs::rename(backup_path(number), backup_path(number + 1)) {
+                    Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+                    _ => {}
+                }
+            }
+
+            // Copy rather than rename, so that `path` exists at every point
+            fs::copy(path, backup_path(1))?;
+        }
+
+        fs::rename(&temp_path, path)
+    }
+
+    /// The last lump with the given name, ignoring case. Later lumps take precedence, as in the engine.
+    pub fn find(&self, name: &str) -> Option<&Lump> {
+        self.lumps.iter().rev().find(|lump| lump.is_named(name))
+    }
+
+    /// The marker lumps of every map, i.e. the lumps immediately followed by a TEXTMAP or THINGS lump
+    pub fn maps(&self) -> impl Iterator<Item = &Lump> {
+        self.lumps
+            .windows(2)
+            .filter(|pair| pair[1].is_named("TEXTMAP") || pair[1].is_named("THINGS"))
+            .map(|pair| &pair[0])
+    }
+
+    /// The lumps between a start and end marker, e.g. `F_START` and `F_END`, for every such pair in the WAD
+    pub fn lumps_between<'w>(
+        &'w self,
+        start: &'w str,
+        end: &'w str,
+    ) -> impl Iterator<Item = &'w Lump> + 'w {
+        let mut inside = false;
+
+        self.lumps.iter().filter(move |lump| {
+            if lump.is_named(start) {
+                inside = true;
+                false
+            } else if lump.is_named(end) {
+                inside = false;
+                false
+            } else {
+                inside
+            }
+        })
+    }
+
+    /// The lumps of the map whose marker is at `marker`, not including the marker: TEXTMAP up to ENDMAP for UDMF maps,
+    /// or the lumps of a binary map
+    pub fn map_lumps(&self, marker: usize) -> &[Lump] {
+        let rest = self.lumps.get(marker + 1..).unwrap_or_default();
+
+        let len = if rest.first().is_some_and(|lump| lump.is_named("TEXTMAP")) {
+            rest.iter()
+                .position(|lump| lump.is_named("ENDMAP"))
+                .map_or(rest.len(), |end| end + 1)
+        } else {
+            rest.iter()
+                .position(|lump| !BINARY_MAP_LUMPS.iter().any(|name| lump.is_named(name)))
+                .unwrap_or(rest.len())
+        };
+
+        &rest[..len]
+    }
+
+    /// Load the map whose marker is at `marker`, from UDMF if it has a TEXTMAP lump and otherwise from the Doom or
+    /// Hexen binary format
+    pub fn load_map(&self, marker: usize) -> Result<Map, MapLoadError> {
+        let name = self
+            .lumps
+            .get(marker)
+            .ok_or(MapLoadError::NoMarker(marker))?
+            .name
+            .clone();
+        let lumps = self.map_lumps(marker);
+        let find = |name| {
+            lumps
+                .iter()
+                .find(|lump| lump.is_named(name))
+                .ok_or(MapLoadError::MissingLump(name))
+        };
+
+        if let Ok(textmap) = find("TEXTMAP") {
+            return Ok(Map::load_udmf_textmap(
+                name,
+                &String::from_utf8_lossy(&textmap.data),
+            )?);
+        }
+
+        let format = match find("BEHAVIOR") {
+            Ok(_) => BinaryFormat::Hexen,
+            Err(_) => BinaryFormat::Doom,
+        };
+        let lumps = BinaryMapLumps {
+            things: &find("THINGS")?.data,
+            line_defs: &find("LINEDEFS")?.data,
+            side_defs: &find("SIDEDEFS")?.data,
+            vertexes: &find("VERTEXES")?.data,
+            sectors: &find("SECTORS")?.data,
+        };
+        let (raw_map, _) = RawMap::parse_binary(name, format, &lumps)?;
+
+        Ok(raw_map.link()?)
+    }
+}
+
+#[derive(Debug, thiserror::Error, Diagnostic)]
+pub enum WadError {
+    #[error("IO error")]
+    Io(#[from] io::Error),
+
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Lump(#[from] LumpError),
+}
+
+/// An error loading a map from a WAD
+#[derive(Debug, thiserror::Error, Diagnostic)]
+pub enum MapLoadError {
+    #[error("There's no lump at index {0} to be a map marker")]
+    NoMarker(usize),
+
+    #[error("Missing {0} lump")]
+    MissingLump(&'static str),
+
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Udmf(#[from] LoadError),
+
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Lump(#[from] LumpError),
+
+    #[error(transparent)]
+    Link(#[from] LinkError),
+}
+
+/// The start of a WAD file: its magic, lump count and directory offset
+pub(crate) struct WadHeader {
+    pub kind: WadKind,
+    pub lump_count: usize,
+    pub directory_offset: usize,
+}
+
+impl WadHeader {
+    pub const LEN: usize = 12;
+
+    /// Parse the header of a WAD file `file_len` bytes long, checking that its directory fits in the file
+    pub fn parse(bytes: &[u8], file_len: u64) -> Result<Self, LumpError> {
+        let mut reader = LumpReader::new("WAD header", bytes).with_start(0);
+
+        let kind = match &reader.bytes::<4>()? {
+            b"IWAD" => WadKind::Iwad,
+            b"PWAD" => WadKind::Pwad,
+            magic => return Err(reader.invalid("magic", i32::from_le_bytes(*magic).into())),
+        };
+
+        let lump_count = reader.count("lump count")?;
+        let directory_offset = reader.count("directory offset")?;
+
+        let directory_end = (lump_count as u64)
+            .checked_mul(session::ENTRY_LEN)
+            .and_then(|len| len.checked_add(directory_offset as u64));
+        match directory_end {
+            Some(end) if end <= file_len => Ok(Self {
+                kind,
+                lump_count,
+                directory_offset,
+            }),
+            _ => Err(reader.invalid(
+                "directory end",
+                directory_end.map_or(i64::MAX, |end| i64::try_from(end).unwrap_or(i64::MAX)),
+            )),
+        }
+    }
+
+    pub fn write<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(self.kind.magic())?;
+        writer.write_count(self.lump_count)?;
+        writer.write_count(self.directory_offset)
+    }
+}
+
+/// An error encountered while reading a binary lump.
+///
+/// The offset is relative to the start of the lump. `lump_start` is the lump's position in its file, when known, and
+/// the source code is a hex dump of the bytes around the error.
+#[derive(Debug, thiserror::Error, Diagnostic)]
+pub enum LumpError {
+    #[error(
+        "{lump} ended unexpectedly at byte {offset}{}, {needed} more bytes were needed",
+        file_position(*lump_start, *offset)
+    )]
+    #[diagnostic(code(waddle::wad::unexpected_end))]
+    UnexpectedEnd {
+        lump: String,
+        offset: usize,
+        needed: usize,
+        lump_start: Option<u64>,
+        #[source_code]
+        hex: String,
+        #[label("The lump ends here")]
+        span: Range<usize>,
+    },
+
+    #[error(
+        "{lump} has an invalid {field} of {value} at byte {offset}{}",
+        file_position(*lump_start, *offset)
+    )]
+    #[diagnostic(code(waddle::wad::invalid_value))]
+    InvalidValue {
+        lump: String,
+        field: &'static str,
+        value: i64,
+        offset: usize,
+        lump_start: Option<u64>,
+        #[source_code]
+        hex: String,
+        #[label("This {field} is invalid")]
+        span: Range<usize>,
+    },
+}
+
+impl LumpError {
+    /// Attribute the error to the lump `name`, stored at `lump_start` in its file
+    pub fn locate(mut self, name: &String8, lump_start: u64) -> Self {
+        match &mut self {
+            LumpError::UnexpectedEnd {
+                lump,
+                lump_start: start,
+                ..
+            }
+            | LumpError::InvalidValue {
+                lump,
+                lump_start: start,
+                ..
+            } => {
+                *lump = format!("{name} ({lump})");
+                *start = Some(lump_start);
+            }
+        }
+
+        self
+    }
+
+    /// The position of the error in the lump's file, if the lump's position is known
+    pub fn file_offset(&self) -> Option<u64> {
+        match self {
+            LumpError::UnexpectedEnd {
+                offset, lump_start, ..
+            }
+            | LumpError::InvalidValue {
+                offset, lump_start, ..
+            } => lump_start.map(|start| start + *offset as u64),
+        }
+    }
+}
+
+fn file_position(lump_start: Option<u64>, offset: usize) -> String {
+    lump_start.map_or_else(String::new, |start| {
+        format!(" (file offset {:#x})", start + offset as u64)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn save_atomic_keeps_backups() {
+        let path = std::env::temp_dir().join(format!("waddle-save-{}.wad", process::id()));
+        let backup = |number: usize| PathBuf::from(format!("{}.{number}", path.display()));
+
+        let mut wad = Wad::new(WadKind::Pwad);
+        for version in 0..4u8 {
+            wad.lumps = vec![Lump::new(String8::new_unchecked("VERSION"), vec![version])];
+            wad.save_atomic_with_backups(&path, 2).unwrap();
+        }
+
+        let version = |path: &Path| Wad::open(path).unwrap().lumps[0].data[0];
+        assert_eq!(version(&path), 3);
+        assert_eq!(version(&backup(1)), 2);
+        assert_eq!(version(&backup(2)), 1);
+        assert!(!backup(3).exists());
+
+        for path in [path.clone(), backup(1), backup(2)] {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn load_map_markers() {
+        let mut wad = Wad::new(WadKind::Pwad);
+        for (name, data) in [
+            ("MAP01", ""),
+            ("TEXTMAP", "namespace = \"zdoom\"; vertex { x = 0; y = 0; }"),
+            ("ENDMAP", ""),
+        ] {
+            wad.lumps.push(Lump::new(
+                String8::new_unchecked(name),
+                data.as_bytes().to_vec(),
+            ));
+        }
 
-impl Wad {}
+        assert_eq!(wad.map_lumps(0).len(), 2);
+        assert!(wad.load_map(0).is_ok());
+        assert!(wad.map_lumps(3).is_empty());
+        assert!(matches!(wad.load_map(3), Err(MapLoadError::NoMarker(3))));
+    }
+}
@@ -0,0 +1,317 @@
+use miette::Diagnostic;
+
+use crate::{
+    string8::trim_nul,
+    wad::{script::tokenize, LumpError, LumpReader, Wad},
+    String8,
+};
+
+/// The variant of the compiled ACS object format
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AcsFormat {
+    /// Hexen's format, with a plain script directory
+    Old,
+    /// ZDoom's chunked format
+    Enhanced,
+    /// ZDoom's chunked format with compressed p-code
+    LittleEnhanced,
+}
+
+/// When a script runs
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ScriptType {
+    Closed,
+    Open,
+    Respawn,
+    Death,
+    Enter,
+    Pickup,
+    BlueReturn,
+    RedReturn,
+    WhiteReturn,
+    Lightning,
+    Unloading,
+    Disconnect,
+    Return,
+    Event,
+    Kill,
+    Reopen,
+    Other(u8),
+}
+
+impl From<u8> for ScriptType {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => ScriptType::Closed,
+            1 => ScriptType::Open,
+            2 => ScriptType::Respawn,
+            3 => ScriptType::Death,
+            4 => ScriptType::Enter,
+            5 => ScriptType::Pickup,
+            6 => ScriptType::BlueReturn,
+            7 => ScriptType::RedReturn,
+            8 => ScriptType::WhiteReturn,
+            12 => ScriptType::Lightning,
+            13 => ScriptType::Unloading,
+            14 => ScriptType::Disconnect,
+            15 => ScriptType::Return,
+            16 => ScriptType::Event,
+            17 => ScriptType::Kill,
+            18 => ScriptType::Reopen,
+            other => ScriptType::Other(other),
+        }
+    }
+}
+
+/// A script in the directory of an ACS object
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ScriptEntry {
+    /// The script number. Named scripts have negative numbers.
+    pub number: i32,
+    pub name: Option<String>,
+    pub type_: ScriptType,
+    pub arg_count: u8,
+    /// The offset of the script's p-code in the object
+    pub offset: usize,
+}
+
+/// A compiled ACS object: a map's BEHAVIOR lump, or a library between A_START and A_END
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ACSLibrary {
+    /// The name of the lump the object was loaded from
+    pub name: String8,
+    pub format: AcsFormat,
+    pub scripts: Vec<ScriptEntry>,
+    /// The libraries this object imports with `#import`, from its LOAD chunk
+    pub imports: Vec<String>,
+}
+
+impl ACSLibrary {
+    /// Read the script directory of an ACS object. The p-code itself isn't decoded.
+    pub fn parse(name: String8, bytes: &[u8]) -> Result<Self, LumpError> {
+        let mut reader = LumpReader::new("ACS object", bytes);
+
+        let magic = reader.bytes::<4>()?;
+        let mut format = match &magic {
+            b"ACS\0" => AcsFormat::Old,
+            b"ACSE" => AcsFormat::Enhanced,
+            b"ACSe" => AcsFormat::LittleEnhanced,
+            _ => return Err(reader.invalid("magic", i32::from_le_bytes(magic).into())),
+        };
+        let directory_offset = reader.count("directory offset")?;
+        let mut chunks = directory_offset..bytes.len();
+
+        // Enhanced objects can be wrapped in an old header, with the real one just before the old directory
+        if format == AcsFormat::Old && (24..=bytes.len()).contains(&directory_offset) {
+            reader.seek(directory_offset - 4)?;
+            let wrapped = match &reader.bytes::<4>()? {
+                b"ACSE" => Some(AcsFormat::Enhanced),
+                b"ACSe" => Some(AcsFormat::LittleEnhanced),
+                _ => None,
+            };
+
+            if let Some(wrapped) = wrapped {
+                reader.seek(directory_offset - 8)?;
+                format = wrapped;
+                chunks = reader.count("chunk offset")?..directory_offset - 8;
+            }
+        }
+
+        let mut library = Self {
+            name,
+            format,
+            scripts: Vec::new(),
+            imports: Vec::new(),
+        };
+
+        if format == AcsFormat::Old {
+            reader.seek(directory_offset)?;
+            let count = reader.count("script count")?;
+
+            for _ in 0..count {
+                let number = reader.i32()?;
+                let offset = reader.count("script offset")?;
+                let arg_count = reader.count("argument count")?;
+
+                // The type is encoded in the thousands
+                library.scripts.push(ScriptEntry {
+                    number: number % 1000,
+                    name: None,
+                    type_: ScriptType::from((number / 1000) as u8),
+                    arg_count: arg_count as u8,
+                    offset,
+                });
+            }
+
+            return Ok(library);
+        }
+
+        let mut names = Vec::new();
+        reader.seek(chunks.start)?;
+        while reader.offset() + 8 <= chunks.end {
+            let id = reader.bytes::<4>()?;
+            let size = reader.count("chunk size")?;
+            let data = reader
+                .remaining()
+                .get(..size)
+                .ok_or_else(|| reader.invalid("chunk size", size as i64))?;
+
+            match &id {
+                b"SPTR" => library.scripts = parse_script_pointers(data)?,
+                b"SNAM" => names = parse_string_table(data, "SNAM chunk")?,
+                b"LOAD" => {
+                    library.imports = data
+                        .split(|&b| b == 0)
+                        .filter(|name| !name.is_empty())
+                        .map(|name| String::from_utf8_lossy(name).into_owned())
+                        .collect();
+                }
+                _ => {}
+            }
+
+            reader.seek(reader.offset() + size)?;
+        }
+
+        for script in library.scripts.iter_mut() {
+            if script.number < 0 {
+                script.name = names.get((-1 - script.number) as usize).cloned();
+            }
+        }
+
+        Ok(library)
+    }
+}
+
+fn parse_script_pointers(data: &[u8]) -> Result<Vec<ScriptEntry>, LumpError> {
+    let mut reader = LumpReader::new("SPTR chunk", data);
+
+    (0..data.len() / 8)
+        .map(|_| {
+            Ok(ScriptEntry {
+                number: reader.i16()?.into(),
+                name: None,
+                type_: ScriptType::from(reader.u8()?),
+                arg_count: reader.u8()?,
+                offset: reader.count("script offset")?,
+            })
+        })
+        .collect()
+}
+
+/// A count followed by that many offsets to nul-terminated strings, relative to the start of the table
+fn parse_string_table(data: &[u8], lump: &'static str) -> Result<Vec<String>, LumpError> {
+    let mut reader = LumpReader::new(lump, data);
+    let count = reader.count("string count")?;
+
+    (0..count)
+        .map(|_| {
+            let offset = reader.count("string offset")?;
+            let string = data
+                .get(offset..)
+                .ok_or_else(|| reader.invalid("string offset", offset as i64))?;
+            let string = trim_nul(string);
+
+            Ok(String::from_utf8_lossy(string).into_owned())
+        })
+        .collect()
+}
+
+/// The library names listed in a LOADACS lump, in uppercase
+pub fn parse_loadacs(contents: &str) -> Vec<String8> {
+    tokenize(contents)
+        .into_iter()
+        .filter_map(|token| String8::new(&token.text.to_ascii_uppercase()).ok())
+        .collect()
+}
+
+#[derive(Debug, thiserror::Error, Diagnostic)]
+pub enum AcsError {
+    #[error("LOADACS lists {name}, which isn't between A_START and A_END")]
+    #[diagnostic(code(waddle::wad::missing_acs_library))]
+    MissingLibrary { name: String },
+
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Lump(#[from] LumpError),
+}
+
+impl Wad {
+    /// The libraries loaded for every map by the WAD's LOADACS lumps, in load order
+    pub fn global_acs_libraries(&self) -> Result<Vec<ACSLibrary>, AcsError> {
+        let libraries: Vec<_> = self.lumps_between("A_START", "A_END").collect();
+
+        self.lumps
+            .iter()
+            .filter(|lump| lump.is_named("LOADACS"))
+            .flat_map(|lump| parse_loadacs(&String::from_utf8_lossy(&lump.data)))
+            .map(|name| {
+                let lump = libraries
+                    .iter()
+                    .rev()
+                    .find(|lump| lump.name.as_bytes().eq_ignore_ascii_case(name.as_bytes()))
+                    .ok_or_else(|| AcsError::MissingLibrary {
+                        name: String::from_utf8_lossy(name.as_bytes())
+                            .trim_end_matches('\0')
+                            .to_owned(),
+                    })?;
+
+                Ok(ACSLibrary::parse(name, &lump.data)?)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::wad::{Lump, WadKind};
+
+    #[test]
+    fn load_global_libraries() {
+        // An ACSE object with one named OPEN script and one numbered ENTER script
+        let mut object = b"ACSE".to_vec();
+        object.extend(8i32.to_le_bytes());
+        object.extend(b"SPTR");
+        object.extend(16i32.to_le_bytes());
+        object.extend((-1i16).to_le_bytes());
+        object.extend([1, 0]);
+        object.extend(0i32.to_le_bytes());
+        object.extend(2i16.to_le_bytes());
+        object.extend([4, 1]);
+        object.extend(0i32.to_le_bytes());
+        object.extend(b"SNAM");
+        object.extend(13i32.to_le_bytes());
+        object.extend(1i32.to_le_bytes());
+        object.extend(8i32.to_le_bytes());
+        object.extend(b"Intro\0");
+
+        let mut wad = Wad::new(WadKind::Pwad);
+        wad.lumps = vec![
+            Lump::new(
+                String8::new_unchecked("LOADACS"),
+                b"// Libraries\nweather\n".to_vec(),
+            ),
+            Lump::new(String8::new_unchecked("A_START"), Vec::new()),
+            Lump::new(String8::new_unchecked("WEATHER"), object),
+            Lump::new(String8::new_unchecked("A_END"), Vec::new()),
+        ];
+
+        let libraries = wad.global_acs_libraries().unwrap();
+        assert_eq!(libraries.len(), 1);
+        assert_eq!(libraries[0].format, AcsFormat::Enhanced);
+
+        let scripts = &libraries[0].scripts;
+        assert_eq!(scripts[0].name.as_deref(), Some("Intro"));
+        assert_eq!(scripts[0].type_, ScriptType::Open);
+        assert_eq!(scripts[1].number, 2);
+        assert_eq!(scripts[1].type_, ScriptType::Enter);
+        assert_eq!(scripts[1].arg_count, 1);
+
+        wad.lumps.remove(2);
+        assert!(matches!(
+            wad.global_acs_libraries(),
+            Err(AcsError::MissingLibrary { name }) if name == "WEATHER"
+        ));
+    }
+}
@@ -0,0 +1,549 @@
+use std::{
+    io::{self, Write},
+    ops::Range,
+};
+
+use miette::Diagnostic;
+
+use crate::{
+    wad::{
+        resource_set::ImageNamespace,
+        script::{tokenize, Token},
+        LumpError, LumpReader, WriteLe,
+    },
+    String8,
+};
+
+/// An animated texture or flat, from Doom's hardcoded table, ANIMATED or ANIMDEFS
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Animation {
+    pub namespace: ImageNamespace,
+    pub frames: AnimationFrames,
+}
+
+/// The frames of an `Animation`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AnimationFrames {
+    /// Every image defined between `first` and `last`, inclusive, in definition order, each shown for `tics`
+    Range {
+        first: String8,
+        last: String8,
+        tics: u16,
+    },
+    /// An explicit list of frames, replacing `base` in the world
+    Pics {
+        base: String8,
+        pics: Vec<AnimationPic>,
+    },
+}
+
+impl AnimationFrames {
+    /// The image this animation replaces
+    pub fn base(&self) -> &String8 {
+        match self {
+            AnimationFrames::Range { first, .. } => first,
+            AnimationFrames::Pics { base, .. } => base,
+        }
+    }
+}
+
+/// A frame of `AnimationFrames::Pics`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AnimationPic {
+    pub pic: Pic,
+    /// The frame is shown for a random duration in `min_tics..=max_tics`. Both are equal for a fixed duration.
+    pub min_tics: u16,
+    pub max_tics: u16,
+}
+
+/// An image in an ANIMDEFS `pic` definition
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Pic {
+    Name(String8),
+    /// The image `n - 1` places after the animation's base, in definition order
+    Number(u16),
+}
+
+/// A switch texture, replaced by `on` when activated
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SwitchDef {
+    pub off: String8,
+    pub on: String8,
+    /// In SWITCHES, 1 for shareware Doom, 2 for registered Doom and 3 for Doom 2. 0 if from ANIMDEFS.
+    pub game: i16,
+}
+
+/// Animation and switch definitions from an ANIMDEFS lump
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AnimDefs {
+    pub animations: Vec<Animation>,
+    pub switches: Vec<SwitchDef>,
+}
+
+/// The animations hardcoded in vanilla Doom, as `(namespace, first, last)`. Every frame lasts 8 tics.
+const VANILLA_ANIMATIONS: &[(ImageNamespace, &str, &str)] = &[
+    (ImageNamespace::Flat, "NUKAGE1", "NUKAGE3"),
+    (ImageNamespace::Flat, "FWATER1", "FWATER4"),
+    (ImageNamespace::Flat, "SWATER1", "SWATER4"),
+    (ImageNamespace::Flat, "LAVA1", "LAVA4"),
+    (ImageNamespace::Flat, "BLOOD1", "BLOOD3"),
+    (ImageNamespace::Flat, "RROCK05", "RROCK08"),
+    (ImageNamespace::Flat, "SLIME01", "SLIME04"),
+    (ImageNamespace::Flat, "SLIME05", "SLIME08"),
+    (ImageNamespace::Flat, "SLIME09", "SLIME12"),
+    (ImageNamespace::Texture, "BLODGR1", "BLODGR4"),
+    (ImageNamespace::Texture, "SLADRIP1", "SLADRIP3"),
+    (ImageNamespace::Texture, "BLODRIP1", "BLODRIP4"),
+    (ImageNamespace::Texture, "FIREWALA", "FIREWALL"),
+    (ImageNamespace::Texture, "GSTFONT1", "GSTFONT3"),
+    (ImageNamespace::Texture, "FIRELAV3", "FIRELAVA"),
+    (ImageNamespace::Texture, "FIREMAG1", "FIREMAG3"),
+    (ImageNamespace::Texture, "FIREBLU1", "FIREBLU2"),
+    (ImageNamespace::Texture, "ROCKRED1", "ROCKRED3"),
+    (ImageNamespace::Texture, "BFALL1", "BFALL4"),
+    (ImageNamespace::Texture, "SFALL1", "SFALL4"),
+    (ImageNamespace::Texture, "WFALL1", "WFALL4"),
+    (ImageNamespace::Texture, "DBRAIN1", "DBRAIN4"),
+];
+
+/// The switches hardcoded in vanilla Doom, as `(off, on, game)`
+const VANILLA_SWITCHES: &[(&str, &str, i16)] = &[
+    ("SW1BRCOM", "SW2BRCOM", 1),
+    ("SW1BRN1", "SW2BRN1", 1),
+    ("SW1BRN2", "SW2BRN2", 1),
+    ("SW1BRNGN", "SW2BRNGN", 1),
+    ("SW1BROWN", "SW2BROWN", 1),
+    ("SW1COMM", "SW2COMM", 1),
+    ("SW1COMP", "SW2COMP", 1),
+    ("SW1DIRT", "SW2DIRT", 1),
+    ("SW1EXIT", "SW2EXIT", 1),
+    ("SW1GRAY", "SW2GRAY", 1),
+    ("SW1GRAY1", "SW2GRAY1", 1),
+    ("SW1METAL", "SW2METAL", 1),
+    ("SW1PIPE", "SW2PIPE", 1),
+    ("SW1SLAD", "SW2SLAD", 1),
+    ("SW1STARG", "SW2STARG", 1),
+    ("SW1STON1", "SW2STON1", 1),
+    ("SW1STON2", "SW2STON2", 1),
+    ("SW1STONE", "SW2STONE", 1),
+    ("SW1STRTN", "SW2STRTN", 1),
+    ("SW1BLUE", "SW2BLUE", 2),
+    ("SW1CMT", "SW2CMT", 2),
+    ("SW1GARG", "SW2GARG", 2),
+    ("SW1GSTON", "SW2GSTON", 2),
+    ("SW1HOT", "SW2HOT", 2),
+    ("SW1LION", "SW2LION", 2),
+    ("SW1SATYR", "SW2SATYR", 2),
+    ("SW1SKIN", "SW2SKIN", 2),
+    ("SW1VINE", "SW2VINE", 2),
+    ("SW1WOOD", "SW2WOOD", 2),
+    ("SW1PANEL", "SW2PANEL", 3),
+    ("SW1ROCK", "SW2ROCK", 3),
+    ("SW1MET2", "SW2MET2", 3),
+    ("SW1WDMET", "SW2WDMET", 3),
+    ("SW1BRIK", "SW2BRIK", 3),
+    ("SW1MOD1", "SW2MOD1", 3),
+    ("SW1ZIM", "SW2ZIM", 3),
+    ("SW1STON6", "SW2STON6", 3),
+    ("SW1TEK", "SW2TEK", 3),
+    ("SW1MARB", "SW2MARB", 3),
+    ("SW1SKULL", "SW2SKULL", 3),
+];
+
+/// The animations built into vanilla Doom, used when no archive has an ANIMATED lump
+pub fn vanilla_animations() -> Vec<Animation> {
+    VANILLA_ANIMATIONS
+        .iter()
+        .map(|&(namespace, first, last)| Animation {
+            namespace,
+            frames: AnimationFrames::Range {
+                first: String8::new_unchecked(first),
+                last: String8::new_unchecked(last),
+                tics: 8,
+            },
+        })
+        .collect()
+}
+
+/// The switches built into vanilla Doom, used when no archive has a SWITCHES lump
+pub fn vanilla_switches() -> Vec<SwitchDef> {
+    VANILLA_SWITCHES
+        .iter()
+        .map(|&(off, on, game)| SwitchDef {
+            off: String8::new_unchecked(off),
+            on: String8::new_unchecked(on),
+            game,
+        })
+        .collect()
+}
+
+/// Read a nul-terminated name stored in 9 bytes, as in Boom's binary lumps
+fn read_name9(reader: &mut LumpReader) -> Result<String8, LumpError> {
+    let name = reader.string8()?;
+    reader.u8()?;
+    Ok(name)
+}
+
+fn write_name9<W: Write>(writer: &mut W, name: &String8) -> io::Result<()> {
+    writer.write_string8(name)?;
+    writer.write_u8(0)
+}
+
+/// Parse Boom's binary ANIMATED lump, which replaces the hardcoded animations
+pub fn parse_animated(bytes: &[u8]) -> Result<Vec<Animation>, LumpError> {
+    let mut reader = LumpReader::new("ANIMATED", bytes);
+    let mut animations = Vec::new();
+
+    loop {
+        let kind = reader.u8()?;
+        let namespace = match kind {
+            0xff => break,
+            0 => ImageNamespace::Flat,
+            // 2 also allows decals in ZDoom
+            1 | 2 => ImageNamespace::Texture,
+            _ => return Err(reader.invalid("animation type", kind.into())),
+        };
+
+        let last = read_name9(&mut reader)?;
+        let first = read_name9(&mut reader)?;
+        let tics = reader.i32()?;
+        let tics = u16::try_from(tics).map_err(|_| reader.invalid("speed", tics.into()))?;
+
+        animations.push(Animation {
+            namespace,
+            frames: AnimationFrames::Range { first, last, tics },
+        });
+    }
+
+    Ok(animations)
+}
+
+/// Write an ANIMATED lump. Fails for animations with explicit pics, which it can't represent.
+pub fn write_animated<W: Write>(writer: &mut W, animations: &[Animation]) -> io::Result<()> {
+    for animation in animations {
+        let AnimationFrames::Range { first, last, tics } = &animation.frames else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "ANIMATED can only store ranges of frames",
+            ));
+        };
+
+        writer.write_u8((animation.namespace == ImageNamespace::Texture).into())?;
+        write_name9(writer, last)?;
+        write_name9(writer, first)?;
+        writer.write_i32((*tics).into())?;
+    }
+
+    writer.write_u8(0xff)
+}
+
+/// Parse Boom's binary SWITCHES lump, which replaces the hardcoded switches
+pub fn parse_switches(bytes: &[u8]) -> Result<Vec<SwitchDef>, LumpError> {
+    let mut reader = LumpReader::new("SWITCHES", bytes);
+    let mut switches = Vec::new();
+
+    loop {
+        let off = read_name9(&mut reader)?;
+        let on = read_name9(&mut reader)?;
+        let game = reader.i16()?;
+
+        if game == 0 {
+            break;
+        }
+
+        switches.push(SwitchDef { off, on, game });
+    }
+
+    Ok(switches)
+}
+
+pub fn write_switches<W: Write>(writer: &mut W, switches: &[SwitchDef]) -> io::Result<()> {
+    for switch in switches {
+        write_name9(writer, &switch.off)?;
+        write_name9(writer, &switch.on)?;
+        writer.write_i16(switch.game)?;
+    }
+
+    writer.write_all(&[0; 20])
+}
+
+#[derive(Debug, thiserror::Error, Diagnostic)]
+pub enum AnimDefsError {
+    #[error("Expected {expected}, found {found:?}")]
+    #[diagnostic(code(waddle::animdefs::unexpected_token))]
+    UnexpectedToken {
+        expected: &'static str,
+        found: String,
+        #[label("Expected {expected} here")]
+        span: Range<usize>,
+    },
+
+    #[error("Expected {expected}, but ANIMDEFS ended")]
+    #[diagnostic(code(waddle::animdefs::unexpected_end))]
+    UnexpectedEnd { expected: &'static str },
+}
+
+/// A cursor over ANIMDEFS tokens
+struct Tokens<'s> {
+    tokens: Vec<Token<'s>>,
+    position: usize,
+}
+
+impl<'s> Tokens<'s> {
+    fn peek(&self) -> Option<&'s str> {
+        self.tokens.get(self.position).map(|token| token.text)
+    }
+
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        self.peek()
+            .is_some_and(|text| text.eq_ignore_ascii_case(keyword))
+    }
+
+    fn next(&mut self, expected: &'static str) -> Result<&Token<'s>, AnimDefsError> {
+        let token = self
+            .tokens
+            .get(self.position)
+            .ok_or(AnimDefsError::UnexpectedEnd { expected })?;
+        self.position += 1;
+
+        Ok(token)
+    }
+
+    fn expect_keyword(&mut self, keyword: &'static str) -> Result<(), AnimDefsError> {
+        let token = self.next(keyword)?;
+
+        if token.text.eq_ignore_ascii_case(keyword) {
+            Ok(())
+        } else {
+            Err(unexpected(keyword, token))
+        }
+    }
+
+    fn name(&mut self) -> Result<String8, AnimDefsError> {
+        let token = self.next("an image name")?;
+
+        String8::new(&token.text.to_ascii_uppercase())
+            .map_err(|_| unexpected("an image name", token))
+    }
+
+    fn number(&mut self) -> Result<u16, AnimDefsError> {
+        let token = self.next("a number")?;
+        token
+            .text
+            .parse()
+            .map_err(|_| unexpected("a number", token))
+    }
+
+    /// `tics <n>` or `rand <min> <max>`, as `(min, max)`
+    fn duration(&mut self) -> Result<(u16, u16), AnimDefsError> {
+        let token = self.next("tics or rand")?;
+
+        if token.text.eq_ignore_ascii_case("tics") {
+            let tics = self.number()?;
+            Ok((tics, tics))
+        } else if token.text.eq_ignore_ascii_case("rand") {
+            Ok((self.number()?, self.number()?))
+        } else {
+            Err(unexpected("tics or rand", token))
+        }
+    }
+}
+
+fn unexpected(expected: &'static str, token: &Token) -> AnimDefsError {
+    AnimDefsError::UnexpectedToken {
+        expected,
+        found: token.text.to_owned(),
+        span: token.span.clone(),
+    }
+}
+
+/// Keywords that start a definition in ANIMDEFS
+const DEFINITION_KEYWORDS: &[&str] = &[
+    "flat",
+    "texture",
+    "switch",
+    "warp",
+    "warp2",
+    "cameratexture",
+    "animateddoor",
+    "skyoffset",
+];
+
+impl AnimDefs {
+    /// Parse ZDoom's textual ANIMDEFS. Animations and switches are supported; other definitions are skipped.
+    pub fn parse(contents: &str) -> Result<Self, AnimDefsError> {
+        let mut tokens = Tokens {
+            tokens: tokenize(contents),
+            position: 0,
+        };
+        let mut defs = AnimDefs::default();
+
+        while let Some(keyword) = tokens.peek() {
+            let keyword = keyword.to_ascii_lowercase();
+            tokens.position += 1;
+
+            match keyword.as_str() {
+                "flat" | "texture" => {
+                    let namespace = if keyword == "flat" {
+                        ImageNamespace::Flat
+                    } else {
+                        ImageNamespace::Texture
+                    };
+
+                    if let Some(frames) = Self::parse_frames(&mut tokens)? {
+                        defs.animations.push(Animation { namespace, frames });
+                    }
+                }
+                "switch" => defs.switches.push(Self::parse_switch(&mut tokens)?),
+                _ => Self::skip_definition(&mut tokens),
+            }
+        }
+
+        Ok(defs)
+    }
+
+    fn parse_frames(tokens: &mut Tokens) -> Result<Option<AnimationFrames>, AnimDefsError> {
+        if tokens.peek_keyword("optional") {
+            tokens.position += 1;
+        }
+        let base = tokens.name()?;
+
+        if tokens.peek_keyword("range") {
+            tokens.position += 1;
+            let last = tokens.name()?;
+            let (tics, _) = tokens.duration()?;
+
+            Self::skip_definition(tokens);
+            return Ok(Some(AnimationFrames::Range {
+                first: base,
+                last,
+                tics,
+            }));
+        }
+
+        let mut pics = Vec::new();
+        while tokens.peek_keyword("pic") {
+            tokens.position += 1;
+
+            let token = tokens.next("a pic")?;
+            let pic = match token.text.parse() {
+                Ok(number) => Pic::Number(number),
+                Err(_) => Pic::Name(
+                    String8::new(&token.text.to_ascii_uppercase())
+                        .map_err(|_| unexpected("a pic", token))?,
+                ),
+            };
+            let (min_tics, max_tics) = tokens.duration()?;
+
+            pics.push(AnimationPic {
+                pic,
+                min_tics,
+                max_tics,
+            });
+        }
+
+        Self::skip_definition(tokens);
+        Ok((!pics.is_empty()).then_some(AnimationFrames::Pics { base, pics }))
+    }
+
+    /// `switch [game] <off> on [sound <sound>] pic <on> tics <n> ...`, keeping the first `on` pic
+    fn parse_switch(tokens: &mut Tokens) -> Result<SwitchDef, AnimDefsError> {
+        if ["doom", "heretic", "hexen", "strife", "any"]
+            .iter()
+            .any(|game| tokens.peek_keyword(game))
+        {
+            tokens.position += 1;
+
+            // Some games are followed by a version number
+            if tokens
+                .peek()
+                .is_some_and(|text| text.parse::<u16>().is_ok())
+            {
+                tokens.position += 1;
+            }
+        }
+
+        let off = tokens.name()?;
+        tokens.expect_keyword("on")?;
+
+        if tokens.peek_keyword("sound") {
+            tokens.position += 2;
+        }
+        tokens.expect_keyword("pic")?;
+        let on = tokens.name()?;
+
+        Self::skip_definition(tokens);
+        Ok(SwitchDef { off, on, game: 0 })
+    }
+
+    /// Skip tokens up to the start of the next definition
+    fn skip_definition(tokens: &mut Tokens) {
+        while tokens.peek().is_some_and(|text| {
+            !DEFINITION_KEYWORDS
+                .iter()
+                .any(|keyword| text.eq_ignore_ascii_case(keyword))
+        }) {
+            tokens.position += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn animation_lumps() {
+        let animations = vanilla_animations();
+        let mut bytes = Vec::new();
+        write_animated(&mut bytes, &animations).unwrap();
+        assert_eq!(bytes.len(), 23 * animations.len() + 1);
+        assert_eq!(parse_animated(&bytes).unwrap(), animations);
+
+        let switches = vanilla_switches();
+        let mut bytes = Vec::new();
+        write_switches(&mut bytes, &switches).unwrap();
+        assert_eq!(parse_switches(&bytes).unwrap(), switches);
+
+        let defs = AnimDefs::parse(
+            r#"
+            // Slime
+            flat NUKAGE1 range NUKAGE3 tics 8
+            texture optional "FIREBLU1"
+                pic 1 tics 4
+                pic FIREBLU2 rand 2 6
+                allowdecals
+            warp flat WATER1 /* skipped */
+            switch doom 1 SW1BRCOM on sound switches/normbutn pic SW2BRCOM tics 0
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(defs.animations.len(), 2);
+        assert_eq!(defs.animations[0], animations[0]);
+        assert_eq!(
+            defs.animations[1].frames,
+            AnimationFrames::Pics {
+                base: String8::new_unchecked("FIREBLU1"),
+                pics: vec![
+                    AnimationPic {
+                        pic: Pic::Number(1),
+                        min_tics: 4,
+                        max_tics: 4,
+                    },
+                    AnimationPic {
+                        pic: Pic::Name(String8::new_unchecked("FIREBLU2")),
+                        min_tics: 2,
+                        max_tics: 6,
+                    },
+                ],
+            }
+        );
+        assert_eq!(defs.switches[0].on, String8::new_unchecked("SW2BRCOM"));
+
+        assert!(matches!(
+            AnimDefs::parse("flat NUKAGE1 range NUKAGE3 tics fast"),
+            Err(AnimDefsError::UnexpectedToken { span, .. }) if span == (32..36)
+        ));
+    }
+}
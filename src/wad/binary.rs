@@ -0,0 +1,286 @@
+use std::{
+    fmt::Write as _,
+    io::{self, Write},
+    ops::Range,
+};
+
+use crate::{wad::LumpError, String8};
+
+/// A cursor over the bytes of a lump, reading little-endian values. Every read is bounds-checked, and errors carry
+/// the lump name, the offset of the failed read and a hex dump of the bytes around it.
+pub(crate) struct LumpReader<'a> {
+    lump: &'static str,
+    bytes: &'a [u8],
+    offset: usize,
+    /// The offset of the last value read
+    value_start: usize,
+    lump_start: Option<u64>,
+}
+
+impl<'a> LumpReader<'a> {
+    pub fn new(lump: &'static str, bytes: &'a [u8]) -> Self {
+        Self {
+            lump,
+            bytes,
+            offset: 0,
+            value_start: 0,
+            lump_start: None,
+        }
+    }
+
+    /// Report errors with file offsets, for a lump stored at `lump_start` in its file
+    pub fn with_start(mut self, lump_start: u64) -> Self {
+        self.lump_start = Some(lump_start);
+        self
+    }
+
+    /// The position of the next read
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn seek(&mut self, offset: usize) -> Result<(), LumpError> {
+        if offset > self.bytes.len() {
+            return Err(self.invalid("offset", offset as i64));
+        }
+
+        self.offset = offset;
+        Ok(())
+    }
+
+    pub fn bytes<const N: usize>(&mut self) -> Result<[u8; N], LumpError> {
+        let bytes = self
+            .bytes
+            .get(self.offset..self.offset + N)
+            .ok_or_else(|| {
+                let (hex, span) = hex_context(self.bytes, self.offset..self.bytes.len());
+
+                LumpError::UnexpectedEnd {
+                    lump: self.lump.to_owned(),
+                    offset: self.offset,
+                    needed: self.offset + N - self.bytes.len(),
+                    lump_start: self.lump_start,
+                    hex,
+                    span,
+                }
+            })?;
+
+        self.value_start = self.offset;
+        self.offset += N;
+        Ok(bytes.try_into().unwrap())
+    }
+
+    pub fn u8(&mut self) -> Result<u8, LumpError> {
+        self.bytes().map(|[b]| b)
+    }
+
+    pub fn i8(&mut self) -> Result<i8, LumpError> {
+        self.bytes().map(i8::from_le_bytes)
+    }
+
+    pub fn i16(&mut self) -> Result<i16, LumpError> {
+        self.bytes().map(i16::from_le_bytes)
+    }
+
+    pub fn u16(&mut self) -> Result<u16, LumpError> {
+        self.bytes().map(u16::from_le_bytes)
+    }
+
+    pub fn i32(&mut self) -> Result<i32, LumpError> {
+        self.bytes().map(i32::from_le_bytes)
+    }
+
+    pub fn u32(&mut self) -> Result<u32, LumpError> {
+        self.bytes().map(u32::from_le_bytes)
+    }
+
+    pub fn u64(&mut self) -> Result<u64, LumpError> {
+        self.bytes().map(u64::from_le_bytes)
+    }
+
+    pub fn f64(&mut self) -> Result<f64, LumpError> {
+        self.bytes().map(f64::from_le_bytes)
+    }
+
+    pub fn string8(&mut self) -> Result<String8, LumpError> {
+        self.bytes().map(String8::from_raw_parts)
+    }
+
+    /// The bytes after the current position
+    pub fn remaining(&self) -> &'a [u8] {
+        &self.bytes[self.offset..]
+    }
+
+    /// Read a count, which must not be negative
+    pub fn count(&mut self, field: &'static str) -> Result<usize, LumpError> {
+        let count = self.i32()?;
+        usize::try_from(count).map_err(|_| self.invalid(field, count.into()))
+    }
+
+    /// An `InvalidValue` error for the value just read
+    pub fn invalid(&self, field: &'static str, value: i64) -> LumpError {
+        let (hex, span) = hex_context(self.bytes, self.value_start..self.offset);
+
+        LumpError::InvalidValue {
+            lump: self.lump.to_owned(),
+            field,
+            value,
+            offset: self.value_start,
+            lump_start: self.lump_start,
+            hex,
+            span,
+        }
+    }
+}
+
+/// A hex dump of the lines of `bytes` around `range`, along with the span of `range` in the dump. Lines are labelled
+/// with their offset in `bytes`.
+fn hex_context(bytes: &[u8], range: Range<usize>) -> (String, Range<usize>) {
+    const LINE_LEN: usize = 16;
+
+    let first_line = (range.start / LINE_LEN).saturating_sub(1);
+    let last_line = range.end.max(range.start + 1).div_ceil(LINE_LEN) + 1;
+    let lines = bytes
+        .chunks(LINE_LEN)
+        .enumerate()
+        .take(last_line)
+        .skip(first_line);
+
+    let mut dump = String::new();
+    let mut span = None;
+    for (line, chunk) in lines {
+        if !dump.is_empty() {
+            dump.push('\n');
+        }
+        write!(dump, "{:08x} ", line * LINE_LEN).unwrap();
+
+        for (i, byte) in chunk.iter().enumerate() {
+            let position = line * LINE_LEN + i;
+            dump.push(' ');
+
+            if position == range.start {
+                span = Some(dump.len()..dump.len());
+            }
+            write!(dump, "{byte:02x}").unwrap();
+            if let Some(span) = span.as_mut().filter(|_| position < range.end) {
+                span.end = dump.len();
+            }
+        }
+    }
+
+    let span = span.unwrap_or(dump.len()..dump.len());
+    (dump, span)
+}
+
+/// Little-endian writes, the counterpart of `LumpReader`
+pub(crate) trait WriteLe: Write {
+    fn write_u8(&mut self, value: u8) -> io::Result<()> {
+        self.write_all(&[value])
+    }
+
+    fn write_i8(&mut self, value: i8) -> io::Result<()> {
+        self.write_all(&value.to_le_bytes())
+    }
+
+    fn write_i16(&mut self, value: i16) -> io::Result<()> {
+        self.write_all(&value.to_le_bytes())
+    }
+
+    fn write_u16(&mut self, value: u16) -> io::Result<()> {
+        self.write_all(&value.to_le_bytes())
+    }
+
+    fn write_i32(&mut self, value: i32) -> io::Result<()> {
+        self.write_all(&value.to_le_bytes())
+    }
+
+    fn write_u32(&mut self, value: u32) -> io::Result<()> {
+        self.write_all(&value.to_le_bytes())
+    }
+
+    fn write_u64(&mut self, value: u64) -> io::Result<()> {
+        self.write_all(&value.to_le_bytes())
+    }
+
+    fn write_f64(&mut self, value: f64) -> io::Result<()> {
+        self.write_all(&value.to_le_bytes())
+    }
+
+    fn write_string8(&mut self, value: &String8) -> io::Result<()> {
+        self.write_all(value.as_bytes())
+    }
+
+    /// Write a count or offset as the `i32` used by WAD structures, failing if it doesn't fit
+    fn write_count(&mut self, value: usize) -> io::Result<()> {
+        let value = i32::try_from(value).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{value} doesn't fit in a 32-bit field"),
+            )
+        })?;
+
+        self.write_i32(value)
+    }
+}
+
+impl<W: Write + ?Sized> WriteLe for W {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip_and_bounds() {
+        let mut bytes = Vec::new();
+        bytes.write_u8(7).unwrap();
+        bytes.write_i16(-2).unwrap();
+        bytes.write_count(1 << 20).unwrap();
+        bytes.write_f64(0.5).unwrap();
+        bytes
+            .write_string8(&String8::new_unchecked("PLAYPAL"))
+            .unwrap();
+
+        let mut reader = LumpReader::new("TEST", &bytes);
+        assert_eq!(reader.u8().unwrap(), 7);
+        assert_eq!(reader.i16().unwrap(), -2);
+        assert_eq!(reader.count("count").unwrap(), 1 << 20);
+        assert_eq!(reader.f64().unwrap(), 0.5);
+        assert_eq!(reader.string8().unwrap(), String8::new_unchecked("PLAYPAL"));
+
+        match reader.u16() {
+            Err(LumpError::UnexpectedEnd {
+                lump,
+                offset,
+                needed,
+                ..
+            }) => assert_eq!((lump.as_str(), offset, needed), ("TEST", 23, 2)),
+            result => panic!("unexpected {result:?}"),
+        }
+
+        assert!(Vec::new().write_count(usize::MAX).is_err());
+    }
+
+    #[test]
+    fn errors_carry_hex_context() {
+        let bytes: Vec<u8> = (0..40).collect();
+        let mut reader = LumpReader::new("TEST", &bytes).with_start(0x100);
+        reader.seek(20).unwrap();
+        reader.i16().unwrap();
+
+        let error = reader
+            .invalid("thing", -1)
+            .locate(&String8::new_unchecked("THINGS"), 0x200);
+        assert_eq!(error.file_offset(), Some(0x214));
+        assert_eq!(
+            error.to_string(),
+            "THINGS (TEST) has an invalid thing of -1 at byte 20 (file offset 0x214)"
+        );
+
+        let LumpError::InvalidValue { hex, span, .. } = error else {
+            unreachable!()
+        };
+        assert_eq!(hex.lines().count(), 3);
+        assert!(hex.starts_with("00000000  00 01"));
+        assert_eq!(&hex[span], "14 15");
+    }
+}
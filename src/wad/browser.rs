@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+
+use crate::{
+    wad::resource_set::{ImageNamespace, ImageSize, ResourceSet},
+    String8,
+};
+
+/// A texture or flat listed in a `TextureBrowser`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BrowserEntry {
+    /// The name in uppercase
+    pub name: String8,
+    pub namespace: ImageNamespace,
+    pub size: ImageSize,
+    /// The index in `ResourceSet::archives` of the archive with the definition in use
+    pub archive: usize,
+    /// The first frame of the animation this image is part of, if any
+    pub animation_group: Option<String8>,
+}
+
+/// Criteria for `TextureBrowser::filter`. Every criterion that is set must match.
+#[derive(Clone, Debug, Default)]
+pub struct BrowserFilter {
+    pub namespace: Option<ImageNamespace>,
+    /// A substring of the name, ignoring case
+    pub name_contains: Option<String>,
+    pub archive: Option<usize>,
+    /// Whether the image must be (or must not be) part of an animation
+    pub animated: Option<bool>,
+}
+
+impl BrowserFilter {
+    pub fn matches(&self, entry: &BrowserEntry) -> bool {
+        self.namespace
+            .is_none_or(|namespace| entry.namespace == namespace)
+            && self.archive.is_none_or(|archive| entry.archive == archive)
+            && self
+                .animated
+                .is_none_or(|animated| entry.animation_group.is_some() == animated)
+            && self.name_contains.as_ref().is_none_or(|needle| {
+                let name = entry.name.as_bytes_trimmed();
+                let needle = needle.to_ascii_uppercase();
+
+                needle.is_empty()
+                    || name
+                        .windows(needle.len())
+                        .any(|window| window == needle.as_bytes())
+            })
+    }
+}
+
+/// The order of entries in a `TextureBrowser`. Ties are broken by name.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BrowserSort {
+    Name,
+    /// By area, smallest first
+    Size,
+    /// By defining archive, in load order
+    Archive,
+}
+
+/// Every texture and flat available in a `ResourceSet`, as listed by an editor's texture browser
+#[derive(Clone, Debug, Default)]
+pub struct TextureBrowser {
+    entries: Vec<BrowserEntry>,
+}
+
+impl TextureBrowser {
+    /// List the images of `resources`, textures first, each in definition order
+    pub fn new(resources: &ResourceSet) -> Self {
+        let mut groups = HashMap::new();
+        for animation in resources.animations() {
+            let frames = resources.animation_frames(&animation);
+
+            if let Some(first) = frames.first() {
+                for frame in frames.iter() {
+                    groups.insert((animation.namespace, frame.clone()), first.clone());
+                }
+            }
+        }
+
+        let entries = [ImageNamespace::Texture, ImageNamespace::Flat]
+            .into_iter()
+            .flat_map(|namespace| {
+                resources
+                    .images(namespace)
+                    .into_iter()
+                    .map(move |info| (namespace, info))
+            })
+            .map(|(namespace, info)| BrowserEntry {
+                animation_group: groups.get(&(namespace, info.name.clone())).cloned(),
+                name: info.name,
+                namespace,
+                size: info.size,
+                archive: info.archive,
+            })
+            .collect();
+
+        Self { entries }
+    }
+
+    pub fn entries(&self) -> &[BrowserEntry] {
+        &self.entries
+    }
+
+    pub fn filter<'b>(
+        &'b self,
+        filter: &'b BrowserFilter,
+    ) -> impl Iterator<Item = &'b BrowserEntry> + 'b {
+        self.entries.iter().filter(|entry| filter.matches(entry))
+    }
+
+    pub fn sort(&mut self, sort: BrowserSort) {
+        match sort {
+            BrowserSort::Name => self.entries.sort_by(|a, b| a.name.cmp(&b.name)),
+            BrowserSort::Size => self.entries.sort_by(|a, b| {
+                let area = |entry: &BrowserEntry| {
+                    u32::from(entry.size.width) * u32::from(entry.size.height)
+                };
+                area(a).cmp(&area(b)).then_with(|| a.name.cmp(&b.name))
+            }),
+            BrowserSort::Archive => self
+                .entries
+                .sort_by(|a, b| a.archive.cmp(&b.archive).then_with(|| a.name.cmp(&b.name))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::wad::{Lump, Wad, WadKind};
+
+    #[test]
+    fn browse_flats() {
+        let mut iwad = Wad::new(WadKind::Iwad);
+        iwad.lumps = [
+            "F_START", "NUKAGE1", "NUKAGE2", "NUKAGE3", "FLOOR0_1", "F_END",
+        ]
+        .into_iter()
+        .map(|name| {
+            let data = if name.ends_with("_START") || name.ends_with("_END") {
+                Vec::new()
+            } else {
+                vec![0; 64 * 64]
+            };
+            Lump::new(String8::new_unchecked(name), data)
+        })
+        .collect();
+
+        let mut pwad = Wad::new(WadKind::Pwad);
+        pwad.lumps = vec![
+            Lump::new(String8::new_unchecked("FF_START"), Vec::new()),
+            Lump::new(String8::new_unchecked("FLOOR0_1"), vec![0; 128 * 128]),
+            Lump::new(String8::new_unchecked("FF_END"), Vec::new()),
+        ];
+
+        let mut resources = ResourceSet::new();
+        resources.push(iwad);
+        resources.push(pwad);
+
+        let mut browser = TextureBrowser::new(&resources);
+        let nukage = String8::new_unchecked("NUKAGE1");
+        assert_eq!(browser.entries().len(), 4);
+        assert!(browser.entries()[..3]
+            .iter()
+            .all(|entry| entry.animation_group.as_ref() == Some(&nukage)));
+
+        let filter = BrowserFilter {
+            name_contains: Some("floor".to_owned()),
+            ..Default::default()
+        };
+        let floors: Vec<_> = browser.filter(&filter).collect();
+        assert_eq!(floors.len(), 1);
+        assert_eq!(floors[0].archive, 1);
+        assert_eq!(floors[0].animation_group, None);
+
+        browser.sort(BrowserSort::Size);
+        assert_eq!(
+            browser.entries().last().unwrap().name,
+            String8::new_unchecked("FLOOR0_1")
+        );
+    }
+}
@@ -0,0 +1,278 @@
+use std::borrow::Cow;
+
+use crate::{
+    map::thing_type::{Games, ThingCategory, ThingInfo, ThingKind, ThingTypes},
+    wad::script::{tokenize, Token},
+};
+
+/// An actor class found by `scan_decorate` or `scan_zscript`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ActorDef {
+    pub name: String,
+    pub parent: Option<String>,
+    /// The class this actor replaces, if any
+    pub replaces: Option<String>,
+    /// The editor number, which DECORATE declares inline. ZScript classes get theirs from MAPINFO's DoomEdNums.
+    pub ed_num: Option<i16>,
+    pub radius: Option<u16>,
+    pub height: Option<u16>,
+    /// Whether the actor sets the `Monster` combo or the `ISMONSTER` flag
+    pub monster: bool,
+}
+
+/// Extract the actors of a DECORATE lump. The scan is lightweight: states and most properties are ignored.
+pub fn scan_decorate(contents: &str) -> Vec<ActorDef> {
+    scan(contents, "actor")
+}
+
+/// Extract the classes of a ZScript lump. This is tolerant, and only looks at class headers and simple properties.
+pub fn scan_zscript(contents: &str) -> Vec<ActorDef> {
+    scan(contents, "class")
+}
+
+fn scan(contents: &str, keyword: &str) -> Vec<ActorDef> {
+    let tokens = tokenize(contents);
+    let mut actors = Vec::new();
+    let mut position = 0;
+
+    while position < tokens.len() {
+        // `extend class` adds to an existing class rather than declaring one
+        let extends = position > 0 && tokens[position - 1].is("extend");
+        if !tokens[position].is(keyword) || extends {
+            position += 1;
+            continue;
+        }
+
+        let Some(open) = tokens[position..]
+            .iter()
+            .position(|token| token.is("{") || token.is(";"))
+            .map(|open| position + open)
+        else {
+            break;
+        };
+
+        let mut actor = parse_header(&tokens[position + 1..open]);
+        let close = matching_brace(&tokens, open);
+        read_properties(&tokens[open..close], &mut actor);
+
+        if !actor.name.is_empty() {
+            actors.push(actor);
+        }
+        position = close;
+    }
+
+    actors
+}
+
+/// `Name [: Parent] [replaces Other] [ednum] [native]`
+fn parse_header(tokens: &[Token]) -> ActorDef {
+    let mut actor = ActorDef::default();
+    let mut tokens = tokens.iter();
+
+    if let Some(name) = tokens.next() {
+        actor.name = name.text.to_owned();
+    }
+
+    while let Some(token) = tokens.next() {
+        if token.is(":") {
+            actor.parent = tokens.next().map(|parent| parent.text.to_owned());
+        } else if token.is("replaces") {
+            actor.replaces = tokens.next().map(|replaced| replaced.text.to_owned());
+        } else if let Ok(ed_num) = token.text.parse() {
+            actor.ed_num = Some(ed_num);
+        }
+    }
+
+    actor
+}
+
+/// The index just after the brace closing the one at `open`, or just after `open` if it isn't a brace
+fn matching_brace(tokens: &[Token], open: usize) -> usize {
+    if !tokens[open].is("{") {
+        return open + 1;
+    }
+
+    let mut depth = 0;
+    for (i, token) in tokens.iter().enumerate().skip(open) {
+        if token.is("{") {
+            depth += 1;
+        } else if token.is("}") {
+            depth -= 1;
+            if depth == 0 {
+                return i + 1;
+            }
+        }
+    }
+
+    tokens.len()
+}
+
+fn read_properties(tokens: &[Token], actor: &mut ActorDef) {
+    for (i, token) in tokens.iter().enumerate() {
+        let number = || tokens.get(i + 1).and_then(|value| value.text.parse().ok());
+
+        if token.is("radius") {
+            actor.radius = number().or(actor.radius);
+        } else if token.is("height") {
+            actor.height = number().or(actor.height);
+        } else if token.is("monster") || token.is("+ismonster") {
+            actor.monster = true;
+        }
+    }
+}
+
+/// Give actors the editor numbers assigned to them in MAPINFO, e.g. `MapInfo::ed_nums`, which take precedence over
+/// those declared in DECORATE
+pub fn assign_ed_nums(actors: &mut [ActorDef], ed_nums: &[(i16, String)]) {
+    for (ed_num, class) in ed_nums {
+        for actor in actors.iter_mut() {
+            if actor.name.eq_ignore_ascii_case(class) {
+                actor.ed_num = Some(*ed_num);
+            }
+        }
+    }
+}
+
+/// Base classes that determine the category of their descendants
+const BASE_CLASSES: &[(&str, ThingCategory)] = &[
+    ("Weapon", ThingCategory::Weapon),
+    ("Ammo", ThingCategory::Ammo),
+    ("BackpackItem", ThingCategory::Ammo),
+    ("Health", ThingCategory::Health),
+    ("HealthPickup", ThingCategory::Health),
+    ("Armor", ThingCategory::Armor),
+    ("BasicArmorPickup", ThingCategory::Armor),
+    ("BasicArmorBonus", ThingCategory::Armor),
+    ("PowerupGiver", ThingCategory::Powerup),
+    ("Key", ThingCategory::Key),
+    ("PlayerPawn", ThingCategory::Other),
+];
+
+impl ThingTypes {
+    /// Register every actor with an editor number as a `ThingKind::Custom` type, replacing built-in types with the
+    /// same number.
+    ///
+    /// Categories and sizes are inherited through `actors` and then through the existing types, matched by class.
+    /// Returns the number of types registered.
+    pub fn register_actors(&mut self, actors: &[ActorDef]) -> usize {
+        let find = |name: &str| {
+            actors
+                .iter()
+                .rev()
+                .find(|actor| actor.name.eq_ignore_ascii_case(name))
+        };
+
+        let infos: Vec<_> = actors
+            .iter()
+            .filter_map(|actor| {
+                let ed_num = actor.ed_num?;
+                let (mut category, mut radius, mut height) = (None, actor.radius, actor.height);
+
+                // Walk up the inheritance chain, bounded in case of cycles
+                let mut current = Some(actor);
+                let mut name = actor.name.as_str();
+                for _ in 0..32 {
+                    if let Some(def) = current {
+                        if def.monster {
+                            category = category.or(Some(ThingCategory::Monster));
+                        }
+                        radius = radius.or(def.radius);
+                        height = height.or(def.height);
+                    }
+
+                    if let Some(&(_, base)) = BASE_CLASSES
+                        .iter()
+                        .find(|(base, _)| base.eq_ignore_ascii_case(name))
+                    {
+                        category = category.or(Some(base));
+                    }
+                    if let Some(info) = self.by_class(name).filter(|info| info.ed_num != ed_num) {
+                        category = category.or(Some(info.category));
+                        radius = radius.or(Some(info.radius));
+                        height = height.or(Some(info.height));
+                    }
+
+                    let Some(parent) = current.and_then(|def| def.parent.as_deref()) else {
+                        break;
+                    };
+                    name = parent;
+                    current = find(parent);
+                }
+
+                Some(ThingInfo {
+                    ed_num,
+                    kind: ThingKind::Custom,
+                    class: Cow::Owned(actor.name.clone()),
+                    name: Cow::Owned(actor.name.clone()),
+                    category: category.unwrap_or(ThingCategory::Other),
+                    // The defaults of the base Actor class
+                    radius: radius.unwrap_or(20),
+                    height: height.unwrap_or(16),
+                    games: Games::ALL,
+                })
+            })
+            .collect();
+
+        let count = infos.len();
+        for info in infos {
+            self.register(info);
+        }
+
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::map::thing_type::Game;
+
+    #[test]
+    fn register_decorate_actors() {
+        let actors = scan_decorate(
+            r#"
+            #include "actors/imps.txt"
+
+            actor FastImp : DoomImp replaces DoomImp 20001
+            {
+                Speed 16
+                States
+                {
+                Spawn:
+                    TROO AB 10 A_Look
+                    Loop
+                }
+            }
+
+            ACTOR Dragon 20002 { Monster Radius 64 Height 100 }
+            actor "BigGun" : Weapon 20003 { }
+            actor Scenery { +NOBLOCKMAP }
+            "#,
+        );
+
+        assert_eq!(actors.len(), 4);
+        assert_eq!(actors[0].parent.as_deref(), Some("DoomImp"));
+        assert_eq!(actors[0].replaces.as_deref(), Some("DoomImp"));
+        assert_eq!(actors[2].name, "BigGun");
+        assert_eq!(actors[3].ed_num, None);
+
+        let mut types = ThingTypes::for_game(Game::Doom2);
+        assert_eq!(types.register_actors(&actors), 3);
+
+        let fast_imp = types.get(20001).unwrap();
+        assert_eq!(fast_imp.category, ThingCategory::Monster);
+        assert_eq!(fast_imp.radius, 20);
+        assert_eq!(types.get(20002).unwrap().height, 100);
+        assert_eq!(types.get(20003).unwrap().category, ThingCategory::Weapon);
+
+        let mut classes = scan_zscript(
+            "version \"4.0\"\nclass Spark : Actor { Default { Radius 4; +NOGRAVITY; } void Fly() { } }\nextend class Spark { }",
+        );
+        assert_eq!(classes.len(), 1);
+        assert_eq!(classes[0].radius, Some(4));
+
+        assign_ed_nums(&mut classes, &[(20004, "spark".to_owned())]);
+        assert_eq!(classes[0].ed_num, Some(20004));
+    }
+}
@@ -0,0 +1,306 @@
+use std::{
+    io::{self, Write},
+    time::Duration,
+};
+
+use crate::{
+    map::thing::Skill,
+    wad::{LumpError, LumpReader, WriteLe},
+};
+
+/// Marks the end of the tic commands in a demo
+const END_MARKER: u8 = 0x80;
+
+/// The version written by Doom 1.9
+pub const VERSION_1_9: u8 = 109;
+
+/// The version used by ports recording with `-longtics`, whose turning angles have full precision
+pub const VERSION_LONGTICS: u8 = 111;
+
+/// The number of tics per second the game runs at
+pub const TICS_PER_SECOND: u32 = 35;
+
+/// The settings a demo was recorded with
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DemoHeader {
+    /// The game version, or `None` for demos recorded before Doom 1.4, whose header doesn't have one
+    pub version: Option<u8>,
+    pub skill: Skill,
+    /// Always 1 in Doom 2
+    pub episode: u8,
+    pub map: u8,
+    /// 0 for single player and cooperative, 1 for deathmatch, 2 for altdeath
+    pub deathmatch: u8,
+    pub respawn: bool,
+    pub fast: bool,
+    pub no_monsters: bool,
+    /// The player whose view is shown during playback
+    pub console_player: u8,
+    /// Which of the four players are in the game
+    pub players: [bool; 4],
+}
+
+impl DemoHeader {
+    /// Whether turning angles are stored with full precision
+    pub fn long_tics(&self) -> bool {
+        self.version == Some(VERSION_LONGTICS)
+    }
+
+    pub fn player_count(&self) -> usize {
+        self.players.iter().filter(|&&present| present).count()
+    }
+}
+
+/// One player's input for one tic
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TicCommand {
+    /// Positive forwards
+    pub forward_move: i8,
+    /// Positive to the right
+    pub side_move: i8,
+    /// The turning angle, in 1/65536ths of a full turn, positive to the left.
+    /// Without long tics, only the high byte is recorded.
+    pub angle_turn: i16,
+    pub buttons: u8,
+}
+
+impl TicCommand {
+    pub fn attack(&self) -> bool {
+        self.buttons & 0x01 != 0
+    }
+
+    pub fn use_(&self) -> bool {
+        self.buttons & 0x02 != 0
+    }
+
+    /// Whether this is a special command, such as pausing or saving, rather than player input
+    pub fn special(&self) -> bool {
+        self.buttons & 0x80 != 0
+    }
+
+    /// The zero-based weapon slot being switched to, if any
+    pub fn weapon_change(&self) -> Option<u8> {
+        (!self.special() && self.buttons & 0x04 != 0).then_some((self.buttons >> 3) & 0x07)
+    }
+}
+
+/// A recording of a game, as the inputs of every player in every tic
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Demo {
+    pub header: DemoHeader,
+    /// For every tic, one command per player in the game, in player order
+    pub tics: Vec<Vec<TicCommand>>,
+    /// Data after the end marker, which some ports use to store extra information
+    pub footer: Vec<u8>,
+}
+
+impl Demo {
+    pub fn parse(bytes: &[u8]) -> Result<Self, LumpError> {
+        let mut reader = LumpReader::new("DEMO", bytes);
+        let header = parse_header(&mut reader)?;
+        let player_count = header.player_count();
+
+        let mut tics = Vec::new();
+        loop {
+            let first = reader.u8()?;
+            if first == END_MARKER {
+                break;
+            }
+
+            let mut commands = Vec::with_capacity(player_count);
+            for i in 0..player_count {
+                let forward_move = if i == 0 { first as i8 } else { reader.i8()? };
+                let side_move = reader.i8()?;
+                let angle_turn = if header.long_tics() {
+                    reader.i16()?
+                } else {
+                    i16::from(reader.i8()?) << 8
+                };
+                let buttons = reader.u8()?;
+
+                commands.push(TicCommand {
+                    forward_move,
+                    side_move,
+                    angle_turn,
+                    buttons,
+                });
+            }
+
+            tics.push(commands);
+        }
+
+        Ok(Self {
+            header,
+            tics,
+            footer: reader.remaining().to_vec(),
+        })
+    }
+
+    /// Write the demo. Fails if a tic doesn't have one command per player in the game.
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let header = &self.header;
+        let players = header.players.map(u8::from);
+
+        match header.version {
+            Some(version) => writer.write_all(&[
+                version,
+                skill_index(header.skill),
+                header.episode,
+                header.map,
+                header.deathmatch,
+                header.respawn.into(),
+                header.fast.into(),
+                header.no_monsters.into(),
+                header.console_player,
+            ])?,
+            None => writer.write_all(&[skill_index(header.skill), header.episode, header.map])?,
+        }
+        writer.write_all(&players)?;
+
+        for (tic, commands) in self.tics.iter().enumerate() {
+            if commands.len() != header.player_count() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "tic {tic} has {} commands, but there are {} players",
+                        commands.len(),
+                        header.player_count()
+                    ),
+                ));
+            }
+
+            for command in commands {
+                writer.write_i8(command.forward_move)?;
+                writer.write_i8(command.side_move)?;
+                if header.long_tics() {
+                    writer.write_i16(command.angle_turn)?;
+                } else {
+                    writer.write_i8((command.angle_turn >> 8) as i8)?;
+                }
+                writer.write_u8(command.buttons)?;
+            }
+        }
+
+        writer.write_u8(END_MARKER)?;
+        writer.write_all(&self.footer)
+    }
+
+    /// The commands of one player, by index in `DemoHeader::players`. Empty if the player isn't in the game.
+    pub fn player_commands(&self, player: usize) -> impl Iterator<Item = &TicCommand> + '_ {
+        let index = self
+            .header
+            .players
+            .get(player)
+            .copied()
+            .unwrap_or(false)
+            .then(|| {
+                self.header.players[..player]
+                    .iter()
+                    .filter(|&&present| present)
+                    .count()
+            });
+
+        self.tics
+            .iter()
+            .filter_map(move |commands| commands.get(index?))
+    }
+
+    /// How long the demo lasts when played back
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs(self.tics.len() as u64) / TICS_PER_SECOND
+    }
+}
+
+fn parse_header(reader: &mut LumpReader) -> Result<DemoHeader, LumpError> {
+    let first = reader.u8()?;
+
+    // Demos from before Doom 1.4 start directly with the skill level, which is at most 4
+    let (version, skill) = if first <= 4 {
+        (None, first)
+    } else if (104..=VERSION_LONGTICS).contains(&first) {
+        (Some(first), reader.u8()?)
+    } else {
+        return Err(reader.invalid("version", first.into()));
+    };
+
+    let skill = *Skill::ALL
+        .get(usize::from(skill))
+        .ok_or_else(|| reader.invalid("skill", skill.into()))?;
+    let [episode, map] = reader.bytes()?;
+
+    let (deathmatch, respawn, fast, no_monsters, console_player) = if version.is_some() {
+        let [deathmatch, respawn, fast, no_monsters, console_player] = reader.bytes()?;
+        (
+            deathmatch,
+            respawn != 0,
+            fast != 0,
+            no_monsters != 0,
+            console_player,
+        )
+    } else {
+        (0, false, false, false, 0)
+    };
+
+    let players = reader.bytes::<4>()?.map(|present| present != 0);
+
+    Ok(DemoHeader {
+        version,
+        skill,
+        episode,
+        map,
+        deathmatch,
+        respawn,
+        fast,
+        no_monsters,
+        console_player,
+        players,
+    })
+}
+
+fn skill_index(skill: Skill) -> u8 {
+    Skill::ALL.iter().position(|&s| s == skill).unwrap() as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn demo_round_trip() {
+        #[rustfmt::skip]
+        let bytes = [
+            VERSION_1_9, 3, 1, 1, 0, 0, 0, 0, 0, 1, 0, 1, 0,
+            // Player 1 runs forward while player 3 turns left and fires
+            50, 0, 0, 0, 0, 0, 4, 1,
+            // Player 1 switches to the shotgun
+            0, 0, 0, 0x04 | (2 << 3), 0, 0, 0xfc, 0,
+            END_MARKER,
+            b'x',
+        ];
+
+        let demo = Demo::parse(&bytes).unwrap();
+        assert_eq!(demo.header.skill, Skill::Skill4);
+        assert_eq!(demo.header.player_count(), 2);
+        assert_eq!(demo.tics.len(), 2);
+        assert_eq!(demo.footer, b"x");
+
+        let player_3: Vec<_> = demo.player_commands(2).collect();
+        assert_eq!(player_3[0].angle_turn, 4 << 8);
+        assert!(player_3[0].attack());
+        assert_eq!(player_3[1].angle_turn, -4 << 8);
+        assert_eq!(demo.player_commands(1).count(), 0);
+
+        let player_1: Vec<_> = demo.player_commands(0).collect();
+        assert_eq!(player_1[0].forward_move, 50);
+        assert_eq!(player_1[1].weapon_change(), Some(2));
+
+        let mut written = Vec::new();
+        demo.write(&mut written).unwrap();
+        assert_eq!(written, bytes);
+
+        assert!(matches!(
+            Demo::parse(&bytes[..bytes.len() - 2]),
+            Err(LumpError::UnexpectedEnd { .. })
+        ));
+    }
+}
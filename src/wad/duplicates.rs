@@ -0,0 +1,179 @@
+use std::{
+    collections::BTreeMap,
+    hash::{Hash, Hasher},
+};
+
+use crate::{
+    fnv::Fnv1a,
+    map::Map,
+    wad::{Lump, Wad},
+    String8,
+};
+
+/// A map in one of several archives
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct MapLocation {
+    /// The index of the archive in the slice passed to `find_duplicate_maps`
+    pub archive: usize,
+    /// The map's marker lump
+    pub map: String8,
+}
+
+/// How alike a group of `DuplicateMaps` are
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DuplicateKind {
+    /// The map lumps are byte for byte the same
+    Identical,
+    /// The maps have the same contents, as in `Map::same_contents`, but are stored differently, e.g. with other
+    /// formatting or rebuilt nodes
+    Structural,
+}
+
+/// Maps which are copies of each other
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DuplicateMaps {
+    pub kind: DuplicateKind,
+    /// The copies, in archive order
+    pub maps: Vec<MapLocation>,
+}
+
+/// Find maps which appear more than once across `archives`, e.g. because several projects were compiled from the same
+/// submission.
+///
+/// Maps are grouped by hash, and then compared to rule out collisions. Groups of identical maps are reported as
+/// `DuplicateKind::Identical`, and maps with the same contents which aren't all identical as a single
+/// `DuplicateKind::Structural` group. Maps which can't be loaded are only compared byte for byte.
+pub fn find_duplicate_maps(archives: &[Wad]) -> Vec<DuplicateMaps> {
+    let mut by_bytes: BTreeMap<u64, Vec<(MapLocation, &[Lump])>> = BTreeMap::new();
+    let mut by_contents: BTreeMap<u64, Vec<Loaded>> = BTreeMap::new();
+
+    for (archive, wad) in archives.iter().enumerate() {
+        let markers = wad
+            .lumps
+            .windows(2)
+            .enumerate()
+            .filter(|(_, pair)| pair[1].is_named("TEXTMAP") || pair[1].is_named("THINGS"));
+
+        for (marker, _) in markers {
+            let location = MapLocation {
+                archive,
+                map: wad.lumps[marker].name.clone(),
+            };
+            let lumps = wad.map_lumps(marker);
+
+            let mut hasher = Fnv1a::default();
+            for lump in lumps {
+                lump.name.hash(&mut hasher);
+                lump.data.hash(&mut hasher);
+            }
+
+            if let Ok(map) = wad.load_map(marker) {
+                by_contents.entry(map.content_hash()).or_default().push((
+                    location.clone(),
+                    lumps,
+                    map,
+                ));
+            }
+            by_bytes
+                .entry(hasher.finish())
+                .or_default()
+                .push((location, lumps));
+        }
+    }
+
+    let identical = by_bytes
+        .into_values()
+        .flat_map(|maps| confirm(maps, |a, b| a.1 == b.1))
+        .filter(|maps| maps.len() > 1)
+        .map(|maps| DuplicateMaps {
+            kind: DuplicateKind::Identical,
+            maps: maps.into_iter().map(|(location, _)| location).collect(),
+        });
+
+    let structural = by_contents
+        .into_values()
+        .flat_map(|maps| confirm(maps, |a, b| a.2.same_contents(&b.2)))
+        .filter(|maps| maps.iter().any(|(_, lumps, _)| *lumps != maps[0].1))
+        .map(|maps| DuplicateMaps {
+            kind: DuplicateKind::Structural,
+            maps: maps.into_iter().map(|(location, ..)| location).collect(),
+        });
+
+    let mut duplicates: Vec<_> = identical.chain(structural).collect();
+    duplicates.sort_by(|a, b| a.maps[0].cmp(&b.maps[0]));
+    duplicates
+}
+
+/// A map which loaded, along with where it came from and its lumps
+type Loaded<'a> = (MapLocation, &'a [Lump], Map);
+
+/// Split items with the same hash into the groups which are really equal, keeping their order
+fn confirm<T>(items: Vec<T>, eq: impl Fn(&T, &T) -> bool) -> Vec<Vec<T>> {
+    let mut groups: Vec<Vec<T>> = Vec::new();
+
+    for item in items {
+        match groups.iter_mut().find(|group| eq(&group[0], &item)) {
+            Some(group) => group.push(item),
+            None => groups.push(vec![item]),
+        }
+    }
+
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::wad::WadKind;
+
+    #[test]
+    fn find_duplicates() {
+        let textmap =
+            "namespace = \"zdoom\";\nvertex { x = 0; y = 0; }\nvertex { x = 64; y = 0; }\n";
+        let reformatted =
+            "// Rebuilt\nnamespace=\"zdoom\";\nvertex{x=0;y=0;}\nvertex{x=64.0;y=0;}\n";
+        let other = "namespace = \"zdoom\";\nvertex { x = 0; y = 0; }\n";
+
+        let wad = |maps: &[(&str, &str)]| {
+            let mut wad = Wad::new(WadKind::Pwad);
+            for (name, textmap) in maps {
+                for (lump, data) in [(*name, ""), ("TEXTMAP", textmap), ("ENDMAP", "")] {
+                    wad.lumps.push(Lump::new(
+                        String8::new_unchecked(lump),
+                        data.as_bytes().to_vec(),
+                    ));
+                }
+            }
+            wad
+        };
+        let archives = [
+            wad(&[("MAP01", textmap), ("MAP02", other)]),
+            wad(&[("MAP07", textmap), ("MAP08", reformatted)]),
+        ];
+
+        assert_eq!(archives[0].map_lumps(0).len(), 2);
+
+        let location = |archive, map| MapLocation {
+            archive,
+            map: String8::new_unchecked(map),
+        };
+        assert_eq!(
+            find_duplicate_maps(&archives),
+            vec![
+                DuplicateMaps {
+                    kind: DuplicateKind::Identical,
+                    maps: vec![location(0, "MAP01"), location(1, "MAP07")],
+                },
+                DuplicateMaps {
+                    kind: DuplicateKind::Structural,
+                    maps: vec![
+                        location(0, "MAP01"),
+                        location(1, "MAP07"),
+                        location(1, "MAP08")
+                    ],
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,187 @@
+use crate::wad::{
+    image::{pixel_index, ImageConversionError, IndexedImage, PixelOutOfBounds},
+    LumpError, LumpReader,
+};
+
+/// The sizes a flat can have, as `(width, height)`. The size of a flat lump is inferred from its length.
+///
+/// Vanilla Doom only supports 64x64 flats. Heretic and Hexen have some 64x65 flats, whose last row is ignored, and
+/// ZDoom supports taller and larger flats.
+pub const FLAT_SIZES: &[(u16, u16)] = &[(64, 64), (64, 65), (64, 128), (128, 128), (256, 256)];
+
+/// A floor or ceiling texture: a raw, opaque, paletted image
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Flat {
+    width: u16,
+    height: u16,
+    pixels: Vec<u8>,
+}
+
+impl Flat {
+    /// A flat filled with a single palette index. Fails if the size is not one of `FLAT_SIZES`.
+    pub fn new(width: u16, height: u16, fill: u8) -> Result<Self, ImageConversionError> {
+        check_size(width, height)?;
+
+        Ok(Self {
+            width,
+            height,
+            pixels: vec![fill; usize::from(width) * usize::from(height)],
+        })
+    }
+
+    pub fn parse(bytes: &[u8]) -> Result<Self, LumpError> {
+        let (width, height) = size_for_len(bytes.len())
+            .ok_or_else(|| LumpReader::new("flat", bytes).invalid("length", bytes.len() as i64))?;
+
+        Ok(Self {
+            width,
+            height,
+            pixels: bytes.to_vec(),
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.pixels.clone()
+    }
+
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    /// Palette indices in row-major order
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    pub fn pixels_mut(&mut self) -> &mut [u8] {
+        &mut self.pixels
+    }
+
+    pub fn get(&self, x: u16, y: u16) -> Result<u8, PixelOutOfBounds> {
+        Ok(self.pixels[pixel_index(self.width, self.height, self.pixels.len(), x, y)?])
+    }
+
+    pub fn set(&mut self, x: u16, y: u16, pixel: u8) -> Result<(), PixelOutOfBounds> {
+        let index = pixel_index(self.width, self.height, self.pixels.len(), x, y)?;
+        self.pixels[index] = pixel;
+        Ok(())
+    }
+}
+
+/// The size of a flat lump of the given length, as `(width, height)`, if it's one of `FLAT_SIZES`
+pub fn size_for_len(len: usize) -> Option<(u16, u16)> {
+    FLAT_SIZES
+        .iter()
+        .copied()
+        .find(|&(width, height)| usize::from(width) * usize::from(height) == len)
+}
+
+fn check_size(width: u16, height: u16) -> Result<(), ImageConversionError> {
+    if FLAT_SIZES.contains(&(width, height)) {
+        Ok(())
+    } else {
+        Err(ImageConversionError::InvalidSize {
+            format: "flat",
+            width,
+            height,
+        })
+    }
+}
+
+impl From<&Flat> for IndexedImage {
+    fn from(flat: &Flat) -> Self {
+        Self {
+            width: flat.width,
+            height: flat.height,
+            pixels: flat.pixels.iter().copied().map(Some).collect(),
+        }
+    }
+}
+
+impl TryFrom<&IndexedImage> for Flat {
+    type Error = ImageConversionError;
+
+    fn try_from(image: &IndexedImage) -> Result<Self, Self::Error> {
+        check_size(image.width, image.height)?;
+        if image.pixels.len() != usize::from(image.width) * usize::from(image.height) {
+            return Err(ImageConversionError::PixelCount {
+                width: image.width,
+                height: image.height,
+                len: image.pixels.len(),
+            });
+        }
+
+        let pixels = image
+            .pixels
+            .iter()
+            .enumerate()
+            .map(|(index, pixel)| {
+                pixel.ok_or_else(|| ImageConversionError::Transparent {
+                    format: "flat",
+                    x: (index % usize::from(image.width)) as u16,
+                    y: (index / usize::from(image.width)) as u16,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            width: image.width,
+            height: image.height,
+            pixels,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flat_conversion() {
+        let mut flat = Flat::parse(&[7; 64 * 128]).unwrap();
+        assert_eq!((flat.width(), flat.height()), (64, 128));
+        flat.set(1, 2, 9).unwrap();
+        assert_eq!(flat.get(1, 2), Ok(9));
+        assert!(flat.get(64, 0).is_err());
+        assert!(flat.set(0, 128, 9).is_err());
+
+        let mut image = IndexedImage::from(&flat);
+        assert_eq!(image.get(63, 127), Ok(Some(7)));
+        assert_eq!(image.get(1, 2), Ok(Some(9)));
+        assert_eq!(
+            image.get(64, 127),
+            Err(PixelOutOfBounds {
+                x: 64,
+                y: 127,
+                width: 64,
+                height: 128
+            })
+        );
+        assert_eq!(Flat::try_from(&image).unwrap(), flat);
+
+        image.set(3, 2, None).unwrap();
+        assert!(matches!(
+            Flat::try_from(&image),
+            Err(ImageConversionError::Transparent { x: 3, y: 2, .. })
+        ));
+
+        assert!(Flat::parse(&[0; 100]).is_err());
+        assert!(matches!(
+            Flat::try_from(&IndexedImage::new(32, 32)),
+            Err(ImageConversionError::InvalidSize { .. })
+        ));
+
+        image.pixels.pop();
+        assert_eq!(image.get(62, 127), Ok(Some(7)));
+        assert!(image.get(63, 127).is_err());
+        assert!(image.set(63, 127, Some(1)).is_err());
+        assert!(matches!(
+            Flat::try_from(&image),
+            Err(ImageConversionError::PixelCount { len, .. }) if len == 64 * 128 - 1
+        ));
+    }
+}
@@ -0,0 +1,89 @@
+use miette::Diagnostic;
+
+/// A paletted image, with optional transparency.
+///
+/// This is the common representation that graphics in the various Doom image formats (flats, patches, etc.) can be
+/// converted to and from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IndexedImage {
+    pub width: u16,
+    pub height: u16,
+    /// Palette indices in row-major order, `None` where the image is transparent
+    pub pixels: Vec<Option<u8>>,
+}
+
+impl IndexedImage {
+    /// A fully transparent image
+    pub fn new(width: u16, height: u16) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![None; usize::from(width) * usize::from(height)],
+        }
+    }
+
+    /// The pixel at `(x, y)`. Fails if it's outside the image, or if `pixels` is too short to hold it.
+    pub fn get(&self, x: u16, y: u16) -> Result<Option<u8>, PixelOutOfBounds> {
+        Ok(self.pixels[pixel_index(self.width, self.height, self.pixels.len(), x, y)?])
+    }
+
+    /// Set the pixel at `(x, y)`, failing as for [IndexedImage::get]
+    pub fn set(&mut self, x: u16, y: u16, pixel: Option<u8>) -> Result<(), PixelOutOfBounds> {
+        let index = pixel_index(self.width, self.height, self.pixels.len(), x, y)?;
+        self.pixels[index] = pixel;
+        Ok(())
+    }
+}
+
+/// The index of a pixel in a row-major image of the given size, which has `len` pixels
+pub(crate) fn pixel_index(
+    width: u16,
+    height: u16,
+    len: usize,
+    x: u16,
+    y: u16,
+) -> Result<usize, PixelOutOfBounds> {
+    let index = usize::from(y) * usize::from(width) + usize::from(x);
+
+    if x < width && y < height && index < len {
+        Ok(index)
+    } else {
+        Err(PixelOutOfBounds {
+            x,
+            y,
+            width,
+            height,
+        })
+    }
+}
+
+/// A pixel outside of an image, or missing from its pixels, was read or written
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error, Diagnostic)]
+#[error("({x}, {y}) is outside of a {width}x{height} image")]
+pub struct PixelOutOfBounds {
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+    pub height: u16,
+}
+
+/// An error converting an `IndexedImage` to a more restrictive format
+#[derive(Debug, thiserror::Error, Diagnostic)]
+pub enum ImageConversionError {
+    #[error("{width}x{height} is not a valid size for a {format}")]
+    InvalidSize {
+        format: &'static str,
+        width: u16,
+        height: u16,
+    },
+
+    #[error("A {width}x{height} image needs {} pixels, but has {len}", usize::from(*width) * usize::from(*height))]
+    PixelCount { width: u16, height: u16, len: usize },
+
+    #[error("A {format} can't have transparent pixels, but ({x}, {y}) is transparent")]
+    Transparent {
+        format: &'static str,
+        x: u16,
+        y: u16,
+    },
+}
@@ -0,0 +1,279 @@
+use crate::{
+    wad::script::{tokenize, Token},
+    String8,
+};
+
+/// The settings of one map from a MAPINFO, ZMAPINFO or UMAPINFO lump. Unset fields use the engine defaults.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MapInfoEntry {
+    /// The map's marker lump, e.g. `MAP01`, in uppercase
+    pub map: String8,
+    pub title: Option<String>,
+    pub music: Option<String8>,
+    pub sky: Option<String8>,
+}
+
+/// The map definitions of a MAPINFO lump, in any of the old and new ZDoom syntaxes or UMAPINFO
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MapInfo {
+    pub maps: Vec<MapInfoEntry>,
+    /// Editor numbers assigned to actor classes in `DoomEdNums` blocks, as `(ed_num, class)`
+    pub ed_nums: Vec<(i16, String)>,
+}
+
+/// Keywords that start a top-level definition, ending the properties of the previous one in the old syntax
+const DEFINITION_KEYWORDS: &[&str] = &[
+    "map",
+    "defaultmap",
+    "adddefaultmap",
+    "gamedefaults",
+    "episode",
+    "clearepisodes",
+    "clusterdef",
+    "gameinfo",
+    "skill",
+    "clearskills",
+    "intermission",
+    "include",
+    "automap",
+    "automap_overlay",
+    "doomednums",
+    "spawnnums",
+    "conversationids",
+];
+
+impl MapInfo {
+    /// Parse a MAPINFO lump. This is tolerant: only map names, titles, music and skies are extracted, and anything
+    /// else is skipped.
+    pub fn parse(contents: &str) -> Self {
+        let tokens = tokenize(contents);
+        let mut maps = Vec::new();
+        let mut ed_nums = Vec::new();
+        let mut position = 0;
+
+        while position < tokens.len() {
+            if tokens[position].is("doomednums") {
+                position += 1;
+                let end = tokens[position..]
+                    .iter()
+                    .position(|token| token.is("}"))
+                    .map_or(tokens.len(), |end| position + end);
+                read_ed_nums(&tokens[position..end], &mut ed_nums);
+
+                position = end;
+                continue;
+            }
+
+            if !tokens[position].is("map") {
+                position += 1;
+                continue;
+            }
+
+            let Some(map) = tokens
+                .get(position + 1)
+                .and_then(|token| String8::new(&token.text.to_ascii_uppercase()).ok())
+            else {
+                position += 1;
+                continue;
+            };
+            position += 2;
+
+            let mut entry = MapInfoEntry {
+                map,
+                ..Default::default()
+            };
+
+            if tokens.get(position).is_some_and(|token| token.is("lookup")) {
+                position += 1;
+            }
+            if let Some(title) = tokens.get(position).filter(|token| token.quoted) {
+                entry.title = Some(title.text.to_owned());
+                position += 1;
+            }
+
+            let braced = tokens.get(position).is_some_and(|token| token.is("{"));
+            let end = if braced {
+                position += 1;
+                tokens[position..]
+                    .iter()
+                    .position(|token| token.is("}"))
+                    .map_or(tokens.len(), |end| position + end)
+            } else {
+                tokens[position..]
+                    .iter()
+                    .position(|token| DEFINITION_KEYWORDS.iter().any(|keyword| token.is(keyword)))
+                    .map_or(tokens.len(), |end| position + end)
+            };
+
+            read_properties(&tokens[position..end], &mut entry);
+            maps.push(entry);
+            position = end;
+        }
+
+        Self { maps, ed_nums }
+    }
+
+    /// The last entry for a map, ignoring case
+    pub fn get(&self, map: &String8) -> Option<&MapInfoEntry> {
+        self.maps
+            .iter()
+            .rev()
+            .find(|entry| entry.map.as_bytes().eq_ignore_ascii_case(map.as_bytes()))
+    }
+}
+
+/// Extract known properties, as either `key value` or `key = value`
+fn read_properties(tokens: &[Token], entry: &mut MapInfoEntry) {
+    let value = |i: usize| {
+        let value = match tokens.get(i + 1) {
+            Some(token) if token.is("=") => tokens.get(i + 2),
+            token => token,
+        }?;
+
+        String8::new(&value.text.to_ascii_uppercase()).ok()
+    };
+
+    for (i, token) in tokens.iter().enumerate() {
+        if token.quoted {
+            continue;
+        }
+
+        if token.is("music") {
+            entry.music = value(i).or(entry.music.take());
+        } else if token.is("sky1") || token.is("skytexture") {
+            entry.sky = value(i).or(entry.sky.take());
+        } else if token.is("levelname") {
+            if let Some(title) = tokens.get(i + 2).filter(|token| token.quoted) {
+                entry.title = Some(title.text.to_owned());
+            }
+        }
+    }
+}
+
+/// `N = Class [, args...]` assignments
+fn read_ed_nums(tokens: &[Token], ed_nums: &mut Vec<(i16, String)>) {
+    for (i, token) in tokens.iter().enumerate() {
+        let Ok(ed_num) = token.text.parse() else {
+            continue;
+        };
+
+        if let [equals, class, ..] = &tokens[i + 1..] {
+            if equals.is("=") {
+                ed_nums.push((ed_num, class.text.to_owned()));
+            }
+        }
+    }
+}
+
+/// The music of the Doom 2 maps, by map number
+const DOOM2_MUSIC: [&str; 32] = [
+    "D_RUNNIN", "D_STALKS", "D_COUNTD", "D_BETWEE", "D_DOOM", "D_THE_DA", "D_SHAWN", "D_DDTBLU",
+    "D_IN_CIT", "D_DEAD", "D_STLKS2", "D_THEDA2", "D_DOOM2", "D_DDTBL2", "D_RUNNI2", "D_DEAD2",
+    "D_STLKS3", "D_ROMERO", "D_SHAWN2", "D_MESSAG", "D_COUNT2", "D_DDTBL3", "D_AMPIE", "D_THEDA3",
+    "D_ADRIAN", "D_MESSG2", "D_ROMER2", "D_TENSE", "D_SHAWN3", "D_OPENIN", "D_EVIL", "D_ULTIMA",
+];
+
+/// The music of Ultimate Doom's fourth episode, which reuses tracks from the others
+const EPISODE4_MUSIC: [&str; 9] = [
+    "D_E3M4", "D_E3M2", "D_E3M3", "D_E1M5", "D_E2M7", "D_E2M4", "D_E2M6", "D_E2M5", "D_E1M9",
+];
+
+/// Split a map name into `(episode, map)` for `ExMy` names, or `(None, map)` for `MAPxx` names
+fn map_number(map: &String8) -> Option<(Option<u8>, u8)> {
+    let name = map.as_bytes_trimmed();
+    let name = std::str::from_utf8(name).ok()?.to_ascii_uppercase();
+
+    if let Some(number) = name.strip_prefix("MAP") {
+        return Some((None, number.parse().ok()?));
+    }
+
+    match name.as_bytes() {
+        [b'E', episode @ b'1'..=b'9', b'M', map @ b'1'..=b'9'] => {
+            Some((Some(episode - b'0'), map - b'0'))
+        }
+        _ => None,
+    }
+}
+
+/// The music lump vanilla Doom plays on a map, if it has one
+pub fn default_music(map: &String8) -> Option<String8> {
+    let name = match map_number(map)? {
+        (None, number) => DOOM2_MUSIC
+            .get(usize::from(number).checked_sub(1)?)?
+            .to_string(),
+        (Some(4), number) => EPISODE4_MUSIC[usize::from(number) - 1].to_string(),
+        (Some(episode @ 1..=3), number) => format!("D_E{episode}M{number}"),
+        _ => return None,
+    };
+
+    String8::new(&name).ok()
+}
+
+/// The sky texture vanilla Doom uses on a map, if it has one
+pub fn default_sky(map: &String8) -> Option<String8> {
+    let sky = match map_number(map)? {
+        (None, 1..=11) => 1,
+        (None, 12..=20) => 2,
+        (None, _) => 3,
+        (Some(episode @ 1..=4), _) => episode,
+        _ => return None,
+    };
+
+    String8::new(&format!("SKY{sky}")).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_syntaxes() {
+        let mapinfo = MapInfo::parse(
+            r#"
+            map MAP01 "Entryway"
+            music D_DOOM
+            sky1 SKY3 0
+            cluster 5
+
+            map MAP02 lookup "HUSTR_2"
+            {
+                music = "d_stalks"
+                sky1 = "SKY2", 0
+            }
+
+            clusterdef 5 { music = "D_READ_M" }
+
+            MAP MAP03 { levelname = "Underhalls" skytexture = "SKY1" }
+
+            DoomEdNums { 20001 = "Spark", 0 20002 = Dragon }
+            "#,
+        );
+
+        let map = |name| mapinfo.get(&String8::new_unchecked(name)).unwrap();
+        assert_eq!(map("MAP01").title.as_deref(), Some("Entryway"));
+        assert_eq!(map("MAP01").music, Some(String8::new_unchecked("D_DOOM")));
+        assert_eq!(map("MAP01").sky, Some(String8::new_unchecked("SKY3")));
+        assert_eq!(map("map02").title.as_deref(), Some("HUSTR_2"));
+        assert_eq!(map("MAP02").music, Some(String8::new_unchecked("D_STALKS")));
+        assert_eq!(map("MAP03").title.as_deref(), Some("Underhalls"));
+        assert_eq!(map("MAP03").sky, Some(String8::new_unchecked("SKY1")));
+        assert_eq!(mapinfo.maps.len(), 3);
+        assert_eq!(
+            mapinfo.ed_nums,
+            [(20001, "Spark".to_owned()), (20002, "Dragon".to_owned())]
+        );
+
+        assert_eq!(
+            default_music(&String8::new_unchecked("MAP01")),
+            Some(String8::new_unchecked("D_RUNNIN"))
+        );
+        assert_eq!(
+            default_music(&String8::new_unchecked("E4M1")),
+            Some(String8::new_unchecked("D_E3M4"))
+        );
+        assert_eq!(
+            default_sky(&String8::new_unchecked("MAP15")),
+            Some(String8::new_unchecked("SKY2"))
+        );
+    }
+}
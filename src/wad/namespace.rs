@@ -0,0 +1,128 @@
+use crate::wad::{name_matches, Lump, Wad};
+
+/// The kind of resource a lump is, as determined by the marker lumps around it
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Namespace {
+    /// Outside of any markers, including the markers themselves
+    #[default]
+    Global,
+    /// Between S_START and S_END, or SS_START and SS_END
+    Sprite,
+    /// Between F_START and F_END, or FF_START and FF_END
+    Flat,
+    /// Between P_START and P_END, or PP_START and PP_END
+    Patch,
+    /// Between C_START and C_END
+    ColorMap,
+    /// Between VX_START and VX_END
+    Voxel,
+    /// Between HI_START and HI_END
+    HiRes,
+    /// Between TX_START and TX_END
+    Texture,
+    /// Between A_START and A_END
+    AcsLibrary,
+}
+
+/// The markers delimiting each namespace, as `(start, end)`
+const MARKERS: &[(Namespace, &str, &str)] = &[
+    (Namespace::Sprite, "S_START", "S_END"),
+    (Namespace::Sprite, "SS_START", "SS_END"),
+    (Namespace::Flat, "F_START", "F_END"),
+    (Namespace::Flat, "FF_START", "FF_END"),
+    (Namespace::Patch, "P_START", "P_END"),
+    (Namespace::Patch, "PP_START", "PP_END"),
+    (Namespace::ColorMap, "C_START", "C_END"),
+    (Namespace::Voxel, "VX_START", "VX_END"),
+    (Namespace::HiRes, "HI_START", "HI_END"),
+    (Namespace::Texture, "TX_START", "TX_END"),
+    (Namespace::AcsLibrary, "A_START", "A_END"),
+];
+
+/// Whether a lump is a marker nested inside a namespace, like the `F1_START` and `P2_END` of the IWADs
+fn is_sub_marker(lump: &Lump) -> bool {
+    let name = lump.name.as_bytes_trimmed();
+
+    [b"_START".as_slice(), b"_END"].iter().any(|suffix| {
+        name.len() > suffix.len() && name[name.len() - suffix.len()..].eq_ignore_ascii_case(suffix)
+    })
+}
+
+impl Wad {
+    /// The namespace of each lump, in order, as determined by the marker lumps around it
+    pub fn namespaces(&self) -> Vec<Namespace> {
+        let mut current = Namespace::Global;
+
+        self.lumps
+            .iter()
+            .map(|lump| {
+                let marker = MARKERS.iter().find(|(_, start, end)| {
+                    name_matches(&lump.name, start) || name_matches(&lump.name, end)
+                });
+
+                match marker {
+                    Some(&(namespace, start, _)) => {
+                        current = if name_matches(&lump.name, start) {
+                            namespace
+                        } else {
+                            Namespace::Global
+                        };
+                        Namespace::Global
+                    }
+                    None if current != Namespace::Global && is_sub_marker(lump) => {
+                        Namespace::Global
+                    }
+                    None => current,
+                }
+            })
+            .collect()
+    }
+
+    /// The lumps in a namespace, in order
+    pub fn lumps_in(&self, namespace: Namespace) -> impl Iterator<Item = &Lump> {
+        self.lumps
+            .iter()
+            .zip(self.namespaces())
+            .filter(move |(_, lump_namespace)| *lump_namespace == namespace)
+            .map(|(lump, _)| lump)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{wad::WadKind, String8};
+
+    #[test]
+    fn classify() {
+        let mut wad = Wad::new(WadKind::Iwad);
+        wad.lumps = [
+            "PLAYPAL", "S_START", "TROOA1", "S_END", "F_START", "F1_START", "FLOOR0_1", "F1_END",
+            "F_END", "PP_START", "WALL00_1", "PP_END", "MAP01",
+        ]
+        .into_iter()
+        .map(|name| Lump::new(String8::new_unchecked(name), Vec::new()))
+        .collect();
+
+        assert_eq!(
+            wad.namespaces(),
+            [
+                Namespace::Global,
+                Namespace::Global,
+                Namespace::Sprite,
+                Namespace::Global,
+                Namespace::Global,
+                Namespace::Global,
+                Namespace::Flat,
+                Namespace::Global,
+                Namespace::Global,
+                Namespace::Global,
+                Namespace::Patch,
+                Namespace::Global,
+                Namespace::Global,
+            ]
+        );
+        assert_eq!(wad.lumps_in(Namespace::Flat).count(), 1);
+    }
+}
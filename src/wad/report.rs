@@ -0,0 +1,199 @@
+use miette::Diagnostic;
+
+use crate::{
+    wad::{
+        mapinfo::{self, MapInfo},
+        ResourceSet,
+    },
+    String8,
+};
+
+/// The resources a map uses, resolved from MAPINFO and the engine defaults
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MapResources {
+    /// The map's marker lump, in uppercase
+    pub map: String8,
+    /// The index in `ResourceSet::archives` of the archive with the map in use
+    pub archive: usize,
+    pub title: Option<String>,
+    /// The music lump, if the map has any music
+    pub music: Option<String8>,
+    /// The sky texture, if the map has a sky
+    pub sky: Option<String8>,
+}
+
+/// A packaging problem found by `ResourceSet::report`
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error, Diagnostic)]
+pub enum ReportIssue {
+    #[error("{map} plays {music}, but no archive has that lump")]
+    #[diagnostic(
+        code(waddle::wad::missing_music),
+        help("Add the music lump, or change the map's music in MAPINFO")
+    )]
+    MissingMusic { map: String, music: String },
+
+    #[error("{map} uses the sky {sky}, but no archive defines that texture")]
+    #[diagnostic(
+        code(waddle::wad::missing_sky),
+        help("Add the sky texture, or change the map's sky in MAPINFO")
+    )]
+    MissingSky { map: String, sky: String },
+}
+
+/// An overview of the maps in a `ResourceSet` and any problems with them
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WadReport {
+    /// Every map, in the order they first appear
+    pub maps: Vec<MapResources>,
+    pub issues: Vec<ReportIssue>,
+}
+
+impl ResourceSet {
+    /// Resolve every map's music and sky, checking that they exist
+    pub fn report(&self) -> WadReport {
+        let mut report = WadReport::default();
+
+        let mapinfo = MapInfo {
+            maps: self
+                .archives()
+                .iter()
+                .flat_map(|wad| wad.lumps.iter())
+                .filter(|lump| {
+                    ["MAPINFO", "ZMAPINFO", "UMAPINFO"]
+                        .iter()
+                        .any(|name| lump.is_named(name))
+                })
+                .flat_map(|lump| MapInfo::parse(&String::from_utf8_lossy(&lump.data)).maps)
+                .collect(),
+            ..Default::default()
+        };
+
+        for (archive, wad) in self.archives().iter().enumerate() {
+            for marker in wad.maps() {
+                let map =
+                    String8::from_raw_parts(marker.name.as_bytes().map(|b| b.to_ascii_uppercase()));
+                let entry = mapinfo.get(&map);
+
+                let resources = MapResources {
+                    title: entry.and_then(|entry| entry.title.clone()),
+                    music: entry
+                        .and_then(|entry| entry.music.clone())
+                        .or_else(|| mapinfo::default_music(&map)),
+                    sky: entry
+                        .and_then(|entry| entry.sky.clone())
+                        .or_else(|| mapinfo::default_sky(&map)),
+                    map,
+                    archive,
+                };
+
+                match report
+                    .maps
+                    .iter_mut()
+                    .find(|existing| existing.map == resources.map)
+                {
+                    Some(existing) => *existing = resources,
+                    None => report.maps.push(resources),
+                }
+            }
+        }
+
+        for resources in report.maps.iter() {
+            let map = lump_name(&resources.map);
+
+            if let Some(music) = &resources.music {
+                if !self
+                    .archives()
+                    .iter()
+                    .any(|wad| wad.find(&lump_name(music)).is_some())
+                {
+                    report.issues.push(ReportIssue::MissingMusic {
+                        map: map.clone(),
+                        music: lump_name(music),
+                    });
+                }
+            }
+
+            if let Some(sky) = &resources.sky {
+                if self.texture_size(sky).is_none() {
+                    report.issues.push(ReportIssue::MissingSky {
+                        map,
+                        sky: lump_name(sky),
+                    });
+                }
+            }
+        }
+
+        report
+    }
+}
+
+fn lump_name(name: &String8) -> String {
+    name.to_string_lossy().into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::wad::{Lump, Texture, Textures, Wad, WadKind};
+
+    #[test]
+    fn missing_music() {
+        let mut texture1 = Vec::new();
+        Textures(vec![Texture {
+            name: String8::new_unchecked("SKY1"),
+            masked: false,
+            width: 256,
+            height: 128,
+            column_directory: 0,
+            patches: Vec::new(),
+        }])
+        .write(&mut texture1)
+        .unwrap();
+
+        let mut pwad = Wad::new(WadKind::Pwad);
+        pwad.lumps = [
+            ("TEXTURE1", texture1),
+            (
+                "MAPINFO",
+                b"map MAP02 \"Hub\" { music = \"D_HUB\" }".to_vec(),
+            ),
+            ("D_HUB", vec![]),
+            ("MAP01", vec![]),
+            ("TEXTMAP", vec![]),
+            ("ENDMAP", vec![]),
+            ("MAP02", vec![]),
+            ("TEXTMAP", vec![]),
+            ("ENDMAP", vec![]),
+            ("MAP12", vec![]),
+            ("THINGS", vec![]),
+        ]
+        .into_iter()
+        .map(|(name, data)| Lump::new(String8::new_unchecked(name), data))
+        .collect();
+
+        let mut resources = ResourceSet::new();
+        resources.push(pwad);
+        let report = resources.report();
+
+        assert_eq!(report.maps.len(), 3);
+        assert_eq!(report.maps[1].title.as_deref(), Some("Hub"));
+        assert_eq!(
+            report.issues,
+            [
+                ReportIssue::MissingMusic {
+                    map: "MAP01".to_owned(),
+                    music: "D_RUNNIN".to_owned()
+                },
+                ReportIssue::MissingMusic {
+                    map: "MAP12".to_owned(),
+                    music: "D_THEDA2".to_owned()
+                },
+                ReportIssue::MissingSky {
+                    map: "MAP12".to_owned(),
+                    sky: "SKY2".to_owned()
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,329 @@
+use std::{cell::RefCell, collections::HashMap};
+
+use crate::{
+    wad::{
+        animation::{self, AnimDefs, Animation, AnimationFrames, Pic, SwitchDef},
+        flat, LumpError, Textures, Wad,
+    },
+    String8,
+};
+
+/// The dimensions of a texture or flat
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ImageSize {
+    pub width: u16,
+    pub height: u16,
+}
+
+/// Whether an image is a composite texture, used on walls, or a flat, used on floors and ceilings
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ImageNamespace {
+    Texture,
+    Flat,
+}
+
+/// A texture or flat, as resolved across all archives of a `ResourceSet`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ImageInfo {
+    /// The name in uppercase
+    pub name: String8,
+    pub size: ImageSize,
+    /// The index in `ResourceSet::archives` of the archive with the definition in use
+    pub archive: usize,
+}
+
+/// A stack of archives loaded together, e.g. an IWAD followed by PWADs. Later archives override earlier ones.
+///
+/// Texture and flat definitions are computed on first use and cached until the archives change. Modify the archives
+/// only through this type's methods so the cache stays valid.
+#[derive(Debug, Default)]
+pub struct ResourceSet {
+    archives: Vec<Wad>,
+    images: RefCell<Option<ImageCache>>,
+}
+
+#[derive(Debug, Default)]
+struct ImageCache {
+    textures: Images,
+    flats: Images,
+}
+
+impl ImageCache {
+    fn namespace(&self, namespace: ImageNamespace) -> &Images {
+        match namespace {
+            ImageNamespace::Texture => &self.textures,
+            ImageNamespace::Flat => &self.flats,
+        }
+    }
+}
+
+/// Images in definition order. A redefinition replaces the original in place.
+#[derive(Debug, Default)]
+struct Images {
+    entries: Vec<ImageInfo>,
+    positions: HashMap<String8, usize>,
+}
+
+impl Images {
+    fn insert(&mut self, info: ImageInfo) {
+        match self.positions.get(&info.name) {
+            Some(&position) => self.entries[position] = info,
+            None => {
+                self.positions.insert(info.name.clone(), self.entries.len());
+                self.entries.push(info);
+            }
+        }
+    }
+
+    fn get(&self, name: &String8) -> Option<&ImageInfo> {
+        self.positions
+            .get(&name.to_uppercase())
+            .map(|&position| &self.entries[position])
+    }
+}
+
+impl ResourceSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an archive on top of the existing ones
+    pub fn push(&mut self, wad: Wad) {
+        self.archives.push(wad);
+        self.invalidate();
+    }
+
+    pub fn remove(&mut self, index: usize) -> Wad {
+        self.invalidate();
+        self.archives.remove(index)
+    }
+
+    pub fn archives(&self) -> &[Wad] {
+        &self.archives
+    }
+
+    /// Mutable access to an archive. Invalidates the image cache.
+    pub fn archive_mut(&mut self, index: usize) -> Option<&mut Wad> {
+        self.invalidate();
+        self.archives.get_mut(index)
+    }
+
+    /// Discard all cached data
+    pub fn invalidate(&mut self) {
+        *self.images.get_mut() = None;
+    }
+
+    /// The size of a composite texture defined in TEXTURE1 or TEXTURE2, ignoring case
+    pub fn texture_size(&self, name: &String8) -> Option<ImageSize> {
+        self.with_images(|images| images.textures.get(name).map(|info| info.size))
+    }
+
+    /// The size of a flat, ignoring case
+    pub fn flat_size(&self, name: &String8) -> Option<ImageSize> {
+        self.with_images(|images| images.flats.get(name).map(|info| info.size))
+    }
+
+    /// Every texture or flat, in definition order
+    pub fn images(&self, namespace: ImageNamespace) -> Vec<ImageInfo> {
+        self.with_images(|images| images.namespace(namespace).entries.clone())
+    }
+
+    /// The animations in effect: Doom's hardcoded ones, replaced by the last ANIMATED lump if any, followed by those
+    /// of every ANIMDEFS lump in load order
+    pub fn animations(&self) -> Vec<Animation> {
+        let mut animations = self
+            .last_binary_lump("ANIMATED", animation::parse_animated)
+            .unwrap_or_else(animation::vanilla_animations);
+
+        animations.extend(self.anim_defs().flat_map(|defs| defs.animations));
+        animations
+    }
+
+    /// The switches in effect: Doom's hardcoded ones, replaced by the last SWITCHES lump if any, followed by those of
+    /// every ANIMDEFS lump in load order
+    pub fn switches(&self) -> Vec<SwitchDef> {
+        let mut switches = self
+            .last_binary_lump("SWITCHES", animation::parse_switches)
+            .unwrap_or_else(animation::vanilla_switches);
+
+        switches.extend(self.anim_defs().flat_map(|defs| defs.switches));
+        switches
+    }
+
+    /// The names of the frames of an animation, in order. Empty if any frame is missing, or if a range's ends are
+    /// out of order, which the engine doesn't support.
+    pub fn animation_frames(&self, animation: &Animation) -> Vec<String8> {
+        self.with_images(|images| {
+            let images = images.namespace(animation.namespace);
+            let position = |name: &String8| images.positions.get(&name.to_uppercase()).copied();
+
+            match &animation.frames {
+                AnimationFrames::Range { first, last, .. } => {
+                    match (position(first), position(last)) {
+                        (Some(first), Some(last)) if first <= last => images.entries[first..=last]
+                            .iter()
+                            .map(|info| info.name.clone())
+                            .collect(),
+                        _ => Vec::new(),
+                    }
+                }
+                AnimationFrames::Pics { base, pics } => pics
+                    .iter()
+                    .map(|pic| match &pic.pic {
+                        Pic::Name(name) => position(name).map(|_| name.to_uppercase()),
+                        Pic::Number(number) => {
+                            let index = position(base)? + usize::from(*number).checked_sub(1)?;
+                            images.entries.get(index).map(|info| info.name.clone())
+                        }
+                    })
+                    .collect::<Option<_>>()
+                    .unwrap_or_default(),
+            }
+        })
+    }
+
+    /// Parse the last lump named `name` in any archive, skipping it if it's invalid
+    fn last_binary_lump<T>(
+        &self,
+        name: &str,
+        parse: impl Fn(&[u8]) -> Result<T, LumpError>,
+    ) -> Option<T> {
+        self.archives
+            .iter()
+            .rev()
+            .find_map(|wad| wad.find(name))
+            .and_then(|lump| parse(&lump.data).ok())
+    }
+
+    /// Every valid ANIMDEFS lump, in load order
+    fn anim_defs(&self) -> impl Iterator<Item = AnimDefs> + '_ {
+        self.archives
+            .iter()
+            .flat_map(|wad| wad.lumps.iter())
+            .filter(|lump| lump.is_named("ANIMDEFS"))
+            .filter_map(|lump| AnimDefs::parse(&String::from_utf8_lossy(&lump.data)).ok())
+    }
+
+    fn with_images<T>(&self, f: impl FnOnce(&ImageCache) -> T) -> T {
+        let mut images = self.images.borrow_mut();
+        f(images.get_or_insert_with(|| self.compute_images()))
+    }
+
+    fn compute_images(&self) -> ImageCache {
+        let mut cache = ImageCache::default();
+
+        // Later definitions replace earlier ones, so insert in load order
+        for (archive, wad) in self.archives.iter().enumerate() {
+            for lump_name in ["TEXTURE1", "TEXTURE2"] {
+                // Invalid texture lumps are skipped here: they're reported when loaded directly
+                let Some(Ok(textures)) =
+                    wad.find(lump_name).map(|lump| Textures::parse(&lump.data))
+                else {
+                    continue;
+                };
+
+                for texture in textures.0 {
+                    cache.textures.insert(ImageInfo {
+                        name: texture.name.to_uppercase(),
+                        size: ImageSize {
+                            width: texture.width,
+                            height: texture.height,
+                        },
+                        archive,
+                    });
+                }
+            }
+
+            for (start, end) in [("F_START", "F_END"), ("FF_START", "FF_END")] {
+                for lump in wad.lumps_between(start, end) {
+                    if let Some((width, height)) = flat::size_for_len(lump.data.len()) {
+                        cache.flats.insert(ImageInfo {
+                            name: lump.name.to_uppercase(),
+                            size: ImageSize { width, height },
+                            archive,
+                        });
+                    }
+                }
+            }
+        }
+
+        cache
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::wad::{Lump, Texture, WadKind};
+
+    fn marker(name: &str) -> Lump {
+        Lump::new(String8::new_unchecked(name), Vec::new())
+    }
+
+    #[test]
+    fn sizes_cached_and_invalidated() {
+        let mut iwad = Wad::new(WadKind::Iwad);
+        let mut texture1 = Vec::new();
+        Textures(vec![Texture {
+            name: String8::new_unchecked("STARTAN3"),
+            masked: false,
+            width: 128,
+            height: 128,
+            column_directory: 0,
+            patches: Vec::new(),
+        }])
+        .write(&mut texture1)
+        .unwrap();
+
+        iwad.lumps = vec![
+            Lump::new(String8::new_unchecked("TEXTURE1"), texture1),
+            marker("F_START"),
+            Lump::new(String8::new_unchecked("FLOOR0_1"), vec![0; 64 * 64]),
+            marker("F_END"),
+        ];
+
+        let mut resources = ResourceSet::new();
+        resources.push(iwad.clone());
+
+        let floor = String8::new_unchecked("floor0_1");
+        assert_eq!(
+            resources.texture_size(&String8::new_unchecked("startan3")),
+            Some(ImageSize {
+                width: 128,
+                height: 128
+            })
+        );
+        assert_eq!(
+            resources.flat_size(&floor),
+            Some(ImageSize {
+                width: 64,
+                height: 64
+            })
+        );
+
+        let mut pwad = Wad::new(WadKind::Pwad);
+        pwad.lumps = vec![
+            marker("FF_START"),
+            Lump::new(String8::new_unchecked("FLOOR0_1"), vec![0; 128 * 128]),
+            marker("FF_END"),
+        ];
+        resources.push(pwad);
+
+        assert_eq!(
+            resources.flat_size(&floor),
+            Some(ImageSize {
+                width: 128,
+                height: 128
+            })
+        );
+
+        resources.remove(1);
+        resources.archive_mut(0).unwrap().lumps.clear();
+        assert_eq!(resources.flat_size(&floor), None);
+
+        let mut bytes = Vec::new();
+        iwad.write(&mut bytes).unwrap();
+        assert_eq!(Wad::parse(&bytes).unwrap(), iwad);
+    }
+}
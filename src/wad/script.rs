@@ -0,0 +1,82 @@
+use std::ops::Range;
+
+/// Characters that are tokens on their own in text lumps like MAPINFO and DECORATE, unless quoted
+const PUNCTUATION: &[char] = &['{', '}', '=', ',', ':', ';', '(', ')'];
+
+/// A word, punctuation character or quoted string in a text lump, with its span in the lump
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Token<'s> {
+    /// The text, without quotes for quoted strings
+    pub text: &'s str,
+    pub span: Range<usize>,
+    pub quoted: bool,
+}
+
+impl Token<'_> {
+    /// Whether this is the given unquoted keyword or punctuation, ignoring case
+    pub fn is(&self, keyword: &str) -> bool {
+        !self.quoted && self.text.eq_ignore_ascii_case(keyword)
+    }
+}
+
+/// Split the contents of a text lump into tokens, skipping `//` and `/* */` comments
+pub(crate) fn tokenize(contents: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut rest = contents;
+
+    loop {
+        let trimmed = rest.trim_start();
+        if let Some(comment) = trimmed.strip_prefix("//") {
+            rest = comment.find('\n').map_or("", |end| &comment[end..]);
+            continue;
+        }
+        if let Some(comment) = trimmed.strip_prefix("/*") {
+            rest = comment.find("*/").map_or("", |end| &comment[end + 2..]);
+            continue;
+        }
+        if trimmed.is_empty() {
+            return tokens;
+        }
+
+        let start = contents.len() - trimmed.len();
+        let (text, len, quoted) = match trimmed.strip_prefix('"') {
+            Some(quoted) => {
+                let end = quoted.find('"').unwrap_or(quoted.len());
+                (&quoted[..end], (end + 2).min(trimmed.len()), true)
+            }
+            None if trimmed.starts_with(PUNCTUATION) => (&trimmed[..1], 1, false),
+            None => {
+                let end = trimmed
+                    .find(|c: char| c.is_whitespace() || PUNCTUATION.contains(&c))
+                    .unwrap_or(trimmed.len());
+                (&trimmed[..end], end, false)
+            }
+        };
+
+        tokens.push(Token {
+            text,
+            span: start..start + len,
+            quoted,
+        });
+        rest = &trimmed[len..];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens() {
+        let tokens = tokenize("map MAP01 \"Entryway\" { // comment\n music=D_RUNNIN /* } */ }");
+        let texts: Vec<_> = tokens.iter().map(|token| token.text).collect();
+
+        assert_eq!(
+            texts,
+            ["map", "MAP01", "Entryway", "{", "music", "=", "D_RUNNIN", "}"]
+        );
+        assert!(tokens[2].quoted);
+        assert_eq!(tokens[2].span, 10..20);
+        assert!(tokens[3].is("{"));
+    }
+}
@@ -0,0 +1,279 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    wad::{name_matches, Lump, LumpError, LumpReader, Wad, WadError, WadHeader, WadKind, WriteLe},
+    String8,
+};
+
+/// The size of a directory entry: offset, size and name
+pub(crate) const ENTRY_LEN: u64 = 16;
+
+/// Where a lump's data is stored in a `WadSession`'s file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DirectoryEntry {
+    pub name: String8,
+    pub offset: u64,
+    pub size: u64,
+}
+
+impl DirectoryEntry {
+    pub fn is_named(&self, name: &str) -> bool {
+        name_matches(&self.name, name)
+    }
+
+    pub(crate) fn read(reader: &mut LumpReader) -> Result<Self, LumpError> {
+        Ok(Self {
+            offset: reader.count("lump offset")? as u64,
+            size: reader.count("lump size")? as u64,
+            name: reader.string8()?,
+        })
+    }
+
+    pub(crate) fn write<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_count(self.offset as usize)?;
+        writer.write_count(self.size as usize)?;
+        writer.write_string8(&self.name)
+    }
+}
+
+/// A WAD file open for incremental editing.
+///
+/// New and updated lumps are appended to the end of the file, leaving old data in place, and `commit` appends a new
+/// directory before pointing the header at it. The file always has a valid directory, so a crash at any point loses
+/// at most the changes since the last commit. Superseded data accumulates until `compact` is called.
+#[derive(Debug)]
+pub struct WadSession {
+    path: PathBuf,
+    file: File,
+    kind: WadKind,
+    directory: Vec<DirectoryEntry>,
+}
+
+impl WadSession {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, WadError> {
+        let path = path.as_ref().to_path_buf();
+        let mut file = OpenOptions::new().read(true).write(true).open(&path)?;
+
+        let mut header = [0; WadHeader::LEN];
+        file.read_exact(&mut header)?;
+        // The header is checked against the file's length, so the directory is never larger than the file
+        let header = WadHeader::parse(&header, file.metadata()?.len())?;
+
+        let directory_len = header
+            .lump_count
+            .checked_mul(ENTRY_LEN as usize)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "WAD directory too large"))?;
+        let mut directory_bytes = vec![0; directory_len];
+        file.seek(SeekFrom::Start(header.directory_offset as u64))?;
+        file.read_exact(&mut directory_bytes)?;
+
+        let mut reader = LumpReader::new("WAD directory", &directory_bytes)
+            .with_start(header.directory_offset as u64);
+        let directory = (0..header.lump_count)
+            .map(|_| DirectoryEntry::read(&mut reader))
+            .collect::<Result<_, LumpError>>()?;
+
+        Ok(Self {
+            path,
+            file,
+            kind: header.kind,
+            directory,
+        })
+    }
+
+    /// The lumps as of the last change, including uncommitted ones
+    pub fn directory(&self) -> &[DirectoryEntry] {
+        &self.directory
+    }
+
+    /// Read the data of the lump at `index` in the directory
+    pub fn read(&mut self, index: usize) -> io::Result<Vec<u8>> {
+        let entry = self.directory.get(index).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "lump index out of range")
+        })?;
+
+        let mut data = vec![0; entry.size as usize];
+        self.file.seek(SeekFrom::Start(entry.offset))?;
+        self.file.read_exact(&mut data)?;
+
+        Ok(data)
+    }
+
+    /// Read the lump at `index` and parse it with `parse`, locating any error in the file
+    pub fn parse<T>(
+        &mut self,
+        index: usize,
+        parse: impl FnOnce(&[u8]) -> Result<T, LumpError>,
+    ) -> Result<T, WadError> {
+        let data = self.read(index)?;
+        let entry = &self.directory[index];
+
+        parse(&data).map_err(|error| error.locate(&entry.name, entry.offset).into())
+    }
+
+    /// Replace the data of the last lump named `lump.name`, or add the lump at the end if there's none.
+    /// The data is appended to the file immediately, but only becomes visible to readers after `commit`.
+    pub fn put(&mut self, lump: Lump) -> io::Result<()> {
+        let offset = self.file.seek(SeekFrom::End(0))?;
+        self.file.write_all(&lump.data)?;
+
+        let entry = DirectoryEntry {
+            name: lump.name.clone(),
+            offset,
+            size: lump.data.len() as u64,
+        };
+
+        let existing = self.directory.iter_mut().rev().find(|existing| {
+            existing
+                .name
+                .as_bytes()
+                .eq_ignore_ascii_case(lump.name.as_bytes())
+        });
+
+        match existing {
+            Some(existing) => *existing = entry,
+            None => self.directory.push(entry),
+        }
+
+        Ok(())
+    }
+
+    /// Remove every lump named `name` from the directory. Returns whether any were found.
+    pub fn remove(&mut self, name: &str) -> bool {
+        let len = self.directory.len();
+        self.directory.retain(|entry| !entry.is_named(name));
+
+        self.directory.len() != len
+    }
+
+    /// Append the directory and point the header at it, making all changes so far durable
+    pub fn commit(&mut self) -> io::Result<()> {
+        let directory_offset = self.file.seek(SeekFrom::End(0))?;
+
+        let mut directory = Vec::with_capacity(self.directory.len() * ENTRY_LEN as usize);
+        for entry in self.directory.iter() {
+            entry.write(&mut directory)?;
+        }
+        self.file.write_all(&directory)?;
+
+        // The new directory must be on disk before the header refers to it
+        self.file.sync_data()?;
+
+        let mut header = Vec::with_capacity(WadHeader::LEN);
+        WadHeader {
+            kind: self.kind,
+            lump_count: self.directory.len(),
+            directory_offset: directory_offset as usize,
+        }
+        .write(&mut header)?;
+
+        self.file.seek(SeekFrom::Start(0))?;
+        self.file.write_all(&header)?;
+        self.file.sync_data()
+    }
+
+    /// The number of bytes in the file that aren't used by the header, the current directory or any lump
+    pub fn wasted_bytes(&self) -> io::Result<u64> {
+        let used = WadHeader::LEN as u64
+            + ENTRY_LEN * self.directory.len() as u64
+            + self.directory.iter().map(|entry| entry.size).sum::<u64>();
+
+        Ok(self.file.metadata()?.len().saturating_sub(used))
+    }
+
+    /// Commit, then rewrite the file without any superseded data.
+    ///
+    /// The compacted file is saved with `Wad::save_atomic`, so a crash leaves either the old or the new file.
+    pub fn compact(&mut self) -> Result<(), WadError> {
+        self.commit()?;
+
+        let lumps = (0..self.directory.len())
+            .map(|index| {
+                Ok(Lump::new(
+                    self.directory[index].name.clone(),
+                    self.read(index)?,
+                ))
+            })
+            .collect::<io::Result<_>>()?;
+        let wad = Wad {
+            kind: self.kind,
+            lumps,
+        };
+
+        wad.save_atomic(&self.path)?;
+        *self = Self::open(&self.path)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn append_and_compact() {
+        let path = std::env::temp_dir().join(format!("waddle-session-{}.wad", std::process::id()));
+
+        let mut wad = Wad::new(WadKind::Pwad);
+        wad.lumps
+            .push(Lump::new(String8::new_unchecked("MAP01"), vec![]));
+        wad.lumps.push(Lump::new(
+            String8::new_unchecked("TEXTMAP"),
+            b"old".to_vec(),
+        ));
+        wad.write(&mut File::create(&path).unwrap()).unwrap();
+
+        let mut session = WadSession::open(&path).unwrap();
+        session
+            .put(Lump::new(
+                String8::new_unchecked("textmap"),
+                b"new".to_vec(),
+            ))
+            .unwrap();
+        session
+            .put(Lump::new(String8::new_unchecked("ENDMAP"), vec![]))
+            .unwrap();
+
+        // Nothing is visible until the commit
+        assert_eq!(Wad::open(&path).unwrap(), wad);
+        session.commit().unwrap();
+
+        let committed = Wad::open(&path).unwrap();
+        assert_eq!(committed.lumps.len(), 3);
+        assert_eq!(committed.find("TEXTMAP").unwrap().data, b"new");
+        assert!(session.wasted_bytes().unwrap() > 0);
+
+        session.compact().unwrap();
+        assert_eq!(session.wasted_bytes().unwrap(), 0);
+        assert_eq!(Wad::open(&path).unwrap(), committed);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn directory_past_end_of_file() {
+        let path =
+            std::env::temp_dir().join(format!("waddle-session-huge-{}.wad", std::process::id()));
+
+        let mut bytes = b"PWAD".to_vec();
+        bytes.extend(i32::MAX.to_le_bytes());
+        bytes.extend(12i32.to_le_bytes());
+        std::fs::write(&path, &bytes).unwrap();
+
+        let result = WadSession::open(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(
+            result,
+            Err(WadError::Lump(LumpError::InvalidValue {
+                field: "directory end",
+                ..
+            }))
+        ));
+        assert!(Wad::parse(&bytes).is_err());
+    }
+}
@@ -0,0 +1,287 @@
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+};
+
+use crate::{
+    wad::{LumpError, LumpReader, WriteLe},
+    Point, String8,
+};
+
+/// The contents of a PNAMES lump: the names of the patch lumps used by textures, indexed by `Patch::patch_idx`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PatchNames(pub Vec<String8>);
+
+impl PatchNames {
+    pub fn parse(bytes: &[u8]) -> Result<Self, LumpError> {
+        let mut reader = LumpReader::new("PNAMES", bytes);
+        let count = reader.count("patch count")?;
+
+        (0..count)
+            .map(|_| reader.string8())
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_count(self.0.len())?;
+
+        for name in self.0.iter() {
+            writer.write_string8(name)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn get(&self, patch_idx: u16) -> Option<&String8> {
+        self.0.get(usize::from(patch_idx))
+    }
+
+    /// The index of a patch name, ignoring case. Adds the name if it's not present yet, or returns `None` if it isn't
+    /// and every 16-bit index is taken.
+    pub fn index_of_or_insert(&mut self, name: &String8) -> Option<u16> {
+        let position = self
+            .0
+            .iter()
+            .position(|existing| existing.as_bytes().eq_ignore_ascii_case(name.as_bytes()));
+
+        match position {
+            Some(position) => u16::try_from(position).ok(),
+            None => {
+                let position = u16::try_from(self.0.len()).ok()?;
+                self.0.push(name.clone());
+                Some(position)
+            }
+        }
+    }
+}
+
+/// A patch placed in a composite `Texture`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Patch {
+    /// The position of the patch's top left corner in the texture. May be negative or extend past the texture edges.
+    pub origin: Point<i16>,
+    /// The index of the patch's name in PNAMES
+    pub patch_idx: u16,
+    /// Unused by the engine, but preserved for round-tripping. Usually 1.
+    pub step_dir: i16,
+    /// Unused by the engine, but preserved for round-tripping. Usually 0.
+    pub color_map: i16,
+}
+
+/// A composite texture, built out of one or more patches
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Texture {
+    pub name: String8,
+    /// Whether the texture has transparent parts, i.e. can be used on the middle of two-sided lines
+    pub masked: bool,
+    pub width: u16,
+    pub height: u16,
+    /// Obsolete, but preserved for round-tripping. Usually 0.
+    pub column_directory: i32,
+    pub patches: Vec<Patch>,
+}
+
+impl Texture {
+    /// The names of the patches composing this texture, or `None` for patches missing from `patch_names`
+    pub fn patch_names<'p>(
+        &'p self,
+        patch_names: &'p PatchNames,
+    ) -> impl Iterator<Item = Option<&'p String8>> + 'p {
+        self.patches
+            .iter()
+            .map(|patch| patch_names.get(patch.patch_idx))
+    }
+
+    fn parse(reader: &mut LumpReader) -> Result<Self, LumpError> {
+        let name = reader.string8()?;
+        let masked = reader.i32()? != 0;
+        let width = reader.u16()?;
+        let height = reader.u16()?;
+        let column_directory = reader.i32()?;
+
+        let patch_count = reader.i16()?;
+        let patch_count = u16::try_from(patch_count)
+            .map_err(|_| reader.invalid("patch count", patch_count.into()))?;
+
+        let patches = (0..patch_count)
+            .map(|_| {
+                Ok(Patch {
+                    origin: Point::new(reader.i16()?, reader.i16()?),
+                    patch_idx: reader.u16()?,
+                    step_dir: reader.i16()?,
+                    color_map: reader.i16()?,
+                })
+            })
+            .collect::<Result<_, LumpError>>()?;
+
+        Ok(Self {
+            name,
+            masked,
+            width,
+            height,
+            column_directory,
+            patches,
+        })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_string8(&self.name)?;
+        writer.write_i32(self.masked.into())?;
+        writer.write_u16(self.width)?;
+        writer.write_u16(self.height)?;
+        writer.write_i32(self.column_directory)?;
+        let patch_count = i16::try_from(self.patches.len()).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} has more patches than fit in a 16-bit field", self.name),
+            )
+        })?;
+        writer.write_i16(patch_count)?;
+
+        for patch in self.patches.iter() {
+            writer.write_i16(patch.origin.x)?;
+            writer.write_i16(patch.origin.y)?;
+            writer.write_u16(patch.patch_idx)?;
+            writer.write_i16(patch.step_dir)?;
+            writer.write_i16(patch.color_map)?;
+        }
+
+        Ok(())
+    }
+
+    /// The size of this texture's definition in a TEXTUREx lump
+    fn encoded_len(&self) -> usize {
+        22 + 10 * self.patches.len()
+    }
+}
+
+/// The contents of a TEXTURE1 or TEXTURE2 lump
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Textures(pub Vec<Texture>);
+
+impl Textures {
+    pub fn parse(bytes: &[u8]) -> Result<Self, LumpError> {
+        let mut reader = LumpReader::new("TEXTUREx", bytes);
+        let count = reader.count("texture count")?;
+
+        let offsets = (0..count)
+            .map(|_| reader.count("texture offset"))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        offsets
+            .into_iter()
+            .map(|offset| {
+                reader.seek(offset)?;
+                Texture::parse(&mut reader)
+            })
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+
+    /// Write the lump, with the textures in order immediately after the offset table
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_count(self.0.len())?;
+
+        let mut offset = 4 + 4 * self.0.len();
+        for texture in self.0.iter() {
+            writer.write_count(offset)?;
+            offset += texture.encoded_len();
+        }
+
+        for texture in self.0.iter() {
+            texture.write(writer)?;
+        }
+
+        Ok(())
+    }
+
+    /// Find a texture by name, ignoring case
+    pub fn get(&self, name: &String8) -> Option<&Texture> {
+        self.0.iter().find(|texture| {
+            texture
+                .name
+                .as_bytes()
+                .eq_ignore_ascii_case(name.as_bytes())
+        })
+    }
+
+    /// The width of every texture, e.g. for `ScaleOptions::texture_widths`
+    pub fn widths(&self) -> HashMap<String8, u16> {
+        self.0
+            .iter()
+            .map(|texture| (texture.name.clone(), texture.width))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn textures_round_trip() {
+        let mut patch_names = PatchNames::default();
+        let wall = patch_names
+            .index_of_or_insert(&String8::new_unchecked("WALL00_1"))
+            .unwrap();
+        let door = patch_names
+            .index_of_or_insert(&String8::new_unchecked("DOOR2_1"))
+            .unwrap();
+        assert_eq!(
+            patch_names.index_of_or_insert(&String8::new_unchecked("wall00_1")),
+            Some(wall)
+        );
+
+        let textures = Textures(vec![
+            Texture {
+                name: String8::new_unchecked("STARTAN3"),
+                masked: false,
+                width: 128,
+                height: 128,
+                column_directory: 0,
+                patches: vec![Patch {
+                    origin: Point::new(0, 0),
+                    patch_idx: wall,
+                    step_dir: 1,
+                    color_map: 0,
+                }],
+            },
+            Texture {
+                name: String8::new_unchecked("BIGDOOR1"),
+                masked: true,
+                width: 128,
+                height: 96,
+                column_directory: 0,
+                patches: vec![
+                    Patch {
+                        origin: Point::new(0, -8),
+                        patch_idx: door,
+                        step_dir: 1,
+                        color_map: 0,
+                    },
+                    Patch {
+                        origin: Point::new(64, -8),
+                        patch_idx: door,
+                        step_dir: 1,
+                        color_map: 0,
+                    },
+                ],
+            },
+        ]);
+
+        let mut bytes = Vec::new();
+        textures.write(&mut bytes).unwrap();
+        assert_eq!(Textures::parse(&bytes).unwrap(), textures);
+
+        let mut bytes = Vec::new();
+        patch_names.write(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), 4 + 2 * 8);
+        assert_eq!(PatchNames::parse(&bytes).unwrap(), patch_names);
+
+        assert!(matches!(
+            Textures::parse(&bytes[..3]),
+            Err(LumpError::UnexpectedEnd { needed: 1, .. })
+        ));
+    }
+}
@@ -18,7 +18,16 @@ use syn::{
 
 #[proc_macro_derive(
     LineDefSpecial,
-    attributes(udmf_special, doom_special, trigger_flags, udmf, doom)
+    attributes(
+        udmf_special,
+        doom_special,
+        trigger_flags,
+        special_meta,
+        motion,
+        udmf,
+        doom,
+        moves
+    )
 )]
 pub fn linedef_special_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
@@ -35,9 +44,61 @@ struct SpecialData {
     udmf_special: Ident,
     doom_special: Ident,
     trigger_flags: Ident,
+    special_meta: Ident,
+    motion: MotionTypes,
     specials: Vec<Special>,
 }
 
+/// The types used to describe the motion of a special, in the order `Motion, MovingPlane, MotionDirection, TargetHeight`
+struct MotionTypes {
+    motion: Ident,
+    plane: Ident,
+    direction: Ident,
+    target: Ident,
+}
+
+impl Parse for MotionTypes {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let motion = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let plane = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let direction = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let target = input.parse()?;
+
+        Ok(Self {
+            motion,
+            plane,
+            direction,
+            target,
+        })
+    }
+}
+
+/// The variants of the motion types describing a special, e.g. `#[moves(Floor, Down, LowestNeighborFloor)]`
+struct Moves {
+    plane: Ident,
+    direction: Ident,
+    target: Ident,
+}
+
+impl Parse for Moves {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let plane = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let direction = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let target = input.parse()?;
+
+        Ok(Self {
+            plane,
+            direction,
+            target,
+        })
+    }
+}
+
 impl SpecialData {
     fn parse(input: DeriveInput) -> Result<Self> {
         let mut udmf_value_buckets = HashMap::new();
@@ -53,11 +114,18 @@ impl SpecialData {
                         .map(|field| field.ident.as_ref().cloned().unwrap())
                         .collect();
 
-                    let udmf_value = parse_literal(parse_attribute(
-                        "udmf",
-                        &variant.attrs,
-                        variant.ident.span(),
-                    )?)?;
+                    let udmf: UdmfAttr =
+                        parse_attribute("udmf", &variant.attrs, variant.ident.span())?;
+                    let udmf_value = parse_literal(udmf.value)?;
+                    let name = udmf.name.unwrap_or_else(|| default_name(&variant.ident));
+
+                    let mut arg_types = vec![ArgType::I16; fields.len()];
+                    for (arg, arg_type) in udmf.arg_types {
+                        let i = fields.iter().position(|f| *f == arg).ok_or_else(|| {
+                            parse::Error::new(arg.span(), format!("No field named `{}`", arg))
+                        })?;
+                        arg_types[i] = arg_type;
+                    }
 
                     udmf_value_buckets
                         .entry(udmf_value)
@@ -69,17 +137,38 @@ impl SpecialData {
                             .collect::<Result<Vec<_>>>()?;
 
                     for doom_mapping in doom_mappings.iter() {
+                        for (arg, arg_type) in doom_mapping.arg_mappings.iter().zip(&arg_types) {
+                            if let DoomMappingArg::Constant(value) = arg {
+                                if !arg_type.contains(*value) {
+                                    return Err(parse::Error::new(
+                                        variant.ident.span(),
+                                        format!(
+                                            "Doom special {} sets a {} arg to {}",
+                                            doom_mapping.value,
+                                            arg_type.name(),
+                                            value
+                                        ),
+                                    ));
+                                }
+                            }
+                        }
+
                         doom_value_buckets
                             .entry(doom_mapping.value)
                             .or_insert_with(Vec::new)
                             .push(variant.ident.span());
                     }
 
+                    let moves = try_parse_attribute("moves", &variant.attrs)?;
+
                     Ok(Special {
                         ident: variant.ident.clone(),
                         udmf_value,
                         doom_mappings,
+                        moves,
                         fields,
+                        arg_types,
+                        name,
                     })
                 })
                 .collect::<Result<Vec<_>>>()?
@@ -113,6 +202,8 @@ impl SpecialData {
             udmf_special: parse_attribute("udmf_special", &input.attrs, input.ident.span())?,
             doom_special: parse_attribute("doom_special", &input.attrs, input.ident.span())?,
             trigger_flags: parse_attribute("trigger_flags", &input.attrs, input.ident.span())?,
+            special_meta: parse_attribute("special_meta", &input.attrs, input.ident.span())?,
+            motion: parse_attribute("motion", &input.attrs, input.ident.span())?,
 
             specials,
         })
@@ -122,8 +213,260 @@ impl SpecialData {
 struct Special {
     ident: Ident,
     udmf_value: i16,
+    /// The name used by ZDoom and ACC, e.g. `Door_Open`
+    name: String,
     fields: Vec<Ident>,
+    /// The type of each field, in the same order as `fields`
+    arg_types: Vec<ArgType>,
     doom_mappings: Vec<DoomMapping>,
+    moves: Option<Moves>,
+}
+
+/// The arguments of the `udmf` attribute, e.g. `#[udmf(2, args(angle: u8), name = "Polyobj_RotateLeft")]`.
+///
+/// Fields which aren't listed in `args` are `i16`. Without a `name`, the name is the variant's with an underscore
+/// after the first word, e.g. `Door_Open` for `DoorOpen`.
+struct UdmfAttr {
+    value: Literal,
+    arg_types: Vec<(Ident, ArgType)>,
+    name: Option<String>,
+}
+
+impl Parse for UdmfAttr {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let value = input.parse()?;
+        let mut arg_types = Vec::new();
+        let mut name = None;
+
+        while input.parse::<Option<Token![,]>>()?.is_some() && !input.is_empty() {
+            let key: Ident = input.parse()?;
+
+            if key == "args" {
+                let contents;
+                parenthesized!(contents in input);
+                let args = contents.parse_terminated(
+                    |input| {
+                        let arg: Ident = input.parse()?;
+                        input.parse::<Token![:]>()?;
+                        Ok((arg, input.parse()?))
+                    },
+                    Token![,],
+                )?;
+                arg_types = args.into_iter().collect();
+            } else if key == "name" {
+                input.parse::<Token![=]>()?;
+                name = Some(input.parse::<syn::LitStr>()?.value());
+            } else {
+                return Err(Error::new(key.span(), "expected `args` or `name`"));
+            }
+        }
+
+        Ok(Self {
+            value,
+            arg_types,
+            name,
+        })
+    }
+}
+
+/// The default name of a special, with an underscore after the first word of the variant, as most ZDoom names have
+fn default_name(variant: &Ident) -> String {
+    let variant = variant.to_string();
+
+    match variant
+        .char_indices()
+        .skip(1)
+        .find(|(_, c)| c.is_ascii_uppercase())
+    {
+        Some((i, _)) => format!("{}_{}", &variant[..i], &variant[i..]),
+        None => variant,
+    }
+}
+
+/// The type of a special's argument. UDMF and Doom store every argument as an `i16`, which is narrowed to the type
+/// of the field.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ArgType {
+    I16,
+    U8,
+    Bool,
+    /// A lock number, converted to the `Lock` type in scope, which keeps any `i16`
+    Lock,
+    /// A byte angle, converted to the `Angle` type in scope with `from_byte_angle` and `to_byte_angle`
+    Angle,
+    /// A height or vertical distance in map units, converted to the `Height` type in scope, which keeps any `i16`
+    Height,
+    /// The enums in scope which convert with `TryFrom<i16>` and `From<_> for i16`, and whose values are `0..=max`
+    CrushMode,
+    SectorPlane,
+    Side,
+}
+
+impl ArgType {
+    const ALL: [ArgType; 9] = [
+        ArgType::I16,
+        ArgType::U8,
+        ArgType::Bool,
+        ArgType::Lock,
+        ArgType::Angle,
+        ArgType::Height,
+        ArgType::CrushMode,
+        ArgType::SectorPlane,
+        ArgType::Side,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            ArgType::I16 => "i16",
+            ArgType::U8 => "u8",
+            ArgType::Bool => "bool",
+            ArgType::Lock => "Lock",
+            ArgType::Angle => "Angle",
+            ArgType::Height => "Height",
+            ArgType::CrushMode => "CrushMode",
+            ArgType::SectorPlane => "SectorPlane",
+            ArgType::Side => "Side",
+        }
+    }
+
+    /// The largest value of an enum type
+    fn enum_max(self) -> Option<i16> {
+        match self {
+            ArgType::I16
+            | ArgType::U8
+            | ArgType::Bool
+            | ArgType::Lock
+            | ArgType::Angle
+            | ArgType::Height => None,
+            ArgType::CrushMode => Some(3),
+            ArgType::SectorPlane => Some(2),
+            ArgType::Side => Some(1),
+        }
+    }
+
+    /// An expression converting the `i16` `value` to this type, evaluating `on_error` if it's out of range
+    fn narrow(self, value: TokenStream, on_error: TokenStream) -> TokenStream {
+        match self {
+            ArgType::I16 => value,
+            ArgType::U8 => quote! {
+                match u8::try_from(#value) {
+                    Ok(value) => value,
+                    Err(_) => #on_error,
+                }
+            },
+            ArgType::Bool => quote! {
+                match #value {
+                    0 => false,
+                    1 => true,
+                    _ => #on_error,
+                }
+            },
+            ArgType::Lock => quote! { Lock::from(#value) },
+            ArgType::Height => quote! { Height(#value) },
+            ArgType::Angle => quote! {
+                match u8::try_from(#value) {
+                    Ok(value) => Angle::from_byte_angle(value),
+                    Err(_) => #on_error,
+                }
+            },
+            ArgType::CrushMode | ArgType::SectorPlane | ArgType::Side => {
+                let ty = Ident::new(self.name(), Span::call_site());
+                quote! {
+                    match #ty::try_from(#value) {
+                        Ok(value) => value,
+                        Err(_) => #on_error,
+                    }
+                }
+            }
+        }
+    }
+
+    /// An expression converting `value` of this type to an `i16`
+    fn widen(self, value: TokenStream) -> TokenStream {
+        match self {
+            ArgType::I16 => value,
+            ArgType::Angle => quote! { i16::from((#value).to_byte_angle()) },
+            ArgType::Height => quote! { (#value).0 },
+            ArgType::U8
+            | ArgType::Bool
+            | ArgType::Lock
+            | ArgType::CrushMode
+            | ArgType::SectorPlane
+            | ArgType::Side => quote! { i16::from(#value) },
+        }
+    }
+
+    /// An expression of this type for a constant arg of a Doom special
+    fn constant(self, value: i16) -> TokenStream {
+        match self {
+            ArgType::I16 => Literal::i16_unsuffixed(value).into_token_stream(),
+            ArgType::U8 => Literal::u8_unsuffixed(value as u8).into_token_stream(),
+            ArgType::Bool => {
+                let value = value != 0;
+                quote! { #value }
+            }
+            ArgType::Lock => {
+                let value = Literal::i16_unsuffixed(value);
+                quote! { Lock::from(#value) }
+            }
+            ArgType::Angle => {
+                let value = Literal::u8_unsuffixed(value as u8);
+                quote! { Angle::from_byte_angle(#value) }
+            }
+            ArgType::Height => {
+                let value = Literal::i16_unsuffixed(value);
+                quote! { Height(#value) }
+            }
+            ArgType::CrushMode | ArgType::SectorPlane | ArgType::Side => {
+                // The value was checked with `contains` when parsing the mapping
+                self.narrow(
+                    Literal::i16_unsuffixed(value).into_token_stream(),
+                    quote! { unreachable!() },
+                )
+            }
+        }
+    }
+
+    fn contains(self, value: i16) -> bool {
+        match self {
+            ArgType::I16 | ArgType::Lock | ArgType::Height => true,
+            ArgType::U8 | ArgType::Angle => u8::try_from(value).is_ok(),
+            ArgType::Bool => matches!(value, 0 | 1),
+            ArgType::CrushMode | ArgType::SectorPlane | ArgType::Side => {
+                (0..=self.enum_max().unwrap()).contains(&value)
+            }
+        }
+    }
+
+    /// The range of `i16` values which fit in this type
+    fn range(self) -> TokenStream {
+        match self {
+            ArgType::I16 | ArgType::Lock | ArgType::Height => quote! { (i16::MIN..=i16::MAX) },
+            ArgType::U8 | ArgType::Angle => quote! { (0..=255) },
+            ArgType::Bool => quote! { (0..=1) },
+            ArgType::CrushMode | ArgType::SectorPlane | ArgType::Side => {
+                let max = Literal::i16_unsuffixed(self.enum_max().unwrap());
+                quote! { (0..=#max) }
+            }
+        }
+    }
+}
+
+impl Parse for ArgType {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let ident: Ident = input.parse()?;
+
+        ArgType::ALL
+            .into_iter()
+            .find(|arg_type| ident == arg_type.name())
+            .ok_or_else(|| {
+                Error::new(
+                    ident.span(),
+                    "unsupported argument type, expected `i16`, `u8`, `bool`, `Lock`, `Angle`, `Height`, \
+                     `CrushMode`, `SectorPlane` or `Side`",
+                )
+            })
+    }
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
@@ -320,6 +663,13 @@ impl ToTokens for SpecialData {
         self.gen_from_udmf_tokens(tokens);
         self.gen_into_udmf_tokens(tokens);
         self.gen_from_doom_tokens(tokens);
+        self.gen_into_doom_tokens(tokens);
+        self.gen_name_tokens(tokens);
+        self.gen_arg_names_tokens(tokens);
+        self.gen_metadata_tokens(tokens);
+        self.gen_arg_out_of_range_tokens(tokens);
+        self.gen_doom_constant_args_tokens(tokens);
+        self.gen_motion_tokens(tokens);
     }
 }
 
@@ -331,9 +681,16 @@ impl SpecialData {
         let match_arms = self.specials.iter().map(|special| {
             let udmf_value = &special.udmf_value;
             let variant = &special.ident;
-            let field_exprs = special.fields.iter().enumerate().map(|(i, field)| {
-                quote! { #field: udmf.args[#i] }
-            });
+            let field_exprs = special
+                .fields
+                .iter()
+                .zip(special.arg_types.iter())
+                .enumerate()
+                .map(|(i, (field, arg_type))| {
+                    let value =
+                        arg_type.narrow(quote! { udmf.args[#i] }, quote! { return Err(udmf) });
+                    quote! { #field: #value }
+                });
             let fields_len = special.fields.len();
             let extra_fields_checks = (fields_len..5).map(|i| {
                 quote! {
@@ -377,7 +734,8 @@ impl SpecialData {
             let field_exprs = special
                 .fields
                 .iter()
-                .map(|field| quote! { #field })
+                .zip(special.arg_types.iter())
+                .map(|(field, arg_type)| arg_type.widen(quote! { #field }))
                 .pad_using(5, |_| quote! { 0 });
 
             quote! {
@@ -408,11 +766,20 @@ impl SpecialData {
                 let fields = special
                     .fields
                     .iter()
+                    .zip(special.arg_types.iter())
                     .zip_longest(doom_mapping.arg_mappings.iter())
                     .map(|e| match e {
-                        EitherOrBoth::Left(f) => quote! { #f: 0 },
+                        EitherOrBoth::Left((f, _)) => quote! { #f: Default::default() },
                         EitherOrBoth::Right(_) => panic!(),
-                        EitherOrBoth::Both(f, v) => quote! { #f: #v },
+                        EitherOrBoth::Both((f, arg_type), DoomMappingArg::Tag) => {
+                            let value =
+                                arg_type.narrow(quote! { tag }, quote! { return Err(doom) });
+                            quote! { #f: #value }
+                        }
+                        EitherOrBoth::Both((f, arg_type), DoomMappingArg::Constant(v)) => {
+                            let value = arg_type.constant(*v);
+                            quote! { #f: #value }
+                        }
                     });
 
                 let flags = doom_mapping
@@ -445,4 +812,294 @@ impl SpecialData {
 
         });
     }
+
+    fn gen_into_doom_tokens(&self, tokens: &mut TokenStream) {
+        let doom_special = &self.doom_special;
+        let linedef_special = &self.linedef_special;
+        let trigger_flags = &self.trigger_flags;
+
+        let match_arms = self.specials.iter().flat_map(|special| {
+            let variant = &special.ident;
+
+            special.doom_mappings.iter().map(move |doom_mapping| {
+                let doom_value = doom_mapping.value;
+                let mut tag_fields = Vec::new();
+                let mut conditions = Vec::new();
+
+                let fields = special.fields.iter().zip(special.arg_types.iter());
+                for e in fields.zip_longest(doom_mapping.arg_mappings.iter()) {
+                    match e {
+                        EitherOrBoth::Left((f, arg_type)) => {
+                            let value = arg_type.widen(quote! { *#f });
+                            conditions.push(quote! { #value == 0 })
+                        }
+                        EitherOrBoth::Right(_) => panic!(),
+                        EitherOrBoth::Both((f, arg_type), DoomMappingArg::Tag) => {
+                            tag_fields.push(arg_type.widen(quote! { *#f }))
+                        }
+                        EitherOrBoth::Both((f, arg_type), DoomMappingArg::Constant(v)) => {
+                            let value = arg_type.widen(quote! { *#f });
+                            conditions.push(quote! { #value == #v })
+                        }
+                    }
+                }
+
+                // Every arg set from the tag must hold the same value
+                let tag = match tag_fields.split_first() {
+                    Some((first, rest)) => {
+                        conditions.extend(rest.iter().map(|f| quote! { #f == #first }));
+                        first.clone()
+                    }
+                    None => quote! { 0 },
+                };
+
+                let fields = &special.fields;
+                let flags = doom_mapping
+                    .trigger_flags
+                    .iter()
+                    .map(|f| quote! { #f: true });
+
+                quote! {
+                    #linedef_special::#variant { #(#fields,)* .. }
+                        if #(#conditions &&)* *trigger_flags == #trigger_flags { #(#flags,)* ..#trigger_flags::default() }
+                        => Some(#doom_special { value: #doom_value, tag: #tag })
+                }
+            })
+        });
+
+        tokens.extend(quote! {
+            impl #linedef_special {
+                /// The Doom special which converts to this special with `trigger_flags`, or `None` if there is no such
+                /// Doom special. If several do, the first one declared is chosen.
+                pub fn to_doom(&self, trigger_flags: &#trigger_flags) -> Option<#doom_special> {
+                    match self {
+                        #(#match_arms,)*
+                        _ => None,
+                    }
+                }
+            }
+        });
+    }
+
+    fn gen_name_tokens(&self, tokens: &mut TokenStream) {
+        let linedef_special = &self.linedef_special;
+
+        let name_arms = self.specials.iter().map(|special| {
+            let variant = &special.ident;
+            let name = &special.name;
+
+            quote! {
+                #linedef_special::#variant { .. } => #name
+            }
+        });
+
+        let from_name_arms = self.specials.iter().map(|special| {
+            let variant = &special.ident;
+            let name = special.name.to_ascii_lowercase();
+            let fields = &special.fields;
+
+            quote! {
+                #name => Some(#linedef_special::#variant { #(#fields: Default::default()),* })
+            }
+        });
+
+        tokens.extend(quote! {
+            impl #linedef_special {
+                /// The name of this special, as used by ZDoom and ACC, e.g. `Door_Open`
+                pub fn name(&self) -> &'static str {
+                    match self {
+                        #(#name_arms,)*
+                    }
+                }
+
+                /// The special with the given name, ignoring case, with all of its args set to 0
+                pub fn from_name(name: &str) -> Option<Self> {
+                    match name.to_ascii_lowercase().as_str() {
+                        #(#from_name_arms,)*
+                        _ => None,
+                    }
+                }
+            }
+        });
+    }
+
+    fn gen_arg_names_tokens(&self, tokens: &mut TokenStream) {
+        let linedef_special = &self.linedef_special;
+
+        let match_arms = self.specials.iter().map(|special| {
+            let variant = &special.ident;
+            // Fields which clash with keywords are prefixed with an underscore
+            let names = special
+                .fields
+                .iter()
+                .map(|field| field.to_string().trim_start_matches('_').to_string());
+
+            quote! {
+                #linedef_special::#variant { .. } => &[#(#names),*]
+            }
+        });
+
+        tokens.extend(quote! {
+            impl #linedef_special {
+                /// The names of the arguments of this special, in the same order as the UDMF args
+                pub fn arg_names(&self) -> &'static [&'static str] {
+                    match self {
+                        #(#match_arms,)*
+                    }
+                }
+            }
+        });
+    }
+
+    fn gen_metadata_tokens(&self, tokens: &mut TokenStream) {
+        let linedef_special = &self.linedef_special;
+        let special_meta = &self.special_meta;
+
+        let entries = self.specials.iter().map(|special| {
+            let udmf_value = special.udmf_value;
+            let name = &special.name;
+            let arg_names = special
+                .fields
+                .iter()
+                .map(|field| field.to_string().trim_start_matches('_').to_string());
+            let doom_values = special.doom_mappings.iter().map(|m| m.value);
+
+            quote! {
+                #special_meta {
+                    udmf_value: #udmf_value,
+                    name: #name,
+                    arg_names: &[#(#arg_names),*],
+                    doom_values: &[#(#doom_values),*],
+                }
+            }
+        });
+
+        tokens.extend(quote! {
+            impl #linedef_special {
+                /// Every special, in declaration order
+                pub const ALL_METADATA: &'static [#special_meta] = &[#(#entries),*];
+            }
+        });
+    }
+
+    fn gen_arg_out_of_range_tokens(&self, tokens: &mut TokenStream) {
+        let udmf_special = &self.udmf_special;
+        let linedef_special = &self.linedef_special;
+
+        let match_arms = self.specials.iter().map(|special| {
+            let udmf_value = special.udmf_value;
+            let checks = special
+                .arg_types
+                .iter()
+                .enumerate()
+                .filter(|(_, arg_type)| **arg_type != ArgType::I16)
+                .map(|(i, arg_type)| {
+                    let range = arg_type.range();
+                    quote! {
+                        if !#range.contains(&udmf.args[#i]) {
+                            return Some((#i, #range));
+                        }
+                    }
+                });
+
+            quote! {
+                #udmf_value => {
+                    #(#checks)*
+                    None
+                }
+            }
+        });
+
+        tokens.extend(quote! {
+            impl #linedef_special {
+                /// The first arg of `udmf` which is out of range for the type of its field, as its index and the
+                /// valid range. `None` if every arg fits, or if there is no special with this value.
+                pub fn arg_out_of_range(
+                    udmf: &#udmf_special,
+                ) -> Option<(usize, std::ops::RangeInclusive<i16>)> {
+                    match udmf.value {
+                        #(#match_arms,)*
+                        _ => None,
+                    }
+                }
+            }
+        });
+    }
+
+    fn gen_doom_constant_args_tokens(&self, tokens: &mut TokenStream) {
+        let linedef_special = &self.linedef_special;
+
+        let match_arms = self.specials.iter().flat_map(|special| {
+            special.doom_mappings.iter().map(move |doom_mapping| {
+                let doom_value = doom_mapping.value;
+                let constants = special
+                    .fields
+                    .iter()
+                    .zip(doom_mapping.arg_mappings.iter())
+                    .filter_map(|(field, arg)| match arg {
+                        DoomMappingArg::Tag => None,
+                        DoomMappingArg::Constant(value) => {
+                            let name = field.to_string().trim_start_matches('_').to_string();
+                            Some(quote! { (#name, #value) })
+                        }
+                    });
+
+                quote! {
+                    #doom_value => Some(&[#(#constants),*])
+                }
+            })
+        });
+
+        tokens.extend(quote! {
+            impl #linedef_special {
+                /// The args which a Doom special sets to fixed values rather than to its tag, as `(arg name, value)`.
+                /// `None` if there is no Doom special with this value.
+                pub fn doom_constant_args(doom_value: i16) -> Option<&'static [(&'static str, i16)]> {
+                    match doom_value {
+                        #(#match_arms,)*
+                        _ => None,
+                    }
+                }
+            }
+        });
+    }
+
+    fn gen_motion_tokens(&self, tokens: &mut TokenStream) {
+        let linedef_special = &self.linedef_special;
+        let MotionTypes {
+            motion,
+            plane: plane_type,
+            direction: direction_type,
+            target: target_type,
+        } = &self.motion;
+
+        let match_arms = self.specials.iter().filter_map(|special| {
+            let variant = &special.ident;
+            let Moves {
+                plane,
+                direction,
+                target,
+            } = special.moves.as_ref()?;
+
+            Some(quote! {
+                #linedef_special::#variant { .. } => Some(#motion {
+                    plane: #plane_type::#plane,
+                    direction: #direction_type::#direction,
+                    target: #target_type::#target,
+                })
+            })
+        });
+
+        tokens.extend(quote! {
+            impl #linedef_special {
+                /// How this special moves sectors, or `None` if it doesn't
+                pub fn motion(&self) -> Option<#motion> {
+                    match self {
+                        #(#match_arms,)*
+                        _ => None,
+                    }
+                }
+            }
+        });
+    }
 }